@@ -25,10 +25,17 @@ export type HookCommand =
  * An enum representing the available verbosity levels of the logger.
  */
 export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+/**
+ * How the generated packages are organized in [`Config::out_dir`].
+ */
+export type OutputLayout =
+  | "flat"
+  | "os"
+  | "format";
 /**
  * Types of supported packages by [`cargo-packager`](https://docs.rs/cargo-packager).
  */
-export type PackageFormat = "all" | "default" | "app" | "dmg" | "wix" | "nsis" | "deb" | "appimage" | "pacman";
+export type PackageFormat = "all" | "default" | "app" | "dmg" | "pkg" | "wix" | "nsis" | "deb" | "appimage" | "pacman" | "shar" | "zip" | "innosetup";
 /**
  * The possible app categories. Corresponds to `LSApplicationCategoryType` on macOS and the GNOME desktop categories on Debian.
  */
@@ -93,12 +100,82 @@ export type Resource =
        * If `src` is a glob, this will always be treated as a directory where all globbed files will be placed under.
        */
       target: string;
+      /**
+       * The Unix file mode of the resource files in the final package, as an octal string, for example `"0755"`.
+       *
+       * Applies to every file matched by `src`. By default, files keep their executable bit and are packaged with `0755` or `0644` accordingly.
+       */
+      mode?: string | null;
+      /**
+       * Whether the resource is skipped with a warning when `src` does not exist, instead of failing the packaging, for example for files only generated on release builds.
+       */
+      optional?: boolean;
+      [k: string]: unknown;
+    }
+  | {
+      /**
+       * The `http`, `https` or `file` URL of the file.
+       */
+      url: string;
+      /**
+       * The expected SHA-256 digest of the file, encoded as a hex string.
+       */
+      sha256: string;
+      /**
+       * A relative path from the root of the final package, including the file name.
+       */
+      target: string;
+      /**
+       * The Unix file mode of the file in the final package, as an octal string, for example `"0755"`.
+       */
+      mode?: string | null;
       [k: string]: unknown;
     };
+/**
+ * How the shared libraries linked by the main binary are checked, see [`Config::shared_libraries_check`].
+ */
+export type SharedLibrariesCheck = "warn" | "error";
+/**
+ * How the version of the main binary is read, see [`Config::binary_version_check`].
+ */
+export type BinaryVersionCheck =
+  | "versionFlag"
+  | "peVersionInfo"
+  | {
+      command: string;
+    };
+/**
+ * Release notes, in markdown, see [`Config::release_notes`].
+ */
+export type ReleaseNotes =
+  | {
+      text: string;
+    }
+  | {
+      file: string;
+    };
 /**
  * A list of dependencies specified as either a list of Strings or as a path to a file that lists the dependencies, one per line.
  */
 export type Dependencies = string[] | string;
+/**
+ * The changelog of a Debian package, specified as either a list of entries or as a path to an existing `debian/changelog` file.
+ */
+export type DebianChangelog =
+  | DebianChangelogEntry[]
+  | string;
+/**
+ * The compression of a member of a Debian package, see [`DebianConfig::control_compression`] and [`DebianConfig::data_compression`].
+ */
+export type DebianCompression = "none" | "gzip" | "xz" | "zstd";
+/**
+ * The compression method of the entries of a zip archive.
+ */
+export type ZipCompression = "store" | "deflate" | "zstd";
+/**
+ * The filesystem of an Apple Disk Image.
+ */
+export type DmgFilesystem = "HFS+" | "APFS";
 /**
  * A wix language.
  */
@@ -125,6 +202,10 @@ export type NsisCompression = "zlib" | "bzip2" | "lzma" | "off";
  * Install Modes for the NSIS installer.
  */
 export type NSISInstallerMode = "currentUser" | "perMachine" | "both";
+/**
+ * The algorithm of a signature.
+ */
+export type SignatureAlgorithm = "minisign";
 
 /**
  * The packaging config.
@@ -152,16 +233,34 @@ export interface Config {
    * The package's product name, for example "My Awesome App".
    */
   productName?: string;
+  /**
+   * The file name safe name of the app used in the file names of the packages of all the formats, like `<slug>_<version>_<arch>.deb` or `<slug>_<version>_<arch>-setup.exe`, see [`Config::slug()`](Config::slug()).
+   *
+   * Defaults to the [`Config::product_name`], or the main binary name when the product name has none of the allowed characters, converted to a slug: - ASCII letters are lowercased and ASCII digits are kept. - every run of other characters, like spaces, `_`, `.` and non-ASCII letters, is replaced with a single `-`. - the leading and trailing `-` are removed.
+   *
+   * For example, `My App (Beta)` becomes `my-app-beta`. An explicit slug must already follow these rules. The `.app` bundle keeps the product name, shown by Finder, but the `.dmg` and `.pkg` files use the slug. The [`DebianConfig::package_name`] and [`PacmanConfig::package_name`] take precedence over the slug for the `.deb` and pacman package files.
+   */
+  slug?: string | null;
   /**
    * The package's version.
    */
   version?: string;
+  /**
+   * An identifier of the build, like a UUID or a git commit hash, stamped into the metadata of every package so any one of them tells the exact build it comes from.
+   *
+   * It is written in the `X-Build-Id` field of the Debian control file, the `CargoPackagerBuildId` key of the macOS `Info.plist`, the `BuildId` string of the Windows version information, the update manifest and the packaging summary.
+   *
+   * It may only contain ASCII letters, digits, `.`, `-`, `_` and `+`. Defaults to a random UUID generated for each packaging run.
+   */
+  buildId?: string | null;
   /**
    * The binaries to package.
    */
   binaries?: Binary[];
   /**
    * The application identifier in reverse domain name notation (e.g. `com.packager.example`). This string must be unique across applications since it is used in some system configurations. This string must contain only alphanumeric characters (A-Z, a-z, and 0-9), hyphens (-), and periods (.).
+   *
+   * When not set, the `CFBundleIdentifier` of the macOS app is derived from the [`Config::publisher`] and [`Config::product_name`] as `com.<publisher>.<product-name>`.
    */
   identifier?: string | null;
   /**
@@ -182,14 +281,94 @@ export interface Config {
   logLevel?: LogLevel | null;
   /**
    * The packaging formats to create, if not present, [`PackageFormat::platform_default`] is used.
+   *
+   * The formats are built one after the other in the order they are listed, except for [`PackageFormat::Dmg`] and [`PackageFormat::Pkg`] which are built last as they package the app bundle of [`PackageFormat::App`], see [`Config::format_order`] to change it.
    */
   formats?: PackageFormat[] | null;
+  /**
+   * The order the [`Config::formats`] are built in, to control the disk usage and when the hooks run, like building a large package first to upload and remove it in the [`Config::before_each_package_command`] of the next format.
+   *
+   * The listed formats are built first, in this order, then the other formats in their default order. The app bundle is still built before the [`PackageFormat::Dmg`] and [`PackageFormat::Pkg`] packages, and [`PackageFormat::All`] and [`PackageFormat::Default`] have no effect.
+   */
+  formatOrder?: PackageFormat[] | null;
+  /**
+   * Whether to skip the [`Config::formats`] that can't be built on the current operating system with a warning, instead of failing with [`Error::FormatUnsupportedOnHost`].
+   *
+   * For example, [`PackageFormat::Dmg`] can only be built on macOS.
+   */
+  skipUnsupportedFormats?: boolean;
+  /**
+   * The maximum size in bytes of each generated package, the packaging fails with [`Error::ArtifactTooLarge`](crate::Error::ArtifactTooLarge) when a package exceeds it.
+   *
+   * The size of a directory, like the `.app` bundle, is the size of all its files.
+   */
+  maxArtifactSize?: number | null;
+  /**
+   * The maximum size in bytes of the packages of a format, overriding [`Config::max_artifact_size`].
+   */
+  maxArtifactSizePerFormat?: {
+    [k: string]: number;
+  } | null;
+  /**
+   * Whether the symlinks of the [`Config::resources`], the [`Config::from_staging_dir`] and the [`Config::bundled_runtime`] are followed, copying their targets into the packages, instead of being preserved as symlinks.
+   *
+   * Only the symlinks to relative paths inside the copied directory are preserved, the others would dangle once installed and are always followed. The symlink cycles are rejected with [`Error::ResourceSymlinkLoop`](crate::Error::ResourceSymlinkLoop).
+   *
+   * Defaults to preserving the symlinks, except in the [`PackageFormat::Zip`] archives, which are mostly extracted on Windows. The symlinks are always followed on Windows hosts and in the [`PackageFormat::Nsis`], [`PackageFormat::Wix`] and [`PackageFormat::InnoSetup`] installers. The [`PackageFormat::Dmg`] and [`PackageFormat::Pkg`] packages use the setting of the [`PackageFormat::App`] bundle.
+   */
+  followSymlinks?: boolean | null;
+  /**
+   * Whether the symlinks of the resources are followed in the packages of a format, overriding [`Config::follow_symlinks`].
+   */
+  followSymlinksPerFormat?: {
+    [k: string]: boolean;
+  } | null;
+  /**
+   * Whether to validate the structure of the packages built by external tools after they exit, since a tool may exit successfully and still leave a truncated or corrupt package behind.
+   *
+   * The Debian packages must be complete `ar` archives, the AppImages must end with a complete squashfs image and the disk images must have a valid UDIF trailer and pass `hdiutil verify`. Fails with [`Error::CorruptArtifact`](crate::Error::CorruptArtifact) otherwise. Defaults to `true`.
+   */
+  validateArtifacts?: boolean | null;
+  /**
+   * The project root, the directory the relative paths of the config are resolved against, like the [`Config::resources`], [`Config::binaries`], [`Config::icons`] and [`Config::out_dir`], independently of the current directory.
+   *
+   * A relative project root is itself resolved against the directory of the config file with the CLI, or against the current directory. The `--root` flag of the CLI takes precedence over this value.
+   *
+   * Without a project root, the relative paths are resolved against the directory of the config file with the CLI, or against the current directory.
+   *
+   * The relative paths are joined onto the project root, without changing the current directory of the process, and the hook commands without a working directory run in it.
+   */
+  projectRoot?: string | null;
   /**
    * The directory where the generated packages will be placed.
    *
    * If [`Config::binaries_dir`] is not set, this is also where the [`Config::binaries`] exist.
+   *
+   * The packages are written in the `.cargo-packager` intermediates directory inside it and only moved to their final path once complete, so an interrupted build never leaves a half-written package, and its partial files are removed by the next build.
+   *
+   * The `--out-dir` (or `--output-dir`) flag of the CLI takes precedence over this value.
    */
   outDir?: string;
+  /**
+   * How the generated packages are organized in [`Config::out_dir`].
+   *
+   * Defaults to [`OutputLayout::Flat`].
+   */
+  outputLayout?: OutputLayout | null;
+  /**
+   * Whether to remove the outputs of previous runs for the formats being built before packaging.
+   *
+   * The removed files are the packages in [`Config::out_dir`] and its [`Config::output_layout`] subdirectory named like the packages of the format, for example `<main binary>_*.deb`, including the ones of other versions and architectures, and the files written next to them like signatures, checksums and split volumes. The other files of the directory are kept.
+   *
+   * Without it, each package overwrites the file it's written to and the stale outputs of previous runs are kept.
+   */
+  cleanOutputs?: boolean;
+  /**
+   * Whether to stop packaging at the first format that fails.
+   *
+   * By default, the other formats are still packaged after a format fails and [`Error::PackagingFailed`] is returned with the packages of the formats that succeeded, unless a single format was packaged.
+   */
+  failFast?: boolean;
   /**
    * The directory where the [`Config::binaries`] exist.
    *
@@ -219,7 +398,9 @@ export interface Config {
    */
   authors?: string[] | null;
   /**
-   * The app's publisher. Defaults to the second element in [`Config::identifier`](Config::identifier) string. Currently maps to the Manufacturer property of the Windows Installer.
+   * The app's publisher, also known as vendor. Defaults to the second element in [`Config::identifier`](Config::identifier) string.
+   *
+   * Maps to the `Manufacturer` property of the Windows Installer, the publisher of the NSIS and Inno Setup installers and the `Maintainer` of the Debian and pacman packages when [`Config::authors`] is not set. Each of them can be overridden with [`WixConfig::manufacturer`], [`NsisConfig::publisher`], [`InnoSetupConfig::publisher`] and [`DebianConfig::maintainer`]. macOS has no publisher field, the vendor is the prefix of the [`Config::identifier`] used as `CFBundleIdentifier`.
    */
   publisher?: string | null;
   /**
@@ -231,9 +412,23 @@ export interface Config {
    */
   copyright?: string | null;
   /**
-   * The app's category.
+   * The app's category, mapped to the category field of each format:
+   *
+   * | Format | Field | | --- | --- | | Desktop file of the deb, pacman and AppImage packages | `Categories=`, the closest [registered categories](https://specifications.freedesktop.org/menu-spec/latest/category-registry.html) | | AppStream metainfo, see [`LinuxConfig::appstream`] | `<categories>`, the same registered categories | | macOS app bundle | `LSApplicationCategoryType` of the `Info.plist` |
+   *
+   * The Windows Installer, NSIS and Inno Setup have no category, the apps are listed by name in Apps & features.
+   *
+   * The category can be named like any of these fields, for example `Developer Tool`, `Development` or `public.app-category.developer-tools`, unknown categories are rejected with a suggestion of the closest one.
    */
   category?: AppCategory | null;
+  /**
+   * The app's keywords, the search terms of the app in the software centers, mapped to:
+   *
+   * | Format | Field | | --- | --- | | Desktop file of the deb, pacman and AppImage packages | `Keywords=` | | AppStream metainfo, see [`LinuxConfig::appstream`] | `<keywords>` | | Windows Installer | The `Keywords` of the MSI summary information, after `Installer` |
+   *
+   * Each keyword must be a non-empty single line without `;`.
+   */
+  keywords?: string[] | null;
   /**
    * The app's icon list. Supports glob patterns.
    */
@@ -249,11 +444,75 @@ export interface Config {
   /**
    * The app's resources to package. This a list of either a glob pattern, path to a file, path to a directory or an object of `src` and `target` paths. In the case of using an object, the `src` could be either a glob pattern, path to a file, path to a directory, and the `target` is a path inside the final resources folder in the installed package.
    *
+   * Packaging fails when the path of a resource does not exist, unless the object sets `optional: true`. A glob pattern matching no files is not an error.
+   *
+   * A resource can also be an object of `url`, `sha256` and `target`, a file downloaded at packaging time and verified against its checksum, see [`Resource::Remote`].
+   *
    * ## Format-specific:
    *
-   * - **[PackageFormat::Nsis] / [PackageFormat::Wix]**: The resources are placed next to the executable in the root of the packager. - **[PackageFormat::Deb]**: The resources are placed in `usr/lib` of the package.
+   * - **[PackageFormat::Nsis] / [PackageFormat::Wix] / [PackageFormat::InnoSetup]**: The resources are placed next to the executable in the root of the packager. - **[PackageFormat::Deb]**: The resources are placed in `usr/lib` of the package. - **[PackageFormat::Shar]**: The resources are placed in `<prefix>/lib/<main-binary-name>` of the installation prefix. - **[PackageFormat::Zip]**: The resources are placed next to the executable in the root of the archive.
    */
   resources?: Resource[] | null;
+  /**
+   * Gitignore-style patterns of the files to exclude when expanding the resource directories and glob patterns.
+   *
+   * The patterns are matched against the paths of the files relative to the resource directory, or against the file names of the glob matches, and take precedence over the include patterns of [`Config::resources`]. The patterns of a `.packagerignore` file at the root of a resource directory also apply to that directory, with these patterns taking precedence over them. Files listed explicitly in [`Config::resources`] are always included.
+   *
+   * The outputs of the previous packaging runs, the files at the root of the [`Config::out_dir`] matching [`DEFAULT_RESOURCES_IGNORE`], are also excluded, with the lowest precedence, so a negated pattern like `!latest.json` includes them again.
+   */
+  resourcesIgnore?: string[] | null;
+  /**
+   * Whether the signatures, checksums and manifests left by previous packaging runs at the root of the [`Config::out_dir`], matching [`DEFAULT_RESOURCES_IGNORE`], are included when expanding the resource directories and glob patterns.
+   *
+   * A warning is logged for each of these files excluded from the resources.
+   *
+   * The default value of this flag is `false`.
+   */
+  includePackagingOutputs?: boolean;
+  /**
+   * A directory tree staged by the build to package wholesale, in addition to the [`Config::resources`].
+   *
+   * Its files are placed at their path in the tree, relative to where each format places the resources, except the system files of its [`StagingDir::manifest`] installed at their absolute path. The [`Config::resources`] take precedence over the files of the tree with the same path, and the [`Config::resources_ignore`] patterns also apply to the tree.
+   *
+   * Packaging fails when the tree has no files, or when one of its symbolic links or manifest entries leads outside of it.
+   */
+  fromStagingDir?: StagingDir | null;
+  /**
+   * A directory placed next to the main binary in every package, keeping its name, like an embedded Java runtime the app launches.
+   *
+   * The files of its `bin` directory are made executable, the other files keep the permissions of the source files.
+   *
+   * ## Format-specific:
+   *
+   * - **[PackageFormat::App] / [PackageFormat::Dmg]**: The directory is placed in `Contents/MacOS` of the `.app`, next to the executable. - **[PackageFormat::Nsis] / [PackageFormat::Wix] / [PackageFormat::InnoSetup]**: The directory is placed next to the executable in the installation directory. - **[PackageFormat::Deb] / [PackageFormat::AppImage] / [PackageFormat::Pacman]**: The directory is placed in `usr/lib/<main-binary-name>`, so `../lib/<main-binary-name>/<name>` relative to the executable in `usr/bin`. - **[PackageFormat::Shar]**: The directory is placed in `<prefix>/lib/<main-binary-name>`, so `../lib/<main-binary-name>/<name>` relative to the executable in `<prefix>/bin`. - **[PackageFormat::Zip]**: The directory is placed next to the executable in the root of the archive.
+   */
+  bundledRuntime?: string | null;
+  /**
+   * A generated wrapper script installed in place of the main binary, running the entrypoint of the app with an interpreter bundled in the [`Config::resources`].
+   *
+   * ## Format-specific:
+   *
+   * - **[PackageFormat::App] / [PackageFormat::Dmg] / [PackageFormat::Pkg]**: The resources are found in `Contents/Resources` of the `.app`. - **[PackageFormat::Deb] / [PackageFormat::AppImage] / [PackageFormat::Pacman] / [PackageFormat::Shar]**: The resources are found in `../lib/<main-binary-name>` relative to the shim. - **[PackageFormat::Zip]**: The resources are found next to the shim.
+   */
+  launcherShim?: LauncherShim | null;
+  /**
+   * The release notes installed with the app, as `RELEASE_NOTES.md`, and used for the `notes` of the update manifest when they aren't set otherwise.
+   *
+   * A [`ReleaseNotes::File`] must exist.
+   *
+   * ## Format-specific:
+   *
+   * - **[PackageFormat::App] / [PackageFormat::Dmg] / [PackageFormat::Pkg]**: The file is placed in `Contents/Resources` of the `.app`. - **[PackageFormat::Nsis] / [PackageFormat::Wix] / [PackageFormat::InnoSetup]**: The file is placed next to the executable in the installation directory. - **[PackageFormat::Deb] / [PackageFormat::AppImage] / [PackageFormat::Pacman]**: The file is placed in `usr/share/doc/<package>`, where `<package>` is the name of the Debian package. - **[PackageFormat::Zip]**: The file is placed next to the executable in the root of the archive.
+   */
+  releaseNotes?: ReleaseNotes | null;
+  /**
+   * The endpoint the app checks for updates, like the URL of the `latest.json` update manifest, written with the version of the package into [`UPDATER_ENDPOINT_FILE_NAME`], installed with the resources, so each package points at the endpoint of its own release:
+   *
+   * ```json { "endpoint": "https://releases.example.com/stable/latest.json", "version": "1.0.0" } ```
+   *
+   * It must be an http or https URL. Like the [`Config::resources`], the file is placed in `Contents/Resources` of the macOS app, next to the executable on Windows and in the zip archives, and in `usr/lib/<main-binary-name>` of the Linux packages.
+   */
+  updaterEndpoint?: string | null;
   /**
    * Paths to external binaries to add to the package.
    *
@@ -264,6 +523,56 @@ export interface Config {
    * If you are building a universal binary for MacOS, the packager expects your external binary to also be universal, and named after the target triple, e.g. `sqlite3-universal-apple-darwin`. See <https://developer.apple.com/documentation/apple-silicon/building-a-universal-macos-binary>
    */
   externalBinaries?: string[] | null;
+  /**
+   * Whether to preserve the extended attributes of the files, like the macOS `com.apple.*` attributes, in the tar-based formats: the [`PackageFormat::Deb`], [`PackageFormat::Pacman`] and [`PackageFormat::Shar`] packages and the `.app.tar.gz` update bundles.
+   *
+   * The attributes are stored as `SCHILY.xattr.<name>` PAX headers, restored by GNU tar and bsdtar when extracting with `--xattrs`. They can only be read on Unix hosts and are skipped on other hosts.
+   *
+   * Symlinks are always archived as symlinks, regardless of this flag, see [`Config::follow_symlinks`] for the symlinks of the [`Config::resources`].
+   */
+  preserveXattrs?: boolean;
+  /**
+   * The Unix mode of the packaged files without an explicit resource `mode`, as an octal string, for example `"0644"`.
+   *
+   * The read permissions of executable files are extended with the execute permissions, so `"0644"` packages the binaries with `0755`. The mode must not have the setuid, setgid or sticky bits set and must be readable and writable by the owner.
+   *
+   * Applies to the [`PackageFormat::App`], [`PackageFormat::Deb`], [`PackageFormat::Pacman`], [`PackageFormat::Shar`] and [`PackageFormat::AppImage`] packages, the Windows packages don't have Unix modes. Defaults to the modes of the files when packaging, normalized to `0644` or `0755` in the tar-based formats.
+   */
+  defaultFileMode?: string | null;
+  /**
+   * The Unix mode of the packaged directories, as an octal string, for example `"0755"`.
+   *
+   * The mode must not have the setuid, setgid or sticky bits set and must be readable, writable and searchable by the owner.
+   *
+   * See [`Config::default_file_mode`] for the formats it applies to.
+   */
+  defaultDirMode?: string | null;
+  /**
+   * Check the shared libraries linked by the main binary before packaging, using `ldd` on Linux and `otool -L` on macOS, and report the ones that won't be present on a clean target system and aren't bundled as [`Config::resources`] or macOS frameworks.
+   *
+   * The libraries of the C and C++ runtimes and of the common graphics and desktop stack are expected on Linux, and the ones in `/usr/lib` and `/System` on macOS. The [`PackageFormat::AppImage`] is not checked since it bundles the libraries of the binary.
+   */
+  sharedLibrariesCheck?: SharedLibrariesCheck | null;
+  /**
+   * Additional shared libraries expected on the target systems, ignored by [`Config::shared_libraries_check`].
+   *
+   * Linux libraries are matched by name without their version, for example `libssl` matches `libssl.so.3`.
+   */
+  allowedSharedLibraries?: string[] | null;
+  /**
+   * Check that the version of the main binary matches [`Config::version`] before packaging, failing with [`Error::VersionMismatch`](crate::Error::VersionMismatch) otherwise, which catches a stale binary left by a build step that didn't run.
+   *
+   * The versions are compared without their build metadata. The [`BinaryVersionCheck::VersionFlag`] and [`BinaryVersionCheck::Command`] checks run the binary, so they can't be used when cross compiling.
+   */
+  binaryVersionCheck?: BinaryVersionCheck | null;
+  /**
+   * The external tools and downloaded runtime files pinned for reproducible builds, keyed by their name: `makensis`, `wix`, `linuxdeploy`, `linuxdeploy-plugin-appimage` or `apprun`.
+   *
+   * The packaging fails with [`Error::ToolVersionMismatch`](crate::Error::ToolVersionMismatch) when the version or the digest of a tool differs from its pin. The resolved versions and digests of the tools used by each package are recorded in [`PackageOutput::tools`](crate::PackageOutput::tools) and in its provenance statement.
+   */
+  pinnedTools?: {
+    [k: string]: ToolPin;
+  } | null;
   /**
    * Windows-specific configuration.
    */
@@ -288,6 +597,14 @@ export interface Config {
    * Pacman configuration.
    */
   pacman?: PacmanConfig | null;
+  /**
+   * Self-extracting installer shell script configuration.
+   */
+  shar?: SharConfig | null;
+  /**
+   * Portable zip archive configuration.
+   */
+  zip?: ZipConfig | null;
   /**
    * WiX configuration.
    */
@@ -296,10 +613,40 @@ export interface Config {
    * Nsis configuration.
    */
   nsis?: NsisConfig | null;
+  /**
+   * Inno Setup configuration.
+   */
+  innoSetup?: InnoSetupConfig | null;
   /**
    * Dmg configuration.
    */
   dmg?: DmgConfig | null;
+  /**
+   * Pkg configuration.
+   */
+  pkg?: PkgConfig | null;
+  /**
+   * The configuration used to sign the generated packages.
+   *
+   * The private key can be a path to the key file, relative to the config file, or the key itself. The CLI flags and their environment variables take precedence over these values.
+   *
+   * Avoid storing the private key password in a configuration file that is checked into version control, use the `CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD` environment variable instead.
+   */
+  signing?: SigningConfig | null;
+  /**
+   * The software bill of materials (SBOM) of the app, generated from the `cargo metadata` of its crate when set, see the [`sbom`](crate::sbom) module.
+   *
+   * The SBOM is written next to the packages and its path is recorded in each package output, it can also be embedded into each package with [`SbomConfig::embed`](crate::sbom::SbomConfig::embed).
+   */
+  sbom?: SbomConfig | null;
+  /**
+   * Variants of the app built from this config, for example `stable` and `nightly`, mapping the name of each flavor to the fields it overrides.
+   *
+   * Each flavor is packaged into the `<out-dir>/<flavor>` directory, see [`Config::flavor_configs`] and the `--flavor` CLI flag to select the flavors to build.
+   */
+  flavors?: {
+    [k: string]: Flavor;
+  } | null;
 }
 /**
  * A binary to package within the final package.
@@ -314,6 +661,71 @@ export interface Binary {
    */
   main?: boolean;
 }
+/**
+ * A directory tree staged by the build, laid out as it should be installed, packaged wholesale instead of declaring its files in [`Config::resources`].
+ */
+export interface StagingDir {
+  /**
+   * The path of the staging directory.
+   */
+  path: string;
+  /**
+   * The path of a JSON manifest mapping the system files of the tree, relative to [`StagingDir::path`], to their absolute installation path, e.g. `{ "etc/app.conf": "/etc/app.conf" }`.
+   *
+   * The system files are only installed by the [`PackageFormat::Deb`] and [`PackageFormat::Pacman`] packages, like the files of [`DebianConfig::files`], and are left out of the other formats.
+   */
+  manifest?: string | null;
+}
+/**
+ * A generated wrapper script installed as the main binary, setting up the environment of an interpreter bundled in the resources, like a Python virtual environment or a Node.js runtime, and running the entrypoint of the app with it.
+ *
+ * The shim is a POSIX shell script for the Linux and macOS packages. Windows targets aren't supported, since their installers expect an `.exe` main binary.
+ */
+export interface LauncherShim {
+  /**
+   * The path of the interpreter, relative to the directory of the resources in the packages, like `venv/bin/python3`.
+   */
+  interpreter: string;
+  /**
+   * The path of the entrypoint run by the interpreter, relative to the directory of the resources in the packages, like `app/main.py`.
+   */
+  entrypoint?: string | null;
+  /**
+   * The arguments passed to the interpreter before the entrypoint, like `-I`.
+   */
+  args?: string[] | null;
+  /**
+   * The environment variables set before running the interpreter.
+   *
+   * The values are expanded by the shell, and the `RESOURCES_DIR` variable is set to the directory of the resources in the installed package, like `$RESOURCES_DIR/venv`.
+   */
+  env?: {
+    [k: string]: string;
+  } | null;
+  /**
+   * Path to a custom Handlebars template of the shim.
+   *
+   * See the default template in <https://github.com/crabnebula-dev/cargo-packager/blob/main/crates/packager/src/package/launcher_shim/launcher.sh>
+   */
+  template?: string | null;
+}
+/**
+ * An external tool or a downloaded runtime file pinned for reproducible builds, see [`Config::pinned_tools`].
+ */
+export interface ToolPin {
+  /**
+   * The path of the tool to use instead of the one found in `PATH` or downloaded by the packager: the `makensis` executable, the directory of the WiX binaries, or the `linuxdeploy`, `linuxdeploy-plugin-appimage` and `AppRun` files.
+   */
+  path?: string | null;
+  /**
+   * The expected version of `makensis` or `wix`, like `3.09` or `3.11.2.4516`, compared to the version reported by the tool.
+   */
+  version?: string | null;
+  /**
+   * The expected SHA-256 hex digest of the `linuxdeploy`, `linuxdeploy-plugin-appimage` and `AppRun` files.
+   */
+  sha256?: string | null;
+}
 /**
  * A file association configuration.
  */
@@ -364,6 +776,14 @@ export interface WindowsConfig {
    * The file digest algorithm to use for creating file signatures. Required for code signing. SHA-256 is recommended.
    */
   digestAlgorithm?: string | null;
+  /**
+   * The file digest algorithms of the signatures, in order, taking precedence over [`WindowsConfig::digest_algorithm`].
+   *
+   * The file is signed with the first algorithm, then a signature is appended with `signtool.exe sign /as` for each of the next ones, timestamped with the same algorithm. This allows dual-signing for legacy systems, like Windows 7 clients that only trust SHA-1 signatures, with `["sha256", "sha1"]`, while modern systems use the SHA-256 signature.
+   *
+   * Defaults to a single SHA-256 signature. Not used with [`WindowsConfig::sign_command`].
+   */
+  digestAlgorithms?: string[] | null;
   /**
    * The SHA1 hash of the signing certificate.
    */
@@ -390,63 +810,232 @@ export interface WindowsConfig {
    * By Default we use `signtool.exe` which can be found only on Windows so if you are on another platform and want to cross-compile and sign you will need to use another tool like `osslsigncode`.
    */
   signCommand?: string | null;
-}
-/**
- * The macOS configuration.
- */
-export interface MacOsConfig {
   /**
-   * MacOS frameworks that need to be packaged with the app.
-   *
-   * Each string can either be the name of a framework (without the `.framework` extension, e.g. `"SDL2"`), in which case we will search for that framework in the standard install locations (`~/Library/Frameworks/`, `/Library/Frameworks/`, and `/Network/Library/Frameworks/`), or a path to a specific framework bundle (e.g. `./data/frameworks/SDL2.framework`).  Note that this setting just makes cargo-packager copy the specified frameworks into the OS X app bundle (under `Foobar.app/Contents/Frameworks/`); you are still responsible for:
+   * Sign using a hardware-backed key, like a smart card, a YubiKey or a PKCS#11 token, exposed through a cryptographic service provider (CSP) or key storage provider (KSP).
    *
-   * - arranging for the compiled binary to link against those frameworks (e.g. by emitting lines like `cargo:rustc-link-lib=framework=SDL2` from your `build.rs` script)
-   *
-   * - embedding the correct rpath in your binary (e.g. by running `install_name_tool -add_rpath "@executable_path/../Frameworks" path/to/binary` after compiling)
+   * When set, it takes precedence over [`WindowsConfig::certificate_thumbprint`].
    */
-  frameworks?: string[] | null;
+  hardwareKey?: WindowsHardwareKeyConfig | null;
   /**
-   * A version string indicating the minimum MacOS version that the packaged app supports (e.g. `"10.11"`). If you are using this config field, you may also want have your `build.rs` script emit `cargo:rustc-env=MACOSX_DEPLOYMENT_TARGET=10.11`.
+   * Whether to timestamp the signatures.
+   *
+   * Disabling it allows signing when no timestamp server is reachable, like in air-gapped builds, but un-timestamped signatures become invalid once the signing certificate expires, whereas timestamped signatures stay valid.
+   *
+   * The default value of this flag is `true`.
    */
-  minimumSystemVersion?: string | null;
+  timestamp?: boolean;
   /**
-   * The exception domain to use on the macOS .app package.
+   * RFC 3161 timestamp servers to fail over to, in order, when [`WindowsConfig::timestamp_url`] is unreachable.
    *
-   * This allows communication to the outside world e.g. a web server you're shipping.
+   * If [`WindowsConfig::timestamp_url`] is not set, the first one is used.
    */
-  exceptionDomain?: string | null;
+  timestampUrls?: string[] | null;
   /**
-   * Code signing identity.
+   * The shortcuts created by the MSI and NSIS installers.
    *
-   * This is typically of the form: `"Developer ID Application: TEAM_NAME (TEAM_ID)"`.
+   * Defaults to a Start Menu shortcut of the main binary.
    */
-  signingIdentity?: string | null;
+  shortcuts?: ShortcutsConfig | null;
   /**
-   * Provider short name for notarization.
+   * Whether to embed a `VERSIONINFO` resource into the main binary packaged by the MSI and NSIS installers, shown in the Details tab of its Properties dialog.
+   *
+   * The resource is built from [`Config::version`], [`Config::publisher`], [`Config::product_name`], [`Config::description`] and [`Config::copyright`] and written into a copy of the binary, which is the one signed and packaged, the original binary is left untouched.
+   *
+   * The default value of this flag is `false`.
    */
-  providerShortName?: string | null;
+  embedVersionInfo?: boolean;
   /**
-   * Path to the entitlements.plist file.
+   * The support link of the Add/Remove Programs entry of the MSI and NSIS installers, written into the `HelpLink` uninstall registry value. Must be an http or https URL.
    */
-  entitlements?: string | null;
+  helpLink?: string | null;
   /**
-   * Path to the Info.plist file for the package.
+   * The link to the publisher or product website in the Add/Remove Programs entry of the MSI and NSIS installers, written into the `URLInfoAbout` uninstall registry value. Must be an http or https URL.
+   *
+   * Defaults to [`Config::homepage`] if it is an http or https URL.
    */
-  infoPlistPath?: string | null;
+  aboutUrl?: string | null;
   /**
-   * Path to the embedded.provisionprofile file for the package.
+   * The link to the update information in the Add/Remove Programs entry of the MSI and NSIS installers, written into the `URLUpdateInfo` uninstall registry value. Must be an http or https URL.
    */
-  embeddedProvisionprofilePath?: string | null;
+  updateInfoUrl?: string | null;
   /**
-   * Apps that need to be packaged within the app.
+   * The longest path of the files installed by the MSI, NSIS and Inno Setup installers, in UTF-16 code units and counting the terminating null character like `MAX_PATH`.
+   *
+   * Packaging fails with [`Error::PathTooLong`](crate::Error::PathTooLong) when a binary or a resource would be installed at a longer path in the longest default install directory, the per-user one of a user with a 20 characters name, instead of failing when the app is installed or launched.
+   *
+   * Defaults to `260`, or to `32767` with [`WindowsConfig::long_path_aware`]. Windows Installer itself doesn't support long paths, so prefer the default with MSI installers.
    */
-  embeddedApps?: string[] | null;
+  maxPathLength?: number | null;
   /**
-   * Whether this is a background application. If true, the app will not appear in the Dock.
+   * Whether the app supports the paths longer than `MAX_PATH`, declared with the `longPathAware` setting in the application manifest of the main binary packaged by the installers, written into a copy of the binary like [`WindowsConfig::embed_version_info`].
    *
-   * Sets the `LSUIElement` flag in the macOS plist file.
+   * The long paths are only supported when they are also enabled on the system, with the `LongPathsEnabled` registry value.
+   *
+   * The default value of this flag is `false`.
+   */
+  longPathAware?: boolean;
+}
+/**
+ * A hardware-backed key used to sign with `signtool.exe`.
+ *
+ * The following arguments are passed to `signtool.exe sign`: `/f <certificate_path> /csp <provider> /kc <key_container>`
+ */
+export interface WindowsHardwareKeyConfig {
+  /**
+   * Path to the file containing the public certificate of the key.
+   */
+  certificatePath: string;
+  /**
+   * The name of the cryptographic service provider (CSP) or key storage provider (KSP) that exposes the key, for example `Microsoft Smart Card Key Storage Provider` or `eToken Base Cryptographic Provider`.
+   */
+  provider: string;
+  /**
+   * The name of the key container on the provider.
+   *
+   * Some providers accept the PIN as part of the container name, for example `[{{PIN}}]=container`.
+   */
+  keyContainer: string;
+}
+/**
+ * The shortcuts created by the MSI and NSIS installers and removed by their uninstallers.
+ */
+export interface ShortcutsConfig {
+  /**
+   * Whether to create a Start Menu shortcut.
+   *
+   * The default value of this flag is `true`.
+   */
+  startMenu?: boolean;
+  /**
+   * Whether to create a Desktop shortcut.
+   *
+   * The NSIS installer still lets the user opt in to the Desktop shortcut on its finish page when this is `false`.
+   *
+   * The default value of this flag is `false`.
+   */
+  desktop?: boolean;
+  /**
+   * The name of the Start Menu folder containing the shortcut.
+   *
+   * Defaults to [`Config::product_name`].
+   */
+  startMenuFolder?: string | null;
+  /**
+   * The command line arguments passed to the binary when launched from the shortcuts.
+   */
+  arguments?: string | null;
+  /**
+   * The name of the binary launched by the shortcuts, one of [`Config::binaries`].
+   *
+   * Defaults to the main binary.
+   */
+  binary?: string | null;
+}
+/**
+ * The macOS configuration.
+ */
+export interface MacOsConfig {
+  /**
+   * MacOS frameworks that need to be packaged with the app.
+   *
+   * Each string can either be the name of a framework (without the `.framework` extension, e.g. `"SDL2"`), in which case we will search for that framework in the standard install locations (`~/Library/Frameworks/`, `/Library/Frameworks/`, and `/Network/Library/Frameworks/`), or a path to a specific framework bundle (e.g. `./data/frameworks/SDL2.framework`).  Note that this setting just makes cargo-packager copy the specified frameworks into the OS X app bundle (under `Foobar.app/Contents/Frameworks/`); you are still responsible for:
+   *
+   * - arranging for the compiled binary to link against those frameworks (e.g. by emitting lines like `cargo:rustc-link-lib=framework=SDL2` from your `build.rs` script)
+   *
+   * - embedding the correct rpath in your binary (e.g. by running `install_name_tool -add_rpath "@executable_path/../Frameworks" path/to/binary` after compiling)
+   *
+   * The rpath and the install names of the `.dylib` frameworks are fixed by [`MacOsConfig::relocate_dylibs`].
+   */
+  frameworks?: string[] | null;
+  /**
+   * Whether to make the `.dylib` [`MacOsConfig::frameworks`] loadable on other Macs.
+   *
+   * The `LC_ID_DYLIB` of each bundled dylib and the references of the binaries and dylibs to it, discovered with `otool -L`, are rewritten to `@rpath/<name>` with `install_name_tool`, and the `@executable_path/../Frameworks` rpath is added to the binaries loading them. The packaging then fails with [`Error::NonRelocatableDylib`](crate::Error::NonRelocatableDylib) when a binary still loads a dylib from an absolute path outside of `/usr/lib` and `/System`. The modified files are signed ad hoc when there is no [`MacOsConfig::signing_identity`].
+   *
+   * Defaults to `true`.
+   */
+  relocateDylibs?: boolean | null;
+  /**
+   * A version string indicating the minimum MacOS version that the packaged app supports (e.g. `"10.11"`). If you are using this config field, you may also want have your `build.rs` script emit `cargo:rustc-env=MACOSX_DEPLOYMENT_TARGET=10.11`.
+   *
+   * It is written to the `LSMinimumSystemVersion` of the `Info.plist`, and a warning is logged when the `LC_BUILD_VERSION` or `LC_VERSION_MIN_MACOSX` load command of a binary requires a newer macOS.
+   */
+  minimumSystemVersion?: string | null;
+  /**
+   * The exception domain to use on the macOS .app package.
+   *
+   * This allows communication to the outside world e.g. a web server you're shipping.
+   */
+  exceptionDomain?: string | null;
+  /**
+   * Code signing identity.
+   *
+   * This is typically of the form: `"Developer ID Application: TEAM_NAME (TEAM_ID)"`.
+   */
+  signingIdentity?: string | null;
+  /**
+   * Provider short name for notarization.
+   */
+  providerShortName?: string | null;
+  /**
+   * The interval in seconds between two checks of the notarization status.
+   *
+   * Defaults to `30`.
+   */
+  notarizationPollInterval?: number | null;
+  /**
+   * The time in seconds after which waiting for the notarization fails.
+   *
+   * The submission keeps running on Apple's side and can be resumed later, see `notarize_resume`. Defaults to waiting until the notarization finishes.
+   */
+  notarizationTimeout?: number | null;
+  /**
+   * Path to the entitlements.plist file.
+   */
+  entitlements?: string | null;
+  /**
+   * Whether to enable the hardened runtime when codesigning native binaries.
+   *
+   * Defaults to `true`. Apple rejects notarization of apps signed without it.
+   */
+  hardenedRuntime?: boolean | null;
+  /**
+   * Path to the Info.plist file for the package.
+   */
+  infoPlistPath?: string | null;
+  /**
+   * Path to the embedded.provisionprofile file for the package.
+   */
+  embeddedProvisionprofilePath?: string | null;
+  /**
+   * Apps that need to be packaged within the app.
+   */
+  embeddedApps?: string[] | null;
+  /**
+   * Whether this is a background application. If true, the app will not appear in the Dock.
+   *
+   * Sets the `LSUIElement` flag in the macOS plist file.
    */
   backgroundApp?: boolean;
+  /**
+   * Per-architecture binaries to combine into a universal binary with `lipo`.
+   *
+   * When both directories are set, each of [`Config::binaries`] is created by running `lipo -create` on its `x86_64` and `aarch64` builds. When only one is set, a thin bundle is created from that directory instead.
+   */
+  universalBinary?: MacOsUniversalBinaryConfig | null;
+}
+/**
+ * Per-architecture binaries used to create a universal macOS binary.
+ */
+export interface MacOsUniversalBinaryConfig {
+  /**
+   * Directory containing the `x86_64` builds of [`Config::binaries`].
+   */
+  x86_64BinariesDir?: string | null;
+  /**
+   * Directory containing the `aarch64` builds of [`Config::binaries`].
+   */
+  aarch64BinariesDir?: string | null;
 }
 /**
  * Linux configuration
@@ -456,6 +1045,103 @@ export interface LinuxConfig {
    * Flag to indicate if desktop entry should be generated.
    */
   generateDesktopEntry?: boolean;
+  /**
+   * The `StartupWMClass` of the desktop entry, used by the desktop environment to group the app windows with its launcher.
+   */
+  startupWmClass?: string | null;
+  /**
+   * The `StartupNotify` value of the desktop entry, whether the app supports startup notification.
+   */
+  startupNotify?: boolean | null;
+  /**
+   * Additional key-value pairs to add to the `[Desktop Entry]` group of the desktop entry.
+   *
+   * Keys must follow the desktop entry specification key syntax, `A-Za-z0-9-` optionally followed by a `[locale]` suffix, and must not be one of the keys generated by the packager. See <https://specifications.freedesktop.org/desktop-entry-spec/latest/recognized-keys.html>
+   */
+  extraDesktopEntries?: {
+    [k: string]: string;
+  } | null;
+  /**
+   * Symlinks to add to the deb and pacman packages, mapping the link path to the path of its target in the package.
+   *
+   * A link that is only a file name is created in `/usr/bin`, so `{ "myapp": "/usr/lib/myapp/bin/myapp" }` makes `myapp` available on `PATH`. Several links can point to the same target. The targets must exist in the package.
+   */
+  symlinks?: {
+    [k: string]: string;
+  } | null;
+  /**
+   * The AppStream metadata describing the app to the Linux software centers like GNOME Software and Discover, installed at `usr/share/metainfo/<id>.metainfo.xml` in the deb, pacman and AppImage packages.
+   */
+  appstream?: AppStreamConfig | null;
+}
+/**
+ * The AppStream metadata of a Linux app, see <https://www.freedesktop.org/software/appstream/docs/sect-Metadata-Application.html>
+ */
+export interface AppStreamConfig {
+  /**
+   * The component id, which must match the id of the desktop entry, the main binary name the `.desktop` file is named after.
+   *
+   * Defaults to the main binary name.
+   */
+  id?: string | null;
+  /**
+   * A short summary of what the app does, required.
+   */
+  summary: string;
+  /**
+   * The description of the app, required. Paragraphs are separated by blank lines.
+   */
+  description: string;
+  /**
+   * The license of the metadata itself. Defaults to `CC0-1.0`.
+   */
+  metadataLicense?: string | null;
+  /**
+   * The SPDX license expression of the app, e.g. `MIT OR Apache-2.0`.
+   */
+  projectLicense?: string | null;
+  /**
+   * The screenshots shown in the software centers, the first one is the default.
+   */
+  screenshots?: AppStreamScreenshot[] | null;
+  /**
+   * The releases of the app, newest first.
+   *
+   * Defaults to the entries of [`DebianConfig::changelog`] when the entries are configured, otherwise to a single release of [`Config::version`].
+   */
+  releases?: AppStreamRelease[] | null;
+}
+/**
+ * A screenshot of the [`AppStreamConfig`].
+ */
+export interface AppStreamScreenshot {
+  /**
+   * The `http` or `https` URL of the image.
+   */
+  url: string;
+  /**
+   * The caption of the screenshot.
+   */
+  caption?: string | null;
+}
+/**
+ * A release of the [`AppStreamConfig`].
+ */
+export interface AppStreamRelease {
+  /**
+   * The version of the release.
+   */
+  version: string;
+  /**
+   * The date of the release, formatted as `YYYY-MM-DD`.
+   *
+   * Defaults to the `SOURCE_DATE_EPOCH` environment variable when set, otherwise to the current date.
+   */
+  date?: string | null;
+  /**
+   * The changes of the release, listed in its description.
+   */
+  changes?: string[] | null;
 }
 /**
  * The Linux Debian configuration.
@@ -468,9 +1154,9 @@ export interface DebianConfig {
   /**
    * Path to a custom desktop file Handlebars template.
    *
-   * Available variables: `categories`, `comment` (optional), `exec`, `icon` and `name`.
+   * Available variables: `categories`, `keywords` (optional), `comment` (optional), `exec`, `icon`, `name`, `mime_type` (optional), `startup_wm_class` (optional), `startup_notify` (optional) and `extra_entries`.
    *
-   * Default file contents: ```text [Desktop Entry] Categories={{categories}} {{#if comment}} Comment={{comment}} {{/if}} Exec={{exec}} {{exec_arg}} Icon={{icon}} Name={{name}} Terminal=false Type=Application {{#if mime_type}} MimeType={{mime_type}} {{/if}} ```
+   * Default file contents: ```text [Desktop Entry] Categories={{categories}} {{#if keywords}} Keywords={{keywords}} {{/if}} {{#if comment}} Comment={{comment}} {{/if}} Exec={{exec}} {{exec_arg}} Icon={{icon}} Name={{name}} Terminal=false Type=Application {{#if mime_type}} MimeType={{mime_type}} {{/if}} {{#if startup_wm_class}} StartupWMClass={{startup_wm_class}} {{/if}} {{#if startup_notify}} StartupNotify={{startup_notify}} {{/if}} {{#each extra_entries}} {{@key}}={{this}} {{/each}} ```
    *
    * The `{{exec_arg}}` will be set to: * "%F", if at least one [Config::file_associations] was specified but no deep link protocols were given. * The "%F" arg means that your application can be invoked with multiple file paths. * "%U", if at least one [Config::deep_link_protocols] was specified. * The "%U" arg means that your application can be invoked with multiple URLs. * If both [Config::file_associations] and [Config::deep_link_protocols] were specified, the "%U" arg will be used, causing the file paths to be passed to your app as `file://` URLs. * An empty string "" (nothing) if neither are given. * This means that your application will never be invoked with any URLs or file paths.
    *
@@ -494,12 +1180,228 @@ export interface DebianConfig {
     [k: string]: string;
   } | null;
   /**
-   * Name to use for the `Package` field in the Debian Control file. Defaults to [`Config::product_name`] converted to kebab-case.
+   * Name to use for the `Package` field in the Debian Control file and the name of the `.deb` file.
+   *
+   * It is used verbatim and must match `[a-z0-9][a-z0-9+.-]+` as required by the Debian policy, see <https://www.debian.org/doc/debian-policy/ch-controlfields.html#source>
+   *
+   * Defaults to [`Config::product_name`] converted to kebab-case for the `Package` field and to the [`Config::slug`] for the file name.
    */
   packageName?: string | null;
+  /**
+   * The list of Debian dependencies that must be installed and configured before this package is unpacked, written to the `Pre-Depends` field.
+   *
+   * See <https://www.debian.org/doc/debian-policy/ch-relationships.html#binary-dependencies-depends-recommends-suggests-enhances-pre-depends>
+   */
+  preDepends?: Dependencies | null;
+  /**
+   * The packages this package replaces files of, written to the `Replaces` field, like the previous name of a renamed package, with an optional version constraint: `oldapp (<< 2.0.0)`.
+   *
+   * See <https://www.debian.org/doc/debian-policy/ch-relationships.html#overwriting-files-and-replacing-packages-replaces>
+   */
+  replaces?: string[] | null;
+  /**
+   * The virtual packages this package provides, written to the `Provides` field.
+   *
+   * See <https://www.debian.org/doc/debian-policy/ch-relationships.html#virtual-packages-provides>
+   */
+  provides?: string[] | null;
+  /**
+   * The packages that can't be installed alongside this package, written to the `Conflicts` field.
+   *
+   * Together with [`DebianConfig::replaces`] and [`DebianConfig::provides`], it lets a renamed package take over from its previous name on upgrade.
+   *
+   * See <https://www.debian.org/doc/debian-policy/ch-relationships.html#conflicting-binary-packages-conflicts>
+   */
+  conflicts?: string[] | null;
+  /**
+   * The changelog of the package, installed gzipped at `usr/share/doc/<package>/changelog.Debian.gz`.
+   *
+   * See <https://www.debian.org/doc/debian-policy/ch-source.html#debian-changelog-debian-changelog>
+   */
+  changelog?: DebianChangelog | null;
+  /**
+   * The `Maintainer` field of the Debian Control file, in the `Name <email>` format.
+   *
+   * Defaults to [`Config::authors`], or to [`Config::publisher`](Config::publisher()) when no author is set.
+   */
+  maintainer?: string | null;
+  /**
+   * The `Homepage` field of the Debian Control file, an http or https URL.
+   *
+   * Defaults to [`Config::homepage`] if it is an http or https URL.
+   */
+  homepage?: string | null;
+  /**
+   * The `Vcs-Browser` field of the Debian Control file, the http or https URL of a web interface to browse the repository of the app.
+   */
+  vcsBrowser?: string | null;
+  /**
+   * The `Vcs-Git` field of the Debian Control file, the URL of the Git repository of the app, optionally followed by `-b <branch>`, like `https://github.com/example/app.git -b main`.
+   *
+   * See <https://www.debian.org/doc/debian-policy/ch-controlfields.html#version-control-system-vcs-fields>
+   */
+  vcsGit?: string | null;
+  /**
+   * The `Bugs` field of the Debian Control file, where to report the bugs of the package, an http, https, `mailto:` or `debbugs://` URL.
+   */
+  bugs?: string | null;
+  /**
+   * Custom fields appended to the Debian Control file, like `X-MyOrg-Build-Id`.
+   *
+   * The field names must start with `X-` and the values must be a single line. Standard fields, like `Recommends`, are rejected unless [`DebianConfig::allow_standard_fields`] is set.
+   */
+  customFields?: {
+    [k: string]: string;
+  } | null;
+  /**
+   * Whether [`DebianConfig::custom_fields`] can set the standard fields of the Debian Control file, replacing the generated values.
+   */
+  allowStandardFields?: boolean;
+  /**
+   * The owners of the files matching glob patterns, see [`DebianFileOwner`].
+   *
+   * The first matching pattern applies, use `*` to set the owner of every file.
+   */
+  fileOwners?: DebianFileOwner[] | null;
+  /**
+   * Check that the [`DebianConfig::depends`] and [`DebianConfig::pre_depends`] are satisfiable by the available packages before building the package, catching the typos in the package names and the versions that don't exist.
+   *
+   * It is opt-in as it needs the package list of the target distribution.
+   */
+  dependencyCheck?: DebianDependencyCheck | null;
+  /**
+   * Split the resources and the bundled runtime, installed at `/usr/lib/<main-binary-name>`, into a `<package>-data` package that the main package depends on, so the large resources that rarely change aren't downloaded again on every update of the app.
+   *
+   * The data package is written next to the main package, as `<name>-data_<version>_<arch>.deb`.
+   */
+  dataPackage?: DebianDataPackage | null;
+  /**
+   * Whether to build byte-identical packages from the same files: the entries of the `data.tar` and `control.tar` are sorted by path, owned by `root` unless [`DebianConfig::file_owners`] say otherwise, and modified at the `SOURCE_DATE_EPOCH`, or `0` when it is not set, like the members of the `.deb` archive.
+   */
+  reproducible?: boolean;
+  /**
+   * The compression of the `control.tar` member of the `.deb` archive, defaults to [`DebianCompression::Gzip`].
+   */
+  controlCompression?: DebianCompression | null;
+  /**
+   * The compression level of the `control.tar` member, from `0` to `9`, or from `1` to `19` with [`DebianCompression::Zstd`]. Defaults to the default level of the compression.
+   */
+  controlCompressionLevel?: number | null;
+  /**
+   * The compression of the `data.tar` member of the `.deb` archive, defaults to [`DebianCompression::Gzip`].
+   */
+  dataCompression?: DebianCompression | null;
+  /**
+   * The compression level of the `data.tar` member, from `0` to `9`, or from `1` to `19` with [`DebianCompression::Zstd`]. Defaults to the default level of the compression.
+   */
+  dataCompressionLevel?: number | null;
+  /**
+   * The oldest dpkg version the packages must install with, like `1.19.7` for Debian 10, rejecting the [`DebianConfig::control_compression`] and [`DebianConfig::data_compression`] it can't extract.
+   *
+   * The `control.tar` can only be uncompressed or compressed with xz since dpkg 1.17.6, the `data.tar` can be compressed with xz since dpkg 1.15.6, and both can be compressed with Zstandard since dpkg 1.21.18.
+   */
+  minDpkgVersion?: string | null;
+  /**
+   * Whether to generate the `postinst` and `postrm` maintainer scripts that run `update-desktop-database` and `gtk-update-icon-cache` after the package is installed and removed, so the desktop entry and icons show up without logging out. The commands are skipped when the tools aren't installed.
+   *
+   * Defaults to `true`.
+   */
+  refreshDesktopCaches?: boolean | null;
+}
+/**
+ * An entry of a [`DebianChangelog`].
+ */
+export interface DebianChangelogEntry {
+  /**
+   * The version of the package, the version of the newest entry must be [`Config::version`].
+   */
+  version: string;
+  /**
+   * The date of the entry, formatted according to RFC 2822, e.g. `Mon, 01 Jan 2024 12:00:00 +0000`.
+   *
+   * Defaults to the `SOURCE_DATE_EPOCH` environment variable when set, otherwise to the current date.
+   */
+  date?: string | null;
+  /**
+   * The author of the entry, formatted as `Name <email>`.
+   *
+   * Defaults to the first of the [`Config::authors`].
+   */
+  author?: string | null;
+  /**
+   * The changes of this version, where line breaks continue the same change.
+   */
+  changes: string[];
+  /**
+   * The distributions of this version. Defaults to `unstable`.
+   */
+  distribution?: string | null;
+  /**
+   * The urgency of the upgrade, one of `low`, `medium`, `high`, `emergency` or `critical`. Defaults to `medium`.
+   */
+  urgency?: string | null;
+}
+/**
+ * The owner of the files of a Debian package matching a glob pattern.
+ *
+ * dpkg sets the owner by name when the user or group exists when the package is unpacked, and by numeric id otherwise, so the user and group should be created by a `preinst` maintainer script, or exist on the target systems. The other files are owned by `root`.
+ */
+export interface DebianFileOwner {
+  /**
+   * The glob pattern matched against the installed paths of the files and directories, like `/var/lib/my-app/*`, where `*` also matches the `/` separator of the directories.
+   */
+  pattern: string;
+  /**
+   * The name of the user owning the matching files, defaults to `root`.
+   */
+  user?: string | null;
+  /**
+   * The numeric id of the user, used when the user doesn't exist. Defaults to `0`.
+   */
+  uid?: number | null;
+  /**
+   * The name of the group owning the matching files, defaults to `root`.
+   */
+  group?: string | null;
+  /**
+   * The numeric id of the group, used when the group doesn't exist. Defaults to `0`.
+   */
+  gid?: number | null;
+}
+/**
+ * A companion Debian package holding the resources of the app, see [`DebianConfig::data_package`].
+ */
+export interface DebianDataPackage {
+  /**
+   * The version of the data package, which the main package depends on.
+   *
+   * Bump it only when the resources change so apt doesn't download them again on updates of the app. Defaults to [`Config::version`].
+   */
+  version?: string | null;
+  /**
+   * Whether the resources don't depend on the architecture, building an `all` architecture package shared by the packages of every architecture.
+   */
+  archIndependent?: boolean;
+}
+/**
+ * The check of the dependencies of a Debian package, see [`DebianConfig::dependency_check`].
+ */
+export interface DebianDependencyCheck {
+  /**
+   * A Debian `Packages` index listing the available packages, like the `*_Packages` files of `/var/lib/apt/lists` or the output of `apt-cache dumpavail`.
+   *
+   * Defaults to the output of `apt-cache dumpavail`, the packages of the apt repositories configured on the build machine.
+   */
+  packageList?: string | null;
+  /**
+   * Fail the packaging with [`Error::UnsatisfiableDependency`](crate::Error::UnsatisfiableDependency) instead of logging a warning for each unsatisfiable dependency.
+   */
+  strict?: boolean;
 }
 /**
  * The Linux AppImage configuration.
+ *
+ * When the `SOURCE_DATE_EPOCH` environment variable is set, the files of the AppImage use it as their modification time so building the same input produces the same AppImage.
  */
 export interface AppImageConfig {
   /**
@@ -526,6 +1428,65 @@ export interface AppImageConfig {
    * List of globs of libraries to exclude from the final AppImage. For example, to exclude libnss3.so, you'd specify `libnss3*`
    */
   excludedLibs?: string[] | null;
+  /**
+   * List of globs of files and directories to remove from the AppDir before squashing it into the AppImage, relative to the AppDir. For example, to remove the translations and the documentation, you'd specify `usr/share/locale/**` and `usr/share/doc/**`.
+   *
+   * The files are removed after the libraries are deployed, so it also removes the files they bring. A warning is emitted for the globs that match nothing.
+   */
+  exclude?: string[] | null;
+  /**
+   * The update information to embed into the AppImage, used by [AppImageUpdate](https://github.com/AppImageCommunity/AppImageUpdate) for delta updates.
+   *
+   * A `.zsync` file is also generated next to the AppImage, which must be uploaded alongside it.
+   *
+   * Supported formats are: - `zsync|<url to the .zsync file>` - `gh-releases-zsync|<github username>|<repo>|<release tag or latest>|<.zsync file name>` - `pling-v1-zsync|<pling product id>|<.zsync file name>`
+   *
+   * See <https://github.com/AppImage/AppImageSpec/blob/master/draft.md#update-information>
+   */
+  updateInformation?: string | null;
+  /**
+   * The AppImage runtime to use instead of the one embedded by `appimagetool`, for example an older runtime compatible with the glibc of old distributions.
+   *
+   * The runtime is the ELF executable prepended to the squashfs image of the AppDir, it can be: - an `http://` or `https://` URL to download it from, - a path to the runtime file, - a release tag of <https://github.com/AppImage/type2-runtime/releases>, for example `continuous`, - `vendored`, the runtime embedded in cargo-packager with the `vendored-appimage-runtime` feature, see `vendor/appimage-runtime/README.md` in the cargo-packager crate.
+   *
+   * `{{arch}}` is replaced with the AppImage architecture (`x86_64`, `i686`, `aarch64` or `armhf`) in URLs and paths, and the runtime must be built for that architecture.
+   *
+   * A path or the `vendored` runtime are read without network access. For fully offline builds, also pin `linuxdeploy`, `linuxdeploy-plugin-appimage` and `apprun` to local files in [`Config::pinned_tools`].
+   *
+   * The runtime is executed every time the AppImage is launched and is not verified, so only use runtimes from trusted sources. Its size is also added to the AppImage, statically linked runtimes being larger than the ones linking to `libfuse`.
+   */
+  runtime?: string | null;
+  /**
+   * Extra arguments appended to the `linuxdeploy-plugin-appimage` invocation, after the ones set by the packager.
+   *
+   * This is an unsupported escape hatch for the tool options not exposed by the packager, use it at your own risk.
+   */
+  extraToolArgs?: string[] | null;
+  /**
+   * The architectures to build a separate AppImage for, each from its own set of binaries, instead of a single AppImage for [`Config::target_triple`].
+   *
+   * The AppImages are named with their architecture and the ELF machine type of the binaries must match the architecture they are declared for. The `latest.json` summary refers to the AppImage of [`Config::target_triple`] when it is one of the architectures, otherwise the first one.
+   */
+  architectures?: AppImageArchitecture[] | null;
+}
+/**
+ * An architecture of [`AppImageConfig::architectures`] and the binaries built for it.
+ */
+export interface AppImageArchitecture {
+  /**
+   * The target triple of the binaries, for example `aarch64-unknown-linux-gnu`.
+   */
+  targetTriple: string;
+  /**
+   * The directory of the [`Config::binaries`] built for this architecture, used instead of [`Config::binaries_dir`].
+   */
+  binariesDir: string;
+  /**
+   * The AppImage runtime of this architecture, used instead of [`AppImageConfig::runtime`].
+   *
+   * See [`AppImageConfig::runtime`] for the supported values.
+   */
+  runtime?: string | null;
 }
 /**
  * The Linux pacman configuration.
@@ -565,6 +1526,57 @@ export interface PacmanConfig {
    * Source of the package to be stored at PKGBUILD. PKGBUILD is a bash script, so version can be referred as ${pkgver}
    */
   source?: string[] | null;
+  /**
+   * Name to use for the `pkgname` of the PKGBUILD and the name of the package file.
+   *
+   * It is used verbatim and must only contain lowercase alphanumerics and `@._+-`, without starting with a hyphen or a period, see <https://wiki.archlinux.org/title/PKGBUILD#pkgname>
+   *
+   * Defaults to [`Config::product_name`] converted to kebab-case for the `pkgname` and to the [`Config::slug`] for the file name.
+   */
+  packageName?: string | null;
+}
+/**
+ * The Linux self-extracting installer shell script configuration.
+ *
+ * The generated `<app>_<version>_<arch>.sh` script embeds a base64-encoded `.tar.gz` of the binaries and resources, verifies its SHA-256 checksum and extracts it into a prefix: the binaries are installed to `<prefix>/bin` and the resources to `<prefix>/lib/<main-binary-name>`.
+ *
+ * Run it with `--prefix <dir>` or the `PREFIX` environment variable to change the prefix, and with `--uninstall` to remove the installed files. It requires a POSIX `sh`, `base64`, `gzip`, `tar` and either `sha256sum` or `shasum`.
+ */
+export interface SharConfig {
+  /**
+   * The default installation prefix, defaults to `/usr/local`.
+   */
+  prefix?: string | null;
+}
+/**
+ * The portable zip archive configuration.
+ *
+ * The generated `<app>_<version>_<arch>.zip` holds the binaries at its root, with the resources and the bundled runtime next to them.
+ */
+export interface ZipConfig {
+  /**
+   * The compression method of the entries, defaults to [`ZipCompression::Deflate`].
+   */
+  compression?: ZipCompression | null;
+  /**
+   * The compression methods of the entries matching glob patterns, overriding [`ZipConfig::compression`].
+   *
+   * The first matching pattern applies.
+   */
+  compressionOverrides?: ZipCompressionOverride[] | null;
+}
+/**
+ * A [`ZipCompression`] for the entries of a zip archive matching a glob pattern.
+ */
+export interface ZipCompressionOverride {
+  /**
+   * The glob pattern matched against the paths of the entries in the archive, like `*.png`, where `*` also matches the `/` separator of the directories.
+   */
+  pattern: string;
+  /**
+   * The compression method of the matching entries.
+   */
+  compression: ZipCompression;
 }
 /**
  * The wix format configuration
@@ -621,17 +1633,43 @@ export interface WixConfig {
   /**
    * Path to a bitmap file to use as the installation user interface banner. This bitmap will appear at the top of all but the first page of the installer.
    *
-   * The required dimensions are 493px × 58px.
+   * The required dimensions are 493px × 58px, in the BMP format. Defaults to the standard WiX banner.
    */
   bannerPath?: string | null;
   /**
-   * Path to a bitmap file to use on the installation user interface dialogs. It is used on the welcome and completion dialogs. The required dimensions are 493px × 312px.
+   * Path to a bitmap file to use on the installation user interface dialogs. It is used on the welcome and completion dialogs. The required dimensions are 493px × 312px, in the BMP format. Defaults to the standard WiX dialog image.
    */
   dialogImagePath?: string | null;
+  /**
+   * Path to an RTF file with the license agreement the user must accept to install the app.
+   *
+   * Takes precedence over [`Config::license_file`], when neither is set no license page is shown.
+   */
+  licenseAgreement?: string | null;
   /**
    * Enables FIPS compliant algorithms.
    */
   fipsCompliant?: boolean;
+  /**
+   * The `Manufacturer` property of the installer, which must not be empty.
+   *
+   * Defaults to [`Config::publisher`](Config::publisher()).
+   */
+  manufacturer?: string | null;
+  /**
+   * The name of the directory in `Program Files` the app is installed to by default, which the user can change in the install directory dialog of the installer.
+   *
+   * The MSI is always installed per machine, so the default directory is in the `Program Files` of the machine.
+   *
+   * Defaults to [`Config::product_name`].
+   */
+  installDirName?: string | null;
+  /**
+   * Extra arguments appended to the `light.exe` invocation, after the ones set by the packager.
+   *
+   * This is an unsupported escape hatch for the tool options not exposed by the packager, use it at your own risk.
+   */
+  extraToolArgs?: string[] | null;
 }
 /**
  * The NSIS format configuration.
@@ -640,9 +1678,17 @@ export interface NsisConfig {
   /**
    * Set the compression algorithm used to compress files in the installer.
    *
+   * [`NsisCompression::Lzma`] gives the smallest installers but is the slowest to compress, [`NsisCompression::Zlib`] is the fastest to compress and decompress but gives larger installers.
+   *
    * See <https://nsis.sourceforge.io/Reference/SetCompressor>
    */
   compression?: NsisCompression | null;
+  /**
+   * Whether all the files are compressed together in a single block, enabled by default.
+   *
+   * Solid compression usually gives smaller installers, but the installer must decompress the whole block before extracting the files. It can't be enabled with [`NsisCompression::Off`].
+   */
+  solid?: boolean | null;
   /**
    * A custom `.nsi` template to use.
    *
@@ -677,6 +1723,18 @@ export interface NsisConfig {
    * The path to an icon file used as the installer icon.
    */
   installerIcon?: string | null;
+  /**
+   * Path to a text or RTF file with the license agreement the user must accept to install the app.
+   *
+   * Takes precedence over [`Config::license_file`], when neither is set no license page is shown.
+   */
+  licenseAgreement?: string | null;
+  /**
+   * The text of the license agreement the user must accept to install the app, embedded into the installer instead of a separate license file. Must not be empty.
+   *
+   * Takes precedence over [`NsisConfig::license_agreement`] and [`Config::license_file`].
+   */
+  licenseText?: string | null;
   /**
    * Whether the installation will be for all users or just the current user.
    */
@@ -684,7 +1742,7 @@ export interface NsisConfig {
   /**
    * A list of installer languages. By default the OS language is used. If the OS language is not in the list of languages, the first language will be used. To allow the user to select the language, set `display_language_selector` to `true`.
    *
-   * See <https://github.com/kichik/nsis/tree/9465c08046f00ccb6eda985abbdbf52c275c6c4d/Contrib/Language%20files> for the complete list of languages.
+   * The language names are case-insensitive and the build fails if one of them is not supported by NSIS, see <https://github.com/kichik/nsis/tree/9465c08046f00ccb6eda985abbdbf52c275c6c4d/Contrib/Language%20files> for the complete list of languages.
    */
   languages?: string[] | null;
   /**
@@ -707,6 +1765,82 @@ export interface NsisConfig {
    * The path should use a constant from <https://nsis.sourceforge.io/Docs/Chapter4.html#varconstant> in addition to `$IDENTIFIER`, `$PUBLISHER` and `$PRODUCTNAME`, for example, if you store your app data in `C:\\Users\\<user>\\AppData\\Local\\<your-company-name>\\<your-product-name>` you'd need to specify ```toml [package.metadata.packager.nsis] appdata-paths = ["$LOCALAPPDATA/$PUBLISHER/$PRODUCTNAME"] ```
    */
   appdataPaths?: string[] | null;
+  /**
+   * NSIS commands that will be injected at the start of the uninstall section, before any of the app files are removed.
+   *
+   * The commands can use the NSIS constants from <https://nsis.sourceforge.io/Docs/Chapter4.html#varconstant>, for example `$INSTDIR` which points to the installation directory, in addition to `${PRODUCTNAME}`, `${MAINBINARYNAME}`, `${MANUFACTURER}` and `${IDENTIFIER}`.
+   *
+   * The commands must not open or close sections or functions.
+   *
+   * ### Example ```toml [package.metadata.packager.nsis] uninstaller-commands = """ nsExec::Exec 'sc stop "${PRODUCTNAME}Service"' RMDir /r "$INSTDIR\\data" """ ```
+   */
+  uninstallerCommands?: string | null;
+  /**
+   * Custom pages shown after the install directory page, in order.
+   *
+   * Each page includes its `.nsh` script and is wired with `Page custom <show> <leave>`, so the script only needs to define the functions creating and leaving the page, usually with [nsDialogs](https://nsis.sourceforge.io/Docs/nsDialogs/Readme.html).
+   *
+   * The functions can read `$INSTDIR` and `$PassiveMode`, which is `1` when the installer runs with `/P`, call `SkipIfPassive` to skip the page in passive mode, and use `Abort` in the leave function to stay on the page.
+   *
+   * ### Example ```toml [[package.metadata.packager.nsis.custom-pages]] script = "installer/license-key.nsh" show = "LicenseKeyPage" leave = "LicenseKeyPageLeave" ```
+   */
+  customPages?: NsisCustomPage[] | null;
+  /**
+   * The publisher of the installer, shown in the installed apps list and used for the `${MANUFACTURER}` and `$PUBLISHER` variables.
+   *
+   * Defaults to [`Config::publisher`](Config::publisher()).
+   */
+  publisher?: string | null;
+  /**
+   * Extra arguments appended to the `makensis` invocation, after the ones set by the packager.
+   *
+   * This is an unsupported escape hatch for the tool options not exposed by the packager, use it at your own risk.
+   */
+  extraToolArgs?: string[] | null;
+}
+/**
+ * The Inno Setup format configuration.
+ *
+ * The packager generates a `.iss` script installing the binaries and resources with the [`WindowsConfig::shortcuts`] and the [`Config::file_associations`], and compiles it with `ISCC` into `<app>_<version>_<arch>-innosetup.exe` when the compiler is found in `PATH` or in the default Inno Setup 6 installation directory. Otherwise the script is written to `<app>_<version>_<arch>-innosetup.iss`, referencing the files to install by their absolute paths on the packaging machine.
+ *
+ * The installer and the uninstaller are signed like the NSIS installer, see [`WindowsConfig`].
+ */
+export interface InnoSetupConfig {
+  /**
+   * A custom handlebars template of the `.iss` script to use instead of the default one.
+   *
+   * See the default template in <https://github.com/crabnebula-dev/cargo-packager/blob/main/crates/packager/src/package/innosetup/installer.iss>
+   */
+  template?: string | null;
+  /**
+   * The publisher of the installer, shown in the installed apps list.
+   *
+   * Defaults to [`Config::publisher`](Config::publisher()).
+   */
+  publisher?: string | null;
+  /**
+   * Extra arguments appended to the `ISCC` invocation, after the ones set by the packager.
+   *
+   * This is an unsupported escape hatch for the tool options not exposed by the packager, use it at your own risk.
+   */
+  extraToolArgs?: string[] | null;
+}
+/**
+ * A custom page of the NSIS installer, see [`NsisConfig::custom_pages`].
+ */
+export interface NsisCustomPage {
+  /**
+   * The path to the `.nsh` script defining the page functions.
+   */
+  script: string;
+  /**
+   * The name of the function creating and showing the page.
+   */
+  show: string;
+  /**
+   * The name of the function called when leaving the page.
+   */
+  leave?: string | null;
 }
 /**
  * The Apple Disk Image (.dmg) configuration.
@@ -732,6 +1866,47 @@ export interface DmgConfig {
    * Position of application folder on window.
    */
   appFolderPosition?: Position | null;
+  /**
+   * The filesystem of the disk image, passed to `hdiutil create -fs`. Defaults to [`DmgFilesystem::HfsPlus`].
+   *
+   * A warning is logged when it can't be mounted by the [`MacOsConfig::minimum_system_version`] of the app.
+   */
+  filesystem?: DmgFilesystem | null;
+  /**
+   * Extra arguments appended to the `create-dmg` invocation, after the ones set by the packager.
+   *
+   * This is an unsupported escape hatch for the tool options not exposed by the packager, use it at your own risk.
+   */
+  extraToolArgs?: string[] | null;
+}
+/**
+ * The macOS installer package (.pkg) configuration.
+ */
+export interface PkgConfig {
+  /**
+   * The absolute path of the directory where the `.app` is installed. Defaults to `/Applications`.
+   */
+  installLocation?: string | null;
+  /**
+   * A script run by the installer before installing the app, which must start with a `#!` shebang.
+   */
+  preinstallScript?: string | null;
+  /**
+   * A script run by the installer after installing the app, which must start with a `#!` shebang.
+   */
+  postinstallScript?: string | null;
+  /**
+   * The identity used to sign the installer package, a `Developer ID Installer` certificate of the keychain or of [`MacOsConfig::signing_certificate`].
+   *
+   * The package is notarized with the [`MacOsConfig`] notarization credentials when signed.
+   */
+  signingIdentity?: string | null;
+  /**
+   * Extra arguments appended to the `pkgbuild` invocation, after the ones set by the packager.
+   *
+   * This is an unsupported escape hatch for the tool options not exposed by the packager, use it at your own risk.
+   */
+  extraToolArgs?: string[] | null;
 }
 /**
  * Position coordinates struct.
@@ -759,3 +1934,118 @@ export interface Size {
    */
   height: number;
 }
+/**
+ * Signing configuration.
+ */
+export interface SigningConfig {
+  /**
+   * The private key to use for signing.
+   */
+  privateKey: string;
+  /**
+   * The private key password.
+   *
+   * If `None`, user will be prompted to write a password. You can skip the prompt by specifying an empty string.
+   */
+  password?: string | null;
+  /**
+   * A template for the trusted comment of the signatures.
+   *
+   * The following placeholders are substituted at sign time: - `{timestamp}`: the number of seconds since the Unix epoch. - `{file}`: the file name of the signed file. - `{version}`: the [`SigningConfig::version`]. - `{channel}`: the [`SigningConfig::channel`].
+   *
+   * Defaults to [`DEFAULT_TRUSTED_COMMENT`].
+   */
+  trustedComment?: string | null;
+  /**
+   * The version substituted for the `{version}` placeholder of [`SigningConfig::trusted_comment`].
+   */
+  version?: string | null;
+  /**
+   * The release channel substituted for the `{channel}` placeholder of [`SigningConfig::trusted_comment`].
+   */
+  channel?: string | null;
+  /**
+   * The algorithms of the signature set created for each file, see [`sign_file_set`].
+   *
+   * When not set, a single [`SignatureAlgorithm::Minisign`] signature is created in `<file>.sig`.
+   */
+  algorithms?: SignatureAlgorithm[] | null;
+  /**
+   * The extensions of the files signed by [`crate::sign_outputs`], for example `["AppImage", "dmg", "tar.gz"]`.
+   *
+   * Each extension is matched case-insensitively against the end of the file names, with or without a leading `.`, so `tar.gz` matches `app.tar.gz` and `app.app.tar.gz`.
+   *
+   * Defaults to the primary artifact of each package format, see [`primary_extension`].
+   */
+  extensions?: string[] | null;
+  /**
+   * The extensions of the files never signed by [`crate::sign_outputs`], taking precedence over [`SigningConfig::extensions`].
+   */
+  excludeExtensions?: string[] | null;
+  /**
+   * The URL of an RFC 3161 time-stamping authority, like `http://timestamp.digicert.com`, that timestamps each signature after signing.
+   *
+   * The timestamp is written next to the signature, in `<signature>.tsr`, see the [`timestamp`](crate::timestamp) module for the protocol.
+   */
+  timestampUrl?: string | null;
+  /**
+   * Whether the minisign signatures are pre-hashed, signing the BLAKE2b-512 hash of the file instead of the whole file, see the [module documentation](self#pre-hashed-and-legacy-signatures).
+   *
+   * Pre-hashed signatures are required to sign large files, like multi-GB DMGs, without reading them into memory, and are the ones expected by the updater. Disable it only for verifiers that don't support them. Defaults to `true`.
+   */
+  prehash?: boolean;
+  /**
+   * The package formats left unsigned by [`crate::sign_outputs`], which writes the SHA-256 digest of their files into `<file>.sha256` instead, see the [module documentation](self#hash-only-formats).
+   */
+  hashOnlyFormats?: PackageFormat[] | null;
+}
+/**
+ * The software bill of materials configuration, see [`Config::sbom`].
+ */
+export interface SbomConfig {
+  /**
+   * The format of the SBOM, defaults to [`SbomFormat::CycloneDx`].
+   */
+  format?: SbomFormat;
+  /**
+   * Whether to also embed the SBOM into each package, as the `sbom.cdx.json` or `sbom.spdx.json` file at the root of its resources: in `usr/lib/<main-binary-name>` on Linux, in `Contents/Resources` of the macOS app and in the installation directory on Windows.
+   */
+  embed?: boolean;
+  /**
+   * The path of the `Cargo.toml` of the crate of the app, defaults to the one in the current directory.
+   */
+  manifestPath?: string | null;
+}
+/**
+ * The format of a software bill of materials.
+ */
+export type SbomFormat = "cyclonedx" | "spdx";
+/**
+ * A variant of the app, overriding a subset of the [`Config`] fields.
+ *
+ * See [`Config::flavors`].
+ */
+export interface Flavor {
+  /**
+   * Overrides [`Config::product_name`].
+   */
+  productName?: string | null;
+  /**
+   * Overrides [`Config::identifier`].
+   */
+  identifier?: string | null;
+  /**
+   * Overrides [`Config::icons`].
+   */
+  icons?: string[] | null;
+  /**
+   * A suffix appended as is to [`Config::version`], for example `-nightly`.
+   */
+  versionSuffix?: string | null;
+  /**
+   * Overrides [`Config::signing`], for example to sign the flavor of a release channel with its own key.
+   *
+   * The `{channel}` of its trusted comment defaults to the name of the flavor, so the channel is selected by the flavors being packaged, see the `--flavor` CLI flag. The CLI signing flags, like `--private-key` and `--channel`, still take precedence.
+   */
+  signing?: SigningConfig | null;
+}
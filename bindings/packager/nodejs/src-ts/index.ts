@@ -19,6 +19,22 @@ export interface SigningConfig {
    * You can skip the prompt by specifying an empty string.
    */
   password?: string;
+  /**
+   * A template for the trusted comment of the signatures.
+   *
+   * Supports the `{timestamp}`, `{file}`, `{version}` and `{channel}` placeholders.
+   * Defaults to `timestamp:{timestamp}\tfile:{file}`.
+   */
+  trustedComment?: string;
+  /** The version substituted for the `{version}` placeholder, defaults to the app version. */
+  version?: string;
+  /** The release channel substituted for the `{channel}` placeholder. */
+  channel?: string;
+  /**
+   * The URL of an RFC 3161 time-stamping authority that timestamps each signature
+   * into a `<signature>.tsr` file.
+   */
+  timestampUrl?: string;
 }
 
 async function packageApp(config: Config = {}, options?: Options) {
@@ -155,6 +155,7 @@ impl Update {
                 )
             })?,
             signature: self.signature.clone(),
+            deltas: Vec::new(),
             timeout: self.timeout.map(|t| Duration::from_millis(t as u64)),
             headers: {
                 let mut map = HeaderMap::new();
@@ -0,0 +1,45 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{env, error::Error, fmt::Write, fs, path::PathBuf};
+
+/// The AppImage architectures a runtime can be vendored for.
+const APPIMAGE_ARCHS: &[&str] = &["x86_64", "i686", "aarch64", "armhf"];
+
+/// Embeds the `runtime-<arch>` files of the vendored AppImage runtime directory
+/// when the `vendored-appimage-runtime` feature is enabled, see `vendor/appimage-runtime/README.md`.
+fn vendor_appimage_runtimes() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-env-changed=CARGO_PACKAGER_APPIMAGE_RUNTIME_DIR");
+    if env::var_os("CARGO_FEATURE_VENDORED_APPIMAGE_RUNTIME").is_none() {
+        return Ok(());
+    }
+
+    let dir = match env::var_os("CARGO_PACKAGER_APPIMAGE_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var("CARGO_MANIFEST_DIR")?).join("vendor/appimage-runtime"),
+    };
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut code = String::from("pub(crate) const VENDORED_RUNTIMES: &[(&str, &[u8])] = &[\n");
+    for arch in APPIMAGE_ARCHS {
+        let path = dir.join(format!("runtime-{arch}"));
+        println!("cargo:rerun-if-changed={}", path.display());
+        if path.is_file() {
+            writeln!(
+                code,
+                "    ({arch:?}, include_bytes!({:?})),",
+                path.canonicalize()?
+            )?;
+        }
+    }
+    code.push_str("];\n");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    fs::write(out_dir.join("vendored_appimage_runtime.rs"), code)?;
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    vendor_appimage_runtimes()
+}
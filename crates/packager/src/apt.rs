@@ -0,0 +1,431 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of the metadata of a flat apt repository for the Linux `.deb` packages.
+//!
+//! The generated `Packages`, `Packages.gz` and `Release` files are written next to the packages,
+//! so the directory can be served as is and added to the apt sources with:
+//!
+//! ```text
+//! deb [signed-by=/usr/share/keyrings/app.gpg] https://example.com/repo ./
+//! ```
+//!
+//! Uploading the repository is left to the caller.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use flate2::{write::GzEncoder, Compression};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+use crate::{shell::CommandExt, util, Error, PackageFormat, PackageOutput};
+
+/// The file name of the package index generated by [`write_repository`].
+pub const PACKAGES_FILE_NAME: &str = "Packages";
+
+/// The file name of the repository description generated by [`write_repository`].
+pub const RELEASE_FILE_NAME: &str = "Release";
+
+/// Options of the apt repository generated by [`write_repository`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct AptRepositoryConfig {
+    /// The `Origin` field of the `Release` file, usually the name of the publisher.
+    pub origin: Option<String>,
+    /// The `Label` field of the `Release` file, shown by apt frontends.
+    pub label: Option<String>,
+    /// The `Suite` field of the `Release` file, like `stable`.
+    pub suite: Option<String>,
+    /// The `Codename` field of the `Release` file.
+    pub codename: Option<String>,
+    /// The ID or user ID of the GPG key used to sign the `Release` file.
+    ///
+    /// When set, the detached `Release.gpg` signature and the clear-signed `InRelease`
+    /// file are generated using `gpg`, which must be installed with the secret key available.
+    pub signing_key: Option<String>,
+}
+
+impl AptRepositoryConfig {
+    /// Creates a new [`AptRepositoryConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `Origin` field of the `Release` file.
+    pub fn origin<S: Into<String>>(mut self, origin: S) -> Self {
+        self.origin.replace(origin.into());
+        self
+    }
+
+    /// Set the `Label` field of the `Release` file.
+    pub fn label<S: Into<String>>(mut self, label: S) -> Self {
+        self.label.replace(label.into());
+        self
+    }
+
+    /// Set the `Suite` field of the `Release` file.
+    pub fn suite<S: Into<String>>(mut self, suite: S) -> Self {
+        self.suite.replace(suite.into());
+        self
+    }
+
+    /// Set the `Codename` field of the `Release` file.
+    pub fn codename<S: Into<String>>(mut self, codename: S) -> Self {
+        self.codename.replace(codename.into());
+        self
+    }
+
+    /// Set the GPG key used to sign the `Release` file.
+    pub fn signing_key<S: Into<String>>(mut self, signing_key: S) -> Self {
+        self.signing_key.replace(signing_key.into());
+        self
+    }
+}
+
+/// The size and digests of a file, as listed by the apt metadata.
+struct FileDigests {
+    size: u64,
+    md5: String,
+    sha1: String,
+    sha256: String,
+}
+
+impl FileDigests {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            md5: format!("{:x}", md5::compute(data)),
+            sha1: hex::encode(Sha1::digest(data)),
+            sha256: hex::encode(Sha256::digest(data)),
+        }
+    }
+}
+
+/// Reads the `control` file of the `control.tar.gz` member of the `.deb` at `path`.
+fn read_control(path: &Path) -> crate::Result<String> {
+    let invalid = |reason: &str| Error::InvalidDebPackage(path.to_path_buf(), reason.into());
+
+    let file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let mut archive = ar::Archive::new(file);
+    while let Some(entry) = archive.next_entry() {
+        let entry = entry.map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+        if entry.header().identifier() != b"control.tar.gz" {
+            continue;
+        }
+
+        let mut control = tar::Archive::new(flate2::read::GzDecoder::new(entry));
+        for entry in control
+            .entries()
+            .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?
+        {
+            let mut entry = entry.map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+            let entry_path = entry
+                .path()
+                .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+            if entry_path
+                .components()
+                .eq(Path::new("control").components())
+            {
+                let mut content = String::new();
+                entry
+                    .read_to_string(&mut content)
+                    .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+                return Ok(content);
+            }
+        }
+        return Err(invalid("`control.tar.gz` has no `control` file"));
+    }
+    Err(invalid("no `control.tar.gz` member"))
+}
+
+/// Returns the value of the `field` of a control file.
+fn control_field<'a>(control: &'a str, field: &str) -> Option<&'a str> {
+    control.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case(field).then(|| value.trim())
+    })
+}
+
+/// Generates the `Packages` index of the `.deb` packages in `packages`.
+///
+/// Each stanza is the control file of a package followed by its `Filename`,
+/// relative to `repo_dir` or just the file name if the package is not inside `repo_dir`,
+/// and its `Size`, `MD5sum`, `SHA1` and `SHA256`. The stanzas are sorted by file name.
+pub fn generate_packages(packages: &[PackageOutput], repo_dir: &Path) -> crate::Result<String> {
+    let mut debs = packages
+        .iter()
+        .filter(|p| p.format == PackageFormat::Deb)
+        .flat_map(|p| &p.paths)
+        .map(|deb| {
+            let name = match deb.strip_prefix(repo_dir) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => deb
+                    .file_name()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| Error::FailedToExtractFilename(deb.clone()))?,
+            };
+            Ok((name.to_string_lossy().replace('\\', "/"), deb))
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    if debs.is_empty() {
+        return Err(Error::AptPackageNotFound);
+    }
+    debs.sort();
+
+    let mut index = String::new();
+    for (name, deb) in debs {
+        let control = read_control(deb)?;
+        let digests =
+            FileDigests::new(&fs::read(deb).map_err(|e| Error::IoWithPath(deb.clone(), e))?);
+
+        if !index.is_empty() {
+            index.push('\n');
+        }
+        index.push_str(control.trim_end());
+        let _ = writeln!(index);
+        let _ = writeln!(index, "Filename: ./{name}");
+        let _ = writeln!(index, "Size: {}", digests.size);
+        let _ = writeln!(index, "MD5sum: {}", digests.md5);
+        let _ = writeln!(index, "SHA1: {}", digests.sha1);
+        let _ = writeln!(index, "SHA256: {}", digests.sha256);
+    }
+    Ok(index)
+}
+
+/// Generates the `Release` file describing the `files` of the repository,
+/// listed with their name and content.
+fn generate_release(
+    files: &[(&str, &[u8])],
+    architectures: &BTreeSet<&str>,
+    repo_config: &AptRepositoryConfig,
+) -> crate::Result<String> {
+    let date = util::source_date_epoch()
+        .and_then(|epoch| OffsetDateTime::from_unix_timestamp(epoch as i64).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc)
+        .format(&Rfc2822)
+        .map_err(time::error::Error::from)?;
+
+    let mut release = String::new();
+    for (field, value) in [
+        ("Origin", &repo_config.origin),
+        ("Label", &repo_config.label),
+        ("Suite", &repo_config.suite),
+        ("Codename", &repo_config.codename),
+    ] {
+        if let Some(value) = value {
+            let _ = writeln!(release, "{field}: {value}");
+        }
+    }
+    let _ = writeln!(release, "Date: {date}");
+    let architectures = architectures.iter().copied().collect::<Vec<_>>();
+    let _ = writeln!(release, "Architectures: {}", architectures.join(" "));
+
+    let digests = files
+        .iter()
+        .map(|(name, data)| (*name, FileDigests::new(data)))
+        .collect::<Vec<_>>();
+    for field in ["MD5Sum", "SHA1", "SHA256"] {
+        let _ = writeln!(release, "{field}:");
+        for (name, digests) in &digests {
+            let digest = match field {
+                "MD5Sum" => &digests.md5,
+                "SHA1" => &digests.sha1,
+                _ => &digests.sha256,
+            };
+            let _ = writeln!(release, " {digest} {} {name}", digests.size);
+        }
+    }
+    Ok(release)
+}
+
+/// Signs the `Release` file at `release_path` with `gpg`,
+/// writing the `Release.gpg` and `InRelease` files next to it.
+fn sign_release(release_path: &Path, signing_key: &str) -> crate::Result<Vec<PathBuf>> {
+    let detached = release_path.with_file_name("Release.gpg");
+    let clear_signed = release_path.with_file_name("InRelease");
+    for (mode, output) in [("--detach-sign", &detached), ("--clearsign", &clear_signed)] {
+        tracing::debug!("Signing {} with gpg", output.display());
+        Command::new("gpg")
+            .args(["--batch", "--yes", "--armor", "--local-user", signing_key])
+            .arg(mode)
+            .arg("--output")
+            .arg(output)
+            .arg(release_path)
+            .output_ok()
+            .map_err(Error::FailedToRunGpg)?;
+    }
+    Ok(vec![detached, clear_signed])
+}
+
+/// Writes the metadata of a flat apt repository of the `.deb` packages in `packages` into `repo_dir`
+/// and returns the paths of the generated files.
+///
+/// This generates the [`PACKAGES_FILE_NAME`] index and its gzip-compressed `Packages.gz` copy,
+/// see [`generate_packages`], and the [`RELEASE_FILE_NAME`] file listing their checksums.
+/// When [`AptRepositoryConfig::signing_key`] is set, `Release.gpg` and `InRelease` are also generated.
+///
+/// The `Date` of the `Release` file is the `SOURCE_DATE_EPOCH` environment variable when set,
+/// otherwise the current date.
+pub fn write_repository(
+    packages: &[PackageOutput],
+    repo_config: &AptRepositoryConfig,
+    repo_dir: &Path,
+) -> crate::Result<Vec<PathBuf>> {
+    let index = generate_packages(packages, repo_dir)?;
+    let architectures = index
+        .split("\n\n")
+        .filter_map(|stanza| control_field(stanza, "Architecture"))
+        .collect::<BTreeSet<_>>();
+
+    let packages_path = repo_dir.join(PACKAGES_FILE_NAME);
+    fs::write(&packages_path, &index).map_err(|e| Error::IoWithPath(packages_path.clone(), e))?;
+
+    let compress = || -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(index.as_bytes())?;
+        encoder.finish()
+    };
+    let compressed = compress()?;
+    let packages_gz_path = repo_dir.join("Packages.gz");
+    fs::write(&packages_gz_path, &compressed)
+        .map_err(|e| Error::IoWithPath(packages_gz_path.clone(), e))?;
+
+    let release = generate_release(
+        &[
+            (PACKAGES_FILE_NAME, index.as_bytes()),
+            ("Packages.gz", &compressed),
+        ],
+        &architectures,
+        repo_config,
+    )?;
+    let release_path = repo_dir.join(RELEASE_FILE_NAME);
+    fs::write(&release_path, release).map_err(|e| Error::IoWithPath(release_path.clone(), e))?;
+
+    let mut paths = vec![packages_path, packages_gz_path, release_path.clone()];
+    if let Some(signing_key) = &repo_config.signing_key {
+        paths.extend(sign_release(&release_path, signing_key)?);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a `.deb` with the `control` file to `path`.
+    fn write_deb(path: &Path, control: &str) {
+        let mut control_tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(control.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        control_tar
+            .append_data(&mut header, "./control", control.as_bytes())
+            .unwrap();
+        let control_tar_gz = control_tar.into_inner().unwrap().finish().unwrap();
+
+        let mut deb = ar::Builder::new(File::create(path).unwrap());
+        for (name, data) in [
+            ("debian-binary", b"2.0\n".as_slice()),
+            ("control.tar.gz", &control_tar_gz),
+        ] {
+            let header = ar::Header::new(name.as_bytes().to_vec(), data.len() as u64);
+            deb.append(&header, data).unwrap();
+        }
+    }
+
+    #[test]
+    fn it_writes_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let amd64 = dir.path().join("app_1.0.0_amd64.deb");
+        let arm64 = dir.path().join("app_1.0.0_arm64.deb");
+        let amd64_control = "Package: app\nVersion: 1.0.0\nArchitecture: amd64\nDescription: An app\n .\n Details\n";
+        write_deb(&amd64, amd64_control);
+        write_deb(
+            &arm64,
+            "Package: app\nVersion: 1.0.0\nArchitecture: arm64\nDescription: An app\n",
+        );
+
+        let packages = vec![
+            PackageOutput::new(PackageFormat::Deb, vec![arm64.clone()]),
+            PackageOutput::new(PackageFormat::Deb, vec![amd64.clone()]),
+        ];
+        let index = generate_packages(&packages, dir.path()).unwrap();
+        let stanzas = index.split("\n\n").collect::<Vec<_>>();
+        assert_eq!(stanzas.len(), 2);
+        let amd64_data = fs::read(&amd64).unwrap();
+        assert_eq!(
+            stanzas[0],
+            format!(
+                "{amd64_control}Filename: ./app_1.0.0_amd64.deb\nSize: {}\nMD5sum: {:x}\nSHA1: {}\nSHA256: {}",
+                amd64_data.len(),
+                md5::compute(&amd64_data),
+                hex::encode(Sha1::digest(&amd64_data)),
+                crate::checksum::sha256_file(&amd64).unwrap(),
+            )
+        );
+        assert_eq!(control_field(stanzas[1], "Architecture"), Some("arm64"));
+        assert_eq!(
+            control_field(stanzas[1], "Filename"),
+            Some("./app_1.0.0_arm64.deb")
+        );
+
+        let paths = write_repository(
+            &packages,
+            &AptRepositoryConfig::new().origin("Example").suite("stable"),
+            dir.path(),
+        )
+        .unwrap();
+        assert_eq!(
+            paths,
+            ["Packages", "Packages.gz", "Release"].map(|name| dir.path().join(name))
+        );
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), index);
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(File::open(&paths[1]).unwrap())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, index);
+
+        let release = fs::read_to_string(&paths[2]).unwrap();
+        assert!(release.starts_with("Origin: Example\nSuite: stable\nDate: "));
+        assert_eq!(
+            control_field(&release, "Architectures"),
+            Some("amd64 arm64")
+        );
+        let sha256 = release.split("SHA256:\n").nth(1).unwrap();
+        assert_eq!(
+            sha256,
+            format!(
+                " {} {} Packages\n {} {} Packages.gz\n",
+                crate::checksum::sha256_file(&paths[0]).unwrap(),
+                index.len(),
+                crate::checksum::sha256_file(&paths[1]).unwrap(),
+                fs::metadata(&paths[1]).unwrap().len(),
+            )
+        );
+
+        assert!(matches!(
+            generate_packages(&[], dir.path()),
+            Err(Error::AptPackageNotFound)
+        ));
+        let invalid = dir.path().join("invalid.deb");
+        fs::write(&invalid, "!<arch>\n").unwrap();
+        assert!(matches!(
+            generate_packages(
+                &[PackageOutput::new(PackageFormat::Deb, vec![invalid])],
+                dir.path()
+            ),
+            Err(Error::InvalidDebPackage(..))
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+thread_local! {
+    /// The token of the packaging run on the current thread.
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// A token to cancel a packaging run started with [`crate::package_with_cancellation`] from another thread.
+///
+/// The cancellation is checked between the packaging steps and before spawning external tools,
+/// and the external tools running when the token is cancelled are killed.
+/// Steps running in-process, like compressing an archive, are not interrupted.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new [`CancellationToken`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the packaging run using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`CancellationToken::cancel`] was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Sets the token of the current thread until dropped.
+pub(crate) struct CancellationGuard(Option<CancellationToken>);
+
+impl CancellationGuard {
+    pub(crate) fn new(token: &CancellationToken) -> Self {
+        Self(CURRENT.with(|current| current.replace(Some(token.clone()))))
+    }
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+/// Returns the token of the packaging run on the current thread.
+pub(crate) fn current() -> Option<CancellationToken> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Whether the packaging run on the current thread was cancelled.
+pub(crate) fn is_cancelled() -> bool {
+    current().is_some_and(|token| token.is_cancelled())
+}
+
+/// Returns [`crate::Error::Cancelled`] if the packaging run on the current thread was cancelled.
+pub(crate) fn check() -> crate::Result<()> {
+    if is_cancelled() {
+        Err(crate::Error::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::{config::HookCommand, Config, Error, PackageFormat};
+
+    #[test]
+    fn it_cancels_packaging() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.out_dir = dir.path().to_path_buf();
+        config.formats = Some(vec![PackageFormat::Nsis]);
+        config.before_packaging_command = Some(HookCommand::Script("sleep 30".into()));
+
+        let token = CancellationToken::new();
+        let token_ = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            token_.cancel();
+        });
+
+        let start = Instant::now();
+        let result = crate::package_with_cancellation(&config, &token);
+        assert!(matches!(result, Err(Error::Cancelled)), "{result:?}");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(!dir.path().join(".cargo-packager").exists());
+        assert!(current().is_none());
+
+        // a cancelled token stops the run before the first step
+        let started = dir.path().join("started");
+        config.before_packaging_command =
+            Some(HookCommand::Script(format!("touch {}", started.display())));
+        let result = crate::package_with_cancellation(&config, &token);
+        assert!(matches!(result, Err(Error::Cancelled)), "{result:?}");
+        assert!(!started.exists());
+    }
+}
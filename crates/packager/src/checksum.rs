@@ -0,0 +1,173 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Checksums of packaged artifacts.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    util::{self, PathExt},
+    Error,
+};
+
+/// The file name of the checksums manifest generated by [`write_sha256sums`].
+pub const SHA256SUMS_FILE_NAME: &str = "SHA256SUMS";
+
+/// The extension of the BLAKE3 digest files generated by [`write_blake3_file`].
+pub const BLAKE3_EXTENSION: &str = "b3";
+
+/// The extension of the SHA-256 digest files generated by [`write_sha256_file`].
+pub const SHA256_EXTENSION: &str = "sha256";
+
+/// Computes the SHA-256 digest of the file at `path`, encoded as a lowercase hex string.
+pub fn sha256_file<P: AsRef<Path>>(path: P) -> crate::Result<String> {
+    sha256_file_bytes(path).map(hex::encode)
+}
+
+/// Computes the SHA-256 digest of the file at `path`.
+pub(crate) fn sha256_file_bytes<P: AsRef<Path>>(path: P) -> crate::Result<[u8; 32]> {
+    let path = path.as_ref();
+    let mut file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Computes the BLAKE3 digest of the file at `path`, encoded as a lowercase hex string.
+pub fn blake3_file<P: AsRef<Path>>(path: P) -> crate::Result<String> {
+    let path = path.as_ref();
+    let mut file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Writes the BLAKE3 digest of the file at `path` into `<path>.b3`
+/// and returns the digest and the path of the digest file.
+///
+/// The digest file uses the same format as `b3sum`, a `<digest>  <file name>` line,
+/// so it can be checked using `b3sum -c <file>.b3`.
+pub fn write_blake3_file<P: AsRef<Path>>(path: P) -> crate::Result<(String, PathBuf)> {
+    let path = path.as_ref();
+    let digest = blake3_file(path)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(path.to_path_buf()))?
+        .to_string_lossy();
+
+    let digest_path = path.with_additional_extension(BLAKE3_EXTENSION);
+    let mut digest_file = util::create_file(&digest_path)?;
+    writeln!(digest_file, "{digest}  {name}")?;
+    digest_file.flush()?;
+
+    Ok((digest, digest_path))
+}
+
+/// Writes the SHA-256 digest of the file at `path` into `<path>.sha256`
+/// and returns the digest and the path of the digest file.
+///
+/// The digest file uses the same format as `sha256sum`, a `<digest>  <file name>` line,
+/// so it can be checked using `sha256sum -c <file>.sha256`.
+pub fn write_sha256_file<P: AsRef<Path>>(path: P) -> crate::Result<(String, PathBuf)> {
+    let path = path.as_ref();
+    let digest = sha256_file(path)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(path.to_path_buf()))?
+        .to_string_lossy();
+
+    let digest_path = path.with_additional_extension(SHA256_EXTENSION);
+    let mut digest_file = util::create_file(&digest_path)?;
+    writeln!(digest_file, "{digest}  {name}")?;
+    digest_file.flush()?;
+
+    Ok((digest, digest_path))
+}
+
+/// Writes a [`SHA256SUMS_FILE_NAME`] manifest of the specified files into `dir`
+/// and returns its path.
+///
+/// The manifest uses the same format as `sha256sum`, a `<digest>  <path>` line per file
+/// sorted by path, where the path is relative to `dir` or just the file name
+/// if the file is not inside `dir`. It can be checked using `sha256sum -c SHA256SUMS`.
+pub fn write_sha256sums<P: AsRef<Path>>(dir: P, files: &[PathBuf]) -> crate::Result<PathBuf> {
+    let dir = dir.as_ref();
+
+    let mut entries = Vec::with_capacity(files.len());
+    for file in files {
+        let name = match file.strip_prefix(dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => file
+                .file_name()
+                .map(PathBuf::from)
+                .ok_or_else(|| Error::FailedToExtractFilename(file.clone()))?,
+        };
+        entries.push((
+            name.to_string_lossy().replace('\\', "/"),
+            sha256_file(file)?,
+        ));
+    }
+    entries.sort();
+
+    let manifest_path = dir.join(SHA256SUMS_FILE_NAME);
+    let mut manifest = util::create_file(&manifest_path)?;
+    for (name, digest) in entries {
+        writeln!(manifest, "{digest}  {name}")?;
+    }
+    manifest.flush()?;
+
+    Ok(manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_writes_sha256sums() {
+        let dir = tempfile::tempdir().unwrap();
+        let b = dir.path().join("b.deb");
+        let a = dir.path().join("a.AppImage");
+        std::fs::write(&b, "b").unwrap();
+        std::fs::write(&a, "").unwrap();
+
+        let manifest = write_sha256sums(dir.path(), &[b, a]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(manifest).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  a.AppImage\n\
+             3e23e8160039594a33894f6564e1b1348bbd7a0088d42c4acb73eeaed59c009d  b.deb\n"
+        );
+    }
+
+    #[test]
+    fn it_writes_blake3_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.AppImage");
+        std::fs::write(&file, "abc").unwrap();
+
+        let (digest, digest_path) = write_blake3_file(&file).unwrap();
+        assert_eq!(
+            digest,
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(digest_path, dir.path().join("app.AppImage.b3"));
+        assert_eq!(
+            std::fs::read_to_string(digest_path).unwrap(),
+            format!("{digest}  app.AppImage\n")
+        );
+
+        // larger than the buffer of `io::copy`
+        std::fs::write(&file, vec![0; 100_000]).unwrap();
+        assert_eq!(
+            blake3_file(&file).unwrap(),
+            blake3::hash(&vec![0; 100_000]).to_hex().as_str()
+        );
+    }
+}
@@ -8,9 +8,12 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use super::{Error, Result};
+use super::{init::ConfigFormat, Error, Result};
 use crate::{config::Binary, Config};
 
+/// The value of the secrets in the configurations printed by `--print-config`.
+const REDACTED: &str = "<redacted>";
+
 impl Config {
     pub(crate) fn name(&self) -> &str {
         self.name.as_deref().unwrap_or_default()
@@ -261,3 +264,71 @@ pub fn detect_configs(cli: &super::Cli) -> Result<Vec<(Option<PathBuf>, Config)>
 
     Ok(configs)
 }
+
+/// Serializes a [`Config::resolved`] configuration for `--print-config`,
+/// with the password and private key of its signing configuration redacted.
+pub(crate) fn render_resolved_config(config: &Config, format: ConfigFormat) -> Result<String> {
+    let mut config = config.clone();
+    if let Some(signing) = &mut config.signing {
+        signing.private_key = REDACTED.into();
+        if signing.password.is_some() {
+            signing.password = Some(REDACTED.into());
+        }
+    }
+    Ok(match format {
+        ConfigFormat::Toml => toml::to_string_pretty(&config)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(&config)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SigningConfig;
+
+    #[test]
+    fn it_round_trips_the_resolved_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.2.3".into();
+        config.identifier = Some("com.example.myapp".into());
+        config.out_dir = dir.path().into();
+        config.binaries = vec![Binary::new("my-app").main(true)];
+        config.signing = Some(
+            SigningConfig::new()
+                .private_key("secret")
+                .password("hunter2"),
+        );
+
+        let resolved = config.resolved().unwrap();
+        assert!(resolved.formats.is_some());
+        assert!(resolved.target_triple.is_some());
+        assert_eq!(resolved.slug.as_deref(), Some("my-app"));
+        assert_eq!(resolved.publisher.as_deref(), Some("example"));
+        assert_eq!(resolved.binaries_dir.as_ref(), Some(&resolved.out_dir));
+
+        let mut redacted = resolved.clone();
+        let signing = redacted.signing.as_mut().unwrap();
+        signing.private_key = REDACTED.into();
+        signing.password = Some(REDACTED.into());
+        let expected = serde_json::to_value(&redacted).unwrap();
+
+        let json = render_resolved_config(&resolved, ConfigFormat::Json).unwrap();
+        assert!(
+            !json.contains("hunter2") && !json.contains("secret"),
+            "{json}"
+        );
+        let parsed = serde_json::from_str::<Config>(&json).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+
+        let toml = render_resolved_config(&resolved, ConfigFormat::Toml).unwrap();
+        assert!(!toml.contains("hunter2"), "{toml}");
+        let parsed = toml::from_str::<Config>(&toml).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+
+        // resolving the printed config doesn't change it
+        let resolved_again = serde_json::to_value(parsed.resolved().unwrap()).unwrap();
+        assert_eq!(resolved_again, expected);
+    }
+}
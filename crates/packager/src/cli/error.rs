@@ -3,8 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use thiserror::Error;
 
 #[non_exhaustive]
@@ -24,6 +25,9 @@ pub enum Error {
     /// TOML parsing error.
     #[error(transparent)]
     Toml(#[from] toml::de::Error),
+    /// TOML serialization error.
+    #[error(transparent)]
+    TomlSerialize(#[from] toml::ser::Error),
     /// JSON Config parsing error.
     #[error("Failed to parse config: {0}")]
     FailedToParseJsonConfig(serde_json::Error),
@@ -38,12 +42,27 @@ pub enum Error {
     /// package.json parsing error.
     #[error("Failed to parse package.json: {0}")]
     FailedToParsePacakgeJson(serde_json::Error),
+    /// No Cargo.toml was found in a directory or its parents.
+    #[error("Failed to find a Cargo.toml in {0} or its parents")]
+    CargoTomlNotFound(PathBuf),
+    /// The Cargo.toml has no `[package]` with a `name`, like the manifest of a virtual workspace.
+    #[error("The Cargo.toml has no `[package]` name, pass the manifest of a package with `--manifest-path`")]
+    NotACargoPackage,
+    /// A configuration already exists where `cargo packager init` would write one.
+    #[error("The configuration {0} already exists, pass `--force` to overwrite it")]
+    ConfigExists(PathBuf),
     /// JSON Config parsing error.
     #[error("Failed to parse config at {0}: {1}")]
     FailedToParseJsonConfigFromPath(PathBuf, serde_json::Error),
     /// TOML Config parsing error.
     #[error("Failed to parse config at {0}: {1}")]
     FailedToParseTomlConfigFromPath(PathBuf, Box<toml::de::Error>),
+    /// `--force` would overwrite a signing key without confirmation, stdin is not a terminal to confirm.
+    #[error("Refusing to overwrite the signing key {0} without confirmation, stdin is not a terminal to confirm it. Pass `--yes` to overwrite it")]
+    KeyOverwriteNeedsConfirmation(PathBuf),
+    /// The file name of the signing key was not typed to confirm its overwrite.
+    #[error("The file name of the signing key was not typed, {0} was not overwritten")]
+    KeyOverwriteAborted(PathBuf),
     /// I/O errors with path.
     #[error("I/O Error ({0}): {1}")]
     IoWithPath(PathBuf, std::io::Error),
@@ -53,7 +72,46 @@ pub enum Error {
     /// Packaging error
     #[error(transparent)]
     Packaging(#[from] crate::Error),
+    /// Some formats failed to package while the others succeeded.
+    #[error(
+        "Failed to package {}, the other packages were built",
+        .0.iter().map(|(format, e)| format!("{}: {e}", format.short_name())).collect::<Vec<_>>().join(", ")
+    )]
+    PartialSuccess(Vec<(crate::PackageFormat, crate::Error)>),
 }
 
 /// Convenient type alias of Result type for cargo-packager.
 pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The path associated with this error, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::FailedToParseJsonConfigFromPath(path, _)
+            | Error::FailedToParseTomlConfigFromPath(path, _)
+            | Error::CargoTomlNotFound(path)
+            | Error::ConfigExists(path)
+            | Error::KeyOverwriteNeedsConfirmation(path)
+            | Error::KeyOverwriteAborted(path)
+            | Error::IoWithPath(path, _) => Some(path),
+            Error::Packaging(e) => e.path(),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes the error like [`crate::Error`], with the variant name as the `kind`,
+/// packaging errors using the variant name of the [`crate::Error`].
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if let Error::Packaging(e) = self {
+            return e.serialize(serializer);
+        }
+
+        let mut error = serializer.serialize_struct("Error", 3)?;
+        error.serialize_field("kind", &crate::error::variant_name(&format!("{self:?}")))?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("path", &self.path())?;
+        error.end()
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Listing of the package formats available on the current platform.
+
+use std::{fmt::Write, path::PathBuf};
+
+use serde::Serialize;
+
+use super::Result;
+use crate::config::PackageFormat;
+
+/// How an external tool required by a package format is obtained.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ToolSource {
+    /// The tool must be installed and available in `PATH`.
+    System,
+    /// The tool is downloaded by cargo-packager the first time it is needed.
+    Downloaded,
+}
+
+/// An external tool required to build a package format.
+#[derive(Debug, Clone)]
+struct ExternalTool {
+    name: &'static str,
+    source: ToolSource,
+}
+
+impl ExternalTool {
+    fn system(name: &'static str) -> Self {
+        Self {
+            name,
+            source: ToolSource::System,
+        }
+    }
+
+    fn downloaded(name: &'static str) -> Self {
+        Self {
+            name,
+            source: ToolSource::Downloaded,
+        }
+    }
+}
+
+/// Returns the external tools required to build the given format.
+fn external_tools(format: PackageFormat) -> Vec<ExternalTool> {
+    match format {
+        PackageFormat::App => vec![ExternalTool::system("xattr")],
+        PackageFormat::Dmg => vec![
+            ExternalTool::system("hdiutil"),
+            ExternalTool::downloaded("create-dmg"),
+        ],
+        PackageFormat::Pkg => vec![
+            ExternalTool::system("pkgbuild"),
+            ExternalTool::system("productbuild"),
+        ],
+        PackageFormat::Wix => vec![ExternalTool::downloaded("WiX Toolset")],
+        #[cfg(windows)]
+        PackageFormat::Nsis => vec![ExternalTool::downloaded("NSIS")],
+        #[cfg(not(windows))]
+        PackageFormat::Nsis => vec![ExternalTool::system("makensis")],
+        // the script is still generated without the compiler
+        PackageFormat::InnoSetup => vec![ExternalTool::system("ISCC")],
+        PackageFormat::AppImage => vec![
+            ExternalTool::system("dd"),
+            ExternalTool::downloaded("linuxdeploy"),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Finds an executable in the directories of the `PATH` environment variable.
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    let file_name = if cfg!(windows) {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    };
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolStatus {
+    name: &'static str,
+    source: ToolSource,
+    /// Whether the tool was found in `PATH`, `None` for downloaded tools.
+    available: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FormatStatus {
+    format: &'static str,
+    default: bool,
+    tools: Vec<ToolStatus>,
+}
+
+fn formats_status() -> Vec<FormatStatus> {
+    PackageFormat::platform_all()
+        .iter()
+        .map(|format| FormatStatus {
+            format: format.short_name(),
+            default: PackageFormat::platform_default().contains(format),
+            tools: external_tools(*format)
+                .into_iter()
+                .map(|tool| ToolStatus {
+                    name: tool.name,
+                    source: tool.source,
+                    available: match tool.source {
+                        ToolSource::System => Some(find_in_path(tool.name).is_some()),
+                        ToolSource::Downloaded => None,
+                    },
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Prints the package formats available on the current platform
+/// and the status of the external tools they require.
+pub fn list(json: bool) -> Result<()> {
+    let formats = formats_status();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&formats)?);
+        return Ok(());
+    }
+
+    let mut out = format!("Package formats available on {}:\n", std::env::consts::OS);
+    for format in formats {
+        let _ = write!(out, "    {:<10}", format.format);
+        if format.default {
+            out.push_str(" (default)");
+        }
+        let tools = format
+            .tools
+            .iter()
+            .map(|tool| match tool.available {
+                Some(true) => format!("{} (found)", tool.name),
+                Some(false) => format!("{} (not found in PATH)", tool.name),
+                None => format!("{} (downloaded automatically)", tool.name),
+            })
+            .collect::<Vec<_>>();
+        if !tools.is_empty() {
+            let _ = write!(out, " requires: {}", tools.join(", "));
+        }
+        out.push('\n');
+    }
+    print!("{out}");
+
+    Ok(())
+}
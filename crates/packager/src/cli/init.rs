@@ -0,0 +1,356 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+
+use super::{Error, Result};
+use crate::config::PackageFormat;
+
+/// The format of the configuration generated by `cargo packager init` or printed by `--print-config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    /// A commented `Packager.toml`.
+    Toml,
+    /// A `packager.json`.
+    Json,
+}
+
+impl ConfigFormat {
+    fn file_name(self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "Packager.toml",
+            ConfigFormat::Json => "packager.json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Generate a starter configuration from the nearest Cargo.toml")]
+pub struct Options {
+    /// The format of the generated configuration.
+    #[clap(long, value_enum, default_value_t = ConfigFormat::Toml)]
+    format: ConfigFormat,
+    /// Path to the Cargo.toml to read, defaults to the nearest one in the current directory or its parents.
+    #[clap(long)]
+    manifest_path: Option<PathBuf>,
+    /// A path where the configuration will be written,
+    /// defaults to `Packager.toml` or `packager.json` next to the Cargo.toml.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+    /// Overwrite the configuration even if it exists on the specified path.
+    #[clap(short, long)]
+    force: bool,
+}
+
+/// The fields of a Cargo.toml the starter configuration is pre-filled with.
+#[derive(Debug)]
+struct CargoPackage {
+    name: String,
+    version: Option<String>,
+    description: Option<String>,
+    binaries: Vec<String>,
+}
+
+impl CargoPackage {
+    fn parse(contents: &str) -> Result<Self> {
+        let toml = toml::from_str::<toml::Table>(contents)
+            .map_err(|e| Error::FailedToParseCargoToml(Box::new(e)))?;
+        let package = toml.get("package").ok_or(Error::NotACargoPackage)?;
+        let string = |key: &str| package.get(key).and_then(|v| v.as_str()).map(String::from);
+        let name = string("name").ok_or(Error::NotACargoPackage)?;
+
+        // the binary of `src/main.rs` is named after the package when there are no `[[bin]]` targets
+        let mut binaries = toml
+            .get("bin")
+            .and_then(|b| b.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|b| b.get("name").and_then(|n| n.as_str()).map(String::from))
+            .collect::<Vec<_>>();
+        if binaries.is_empty() {
+            binaries.push(name.clone());
+        }
+
+        Ok(Self {
+            version: string("version"),
+            description: string("description"),
+            binaries,
+            name,
+        })
+    }
+
+    /// Returns whether `binary` is the main binary, the only one or the one named after the package.
+    fn is_main(&self, binary: &str) -> bool {
+        self.binaries.len() == 1 || binary == self.name
+    }
+
+    fn identifier(&self) -> String {
+        format!("com.example.{}", self.name.replace('_', "-").to_lowercase())
+    }
+
+    /// Returns the name with its `-` and `_` separated words capitalized, like `My App` for `my-app`.
+    fn product_name(&self) -> String {
+        self.name
+            .split(['-', '_'])
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                let mut chars = w.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Returns the configuration key, the TOML table name and the config type of the section of `format`.
+fn format_section(format: PackageFormat) -> Option<(&'static str, &'static str, &'static str)> {
+    Some(match format {
+        PackageFormat::App => ("macos", "macos", "MacOsConfig"),
+        PackageFormat::Dmg => ("dmg", "dmg", "DmgConfig"),
+        PackageFormat::Pkg => ("pkg", "pkg", "PkgConfig"),
+        PackageFormat::Wix => ("wix", "wix", "WixConfig"),
+        PackageFormat::Nsis => ("nsis", "nsis", "NsisConfig"),
+        PackageFormat::Zip => ("zip", "zip", "ZipConfig"),
+        PackageFormat::InnoSetup => ("innoSetup", "inno-setup", "InnoSetupConfig"),
+        PackageFormat::Deb => ("deb", "deb", "DebianConfig"),
+        PackageFormat::AppImage => ("appimage", "appimage", "AppImageConfig"),
+        PackageFormat::Pacman => ("pacman", "pacman", "PacmanConfig"),
+        PackageFormat::Shar => ("shar", "shar", "SharConfig"),
+        _ => return None,
+    })
+}
+
+fn docs_url(path: &str) -> String {
+    format!("https://docs.rs/cargo-packager/latest/cargo_packager/{path}.html")
+}
+
+fn quote(value: &str) -> String {
+    toml::Value::String(value.into()).to_string()
+}
+
+/// Renders a commented `Packager.toml` with a section for each of `formats`.
+fn render_toml(package: &CargoPackage, formats: &[PackageFormat]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# The cargo-packager configuration, see {} for all the options.\n",
+        docs_url("config/struct.Config")
+    );
+    let _ = writeln!(out, "name = {}", quote(&package.name));
+    let _ = writeln!(out, "product-name = {}", quote(&package.product_name()));
+    match &package.version {
+        Some(version) => {
+            let _ = writeln!(out, "version = {}", quote(version));
+        }
+        None => {
+            let _ = writeln!(
+                out,
+                "# Cargo.toml doesn't set a version string, set the package version here."
+            );
+            let _ = writeln!(out, "version = \"0.1.0\"");
+        }
+    }
+    if let Some(description) = &package.description {
+        let _ = writeln!(out, "description = {}", quote(description));
+    }
+    let _ = writeln!(
+        out,
+        "# A unique reverse domain name, replace `example` with your domain."
+    );
+    let _ = writeln!(out, "identifier = {}", quote(&package.identifier()));
+    let _ = writeln!(out, "# The binaries are built before packaging them.");
+    let _ = writeln!(out, "before-packaging-command = \"cargo build --release\"");
+    let _ = writeln!(out, "out-dir = \"target/release\"");
+    let _ = writeln!(out, "# icons = [\"icons/*.png\"]");
+    let _ = writeln!(out, "# resources = [\"assets\"]");
+
+    for binary in &package.binaries {
+        let _ = writeln!(out, "\n[[binaries]]");
+        let _ = writeln!(out, "path = {}", quote(binary));
+        let _ = writeln!(out, "main = {}", package.is_main(binary));
+    }
+
+    let mut written = Vec::new();
+    for (_, table, type_name) in formats.iter().filter_map(|f| format_section(*f)) {
+        if written.contains(&table) {
+            continue;
+        }
+        written.push(table);
+        let _ = writeln!(
+            out,
+            "\n# See {} for the options.",
+            docs_url(&format!("config/struct.{type_name}"))
+        );
+        let _ = writeln!(out, "[{table}]");
+    }
+
+    let _ = writeln!(
+        out,
+        "\n# The packages are signed when a private key is set, see {}.",
+        docs_url("sign/struct.SigningConfig")
+    );
+    let _ = writeln!(
+        out,
+        "# Generate one with `cargo packager signer generate` and prefer passing it with the \
+        CARGO_PACKAGER_SIGN_PRIVATE_KEY environment variable."
+    );
+    let _ = writeln!(out, "[signing]");
+    let _ = writeln!(out, "private-key = \"\"");
+    out
+}
+
+/// Renders a `packager.json` with a section for each of `formats`.
+fn render_json(package: &CargoPackage, formats: &[PackageFormat]) -> Result<String> {
+    let mut config = serde_json::json!({
+        "name": package.name,
+        "productName": package.product_name(),
+        "version": package.version.as_deref().unwrap_or("0.1.0"),
+        "description": package.description,
+        "identifier": package.identifier(),
+        "beforePackagingCommand": "cargo build --release",
+        "outDir": "target/release",
+        "binaries": package.binaries.iter().map(|binary| serde_json::json!({
+            "path": binary,
+            "main": package.is_main(binary),
+        })).collect::<Vec<_>>(),
+    });
+    let object = config.as_object_mut().expect("the config is an object");
+    if package.description.is_none() {
+        object.remove("description");
+    }
+    for (key, _, _) in formats.iter().filter_map(|f| format_section(*f)) {
+        object.insert(key.into(), serde_json::json!({}));
+    }
+    object.insert("signing".into(), serde_json::json!({ "privateKey": "" }));
+    Ok(serde_json::to_string_pretty(&config)? + "\n")
+}
+
+/// Returns the path of the nearest Cargo.toml in `dir` or its parents.
+fn find_manifest(dir: &Path) -> Result<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join("Cargo.toml"))
+        .find(|p| p.is_file())
+        .ok_or_else(|| Error::CargoTomlNotFound(dir.to_path_buf()))
+}
+
+pub fn command(options: Options) -> Result<()> {
+    let manifest_path = match options.manifest_path {
+        Some(path) => path,
+        None => find_manifest(&std::env::current_dir()?)?,
+    };
+    let contents = fs::read_to_string(&manifest_path)
+        .map_err(|e| Error::IoWithPath(manifest_path.clone(), e))?;
+    let package = CargoPackage::parse(&contents)?;
+
+    let path = options.output.unwrap_or_else(|| {
+        manifest_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(options.format.file_name())
+    });
+    if path.exists() && !options.force {
+        return Err(Error::ConfigExists(path));
+    }
+
+    let formats = PackageFormat::platform_all();
+    let config = match options.format {
+        ConfigFormat::Toml => render_toml(&package, formats),
+        ConfigFormat::Json => render_json(&package, formats)?,
+    };
+    fs::write(&path, config).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    tracing::info!(
+        "Generated the starter configuration {} of {}",
+        path.display(),
+        package.name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    const CARGO_TOML: &str = r#"
+[package]
+name = "my_app"
+version = "1.2.3"
+description = "My \"awesome\" app"
+
+[[bin]]
+name = "my_app"
+
+[[bin]]
+name = "helper"
+"#;
+
+    #[test]
+    fn it_generates_configs_that_parse_back() {
+        let package = CargoPackage::parse(CARGO_TOML).unwrap();
+        let every_format = [
+            PackageFormat::App,
+            PackageFormat::Dmg,
+            PackageFormat::Pkg,
+            PackageFormat::Wix,
+            PackageFormat::Nsis,
+            PackageFormat::Zip,
+            PackageFormat::InnoSetup,
+            PackageFormat::Deb,
+            PackageFormat::AppImage,
+            PackageFormat::Pacman,
+            PackageFormat::Shar,
+        ];
+
+        let toml = render_toml(&package, &every_format);
+        let json = render_json(&package, &every_format).unwrap();
+        for config in [
+            toml::from_str::<Config>(&toml).unwrap(),
+            serde_json::from_str::<Config>(&json).unwrap(),
+        ] {
+            assert_eq!(config.name(), "my_app");
+            assert_eq!(config.product_name, "My App");
+            assert_eq!(config.version, "1.2.3");
+            assert_eq!(config.description.as_deref(), Some("My \"awesome\" app"));
+            assert_eq!(config.identifier.as_deref(), Some("com.example.my-app"));
+            assert_eq!(config.binaries.len(), 2);
+            assert!(config.binaries[0].main && !config.binaries[1].main);
+            assert!(config.deb.is_some() && config.nsis.is_some() && config.inno_setup.is_some());
+            assert!(config.signing.is_some());
+            config.bundle_identifier().unwrap();
+        }
+    }
+
+    #[test]
+    fn it_does_not_overwrite_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        fs::write(&manifest_path, CARGO_TOML).unwrap();
+        let config_path = dir.path().join("Packager.toml");
+        fs::write(&config_path, "name = \"existing\"").unwrap();
+
+        let options = |force| Options {
+            format: ConfigFormat::Toml,
+            manifest_path: Some(manifest_path.clone()),
+            output: None,
+            force,
+        };
+        assert!(matches!(
+            command(options(false)),
+            Err(Error::ConfigExists(path)) if path == config_path
+        ));
+        command(options(true)).unwrap();
+        assert!(fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("name = \"my_app\""));
+    }
+}
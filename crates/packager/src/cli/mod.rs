@@ -4,25 +4,73 @@
 
 //! The cli entry point
 
-use std::{ffi::OsString, fmt::Write, fs, path::PathBuf};
+use std::{collections::BTreeMap, ffi::OsString, fmt::Write, fs, path::PathBuf};
 
 use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand};
 
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+use crate::apt::{self, AptRepositoryConfig};
 use crate::{
-    config::{LogLevel, PackageFormat},
-    init_tracing_subscriber, package, parse_log_level, sign_outputs, summarise_outputs, util,
-    SigningConfig,
+    checksum,
+    config::{LogLevel, PackageFormat, SharedLibrariesCheck},
+    delta, hash_outputs_blake3,
+    homebrew::{self, HomebrewCaskConfig},
+    init_tracing_subscriber, package, parse_log_level,
+    provenance::{self, ProvenanceConfig},
+    release,
+    scan::{self, ScanConfig},
+    scoop::{self, ScoopManifestConfig},
+    sign, sign_checksums, sign_outputs, split, summarise_outputs_with_config,
+    updater::{self, ReleaseNotes, UpdateManifestConfig},
+    util::{self, PathExt},
+    winget::{self, WingetManifestConfig},
+    PackageOutput, SigningConfig,
 };
 
 mod config;
 mod error;
+mod formats;
+mod init;
 mod signer;
+mod summary;
+mod verify_release;
+mod zsync;
 
 use self::error::{Error, Result};
 
 #[derive(Debug, Clone, Subcommand)]
 enum Commands {
+    Init(init::Options),
     Signer(signer::Options),
+    Sign(signer::sign::Options),
+    Verify(signer::verify::Options),
+    SigInfo(signer::info::Options),
+    Zsync(zsync::Options),
+    VerifyRelease(verify_release::Options),
+}
+
+/// Where the signing public key is emitted by `--emit-public-key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum PublicKeyDestination {
+    /// The `public_key` field of the generated `latest.json`.
+    Manifest,
+    /// A `pubkey.pub` file in the output directory.
+    Sidecar,
+}
+
+/// The format of the error printed when the CLI fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ErrorFormat {
+    /// A human readable message.
+    Human,
+    /// A JSON object with the `kind` of the error, its `message` and its associated `path`.
+    Json,
 }
 
 #[derive(Parser, Debug)]
@@ -41,6 +89,12 @@ pub(crate) struct Cli {
     /// Disables logging
     #[clap(short, long, global = true)]
     quite: bool,
+    /// The format of the error printed on failure.
+    ///
+    /// The `json` format prints a single line to stderr, like
+    /// `{"kind":"IoWithPath","message":"...","path":"..."}`, where `kind` is a stable name of the error.
+    #[clap(long, value_enum, global = true, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
 
     /// The package fromats to build.
     #[clap(short, long, value_enum, value_delimiter = ',')]
@@ -58,19 +112,179 @@ pub(crate) struct Cli {
     /// The password for the signing private key.
     #[clap(long, env = "CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD")]
     password: Option<String>,
+    /// A template for the trusted comment of the signatures,
+    /// supporting the `{timestamp}`, `{file}`, `{version}` and `{channel}` placeholders.
+    ///
+    /// Defaults to `timestamp:{timestamp}\tfile:{file}`.
+    #[clap(long)]
+    trusted_comment: Option<String>,
+    /// The release channel substituted for the `{channel}` placeholder of the trusted comment.
+    ///
+    /// Defaults to the `signing.channel` of the config, which is the name of the flavor
+    /// for the flavors setting their own `signing`.
+    #[clap(long)]
+    channel: Option<String>,
+    /// The URL of an RFC 3161 time-stamping authority that timestamps each signature
+    /// into a `<signature>.tsr` file.
+    #[clap(long)]
+    timestamp_url: Option<String>,
+    /// Generate a `SHA256SUMS` manifest of the generated outputs in the output directory.
+    ///
+    /// When a private key is provided, only the manifest is signed
+    /// instead of signing each of the generated outputs.
+    #[clap(long)]
+    checksums: bool,
+    /// Scan each generated output for viruses with `clamscan` after all the formats are packaged
+    /// and before signing them, failing on a detection.
+    ///
+    /// The scan is skipped with a warning when the scanner is not installed, unless `--strict` is set.
+    #[clap(long)]
+    scan: bool,
+    /// The scanner command of `--scan`, where `%1` is replaced with the path of the scanned output,
+    /// exiting with a non-zero code on a detection.
+    #[clap(long, requires = "scan")]
+    scan_command: Option<String>,
+    /// Write the `--output-json` summary of the generated outputs, with their paths relative
+    /// to the output directory and the SHA-256 digest of their files, into an `artifacts.json` manifest
+    /// in the output directory, for example to list them on a release page.
+    ///
+    /// When a private key is provided, the manifest is signed into `artifacts.json.sig`.
+    /// Consumers verify the signature of the manifest first, then each downloaded output
+    /// against its digest in the manifest and its own signature.
+    #[clap(long)]
+    artifacts_manifest: bool,
+    /// Write a `GITHUB_RELEASE.md` snippet in the output directory with a markdown table
+    /// of the generated outputs and their SHA-256 digest, ready to paste in the notes of a GitHub release.
+    ///
+    /// GitHub renames the release assets with spaces or special characters, so the outputs with such names
+    /// are also linked under the asset name listed in the table, which is the file to upload.
+    #[clap(long)]
+    github_release: bool,
+    /// Bundle the generated outputs with their signatures, the generated `latest.json`
+    /// and the `SHA256SUMS` manifest into a `release-<version>.zip` archive in the output directory.
+    ///
+    /// When a private key is provided, the archive is signed.
+    #[clap(long)]
+    release_bundle: bool,
+    /// Write the BLAKE3 digest of each generated output into a `<file>.b3` file
+    /// and include it in the generated `latest.json`.
+    #[clap(long)]
+    blake3: bool,
+    /// Write an in-toto provenance statement of each generated output into a `<file>.intoto.json` file,
+    /// with the SHA-256 digest of its files and the parameters of the invocation.
+    ///
+    /// When a private key is provided, the statements are signed.
+    #[clap(long)]
+    provenance: bool,
+    /// The identity of the builder recorded in the provenance statements, like the URL of the CI runner.
+    #[clap(long, requires = "provenance")]
+    provenance_builder_id: Option<String>,
+    /// The artifacts of the previous version to make delta updates from,
+    /// written next to the generated outputs with the same extension as `<file>.<digest>.delta`
+    /// and included in the generated `latest.json`.
+    ///
+    /// When a private key is provided, the deltas are signed.
+    #[clap(long, value_delimiter = ',')]
+    delta_from: Option<Vec<PathBuf>>,
+    /// Split each generated output larger than this size, in bytes, into `<file>.part<n>` volumes
+    /// with a `<file>.parts.json` manifest describing how to join them.
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    split_volume_size: Option<u64>,
+    /// Load the release notes of the generated `latest.json` from a file or a string.
+    ///
+    /// Defaults to the `releaseNotes` of the configuration.
+    #[clap(long)]
+    release_notes: Option<String>,
+    /// The release date of the generated `latest.json`, formatted according to RFC 3339.
+    ///
+    /// Defaults to the signing timestamp of the outputs.
+    #[clap(long)]
+    pub_date: Option<String>,
+    /// The date after which clients must reject the generated `latest.json`, formatted according to RFC 3339.
+    ///
+    /// The manifest never expires by default.
+    #[clap(long)]
+    expires_at: Option<String>,
+    /// Emit the public key of the signing private key into the generated `latest.json`, a `pubkey.pub` file, or both.
+    ///
+    /// Clients must still get the key they trust out-of-band for the first install.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    emit_public_key: Vec<PublicKeyDestination>,
+    /// Generate a Homebrew Cask of the `.dmg` or `.app` in the output directory,
+    /// using this template for the download URL of the artifact.
+    ///
+    /// The `{{version}}` and `{{artefact}}` placeholders are replaced
+    /// with the version of the app and the file name of the artifact.
+    #[clap(long)]
+    homebrew_cask_url: Option<String>,
+    /// Generate a Scoop manifest of the NSIS installer in the output directory,
+    /// using this template for the download URL of the installer.
+    ///
+    /// The `{{version}}` and `{{artefact}}` placeholders are replaced
+    /// with the version of the app and the file name of the installer.
+    #[clap(long)]
+    scoop_url: Option<String>,
+    /// The SPDX identifier of the license of the app in the generated Scoop manifest.
+    #[clap(long, requires = "scoop_url")]
+    scoop_license: Option<String>,
+    /// Generate the winget manifests of the NSIS and MSI installers in the output directory,
+    /// using this template for the download URL of the installers.
+    ///
+    /// The `{{version}}` and `{{artefact}}` placeholders are replaced
+    /// with the version of the app and the file name of the installer.
+    #[clap(long, requires = "winget_license")]
+    winget_url: Option<String>,
+    /// The license of the app in the generated winget manifests.
+    #[clap(long, requires = "winget_url")]
+    winget_license: Option<String>,
+    /// The package identifier of the generated winget manifests, like `Publisher.App`.
+    #[clap(long, requires = "winget_url")]
+    winget_identifier: Option<String>,
+    /// Generate the `Packages`, `Packages.gz` and `Release` files of a flat apt repository
+    /// of the `.deb` packages in the output directory.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    #[clap(long)]
+    apt_repository: bool,
+    /// The GPG key used to sign the `Release` file of the apt repository,
+    /// generating `Release.gpg` and `InRelease`.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    #[clap(long, requires = "apt_repository")]
+    apt_signing_key: Option<String>,
     /// Which packages to use from the current workspace.
     #[clap(short, long, value_delimiter = ',')]
     pub(crate) packages: Option<Vec<String>>,
-    /// The directory where the packages will be placed.
+    /// The directory where the packages, their signatures and the checksums and update manifests will be placed,
+    /// created if missing.
     ///
+    /// Takes precedence over the [`Config::out_dir`] of every configuration,
+    /// the flavors are still packaged into their own subdirectory of it.
     /// If [`Config::binaries_dir`] is not defined, it is also the path where the binaries are located if they use relative paths.
-    #[clap(short, long, alias = "out")]
+    #[clap(short, long, alias = "out", visible_alias = "output-dir")]
     out_dir: Option<PathBuf>,
     /// The directory where the [`Config::binaries`] exist.
     ///
     /// Defaults to [`Config::out_dir`]
     #[clap(long)]
     binaries_dir: Option<PathBuf>,
+    /// The project root the relative paths of every configuration are resolved against,
+    /// like its resources, binaries and icons, independently of the current directory.
+    ///
+    /// Takes precedence over the [`Config::project_root`] of every configuration,
+    /// which defaults to the directory of its configuration file.
+    #[clap(long)]
+    root: Option<PathBuf>,
     /// Package the release version of your app.
     /// Ignored when `--config` is used.
     #[clap(short, long, group = "cargo-profile")]
@@ -83,9 +297,53 @@ pub(crate) struct Cli {
     /// Ignored when `--config` is used.
     #[clap(long)]
     manifest_path: Option<PathBuf>,
+    /// Skip the package formats that can't be built on the current operating system,
+    /// instead of failing.
+    #[clap(long)]
+    skip_unsupported: bool,
+    /// Warn about the shared libraries linked by the main binary
+    /// that won't be present on a clean target system and aren't bundled.
+    #[clap(long)]
+    check_shared_libraries: bool,
+    /// Fail instead of warning about the shared libraries of `--check-shared-libraries`,
+    /// and instead of skipping the `--scan` when the scanner is not installed.
+    #[clap(long)]
+    strict: bool,
+    /// Remove the outputs of previous runs for the formats being built before packaging.
+    #[clap(long, alias = "clean")]
+    force: bool,
+    /// Stop at the first format that fails to package.
+    ///
+    /// By default, the other formats are still packaged and signed, and the CLI exits
+    /// with code 2 when some formats failed and others succeeded.
+    #[clap(long)]
+    fail_fast: bool,
     /// Target triple to use for detecting your app binaries.
     #[clap(long)]
     target: Option<String>,
+    /// The flavors of the app to build, defaults to all the flavors defined in the configuration.
+    #[clap(long, value_delimiter = ',')]
+    flavor: Option<Vec<String>>,
+    /// List the package formats available on the current platform
+    /// and whether the external tools they require are installed.
+    #[clap(long)]
+    list_formats: bool,
+    /// Print the `--list-formats` output or the summary of the packaging run as JSON.
+    #[clap(long)]
+    output_json: bool,
+    /// Print the fully-resolved configurations, as JSON by default or as TOML, without building anything.
+    ///
+    /// The configurations are printed as the packagers see them, after the CLI flags,
+    /// the Cargo.toml metadata and the defaults are applied, one document per configuration and flavor.
+    /// The signing password and private key are redacted.
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "json"
+    )]
+    print_config: Option<init::ConfigFormat>,
 
     #[command(subcommand)]
     command: Option<Commands>,
@@ -99,11 +357,21 @@ fn run_cli(cli: Cli) -> Result<()> {
     // otherwise run the default packaging command
     if let Some(command) = cli.command {
         match command {
+            Commands::Init(opts) => init::command(opts)?,
             Commands::Signer(opts) => signer::command(opts)?,
+            Commands::Sign(opts) => signer::sign::command(opts)?,
+            Commands::Verify(opts) => signer::verify::command(opts)?,
+            Commands::SigInfo(opts) => signer::info::command(opts)?,
+            Commands::Zsync(opts) => zsync::command(opts)?,
+            Commands::VerifyRelease(opts) => verify_release::command(opts)?,
         }
         return Ok(());
     }
 
+    if cli.list_formats {
+        return formats::list(cli.output_json);
+    }
+
     let configs = config::detect_configs(&cli)?;
 
     if configs.is_empty() {
@@ -115,14 +383,15 @@ fn run_cli(cli: Cli) -> Result<()> {
         .out_dir
         .as_ref()
         .map(|p| {
-            if p.exists() {
-                dunce::canonicalize(p).map_err(|e| Error::IoWithPath(p.clone(), e))
-            } else {
-                fs::create_dir_all(p).map_err(|e| Error::IoWithPath(p.clone(), e))?;
-                Ok(p.to_owned())
-            }
+            fs::create_dir_all(p).map_err(|e| Error::IoWithPath(p.clone(), e))?;
+            dunce::canonicalize(p).map_err(|e| Error::IoWithPath(p.clone(), e))
         })
         .transpose()?;
+    let cli_root = cli
+        .root
+        .as_ref()
+        .map(|p| dunce::canonicalize(p).map_err(|e| Error::IoWithPath(p.clone(), e)))
+        .transpose()?;
 
     let private_key = match cli.private_key {
         Some(path) if PathBuf::from(&path).exists() => Some(
@@ -131,25 +400,98 @@ fn run_cli(cli: Cli) -> Result<()> {
         k => k,
     };
 
-    let signing_config = private_key.map(|k| SigningConfig {
-        private_key: k,
+    // the values of the CLI flags, merged with the signing configuration of each config
+    let cli_signing_config = SigningConfig {
+        private_key: private_key.unwrap_or_default(),
         password: cli.password,
-    });
+        trusted_comment: cli.trusted_comment,
+        version: None,
+        channel: cli.channel,
+        algorithms: None,
+        extensions: None,
+        exclude_extensions: None,
+        timestamp_url: cli.timestamp_url,
+        prehash: true,
+        hash_only_formats: None,
+    };
+
+    let mut manifest_config = UpdateManifestConfig::new();
+    if let Some(notes) = cli.release_notes {
+        let path = PathBuf::from(&notes);
+        manifest_config = manifest_config.notes(if path.exists() {
+            ReleaseNotes::File(dunce::canonicalize(&path).map_err(|e| Error::IoWithPath(path, e))?)
+        } else {
+            ReleaseNotes::Text(notes)
+        });
+    }
+    if let Some(pub_date) = cli.pub_date {
+        manifest_config = manifest_config.pub_date(pub_date);
+    }
+    if let Some(expires_at) = cli.expires_at {
+        manifest_config = manifest_config.expires_at(expires_at);
+    }
+
+    // the parameters of the invocation recorded in the provenance statements, leaving out the secrets
+    let mut provenance_config = ProvenanceConfig::new()
+        .parameter("formats", serde_json::to_value(&cli.formats)?)
+        .parameter("release", cli.release)
+        .parameter("profile", cli.profile.clone())
+        .parameter("target", cli.target.clone())
+        .parameter("flavor", cli.flavor.clone());
+    if let Some(builder_id) = &cli.provenance_builder_id {
+        provenance_config = provenance_config.builder_id(builder_id);
+    }
+
+    let mut scan_config = ScanConfig::new().strict(cli.strict);
+    if let Some(command) = &cli.scan_command {
+        scan_config = scan_config.command(command);
+    }
 
     let mut outputs = Vec::new();
+    let mut failures = Vec::new();
     let mut signatures = Vec::new();
     let mut summaries = Vec::new();
+    #[allow(clippy::type_complexity)]
+    let mut out_dir_packages: BTreeMap<
+        PathBuf,
+        (crate::Config, Option<SigningConfig>, Vec<PackageOutput>),
+    > = BTreeMap::new();
+    // expand the flavors of each config, packaged into their own subdirectory of the out dir
+    let mut flavored_configs = Vec::new();
     for (config_dir, mut config) in configs {
-        tracing::trace!(config = ?config);
-
         if let Some(dir) = &cli_out_dir {
             config.out_dir.clone_from(dir)
         }
+        for config in config.flavor_configs(cli.flavor.as_deref())? {
+            flavored_configs.push((config_dir.clone(), config));
+        }
+    }
+
+    for (config_dir, mut config) in flavored_configs {
+        tracing::trace!(config = ?config);
 
         if let Some(formats) = &cli.formats {
             config.formats.replace(formats.clone());
         }
 
+        if cli.skip_unsupported {
+            config.skip_unsupported_formats = true;
+        }
+
+        if cli.force {
+            config.clean_outputs = true;
+        }
+
+        if cli.fail_fast {
+            config.fail_fast = true;
+        }
+
+        if cli.strict {
+            config.shared_libraries_check = Some(SharedLibrariesCheck::Error);
+        } else if cli.check_shared_libraries && config.shared_libraries_check.is_none() {
+            config.shared_libraries_check = Some(SharedLibrariesCheck::Warn);
+        }
+
         if let Some(target_triple) = &cli.target {
             config.target_triple.replace(target_triple.clone());
         }
@@ -175,34 +517,265 @@ fn run_cli(cli: Cli) -> Result<()> {
                 .map_err(|e| Error::IoWithPath(parent.to_path_buf(), e))?;
         }
 
+        if let Some(root) = &cli_root {
+            config.project_root.replace(root.clone());
+        }
+        if let Some(root) = &config.project_root {
+            // a relative project root is resolved against the directory of the config
+            let root = dunce::canonicalize(root).map_err(|e| Error::IoWithPath(root.clone(), e))?;
+            std::env::set_current_dir(&root).map_err(|e| Error::IoWithPath(root.clone(), e))?;
+            config.project_root.replace(root);
+        }
+
+        let mut signing_config =
+            merge_signing_config(&cli_signing_config, config.signing.as_ref())?;
+        if let Some(signing_config) = &mut signing_config {
+            signing_config
+                .version
+                .get_or_insert_with(|| config.version.clone());
+            signing_config.validate()?;
+        }
+
+        if let Some(format) = cli.print_config {
+            let mut resolved = config.resolved()?;
+            resolved.signing = signing_config;
+            println!("{}", config::render_resolved_config(&resolved, format)?);
+            continue;
+        }
+
         // create the packages
-        let mut packages = package(&config)?;
+        let mut packages = match package(&config) {
+            Ok(packages) => packages,
+            // keep going with the formats that succeeded
+            Err(crate::Error::PackagingFailed { packages, errors }) if !packages.is_empty() => {
+                failures.extend(errors);
+                packages
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if cli.scan {
+            scan::scan_outputs(&scan_config, &packages)?;
+        }
 
         // sign the packages
-        if let Some(signing_config) = &signing_config {
+        if let Some(signing_config) = signing_config.as_ref().filter(|_| !cli.checksums) {
             let s = sign_outputs(signing_config, &mut packages)?;
             signatures.extend(s);
         }
 
+        if cli.blake3 {
+            summaries.extend(hash_outputs_blake3(&mut packages)?);
+        }
+
+        if let Some(previous) = &cli.delta_from {
+            let deltas = delta::delta_outputs(&mut packages, previous, signing_config.as_ref())?;
+            summaries.extend(deltas);
+        }
+
+        if cli.provenance {
+            let provenance = provenance::write_provenance(
+                &config,
+                &packages,
+                &provenance_config,
+                signing_config.as_ref(),
+            )?;
+            summaries.extend(provenance);
+        }
+
+        if let Some(volume_size) = cli.split_volume_size {
+            for path in packages.iter().flat_map(|p| &p.paths) {
+                if path.is_file() && util::disk_size(path) > volume_size {
+                    let (manifest, volumes) = split::split_file(path, volume_size)?;
+                    summaries.extend(volumes);
+                    summaries.push(manifest);
+                }
+            }
+        }
+
+        let mut manifest_config = manifest_config.clone();
+        if let Some(signing_config) = &signing_config {
+            if cli.emit_public_key.contains(&PublicKeyDestination::Sidecar) {
+                let (path, _) = updater::write_public_key(signing_config, &config.out_dir())?;
+                summaries.push(path);
+            }
+            if cli
+                .emit_public_key
+                .contains(&PublicKeyDestination::Manifest)
+            {
+                manifest_config =
+                    manifest_config.public_key(sign::signing_public_key(signing_config)?);
+            }
+        } else if !cli.emit_public_key.is_empty() {
+            tracing::warn!("The packages are not signed, no public key to emit");
+        }
+
         // build summary
-        summaries.push(summarise_outputs(&config, &mut packages)?);
+        let manifest_path = summarise_outputs_with_config(&config, &packages, &manifest_config)?;
+        if let Some(signing_config) = &signing_config {
+            let (signature_path, _) =
+                updater::sign_update_manifest(signing_config, &manifest_path)?;
+            signatures.push(signature_path);
+        }
+        summaries.push(manifest_path);
+
+        if let Some(url_template) = &cli.homebrew_cask_url {
+            if packages
+                .iter()
+                .any(|p| matches!(p.format, PackageFormat::App | PackageFormat::Dmg))
+            {
+                summaries.push(homebrew::write_cask(
+                    &config,
+                    &packages,
+                    &HomebrewCaskConfig::new(url_template),
+                    &config.out_dir(),
+                )?);
+            }
+        }
 
+        if let Some(url_template) = &cli.scoop_url {
+            if packages.iter().any(|p| p.format == PackageFormat::Nsis) {
+                let mut manifest_config = ScoopManifestConfig::new(url_template);
+                if let Some(license) = &cli.scoop_license {
+                    manifest_config = manifest_config.license(license);
+                }
+                summaries.push(scoop::write_manifest(
+                    &config,
+                    &packages,
+                    &manifest_config,
+                    &config.out_dir(),
+                )?);
+            }
+        }
+
+        if let (Some(url_template), Some(license)) = (&cli.winget_url, &cli.winget_license) {
+            if packages
+                .iter()
+                .any(|p| matches!(p.format, PackageFormat::Nsis | PackageFormat::Wix))
+            {
+                let mut manifest_config = WingetManifestConfig::new(url_template, license);
+                if let Some(identifier) = &cli.winget_identifier {
+                    manifest_config = manifest_config.identifier(identifier);
+                }
+                summaries.extend(winget::write_manifests(
+                    &config,
+                    &packages,
+                    &manifest_config,
+                    &config.out_dir(),
+                )?);
+            }
+        }
+
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        if cli.apt_repository && packages.iter().any(|p| p.format == PackageFormat::Deb) {
+            let mut repo_config = AptRepositoryConfig::new();
+            if let Some(signing_key) = &cli.apt_signing_key {
+                repo_config = repo_config.signing_key(signing_key);
+            }
+            summaries.extend(apt::write_repository(
+                &packages,
+                &repo_config,
+                &config.out_dir(),
+            )?);
+        }
+
+        if cli.checksums || cli.artifacts_manifest || cli.github_release || cli.release_bundle {
+            // multiple configs can share the same output directory,
+            // so the manifests and release bundles are generated once all packages are built
+            out_dir_packages
+                .entry(config.out_dir())
+                .or_insert_with(|| (config.clone(), signing_config, Vec::new()))
+                .2
+                .extend(packages);
+        } else {
+            outputs.extend(packages);
+        }
+    }
+
+    if cli.print_config.is_some() {
+        return Ok(());
+    }
+
+    // generate and sign the checksums manifests and release bundles
+    for (out_dir, (config, signing_config, mut packages)) in out_dir_packages {
+        if cli.checksums {
+            let manifest = if let Some(signing_config) = &signing_config {
+                let mut signing_config = signing_config.clone();
+                signing_config
+                    .version
+                    .get_or_insert_with(|| config.version.clone());
+                let (manifest, _) = sign_checksums(&signing_config, &mut packages, &out_dir)?;
+                signatures.push(manifest.with_additional_extension("sig"));
+                manifest
+            } else {
+                let files = packages
+                    .iter()
+                    .flat_map(|p| p.paths.iter().filter(|p| p.is_file()).cloned())
+                    .collect::<Vec<_>>();
+                checksum::write_sha256sums(&out_dir, &files)?
+            };
+            summaries.push(manifest);
+        }
+
+        if cli.artifacts_manifest {
+            let manifest = summary::write_manifest(&packages, &out_dir)?;
+            if let Some(signing_config) = &signing_config {
+                let mut signing_config = signing_config.clone();
+                signing_config
+                    .version
+                    .get_or_insert_with(|| config.version.clone());
+                let (signature, _) = sign::sign_file(&signing_config, &manifest)?;
+                signatures.push(signature);
+            }
+            summaries.push(manifest);
+        }
+
+        if cli.github_release {
+            let (release, assets) = summary::write_github_release(&packages, &out_dir)?;
+            summaries.push(release);
+            summaries.extend(assets);
+        }
+
+        if cli.release_bundle {
+            let extra_files = signatures
+                .iter()
+                .chain(summaries.iter())
+                .filter(|p| p.starts_with(&out_dir))
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut paths = release::bundle_release(
+                &config,
+                &mut packages,
+                &extra_files,
+                signing_config.as_ref(),
+            )?
+            .into_iter();
+            // the bundle is followed by its signature
+            summaries.extend(paths.next());
+            signatures.extend(paths);
+        }
         outputs.extend(packages);
     }
 
     // flatten paths
-    let outputs = outputs
-        .into_iter()
-        .flat_map(|o| o.paths)
+    let paths = outputs
+        .iter()
+        .flat_map(|o| o.paths.iter().cloned())
         .chain(summaries.into_iter())
         .collect::<Vec<_>>();
 
     // print information when finished
-    let len = outputs.len();
+    let len = paths.len();
     if len >= 1 {
         let pluralised = if len == 1 { "package" } else { "packages" };
         let mut printable_paths = String::new();
-        for path in outputs {
+        for path in paths {
             let _ = writeln!(printable_paths, "        {}", util::display_path(path));
         }
         tracing::info!(
@@ -228,23 +801,99 @@ fn run_cli(cli: Cli) -> Result<()> {
         );
     }
 
+    summary::print(&outputs, cli.output_json)?;
+
+    if !failures.is_empty() {
+        return Err(Error::PartialSuccess(failures));
+    }
+
     Ok(())
 }
 
+/// Merges the signing configuration of a config with the values of the CLI flags,
+/// which take precedence. Returns `None` when no private key is set.
+fn merge_signing_config(
+    cli_signing_config: &SigningConfig,
+    signing_config: Option<&SigningConfig>,
+) -> Result<Option<SigningConfig>> {
+    let mut merged = signing_config.cloned().unwrap_or_default();
+
+    if merged.password.as_deref().is_some_and(|p| !p.is_empty()) {
+        tracing::warn!(
+            "The signing private key password is set in the configuration, \
+            prefer the `CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD` environment variable \
+            to avoid checking it into version control"
+        );
+    }
+
+    if !cli_signing_config.private_key.is_empty() {
+        merged
+            .private_key
+            .clone_from(&cli_signing_config.private_key);
+    } else if PathBuf::from(&merged.private_key).is_file() {
+        let path = PathBuf::from(&merged.private_key);
+        merged.private_key = fs::read_to_string(&path).map_err(|e| Error::IoWithPath(path, e))?;
+    }
+    if cli_signing_config.password.is_some() {
+        merged.password.clone_from(&cli_signing_config.password);
+    }
+    if cli_signing_config.trusted_comment.is_some() {
+        merged
+            .trusted_comment
+            .clone_from(&cli_signing_config.trusted_comment);
+    }
+    if cli_signing_config.channel.is_some() {
+        merged.channel.clone_from(&cli_signing_config.channel);
+    }
+    if cli_signing_config.timestamp_url.is_some() {
+        merged
+            .timestamp_url
+            .clone_from(&cli_signing_config.timestamp_url);
+    }
+
+    Ok((!merged.private_key.is_empty()).then_some(merged))
+}
+
 /// Run the packager CLI
+///
+/// Exits with code 1 on failure, or 2 when some formats failed to package and the others succeeded.
 pub fn run<I, A>(args: I, bin_name: Option<String>)
 where
     I: IntoIterator<Item = A>,
     A: Into<OsString> + Clone,
 {
-    if let Err(e) = try_run(args, bin_name) {
-        tracing::error!("{}", e);
-        std::process::exit(1);
+    let (error_format, result) = match parse_cli(args, bin_name) {
+        Ok(cli) => (cli.error_format, run_parsed_cli(cli)),
+        Err(e) => (ErrorFormat::Human, Err(e)),
+    };
+    if let Err(e) = result {
+        match error_format {
+            ErrorFormat::Human => tracing::error!("{}", e),
+            ErrorFormat::Json => match serde_json::to_string(&e) {
+                Ok(json) => eprintln!("{json}"),
+                Err(_) => tracing::error!("{}", e),
+            },
+        }
+        // distinguish the partial failures, where some packages were still built
+        let code = if matches!(e, Error::PartialSuccess(_)) {
+            2
+        } else {
+            1
+        };
+        std::process::exit(code);
     }
 }
 
 /// Try run the packager CLI
 pub fn try_run<I, A>(args: I, bin_name: Option<String>) -> Result<()>
+where
+    I: IntoIterator<Item = A>,
+    A: Into<OsString> + Clone,
+{
+    run_parsed_cli(parse_cli(args, bin_name)?)
+}
+
+fn parse_cli<I, A>(args: I, bin_name: Option<String>) -> Result<Cli>
 where
     I: IntoIterator<Item = A>,
     A: Into<OsString> + Clone,
@@ -260,7 +909,10 @@ where
             None => Cli::command(),
         })
     })?;
+    Ok(cli)
+}
 
+fn run_parsed_cli(cli: Cli) -> Result<()> {
     if !cli.quite {
         init_tracing_subscriber(cli.verbose);
         if std::env::var_os("CARGO_TERM_COLOR").is_none() {
@@ -270,3 +922,141 @@ where
 
     run_cli(cli)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_has_valid_arguments() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn it_merges_signing_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("key");
+        fs::write(&key_path, "key from file").unwrap();
+
+        let config = SigningConfig::new()
+            .private_key(key_path.display().to_string())
+            .password("config password")
+            .channel("beta");
+        let merged = merge_signing_config(&SigningConfig::default(), Some(&config))
+            .unwrap()
+            .unwrap();
+        assert_eq!(merged.private_key, "key from file");
+        assert_eq!(merged.password.as_deref(), Some("config password"));
+        assert_eq!(merged.channel.as_deref(), Some("beta"));
+
+        let cli = SigningConfig::new()
+            .private_key("key from cli")
+            .password("")
+            .trusted_comment("file:{file}");
+        let merged = merge_signing_config(&cli, Some(&config)).unwrap().unwrap();
+        assert_eq!(merged.private_key, "key from cli");
+        assert_eq!(merged.password.as_deref(), Some(""));
+        assert_eq!(merged.trusted_comment.as_deref(), Some("file:{file}"));
+        assert_eq!(merged.channel.as_deref(), Some("beta"));
+
+        assert!(merge_signing_config(&SigningConfig::default(), None)
+            .unwrap()
+            .is_none());
+        assert!(merge_signing_config(&cli, None).unwrap().is_some());
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    #[test]
+    fn it_places_every_artifact_in_the_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh").unwrap();
+        let configured_out_dir = dir.path().join("configured");
+        let config = serde_json::json!({
+            "productName": "App",
+            "version": "1.0.0",
+            "identifier": "com.example.app",
+            "outDir": configured_out_dir,
+            "binariesDir": bin_dir,
+            "binaries": [{ "path": "app", "main": true }],
+        });
+        let keypair = crate::sign::generate_key_unencrypted().unwrap();
+        let out_dir = dir.path().join("scratch").join("dist");
+
+        let cli = Cli::try_parse_from([
+            "--quite".into(),
+            "--config".into(),
+            config.to_string(),
+            "--output-dir".into(),
+            out_dir.display().to_string(),
+            "--formats".into(),
+            "deb,shar".into(),
+            "--private-key".into(),
+            keypair.sk,
+        ])
+        .unwrap();
+        run_cli(cli).unwrap();
+
+        assert!(!configured_out_dir.exists());
+        let artifacts = fs::read_dir(&out_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        for extension in [".deb", ".deb.sig", ".sh", ".sh.sig"] {
+            assert!(
+                artifacts.iter().any(|a| a.ends_with(extension)),
+                "{artifacts:?}"
+            );
+        }
+        assert!(
+            artifacts.iter().any(|a| a == "latest.json"),
+            "{artifacts:?}"
+        );
+    }
+
+    #[test]
+    fn it_serializes_errors() {
+        let cli = Cli::try_parse_from(["--error-format", "json"]).unwrap();
+        assert_eq!(cli.error_format, ErrorFormat::Json);
+
+        let error = Error::IoWithPath(
+            PathBuf::from("dist"),
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        );
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "IoWithPath",
+                "message": error.to_string(),
+                "path": "dist"
+            })
+        );
+
+        let error = Error::Packaging(crate::Error::SigningKeyExists(PathBuf::from("key")));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "SigningKeyExists",
+                "message": error.to_string(),
+                "path": "key"
+            })
+        );
+
+        let error = Error::Packaging(crate::Error::Architecture);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "Architecture",
+                "message": "Unable to determine target-architecture",
+                "path": null
+            })
+        );
+    }
+}
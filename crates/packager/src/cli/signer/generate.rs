@@ -3,11 +3,14 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use std::path::PathBuf;
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 
-use crate::cli::Result;
+use crate::cli::{Error, Result};
 
 #[derive(Debug, Clone, Parser)]
 #[clap(about = "Generate a new signing key to sign files")]
@@ -15,30 +18,90 @@ pub struct Options {
     /// Set a password for the new signing key.
     #[clap(long, env = "CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD")]
     password: Option<String>,
+    /// Generate a signing key that is not encrypted, without prompting for a password.
+    ///
+    /// Anyone who can read an unencrypted key can sign files with it,
+    /// only store it in a secret store like the encrypted secrets of a CI provider.
+    #[clap(long, conflicts_with = "password")]
+    no_password: bool,
     #[clap(long)]
     /// A path where the private key will be stored.
     path: Option<PathBuf>,
+    /// A path where the public key will be stored, defaults to the path of the private key with a `.pub` extension appended.
+    #[clap(long, requires = "path")]
+    public_key_path: Option<PathBuf>,
     /// Overwrite the private key even if it exists on the specified path.
+    ///
+    /// The existing key is deleted irrecoverably, so its file name must be typed to confirm,
+    /// unless `--yes` is set. Fails when stdin is not a terminal to confirm.
     #[clap(short, long)]
     force: bool,
+    /// Overwrite an existing private key with `--force` without confirming, for automation.
+    #[clap(short, long, requires = "force")]
+    yes: bool,
     /// Run in CI mode and skip prompting for values.
     #[clap(long)]
     ci: bool,
 }
 
+/// Asks to type the file name of the private key at `path` before it is overwritten,
+/// failing with [`Error::KeyOverwriteNeedsConfirmation`] when stdin is not a terminal
+/// and with [`Error::KeyOverwriteAborted`] when another name is typed.
+fn confirm_overwrite(
+    path: &Path,
+    stdin_is_terminal: bool,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> Result<()> {
+    if !stdin_is_terminal {
+        return Err(Error::KeyOverwriteNeedsConfirmation(path.to_path_buf()));
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    write!(
+        output,
+        "The private key {} already exists and will be deleted irrecoverably.\nType its file name `{file_name}` to overwrite it: ",
+        path.display()
+    )?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    if answer.trim_end_matches(['\n', '\r']) != file_name {
+        return Err(Error::KeyOverwriteAborted(path.to_path_buf()));
+    }
+    Ok(())
+}
+
 pub fn command(mut options: Options) -> Result<()> {
     options.ci = options.ci || std::env::var("CI").is_ok();
-    if options.ci && options.password.is_none() {
+    if let Some(path) = options.path.as_deref().filter(|path| path.exists()) {
+        if options.force && !options.yes {
+            let stdin = io::stdin();
+            confirm_overwrite(path, stdin.is_terminal(), stdin.lock(), io::stderr())?;
+        }
+    }
+    if options.ci && options.password.is_none() && !options.no_password {
         tracing::warn!("Generating a new private key without a password, for security reasons, we recommend setting a password instead.");
-        options.password.replace("".into());
+        options.no_password = true;
     }
 
     tracing::info!("Generating a new signing key.");
-    let keypair = crate::sign::generate_key(options.password)?;
+    let keypair = if options.no_password {
+        crate::sign::generate_key_unencrypted()?
+    } else {
+        crate::sign::generate_key(options.password)?
+    };
 
     match options.path {
         Some(path) => {
-            let keys = crate::sign::save_keypair(&keypair, path, options.force)?;
+            let keys = crate::sign::save_keypair_with_public_key_path(
+                &keypair,
+                path,
+                options.public_key_path.as_deref(),
+                options.force,
+            )?;
             tracing::info!(
                 "Finished generating and saving the keys:\n        {}\n        {}",
                 keys.0.display(),
@@ -53,3 +116,36 @@ pub fn command(mut options: Options) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_confirms_key_overwrites() {
+        let path = Path::new("keys").join("app.key");
+
+        // without a terminal to confirm, the key is not deleted silently
+        assert!(matches!(
+            confirm_overwrite(&path, false, "app.key\n".as_bytes(), io::sink()),
+            Err(Error::KeyOverwriteNeedsConfirmation(p)) if p == path
+        ));
+
+        let mut prompt = Vec::new();
+        confirm_overwrite(&path, true, "app.key\n".as_bytes(), &mut prompt).unwrap();
+        assert!(String::from_utf8(prompt)
+            .unwrap()
+            .ends_with("Type its file name `app.key` to overwrite it: "));
+        confirm_overwrite(&path, true, "app.key\r\n".as_bytes(), io::sink()).unwrap();
+
+        for answer in ["y\n", "\n", "", "app\n", " app.key\n"] {
+            assert!(
+                matches!(
+                    confirm_overwrite(&path, true, answer.as_bytes(), io::sink()),
+                    Err(Error::KeyOverwriteAborted(p)) if p == path
+                ),
+                "{answer:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{fmt::Write, fs, path::PathBuf};
+
+use clap::Parser;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::cli::{Error, Result};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Print the key id and the comments of a signature, without verifying it")]
+pub struct Options {
+    /// Print the signature metadata as JSON.
+    #[clap(long)]
+    output_json: bool,
+    /// The path of the signature.
+    signature: PathBuf,
+}
+
+pub fn command(options: Options) -> Result<()> {
+    let signature = fs::read_to_string(&options.signature)
+        .map_err(|e| Error::IoWithPath(options.signature.clone(), e))?;
+    let info = crate::sign::signature_info(&signature)?;
+
+    if options.output_json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "key id:            {}", info.key_id);
+    let _ = writeln!(out, "untrusted comment: {}", info.untrusted_comment);
+    let _ = writeln!(out, "trusted comment:   {}", info.trusted_comment);
+    if let Some(timestamp) = info.timestamp {
+        let date = i64::try_from(timestamp)
+            .ok()
+            .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+            .and_then(|d| d.format(&Rfc3339).ok());
+        let _ = match date {
+            Some(date) => writeln!(out, "timestamp:         {timestamp} ({date})"),
+            None => writeln!(out, "timestamp:         {timestamp}"),
+        };
+    }
+    if let Some(file) = &info.file {
+        let _ = writeln!(out, "file:              {file}");
+    }
+    print!("{out}");
+
+    Ok(())
+}
@@ -7,16 +7,19 @@ use clap::{Parser, Subcommand};
 use super::Result;
 
 mod generate;
-mod sign;
+pub(crate) mod info;
+pub(crate) mod sign;
+pub(crate) mod verify;
 
 #[derive(Debug, Clone, Subcommand)]
 enum Commands {
     Sign(sign::Options),
     Generate(generate::Options),
+    Verify(verify::Options),
 }
 
 #[derive(Debug, Clone, Parser)]
-#[clap(about = "Sign a file or generate a new signing key to sign files")]
+#[clap(about = "Sign or verify files, or generate a new signing key to sign files")]
 pub struct Options {
     #[command(subcommand)]
     command: Commands,
@@ -26,5 +29,6 @@ pub fn command(options: Options) -> Result<()> {
     match options.command {
         Commands::Sign(opts) => sign::command(opts),
         Commands::Generate(opts) => generate::command(opts),
+        Commands::Verify(opts) => verify::command(opts),
     }
 }
@@ -10,16 +10,37 @@ use clap::Parser;
 use crate::cli::{Error, Result};
 
 #[derive(Debug, Clone, Parser)]
-#[clap(about = "Sign a file")]
+#[clap(about = "Sign already built files")]
 pub struct Options {
     /// Load the private key from a file or a string.
+    ///
+    /// Unencrypted OpenSSH and PEM Ed25519 keys are also accepted.
     #[clap(short = 'k', long, env = "CARGO_PACKAGER_SIGN_PRIVATE_KEY")]
     private_key: Option<String>,
     /// The password for the private key.
     #[clap(long, env = "CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD")]
     password: Option<String>,
-    /// The file to be signed.
-    file: PathBuf,
+    /// A template for the trusted comment of the signature,
+    /// supporting the `{timestamp}`, `{file}`, `{version}` and `{channel}` placeholders.
+    ///
+    /// Defaults to `timestamp:{timestamp}\tfile:{file}`.
+    #[clap(long)]
+    trusted_comment: Option<String>,
+    /// The version substituted for the `{version}` placeholder of the trusted comment.
+    #[clap(long)]
+    package_version: Option<String>,
+    /// The release channel substituted for the `{channel}` placeholder of the trusted comment.
+    #[clap(long)]
+    channel: Option<String>,
+    /// The URL of an RFC 3161 time-stamping authority that timestamps each signature
+    /// into a `<signature>.tsr` file.
+    #[clap(long)]
+    timestamp_url: Option<String>,
+    /// The files to be signed.
+    ///
+    /// If one of them is a directory, a `MANIFEST` of its files is written at its root and signed instead.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
 }
 
 pub fn command(options: Options) -> Result<()> {
@@ -37,13 +58,35 @@ pub fn command(options: Options) -> Result<()> {
     let config = crate::sign::SigningConfig {
         private_key,
         password: Some(options.password.unwrap_or_default()),
+        trusted_comment: options.trusted_comment,
+        version: options.package_version,
+        channel: options.channel,
+        algorithms: None,
+        extensions: None,
+        exclude_extensions: None,
+        timestamp_url: options.timestamp_url,
+        prehash: true,
+        hash_only_formats: None,
     };
-    let signature_path = crate::sign::sign_file(&config, options.file)?;
+    for file in options.files {
+        if file.is_dir() {
+            let (manifest_path, _) = crate::sign::sign_directory(&config, file)?;
 
-    tracing::info!(
-        "Signed the file successfully! find the signature at: {}",
-        signature_path.0.display()
-    );
+            tracing::info!(
+                "Signed the directory successfully! find the manifest at: {}",
+                manifest_path.display()
+            );
+
+            continue;
+        }
+
+        let signature_path = crate::sign::sign_file(&config, file)?;
+
+        tracing::info!(
+            "Signed the file successfully! find the signature at: {}",
+            signature_path.0.display()
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,83 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+
+use crate::{
+    cli::{Error, Result},
+    util::PathExt,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Verify the signature of a file")]
+pub struct Options {
+    /// Load the public key from a file or a string.
+    ///
+    /// Unencrypted OpenSSH and PEM Ed25519 keys are also accepted.
+    #[clap(short = 'p', long, env = "CARGO_PACKAGER_SIGN_PUBLIC_KEY")]
+    public_key: String,
+    /// The path of the signature.
+    ///
+    /// Defaults to the `.sig` file next to the verified file.
+    #[clap(long)]
+    signature: Option<PathBuf>,
+    /// Also verify the `<signature>.tsr` RFC 3161 timestamp of the signature.
+    #[clap(long)]
+    timestamp: bool,
+    /// The PEM certificates of the time-stamping authority to verify the timestamp against
+    /// with `openssl ts -verify`.
+    ///
+    /// Without it, only the match of the timestamp with the signature is verified.
+    #[clap(long, requires = "timestamp")]
+    tsa_ca_file: Option<PathBuf>,
+    /// The file to be verified.
+    ///
+    /// If it is a directory, its `MANIFEST` and the files it lists are verified instead.
+    file: PathBuf,
+}
+
+pub fn command(options: Options) -> Result<()> {
+    let public_key = if PathBuf::from(&options.public_key).exists() {
+        fs::read_to_string(&options.public_key)
+            .map_err(|e| Error::IoWithPath(PathBuf::from(&options.public_key), e))?
+    } else {
+        options.public_key
+    };
+
+    if options.file.is_dir() {
+        crate::sign::verify_directory(&options.file, &public_key)?;
+        tracing::info!(
+            "Verified the directory {} successfully!",
+            options.file.display()
+        );
+        return Ok(());
+    }
+
+    let signature_path = options
+        .signature
+        .unwrap_or_else(|| options.file.with_additional_extension("sig"));
+    let signature = fs::read_to_string(&signature_path)
+        .map_err(|e| Error::IoWithPath(signature_path.clone(), e))?;
+    let trusted_comment =
+        crate::sign::verify_file_with_signature(&options.file, &signature, &public_key)?;
+
+    if options.timestamp {
+        let timestamp =
+            crate::timestamp::verify_timestamp(&signature_path, options.tsa_ca_file.as_deref())?;
+        tracing::info!(
+            "Verified the timestamp of the signature successfully! timestamped at: {}",
+            timestamp.gen_time
+        );
+    }
+
+    tracing::info!(
+        "Verified the file {} successfully! trusted comment: {}",
+        options.file.display(),
+        trusted_comment
+    );
+
+    Ok(())
+}
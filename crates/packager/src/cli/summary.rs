@@ -0,0 +1,343 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Summary of the packages built in a packaging run.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use super::{Error, Result};
+use crate::{checksum, util, PackageOutput};
+
+/// The file name of the artifacts manifest generated by [`write_manifest`].
+pub const ARTIFACTS_MANIFEST_FILE_NAME: &str = "artifacts.json";
+
+/// The file name of the GitHub release snippet generated by [`write_github_release`].
+pub const GITHUB_RELEASE_FILE_NAME: &str = "GITHUB_RELEASE.md";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageStatus {
+    format: &'static str,
+    paths: Vec<String>,
+    /// Time spent building the package, in milliseconds.
+    duration_ms: u128,
+    /// Size of the package files on disk, in bytes.
+    size: u64,
+    signed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_id: Option<String>,
+    /// The software bill of materials of the package.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sbom: Option<String>,
+    /// SHA-256 digest of each file of [`PackageStatus::paths`] and of the [`PackageStatus::sbom`],
+    /// only in the artifacts manifest.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    sha256: BTreeMap<String, String>,
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} {}", UNITS[unit])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+fn packages_status(packages: &[PackageOutput]) -> Vec<PackageStatus> {
+    packages
+        .iter()
+        .map(|package| PackageStatus {
+            format: package.format.short_name(),
+            paths: package.paths.iter().map(util::display_path).collect(),
+            duration_ms: package.duration.as_millis(),
+            size: package.paths.iter().map(|p| util::disk_size(p)).sum(),
+            signed: package.signed,
+            build_id: package.build_id.clone(),
+            sbom: package.sbom.as_ref().map(util::display_path),
+            sha256: BTreeMap::new(),
+        })
+        .collect()
+}
+
+fn render_table(packages: &[PackageStatus]) -> String {
+    let mut out = String::from("Packaging summary:\n");
+    let _ = writeln!(
+        out,
+        "    {:<10} {:>10} {:>12}  signed",
+        "format", "time", "size"
+    );
+    let mut total = Duration::ZERO;
+    for package in packages {
+        let duration = Duration::from_millis(package.duration_ms as u64);
+        total += duration;
+        let _ = writeln!(
+            out,
+            "    {:<10} {:>9.2}s {:>12}  {}",
+            package.format,
+            duration.as_secs_f64(),
+            format_size(package.size),
+            if package.signed { "yes" } else { "no" }
+        );
+    }
+    let _ = writeln!(out, "    {:<10} {:>9.2}s", "total", total.as_secs_f64());
+    if let Some(build_id) = packages.iter().find_map(|p| p.build_id.as_deref()) {
+        let _ = writeln!(out, "    {:<10} {build_id}", "build id");
+    }
+    out
+}
+
+/// Prints the format, build time, size and signed status of the given packages,
+/// and their build id.
+pub fn print(packages: &[PackageOutput], json: bool) -> Result<()> {
+    let packages = packages_status(packages);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&packages)?);
+    } else if !packages.is_empty() {
+        print!("{}", render_table(&packages));
+    }
+
+    Ok(())
+}
+
+/// Returns the files of a package with their SHA-256 digest, its software bill of materials first,
+/// skipping the directories like the `.app` bundles which have no digest.
+fn hashed_files(package: &PackageOutput) -> Result<Vec<(&PathBuf, String)>> {
+    let mut files = Vec::new();
+    for path in package.sbom.iter().chain(&package.paths) {
+        if path.is_file() {
+            files.push((path, checksum::sha256_file(path)?));
+        }
+    }
+    Ok(files)
+}
+
+/// Writes the `--output-json` summary of the given packages into an [`ARTIFACTS_MANIFEST_FILE_NAME`]
+/// file in `dir`, with their paths relative to `dir`, or just their file name if they are not inside `dir`,
+/// and the SHA-256 digest of their files and of their software bill of materials.
+///
+/// Returns the path of the manifest.
+pub fn write_manifest(packages: &[PackageOutput], dir: &Path) -> Result<PathBuf> {
+    let mut statuses = packages_status(packages);
+    let name = |path: &PathBuf| -> Result<String> {
+        Ok(match path.strip_prefix(dir) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .ok_or_else(|| crate::Error::FailedToExtractFilename(path.clone()))?,
+        })
+    };
+
+    for (status, package) in statuses.iter_mut().zip(packages) {
+        status.paths = package.paths.iter().map(name).collect::<Result<_>>()?;
+        status.sbom = package.sbom.as_ref().map(name).transpose()?;
+        for (path, digest) in hashed_files(package)? {
+            status.sha256.insert(name(path)?, digest);
+        }
+    }
+
+    let path = dir.join(ARTIFACTS_MANIFEST_FILE_NAME);
+    let manifest = serde_json::to_string_pretty(&statuses)?;
+    fs::write(&path, manifest).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    Ok(path)
+}
+
+/// Returns the name GitHub keeps for a release asset named `file_name`, where every run of characters
+/// other than ASCII letters, digits, `-`, `_` and `.` is replaced with a single `-`,
+/// instead of the `.` GitHub replaces them with, and the leading and trailing `.` are removed.
+fn github_asset_name(file_name: &str) -> String {
+    let mut name = String::with_capacity(file_name.len());
+    for c in file_name.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    name.trim_matches('.').to_string()
+}
+
+/// Writes a [`GITHUB_RELEASE_FILE_NAME`] markdown snippet into `dir` with a table of the files
+/// of the given packages, like the [`ARTIFACTS_MANIFEST_FILE_NAME`], listing the name of each file
+/// as a GitHub release asset with its format and SHA-256 digest, ready to paste in the release notes.
+///
+/// GitHub renames the assets with spaces or other special characters, so a file with such a name
+/// is linked, or copied, next to it under its asset name, where every run of these characters
+/// is replaced with a single `-`, and the asset name is the file to upload.
+///
+/// Returns the path of the snippet and the paths of the files created under their asset name.
+pub fn write_github_release(
+    packages: &[PackageOutput],
+    dir: &Path,
+) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let mut out = String::from("| Asset | Format | SHA-256 |\n| --- | --- | --- |\n");
+    let mut renamed = Vec::new();
+    for package in packages {
+        for (path, digest) in hashed_files(package)? {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| crate::Error::FailedToExtractFilename(path.clone()))?
+                .to_string_lossy();
+            let asset_name = github_asset_name(&file_name);
+            if asset_name != file_name {
+                let asset = path.with_file_name(&asset_name);
+                tracing::debug!("Linking {} as {}", path.display(), asset.display());
+                if asset.exists() {
+                    fs::remove_file(&asset).map_err(|e| Error::IoWithPath(asset.clone(), e))?;
+                }
+                if fs::hard_link(path, &asset).is_err() {
+                    fs::copy(path, &asset)
+                        .map_err(|e| crate::Error::CopyFile(path.clone(), asset.clone(), e))?;
+                }
+                renamed.push(asset);
+            }
+            let _ = writeln!(
+                out,
+                "| `{asset_name}` | {} | `{digest}` |",
+                package.format.short_name()
+            );
+        }
+    }
+
+    let path = dir.join(GITHUB_RELEASE_FILE_NAME);
+    fs::write(&path, out).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    Ok((path, renamed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackageFormat;
+
+    #[test]
+    fn it_summarises_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("App.app");
+        std::fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        std::fs::write(app.join("Contents/MacOS/app"), [0; 1000]).unwrap();
+        std::fs::write(app.join("Contents/Info.plist"), [0; 24]).unwrap();
+        let deb = dir.path().join("app.deb");
+        std::fs::write(&deb, vec![0; 3 * 1024 * 1024]).unwrap();
+
+        let mut app_package = PackageOutput::new(PackageFormat::App, vec![app]);
+        app_package.duration = Duration::from_millis(1500);
+        let mut deb_package = PackageOutput::new(PackageFormat::Deb, vec![deb]);
+        deb_package.duration = Duration::from_millis(250);
+        deb_package.signed = true;
+
+        let packages = packages_status(&[app_package, deb_package]);
+        assert_eq!(packages[0].size, 1024);
+        assert_eq!(packages[1].duration_ms, 250);
+
+        assert_eq!(
+            render_table(&packages),
+            "Packaging summary:
+    format           time         size  signed
+    app             1.50s      1.0 KiB  no
+    deb             0.25s      3.0 MiB  yes
+    total           1.75s
+"
+        );
+
+        let mut package = PackageOutput::new(PackageFormat::Deb, Vec::new());
+        package.build_id = Some("3f2c9a1".into());
+        assert!(render_table(&packages_status(&[package])).ends_with("    build id   3f2c9a1\n"));
+    }
+
+    #[test]
+    fn it_signs_the_artifacts_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("app.deb");
+        std::fs::write(&deb, "deb").unwrap();
+        let sbom = dir.path().join("app_1.0.0.cdx.json");
+        std::fs::write(&sbom, "{}").unwrap();
+        let mut package = PackageOutput::new(PackageFormat::Deb, vec![deb]);
+        package.signed = true;
+        package.sbom = Some(sbom.clone());
+
+        let manifest = write_manifest(&[package], dir.path()).unwrap();
+        assert_eq!(manifest, dir.path().join(ARTIFACTS_MANIFEST_FILE_NAME));
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(json[0]["paths"], serde_json::json!(["app.deb"]));
+        assert_eq!(json[0]["sbom"], "app_1.0.0.cdx.json");
+        assert_eq!(
+            json[0]["sha256"]["app_1.0.0.cdx.json"],
+            crate::checksum::sha256_file(&sbom).unwrap().as_str()
+        );
+        assert_eq!(
+            json[0]["sha256"]["app.deb"],
+            crate::checksum::sha256_file(dir.path().join("app.deb"))
+                .unwrap()
+                .as_str()
+        );
+
+        let keypair = crate::sign::generate_key(Some(String::new())).unwrap();
+        let config = crate::SigningConfig::new()
+            .private_key(keypair.sk)
+            .password("");
+        let (signature, _) = crate::sign::sign_file(&config, &manifest).unwrap();
+        assert_eq!(signature, dir.path().join("artifacts.json.sig"));
+        crate::sign::verify_file(&manifest, &keypair.pk).unwrap();
+
+        let tampered = std::fs::read_to_string(&manifest)
+            .unwrap()
+            .replace("app.deb", "evil.deb");
+        std::fs::write(&manifest, tampered).unwrap();
+        assert!(crate::sign::verify_file(&manifest, &keypair.pk).is_err());
+    }
+
+    #[test]
+    fn it_writes_the_github_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("my-app_1.0.0_amd64.deb");
+        std::fs::write(&deb, "deb").unwrap();
+        let app = dir.path().join("My App.app");
+        std::fs::create_dir_all(&app).unwrap();
+        let archive = dir.path().join("My App.app.tar.gz");
+        std::fs::write(&archive, "app").unwrap();
+        let packages = [
+            PackageOutput::new(PackageFormat::Deb, vec![deb.clone()]),
+            PackageOutput::new(PackageFormat::App, vec![app, archive.clone()]),
+        ];
+
+        let (path, renamed) = write_github_release(&packages, dir.path()).unwrap();
+        assert_eq!(path, dir.path().join(GITHUB_RELEASE_FILE_NAME));
+        assert_eq!(renamed, [dir.path().join("My-App.app.tar.gz")]);
+        assert_eq!(std::fs::read_to_string(&renamed[0]).unwrap(), "app");
+
+        let release = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            release,
+            format!(
+                "| Asset | Format | SHA-256 |
+| --- | --- | --- |
+| `my-app_1.0.0_amd64.deb` | deb | `{}` |
+| `My-App.app.tar.gz` | app | `{}` |
+",
+                crate::checksum::sha256_file(&deb).unwrap(),
+                crate::checksum::sha256_file(&archive).unwrap()
+            )
+        );
+
+        assert_eq!(github_asset_name("My App (beta).dmg"), "My-App-beta-.dmg");
+        assert_eq!(github_asset_name(".hidden+file"), "hidden-file");
+    }
+}
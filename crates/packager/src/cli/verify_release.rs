@@ -0,0 +1,45 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+
+use super::{Error, Result};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(
+    about = "Verify the published files of a release against its checksums, update manifest and signatures, without rebuilding it"
+)]
+pub struct Options {
+    /// Load the public key from a file or a string.
+    #[clap(short = 'p', long, env = "CARGO_PACKAGER_SIGN_PUBLIC_KEY")]
+    public_key: String,
+    /// The directory of the release files, for example downloaded back from the release host,
+    /// with its `SHA256SUMS` and optionally its `latest.json` and their signatures.
+    dir: PathBuf,
+}
+
+pub fn command(options: Options) -> Result<()> {
+    let public_key = if PathBuf::from(&options.public_key).exists() {
+        fs::read_to_string(&options.public_key)
+            .map_err(|e| Error::IoWithPath(PathBuf::from(&options.public_key), e))?
+    } else {
+        options.public_key
+    };
+
+    let drift = crate::release::verify_release(&options.dir, &public_key)?;
+    if !drift.is_empty() {
+        for drift in &drift {
+            tracing::error!("{drift}");
+        }
+        return Err(crate::Error::ReleaseDrift(options.dir, drift.len()).into());
+    }
+
+    tracing::info!(
+        "Verified the release {} successfully!",
+        options.dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use super::Result;
+
+#[derive(Debug, Clone, Parser)]
+#[clap(
+    about = "Generate the .zsync file of an AppImage to publish it as a delta update of a previous release"
+)]
+pub struct Options {
+    /// The AppImage of the previous release, which must embed the same update information.
+    #[clap(long)]
+    previous: PathBuf,
+    /// The URL of the AppImage written to the .zsync file, relative to the .zsync file or absolute.
+    ///
+    /// Defaults to the file name of the AppImage.
+    #[clap(long)]
+    url: Option<String>,
+    /// The AppImage of the new release, the .zsync file is written next to it.
+    appimage: PathBuf,
+}
+
+pub fn command(options: Options) -> Result<()> {
+    let zsync =
+        crate::zsync::make_zsync(&options.previous, &options.appimage, options.url.as_deref())?;
+    tracing::info!(
+        "Generated {}, at least {} of the {} blocks are reused from {}",
+        zsync.path.display(),
+        zsync.reused_blocks,
+        zsync.blocks,
+        options.previous.display()
+    );
+    Ok(())
+}
@@ -10,6 +10,7 @@ use std::{
     io::prelude::*,
     path::{Path, PathBuf},
     process::Command,
+    time::{Duration, Instant},
 };
 
 use serde::Deserialize;
@@ -169,6 +170,36 @@ impl PartialOrd for SignTarget {
 
 #[tracing::instrument(level = "trace", skip(config))]
 pub fn try_sign(targets: Vec<SignTarget>, identity: &str, config: &Config) -> crate::Result<()> {
+    if let Some(entitlements) = config.macos().and_then(|m| m.entitlements.as_ref()) {
+        let entitlements = PathBuf::from(entitlements);
+        plist::Value::from_file(&entitlements)
+            .map_err(|e| Error::InvalidEntitlements(entitlements, e))?;
+    }
+
+    let packager_keychain = setup_config_keychain(config)?;
+
+    for target in targets {
+        sign(
+            &target.path,
+            identity,
+            config,
+            target.is_native_binary,
+            packager_keychain,
+        )?;
+    }
+
+    if packager_keychain {
+        // delete the keychain again after signing
+        delete_keychain();
+    }
+
+    Ok(())
+}
+
+/// Imports the [`MacOsConfig::signing_certificate`](crate::config::MacOsConfig::signing_certificate)
+/// or the `APPLE_CERTIFICATE` certificate into the packager keychain, if any,
+/// and returns whether the keychain was created.
+fn setup_config_keychain(config: &Config) -> crate::Result<bool> {
     let certificate_encoded = config
         .macos()
         .and_then(|m| m.signing_certificate.clone())
@@ -179,33 +210,52 @@ pub fn try_sign(targets: Vec<SignTarget>, identity: &str, config: &Config) -> cr
         .and_then(|m| m.signing_certificate_password.clone())
         .or_else(|| std::env::var_os("APPLE_CERTIFICATE_PASSWORD"));
 
-    let packager_keychain = if let (Some(certificate_encoded), Some(certificate_password)) =
+    if let (Some(certificate_encoded), Some(certificate_password)) =
         (certificate_encoded, certificate_password)
     {
         // setup keychain allow you to import your certificate
         // for CI build
         setup_keychain(certificate_encoded, certificate_password)?;
-        true
+        Ok(true)
     } else {
-        false
-    };
+        Ok(false)
+    }
+}
 
-    for target in targets {
-        sign(
-            &target.path,
-            identity,
-            config,
-            target.is_native_binary,
-            packager_keychain,
-        )?;
+/// Signs the installer package `unsigned_path` with the `Developer ID Installer` `identity`
+/// using `productsign`, writing the signed package to `signed_path`.
+#[tracing::instrument(level = "trace", skip(config))]
+pub fn sign_installer(
+    unsigned_path: &Path,
+    signed_path: &Path,
+    identity: &str,
+    config: &Config,
+) -> crate::Result<()> {
+    tracing::info!(
+        "Signing {} with identity \"{}\"",
+        signed_path.display(),
+        identity
+    );
+
+    let packager_keychain = setup_config_keychain(config)?;
+
+    let mut cmd = Command::new("productsign");
+    cmd.args(["--sign", identity, "--timestamp"]);
+    if packager_keychain {
+        cmd.args(["--keychain", KEYCHAIN_ID]);
     }
+    let result = cmd
+        .arg(unsigned_path)
+        .arg(signed_path)
+        .output_ok()
+        .map_err(Error::FailedToRunProductsign);
 
     if packager_keychain {
         // delete the keychain again after signing
         delete_keychain();
     }
 
-    Ok(())
+    result.map(|_| ())
 }
 
 #[tracing::instrument(level = "trace", skip(config))]
@@ -222,6 +272,29 @@ fn sign(
         identity
     );
 
+    let macos = config.macos();
+    let entitlements = macos.and_then(|macos| macos.entitlements.as_deref());
+    let hardened_runtime = is_native_binary
+        && macos
+            .and_then(|macos| macos.hardened_runtime)
+            .unwrap_or(true);
+    let args = codesign_args(identity, packager_keychain, entitlements, hardened_runtime);
+
+    Command::new("codesign")
+        .args(args)
+        .arg(path_to_sign)
+        .output_ok()
+        .map_err(Error::FailedToRunCodesign)?;
+
+    Ok(())
+}
+
+fn codesign_args<'a>(
+    identity: &'a str,
+    packager_keychain: bool,
+    entitlements: Option<&'a str>,
+    hardened_runtime: bool,
+) -> Vec<&'a str> {
     let mut args = vec!["--force", "-s", identity];
 
     if packager_keychain {
@@ -229,89 +302,123 @@ fn sign(
         args.push(KEYCHAIN_ID);
     }
 
-    if let Some(entitlements_path) = config.macos().and_then(|macos| macos.entitlements.as_ref()) {
+    if let Some(entitlements_path) = entitlements {
         args.push("--entitlements");
         args.push(entitlements_path);
     }
 
-    if is_native_binary {
+    if hardened_runtime {
         args.push("--options");
         args.push("runtime");
     }
 
     args.push("--timestamp");
 
-    Command::new("codesign")
-        .args(args)
-        .arg(path_to_sign)
-        .output_ok()
-        .map_err(Error::FailedToRunCodesign)?;
-
-    Ok(())
+    args
 }
 
+/// The number of consecutive failures of `notarytool info` tolerated while polling.
+const NOTARIZATION_POLL_RETRIES: u32 = 3;
+
 #[derive(Deserialize, Debug)]
 struct NotarytoolSubmitOutput {
     id: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct NotarytoolInfoOutput {
+    id: String,
     status: String,
+    #[serde(default)]
     message: String,
 }
 
+/// Returns the path of the file of the scratch directory storing
+/// the id of the pending notarization submission of `app_bundle_path`.
+pub fn notarization_submission_path(
+    app_bundle_path: &Path,
+    config: &Config,
+) -> crate::Result<PathBuf> {
+    let filename = app_bundle_path
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(app_bundle_path.to_path_buf()))?;
+    Ok(config
+        .out_dir()
+        .join(".cargo-packager")
+        .join(format!("{}.notarization-id", filename.to_string_lossy())))
+}
+
+/// Returns the notarization credentials of the config,
+/// falling back to the ones defined by the environment variables.
+pub fn config_notarize_auth(config: &Config) -> crate::Result<MacOsNotarizationCredentials> {
+    config
+        .macos()
+        .and_then(|m| m.notarization_credentials.clone())
+        .ok_or(Error::MissingNotarizeAuthVars)
+        .or_else(|_| notarize_auth())
+}
+
+/// Submits the `.app` bundle or the `.pkg` installer package at `app_bundle_path` for notarization,
+/// waits for the result and staples it once accepted.
 #[tracing::instrument(level = "trace", skip(config))]
 pub fn notarize(
     app_bundle_path: PathBuf,
     auth: MacOsNotarizationCredentials,
     config: &Config,
 ) -> crate::Result<()> {
-    let bundle_stem = app_bundle_path
-        .file_stem()
-        .ok_or_else(|| Error::FailedToExtractFilename(app_bundle_path.clone()))?;
-
     let tmp_dir = tempfile::tempdir()?;
-    let zip_path = tmp_dir
-        .path()
-        .join(format!("{}.zip", bundle_stem.to_string_lossy()));
-
-    let app_bundle_path_str = app_bundle_path.to_string_lossy().to_string();
-    let zip_path_str = zip_path.to_string_lossy().to_string();
-    let zip_args = vec![
-        "-c",
-        "-k",
-        "--keepParent",
-        "--sequesterRsrc",
-        &app_bundle_path_str,
-        &zip_path_str,
-    ];
-
-    // use ditto to create a PKZip almost identical to Finder
-    // this remove almost 99% of false alarm in notarization
-    Command::new("ditto")
-        .args(zip_args)
-        .output_ok()
-        .map_err(Error::FailedToRunDitto)?;
+    // installer packages are submitted as is, app bundles are zipped first
+    let upload_path = if app_bundle_path.is_dir() {
+        let bundle_stem = app_bundle_path
+            .file_stem()
+            .ok_or_else(|| Error::FailedToExtractFilename(app_bundle_path.clone()))?;
+        let zip_path = tmp_dir
+            .path()
+            .join(format!("{}.zip", bundle_stem.to_string_lossy()));
+
+        let app_bundle_path_str = app_bundle_path.to_string_lossy().to_string();
+        let zip_path_str = zip_path.to_string_lossy().to_string();
+        let zip_args = vec![
+            "-c",
+            "-k",
+            "--keepParent",
+            "--sequesterRsrc",
+            &app_bundle_path_str,
+            &zip_path_str,
+        ];
+
+        // use ditto to create a PKZip almost identical to Finder
+        // this remove almost 99% of false alarm in notarization
+        Command::new("ditto")
+            .args(zip_args)
+            .output_ok()
+            .map_err(Error::FailedToRunDitto)?;
 
-    // sign the zip file
-    if let Some(identity) = &config
-        .macos()
-        .and_then(|macos| macos.signing_identity.as_ref())
-    {
-        try_sign(
-            vec![SignTarget {
-                path: zip_path.clone(),
-                is_native_binary: false,
-            }],
-            identity,
-            config,
-        )?;
+        // sign the zip file
+        if let Some(identity) = &config
+            .macos()
+            .and_then(|macos| macos.signing_identity.as_ref())
+        {
+            try_sign(
+                vec![SignTarget {
+                    path: zip_path.clone(),
+                    is_native_binary: false,
+                }],
+                identity,
+                config,
+            )?;
+        };
+        zip_path
+    } else {
+        app_bundle_path.clone()
     };
 
-    let zip_path_str = zip_path.to_string_lossy().to_string();
+    let upload_path_str = upload_path.to_string_lossy().to_string();
 
     let notarize_args = vec![
         "notarytool",
         "submit",
-        &zip_path_str,
-        "--wait",
+        &upload_path_str,
         "--output-format",
         "json",
     ];
@@ -329,32 +436,148 @@ pub fn notarize(
     }
 
     let output_str = String::from_utf8_lossy(&output.stdout);
-    if let Ok(submit_output) = serde_json::from_str::<NotarytoolSubmitOutput>(&output_str) {
-        let log_message = format!(
-            "Finished with status {} for id {} ({})",
-            submit_output.status, submit_output.id, submit_output.message
-        );
-        if submit_output.status == "Accepted" {
-            tracing::info!("Notarizing {}", log_message);
-            staple_app(app_bundle_path)?;
-            Ok(())
-        } else if let Ok(output) = Command::new("xcrun")
-            .args(["notarytool", "log"])
-            .arg(&submit_output.id)
-            .notarytool_args(&auth)
+    let submission = serde_json::from_str::<NotarytoolSubmitOutput>(&output_str)
+        .map_err(|_| Error::FailedToParseNotarytoolOutput(output_str.to_string()))?;
+
+    // persist the submission id so an interrupted wait can be resumed without resubmitting
+    let submission_path = notarization_submission_path(&app_bundle_path, config)?;
+    if let Some(parent) = submission_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&submission_path, &submission.id)
+        .map_err(|e| Error::IoWithPath(submission_path.clone(), e))?;
+    tracing::info!(
+        "Submitted {} for notarization with id {} (saved in {})",
+        app_bundle_path.display(),
+        submission.id,
+        submission_path.display()
+    );
+
+    wait_for_notarization(app_bundle_path, &submission.id, &auth, config)
+}
+
+/// Re-attaches to the notarization submission `submission_id` of `app_bundle_path`
+/// instead of submitting it again, and staples the app or installer package once accepted.
+///
+/// When `submission_id` is `None`, the id persisted by [`notarize`] is used.
+#[tracing::instrument(level = "trace", skip(config))]
+pub fn notarize_resume(
+    app_bundle_path: PathBuf,
+    submission_id: Option<String>,
+    auth: MacOsNotarizationCredentials,
+    config: &Config,
+) -> crate::Result<()> {
+    let submission_id = match submission_id {
+        Some(id) => id,
+        None => {
+            let submission_path = notarization_submission_path(&app_bundle_path, config)?;
+            std::fs::read_to_string(&submission_path)
+                .map_err(|_| Error::NotarizeSubmissionNotFound(submission_path))?
+                .trim()
+                .to_string()
+        }
+    };
+
+    tracing::info!(
+        "Resuming notarization of {} with id {}",
+        app_bundle_path.display(),
+        submission_id
+    );
+
+    wait_for_notarization(app_bundle_path, &submission_id, &auth, config)
+}
+
+/// Polls the status of the submission `submission_id` until it is no longer in progress,
+/// then staples the app when it was accepted.
+fn wait_for_notarization(
+    app_bundle_path: PathBuf,
+    submission_id: &str,
+    auth: &MacOsNotarizationCredentials,
+    config: &Config,
+) -> crate::Result<()> {
+    let macos = config.macos();
+    let poll_interval = Duration::from_secs(
+        macos
+            .and_then(|m| m.notarization_poll_interval)
+            .unwrap_or(30),
+    );
+    let timeout = macos
+        .and_then(|m| m.notarization_timeout)
+        .map(Duration::from_secs);
+
+    let start = Instant::now();
+    let mut failures = 0;
+    let info = loop {
+        crate::cancellation::check()?;
+
+        match Command::new("xcrun")
+            .args([
+                "notarytool",
+                "info",
+                submission_id,
+                "--output-format",
+                "json",
+            ])
+            .notarytool_args(auth)
             .output_ok()
         {
-            Err(Error::NotarizeRejected(format!(
-                "{log_message}\nLog:\n{}",
-                String::from_utf8_lossy(&output.stdout),
-            )))
-        } else {
-            Err(Error::NotarizeRejected(log_message))
+            Ok(output) => {
+                failures = 0;
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                let info = serde_json::from_str::<NotarytoolInfoOutput>(&output_str)
+                    .map_err(|_| Error::FailedToParseNotarytoolOutput(output_str.to_string()))?;
+                if info.status != "In Progress" {
+                    break info;
+                }
+                tracing::debug!("Notarization {} is still in progress", submission_id);
+            }
+            Err(e) => {
+                failures += 1;
+                if failures >= NOTARIZATION_POLL_RETRIES {
+                    return Err(Error::FailedToRunXcrun(e));
+                }
+                tracing::warn!(
+                    "Failed to check the status of the notarization {}, retrying: {}",
+                    submission_id,
+                    e
+                );
+            }
+        }
+
+        if let Some(timeout) = timeout {
+            if start.elapsed() + poll_interval > timeout {
+                return Err(Error::NotarizeTimeout(
+                    submission_id.to_string(),
+                    timeout.as_secs(),
+                ));
+            }
         }
+        std::thread::sleep(poll_interval);
+    };
+
+    // the submission is finished, there is nothing left to resume
+    let _ = std::fs::remove_file(notarization_submission_path(&app_bundle_path, config)?);
+
+    let log_message = format!(
+        "Finished with status {} for id {} ({})",
+        info.status, info.id, info.message
+    );
+    if info.status == "Accepted" {
+        tracing::info!("Notarizing {}", log_message);
+        staple_app(app_bundle_path)?;
+        Ok(())
+    } else if let Ok(output) = Command::new("xcrun")
+        .args(["notarytool", "log"])
+        .arg(&info.id)
+        .notarytool_args(auth)
+        .output_ok()
+    {
+        Err(Error::NotarizeRejected(format!(
+            "{log_message}\nLog:\n{}",
+            String::from_utf8_lossy(&output.stdout),
+        )))
     } else {
-        Err(Error::FailedToParseNotarytoolOutput(
-            output_str.into_owned(),
-        ))
+        Err(Error::NotarizeRejected(log_message))
     }
 }
 
@@ -378,6 +601,96 @@ fn staple_app(app_bundle_path: PathBuf) -> crate::Result<()> {
     Ok(())
 }
 
+/// The notarization status of an artifact, returned by [`check_notarization`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NotarizationStatus {
+    /// Notarized, with the notarization ticket stapled to the artifact so Gatekeeper verifies it offline.
+    Stapled,
+    /// Notarized, without a stapled ticket, Gatekeeper fetches it from Apple when the artifact is first opened.
+    Notarized,
+    /// Signed but rejected by Gatekeeper, like an artifact that isn't notarized
+    /// or whose signature or notarization ticket was revoked.
+    Rejected {
+        /// The `source` of the assessment, like `Unnotarized Developer ID`.
+        source: Option<String>,
+        /// The output of `spctl --assess`.
+        reason: String,
+    },
+    /// Not signed.
+    Unsigned,
+}
+
+/// Returns the `spctl --assess` arguments of the artifact at `path`,
+/// which are assessed by type: `.dmg` as opened files and `.pkg` as installers.
+fn spctl_args(path: &Path) -> Vec<&'static str> {
+    let mut args = vec!["--assess", "-vv", "--type"];
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("dmg") => args.extend(["open", "--context", "context:primary-signature"]),
+        Some("pkg") => args.push("install"),
+        _ => args.push("execute"),
+    }
+    args
+}
+
+/// Parses the output of `spctl --assess -vv`, ignoring whether the ticket is stapled.
+fn parse_assessment(accepted: bool, output: &str) -> NotarizationStatus {
+    let source = output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("source="))
+        .map(str::to_string);
+    if output.contains("no usable signature") || output.contains("not signed at all") {
+        return NotarizationStatus::Unsigned;
+    }
+    match source {
+        Some(source) if accepted && source.starts_with("Notarized") => {
+            NotarizationStatus::Notarized
+        }
+        source => NotarizationStatus::Rejected {
+            source,
+            reason: output.trim().to_string(),
+        },
+    }
+}
+
+/// Checks the notarization of the `.app`, `.dmg` or `.pkg` at `path`
+/// with `spctl --assess` and `xcrun stapler validate`.
+#[tracing::instrument(level = "trace")]
+pub fn check_notarization(path: &Path) -> crate::Result<NotarizationStatus> {
+    if !path.exists() {
+        return Err(Error::DoesNotExist(path.to_path_buf()));
+    }
+    // spctl exits with an error when the artifact is rejected, the verdict is in its output
+    let output = Command::new("spctl")
+        .args(spctl_args(path))
+        .arg(path)
+        .output()
+        .map_err(Error::FailedToRunSpctl)?;
+    let assessment = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    tracing::debug!("spctl assessment of {}: {assessment}", path.display());
+
+    let status = parse_assessment(output.status.success(), &assessment);
+    if status != NotarizationStatus::Notarized {
+        return Ok(status);
+    }
+    let stapled = Command::new("xcrun")
+        .args(["stapler", "validate", "-q"])
+        .arg(path)
+        .output()
+        .map_err(Error::FailedToRunXcrun)?
+        .status
+        .success();
+    Ok(if stapled {
+        NotarizationStatus::Stapled
+    } else {
+        NotarizationStatus::Notarized
+    })
+}
+
 pub trait NotarytoolCmdExt {
     fn notarytool_args(&mut self, auth: &MacOsNotarizationCredentials) -> &mut Self;
 }
@@ -496,3 +809,117 @@ fn find_api_key(folder: PathBuf, file_name: &OsString) -> Option<PathBuf> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_persists_notarization_submissions_in_scratch_dir() {
+        let mut config = Config::default();
+        config.out_dir = "dist".into();
+        assert_eq!(
+            notarization_submission_path(Path::new("dist/My App.app"), &config).unwrap(),
+            config
+                .out_dir()
+                .join(".cargo-packager/My App.app.notarization-id")
+        );
+
+        let info: NotarytoolInfoOutput = serde_json::from_str(
+            r#"{"id":"2efe2717","status":"In Progress","name":"My App.zip","createdDate":"2024-01-01T00:00:00.000Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(info.status, "In Progress");
+        assert!(info.message.is_empty());
+    }
+
+    #[test]
+    fn it_parses_the_gatekeeper_assessment() {
+        assert_eq!(
+            parse_assessment(
+                true,
+                "App.app: accepted\nsource=Notarized Developer ID\norigin=Developer ID Application: Me (TEAMID)\n"
+            ),
+            NotarizationStatus::Notarized
+        );
+        assert_eq!(
+            parse_assessment(
+                false,
+                "App.app: rejected\nsource=Unnotarized Developer ID\norigin=Developer ID Application: Me (TEAMID)\n"
+            ),
+            NotarizationStatus::Rejected {
+                source: Some("Unnotarized Developer ID".into()),
+                reason: "App.app: rejected\nsource=Unnotarized Developer ID\norigin=Developer ID Application: Me (TEAMID)".into(),
+            }
+        );
+        assert_eq!(
+            parse_assessment(false, "App.dmg: rejected\nsource=no usable signature\n"),
+            NotarizationStatus::Unsigned
+        );
+        assert_eq!(
+            spctl_args(Path::new("App.dmg")),
+            [
+                "--assess",
+                "-vv",
+                "--type",
+                "open",
+                "--context",
+                "context:primary-signature"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_unsigned_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("App.app");
+        std::fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        std::fs::write(app.join("Contents/MacOS/App"), "#!/bin/sh\n").unwrap();
+        std::fs::write(
+            app.join("Contents/Info.plist"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict><key>CFBundleExecutable</key><string>App</string></dict></plist>"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            check_notarization(&app).unwrap(),
+            NotarizationStatus::Unsigned | NotarizationStatus::Rejected { .. }
+        ));
+        assert!(matches!(
+            check_notarization(&dir.path().join("Missing.app")),
+            Err(Error::DoesNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn codesign_args_with_entitlements_and_hardened_runtime() {
+        assert_eq!(
+            codesign_args("Developer ID", false, Some("entitlements.plist"), true),
+            vec![
+                "--force",
+                "-s",
+                "Developer ID",
+                "--entitlements",
+                "entitlements.plist",
+                "--options",
+                "runtime",
+                "--timestamp"
+            ]
+        );
+    }
+
+    #[test]
+    fn codesign_args_without_hardened_runtime() {
+        assert_eq!(
+            codesign_args("Developer ID", true, None, false),
+            vec![
+                "--force",
+                "-s",
+                "Developer ID",
+                "--keychain",
+                KEYCHAIN_ID,
+                "--timestamp"
+            ]
+        );
+    }
+}
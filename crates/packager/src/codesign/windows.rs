@@ -3,14 +3,18 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use std::{fmt::Debug, path::Path, process::Command};
+use std::{ffi::OsString, fmt::Debug, path::Path, process::Command};
 
 #[cfg(windows)]
 use once_cell::sync::Lazy;
 #[cfg(windows)]
 use std::path::PathBuf;
 
-use crate::{config::Config, shell::CommandExt, util};
+use crate::{
+    config::{Config, WindowsHardwareKeyConfig},
+    shell::CommandExt,
+    util,
+};
 
 #[cfg(windows)]
 use crate::util::Bitness;
@@ -20,10 +24,44 @@ use crate::util::Bitness;
 pub struct SignParams {
     pub product_name: String,
     pub digest_algorithm: String,
+    /// The digest algorithms of the signatures appended after the primary one.
+    pub appended_digest_algorithms: Vec<String>,
     pub certificate_thumbprint: String,
     pub timestamp_url: Option<String>,
     pub tsp: bool,
     pub sign_command: Option<String>,
+    pub hardware_key: Option<WindowsHardwareKeyConfig>,
+    pub timestamp: bool,
+    pub timestamp_urls: Vec<String>,
+}
+
+/// A timestamp server used by `signtool.exe`.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+struct TimestampServer<'a> {
+    url: &'a str,
+    /// Whether the server uses the RFC 3161 Time-Stamp Protocol.
+    tsp: bool,
+}
+
+impl SignParams {
+    /// Returns the timestamp servers to try in order, empty if timestamping is disabled.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn timestamp_servers(&self) -> Vec<TimestampServer<'_>> {
+        if !self.timestamp {
+            return Vec::new();
+        }
+
+        self.timestamp_url
+            .iter()
+            .map(|url| TimestampServer { url, tsp: self.tsp })
+            .chain(
+                self.timestamp_urls
+                    .iter()
+                    .map(|url| TimestampServer { url, tsp: true }),
+            )
+            .collect()
+    }
 }
 
 impl Config {
@@ -31,6 +69,10 @@ impl Config {
         self.windows()
             .and_then(|w| w.certificate_thumbprint.as_ref())
             .is_some()
+            || self
+                .windows()
+                .and_then(|w| w.hardware_key.as_ref())
+                .is_some()
             || self.custom_sign_command()
     }
 
@@ -42,12 +84,17 @@ impl Config {
 
     pub(crate) fn sign_params(&self) -> SignParams {
         let windows = self.windows();
+        let mut digest_algorithms = windows
+            .and_then(|w| w.digest_algorithms.clone())
+            .unwrap_or_default()
+            .into_iter();
         SignParams {
             product_name: self.product_name.clone(),
-            digest_algorithm: windows
-                .and_then(|w| w.digest_algorithm.as_ref())
-                .cloned()
+            digest_algorithm: digest_algorithms
+                .next()
+                .or_else(|| windows.and_then(|w| w.digest_algorithm.clone()))
                 .unwrap_or_else(|| "sha256".to_string()),
+            appended_digest_algorithms: digest_algorithms.collect(),
             certificate_thumbprint: windows
                 .and_then(|w| w.certificate_thumbprint.as_ref())
                 .cloned()
@@ -55,6 +102,12 @@ impl Config {
             timestamp_url: windows.and_then(|w| w.timestamp_url.as_ref()).cloned(),
             tsp: windows.map(|w| w.tsp).unwrap_or_default(),
             sign_command: windows.and_then(|w| w.sign_command.as_ref()).cloned(),
+            hardware_key: windows.and_then(|w| w.hardware_key.as_ref()).cloned(),
+            timestamp: windows.map(|w| w.timestamp).unwrap_or(true),
+            timestamp_urls: windows
+                .and_then(|w| w.timestamp_urls.as_ref())
+                .cloned()
+                .unwrap_or_default(),
         }
     }
 }
@@ -150,34 +203,127 @@ pub fn sign_command_custom<P: AsRef<Path> + Debug>(
     Ok(cmd)
 }
 
+/// Checks that the cryptographic provider of the hardware key is registered,
+/// either as a legacy cryptographic service provider (CSP) or as a key storage provider (KSP).
 #[cfg(windows)]
-#[tracing::instrument(level = "trace")]
-pub fn sign_command_default<P: AsRef<Path> + Debug>(
+fn ensure_crypto_provider(provider: &str) -> crate::Result<()> {
+    const CSP_REGKEY_PATH: &str = r"SOFTWARE\Microsoft\Cryptography\Defaults\Provider";
+    const KSP_REGKEY_PATH: &str = r"SYSTEM\CurrentControlSet\Control\Cryptography\Providers";
+
+    let registered = [CSP_REGKEY_PATH, KSP_REGKEY_PATH].iter().any(|path| {
+        windows_registry::LOCAL_MACHINE
+            .open(format!(r"{path}\{provider}"))
+            .is_ok()
+    });
+
+    if registered {
+        Ok(())
+    } else {
+        Err(crate::Error::SignCryptoProviderNotFound(
+            provider.to_string(),
+        ))
+    }
+}
+
+/// Arguments passed to `signtool.exe`:
+///
+/// - `sign /fd <digest_algorithm>`, followed by `/as` when appending a signature.
+/// - `/f <certificate_path> /csp <provider> /kc <key_container>` when signing with a hardware key,
+///   or `/sha1 <certificate_thumbprint>` otherwise.
+/// - `/d <product_name>`
+/// - `/tr <timestamp_url> /td <digest_algorithm>` when using TSP, or `/t <timestamp_url>` otherwise,
+///   omitted when there is no timestamp server.
+/// - the path of the file to sign.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn signtool_args<P: AsRef<Path>>(
     path: P,
     params: &SignParams,
-) -> crate::Result<Command> {
-    let signtool = signtool().ok_or(crate::Error::SignToolNotFound)?;
+    digest_algorithm: &str,
+    append: bool,
+    timestamp_server: Option<&TimestampServer<'_>>,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["sign".into(), "/fd".into(), digest_algorithm.into()];
+    if append {
+        args.push("/as".into());
+    }
 
-    let mut cmd = Command::new(signtool);
-    cmd.arg("sign");
-    cmd.args(["/fd", &params.digest_algorithm]);
-    cmd.args(["/sha1", &params.certificate_thumbprint]);
-    cmd.args(["/d", &params.product_name]);
-
-    if let Some(ref timestamp_url) = params.timestamp_url {
-        if params.tsp {
-            cmd.args(["/tr", timestamp_url]);
-            cmd.args(["/td", &params.digest_algorithm]);
+    match &params.hardware_key {
+        Some(hardware_key) => {
+            args.push("/f".into());
+            args.push(hardware_key.certificate_path.clone().into());
+            args.push("/csp".into());
+            args.push((&hardware_key.provider).into());
+            args.push("/kc".into());
+            args.push((&hardware_key.key_container).into());
+        }
+        None => {
+            args.push("/sha1".into());
+            args.push((&params.certificate_thumbprint).into());
+        }
+    }
+
+    args.push("/d".into());
+    args.push((&params.product_name).into());
+
+    if let Some(server) = timestamp_server {
+        if server.tsp {
+            args.push("/tr".into());
+            args.push(server.url.into());
+            args.push("/td".into());
+            args.push(digest_algorithm.into());
         } else {
-            cmd.args(["/t", timestamp_url]);
+            args.push("/t".into());
+            args.push(server.url.into());
         }
     }
 
-    cmd.arg(path.as_ref());
+    args.push(path.as_ref().into());
+
+    args
+}
+
+#[cfg(windows)]
+fn signtool_command<P: AsRef<Path>>(
+    path: P,
+    params: &SignParams,
+    digest_algorithm: &str,
+    append: bool,
+    timestamp_server: Option<&TimestampServer<'_>>,
+) -> crate::Result<Command> {
+    let signtool = signtool().ok_or(crate::Error::SignToolNotFound)?;
+
+    if let Some(hardware_key) = &params.hardware_key {
+        ensure_crypto_provider(&hardware_key.provider)?;
+    }
+
+    let mut cmd = Command::new(signtool);
+    cmd.args(signtool_args(
+        path,
+        params,
+        digest_algorithm,
+        append,
+        timestamp_server,
+    ));
 
     Ok(cmd)
 }
 
+/// Creates the `signtool.exe` command of the primary signature using the first timestamp server.
+#[cfg(windows)]
+#[tracing::instrument(level = "trace")]
+pub fn sign_command_default<P: AsRef<Path> + Debug>(
+    path: P,
+    params: &SignParams,
+) -> crate::Result<Command> {
+    signtool_command(
+        path,
+        params,
+        &params.digest_algorithm,
+        false,
+        params.timestamp_servers().first(),
+    )
+}
+
 #[tracing::instrument(level = "trace")]
 pub fn sign_command<P: AsRef<Path> + Debug>(
     path: P,
@@ -221,19 +367,72 @@ pub fn sign_default<P: AsRef<Path> + Debug>(path: P, params: &SignParams) -> cra
     let signtool = signtool().ok_or(crate::Error::SignToolNotFound)?;
     let path = path.as_ref();
 
-    tracing::info!(
-        "Codesigning {} with certificate \"{}\"",
-        util::display_path(path),
-        params.certificate_thumbprint
-    );
+    match &params.hardware_key {
+        Some(hardware_key) => tracing::info!(
+            "Codesigning {} with key container \"{}\" from \"{}\"",
+            util::display_path(path),
+            hardware_key.key_container,
+            hardware_key.provider
+        ),
+        None => tracing::info!(
+            "Codesigning {} with certificate \"{}\"",
+            util::display_path(path),
+            params.certificate_thumbprint
+        ),
+    }
 
-    let mut cmd = sign_command_default(path, params)?;
+    if !params.timestamp {
+        tracing::warn!(
+            "Timestamping is disabled, the signature of {} will become invalid once the signing certificate expires!",
+            util::display_path(path)
+        );
+    }
+
+    let servers = params.timestamp_servers();
+    let attempts = if servers.is_empty() {
+        vec![None]
+    } else {
+        servers.iter().map(Some).collect()
+    };
 
     tracing::debug!("Running signtool {:?}", signtool);
-    let output = cmd.output_ok().map_err(crate::Error::SignToolFailed)?;
+    let digest_algorithms =
+        std::iter::once(&params.digest_algorithm).chain(&params.appended_digest_algorithms);
+    'signatures: for (i, digest_algorithm) in digest_algorithms.enumerate() {
+        if i > 0 {
+            tracing::info!(
+                "Appending a {} signature to {}",
+                digest_algorithm,
+                util::display_path(path)
+            );
+        }
 
-    let stdout = String::from_utf8_lossy(output.stdout.as_slice());
-    tracing::debug!("{:?}", stdout);
+        let mut last_error = None;
+        for server in &attempts {
+            let mut cmd = signtool_command(path, params, digest_algorithm, i > 0, *server)?;
+            match cmd.output_ok() {
+                Ok(output) => {
+                    let stdout = String::from_utf8_lossy(output.stdout.as_slice());
+                    tracing::debug!("{:?}", stdout);
+                    continue 'signatures;
+                }
+                Err(e) => {
+                    if let Some(server) = server {
+                        tracing::warn!(
+                            "Failed to sign {} using timestamp server {}: {e}",
+                            util::display_path(path),
+                            server.url
+                        );
+                    }
+                    last_error.replace(e);
+                }
+            }
+        }
+
+        return Err(crate::Error::SignToolFailed(
+            last_error.expect("signtool is run at least once"),
+        ));
+    }
 
     Ok(())
 }
@@ -261,3 +460,218 @@ pub fn try_sign(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> SignParams {
+        SignParams {
+            product_name: "App".into(),
+            digest_algorithm: "sha256".into(),
+            appended_digest_algorithms: Vec::new(),
+            certificate_thumbprint: "ABCDEF".into(),
+            timestamp_url: Some("http://timestamp.example.com".into()),
+            tsp: true,
+            sign_command: None,
+            hardware_key: None,
+            timestamp: true,
+            timestamp_urls: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn it_builds_signtool_args_with_thumbprint() {
+        assert_eq!(
+            signtool_args(
+                "app.exe",
+                &params(),
+                "sha256",
+                false,
+                params().timestamp_servers().first()
+            ),
+            [
+                "sign",
+                "/fd",
+                "sha256",
+                "/sha1",
+                "ABCDEF",
+                "/d",
+                "App",
+                "/tr",
+                "http://timestamp.example.com",
+                "/td",
+                "sha256",
+                "app.exe"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_builds_signtool_args_with_hardware_key() {
+        let params = SignParams {
+            tsp: false,
+            hardware_key: Some(WindowsHardwareKeyConfig::new(
+                "cert.cer",
+                "eToken Base Cryptographic Provider",
+                "[{{pin}}]=container",
+            )),
+            ..params()
+        };
+        assert_eq!(
+            signtool_args(
+                "app.exe",
+                &params,
+                "sha256",
+                false,
+                params.timestamp_servers().first()
+            ),
+            [
+                "sign",
+                "/fd",
+                "sha256",
+                "/f",
+                "cert.cer",
+                "/csp",
+                "eToken Base Cryptographic Provider",
+                "/kc",
+                "[{{pin}}]=container",
+                "/d",
+                "App",
+                "/t",
+                "http://timestamp.example.com",
+                "app.exe"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_fails_over_timestamp_servers() {
+        let params = SignParams {
+            timestamp_urls: vec![
+                "http://tsa1.example.com".into(),
+                "http://tsa2.example.com".into(),
+            ],
+            ..params()
+        };
+        assert_eq!(
+            params.timestamp_servers(),
+            [
+                TimestampServer {
+                    url: "http://timestamp.example.com",
+                    tsp: true
+                },
+                TimestampServer {
+                    url: "http://tsa1.example.com",
+                    tsp: true
+                },
+                TimestampServer {
+                    url: "http://tsa2.example.com",
+                    tsp: true
+                },
+            ]
+        );
+        assert_eq!(
+            signtool_args(
+                "app.exe",
+                &params,
+                "sha256",
+                false,
+                params.timestamp_servers().get(2)
+            )[7..],
+            ["/tr", "http://tsa2.example.com", "/td", "sha256", "app.exe"]
+        );
+    }
+
+    #[test]
+    fn it_builds_signtool_args_without_timestamp() {
+        let params = SignParams {
+            timestamp: false,
+            timestamp_urls: vec!["http://tsa1.example.com".into()],
+            ..params()
+        };
+        assert!(params.timestamp_servers().is_empty());
+        assert_eq!(
+            signtool_args(
+                "app.exe",
+                &params,
+                "sha256",
+                false,
+                params.timestamp_servers().first()
+            ),
+            ["sign", "/fd", "sha256", "/sha1", "ABCDEF", "/d", "App", "app.exe"]
+        );
+    }
+
+    #[test]
+    fn it_builds_signtool_args_for_dual_signing() {
+        let mut config = Config::default();
+        config.windows = Some(
+            crate::config::WindowsConfig::new()
+                .digest_algorithm("sha512")
+                .digest_algorithms(["sha256", "sha1"]),
+        );
+        let config_params = config.sign_params();
+        assert_eq!(config_params.digest_algorithm, "sha256");
+        assert_eq!(config_params.appended_digest_algorithms, ["sha1"]);
+
+        let params = SignParams {
+            appended_digest_algorithms: config_params.appended_digest_algorithms,
+            ..params()
+        };
+        assert_eq!(
+            signtool_args(
+                "app.exe",
+                &params,
+                "sha1",
+                true,
+                params.timestamp_servers().first()
+            ),
+            [
+                "sign",
+                "/fd",
+                "sha1",
+                "/as",
+                "/sha1",
+                "ABCDEF",
+                "/d",
+                "App",
+                "/tr",
+                "http://timestamp.example.com",
+                "/td",
+                "sha1",
+                "app.exe"
+            ]
+        );
+    }
+
+    /// Requires a code signing certificate in the certificate store,
+    /// with its thumbprint in the `CARGO_PACKAGER_TEST_CERTIFICATE_THUMBPRINT` environment variable.
+    #[cfg(windows)]
+    #[test]
+    #[ignore = "requires a code signing certificate"]
+    fn it_dual_signs_with_signtool() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("app.exe");
+        std::fs::copy(std::env::current_exe().unwrap(), &exe).unwrap();
+
+        let params = SignParams {
+            certificate_thumbprint: std::env::var("CARGO_PACKAGER_TEST_CERTIFICATE_THUMBPRINT")
+                .unwrap(),
+            appended_digest_algorithms: vec!["sha1".into()],
+            timestamp: false,
+            ..params()
+        };
+        sign_default(&exe, &params).unwrap();
+
+        let output = Command::new(signtool().unwrap())
+            .args(["verify", "/pa", "/all", "/v"])
+            .arg(&exe)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.matches("Signature Index:").count(), 2, "{stdout}");
+        assert!(stdout.contains("Hash of file (sha256)"), "{stdout}");
+        assert!(stdout.contains("Hash of file (sha1)"), "{stdout}");
+    }
+}
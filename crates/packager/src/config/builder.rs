@@ -1,10 +1,12 @@
 use std::path::PathBuf;
 
-use crate::{Config, PackageFormat};
+use crate::{Config, PackageFormat, SigningConfig};
 
 use super::{
-    AppImageConfig, Binary, DebianConfig, FileAssociation, HookCommand, LogLevel, MacOsConfig,
-    NsisConfig, PacmanConfig, Resource, WindowsConfig, WixConfig,
+    AppCategory, AppImageConfig, Binary, BinaryVersionCheck, DebianConfig, DmgConfig,
+    FileAssociation, Flavor, HookCommand, InnoSetupConfig, LauncherShim, LogLevel, MacOsConfig,
+    NsisConfig, OutputLayout, PacmanConfig, PkgConfig, ReleaseNotes, Resource, SharConfig,
+    SharedLibrariesCheck, StagingDir, ToolPin, WindowsConfig, WixConfig, ZipConfig,
 };
 
 /// A builder type for [`Config`].
@@ -28,12 +30,24 @@ impl ConfigBuilder {
         self
     }
 
+    /// Sets [`Config::slug`].
+    pub fn slug<S: Into<String>>(mut self, slug: S) -> Self {
+        self.0.slug.replace(slug.into());
+        self
+    }
+
     /// Sets [`Config::version`].
     pub fn version<S: Into<String>>(mut self, version: S) -> Self {
         self.0.version = version.into();
         self
     }
 
+    /// Sets [`Config::build_id`].
+    pub fn build_id<S: Into<String>>(mut self, build_id: S) -> Self {
+        self.0.build_id.replace(build_id.into());
+        self
+    }
+
     /// Sets [`Config::binaries`].
     pub fn binaries<I: IntoIterator<Item = Binary>>(mut self, binaries: I) -> Self {
         self.0.binaries = binaries.into_iter().collect();
@@ -70,12 +84,127 @@ impl ConfigBuilder {
         self
     }
 
+    /// Sets [`Config::format_order`].
+    pub fn format_order<I: IntoIterator<Item = PackageFormat>>(mut self, format_order: I) -> Self {
+        self.0.format_order = Some(format_order.into_iter().collect());
+        self
+    }
+
+    /// Sets [`Config::skip_unsupported_formats`].
+    pub fn skip_unsupported_formats(mut self, skip: bool) -> Self {
+        self.0.skip_unsupported_formats = skip;
+        self
+    }
+
+    /// Sets [`Config::max_artifact_size`].
+    pub fn max_artifact_size(mut self, size: u64) -> Self {
+        self.0.max_artifact_size.replace(size);
+        self
+    }
+
+    /// Sets [`Config::max_artifact_size_per_format`].
+    pub fn max_artifact_size_per_format<I: IntoIterator<Item = (PackageFormat, u64)>>(
+        mut self,
+        sizes: I,
+    ) -> Self {
+        self.0
+            .max_artifact_size_per_format
+            .replace(sizes.into_iter().collect());
+        self
+    }
+
+    /// Sets [`Config::follow_symlinks`].
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.0.follow_symlinks.replace(follow);
+        self
+    }
+
+    /// Sets [`Config::follow_symlinks_per_format`].
+    pub fn follow_symlinks_per_format<I: IntoIterator<Item = (PackageFormat, bool)>>(
+        mut self,
+        follow: I,
+    ) -> Self {
+        self.0
+            .follow_symlinks_per_format
+            .replace(follow.into_iter().collect());
+        self
+    }
+
+    /// Sets [`Config::validate_artifacts`].
+    pub fn validate_artifacts(mut self, validate: bool) -> Self {
+        self.0.validate_artifacts.replace(validate);
+        self
+    }
+
+    /// Sets [`Config::shared_libraries_check`].
+    pub fn shared_libraries_check(mut self, check: SharedLibrariesCheck) -> Self {
+        self.0.shared_libraries_check.replace(check);
+        self
+    }
+
+    /// Sets [`Config::allowed_shared_libraries`].
+    pub fn allowed_shared_libraries<I, S>(mut self, libraries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.0
+            .allowed_shared_libraries
+            .replace(libraries.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::binary_version_check`].
+    pub fn binary_version_check(mut self, check: BinaryVersionCheck) -> Self {
+        self.0.binary_version_check.replace(check);
+        self
+    }
+
+    /// Sets [`Config::pinned_tools`].
+    pub fn pinned_tools<I, S>(mut self, pinned_tools: I) -> Self
+    where
+        I: IntoIterator<Item = (S, ToolPin)>,
+        S: Into<String>,
+    {
+        self.0.pinned_tools.replace(
+            pinned_tools
+                .into_iter()
+                .map(|(name, pin)| (name.into(), pin))
+                .collect(),
+        );
+        self
+    }
+
+    /// Sets [`Config::project_root`].
+    pub fn project_root<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.0.project_root.replace(path.into());
+        self
+    }
+
     /// Sets [`Config::out_dir`].
     pub fn out_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
         self.0.out_dir = path.into();
         self
     }
 
+    /// Sets [`Config::output_layout`].
+    pub fn output_layout(mut self, layout: OutputLayout) -> Self {
+        self.0.output_layout.replace(layout);
+        self
+    }
+
+    /// Sets [`Config::clean_outputs`].
+    pub fn clean_outputs(mut self, clean: bool) -> Self {
+        self.0.clean_outputs = clean;
+        self
+    }
+
+    /// Sets [`Config::fail_fast`].
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.0.fail_fast = fail_fast;
+        self
+    }
+
     /// Sets [`Config::target_triple`].
     pub fn target_triple<S: Into<String>>(mut self, target_triple: S) -> Self {
         self.0.target_triple.replace(target_triple.into());
@@ -130,6 +259,24 @@ impl ConfigBuilder {
         self
     }
 
+    /// Sets [`Config::category`].
+    pub fn category(mut self, category: AppCategory) -> Self {
+        self.0.category.replace(category);
+        self
+    }
+
+    /// Sets [`Config::keywords`].
+    pub fn keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.0
+            .keywords
+            .replace(keywords.into_iter().map(Into::into).collect());
+        self
+    }
+
     /// Sets [`Config::icons`].
     pub fn icons<I, S>(mut self, icons: I) -> Self
     where
@@ -159,6 +306,54 @@ impl ConfigBuilder {
         self
     }
 
+    /// Sets [`Config::resources_ignore`].
+    pub fn resources_ignore<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.0
+            .resources_ignore
+            .replace(patterns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::include_packaging_outputs`].
+    pub fn include_packaging_outputs(mut self, include_packaging_outputs: bool) -> Self {
+        self.0.include_packaging_outputs = include_packaging_outputs;
+        self
+    }
+
+    /// Sets [`Config::from_staging_dir`].
+    pub fn from_staging_dir(mut self, staging_dir: StagingDir) -> Self {
+        self.0.from_staging_dir.replace(staging_dir);
+        self
+    }
+
+    /// Sets [`Config::bundled_runtime`].
+    pub fn bundled_runtime<P: Into<PathBuf>>(mut self, bundled_runtime: P) -> Self {
+        self.0.bundled_runtime.replace(bundled_runtime.into());
+        self
+    }
+
+    /// Sets [`Config::launcher_shim`].
+    pub fn launcher_shim(mut self, launcher_shim: LauncherShim) -> Self {
+        self.0.launcher_shim.replace(launcher_shim);
+        self
+    }
+
+    /// Sets [`Config::release_notes`].
+    pub fn release_notes(mut self, release_notes: ReleaseNotes) -> Self {
+        self.0.release_notes.replace(release_notes);
+        self
+    }
+
+    /// Sets [`Config::updater_endpoint`].
+    pub fn updater_endpoint<S: Into<String>>(mut self, updater_endpoint: S) -> Self {
+        self.0.updater_endpoint.replace(updater_endpoint.into());
+        self
+    }
+
     /// Sets [`Config::external_binaries`].
     pub fn external_binaries<I, P>(mut self, external_binaries: I) -> Self
     where
@@ -171,6 +366,24 @@ impl ConfigBuilder {
         self
     }
 
+    /// Sets [`Config::preserve_xattrs`].
+    pub fn preserve_xattrs(mut self, preserve: bool) -> Self {
+        self.0.preserve_xattrs = preserve;
+        self
+    }
+
+    /// Sets [`Config::default_file_mode`].
+    pub fn default_file_mode<S: Into<String>>(mut self, mode: S) -> Self {
+        self.0.default_file_mode.replace(mode.into());
+        self
+    }
+
+    /// Sets [`Config::default_dir_mode`].
+    pub fn default_dir_mode<S: Into<String>>(mut self, mode: S) -> Self {
+        self.0.default_dir_mode.replace(mode.into());
+        self
+    }
+
     /// Set the [Windows](Config::windows) specific configuration.
     pub fn windows(mut self, windows: WindowsConfig) -> Self {
         self.0.windows.replace(windows);
@@ -183,6 +396,12 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set the [DMG](Config::dmg) specific configuration.
+    pub fn dmg(mut self, dmg: DmgConfig) -> Self {
+        self.0.dmg.replace(dmg);
+        self
+    }
+
     /// Set the [WiX](Config::wix) specific configuration.
     pub fn wix(mut self, wix: WixConfig) -> Self {
         self.0.wix.replace(wix);
@@ -195,6 +414,12 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set the [Inno Setup](Config::inno_setup) specific configuration.
+    pub fn inno_setup(mut self, inno_setup: InnoSetupConfig) -> Self {
+        self.0.inno_setup.replace(inno_setup);
+        self
+    }
+
     /// Set the [Debian](Config::deb) specific configuration.
     pub fn deb(mut self, deb: DebianConfig) -> Self {
         self.0.deb.replace(deb);
@@ -212,4 +437,46 @@ impl ConfigBuilder {
         self.0.pacman.replace(pacman);
         self
     }
+
+    /// Set the [Shar](Config::shar) specific configuration.
+    pub fn shar(mut self, shar: SharConfig) -> Self {
+        self.0.shar.replace(shar);
+        self
+    }
+
+    /// Set the [Zip](Config::zip) specific configuration.
+    pub fn zip(mut self, zip: ZipConfig) -> Self {
+        self.0.zip.replace(zip);
+        self
+    }
+
+    /// Set the [Pkg](Config::pkg) specific configuration.
+    pub fn pkg(mut self, pkg: PkgConfig) -> Self {
+        self.0.pkg.replace(pkg);
+        self
+    }
+
+    /// Set the [flavors](Config::flavors) of the app.
+    pub fn flavors<I, S>(mut self, flavors: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Flavor)>,
+        S: Into<String>,
+    {
+        self.0
+            .flavors
+            .replace(flavors.into_iter().map(|(k, v)| (k.into(), v)).collect());
+        self
+    }
+
+    /// Set the [signing](Config::signing) configuration.
+    pub fn signing(mut self, signing: SigningConfig) -> Self {
+        self.0.signing.replace(signing);
+        self
+    }
+
+    /// Sets [`Config::sbom`].
+    pub fn sbom(mut self, sbom: crate::sbom::SbomConfig) -> Self {
+        self.0.sbom.replace(sbom);
+        self
+    }
 }
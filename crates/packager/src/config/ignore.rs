@@ -0,0 +1,184 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Gitignore-style patterns excluding files from the resources.
+
+use std::{
+    fs,
+    path::{Component, Path},
+};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::Error;
+
+/// The name of the file listing the ignore patterns of a resource directory.
+pub(crate) const IGNORE_FILE_NAME: &str = ".packagerignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone)]
+struct IgnorePattern {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    /// Whether the pattern is matched against the whole relative path instead of the file name.
+    anchored: bool,
+}
+
+/// A list of gitignore-style patterns, where later patterns take precedence.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IgnorePatterns(Vec<IgnorePattern>);
+
+impl IgnorePatterns {
+    /// Parses gitignore-style patterns, skipping blank lines and `#` comments.
+    pub(crate) fn parse<I, S>(patterns: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut out = Vec::new();
+        for line in patterns {
+            let line = line.as_ref().trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern),
+            };
+            let anchored = pattern.contains('/');
+            let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+            out.push(IgnorePattern {
+                pattern: Pattern::new(pattern)
+                    .map_err(|e| Error::InvalidIgnorePattern(line.to_string(), e.msg.into()))?,
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+        Ok(Self(out))
+    }
+
+    /// Reads the patterns of the [`IGNORE_FILE_NAME`] file in `dir`, if it exists.
+    pub(crate) fn from_dir(dir: &Path) -> crate::Result<Self> {
+        let path = dir.join(IGNORE_FILE_NAME);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| Error::IoWithPath(path, e))?;
+        Self::parse(content.lines())
+    }
+
+    /// Appends the patterns of `other`, taking precedence over these patterns.
+    pub(crate) fn extend(&mut self, other: &Self) {
+        self.0.extend(other.0.iter().cloned());
+    }
+
+    fn matches(&self, path: &str, name: &str, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for p in &self.0 {
+            if p.dir_only && !is_dir {
+                continue;
+            }
+            let candidate = if p.anchored { path } else { name };
+            if p.pattern.matches_with(candidate, MATCH_OPTIONS) {
+                ignored = !p.negated;
+            }
+        }
+        ignored
+    }
+
+    /// Whether the file at `relative` is ignored.
+    ///
+    /// As in gitignore, a file is ignored when one of its parent directories is ignored,
+    /// and negated patterns can't include it again.
+    pub(crate) fn is_ignored(&self, relative: &Path) -> bool {
+        if self.0.is_empty() {
+            return false;
+        }
+
+        let components = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(c) => Some(c.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let mut path = String::new();
+        for (i, name) in components.iter().enumerate() {
+            if i > 0 {
+                path.push('/');
+            }
+            path.push_str(name);
+            if self.matches(&path, name, i + 1 < components.len()) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_matches_gitignore_patterns() {
+        let patterns = IgnorePatterns::parse([
+            "# editor files",
+            "*~",
+            ".DS_Store",
+            "",
+            "/build/",
+            "docs/**/*.tmp",
+            "*.log",
+            "!keep.log",
+            "cache/",
+            "!cache/kept",
+        ])
+        .unwrap();
+
+        for ignored in [
+            "notes.txt~",
+            ".DS_Store",
+            "images/.DS_Store",
+            "build/out.bin",
+            "docs/a/b/c.tmp",
+            "logs/debug.log",
+            "cache/data",
+            "sub/cache/data",
+            // a negated pattern can't include a file of an ignored directory
+            "cache/kept",
+        ] {
+            assert!(patterns.is_ignored(Path::new(ignored)), "{ignored}");
+        }
+        for kept in [
+            "notes.txt",
+            "sub/build/out.bin",
+            "build",
+            "other/docs/a.tmp",
+            "keep.log",
+            "logs/keep.log",
+            "cache",
+        ] {
+            assert!(!patterns.is_ignored(Path::new(kept)), "{kept}");
+        }
+
+        assert!(matches!(
+            IgnorePatterns::parse(["a**b/"]),
+            Err(Error::InvalidIgnorePattern(..))
+        ));
+    }
+}
@@ -8,7 +8,7 @@ use std::{
     collections::HashMap,
     ffi::OsString,
     fmt::{self, Display},
-    fs,
+    fs, io,
     path::{Path, PathBuf},
 };
 
@@ -16,10 +16,13 @@ use relative_path::PathExt;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::{util, Error};
+use crate::{util, Error, SigningConfig};
 
 mod builder;
 mod category;
+mod ignore;
+
+use self::ignore::IgnorePatterns;
 
 pub use builder::*;
 pub use category::AppCategory;
@@ -173,6 +176,136 @@ impl DeepLinkProtocol {
     }
 }
 
+/// The owner of the files of a Debian package matching a glob pattern.
+///
+/// dpkg sets the owner by name when the user or group exists when the package is unpacked,
+/// and by numeric id otherwise, so the user and group should be created by a `preinst`
+/// maintainer script, or exist on the target systems. The other files are owned by `root`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct DebianFileOwner {
+    /// The glob pattern matched against the installed paths of the files and directories,
+    /// like `/var/lib/my-app/*`, where `*` also matches the `/` separator of the directories.
+    pub pattern: String,
+    /// The name of the user owning the matching files, defaults to `root`.
+    pub user: Option<String>,
+    /// The numeric id of the user, used when the user doesn't exist. Defaults to `0`.
+    pub uid: Option<u64>,
+    /// The name of the group owning the matching files, defaults to `root`.
+    pub group: Option<String>,
+    /// The numeric id of the group, used when the group doesn't exist. Defaults to `0`.
+    pub gid: Option<u64>,
+}
+
+impl DebianFileOwner {
+    /// Creates a new [`DebianFileOwner`] of the files matching `pattern`.
+    pub fn new<S: Into<String>>(pattern: S) -> Self {
+        Self {
+            pattern: pattern.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the name of the user owning the matching files.
+    pub fn user<S: Into<String>>(mut self, user: S) -> Self {
+        self.user.replace(user.into());
+        self
+    }
+
+    /// Set the numeric id of the user, used when the user doesn't exist.
+    pub fn uid(mut self, uid: u64) -> Self {
+        self.uid.replace(uid);
+        self
+    }
+
+    /// Set the name of the group owning the matching files.
+    pub fn group<S: Into<String>>(mut self, group: S) -> Self {
+        self.group.replace(group.into());
+        self
+    }
+
+    /// Set the numeric id of the group, used when the group doesn't exist.
+    pub fn gid(mut self, gid: u64) -> Self {
+        self.gid.replace(gid);
+        self
+    }
+}
+
+/// A companion Debian package holding the resources of the app, see [`DebianConfig::data_package`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct DebianDataPackage {
+    /// The version of the data package, which the main package depends on.
+    ///
+    /// Bump it only when the resources change so apt doesn't download them again
+    /// on updates of the app. Defaults to [`Config::version`].
+    pub version: Option<String>,
+    /// Whether the resources don't depend on the architecture,
+    /// building an `all` architecture package shared by the packages of every architecture.
+    #[serde(default, alias = "arch-independent", alias = "arch_independent")]
+    pub arch_independent: bool,
+}
+
+impl DebianDataPackage {
+    /// Creates a new [`DebianDataPackage`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the version of the data package.
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.version.replace(version.into());
+        self
+    }
+
+    /// Set whether the resources don't depend on the architecture.
+    pub fn arch_independent(mut self, arch_independent: bool) -> Self {
+        self.arch_independent = arch_independent;
+        self
+    }
+}
+
+/// The check of the dependencies of a Debian package, see [`DebianConfig::dependency_check`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct DebianDependencyCheck {
+    /// A Debian `Packages` index listing the available packages, like the `*_Packages` files
+    /// of `/var/lib/apt/lists` or the output of `apt-cache dumpavail`.
+    ///
+    /// Defaults to the output of `apt-cache dumpavail`, the packages of the apt repositories configured on the build machine.
+    #[serde(alias = "package-list", alias = "package_list")]
+    pub package_list: Option<PathBuf>,
+    /// Fail the packaging with [`Error::UnsatisfiableDependency`](crate::Error::UnsatisfiableDependency)
+    /// instead of logging a warning for each unsatisfiable dependency.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl DebianDependencyCheck {
+    /// Creates a new [`DebianDependencyCheck`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the Debian `Packages` index listing the available packages.
+    pub fn package_list<P: Into<PathBuf>>(mut self, package_list: P) -> Self {
+        self.package_list.replace(package_list.into());
+        self
+    }
+
+    /// Set whether an unsatisfiable dependency fails the packaging.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
 /// The Linux Debian configuration.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -183,12 +316,17 @@ pub struct DebianConfig {
     pub depends: Option<Dependencies>,
     /// Path to a custom desktop file Handlebars template.
     ///
-    /// Available variables: `categories`, `comment` (optional), `exec`, `icon` and `name`.
+    /// Available variables: `categories`, `keywords` (optional), `comment` (optional), `exec`, `icon`, `name`,
+    /// `mime_type` (optional), `startup_wm_class` (optional), `startup_notify` (optional)
+    /// and `extra_entries`.
     ///
     /// Default file contents:
     /// ```text
     /// [Desktop Entry]
     /// Categories={{categories}}
+    /// {{#if keywords}}
+    /// Keywords={{keywords}}
+    /// {{/if}}
     /// {{#if comment}}
     /// Comment={{comment}}
     /// {{/if}}
@@ -200,6 +338,15 @@ pub struct DebianConfig {
     /// {{#if mime_type}}
     /// MimeType={{mime_type}}
     /// {{/if}}
+    /// {{#if startup_wm_class}}
+    /// StartupWMClass={{startup_wm_class}}
+    /// {{/if}}
+    /// {{#if startup_notify}}
+    /// StartupNotify={{startup_notify}}
+    /// {{/if}}
+    /// {{#each extra_entries}}
+    /// {{@key}}={{this}}
+    /// {{/each}}
     /// ```
     ///
     /// The `{{exec_arg}}` will be set to:
@@ -228,10 +375,134 @@ pub struct DebianConfig {
     /// List of custom files to add to the deb package.
     /// Maps a dir/file to a dir/file inside the debian package.
     pub files: Option<HashMap<String, String>>,
-    /// Name to use for the `Package` field in the Debian Control file.
-    /// Defaults to [`Config::product_name`] converted to kebab-case.
+    /// Name to use for the `Package` field in the Debian Control file and the name of the `.deb` file.
+    ///
+    /// It is used verbatim and must match `[a-z0-9][a-z0-9+.-]+` as required by the Debian policy,
+    /// see <https://www.debian.org/doc/debian-policy/ch-controlfields.html#source>
+    ///
+    /// Defaults to [`Config::product_name`] converted to kebab-case for the `Package` field
+    /// and to the [`Config::slug`] for the file name.
     #[serde(alias = "package-name", alias = "package_name")]
     pub package_name: Option<String>,
+    /// The list of Debian dependencies that must be installed and configured
+    /// before this package is unpacked, written to the `Pre-Depends` field.
+    ///
+    /// See <https://www.debian.org/doc/debian-policy/ch-relationships.html#binary-dependencies-depends-recommends-suggests-enhances-pre-depends>
+    #[serde(alias = "pre-depends", alias = "pre_depends")]
+    pub pre_depends: Option<Dependencies>,
+    /// The packages this package replaces files of, written to the `Replaces` field,
+    /// like the previous name of a renamed package, with an optional version constraint: `oldapp (<< 2.0.0)`.
+    ///
+    /// See <https://www.debian.org/doc/debian-policy/ch-relationships.html#overwriting-files-and-replacing-packages-replaces>
+    pub replaces: Option<Vec<String>>,
+    /// The virtual packages this package provides, written to the `Provides` field.
+    ///
+    /// See <https://www.debian.org/doc/debian-policy/ch-relationships.html#virtual-packages-provides>
+    pub provides: Option<Vec<String>>,
+    /// The packages that can't be installed alongside this package, written to the `Conflicts` field.
+    ///
+    /// Together with [`DebianConfig::replaces`] and [`DebianConfig::provides`],
+    /// it lets a renamed package take over from its previous name on upgrade.
+    ///
+    /// See <https://www.debian.org/doc/debian-policy/ch-relationships.html#conflicting-binary-packages-conflicts>
+    pub conflicts: Option<Vec<String>>,
+    /// The changelog of the package, installed gzipped at `usr/share/doc/<package>/changelog.Debian.gz`.
+    ///
+    /// See <https://www.debian.org/doc/debian-policy/ch-source.html#debian-changelog-debian-changelog>
+    pub changelog: Option<DebianChangelog>,
+    /// The `Maintainer` field of the Debian Control file, in the `Name <email>` format.
+    ///
+    /// Defaults to [`Config::authors`], or to [`Config::publisher`](Config::publisher()) when no author is set.
+    pub maintainer: Option<String>,
+    /// The `Homepage` field of the Debian Control file, an http or https URL.
+    ///
+    /// Defaults to [`Config::homepage`] if it is an http or https URL.
+    pub homepage: Option<String>,
+    /// The `Vcs-Browser` field of the Debian Control file,
+    /// the http or https URL of a web interface to browse the repository of the app.
+    #[serde(alias = "vcs-browser", alias = "vcs_browser")]
+    pub vcs_browser: Option<String>,
+    /// The `Vcs-Git` field of the Debian Control file, the URL of the Git repository of the app,
+    /// optionally followed by `-b <branch>`, like `https://github.com/example/app.git -b main`.
+    ///
+    /// See <https://www.debian.org/doc/debian-policy/ch-controlfields.html#version-control-system-vcs-fields>
+    #[serde(alias = "vcs-git", alias = "vcs_git")]
+    pub vcs_git: Option<String>,
+    /// The `Bugs` field of the Debian Control file, where to report the bugs of the package,
+    /// an http, https, `mailto:` or `debbugs://` URL.
+    pub bugs: Option<String>,
+    /// Custom fields appended to the Debian Control file, like `X-MyOrg-Build-Id`.
+    ///
+    /// The field names must start with `X-` and the values must be a single line.
+    /// Standard fields, like `Recommends`, are rejected unless [`DebianConfig::allow_standard_fields`] is set.
+    #[serde(alias = "custom-fields", alias = "custom_fields")]
+    pub custom_fields: Option<HashMap<String, String>>,
+    /// Whether [`DebianConfig::custom_fields`] can set the standard fields of the Debian Control file,
+    /// replacing the generated values.
+    #[serde(
+        default,
+        alias = "allow-standard-fields",
+        alias = "allow_standard_fields"
+    )]
+    pub allow_standard_fields: bool,
+    /// The owners of the files matching glob patterns, see [`DebianFileOwner`].
+    ///
+    /// The first matching pattern applies, use `*` to set the owner of every file.
+    #[serde(alias = "file-owners", alias = "file_owners")]
+    pub file_owners: Option<Vec<DebianFileOwner>>,
+    /// Check that the [`DebianConfig::depends`] and [`DebianConfig::pre_depends`] are satisfiable
+    /// by the available packages before building the package, catching the typos in the package names
+    /// and the versions that don't exist.
+    ///
+    /// It is opt-in as it needs the package list of the target distribution.
+    #[serde(alias = "dependency-check", alias = "dependency_check")]
+    pub dependency_check: Option<DebianDependencyCheck>,
+    /// Split the resources and the bundled runtime, installed at `/usr/lib/<main-binary-name>`,
+    /// into a `<package>-data` package that the main package depends on,
+    /// so the large resources that rarely change aren't downloaded again on every update of the app.
+    ///
+    /// The data package is written next to the main package, as `<name>-data_<version>_<arch>.deb`.
+    #[serde(alias = "data-package", alias = "data_package")]
+    pub data_package: Option<DebianDataPackage>,
+    /// Whether to build byte-identical packages from the same files: the entries of the `data.tar`
+    /// and `control.tar` are sorted by path, owned by `root` unless [`DebianConfig::file_owners`] say otherwise,
+    /// and modified at the `SOURCE_DATE_EPOCH`, or `0` when it is not set, like the members of the `.deb` archive.
+    #[serde(default)]
+    pub reproducible: bool,
+    /// The compression of the `control.tar` member of the `.deb` archive, defaults to [`DebianCompression::Gzip`].
+    #[serde(alias = "control-compression", alias = "control_compression")]
+    pub control_compression: Option<DebianCompression>,
+    /// The compression level of the `control.tar` member, from `0` to `9`, or from `1` to `19` with
+    /// [`DebianCompression::Zstd`]. Defaults to the default level of the compression.
+    #[serde(
+        alias = "control-compression-level",
+        alias = "control_compression_level"
+    )]
+    pub control_compression_level: Option<u32>,
+    /// The compression of the `data.tar` member of the `.deb` archive, defaults to [`DebianCompression::Gzip`].
+    #[serde(alias = "data-compression", alias = "data_compression")]
+    pub data_compression: Option<DebianCompression>,
+    /// The compression level of the `data.tar` member, from `0` to `9`, or from `1` to `19` with
+    /// [`DebianCompression::Zstd`]. Defaults to the default level of the compression.
+    #[serde(alias = "data-compression-level", alias = "data_compression_level")]
+    pub data_compression_level: Option<u32>,
+    /// The oldest dpkg version the packages must install with, like `1.19.7` for Debian 10,
+    /// rejecting the [`DebianConfig::control_compression`] and [`DebianConfig::data_compression`]
+    /// it can't extract.
+    ///
+    /// The `control.tar` can only be uncompressed or compressed with xz since dpkg 1.17.6,
+    /// the `data.tar` can be compressed with xz since dpkg 1.15.6,
+    /// and both can be compressed with Zstandard since dpkg 1.21.18.
+    #[serde(alias = "min-dpkg-version", alias = "min_dpkg_version")]
+    pub min_dpkg_version: Option<String>,
+    /// Whether to generate the `postinst` and `postrm` maintainer scripts that run
+    /// `update-desktop-database` and `gtk-update-icon-cache` after the package is installed
+    /// and removed, so the desktop entry and icons show up without logging out.
+    /// The commands are skipped when the tools aren't installed.
+    ///
+    /// Defaults to `true`.
+    #[serde(alias = "refresh-desktop-caches", alias = "refresh_desktop_caches")]
+    pub refresh_desktop_caches: Option<bool>,
 }
 
 impl DebianConfig {
@@ -262,9 +533,167 @@ impl DebianConfig {
         self
     }
 
+    /// Set the name to use for the `Package` field in the Debian Control file and the name of the `.deb` file.
+    pub fn package_name<S: Into<String>>(mut self, package_name: S) -> Self {
+        self.package_name.replace(package_name.into());
+        self
+    }
+
+    /// Set the list of Debian pre-dependencies directly using an iterator of strings.
+    pub fn pre_depends<I, S>(mut self, pre_depends: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.pre_depends.replace(Dependencies::List(
+            pre_depends.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Set the list of Debian pre-dependencies indirectly via a path to a file,
+    /// which must contain one dependency (a package name) per line.
+    pub fn pre_depends_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.pre_depends.replace(Dependencies::Path(path.into()));
+        self
+    }
+
+    /// Set the packages this package replaces.
+    pub fn replaces<I, S>(mut self, replaces: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.replaces
+            .replace(replaces.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the virtual packages this package provides.
+    pub fn provides<I, S>(mut self, provides: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.provides
+            .replace(provides.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the packages that conflict with this package.
+    pub fn conflicts<I, S>(mut self, conflicts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.conflicts
+            .replace(conflicts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the changelog entries of the package, newest first.
+    pub fn changelog<I: IntoIterator<Item = DebianChangelogEntry>>(mut self, entries: I) -> Self {
+        self.changelog
+            .replace(DebianChangelog::Entries(entries.into_iter().collect()));
+        self
+    }
+
+    /// Set the custom fields appended to the Debian Control file.
+    pub fn custom_fields<I, K, V>(mut self, custom_fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.custom_fields.replace(
+            custom_fields
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Set whether the custom fields can set the standard fields of the Debian Control file.
+    pub fn allow_standard_fields(mut self, allow: bool) -> Self {
+        self.allow_standard_fields = allow;
+        self
+    }
+
+    /// Set the owners of the files matching glob patterns.
+    pub fn file_owners<I: IntoIterator<Item = DebianFileOwner>>(mut self, file_owners: I) -> Self {
+        self.file_owners.replace(file_owners.into_iter().collect());
+        self
+    }
+
+    /// Set the companion package holding the resources of the app.
+    pub fn data_package(mut self, data_package: DebianDataPackage) -> Self {
+        self.data_package.replace(data_package);
+        self
+    }
+
+    /// Set whether to build byte-identical packages from the same files, see [`DebianConfig::reproducible`].
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// Set the compression of the `control.tar` member.
+    pub fn control_compression(mut self, compression: DebianCompression) -> Self {
+        self.control_compression.replace(compression);
+        self
+    }
+
+    /// Set the compression level of the `control.tar` member.
+    pub fn control_compression_level(mut self, level: u32) -> Self {
+        self.control_compression_level.replace(level);
+        self
+    }
+
+    /// Set the compression of the `data.tar` member.
+    pub fn data_compression(mut self, compression: DebianCompression) -> Self {
+        self.data_compression.replace(compression);
+        self
+    }
+
+    /// Set the compression level of the `data.tar` member.
+    pub fn data_compression_level(mut self, level: u32) -> Self {
+        self.data_compression_level.replace(level);
+        self
+    }
+
+    /// Set the oldest dpkg version the packages must install with.
+    pub fn min_dpkg_version<S: Into<String>>(mut self, min_dpkg_version: S) -> Self {
+        self.min_dpkg_version.replace(min_dpkg_version.into());
+        self
+    }
+
+    /// Set whether to refresh the desktop entry and icon caches on install and removal.
+    pub fn refresh_desktop_caches(mut self, refresh_desktop_caches: bool) -> Self {
+        self.refresh_desktop_caches.replace(refresh_desktop_caches);
+        self
+    }
+
+    /// Set the check of the dependencies against the available packages.
+    pub fn dependency_check(mut self, dependency_check: DebianDependencyCheck) -> Self {
+        self.dependency_check.replace(dependency_check);
+        self
+    }
+
+    /// Set the path to an existing `debian/changelog` file to use as the changelog of the package.
+    pub fn changelog_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.changelog.replace(DebianChangelog::Path(path.into()));
+        self
+    }
+
     /// Set the path to a custom desktop file Handlebars template.
     ///
-    /// Available variables: `categories`, `comment` (optional), `exec`, `icon` and `name`.
+    /// Available variables: `categories`, `comment` (optional), `exec`, `icon`, `name`,
+    /// `mime_type` (optional), `startup_wm_class` (optional), `startup_notify` (optional)
+    /// and `extra_entries`.
     ///
     /// Default file contents:
     /// ```text
@@ -281,6 +710,15 @@ impl DebianConfig {
     /// {{#if mime_type}}
     /// MimeType={{mime_type}}
     /// {{/if}}
+    /// {{#if startup_wm_class}}
+    /// StartupWMClass={{startup_wm_class}}
+    /// {{/if}}
+    /// {{#if startup_notify}}
+    /// StartupNotify={{startup_notify}}
+    /// {{/if}}
+    /// {{#each extra_entries}}
+    /// {{@key}}={{this}}
+    /// {{/each}}
     /// ```
     pub fn desktop_template<P: Into<PathBuf>>(mut self, desktop_template: P) -> Self {
         self.desktop_template.replace(desktop_template.into());
@@ -316,67 +754,245 @@ impl DebianConfig {
         );
         self
     }
+
+    /// Set the `Maintainer` field of the Debian Control file.
+    pub fn maintainer<S: Into<String>>(mut self, maintainer: S) -> Self {
+        self.maintainer.replace(maintainer.into());
+        self
+    }
+
+    /// Set the `Homepage` field of the Debian Control file.
+    pub fn homepage<S: Into<String>>(mut self, homepage: S) -> Self {
+        self.homepage.replace(homepage.into());
+        self
+    }
+
+    /// Set the `Vcs-Browser` field of the Debian Control file.
+    pub fn vcs_browser<S: Into<String>>(mut self, vcs_browser: S) -> Self {
+        self.vcs_browser.replace(vcs_browser.into());
+        self
+    }
+
+    /// Set the `Vcs-Git` field of the Debian Control file.
+    pub fn vcs_git<S: Into<String>>(mut self, vcs_git: S) -> Self {
+        self.vcs_git.replace(vcs_git.into());
+        self
+    }
+
+    /// Set the `Bugs` field of the Debian Control file.
+    pub fn bugs<S: Into<String>>(mut self, bugs: S) -> Self {
+        self.bugs.replace(bugs.into());
+        self
+    }
 }
 
-/// A list of dependencies specified as either a list of Strings
-/// or as a path to a file that lists the dependencies, one per line.
+/// The file name of the [`Config::release_notes`] installed with the app.
+pub const RELEASE_NOTES_FILE_NAME: &str = "RELEASE_NOTES.md";
+
+/// The file name of the [`Config::updater_endpoint`] installed with the app.
+pub const UPDATER_ENDPOINT_FILE_NAME: &str = "updater.json";
+
+/// The patterns of the files written next to the packages by the previous packaging runs,
+/// excluded from the resources found at the root of the [`Config::out_dir`] unless [`Config::include_packaging_outputs`] is set:
+/// the signatures, the checksums, the provenance statements, the [`SHA256SUMS`](crate::checksum::SHA256SUMS_FILE_NAME) manifest,
+/// the [update manifest](crate::updater::UPDATE_MANIFEST_FILE_NAME) and the `artifacts.json` summary.
+pub const DEFAULT_RESOURCES_IGNORE: &[&str] = &[
+    "*.sig",
+    "*.sha256",
+    "*.b3",
+    "*.intoto.json",
+    "SHA256SUMS",
+    "latest.json",
+    "artifacts.json",
+];
+
+/// Release notes, in markdown, see [`Config::release_notes`].
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
-#[serde(untagged)]
-#[non_exhaustive]
-pub enum Dependencies {
-    /// The list of dependencies provided directly as a vector of Strings.
-    List(Vec<String>),
-    /// A path to the file containing the list of dependences, formatted as one per line:
-    /// ```text
-    /// libc6
-    /// libxcursor1
-    /// libdbus-1-3
-    /// libasyncns0
-    /// ...
-    /// ```
-    Path(PathBuf),
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub enum ReleaseNotes {
+    /// The release notes.
+    Text(String),
+    /// A file containing the release notes.
+    File(PathBuf),
 }
-impl Dependencies {
-    /// Returns the dependencies as a list of Strings.
-    pub fn to_list(&self) -> crate::Result<Vec<String>> {
+
+impl ReleaseNotes {
+    pub(crate) fn read(&self) -> crate::Result<String> {
         match self {
-            Self::List(v) => Ok(v.clone()),
-            Self::Path(path) => {
-                let trimmed_lines = fs::read_to_string(path)
-                    .map_err(|e| Error::IoWithPath(path.clone(), e))?
-                    .lines()
-                    .filter_map(|line| {
-                        let trimmed = line.trim();
-                        if !trimmed.is_empty() {
-                            Some(trimmed.to_owned())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                Ok(trimmed_lines)
+            Self::Text(text) => Ok(text.clone()),
+            Self::File(path) => {
+                std::fs::read_to_string(path).map_err(|e| Error::IoWithPath(path.clone(), e))
             }
         }
     }
 }
 
-/// The Linux AppImage configuration.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+/// The compression of a member of a Debian package, see [`DebianConfig::control_compression`]
+/// and [`DebianConfig::data_compression`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
-#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
 #[non_exhaustive]
-pub struct AppImageConfig {
-    /// List of libs that exist in `/usr/lib*` to be include in the final AppImage.
-    /// The libs will be searched for, using the command
-    /// `find -L /usr/lib* -name <libname>`
-    pub libs: Option<Vec<String>>,
-    /// List of binary paths to include in the final AppImage.
-    /// For example, if you want `xdg-open`, you'd specify `/usr/bin/xdg-open`
-    pub bins: Option<Vec<String>>,
-    /// List of custom files to add to the appimage package.
-    /// Maps a dir/file to a dir/file inside the appimage package.
-    pub files: Option<HashMap<String, String>>,
+pub enum DebianCompression {
+    /// The member is stored uncompressed, as `<member>.tar`.
+    None,
+    /// The member is compressed with gzip, as `<member>.tar.gz`, supported by every dpkg.
+    #[default]
+    Gzip,
+    /// The member is compressed with xz, as `<member>.tar.xz`, using the `xz` tool of the `PATH`.
+    Xz,
+    /// The member is compressed with Zstandard, as `<member>.tar.zst`, using the `zstd` tool of the `PATH`.
+    ///
+    /// Trained dictionaries aren't supported: dpkg decompresses the members without a dictionary,
+    /// so a package compressed with one couldn't be installed.
+    Zstd,
+}
+
+/// The changelog of a Debian package, specified as either a list of entries
+/// or as a path to an existing `debian/changelog` file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+#[non_exhaustive]
+pub enum DebianChangelog {
+    /// The changelog entries, newest first.
+    Entries(Vec<DebianChangelogEntry>),
+    /// A path to an existing `debian/changelog` file, installed as is.
+    Path(PathBuf),
+}
+
+/// An entry of a [`DebianChangelog`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct DebianChangelogEntry {
+    /// The version of the package, the version of the newest entry must be [`Config::version`].
+    pub version: String,
+    /// The date of the entry, formatted according to RFC 2822, e.g. `Mon, 01 Jan 2024 12:00:00 +0000`.
+    ///
+    /// Defaults to the `SOURCE_DATE_EPOCH` environment variable when set, otherwise to the current date.
+    pub date: Option<String>,
+    /// The author of the entry, formatted as `Name <email>`.
+    ///
+    /// Defaults to the first of the [`Config::authors`].
+    pub author: Option<String>,
+    /// The changes of this version, where line breaks continue the same change.
+    pub changes: Vec<String>,
+    /// The distributions of this version. Defaults to `unstable`.
+    pub distribution: Option<String>,
+    /// The urgency of the upgrade, one of `low`, `medium`, `high`, `emergency` or `critical`.
+    /// Defaults to `medium`.
+    pub urgency: Option<String>,
+}
+
+impl DebianChangelogEntry {
+    /// Creates a new [`DebianChangelogEntry`] for the specified version.
+    pub fn new<S: Into<String>>(version: S) -> Self {
+        Self {
+            version: version.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the date of the entry, formatted according to RFC 2822.
+    pub fn date<S: Into<String>>(mut self, date: S) -> Self {
+        self.date.replace(date.into());
+        self
+    }
+
+    /// Set the author of the entry, formatted as `Name <email>`.
+    pub fn author<S: Into<String>>(mut self, author: S) -> Self {
+        self.author.replace(author.into());
+        self
+    }
+
+    /// Set the changes of this version.
+    pub fn changes<I, S>(mut self, changes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.changes = changes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the distributions of this version.
+    pub fn distribution<S: Into<String>>(mut self, distribution: S) -> Self {
+        self.distribution.replace(distribution.into());
+        self
+    }
+
+    /// Set the urgency of the upgrade.
+    pub fn urgency<S: Into<String>>(mut self, urgency: S) -> Self {
+        self.urgency.replace(urgency.into());
+        self
+    }
+}
+
+/// A list of dependencies specified as either a list of Strings
+/// or as a path to a file that lists the dependencies, one per line.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+#[non_exhaustive]
+pub enum Dependencies {
+    /// The list of dependencies provided directly as a vector of Strings.
+    List(Vec<String>),
+    /// A path to the file containing the list of dependences, formatted as one per line:
+    /// ```text
+    /// libc6
+    /// libxcursor1
+    /// libdbus-1-3
+    /// libasyncns0
+    /// ...
+    /// ```
+    Path(PathBuf),
+}
+impl Dependencies {
+    /// Returns the dependencies as a list of Strings.
+    pub fn to_list(&self) -> crate::Result<Vec<String>> {
+        match self {
+            Self::List(v) => Ok(v.clone()),
+            Self::Path(path) => {
+                let trimmed_lines = fs::read_to_string(path)
+                    .map_err(|e| Error::IoWithPath(path.clone(), e))?
+                    .lines()
+                    .filter_map(|line| {
+                        let trimmed = line.trim();
+                        if !trimmed.is_empty() {
+                            Some(trimmed.to_owned())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                Ok(trimmed_lines)
+            }
+        }
+    }
+}
+
+/// The Linux AppImage configuration.
+///
+/// When the `SOURCE_DATE_EPOCH` environment variable is set, the files of the AppImage
+/// use it as their modification time so building the same input produces the same AppImage.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AppImageConfig {
+    /// List of libs that exist in `/usr/lib*` to be include in the final AppImage.
+    /// The libs will be searched for, using the command
+    /// `find -L /usr/lib* -name <libname>`
+    pub libs: Option<Vec<String>>,
+    /// List of binary paths to include in the final AppImage.
+    /// For example, if you want `xdg-open`, you'd specify `/usr/bin/xdg-open`
+    pub bins: Option<Vec<String>>,
+    /// List of custom files to add to the appimage package.
+    /// Maps a dir/file to a dir/file inside the appimage package.
+    pub files: Option<HashMap<String, String>>,
     /// A map of [`linuxdeploy`](https://github.com/linuxdeploy/linuxdeploy)
     /// plugin name and its URL to be downloaded and executed while packaing the appimage.
     /// For example, if you want to use the
@@ -388,6 +1004,59 @@ pub struct AppImageConfig {
     /// For example, to exclude libnss3.so, you'd specify `libnss3*`
     #[serde(alias = "excluded-libraries", alias = "excluded_libraries")]
     pub excluded_libs: Option<Vec<String>>,
+    /// List of globs of files and directories to remove from the AppDir before squashing it into the AppImage,
+    /// relative to the AppDir. For example, to remove the translations and the documentation,
+    /// you'd specify `usr/share/locale/**` and `usr/share/doc/**`.
+    ///
+    /// The files are removed after the libraries are deployed, so it also removes the files they bring.
+    /// A warning is emitted for the globs that match nothing.
+    pub exclude: Option<Vec<String>>,
+    /// The update information to embed into the AppImage, used by
+    /// [AppImageUpdate](https://github.com/AppImageCommunity/AppImageUpdate) for delta updates.
+    ///
+    /// A `.zsync` file is also generated next to the AppImage, which must be uploaded alongside it.
+    ///
+    /// Supported formats are:
+    /// - `zsync|<url to the .zsync file>`
+    /// - `gh-releases-zsync|<github username>|<repo>|<release tag or latest>|<.zsync file name>`
+    /// - `pling-v1-zsync|<pling product id>|<.zsync file name>`
+    ///
+    /// See <https://github.com/AppImage/AppImageSpec/blob/master/draft.md#update-information>
+    #[serde(alias = "update-information", alias = "update_information")]
+    pub update_information: Option<String>,
+    /// The AppImage runtime to use instead of the one embedded by `appimagetool`,
+    /// for example an older runtime compatible with the glibc of old distributions.
+    ///
+    /// The runtime is the ELF executable prepended to the squashfs image of the AppDir, it can be:
+    /// - an `http://` or `https://` URL to download it from,
+    /// - a path to the runtime file,
+    /// - a release tag of <https://github.com/AppImage/type2-runtime/releases>, for example `continuous`,
+    /// - `vendored`, the runtime embedded in cargo-packager with the `vendored-appimage-runtime` feature,
+    ///   see `vendor/appimage-runtime/README.md` in the cargo-packager crate.
+    ///
+    /// `{{arch}}` is replaced with the AppImage architecture (`x86_64`, `i686`, `aarch64` or `armhf`)
+    /// in URLs and paths, and the runtime must be built for that architecture.
+    ///
+    /// A path or the `vendored` runtime are read without network access. For fully offline builds,
+    /// also pin `linuxdeploy`, `linuxdeploy-plugin-appimage` and `apprun` to local files in [`Config::pinned_tools`].
+    ///
+    /// The runtime is executed every time the AppImage is launched and is not verified,
+    /// so only use runtimes from trusted sources. Its size is also added to the AppImage,
+    /// statically linked runtimes being larger than the ones linking to `libfuse`.
+    pub runtime: Option<String>,
+    /// Extra arguments appended to the `linuxdeploy-plugin-appimage` invocation, after the ones set by the packager.
+    ///
+    /// This is an unsupported escape hatch for the tool options not exposed by the packager,
+    /// use it at your own risk.
+    #[serde(alias = "extra-tool-args", alias = "extra_tool_args")]
+    pub extra_tool_args: Option<Vec<String>>,
+    /// The architectures to build a separate AppImage for, each from its own set of binaries,
+    /// instead of a single AppImage for [`Config::target_triple`].
+    ///
+    /// The AppImages are named with their architecture and the ELF machine type of the binaries
+    /// must match the architecture they are declared for. The `latest.json` summary refers to
+    /// the AppImage of [`Config::target_triple`] when it is one of the architectures, otherwise the first one.
+    pub architectures: Option<Vec<AppImageArchitecture>>,
 }
 
 impl AppImageConfig {
@@ -438,6 +1107,19 @@ impl AppImageConfig {
         self
     }
 
+    /// Set the list of globs of files and directories to remove from the AppDir before squashing it.
+    ///
+    /// See [`AppImageConfig::exclude`] for more information.
+    pub fn exclude<I, S>(mut self, exclude: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude
+            .replace(exclude.into_iter().map(Into::into).collect());
+        self
+    }
+
     /// Set the map of [`linuxdeploy`](https://github.com/linuxdeploy/linuxdeploy)
     /// plugin name and its URL to be downloaded and executed while packaing the appimage.
     /// For example, if you want to use the
@@ -457,6 +1139,191 @@ impl AppImageConfig {
         );
         self
     }
+
+    /// Set the update information to embed into the AppImage.
+    ///
+    /// See [`AppImageConfig::update_information`] for the supported formats.
+    pub fn update_information<S: Into<String>>(mut self, update_information: S) -> Self {
+        self.update_information.replace(update_information.into());
+        self
+    }
+
+    /// Set the AppImage runtime to use.
+    ///
+    /// See [`AppImageConfig::runtime`] for the supported values.
+    pub fn runtime<S: Into<String>>(mut self, runtime: S) -> Self {
+        self.runtime.replace(runtime.into());
+        self
+    }
+
+    /// Set the extra arguments appended to the `linuxdeploy-plugin-appimage` invocation.
+    pub fn extra_tool_args<I, S>(mut self, extra_tool_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_tool_args
+            .replace(extra_tool_args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the architectures to build a separate AppImage for.
+    pub fn architectures<I: IntoIterator<Item = AppImageArchitecture>>(
+        mut self,
+        architectures: I,
+    ) -> Self {
+        self.architectures
+            .replace(architectures.into_iter().collect());
+        self
+    }
+}
+
+/// An architecture of [`AppImageConfig::architectures`] and the binaries built for it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AppImageArchitecture {
+    /// The target triple of the binaries, for example `aarch64-unknown-linux-gnu`.
+    #[serde(alias = "target-triple", alias = "target_triple")]
+    pub target_triple: String,
+    /// The directory of the [`Config::binaries`] built for this architecture,
+    /// used instead of [`Config::binaries_dir`].
+    #[serde(alias = "binaries-dir", alias = "binaries_dir")]
+    pub binaries_dir: PathBuf,
+    /// The AppImage runtime of this architecture, used instead of [`AppImageConfig::runtime`].
+    ///
+    /// See [`AppImageConfig::runtime`] for the supported values.
+    pub runtime: Option<String>,
+}
+
+impl AppImageArchitecture {
+    /// Creates a new [`AppImageArchitecture`] for the binaries of `target_triple` in `binaries_dir`.
+    pub fn new<S: Into<String>, P: Into<PathBuf>>(target_triple: S, binaries_dir: P) -> Self {
+        Self {
+            target_triple: target_triple.into(),
+            binaries_dir: binaries_dir.into(),
+            runtime: None,
+        }
+    }
+
+    /// Set the AppImage runtime of this architecture.
+    pub fn runtime<S: Into<String>>(mut self, runtime: S) -> Self {
+        self.runtime.replace(runtime.into());
+        self
+    }
+}
+
+/// The Linux self-extracting installer shell script configuration.
+///
+/// The generated `<app>_<version>_<arch>.sh` script embeds a base64-encoded `.tar.gz`
+/// of the binaries and resources, verifies its SHA-256 checksum and extracts it into a prefix:
+/// the binaries are installed to `<prefix>/bin` and the resources to `<prefix>/lib/<main-binary-name>`.
+///
+/// Run it with `--prefix <dir>` or the `PREFIX` environment variable to change the prefix,
+/// and with `--uninstall` to remove the installed files. It requires a POSIX `sh`,
+/// `base64`, `gzip`, `tar` and either `sha256sum` or `shasum`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct SharConfig {
+    /// The default installation prefix, defaults to `/usr/local`.
+    pub prefix: Option<String>,
+}
+
+impl SharConfig {
+    /// Creates a new [`SharConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the default installation prefix.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix.replace(prefix.into());
+        self
+    }
+}
+
+/// The compression method of the entries of a zip archive.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ZipCompression {
+    /// The entry is stored uncompressed, for already compressed files like images or videos.
+    Store,
+    /// The entry is compressed with deflate, supported by every unzipper.
+    #[default]
+    Deflate,
+    /// The entry is compressed with Zstandard.
+    ///
+    /// It is rejected as the unzippers of Windows Explorer, macOS and Info-ZIP can't extract it.
+    Zstd,
+}
+
+/// A [`ZipCompression`] for the entries of a zip archive matching a glob pattern.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ZipCompressionOverride {
+    /// The glob pattern matched against the paths of the entries in the archive, like `*.png`,
+    /// where `*` also matches the `/` separator of the directories.
+    pub pattern: String,
+    /// The compression method of the matching entries.
+    pub compression: ZipCompression,
+}
+
+impl ZipCompressionOverride {
+    /// Creates a new [`ZipCompressionOverride`].
+    pub fn new<S: Into<String>>(pattern: S, compression: ZipCompression) -> Self {
+        Self {
+            pattern: pattern.into(),
+            compression,
+        }
+    }
+}
+
+/// The portable zip archive configuration.
+///
+/// The generated `<app>_<version>_<arch>.zip` holds the binaries at its root,
+/// with the resources and the bundled runtime next to them.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ZipConfig {
+    /// The compression method of the entries, defaults to [`ZipCompression::Deflate`].
+    pub compression: Option<ZipCompression>,
+    /// The compression methods of the entries matching glob patterns, overriding [`ZipConfig::compression`].
+    ///
+    /// The first matching pattern applies.
+    #[serde(alias = "compression-overrides", alias = "compression_overrides")]
+    pub compression_overrides: Option<Vec<ZipCompressionOverride>>,
+}
+
+impl ZipConfig {
+    /// Creates a new [`ZipConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the compression method of the entries.
+    pub fn compression(mut self, compression: ZipCompression) -> Self {
+        self.compression.replace(compression);
+        self
+    }
+
+    /// Set the compression methods of the entries matching glob patterns.
+    pub fn compression_overrides<I: IntoIterator<Item = ZipCompressionOverride>>(
+        mut self,
+        compression_overrides: I,
+    ) -> Self {
+        self.compression_overrides
+            .replace(compression_overrides.into_iter().collect());
+        self
+    }
 }
 
 /// The Linux pacman configuration.
@@ -489,6 +1356,16 @@ pub struct PacmanConfig {
     /// Source of the package to be stored at PKGBUILD.
     /// PKGBUILD is a bash script, so version can be referred as ${pkgver}
     pub source: Option<Vec<String>>,
+    /// Name to use for the `pkgname` of the PKGBUILD and the name of the package file.
+    ///
+    /// It is used verbatim and must only contain lowercase alphanumerics and `@._+-`,
+    /// without starting with a hyphen or a period,
+    /// see <https://wiki.archlinux.org/title/PKGBUILD#pkgname>
+    ///
+    /// Defaults to [`Config::product_name`] converted to kebab-case for the `pkgname`
+    /// and to the [`Config::slug`] for the file name.
+    #[serde(alias = "package-name", alias = "package_name")]
+    pub package_name: Option<String>,
 }
 
 impl PacmanConfig {
@@ -575,6 +1452,12 @@ impl PacmanConfig {
             .replace(source.into_iter().map(Into::into).collect());
         self
     }
+
+    /// Set the name to use for the `pkgname` of the PKGBUILD and the name of the package file.
+    pub fn package_name<S: Into<String>>(mut self, package_name: S) -> Self {
+        self.package_name.replace(package_name.into());
+        self
+    }
 }
 
 /// Position coordinates struct.
@@ -599,6 +1482,38 @@ pub struct Size {
     pub height: u32,
 }
 
+/// The filesystem of an Apple Disk Image.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub enum DmgFilesystem {
+    /// HFS+, mounted by all macOS versions.
+    #[default]
+    #[serde(rename = "HFS+", alias = "hfs+")]
+    HfsPlus,
+    /// APFS, only mounted by macOS 10.13 and later.
+    #[serde(rename = "APFS", alias = "apfs")]
+    Apfs,
+}
+
+impl DmgFilesystem {
+    /// Returns the name of the filesystem passed to `hdiutil create -fs`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::HfsPlus => "HFS+",
+            Self::Apfs => "APFS",
+        }
+    }
+
+    /// Returns the minimum macOS version mounting a disk image of the filesystem.
+    pub fn minimum_system_version(&self) -> Option<&'static str> {
+        match self {
+            Self::HfsPlus => None,
+            Self::Apfs => Some("10.13"),
+        }
+    }
+}
+
 /// The Apple Disk Image (.dmg) configuration.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -621,6 +1536,16 @@ pub struct DmgConfig {
         alias = "application_folder_position"
     )]
     pub app_folder_position: Option<Position>,
+    /// The filesystem of the disk image, passed to `hdiutil create -fs`. Defaults to [`DmgFilesystem::HfsPlus`].
+    ///
+    /// A warning is logged when it can't be mounted by the [`MacOsConfig::minimum_system_version`] of the app.
+    pub filesystem: Option<DmgFilesystem>,
+    /// Extra arguments appended to the `create-dmg` invocation, after the ones set by the packager.
+    ///
+    /// This is an unsupported escape hatch for the tool options not exposed by the packager,
+    /// use it at your own risk.
+    #[serde(alias = "extra-tool-args", alias = "extra_tool_args")]
+    pub extra_tool_args: Option<Vec<String>>,
 }
 
 impl DmgConfig {
@@ -658,16 +1583,104 @@ impl DmgConfig {
         self.app_folder_position.replace(position);
         self
     }
+
+    /// Set the filesystem of the disk image.
+    pub fn filesystem(mut self, filesystem: DmgFilesystem) -> Self {
+        self.filesystem.replace(filesystem);
+        self
+    }
+
+    /// Set the extra arguments appended to the `create-dmg` invocation.
+    pub fn extra_tool_args<I, S>(mut self, extra_tool_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_tool_args
+            .replace(extra_tool_args.into_iter().map(Into::into).collect());
+        self
+    }
 }
 
-/// Notarization authentication credentials.
-#[derive(Clone, Debug)]
-pub enum MacOsNotarizationCredentials {
-    /// Apple ID authentication.
-    AppleId {
-        /// Apple ID.
-        apple_id: OsString,
-        /// Password.
+/// The macOS installer package (.pkg) configuration.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct PkgConfig {
+    /// The absolute path of the directory where the `.app` is installed. Defaults to `/Applications`.
+    #[serde(alias = "install-location", alias = "install_location")]
+    pub install_location: Option<PathBuf>,
+    /// A script run by the installer before installing the app, which must start with a `#!` shebang.
+    #[serde(alias = "preinstall-script", alias = "preinstall_script")]
+    pub preinstall_script: Option<PathBuf>,
+    /// A script run by the installer after installing the app, which must start with a `#!` shebang.
+    #[serde(alias = "postinstall-script", alias = "postinstall_script")]
+    pub postinstall_script: Option<PathBuf>,
+    /// The identity used to sign the installer package, a `Developer ID Installer` certificate
+    /// of the keychain or of [`MacOsConfig::signing_certificate`].
+    ///
+    /// The package is notarized with the [`MacOsConfig`] notarization credentials when signed.
+    #[serde(alias = "signing-identity", alias = "signing_identity")]
+    pub signing_identity: Option<String>,
+    /// Extra arguments appended to the `pkgbuild` invocation, after the ones set by the packager.
+    ///
+    /// This is an unsupported escape hatch for the tool options not exposed by the packager,
+    /// use it at your own risk.
+    #[serde(alias = "extra-tool-args", alias = "extra_tool_args")]
+    pub extra_tool_args: Option<Vec<String>>,
+}
+
+impl PkgConfig {
+    /// Creates a new [`PkgConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the absolute path of the directory where the `.app` is installed.
+    pub fn install_location<P: Into<PathBuf>>(mut self, install_location: P) -> Self {
+        self.install_location.replace(install_location.into());
+        self
+    }
+
+    /// Set the script run by the installer before installing the app.
+    pub fn preinstall_script<P: Into<PathBuf>>(mut self, script: P) -> Self {
+        self.preinstall_script.replace(script.into());
+        self
+    }
+
+    /// Set the script run by the installer after installing the app.
+    pub fn postinstall_script<P: Into<PathBuf>>(mut self, script: P) -> Self {
+        self.postinstall_script.replace(script.into());
+        self
+    }
+
+    /// Set the identity used to sign the installer package.
+    pub fn signing_identity<S: Into<String>>(mut self, identity: S) -> Self {
+        self.signing_identity.replace(identity.into());
+        self
+    }
+
+    /// Set the extra arguments appended to the `pkgbuild` invocation.
+    pub fn extra_tool_args<I, S>(mut self, extra_tool_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_tool_args
+            .replace(extra_tool_args.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+/// Notarization authentication credentials.
+#[derive(Clone, Debug)]
+pub enum MacOsNotarizationCredentials {
+    /// Apple ID authentication.
+    AppleId {
+        /// Apple ID.
+        apple_id: OsString,
+        /// Password.
         password: OsString,
         /// Team ID.
         team_id: OsString,
@@ -694,6 +1707,44 @@ pub enum MacOsNotarizationCredentials {
     },
 }
 
+/// Per-architecture binaries used to create a universal macOS binary.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct MacOsUniversalBinaryConfig {
+    /// Directory containing the `x86_64` builds of [`Config::binaries`].
+    #[serde(
+        rename = "x86_64BinariesDir",
+        alias = "x86_64-binaries-dir",
+        alias = "x86_64_binaries_dir"
+    )]
+    pub x86_64_binaries_dir: Option<PathBuf>,
+    /// Directory containing the `aarch64` builds of [`Config::binaries`].
+    #[serde(alias = "aarch64-binaries-dir", alias = "aarch64_binaries_dir")]
+    pub aarch64_binaries_dir: Option<PathBuf>,
+}
+
+impl MacOsUniversalBinaryConfig {
+    /// Creates a new [`MacOsUniversalBinaryConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory containing the `x86_64` builds of [`Config::binaries`].
+    pub fn x86_64_binaries_dir<P: Into<PathBuf>>(mut self, x86_64_binaries_dir: P) -> Self {
+        self.x86_64_binaries_dir.replace(x86_64_binaries_dir.into());
+        self
+    }
+
+    /// Directory containing the `aarch64` builds of [`Config::binaries`].
+    pub fn aarch64_binaries_dir<P: Into<PathBuf>>(mut self, aarch64_binaries_dir: P) -> Self {
+        self.aarch64_binaries_dir
+            .replace(aarch64_binaries_dir.into());
+        self
+    }
+}
+
 /// The macOS configuration.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -710,9 +1761,26 @@ pub struct MacOsConfig {
     /// - arranging for the compiled binary to link against those frameworks (e.g. by emitting lines like `cargo:rustc-link-lib=framework=SDL2` from your `build.rs` script)
     ///
     /// - embedding the correct rpath in your binary (e.g. by running `install_name_tool -add_rpath "@executable_path/../Frameworks" path/to/binary` after compiling)
+    ///
+    /// The rpath and the install names of the `.dylib` frameworks are fixed by [`MacOsConfig::relocate_dylibs`].
     pub frameworks: Option<Vec<String>>,
+    /// Whether to make the `.dylib` [`MacOsConfig::frameworks`] loadable on other Macs.
+    ///
+    /// The `LC_ID_DYLIB` of each bundled dylib and the references of the binaries and dylibs to it,
+    /// discovered with `otool -L`, are rewritten to `@rpath/<name>` with `install_name_tool`, and the
+    /// `@executable_path/../Frameworks` rpath is added to the binaries loading them. The packaging then fails with
+    /// [`Error::NonRelocatableDylib`](crate::Error::NonRelocatableDylib) when a binary still loads a dylib
+    /// from an absolute path outside of `/usr/lib` and `/System`. The modified files are signed ad hoc
+    /// when there is no [`MacOsConfig::signing_identity`].
+    ///
+    /// Defaults to `true`.
+    #[serde(alias = "relocate-dylibs", alias = "relocate_dylibs")]
+    pub relocate_dylibs: Option<bool>,
     /// A version string indicating the minimum MacOS version that the packaged app supports (e.g. `"10.11"`).
     /// If you are using this config field, you may also want have your `build.rs` script emit `cargo:rustc-env=MACOSX_DEPLOYMENT_TARGET=10.11`.
+    ///
+    /// It is written to the `LSMinimumSystemVersion` of the `Info.plist`, and a warning is logged when
+    /// the `LC_BUILD_VERSION` or `LC_VERSION_MIN_MACOSX` load command of a binary requires a newer macOS.
     #[serde(alias = "minimum-system-version", alias = "minimum_system_version")]
     pub minimum_system_version: Option<String>,
     /// The exception domain to use on the macOS .app package.
@@ -743,8 +1811,27 @@ pub struct MacOsConfig {
     /// Provider short name for notarization.
     #[serde(alias = "provider-short-name", alias = "provider_short_name")]
     pub provider_short_name: Option<String>,
+    /// The interval in seconds between two checks of the notarization status.
+    ///
+    /// Defaults to `30`.
+    #[serde(
+        alias = "notarization-poll-interval",
+        alias = "notarization_poll_interval"
+    )]
+    pub notarization_poll_interval: Option<u64>,
+    /// The time in seconds after which waiting for the notarization fails.
+    ///
+    /// The submission keeps running on Apple's side and can be resumed later,
+    /// see `notarize_resume`. Defaults to waiting until the notarization finishes.
+    #[serde(alias = "notarization-timeout", alias = "notarization_timeout")]
+    pub notarization_timeout: Option<u64>,
     /// Path to the entitlements.plist file.
     pub entitlements: Option<String>,
+    /// Whether to enable the hardened runtime when codesigning native binaries.
+    ///
+    /// Defaults to `true`. Apple rejects notarization of apps signed without it.
+    #[serde(alias = "hardened-runtime", alias = "hardened_runtime")]
+    pub hardened_runtime: Option<bool>,
     /// Path to the Info.plist file for the package.
     #[serde(alias = "info-plist-path", alias = "info_plist_path")]
     pub info_plist_path: Option<PathBuf>,
@@ -762,6 +1849,13 @@ pub struct MacOsConfig {
     /// Sets the `LSUIElement` flag in the macOS plist file.
     #[serde(default, alias = "background_app", alias = "background-app")]
     pub background_app: bool,
+    /// Per-architecture binaries to combine into a universal binary with `lipo`.
+    ///
+    /// When both directories are set, each of [`Config::binaries`] is created by running
+    /// `lipo -create` on its `x86_64` and `aarch64` builds. When only one is set,
+    /// a thin bundle is created from that directory instead.
+    #[serde(alias = "universal-binary", alias = "universal_binary")]
+    pub universal_binary: Option<MacOsUniversalBinaryConfig>,
 }
 
 impl MacOsConfig {
@@ -790,6 +1884,12 @@ impl MacOsConfig {
         self
     }
 
+    /// Whether to make the bundled dylibs loadable on other Macs, see [`MacOsConfig::relocate_dylibs`].
+    pub fn relocate_dylibs(mut self, relocate_dylibs: bool) -> Self {
+        self.relocate_dylibs.replace(relocate_dylibs);
+        self
+    }
+
     /// A version string indicating the minimum MacOS version that the packaged app supports (e.g. `"10.11"`).
     /// If you are using this config field, you may also want have your `build.rs` script emit `cargo:rustc-env=MACOSX_DEPLOYMENT_TARGET=10.11`.
     pub fn minimum_system_version<S: Into<String>>(mut self, minimum_system_version: S) -> Self {
@@ -818,12 +1918,31 @@ impl MacOsConfig {
         self
     }
 
+    /// The interval in seconds between two checks of the notarization status.
+    pub fn notarization_poll_interval(mut self, notarization_poll_interval: u64) -> Self {
+        self.notarization_poll_interval
+            .replace(notarization_poll_interval);
+        self
+    }
+
+    /// The time in seconds after which waiting for the notarization fails.
+    pub fn notarization_timeout(mut self, notarization_timeout: u64) -> Self {
+        self.notarization_timeout.replace(notarization_timeout);
+        self
+    }
+
     /// Path to the entitlements.plist file.
     pub fn entitlements<S: Into<String>>(mut self, entitlements: S) -> Self {
         self.entitlements.replace(entitlements.into());
         self
     }
 
+    /// Whether to enable the hardened runtime when codesigning native binaries.
+    pub fn hardened_runtime(mut self, hardened_runtime: bool) -> Self {
+        self.hardened_runtime.replace(hardened_runtime);
+        self
+    }
+
     /// Path to the Info.plist file for the package.
     pub fn info_plist_path<S: Into<PathBuf>>(mut self, info_plist_path: S) -> Self {
         self.info_plist_path.replace(info_plist_path.into());
@@ -850,10 +1969,16 @@ impl MacOsConfig {
             .replace(embedded_apps.into_iter().map(Into::into).collect());
         self
     }
+
+    /// Per-architecture binaries to combine into a universal binary with `lipo`.
+    pub fn universal_binary(mut self, universal_binary: MacOsUniversalBinaryConfig) -> Self {
+        self.universal_binary.replace(universal_binary);
+        self
+    }
 }
 
 /// Linux configuration
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 #[non_exhaustive]
@@ -865,6 +1990,251 @@ pub struct LinuxConfig {
         alias = "generate_desktop_entry"
     )]
     pub generate_desktop_entry: bool,
+    /// The `StartupWMClass` of the desktop entry, used by the desktop environment
+    /// to group the app windows with its launcher.
+    #[serde(alias = "startup-wm-class", alias = "startup_wm_class")]
+    pub startup_wm_class: Option<String>,
+    /// The `StartupNotify` value of the desktop entry, whether the app
+    /// supports startup notification.
+    #[serde(alias = "startup-notify", alias = "startup_notify")]
+    pub startup_notify: Option<bool>,
+    /// Additional key-value pairs to add to the `[Desktop Entry]` group of the desktop entry.
+    ///
+    /// Keys must follow the desktop entry specification key syntax, `A-Za-z0-9-`
+    /// optionally followed by a `[locale]` suffix, and must not be one of the keys generated by the packager.
+    /// See <https://specifications.freedesktop.org/desktop-entry-spec/latest/recognized-keys.html>
+    #[serde(alias = "extra-desktop-entries", alias = "extra_desktop_entries")]
+    pub extra_desktop_entries: Option<HashMap<String, String>>,
+    /// Symlinks to add to the deb and pacman packages, mapping the link path to the path of its target in the package.
+    ///
+    /// A link that is only a file name is created in `/usr/bin`, so `{ "myapp": "/usr/lib/myapp/bin/myapp" }`
+    /// makes `myapp` available on `PATH`. Several links can point to the same target.
+    /// The targets must exist in the package.
+    pub symlinks: Option<HashMap<String, String>>,
+    /// The AppStream metadata describing the app to the Linux software centers like GNOME Software and Discover,
+    /// installed at `usr/share/metainfo/<id>.metainfo.xml` in the deb, pacman and AppImage packages.
+    pub appstream: Option<AppStreamConfig>,
+}
+
+impl Default for LinuxConfig {
+    fn default() -> Self {
+        Self {
+            generate_desktop_entry: true,
+            startup_wm_class: None,
+            startup_notify: None,
+            extra_desktop_entries: None,
+            symlinks: None,
+            appstream: None,
+        }
+    }
+}
+
+impl LinuxConfig {
+    /// Creates a new [`LinuxConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flag to indicate if desktop entry should be generated.
+    pub fn generate_desktop_entry(mut self, generate_desktop_entry: bool) -> Self {
+        self.generate_desktop_entry = generate_desktop_entry;
+        self
+    }
+
+    /// Set the `StartupWMClass` of the desktop entry.
+    pub fn startup_wm_class<S: Into<String>>(mut self, startup_wm_class: S) -> Self {
+        self.startup_wm_class.replace(startup_wm_class.into());
+        self
+    }
+
+    /// Set the `StartupNotify` value of the desktop entry.
+    pub fn startup_notify(mut self, startup_notify: bool) -> Self {
+        self.startup_notify.replace(startup_notify);
+        self
+    }
+
+    /// Set additional key-value pairs to add to the `[Desktop Entry]` group of the desktop entry.
+    pub fn extra_desktop_entries<I, S, T>(mut self, extra_desktop_entries: I) -> Self
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        self.extra_desktop_entries.replace(
+            extra_desktop_entries
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the symlinks to add to the deb and pacman packages, mapping the link path to the path of its target.
+    pub fn symlinks<I, S, T>(mut self, symlinks: I) -> Self
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        self.symlinks.replace(
+            symlinks
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the AppStream metadata of the app.
+    pub fn appstream(mut self, appstream: AppStreamConfig) -> Self {
+        self.appstream.replace(appstream);
+        self
+    }
+}
+
+/// The AppStream metadata of a Linux app, see <https://www.freedesktop.org/software/appstream/docs/sect-Metadata-Application.html>
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AppStreamConfig {
+    /// The component id, which must match the id of the desktop entry,
+    /// the main binary name the `.desktop` file is named after.
+    ///
+    /// Defaults to the main binary name.
+    pub id: Option<String>,
+    /// A short summary of what the app does, required.
+    pub summary: String,
+    /// The description of the app, required. Paragraphs are separated by blank lines.
+    pub description: String,
+    /// The license of the metadata itself. Defaults to `CC0-1.0`.
+    #[serde(alias = "metadata-license", alias = "metadata_license")]
+    pub metadata_license: Option<String>,
+    /// The SPDX license expression of the app, e.g. `MIT OR Apache-2.0`.
+    #[serde(alias = "project-license", alias = "project_license")]
+    pub project_license: Option<String>,
+    /// The screenshots shown in the software centers, the first one is the default.
+    pub screenshots: Option<Vec<AppStreamScreenshot>>,
+    /// The releases of the app, newest first.
+    ///
+    /// Defaults to the entries of [`DebianConfig::changelog`] when the entries are configured,
+    /// otherwise to a single release of [`Config::version`].
+    pub releases: Option<Vec<AppStreamRelease>>,
+}
+
+impl AppStreamConfig {
+    /// Creates a new [`AppStreamConfig`] with the required summary and description.
+    pub fn new<S: Into<String>, D: Into<String>>(summary: S, description: D) -> Self {
+        Self {
+            summary: summary.into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the component id, which must match the id of the desktop entry.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id.replace(id.into());
+        self
+    }
+
+    /// Set the license of the metadata itself.
+    pub fn metadata_license<S: Into<String>>(mut self, license: S) -> Self {
+        self.metadata_license.replace(license.into());
+        self
+    }
+
+    /// Set the SPDX license expression of the app.
+    pub fn project_license<S: Into<String>>(mut self, license: S) -> Self {
+        self.project_license.replace(license.into());
+        self
+    }
+
+    /// Set the screenshots shown in the software centers.
+    pub fn screenshots<I: IntoIterator<Item = AppStreamScreenshot>>(
+        mut self,
+        screenshots: I,
+    ) -> Self {
+        self.screenshots.replace(screenshots.into_iter().collect());
+        self
+    }
+
+    /// Set the releases of the app, newest first.
+    pub fn releases<I: IntoIterator<Item = AppStreamRelease>>(mut self, releases: I) -> Self {
+        self.releases.replace(releases.into_iter().collect());
+        self
+    }
+}
+
+/// A screenshot of the [`AppStreamConfig`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AppStreamScreenshot {
+    /// The `http` or `https` URL of the image.
+    pub url: String,
+    /// The caption of the screenshot.
+    pub caption: Option<String>,
+}
+
+impl AppStreamScreenshot {
+    /// Creates a new [`AppStreamScreenshot`] of the image at `url`.
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the caption of the screenshot.
+    pub fn caption<S: Into<String>>(mut self, caption: S) -> Self {
+        self.caption.replace(caption.into());
+        self
+    }
+}
+
+/// A release of the [`AppStreamConfig`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AppStreamRelease {
+    /// The version of the release.
+    pub version: String,
+    /// The date of the release, formatted as `YYYY-MM-DD`.
+    ///
+    /// Defaults to the `SOURCE_DATE_EPOCH` environment variable when set, otherwise to the current date.
+    pub date: Option<String>,
+    /// The changes of the release, listed in its description.
+    pub changes: Option<Vec<String>>,
+}
+
+impl AppStreamRelease {
+    /// Creates a new [`AppStreamRelease`] of the specified version.
+    pub fn new<S: Into<String>>(version: S) -> Self {
+        Self {
+            version: version.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the date of the release, formatted as `YYYY-MM-DD`.
+    pub fn date<S: Into<String>>(mut self, date: S) -> Self {
+        self.date.replace(date.into());
+        self
+    }
+
+    /// Set the changes of the release.
+    pub fn changes<I, S>(mut self, changes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.changes
+            .replace(changes.into_iter().map(Into::into).collect());
+        self
+    }
 }
 
 /// A wix language.
@@ -946,17 +2316,42 @@ pub struct WixConfig {
     /// Path to a bitmap file to use as the installation user interface banner.
     /// This bitmap will appear at the top of all but the first page of the installer.
     ///
-    /// The required dimensions are 493px × 58px.
+    /// The required dimensions are 493px × 58px, in the BMP format.
+    /// Defaults to the standard WiX banner.
     #[serde(alias = "banner-path", alias = "banner_path")]
     pub banner_path: Option<PathBuf>,
     /// Path to a bitmap file to use on the installation user interface dialogs.
     /// It is used on the welcome and completion dialogs.
-    /// The required dimensions are 493px × 312px.
+    /// The required dimensions are 493px × 312px, in the BMP format.
+    /// Defaults to the standard WiX dialog image.
     #[serde(alias = "dialog-image-path", alias = "dialog_image_path")]
     pub dialog_image_path: Option<PathBuf>,
+    /// Path to an RTF file with the license agreement the user must accept to install the app.
+    ///
+    /// Takes precedence over [`Config::license_file`], when neither is set no license page is shown.
+    #[serde(alias = "license-agreement", alias = "license_agreement")]
+    pub license_agreement: Option<PathBuf>,
     /// Enables FIPS compliant algorithms.
     #[serde(default, alias = "fips-compliant", alias = "fips_compliant")]
     pub fips_compliant: bool,
+    /// The `Manufacturer` property of the installer, which must not be empty.
+    ///
+    /// Defaults to [`Config::publisher`](Config::publisher()).
+    pub manufacturer: Option<String>,
+    /// The name of the directory in `Program Files` the app is installed to by default,
+    /// which the user can change in the install directory dialog of the installer.
+    ///
+    /// The MSI is always installed per machine, so the default directory is in the `Program Files` of the machine.
+    ///
+    /// Defaults to [`Config::product_name`].
+    #[serde(alias = "install-dir-name", alias = "install_dir_name")]
+    pub install_dir_name: Option<String>,
+    /// Extra arguments appended to the `light.exe` invocation, after the ones set by the packager.
+    ///
+    /// This is an unsupported escape hatch for the tool options not exposed by the packager,
+    /// use it at your own risk.
+    #[serde(alias = "extra-tool-args", alias = "extra_tool_args")]
+    pub extra_tool_args: Option<Vec<String>>,
 }
 
 impl WixConfig {
@@ -1110,11 +2505,40 @@ impl WixConfig {
         self
     }
 
+    /// Set the path to an RTF file with the license agreement the user must accept to install the app.
+    pub fn license_agreement<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.license_agreement.replace(path.into());
+        self
+    }
+
     /// Set whether to enable or disable FIPS compliant algorithms.
     pub fn fips_compliant(mut self, fips_compliant: bool) -> Self {
         self.fips_compliant = fips_compliant;
         self
     }
+
+    /// Set the `Manufacturer` property of the installer.
+    pub fn manufacturer<S: Into<String>>(mut self, manufacturer: S) -> Self {
+        self.manufacturer.replace(manufacturer.into());
+        self
+    }
+
+    /// Set the name of the directory in `Program Files` the app is installed to by default.
+    pub fn install_dir_name<S: Into<String>>(mut self, install_dir_name: S) -> Self {
+        self.install_dir_name.replace(install_dir_name.into());
+        self
+    }
+
+    /// Set the extra arguments appended to the `light.exe` invocation.
+    pub fn extra_tool_args<I, S>(mut self, extra_tool_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_tool_args
+            .replace(extra_tool_args.into_iter().map(Into::into).collect());
+        self
+    }
 }
 
 /// Install Modes for the NSIS installer.
@@ -1162,6 +2586,38 @@ pub enum NsisCompression {
     Off,
 }
 
+/// A custom page of the NSIS installer, see [`NsisConfig::custom_pages`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct NsisCustomPage {
+    /// The path to the `.nsh` script defining the page functions.
+    pub script: PathBuf,
+    /// The name of the function creating and showing the page.
+    pub show: String,
+    /// The name of the function called when leaving the page.
+    pub leave: Option<String>,
+}
+
+impl NsisCustomPage {
+    /// Creates a new [`NsisCustomPage`] from the script defining it
+    /// and the name of the function showing it.
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(script: P, show: S) -> Self {
+        Self {
+            script: script.into(),
+            show: show.into(),
+            leave: None,
+        }
+    }
+
+    /// Set the name of the function called when leaving the page.
+    pub fn leave<S: Into<String>>(mut self, leave: S) -> Self {
+        self.leave.replace(leave.into());
+        self
+    }
+}
+
 /// The NSIS format configuration.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -1170,8 +2626,16 @@ pub enum NsisCompression {
 pub struct NsisConfig {
     /// Set the compression algorithm used to compress files in the installer.
     ///
+    /// [`NsisCompression::Lzma`] gives the smallest installers but is the slowest to compress,
+    /// [`NsisCompression::Zlib`] is the fastest to compress and decompress but gives larger installers.
+    ///
     /// See <https://nsis.sourceforge.io/Reference/SetCompressor>
     pub compression: Option<NsisCompression>,
+    /// Whether all the files are compressed together in a single block, enabled by default.
+    ///
+    /// Solid compression usually gives smaller installers, but the installer must decompress
+    /// the whole block before extracting the files. It can't be enabled with [`NsisCompression::Off`].
+    pub solid: Option<bool>,
     /// A custom `.nsi` template to use.
     ///
     /// See the default template here
@@ -1214,6 +2678,17 @@ pub struct NsisConfig {
     /// The path to an icon file used as the installer icon.
     #[serde(alias = "installer-icon", alias = "installer_icon")]
     pub installer_icon: Option<PathBuf>,
+    /// Path to a text or RTF file with the license agreement the user must accept to install the app.
+    ///
+    /// Takes precedence over [`Config::license_file`], when neither is set no license page is shown.
+    #[serde(alias = "license-agreement", alias = "license_agreement")]
+    pub license_agreement: Option<PathBuf>,
+    /// The text of the license agreement the user must accept to install the app,
+    /// embedded into the installer instead of a separate license file. Must not be empty.
+    ///
+    /// Takes precedence over [`NsisConfig::license_agreement`] and [`Config::license_file`].
+    #[serde(alias = "license-text", alias = "license_text")]
+    pub license_text: Option<String>,
     /// Whether the installation will be for all users or just the current user.
     #[serde(default, alias = "installer-mode", alias = "installer_mode")]
     pub install_mode: NSISInstallerMode,
@@ -1221,7 +2696,8 @@ pub struct NsisConfig {
     /// By default the OS language is used. If the OS language is not in the list of languages, the first language will be used.
     /// To allow the user to select the language, set `display_language_selector` to `true`.
     ///
-    /// See <https://github.com/kichik/nsis/tree/9465c08046f00ccb6eda985abbdbf52c275c6c4d/Contrib/Language%20files> for the complete list of languages.
+    /// The language names are case-insensitive and the build fails if one of them is not supported by NSIS,
+    /// see <https://github.com/kichik/nsis/tree/9465c08046f00ccb6eda985abbdbf52c275c6c4d/Contrib/Language%20files> for the complete list of languages.
     pub languages: Option<Vec<String>>,
     /// An key-value pair where the key is the language and the
     /// value is the path to a custom `.nsi` file that holds the translated text for cargo-packager's custom messages.
@@ -1253,15 +2729,64 @@ pub struct NsisConfig {
     /// ```
     #[serde(default, alias = "appdata-paths", alias = "appdata_paths")]
     pub appdata_paths: Option<Vec<String>>,
-}
-
-impl NsisConfig {
-    /// Creates a new [`NsisConfig`].
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the compression algorithm used to compress files in the installer.
+    /// NSIS commands that will be injected at the start of the uninstall section,
+    /// before any of the app files are removed.
+    ///
+    /// The commands can use the NSIS constants from <https://nsis.sourceforge.io/Docs/Chapter4.html#varconstant>,
+    /// for example `$INSTDIR` which points to the installation directory,
+    /// in addition to `${PRODUCTNAME}`, `${MAINBINARYNAME}`, `${MANUFACTURER}` and `${IDENTIFIER}`.
+    ///
+    /// The commands must not open or close sections or functions.
+    ///
+    /// ### Example
+    /// ```toml
+    /// [package.metadata.packager.nsis]
+    /// uninstaller-commands = """
+    ///     nsExec::Exec 'sc stop "${PRODUCTNAME}Service"'
+    ///     RMDir /r "$INSTDIR\\data"
+    /// """
+    /// ```
+    #[serde(alias = "uninstaller-commands", alias = "uninstaller_commands")]
+    pub uninstaller_commands: Option<String>,
+    /// Custom pages shown after the install directory page, in order.
+    ///
+    /// Each page includes its `.nsh` script and is wired with `Page custom <show> <leave>`,
+    /// so the script only needs to define the functions creating and leaving the page,
+    /// usually with [nsDialogs](https://nsis.sourceforge.io/Docs/nsDialogs/Readme.html).
+    ///
+    /// The functions can read `$INSTDIR` and `$PassiveMode`, which is `1` when the installer runs with `/P`,
+    /// call `SkipIfPassive` to skip the page in passive mode, and use `Abort` in the leave function
+    /// to stay on the page.
+    ///
+    /// ### Example
+    /// ```toml
+    /// [[package.metadata.packager.nsis.custom-pages]]
+    /// script = "installer/license-key.nsh"
+    /// show = "LicenseKeyPage"
+    /// leave = "LicenseKeyPageLeave"
+    /// ```
+    #[serde(alias = "custom-pages", alias = "custom_pages")]
+    pub custom_pages: Option<Vec<NsisCustomPage>>,
+    /// The publisher of the installer, shown in the installed apps list
+    /// and used for the `${MANUFACTURER}` and `$PUBLISHER` variables.
+    ///
+    /// Defaults to [`Config::publisher`](Config::publisher()).
+    pub publisher: Option<String>,
+    /// Extra arguments appended to the `makensis` invocation, after the ones set by the packager.
+    ///
+    /// This is an unsupported escape hatch for the tool options not exposed by the packager,
+    /// use it at your own risk.
+    #[serde(alias = "extra-tool-args", alias = "extra_tool_args")]
+    pub extra_tool_args: Option<Vec<String>>,
+}
+
+impl NsisConfig {
+    /// Creates a new [`NsisConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the compression algorithm used to compress files in the installer.
     ///
     /// See <https://nsis.sourceforge.io/Reference/SetCompressor>
     pub fn compression(mut self, compression: NsisCompression) -> Self {
@@ -1269,6 +2794,12 @@ impl NsisConfig {
         self
     }
 
+    /// Set whether all the files are compressed together in a single block.
+    pub fn solid(mut self, solid: bool) -> Self {
+        self.solid.replace(solid);
+        self
+    }
+
     /// Set a custom `.nsi` template to use.
     ///
     /// See the default template here
@@ -1327,6 +2858,18 @@ impl NsisConfig {
         self
     }
 
+    /// Set the path to a text or RTF file with the license agreement the user must accept to install the app.
+    pub fn license_agreement<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.license_agreement.replace(path.into());
+        self
+    }
+
+    /// Set the text of the license agreement the user must accept to install the app.
+    pub fn license_text<S: Into<String>>(mut self, license_text: S) -> Self {
+        self.license_text.replace(license_text.into());
+        self
+    }
+
     /// Set whether the installation will be for all users or just the current user.
     pub fn install_mode(mut self, install_mode: NSISInstallerMode) -> Self {
         self.install_mode = install_mode;
@@ -1397,6 +2940,102 @@ impl NsisConfig {
             .replace(appdata_paths.into_iter().map(Into::into).collect());
         self
     }
+
+    /// Set NSIS commands that will be injected at the start of the uninstall section.
+    ///
+    /// See [`NsisConfig::uninstaller_commands`] for the available variables.
+    pub fn uninstaller_commands<S: Into<String>>(mut self, uninstaller_commands: S) -> Self {
+        self.uninstaller_commands
+            .replace(uninstaller_commands.into());
+        self
+    }
+
+    /// Set the custom pages shown after the install directory page.
+    ///
+    /// See [`NsisConfig::custom_pages`] for the available variables and functions.
+    pub fn custom_pages<I: IntoIterator<Item = NsisCustomPage>>(mut self, custom_pages: I) -> Self {
+        self.custom_pages
+            .replace(custom_pages.into_iter().collect());
+        self
+    }
+
+    /// Set the publisher of the installer.
+    pub fn publisher<S: Into<String>>(mut self, publisher: S) -> Self {
+        self.publisher.replace(publisher.into());
+        self
+    }
+
+    /// Set the extra arguments appended to the `makensis` invocation.
+    pub fn extra_tool_args<I, S>(mut self, extra_tool_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_tool_args
+            .replace(extra_tool_args.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+/// The Inno Setup format configuration.
+///
+/// The packager generates a `.iss` script installing the binaries and resources
+/// with the [`WindowsConfig::shortcuts`] and the [`Config::file_associations`],
+/// and compiles it with `ISCC` into `<app>_<version>_<arch>-innosetup.exe` when the compiler is found
+/// in `PATH` or in the default Inno Setup 6 installation directory.
+/// Otherwise the script is written to `<app>_<version>_<arch>-innosetup.iss`,
+/// referencing the files to install by their absolute paths on the packaging machine.
+///
+/// The installer and the uninstaller are signed like the NSIS installer, see [`WindowsConfig`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct InnoSetupConfig {
+    /// A custom handlebars template of the `.iss` script to use instead of the default one.
+    ///
+    /// See the default template in <https://github.com/crabnebula-dev/cargo-packager/blob/main/crates/packager/src/package/innosetup/installer.iss>
+    pub template: Option<PathBuf>,
+    /// The publisher of the installer, shown in the installed apps list.
+    ///
+    /// Defaults to [`Config::publisher`](Config::publisher()).
+    pub publisher: Option<String>,
+    /// Extra arguments appended to the `ISCC` invocation, after the ones set by the packager.
+    ///
+    /// This is an unsupported escape hatch for the tool options not exposed by the packager,
+    /// use it at your own risk.
+    #[serde(alias = "extra-tool-args", alias = "extra_tool_args")]
+    pub extra_tool_args: Option<Vec<String>>,
+}
+
+impl InnoSetupConfig {
+    /// Creates a new [`InnoSetupConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a custom handlebars template of the `.iss` script.
+    pub fn template<P: Into<PathBuf>>(mut self, template: P) -> Self {
+        self.template.replace(template.into());
+        self
+    }
+
+    /// Set the publisher of the installer.
+    pub fn publisher<S: Into<String>>(mut self, publisher: S) -> Self {
+        self.publisher.replace(publisher.into());
+        self
+    }
+
+    /// Set the extra arguments appended to the `ISCC` invocation.
+    pub fn extra_tool_args<I, S>(mut self, extra_tool_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_tool_args
+            .replace(extra_tool_args.into_iter().map(Into::into).collect());
+        self
+    }
 }
 
 /// The Windows configuration.
@@ -1408,6 +3047,16 @@ pub struct WindowsConfig {
     /// The file digest algorithm to use for creating file signatures. Required for code signing. SHA-256 is recommended.
     #[serde(alias = "digest-algorithm", alias = "digest_algorithm")]
     pub digest_algorithm: Option<String>,
+    /// The file digest algorithms of the signatures, in order, taking precedence over [`WindowsConfig::digest_algorithm`].
+    ///
+    /// The file is signed with the first algorithm, then a signature is appended with `signtool.exe sign /as`
+    /// for each of the next ones, timestamped with the same algorithm.
+    /// This allows dual-signing for legacy systems, like Windows 7 clients that only trust SHA-1 signatures,
+    /// with `["sha256", "sha1"]`, while modern systems use the SHA-256 signature.
+    ///
+    /// Defaults to a single SHA-256 signature. Not used with [`WindowsConfig::sign_command`].
+    #[serde(alias = "digest-algorithms", alias = "digest_algorithms")]
+    pub digest_algorithms: Option<Vec<String>>,
     /// The SHA1 hash of the signing certificate.
     #[serde(alias = "certificate-thumbprint", alias = "certificate_thumbprint")]
     pub certificate_thumbprint: Option<String>,
@@ -1439,17 +3088,99 @@ pub struct WindowsConfig {
     /// need to use another tool like `osslsigncode`.
     #[serde(alias = "sign-command", alias = "sign_command")]
     pub sign_command: Option<String>,
+    /// Sign using a hardware-backed key, like a smart card, a YubiKey or a PKCS#11 token,
+    /// exposed through a cryptographic service provider (CSP) or key storage provider (KSP).
+    ///
+    /// When set, it takes precedence over [`WindowsConfig::certificate_thumbprint`].
+    #[serde(alias = "hardware-key", alias = "hardware_key")]
+    pub hardware_key: Option<WindowsHardwareKeyConfig>,
+    /// Whether to timestamp the signatures.
+    ///
+    /// Disabling it allows signing when no timestamp server is reachable, like in air-gapped builds,
+    /// but un-timestamped signatures become invalid once the signing certificate expires,
+    /// whereas timestamped signatures stay valid.
+    ///
+    /// The default value of this flag is `true`.
+    #[serde(default = "default_true")]
+    pub timestamp: bool,
+    /// RFC 3161 timestamp servers to fail over to, in order,
+    /// when [`WindowsConfig::timestamp_url`] is unreachable.
+    ///
+    /// If [`WindowsConfig::timestamp_url`] is not set, the first one is used.
+    #[serde(alias = "timestamp-urls", alias = "timestamp_urls")]
+    pub timestamp_urls: Option<Vec<String>>,
+    /// The shortcuts created by the MSI and NSIS installers.
+    ///
+    /// Defaults to a Start Menu shortcut of the main binary.
+    pub shortcuts: Option<ShortcutsConfig>,
+    /// Whether to embed a `VERSIONINFO` resource into the main binary packaged by the MSI and NSIS installers,
+    /// shown in the Details tab of its Properties dialog.
+    ///
+    /// The resource is built from [`Config::version`], [`Config::publisher`], [`Config::product_name`],
+    /// [`Config::description`] and [`Config::copyright`] and written into a copy of the binary,
+    /// which is the one signed and packaged, the original binary is left untouched.
+    ///
+    /// The default value of this flag is `false`.
+    #[serde(default, alias = "embed-version-info", alias = "embed_version_info")]
+    pub embed_version_info: bool,
+    /// The support link of the Add/Remove Programs entry of the MSI and NSIS installers,
+    /// written into the `HelpLink` uninstall registry value. Must be an http or https URL.
+    #[serde(alias = "help-link", alias = "help_link")]
+    pub help_link: Option<String>,
+    /// The link to the publisher or product website in the Add/Remove Programs entry of the MSI and NSIS installers,
+    /// written into the `URLInfoAbout` uninstall registry value. Must be an http or https URL.
+    ///
+    /// Defaults to [`Config::homepage`] if it is an http or https URL.
+    #[serde(alias = "about-url", alias = "about_url")]
+    pub about_url: Option<String>,
+    /// The link to the update information in the Add/Remove Programs entry of the MSI and NSIS installers,
+    /// written into the `URLUpdateInfo` uninstall registry value. Must be an http or https URL.
+    #[serde(alias = "update-info-url", alias = "update_info_url")]
+    pub update_info_url: Option<String>,
+    /// The longest path of the files installed by the MSI, NSIS and Inno Setup installers,
+    /// in UTF-16 code units and counting the terminating null character like `MAX_PATH`.
+    ///
+    /// Packaging fails with [`Error::PathTooLong`](crate::Error::PathTooLong) when a binary or a resource
+    /// would be installed at a longer path in the longest default install directory,
+    /// the per-user one of a user with a 20 characters name,
+    /// instead of failing when the app is installed or launched.
+    ///
+    /// Defaults to `260`, or to `32767` with [`WindowsConfig::long_path_aware`].
+    /// Windows Installer itself doesn't support long paths, so prefer the default with MSI installers.
+    #[serde(alias = "max-path-length", alias = "max_path_length")]
+    pub max_path_length: Option<usize>,
+    /// Whether the app supports the paths longer than `MAX_PATH`, declared with the `longPathAware`
+    /// setting in the application manifest of the main binary packaged by the installers,
+    /// written into a copy of the binary like [`WindowsConfig::embed_version_info`].
+    ///
+    /// The long paths are only supported when they are also enabled on the system,
+    /// with the `LongPathsEnabled` registry value.
+    ///
+    /// The default value of this flag is `false`.
+    #[serde(default, alias = "long-path-aware", alias = "long_path_aware")]
+    pub long_path_aware: bool,
 }
 
 impl Default for WindowsConfig {
     fn default() -> Self {
         Self {
             digest_algorithm: None,
+            digest_algorithms: None,
             certificate_thumbprint: None,
             timestamp_url: None,
             tsp: false,
             allow_downgrades: true,
             sign_command: None,
+            hardware_key: None,
+            timestamp: true,
+            timestamp_urls: None,
+            shortcuts: None,
+            embed_version_info: false,
+            help_link: None,
+            about_url: None,
+            update_info_url: None,
+            max_path_length: None,
+            long_path_aware: false,
         }
     }
 }
@@ -1466,6 +3197,17 @@ impl WindowsConfig {
         self
     }
 
+    /// Set the file digest algorithms of the signatures, in order.
+    pub fn digest_algorithms<I, S>(mut self, digest_algorithms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.digest_algorithms
+            .replace(digest_algorithms.into_iter().map(Into::into).collect());
+        self
+    }
+
     /// Set the SHA1 hash of the signing certificate.
     pub fn certificate_thumbprint<S: Into<String>>(mut self, certificate_thumbprint: S) -> Self {
         self.certificate_thumbprint
@@ -1495,6 +3237,273 @@ impl WindowsConfig {
         self.allow_downgrades = allow;
         self
     }
+
+    /// Set the hardware-backed key to sign with.
+    pub fn hardware_key(mut self, hardware_key: WindowsHardwareKeyConfig) -> Self {
+        self.hardware_key.replace(hardware_key);
+        self
+    }
+
+    /// Set whether to timestamp the signatures.
+    ///
+    /// Un-timestamped signatures become invalid once the signing certificate expires.
+    ///
+    /// The default value of this flag is `true`.
+    pub fn timestamp(mut self, timestamp: bool) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set the RFC 3161 timestamp servers to fail over to, in order.
+    pub fn timestamp_urls<I, S>(mut self, timestamp_urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.timestamp_urls
+            .replace(timestamp_urls.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the shortcuts created by the MSI and NSIS installers.
+    pub fn shortcuts(mut self, shortcuts: ShortcutsConfig) -> Self {
+        self.shortcuts.replace(shortcuts);
+        self
+    }
+
+    /// Set the support link of the Add/Remove Programs entry.
+    pub fn help_link<S: Into<String>>(mut self, help_link: S) -> Self {
+        self.help_link.replace(help_link.into());
+        self
+    }
+
+    /// Set the link to the publisher or product website in the Add/Remove Programs entry.
+    pub fn about_url<S: Into<String>>(mut self, about_url: S) -> Self {
+        self.about_url.replace(about_url.into());
+        self
+    }
+
+    /// Set the link to the update information in the Add/Remove Programs entry.
+    pub fn update_info_url<S: Into<String>>(mut self, update_info_url: S) -> Self {
+        self.update_info_url.replace(update_info_url.into());
+        self
+    }
+
+    /// Set whether to embed a `VERSIONINFO` resource into the main binary.
+    pub fn embed_version_info(mut self, embed_version_info: bool) -> Self {
+        self.embed_version_info = embed_version_info;
+        self
+    }
+
+    /// Set the longest path of the files installed by the installers.
+    pub fn max_path_length(mut self, max_path_length: usize) -> Self {
+        self.max_path_length.replace(max_path_length);
+        self
+    }
+
+    /// Set whether the app supports the paths longer than `MAX_PATH`.
+    pub fn long_path_aware(mut self, long_path_aware: bool) -> Self {
+        self.long_path_aware = long_path_aware;
+        self
+    }
+}
+
+/// A hardware-backed key used to sign with `signtool.exe`.
+///
+/// The following arguments are passed to `signtool.exe sign`:
+/// `/f <certificate_path> /csp <provider> /kc <key_container>`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct WindowsHardwareKeyConfig {
+    /// Path to the file containing the public certificate of the key.
+    #[serde(alias = "certificate-path", alias = "certificate_path")]
+    pub certificate_path: PathBuf,
+    /// The name of the cryptographic service provider (CSP) or key storage provider (KSP)
+    /// that exposes the key, for example `Microsoft Smart Card Key Storage Provider`
+    /// or `eToken Base Cryptographic Provider`.
+    pub provider: String,
+    /// The name of the key container on the provider.
+    ///
+    /// Some providers accept the PIN as part of the container name, for example `[{{PIN}}]=container`.
+    #[serde(alias = "key-container", alias = "key_container")]
+    pub key_container: String,
+}
+
+impl WindowsHardwareKeyConfig {
+    /// Creates a new [`WindowsHardwareKeyConfig`].
+    pub fn new<P: Into<PathBuf>, S: Into<String>, T: Into<String>>(
+        certificate_path: P,
+        provider: S,
+        key_container: T,
+    ) -> Self {
+        Self {
+            certificate_path: certificate_path.into(),
+            provider: provider.into(),
+            key_container: key_container.into(),
+        }
+    }
+}
+
+/// The shortcuts created by the MSI and NSIS installers and removed by their uninstallers.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ShortcutsConfig {
+    /// Whether to create a Start Menu shortcut.
+    ///
+    /// The default value of this flag is `true`.
+    #[serde(default = "default_true", alias = "start-menu", alias = "start_menu")]
+    pub start_menu: bool,
+    /// Whether to create a Desktop shortcut.
+    ///
+    /// The NSIS installer still lets the user opt in to the Desktop shortcut
+    /// on its finish page when this is `false`.
+    ///
+    /// The default value of this flag is `false`.
+    #[serde(default)]
+    pub desktop: bool,
+    /// The name of the Start Menu folder containing the shortcut.
+    ///
+    /// Defaults to [`Config::product_name`].
+    #[serde(alias = "start-menu-folder", alias = "start_menu_folder")]
+    pub start_menu_folder: Option<String>,
+    /// The command line arguments passed to the binary when launched from the shortcuts.
+    pub arguments: Option<String>,
+    /// The name of the binary launched by the shortcuts, one of [`Config::binaries`].
+    ///
+    /// Defaults to the main binary.
+    pub binary: Option<String>,
+}
+
+impl Default for ShortcutsConfig {
+    fn default() -> Self {
+        Self {
+            start_menu: true,
+            desktop: false,
+            start_menu_folder: None,
+            arguments: None,
+            binary: None,
+        }
+    }
+}
+
+impl ShortcutsConfig {
+    /// Creates a new [`ShortcutsConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to create a Start Menu shortcut.
+    pub fn start_menu(mut self, start_menu: bool) -> Self {
+        self.start_menu = start_menu;
+        self
+    }
+
+    /// Set whether to create a Desktop shortcut.
+    pub fn desktop(mut self, desktop: bool) -> Self {
+        self.desktop = desktop;
+        self
+    }
+
+    /// Set the name of the Start Menu folder containing the shortcut.
+    pub fn start_menu_folder<S: Into<String>>(mut self, start_menu_folder: S) -> Self {
+        self.start_menu_folder.replace(start_menu_folder.into());
+        self
+    }
+
+    /// Set the command line arguments passed to the binary when launched from the shortcuts.
+    pub fn arguments<S: Into<String>>(mut self, arguments: S) -> Self {
+        self.arguments.replace(arguments.into());
+        self
+    }
+
+    /// Set the name of the binary launched by the shortcuts.
+    pub fn binary<S: Into<String>>(mut self, binary: S) -> Self {
+        self.binary.replace(binary.into());
+        self
+    }
+}
+
+/// The names of the external tools and downloaded runtime files that can be pinned in [`Config::pinned_tools`].
+pub const PINNABLE_TOOLS: &[&str] = &[
+    "makensis",
+    "wix",
+    "linuxdeploy",
+    "linuxdeploy-plugin-appimage",
+    "apprun",
+];
+
+/// An external tool or a downloaded runtime file pinned for reproducible builds, see [`Config::pinned_tools`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ToolPin {
+    /// The path of the tool to use instead of the one found in `PATH` or downloaded by the packager:
+    /// the `makensis` executable, the directory of the WiX binaries,
+    /// or the `linuxdeploy`, `linuxdeploy-plugin-appimage` and `AppRun` files.
+    pub path: Option<PathBuf>,
+    /// The expected version of `makensis` or `wix`, like `3.09` or `3.11.2.4516`,
+    /// compared to the version reported by the tool.
+    pub version: Option<String>,
+    /// The expected SHA-256 hex digest of the `linuxdeploy`, `linuxdeploy-plugin-appimage` and `AppRun` files.
+    pub sha256: Option<String>,
+}
+
+impl ToolPin {
+    /// Creates a new [`ToolPin`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the path of the tool.
+    pub fn path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.path.replace(path.into());
+        self
+    }
+
+    /// Set the expected version of the tool.
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.version.replace(version.into());
+        self
+    }
+
+    /// Set the expected SHA-256 hex digest of the tool.
+    pub fn sha256<S: Into<String>>(mut self, sha256: S) -> Self {
+        self.sha256.replace(sha256.into());
+        self
+    }
+}
+
+/// How the shared libraries linked by the main binary are checked, see [`Config::shared_libraries_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum SharedLibrariesCheck {
+    /// Warn about the libraries that won't be present on a clean target system.
+    Warn,
+    /// Fail the packaging with [`Error::UnavailableSharedLibraries`](crate::Error::UnavailableSharedLibraries).
+    Error,
+}
+
+/// How the version of the main binary is read, see [`Config::binary_version_check`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub enum BinaryVersionCheck {
+    /// Run the main binary with `--version` and read the first version in its output,
+    /// for example `1.2.3` in `my-app 1.2.3`.
+    VersionFlag,
+    /// Read the `ProductVersion` of the `VERSIONINFO` resource of the Windows main binary.
+    PeVersionInfo,
+    /// Run the given script with the `CARGO_PACKAGER_MAIN_BINARY` environment variable
+    /// set to the path of the main binary, and read the first version in its output.
+    Command(String),
 }
 
 /// An enum representing the available verbosity levels of the logger.
@@ -1528,6 +3537,40 @@ pub enum LogLevel {
     Trace,
 }
 
+/// How the generated packages are organized in [`Config::out_dir`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub enum OutputLayout {
+    /// All packages are placed directly in [`Config::out_dir`].
+    #[default]
+    Flat,
+    /// The packages are placed in a `macos`, `windows` or `linux` subdirectory,
+    /// depending on the operating system they target, with the portable zip archives in `windows`.
+    Os,
+    /// The packages are placed in a subdirectory named after their format, e.g. `deb`.
+    Format,
+}
+
+impl OutputLayout {
+    /// Returns the subdirectory of [`Config::out_dir`] where the packages of `format` are placed.
+    pub fn subdirectory(&self, format: PackageFormat) -> Option<&'static str> {
+        match self {
+            Self::Flat => None,
+            Self::Os => Some(match format {
+                PackageFormat::App | PackageFormat::Dmg | PackageFormat::Pkg => "macos",
+                PackageFormat::Wix
+                | PackageFormat::Nsis
+                | PackageFormat::Zip
+                | PackageFormat::InnoSetup => "windows",
+                _ => "linux",
+            }),
+            Self::Format => Some(format.short_name()),
+        }
+    }
+}
+
 /// A binary to package within the final package.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -1565,25 +3608,160 @@ impl Binary {
     }
 }
 
-/// A path to a resource (with optional glob pattern)
-/// or an object of `src` and `target` paths.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// A directory tree staged by the build, laid out as it should be installed,
+/// packaged wholesale instead of declaring its files in [`Config::resources`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
-#[serde(untagged)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 #[non_exhaustive]
-pub enum Resource {
-    /// Supports glob patterns
-    Single(String),
-    /// An object descriping the src file or directory
-    /// and its target location in the final package.
-    Mapped {
-        /// The src file or directory, supports glob patterns.
+pub struct StagingDir {
+    /// The path of the staging directory.
+    pub path: PathBuf,
+    /// The path of a JSON manifest mapping the system files of the tree, relative to [`StagingDir::path`],
+    /// to their absolute installation path, e.g. `{ "etc/app.conf": "/etc/app.conf" }`.
+    ///
+    /// The system files are only installed by the [`PackageFormat::Deb`] and [`PackageFormat::Pacman`] packages,
+    /// like the files of [`DebianConfig::files`], and are left out of the other formats.
+    pub manifest: Option<PathBuf>,
+}
+
+impl StagingDir {
+    /// Creates a new [`StagingDir`] of the tree at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            manifest: None,
+        }
+    }
+
+    /// Set the path of the JSON manifest of the system files.
+    pub fn manifest<P: Into<PathBuf>>(mut self, manifest: P) -> Self {
+        self.manifest.replace(manifest.into());
+        self
+    }
+}
+
+/// A generated wrapper script installed as the main binary, setting up the environment
+/// of an interpreter bundled in the resources, like a Python virtual environment or a Node.js runtime,
+/// and running the entrypoint of the app with it.
+///
+/// The shim is a POSIX shell script for the Linux and macOS packages. Windows targets aren't supported,
+/// since their installers expect an `.exe` main binary.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LauncherShim {
+    /// The path of the interpreter, relative to the directory of the resources in the packages,
+    /// like `venv/bin/python3`.
+    pub interpreter: PathBuf,
+    /// The path of the entrypoint run by the interpreter, relative to the directory of the resources
+    /// in the packages, like `app/main.py`.
+    pub entrypoint: Option<PathBuf>,
+    /// The arguments passed to the interpreter before the entrypoint, like `-I`.
+    pub args: Option<Vec<String>>,
+    /// The environment variables set before running the interpreter.
+    ///
+    /// The values are expanded by the shell, and the `RESOURCES_DIR` variable is set
+    /// to the directory of the resources in the installed package, like `$RESOURCES_DIR/venv`.
+    pub env: Option<HashMap<String, String>>,
+    /// Path to a custom Handlebars template of the shim.
+    ///
+    /// See the default template in <https://github.com/crabnebula-dev/cargo-packager/blob/main/crates/packager/src/package/launcher_shim/launcher.sh>
+    pub template: Option<PathBuf>,
+}
+
+impl LauncherShim {
+    /// Creates a new [`LauncherShim`] running the interpreter at `interpreter`,
+    /// relative to the directory of the resources.
+    pub fn new<P: Into<PathBuf>>(interpreter: P) -> Self {
+        Self {
+            interpreter: interpreter.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the path of the entrypoint run by the interpreter.
+    pub fn entrypoint<P: Into<PathBuf>>(mut self, entrypoint: P) -> Self {
+        self.entrypoint.replace(entrypoint.into());
+        self
+    }
+
+    /// Set the arguments passed to the interpreter before the entrypoint.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args
+            .replace(args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the environment variables set before running the interpreter.
+    pub fn env<I, K, V>(mut self, env: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env
+            .replace(env.into_iter().map(|(k, v)| (k.into(), v.into())).collect());
+        self
+    }
+
+    /// Set the path to a custom Handlebars template of the shim.
+    pub fn template<P: Into<PathBuf>>(mut self, template: P) -> Self {
+        self.template.replace(template.into());
+        self
+    }
+}
+
+/// A path to a resource (with optional glob pattern)
+/// or an object of `src` and `target` paths.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+#[non_exhaustive]
+pub enum Resource {
+    /// Supports glob patterns
+    Single(String),
+    /// An object descriping the src file or directory
+    /// and its target location in the final package.
+    Mapped {
+        /// The src file or directory, supports glob patterns.
         src: String,
         /// A relative path from the root of the final package.
         ///
         /// If `src` is a glob, this will always be treated as a directory
         /// where all globbed files will be placed under.
         target: PathBuf,
+        /// The Unix file mode of the resource files in the final package,
+        /// as an octal string, for example `"0755"`.
+        ///
+        /// Applies to every file matched by `src`. By default, files keep their
+        /// executable bit and are packaged with `0755` or `0644` accordingly.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<String>,
+        /// Whether the resource is skipped with a warning when `src` does not exist,
+        /// instead of failing the packaging, for example for files only generated on release builds.
+        #[serde(default)]
+        optional: bool,
+    },
+    /// A file downloaded from a URL at packaging time, like a large asset hosted on a CDN.
+    ///
+    /// The download is verified against `sha256` and cached across runs by its checksum,
+    /// in the `.cargo-packager/resources` directory of the user cache directory.
+    Remote {
+        /// The `http`, `https` or `file` URL of the file.
+        url: String,
+        /// The expected SHA-256 digest of the file, encoded as a hex string.
+        sha256: String,
+        /// A relative path from the root of the final package, including the file name.
+        target: PathBuf,
+        /// The Unix file mode of the file in the final package, as an octal string, for example `"0755"`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<String>,
     },
 }
 
@@ -1604,6 +3782,74 @@ pub enum HookCommand {
     },
 }
 
+/// A variant of the app, overriding a subset of the [`Config`] fields.
+///
+/// See [`Config::flavors`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct Flavor {
+    /// Overrides [`Config::product_name`].
+    #[serde(alias = "product-name", alias = "product_name")]
+    pub product_name: Option<String>,
+    /// Overrides [`Config::identifier`].
+    pub identifier: Option<String>,
+    /// Overrides [`Config::icons`].
+    pub icons: Option<Vec<String>>,
+    /// A suffix appended as is to [`Config::version`], for example `-nightly`.
+    #[serde(alias = "version-suffix", alias = "version_suffix")]
+    pub version_suffix: Option<String>,
+    /// Overrides [`Config::signing`], for example to sign the flavor of a release channel with its own key.
+    ///
+    /// The `{channel}` of its trusted comment defaults to the name of the flavor,
+    /// so the channel is selected by the flavors being packaged, see the `--flavor` CLI flag.
+    /// The CLI signing flags, like `--private-key` and `--channel`, still take precedence.
+    pub signing: Option<SigningConfig>,
+}
+
+impl Flavor {
+    /// Creates a new [`Flavor`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the product name of the flavor.
+    pub fn product_name<S: Into<String>>(mut self, product_name: S) -> Self {
+        self.product_name.replace(product_name.into());
+        self
+    }
+
+    /// Set the identifier of the flavor.
+    pub fn identifier<S: Into<String>>(mut self, identifier: S) -> Self {
+        self.identifier.replace(identifier.into());
+        self
+    }
+
+    /// Set the icons of the flavor.
+    pub fn icons<I, S>(mut self, icons: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.icons
+            .replace(icons.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the suffix appended to the version of the flavor.
+    pub fn version_suffix<S: Into<String>>(mut self, version_suffix: S) -> Self {
+        self.version_suffix.replace(version_suffix.into());
+        self
+    }
+
+    /// Set the signing configuration of the flavor.
+    pub fn signing(mut self, signing: SigningConfig) -> Self {
+        self.signing.replace(signing);
+        self
+    }
+}
+
 /// The packaging config.
 #[derive(Deserialize, Serialize, Default, Debug, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -1630,9 +3876,34 @@ pub struct Config {
     /// The package's product name, for example "My Awesome App".
     #[serde(default, alias = "product-name", alias = "product_name")]
     pub product_name: String,
+    /// The file name safe name of the app used in the file names of the packages of all the formats,
+    /// like `<slug>_<version>_<arch>.deb` or `<slug>_<version>_<arch>-setup.exe`, see [`Config::slug()`](Config::slug()).
+    ///
+    /// Defaults to the [`Config::product_name`], or the main binary name when the product name has none
+    /// of the allowed characters, converted to a slug:
+    /// - ASCII letters are lowercased and ASCII digits are kept.
+    /// - every run of other characters, like spaces, `_`, `.` and non-ASCII letters, is replaced with a single `-`.
+    /// - the leading and trailing `-` are removed.
+    ///
+    /// For example, `My App (Beta)` becomes `my-app-beta`. An explicit slug must already follow these rules.
+    /// The `.app` bundle keeps the product name, shown by Finder, but the `.dmg` and `.pkg` files use the slug.
+    /// The [`DebianConfig::package_name`] and [`PacmanConfig::package_name`] take precedence over the slug
+    /// for the `.deb` and pacman package files.
+    pub slug: Option<String>,
     /// The package's version.
     #[serde(default)]
     pub version: String,
+    /// An identifier of the build, like a UUID or a git commit hash, stamped into the metadata of every package
+    /// so any one of them tells the exact build it comes from.
+    ///
+    /// It is written in the `X-Build-Id` field of the Debian control file, the `CargoPackagerBuildId` key of the
+    /// macOS `Info.plist`, the `BuildId` string of the Windows version information, the update manifest
+    /// and the packaging summary.
+    ///
+    /// It may only contain ASCII letters, digits, `.`, `-`, `_` and `+`.
+    /// Defaults to a random UUID generated for each packaging run.
+    #[serde(default, alias = "build-id", alias = "build_id")]
+    pub build_id: Option<String>,
     /// The binaries to package.
     #[serde(default)]
     pub binaries: Vec<Binary>,
@@ -1640,6 +3911,9 @@ pub struct Config {
     /// This string must be unique across applications since it is used in some system configurations.
     /// This string must contain only alphanumeric characters (A-Z, a-z, and 0-9), hyphens (-),
     /// and periods (.).
+    ///
+    /// When not set, the `CFBundleIdentifier` of the macOS app is derived from the [`Config::publisher`]
+    /// and [`Config::product_name`] as `com.<publisher>.<product-name>`.
     #[cfg_attr(feature = "schema", schemars(regex(pattern = r"^[a-zA-Z0-9-\.]*$")))]
     pub identifier: Option<String>,
     /// The command to run before starting to package an application.
@@ -1659,12 +3933,119 @@ pub struct Config {
     #[serde(alias = "log-level", alias = "log_level")]
     pub log_level: Option<LogLevel>,
     /// The packaging formats to create, if not present, [`PackageFormat::platform_default`] is used.
+    ///
+    /// The formats are built one after the other in the order they are listed,
+    /// except for [`PackageFormat::Dmg`] and [`PackageFormat::Pkg`] which are built last
+    /// as they package the app bundle of [`PackageFormat::App`], see [`Config::format_order`] to change it.
     pub formats: Option<Vec<PackageFormat>>,
+    /// The order the [`Config::formats`] are built in, to control the disk usage and when the hooks run,
+    /// like building a large package first to upload and remove it
+    /// in the [`Config::before_each_package_command`] of the next format.
+    ///
+    /// The listed formats are built first, in this order, then the other formats in their default order.
+    /// The app bundle is still built before the [`PackageFormat::Dmg`] and [`PackageFormat::Pkg`] packages,
+    /// and [`PackageFormat::All`] and [`PackageFormat::Default`] have no effect.
+    #[serde(alias = "format-order", alias = "format_order")]
+    pub format_order: Option<Vec<PackageFormat>>,
+    /// Whether to skip the [`Config::formats`] that can't be built on the current operating system
+    /// with a warning, instead of failing with [`Error::FormatUnsupportedOnHost`].
+    ///
+    /// For example, [`PackageFormat::Dmg`] can only be built on macOS.
+    #[serde(
+        default,
+        alias = "skip-unsupported-formats",
+        alias = "skip_unsupported_formats"
+    )]
+    pub skip_unsupported_formats: bool,
+    /// The maximum size in bytes of each generated package, the packaging fails with
+    /// [`Error::ArtifactTooLarge`](crate::Error::ArtifactTooLarge) when a package exceeds it.
+    ///
+    /// The size of a directory, like the `.app` bundle, is the size of all its files.
+    #[serde(alias = "max-artifact-size", alias = "max_artifact_size")]
+    pub max_artifact_size: Option<u64>,
+    /// The maximum size in bytes of the packages of a format, overriding [`Config::max_artifact_size`].
+    #[serde(
+        alias = "max-artifact-size-per-format",
+        alias = "max_artifact_size_per_format"
+    )]
+    pub max_artifact_size_per_format: Option<HashMap<PackageFormat, u64>>,
+    /// Whether the symlinks of the [`Config::resources`], the [`Config::from_staging_dir`]
+    /// and the [`Config::bundled_runtime`] are followed, copying their targets into the packages,
+    /// instead of being preserved as symlinks.
+    ///
+    /// Only the symlinks to relative paths inside the copied directory are preserved,
+    /// the others would dangle once installed and are always followed.
+    /// The symlink cycles are rejected with [`Error::ResourceSymlinkLoop`](crate::Error::ResourceSymlinkLoop).
+    ///
+    /// Defaults to preserving the symlinks, except in the [`PackageFormat::Zip`] archives,
+    /// which are mostly extracted on Windows. The symlinks are always followed on Windows hosts
+    /// and in the [`PackageFormat::Nsis`], [`PackageFormat::Wix`] and [`PackageFormat::InnoSetup`] installers.
+    /// The [`PackageFormat::Dmg`] and [`PackageFormat::Pkg`] packages use the setting of the [`PackageFormat::App`] bundle.
+    #[serde(alias = "follow-symlinks", alias = "follow_symlinks")]
+    pub follow_symlinks: Option<bool>,
+    /// Whether the symlinks of the resources are followed in the packages of a format,
+    /// overriding [`Config::follow_symlinks`].
+    #[serde(
+        alias = "follow-symlinks-per-format",
+        alias = "follow_symlinks_per_format"
+    )]
+    pub follow_symlinks_per_format: Option<HashMap<PackageFormat, bool>>,
+    /// Whether to validate the structure of the packages built by external tools after they exit,
+    /// since a tool may exit successfully and still leave a truncated or corrupt package behind.
+    ///
+    /// The Debian packages must be complete `ar` archives, the AppImages must end with a complete squashfs image
+    /// and the disk images must have a valid UDIF trailer and pass `hdiutil verify`.
+    /// Fails with [`Error::CorruptArtifact`](crate::Error::CorruptArtifact) otherwise. Defaults to `true`.
+    #[serde(alias = "validate-artifacts", alias = "validate_artifacts")]
+    pub validate_artifacts: Option<bool>,
+    /// The project root, the directory the relative paths of the config are resolved against,
+    /// like the [`Config::resources`], [`Config::binaries`], [`Config::icons`] and [`Config::out_dir`],
+    /// independently of the current directory.
+    ///
+    /// A relative project root is itself resolved against the directory of the config file with the CLI,
+    /// or against the current directory. The `--root` flag of the CLI takes precedence over this value.
+    ///
+    /// Without a project root, the relative paths are resolved against the directory of the config file
+    /// with the CLI, or against the current directory.
+    ///
+    /// The relative paths are joined onto the project root, without changing the current directory of the process,
+    /// and the hook commands without a working directory run in it.
+    #[serde(alias = "project-root", alias = "project_root")]
+    pub project_root: Option<PathBuf>,
     /// The directory where the generated packages will be placed.
     ///
     /// If [`Config::binaries_dir`] is not set, this is also where the [`Config::binaries`] exist.
+    ///
+    /// The packages are written in the `.cargo-packager` intermediates directory inside it
+    /// and only moved to their final path once complete, so an interrupted build never leaves
+    /// a half-written package, and its partial files are removed by the next build.
+    ///
+    /// The `--out-dir` (or `--output-dir`) flag of the CLI takes precedence over this value.
     #[serde(default, alias = "out-dir", alias = "out_dir")]
     pub out_dir: PathBuf,
+    /// How the generated packages are organized in [`Config::out_dir`].
+    ///
+    /// Defaults to [`OutputLayout::Flat`].
+    #[serde(alias = "output-layout", alias = "output_layout")]
+    pub output_layout: Option<OutputLayout>,
+    /// Whether to remove the outputs of previous runs for the formats being built before packaging.
+    ///
+    /// The removed files are the packages in [`Config::out_dir`] and its [`Config::output_layout`]
+    /// subdirectory named like the packages of the format, for example `<main binary>_*.deb`,
+    /// including the ones of other versions and architectures, and the files written next to them
+    /// like signatures, checksums and split volumes. The other files of the directory are kept.
+    ///
+    /// Without it, each package overwrites the file it's written to
+    /// and the stale outputs of previous runs are kept.
+    #[serde(default, alias = "clean-outputs", alias = "clean_outputs")]
+    pub clean_outputs: bool,
+    /// Whether to stop packaging at the first format that fails.
+    ///
+    /// By default, the other formats are still packaged after a format fails
+    /// and [`Error::PackagingFailed`] is returned with the packages of the formats that succeeded,
+    /// unless a single format was packaged.
+    #[serde(default, alias = "fail-fast", alias = "fail_fast")]
+    pub fail_fast: bool,
     /// The directory where the [`Config::binaries`] exist.
     ///
     /// Defaults to [`Config::out_dir`].
@@ -1685,16 +4066,45 @@ pub struct Config {
     /// The package's authors.
     #[serde(default)]
     pub authors: Option<Vec<String>>,
-    /// The app's publisher. Defaults to the second element in [`Config::identifier`](Config::identifier) string.
-    /// Currently maps to the Manufacturer property of the Windows Installer.
+    /// The app's publisher, also known as vendor.
+    /// Defaults to the second element in [`Config::identifier`](Config::identifier) string.
+    ///
+    /// Maps to the `Manufacturer` property of the Windows Installer, the publisher of the NSIS and Inno Setup installers
+    /// and the `Maintainer` of the Debian and pacman packages when [`Config::authors`] is not set.
+    /// Each of them can be overridden with [`WixConfig::manufacturer`], [`NsisConfig::publisher`],
+    /// [`InnoSetupConfig::publisher`] and [`DebianConfig::maintainer`]. macOS has no publisher field,
+    /// the vendor is the prefix of the [`Config::identifier`] used as `CFBundleIdentifier`.
+    #[serde(alias = "vendor")]
     pub publisher: Option<String>,
     /// A path to the license file.
     #[serde(alias = "license-file", alias = "license_file")]
     pub license_file: Option<PathBuf>,
     /// The app's copyright.
     pub copyright: Option<String>,
-    /// The app's category.
+    /// The app's category, mapped to the category field of each format:
+    ///
+    /// | Format | Field |
+    /// | --- | --- |
+    /// | Desktop file of the deb, pacman and AppImage packages | `Categories=`, the closest [registered categories](https://specifications.freedesktop.org/menu-spec/latest/category-registry.html) |
+    /// | AppStream metainfo, see [`LinuxConfig::appstream`] | `<categories>`, the same registered categories |
+    /// | macOS app bundle | `LSApplicationCategoryType` of the `Info.plist` |
+    ///
+    /// The Windows Installer, NSIS and Inno Setup have no category, the apps are listed by name in Apps & features.
+    ///
+    /// The category can be named like any of these fields, for example `Developer Tool`,
+    /// `Development` or `public.app-category.developer-tools`, unknown categories are rejected
+    /// with a suggestion of the closest one.
     pub category: Option<AppCategory>,
+    /// The app's keywords, the search terms of the app in the software centers, mapped to:
+    ///
+    /// | Format | Field |
+    /// | --- | --- |
+    /// | Desktop file of the deb, pacman and AppImage packages | `Keywords=` |
+    /// | AppStream metainfo, see [`LinuxConfig::appstream`] | `<keywords>` |
+    /// | Windows Installer | The `Keywords` of the MSI summary information, after `Installer` |
+    ///
+    /// Each keyword must be a non-empty single line without `;`.
+    pub keywords: Option<Vec<String>>,
     /// The app's icon list. Supports glob patterns.
     pub icons: Option<Vec<String>>,
     /// The file associations
@@ -1708,11 +4118,108 @@ pub struct Config {
     /// the `src` could be either a glob pattern, path to a file, path to a directory,
     /// and the `target` is a path inside the final resources folder in the installed package.
     ///
+    /// Packaging fails when the path of a resource does not exist, unless the object sets `optional: true`.
+    /// A glob pattern matching no files is not an error.
+    ///
+    /// A resource can also be an object of `url`, `sha256` and `target`, a file downloaded at packaging time
+    /// and verified against its checksum, see [`Resource::Remote`].
+    ///
     /// ## Format-specific:
     ///
-    /// - **[PackageFormat::Nsis] / [PackageFormat::Wix]**: The resources are placed next to the executable in the root of the packager.
+    /// - **[PackageFormat::Nsis] / [PackageFormat::Wix] / [PackageFormat::InnoSetup]**: The resources are placed next to the executable in the root of the packager.
     /// - **[PackageFormat::Deb]**: The resources are placed in `usr/lib` of the package.
+    /// - **[PackageFormat::Shar]**: The resources are placed in `<prefix>/lib/<main-binary-name>` of the installation prefix.
+    /// - **[PackageFormat::Zip]**: The resources are placed next to the executable in the root of the archive.
     pub resources: Option<Vec<Resource>>,
+    /// Gitignore-style patterns of the files to exclude when expanding the resource directories and glob patterns.
+    ///
+    /// The patterns are matched against the paths of the files relative to the resource directory,
+    /// or against the file names of the glob matches, and take precedence over the include patterns of [`Config::resources`].
+    /// The patterns of a `.packagerignore` file at the root of a resource directory also apply to that directory,
+    /// with these patterns taking precedence over them. Files listed explicitly in [`Config::resources`] are always included.
+    ///
+    /// The outputs of the previous packaging runs, the files at the root of the [`Config::out_dir`] matching [`DEFAULT_RESOURCES_IGNORE`],
+    /// are also excluded, with the lowest precedence, so a negated pattern like `!latest.json` includes them again.
+    #[serde(alias = "resources-ignore", alias = "resources_ignore")]
+    pub resources_ignore: Option<Vec<String>>,
+    /// Whether the signatures, checksums and manifests left by previous packaging runs at the root of the [`Config::out_dir`],
+    /// matching [`DEFAULT_RESOURCES_IGNORE`], are included when expanding the resource directories and glob patterns.
+    ///
+    /// A warning is logged for each of these files excluded from the resources.
+    ///
+    /// The default value of this flag is `false`.
+    #[serde(
+        default,
+        alias = "include-packaging-outputs",
+        alias = "include_packaging_outputs"
+    )]
+    pub include_packaging_outputs: bool,
+    /// A directory tree staged by the build to package wholesale, in addition to the [`Config::resources`].
+    ///
+    /// Its files are placed at their path in the tree, relative to where each format places the resources,
+    /// except the system files of its [`StagingDir::manifest`] installed at their absolute path.
+    /// The [`Config::resources`] take precedence over the files of the tree with the same path,
+    /// and the [`Config::resources_ignore`] patterns also apply to the tree.
+    ///
+    /// Packaging fails when the tree has no files, or when one of its symbolic links or manifest entries
+    /// leads outside of it.
+    #[serde(alias = "from-staging-dir", alias = "from_staging_dir")]
+    pub from_staging_dir: Option<StagingDir>,
+    /// A directory placed next to the main binary in every package, keeping its name,
+    /// like an embedded Java runtime the app launches.
+    ///
+    /// The files of its `bin` directory are made executable,
+    /// the other files keep the permissions of the source files.
+    ///
+    /// ## Format-specific:
+    ///
+    /// - **[PackageFormat::App] / [PackageFormat::Dmg]**: The directory is placed in `Contents/MacOS` of the `.app`, next to the executable.
+    /// - **[PackageFormat::Nsis] / [PackageFormat::Wix] / [PackageFormat::InnoSetup]**: The directory is placed next to the executable in the installation directory.
+    /// - **[PackageFormat::Deb] / [PackageFormat::AppImage] / [PackageFormat::Pacman]**: The directory is placed in `usr/lib/<main-binary-name>`,
+    ///   so `../lib/<main-binary-name>/<name>` relative to the executable in `usr/bin`.
+    /// - **[PackageFormat::Shar]**: The directory is placed in `<prefix>/lib/<main-binary-name>`,
+    ///   so `../lib/<main-binary-name>/<name>` relative to the executable in `<prefix>/bin`.
+    /// - **[PackageFormat::Zip]**: The directory is placed next to the executable in the root of the archive.
+    #[serde(alias = "bundled-runtime", alias = "bundled_runtime")]
+    pub bundled_runtime: Option<PathBuf>,
+    /// A generated wrapper script installed in place of the main binary,
+    /// running the entrypoint of the app with an interpreter bundled in the [`Config::resources`].
+    ///
+    /// ## Format-specific:
+    ///
+    /// - **[PackageFormat::App] / [PackageFormat::Dmg] / [PackageFormat::Pkg]**: The resources are found in `Contents/Resources` of the `.app`.
+    /// - **[PackageFormat::Deb] / [PackageFormat::AppImage] / [PackageFormat::Pacman] / [PackageFormat::Shar]**: The resources are found in `../lib/<main-binary-name>`
+    ///   relative to the shim.
+    /// - **[PackageFormat::Zip]**: The resources are found next to the shim.
+    #[serde(alias = "launcher-shim", alias = "launcher_shim")]
+    pub launcher_shim: Option<LauncherShim>,
+    /// The release notes installed with the app, as `RELEASE_NOTES.md`,
+    /// and used for the `notes` of the update manifest when they aren't set otherwise.
+    ///
+    /// A [`ReleaseNotes::File`] must exist.
+    ///
+    /// ## Format-specific:
+    ///
+    /// - **[PackageFormat::App] / [PackageFormat::Dmg] / [PackageFormat::Pkg]**: The file is placed in `Contents/Resources` of the `.app`.
+    /// - **[PackageFormat::Nsis] / [PackageFormat::Wix] / [PackageFormat::InnoSetup]**: The file is placed next to the executable in the installation directory.
+    /// - **[PackageFormat::Deb] / [PackageFormat::AppImage] / [PackageFormat::Pacman]**: The file is placed in `usr/share/doc/<package>`,
+    ///   where `<package>` is the name of the Debian package.
+    /// - **[PackageFormat::Zip]**: The file is placed next to the executable in the root of the archive.
+    #[serde(alias = "release-notes", alias = "release_notes")]
+    pub release_notes: Option<ReleaseNotes>,
+    /// The endpoint the app checks for updates, like the URL of the `latest.json` update manifest,
+    /// written with the version of the package into [`UPDATER_ENDPOINT_FILE_NAME`], installed with the resources,
+    /// so each package points at the endpoint of its own release:
+    ///
+    /// ```json
+    /// { "endpoint": "https://releases.example.com/stable/latest.json", "version": "1.0.0" }
+    /// ```
+    ///
+    /// It must be an http or https URL. Like the [`Config::resources`], the file is placed
+    /// in `Contents/Resources` of the macOS app, next to the executable on Windows and in the zip archives,
+    /// and in `usr/lib/<main-binary-name>` of the Linux packages.
+    #[serde(alias = "updater-endpoint", alias = "updater_endpoint")]
+    pub updater_endpoint: Option<String>,
     /// Paths to external binaries to add to the package.
     ///
     /// The path specified should not include `-<target-triple><.exe>` suffix,
@@ -1730,6 +4237,70 @@ pub struct Config {
     /// <https://developer.apple.com/documentation/apple-silicon/building-a-universal-macos-binary>
     #[serde(alias = "external-binaries", alias = "external_binaries")]
     pub external_binaries: Option<Vec<PathBuf>>,
+    /// Whether to preserve the extended attributes of the files, like the macOS `com.apple.*` attributes,
+    /// in the tar-based formats: the [`PackageFormat::Deb`], [`PackageFormat::Pacman`]
+    /// and [`PackageFormat::Shar`] packages and the `.app.tar.gz` update bundles.
+    ///
+    /// The attributes are stored as `SCHILY.xattr.<name>` PAX headers,
+    /// restored by GNU tar and bsdtar when extracting with `--xattrs`.
+    /// They can only be read on Unix hosts and are skipped on other hosts.
+    ///
+    /// Symlinks are always archived as symlinks, regardless of this flag,
+    /// see [`Config::follow_symlinks`] for the symlinks of the [`Config::resources`].
+    #[serde(default, alias = "preserve-xattrs", alias = "preserve_xattrs")]
+    pub preserve_xattrs: bool,
+    /// The Unix mode of the packaged files without an explicit resource `mode`,
+    /// as an octal string, for example `"0644"`.
+    ///
+    /// The read permissions of executable files are extended with the execute permissions,
+    /// so `"0644"` packages the binaries with `0755`. The mode must not have the setuid,
+    /// setgid or sticky bits set and must be readable and writable by the owner.
+    ///
+    /// Applies to the [`PackageFormat::App`], [`PackageFormat::Deb`], [`PackageFormat::Pacman`],
+    /// [`PackageFormat::Shar`] and [`PackageFormat::AppImage`] packages, the Windows packages don't
+    /// have Unix modes. Defaults to the modes of the files when packaging,
+    /// normalized to `0644` or `0755` in the tar-based formats.
+    #[serde(alias = "default-file-mode", alias = "default_file_mode")]
+    pub default_file_mode: Option<String>,
+    /// The Unix mode of the packaged directories, as an octal string, for example `"0755"`.
+    ///
+    /// The mode must not have the setuid, setgid or sticky bits set
+    /// and must be readable, writable and searchable by the owner.
+    ///
+    /// See [`Config::default_file_mode`] for the formats it applies to.
+    #[serde(alias = "default-dir-mode", alias = "default_dir_mode")]
+    pub default_dir_mode: Option<String>,
+    /// Check the shared libraries linked by the main binary before packaging, using `ldd` on Linux
+    /// and `otool -L` on macOS, and report the ones that won't be present on a clean target system
+    /// and aren't bundled as [`Config::resources`] or macOS frameworks.
+    ///
+    /// The libraries of the C and C++ runtimes and of the common graphics and desktop stack are expected
+    /// on Linux, and the ones in `/usr/lib` and `/System` on macOS. The [`PackageFormat::AppImage`] is not
+    /// checked since it bundles the libraries of the binary.
+    #[serde(alias = "shared-libraries-check", alias = "shared_libraries_check")]
+    pub shared_libraries_check: Option<SharedLibrariesCheck>,
+    /// Additional shared libraries expected on the target systems, ignored by [`Config::shared_libraries_check`].
+    ///
+    /// Linux libraries are matched by name without their version, for example `libssl` matches `libssl.so.3`.
+    #[serde(alias = "allowed-shared-libraries", alias = "allowed_shared_libraries")]
+    pub allowed_shared_libraries: Option<Vec<String>>,
+    /// Check that the version of the main binary matches [`Config::version`] before packaging,
+    /// failing with [`Error::VersionMismatch`](crate::Error::VersionMismatch) otherwise,
+    /// which catches a stale binary left by a build step that didn't run.
+    ///
+    /// The versions are compared without their build metadata. The [`BinaryVersionCheck::VersionFlag`]
+    /// and [`BinaryVersionCheck::Command`] checks run the binary, so they can't be used when cross compiling.
+    #[serde(alias = "binary-version-check", alias = "binary_version_check")]
+    pub binary_version_check: Option<BinaryVersionCheck>,
+    /// The external tools and downloaded runtime files pinned for reproducible builds, keyed by their name:
+    /// `makensis`, `wix`, `linuxdeploy`, `linuxdeploy-plugin-appimage` or `apprun`.
+    ///
+    /// The packaging fails with [`Error::ToolVersionMismatch`](crate::Error::ToolVersionMismatch)
+    /// when the version or the digest of a tool differs from its pin.
+    /// The resolved versions and digests of the tools used by each package are recorded
+    /// in [`PackageOutput::tools`](crate::PackageOutput::tools) and in its provenance statement.
+    #[serde(alias = "pinned-tools", alias = "pinned_tools")]
+    pub pinned_tools: Option<HashMap<String, ToolPin>>,
     /// Windows-specific configuration.
     pub windows: Option<WindowsConfig>,
     /// MacOS-specific configuration.
@@ -1742,12 +4313,41 @@ pub struct Config {
     pub appimage: Option<AppImageConfig>,
     /// Pacman configuration.
     pub pacman: Option<PacmanConfig>,
+    /// Self-extracting installer shell script configuration.
+    pub shar: Option<SharConfig>,
+    /// Portable zip archive configuration.
+    pub zip: Option<ZipConfig>,
     /// WiX configuration.
     pub wix: Option<WixConfig>,
     /// Nsis configuration.
     pub nsis: Option<NsisConfig>,
+    /// Inno Setup configuration.
+    #[serde(alias = "inno-setup", alias = "inno_setup", alias = "innosetup")]
+    pub inno_setup: Option<InnoSetupConfig>,
     /// Dmg configuration.
     pub dmg: Option<DmgConfig>,
+    /// Pkg configuration.
+    pub pkg: Option<PkgConfig>,
+    /// The configuration used to sign the generated packages.
+    ///
+    /// The private key can be a path to the key file, relative to the config file, or the key itself.
+    /// The CLI flags and their environment variables take precedence over these values.
+    ///
+    /// Avoid storing the private key password in a configuration file that is checked into version control,
+    /// use the `CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD` environment variable instead.
+    pub signing: Option<SigningConfig>,
+    /// The software bill of materials (SBOM) of the app, generated from the `cargo metadata` of its crate
+    /// when set, see the [`sbom`](crate::sbom) module.
+    ///
+    /// The SBOM is written next to the packages and its path is recorded in each package output,
+    /// it can also be embedded into each package with [`SbomConfig::embed`](crate::sbom::SbomConfig::embed).
+    pub sbom: Option<crate::sbom::SbomConfig>,
+    /// Variants of the app built from this config, for example `stable` and `nightly`,
+    /// mapping the name of each flavor to the fields it overrides.
+    ///
+    /// Each flavor is packaged into the `<out-dir>/<flavor>` directory,
+    /// see [`Config::flavor_configs`] and the `--flavor` CLI flag to select the flavors to build.
+    pub flavors: Option<HashMap<String, Flavor>>,
     /// When set, a summary `latest.json` build artefact will be generated which can be
     /// hosted alongside other build artefacts as an endpoint for the updater, including
     /// meta data about the version and URL's to point at each of the other build artefacts.
@@ -1760,7 +4360,7 @@ pub struct Config {
     ///
     /// - `{{version}}`: The version of the app which is being packaged
     /// - `{{artefact}}`: The file name of the particular build artefact
-    ///     One URL is produced per build artefact.
+    ///   One URL is produced per build artefact.
     pub endpoint: Option<Url>,
 }
 
@@ -1810,11 +4410,31 @@ impl Config {
         self.pacman.as_ref()
     }
 
+    /// Returns the [shar](Config::shar) specific configuration.
+    pub fn shar(&self) -> Option<&SharConfig> {
+        self.shar.as_ref()
+    }
+
+    /// Returns the [Inno Setup](Config::inno_setup) specific configuration.
+    pub fn inno_setup(&self) -> Option<&InnoSetupConfig> {
+        self.inno_setup.as_ref()
+    }
+
+    /// Returns the [zip](Config::zip) specific configuration.
+    pub fn zip(&self) -> Option<&ZipConfig> {
+        self.zip.as_ref()
+    }
+
     /// Returns the [dmg](Config::dmg) specific configuration.
     pub fn dmg(&self) -> Option<&DmgConfig> {
         self.dmg.as_ref()
     }
 
+    /// Returns the [pkg](Config::pkg) specific configuration.
+    pub fn pkg(&self) -> Option<&PkgConfig> {
+        self.pkg.as_ref()
+    }
+
     /// Returns the target triple of this config, if not set, fallsback to the current OS target triple.
     pub fn target_triple(&self) -> String {
         self.target_triple.clone().unwrap_or_else(|| {
@@ -1880,6 +4500,61 @@ impl Config {
         })
     }
 
+    /// Returns the config of the flavor named `name`, with the overrides of the flavor applied,
+    /// [`Config::out_dir`] set to the `<out-dir>/<name>` directory and no flavors.
+    pub fn flavor(&self, name: &str) -> crate::Result<Config> {
+        let flavor = self
+            .flavors
+            .as_ref()
+            .and_then(|f| f.get(name))
+            .ok_or_else(|| Error::UnknownFlavor(name.into()))?;
+
+        let mut config = self.clone();
+        config.flavors = None;
+        config.out_dir = self.out_dir.join(name);
+        // the binaries are still read from the out dir of this config
+        config.binaries_dir.get_or_insert_with(|| {
+            if self.out_dir.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                self.out_dir.clone()
+            }
+        });
+        if let Some(product_name) = &flavor.product_name {
+            config.product_name.clone_from(product_name);
+        }
+        if let Some(identifier) = &flavor.identifier {
+            config.identifier.replace(identifier.clone());
+        }
+        if let Some(icons) = &flavor.icons {
+            config.icons.replace(icons.clone());
+        }
+        if let Some(version_suffix) = &flavor.version_suffix {
+            config.version.push_str(version_suffix);
+        }
+        if let Some(signing) = &flavor.signing {
+            let mut signing = signing.clone();
+            signing.channel.get_or_insert_with(|| name.into());
+            config.signing.replace(signing);
+        }
+        Ok(config)
+    }
+
+    /// Returns the configs of the `selected` flavors, or of all flavors sorted by name when `None`.
+    ///
+    /// When no flavors are defined and none is selected, the config itself is returned.
+    pub fn flavor_configs(&self, selected: Option<&[String]>) -> crate::Result<Vec<Config>> {
+        match (selected, &self.flavors) {
+            (Some(selected), _) => selected.iter().map(|name| self.flavor(name)).collect(),
+            (None, Some(flavors)) if !flavors.is_empty() => {
+                let mut names = flavors.keys().collect::<Vec<_>>();
+                names.sort();
+                names.into_iter().map(|name| self.flavor(name)).collect()
+            }
+            (None, _) => Ok(vec![self.clone()]),
+        }
+    }
+
     /// Returns the out dir. Defaults to the current directory.
     pub fn out_dir(&self) -> PathBuf {
         if self.out_dir.as_os_str().is_empty() {
@@ -1926,6 +4601,240 @@ impl Config {
             .ok_or_else(|| crate::Error::MainBinaryNotFound)
     }
 
+    /// Returns the [`Config::slug`] used in the file names of the packages,
+    /// derived from the product name or the main binary name when not set.
+    pub fn slug(&self) -> crate::Result<String> {
+        if let Some(slug) = &self.slug {
+            return Ok(slug.clone());
+        }
+        let slug = util::slugify(&self.product_name);
+        if !slug.is_empty() {
+            return Ok(slug);
+        }
+        let main_binary_name = self.main_binary_name()?;
+        match util::slugify(&main_binary_name) {
+            slug if slug.is_empty() => Err(Error::InvalidSlug(main_binary_name)),
+            slug => Ok(slug),
+        }
+    }
+
+    /// Returns this config with the defaults the packagers fall back to filled in, without building anything:
+    /// the [`Config::formats`] to build, the [`Config::target_triple`], the absolute [`Config::out_dir`]
+    /// and [`Config::binaries_dir`], the [`Config::publisher`] and the [`Config::slug`].
+    ///
+    /// Packaging the resolved config, or the config it deserializes into once serialized,
+    /// builds the same packages as packaging this config from the same directory.
+    pub fn resolved(&self) -> crate::Result<Config> {
+        let cwd = std::env::current_dir()?;
+        let absolute = |path: &Path| {
+            dunce::canonicalize(path).unwrap_or_else(|_| cwd.join(path).components().collect())
+        };
+
+        let mut config = self.clone();
+        config
+            .formats
+            .replace(crate::package::resolve_formats(self)?);
+        config.target_triple.replace(self.target_triple());
+        config.out_dir = if self.out_dir.as_os_str().is_empty() {
+            cwd.clone()
+        } else {
+            absolute(&self.out_dir)
+        };
+        config.binaries_dir.replace(match &self.binaries_dir {
+            Some(path) => absolute(path),
+            None => config.out_dir.clone(),
+        });
+        config.publisher = Some(self.publisher()).filter(|p| !p.is_empty());
+        config.slug = self.slug().ok();
+        Ok(config)
+    }
+
+    /// Returns this config with its relative paths joined onto the [`Config::project_root`],
+    /// so they are resolved against it rather than the current directory, or this config without a project root.
+    ///
+    /// A relative project root is itself resolved against the current directory.
+    /// The hook commands without a working directory run in the project root.
+    pub(crate) fn with_project_root(&self) -> crate::Result<Config> {
+        let Some(root) = &self.project_root else {
+            return Ok(self.clone());
+        };
+        let root = dunce::canonicalize(root).map_err(|e| Error::IoWithPath(root.clone(), e))?;
+        let path = |path: &mut PathBuf| {
+            if path.is_relative() {
+                *path = root.join(&*path);
+            }
+        };
+        let string = |value: &mut String| {
+            if Path::new(value.as_str()).is_relative() {
+                *value = root.join(value.as_str()).to_string_lossy().into_owned();
+            }
+        };
+        let file_map = |files: &mut HashMap<String, String>| {
+            *files = std::mem::take(files)
+                .into_iter()
+                .map(|(mut src, target)| {
+                    string(&mut src);
+                    (src, target)
+                })
+                .collect();
+        };
+        let hook = |hook: &mut HookCommand| {
+            *hook = match std::mem::replace(hook, HookCommand::Script(String::new())) {
+                HookCommand::Script(script) => HookCommand::ScriptWithOptions {
+                    script,
+                    dir: Some(root.to_string_lossy().into_owned()),
+                },
+                HookCommand::ScriptWithOptions { script, mut dir } => {
+                    string(dir.get_or_insert_with(String::new));
+                    HookCommand::ScriptWithOptions { script, dir }
+                }
+            }
+        };
+
+        let mut config = self.clone();
+        config.project_root = Some(root.clone());
+        path(&mut config.out_dir);
+        config.binaries_dir.iter_mut().for_each(path);
+        config.license_file.iter_mut().for_each(path);
+        config.bundled_runtime.iter_mut().for_each(path);
+        config.external_binaries.iter_mut().flatten().for_each(path);
+        config.icons.iter_mut().flatten().for_each(string);
+        for flavor in config.flavors.iter_mut().flat_map(|f| f.values_mut()) {
+            flavor.icons.iter_mut().flatten().for_each(string);
+        }
+        for resource in config.resources.iter_mut().flatten() {
+            match resource {
+                Resource::Single(src) | Resource::Mapped { src, .. } => string(src),
+                Resource::Remote { .. } => {}
+            }
+        }
+        if let Some(staging_dir) = &mut config.from_staging_dir {
+            path(&mut staging_dir.path);
+            staging_dir.manifest.iter_mut().for_each(path);
+        }
+        if let Some(shim) = &mut config.launcher_shim {
+            shim.template.iter_mut().for_each(path);
+        }
+        if let Some(ReleaseNotes::File(notes)) = &mut config.release_notes {
+            path(notes);
+        }
+        for pin in config.pinned_tools.iter_mut().flat_map(|p| p.values_mut()) {
+            pin.path.iter_mut().for_each(path);
+        }
+        config.before_packaging_command.iter_mut().for_each(hook);
+        config.before_each_package_command.iter_mut().for_each(hook);
+
+        if let Some(deb) = &mut config.deb {
+            deb.desktop_template.iter_mut().for_each(path);
+            deb.files.iter_mut().for_each(file_map);
+            if let Some(Dependencies::Path(depends)) = &mut deb.depends {
+                path(depends);
+            }
+            if let Some(DebianChangelog::Path(changelog)) = &mut deb.changelog {
+                path(changelog);
+            }
+            if let Some(check) = &mut deb.dependency_check {
+                check.package_list.iter_mut().for_each(path);
+            }
+        }
+        if let Some(appimage) = &mut config.appimage {
+            appimage.files.iter_mut().for_each(file_map);
+            appimage.bins.iter_mut().flatten().for_each(string);
+            for architecture in appimage.architectures.iter_mut().flatten() {
+                path(&mut architecture.binaries_dir);
+            }
+        }
+        if let Some(pacman) = &mut config.pacman {
+            pacman.files.iter_mut().for_each(file_map);
+            if let Some(Dependencies::Path(depends)) = &mut pacman.depends {
+                path(depends);
+            }
+        }
+        if let Some(macos) = &mut config.macos {
+            macos.entitlements.iter_mut().for_each(string);
+            macos.info_plist_path.iter_mut().for_each(path);
+            macos
+                .embedded_provisionprofile_path
+                .iter_mut()
+                .for_each(path);
+            macos.embedded_apps.iter_mut().flatten().for_each(string);
+            // the other frameworks are names searched in the system directories
+            macos
+                .frameworks
+                .iter_mut()
+                .flatten()
+                .filter(|f| {
+                    [".framework", ".app", ".dylib"]
+                        .iter()
+                        .any(|e| f.ends_with(e))
+                })
+                .for_each(string);
+            if let Some(universal_binary) = &mut macos.universal_binary {
+                universal_binary
+                    .x86_64_binaries_dir
+                    .iter_mut()
+                    .for_each(path);
+                universal_binary
+                    .aarch64_binaries_dir
+                    .iter_mut()
+                    .for_each(path);
+            }
+            if let Some(MacOsNotarizationCredentials::ApiKey { key_path, .. }) =
+                &mut macos.notarization_credentials
+            {
+                path(key_path);
+            }
+        }
+        if let Some(dmg) = &mut config.dmg {
+            dmg.background.iter_mut().for_each(path);
+        }
+        if let Some(pkg) = &mut config.pkg {
+            pkg.preinstall_script.iter_mut().for_each(path);
+            pkg.postinstall_script.iter_mut().for_each(path);
+        }
+        if let Some(hardware_key) = config
+            .windows
+            .as_mut()
+            .and_then(|w| w.hardware_key.as_mut())
+        {
+            path(&mut hardware_key.certificate_path);
+        }
+        if let Some(wix) = &mut config.wix {
+            wix.template.iter_mut().for_each(path);
+            wix.merge_modules.iter_mut().flatten().for_each(path);
+            wix.fragment_paths.iter_mut().flatten().for_each(path);
+            wix.banner_path.iter_mut().for_each(path);
+            wix.dialog_image_path.iter_mut().for_each(path);
+            wix.license_agreement.iter_mut().for_each(path);
+            for language in wix.languages.iter_mut().flatten() {
+                if let WixLanguage::Custom {
+                    path: Some(locale), ..
+                } = language
+                {
+                    path(locale);
+                }
+            }
+        }
+        if let Some(nsis) = &mut config.nsis {
+            nsis.template.iter_mut().for_each(path);
+            nsis.header_image.iter_mut().for_each(path);
+            nsis.sidebar_image.iter_mut().for_each(path);
+            nsis.installer_icon.iter_mut().for_each(path);
+            nsis.license_agreement.iter_mut().for_each(path);
+            nsis.custom_language_files
+                .iter_mut()
+                .flat_map(|f| f.values_mut())
+                .for_each(path);
+            for page in nsis.custom_pages.iter_mut().flatten() {
+                path(&mut page.script);
+            }
+        }
+        if let Some(inno_setup) = &mut config.inno_setup {
+            inno_setup.template.iter_mut().for_each(path);
+        }
+        Ok(config)
+    }
+
     /// Returns all icons path.
     pub fn icons(&self) -> crate::Result<Option<Vec<PathBuf>>> {
         let Some(patterns) = &self.icons else {
@@ -1941,29 +4850,672 @@ impl Config {
     }
 }
 
+/// `MAX_PATH`, the longest path supported by Windows without long path support.
+const MAX_PATH: usize = 260;
+/// The longest path supported by Windows with long path support.
+const MAX_LONG_PATH: usize = 32767;
+/// The root of the longest default install directory of the Windows installers,
+/// the per-user `{autopf}` of Inno Setup for a user with a 20 characters name, the longest allowed.
+const WORST_CASE_INSTALL_ROOT: &str = r"C:\Users\XXXXXXXXXXXXXXXXXXXX\AppData\Local\Programs\";
+
 #[derive(Debug, Clone)]
 pub(crate) struct ResolvedResource {
     pub src: PathBuf,
     pub target: PathBuf,
+    pub mode: Option<u32>,
+    /// The target of the symlink to create instead of copying `src`, when the symlink is preserved.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub symlink: Option<PathBuf>,
 }
 
-impl Config {
-    #[inline]
-    pub(crate) fn resources_from_dir(
-        src_dir: &Path,
-        target_dir: &Path,
-    ) -> crate::Result<Vec<ResolvedResource>> {
-        let mut out = Vec::new();
-        for entry in walkdir::WalkDir::new(src_dir) {
-            let entry = entry?;
+/// Copies the resolved resources into `path`, applying their modes.
+fn copy_resolved_resources(resources: Vec<ResolvedResource>, path: &Path) -> crate::Result<()> {
+    for resource in resources {
+        let dest = path.join(resource.target);
+        fs::create_dir_all(
+            dest.parent()
+                .ok_or_else(|| crate::Error::ParentDirNotFound(dest.to_path_buf()))?,
+        )?;
+        // a staged symlink overwritten by a configured resource must not be written through
+        if dest.is_symlink() {
+            fs::remove_file(&dest).map_err(|e| Error::IoWithPath(dest.clone(), e))?;
+        }
+        // the symlinks are only preserved on Unix hosts
+        #[cfg(unix)]
+        if let Some(link) = &resource.symlink {
+            if dest.is_dir() {
+                fs::remove_dir_all(&dest).map_err(|e| Error::IoWithPath(dest.clone(), e))?;
+            }
+            std::os::unix::fs::symlink(link, &dest)
+                .map_err(|e| Error::Symlink(link.clone(), dest.clone(), e))?;
+            continue;
+        }
+        fs::copy(&resource.src, &dest)
+            .map_err(|e| Error::CopyFile(resource.src.clone(), dest.clone(), e))?;
+        #[cfg(unix)]
+        if let Some(mode) = resource.mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dest, fs::Permissions::from_mode(mode))
+                .map_err(|e| Error::IoWithPath(dest.clone(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the target of the symlink at `path` when it is a relative path resolving inside `root`,
+/// so the symlink still resolves once copied with the directory.
+fn preserved_symlink(path: &Path, root: &Path) -> crate::Result<Option<PathBuf>> {
+    let link = fs::read_link(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let inside = link.is_relative() && dunce::canonicalize(path).is_ok_and(|t| t.starts_with(root));
+    Ok(inside.then_some(link))
+}
+
+/// Parses an octal Unix file mode like `0755`, `755` or `0o755`.
+pub(crate) fn parse_file_mode(mode: &str) -> crate::Result<u32> {
+    let digits = mode.strip_prefix("0o").unwrap_or(mode);
+    if digits.is_empty() || digits.len() > 5 {
+        return Err(Error::InvalidFileMode(mode.to_string()));
+    }
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|m| *m <= 0o7777)
+        .ok_or_else(|| Error::InvalidFileMode(mode.to_string()))
+}
+
+/// The default Unix modes of the packaged files and directories,
+/// see [`Config::default_file_mode`] and [`Config::default_dir_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct DefaultModes {
+    /// The mode of the files.
+    pub file: Option<u32>,
+    /// The mode of the directories.
+    pub dir: Option<u32>,
+}
+
+impl DefaultModes {
+    /// Returns the default mode of an entry, `executable` files get the
+    /// execute permissions of the classes that can read them.
+    #[cfg(unix)]
+    pub fn mode(&self, is_dir: bool, executable: bool) -> Option<u32> {
+        if is_dir {
+            self.dir
+        } else if executable {
+            self.file.map(|mode| mode | ((mode & 0o444) >> 2))
+        } else {
+            self.file
+        }
+    }
+}
+
+/// The dimensions of the [`WixConfig::banner_path`] bitmap required by WiX.
+pub(crate) const WIX_BANNER_SIZE: (u32, u32) = (493, 58);
+/// The dimensions of the [`WixConfig::dialog_image_path`] bitmap required by WiX.
+pub(crate) const WIX_DIALOG_SIZE: (u32, u32) = (493, 312);
+
+/// Reads the width and height of a BMP image from its DIB header.
+fn bmp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(b"BM") {
+        return None;
+    }
+    let u16_at = |i: usize| Some(u16::from_le_bytes(data.get(i..i + 2)?.try_into().ok()?));
+    let i32_at = |i: usize| Some(i32::from_le_bytes(data.get(i..i + 4)?.try_into().ok()?));
+    let header_size = i32_at(14)?;
+    if header_size == 12 {
+        // BITMAPCOREHEADER
+        Some((u16_at(18)? as u32, u16_at(20)? as u32))
+    } else {
+        // top-down bitmaps have a negative height
+        Some((i32_at(18)?.unsigned_abs(), i32_at(22)?.unsigned_abs()))
+    }
+}
+
+impl Config {
+    /// Validates that the [`WixConfig::banner_path`] and [`WixConfig::dialog_image_path`] bitmaps
+    /// are BMP images of the dimensions required by WiX.
+    pub(crate) fn wix_bitmaps(&self) -> crate::Result<()> {
+        let Some(wix) = self.wix() else {
+            return Ok(());
+        };
+        for (path, (width, height)) in [
+            (&wix.banner_path, WIX_BANNER_SIZE),
+            (&wix.dialog_image_path, WIX_DIALOG_SIZE),
+        ] {
+            let Some(path) = path else {
+                continue;
+            };
+            let data = fs::read(path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+            let reason = match bmp_dimensions(&data) {
+                Some(size) if size == (width, height) => continue,
+                Some((w, h)) => format!("found {w}×{h} pixels"),
+                None => "it is not a BMP image".into(),
+            };
+            return Err(Error::InvalidWixBitmap(path.clone(), width, height, reason));
+        }
+        Ok(())
+    }
+
+    /// Checks the [`Config::build_id`], which must be made of the characters allowed in the metadata of every format.
+    pub(crate) fn validate_build_id(&self) -> crate::Result<()> {
+        match &self.build_id {
+            Some(build_id)
+                if build_id.is_empty()
+                    || build_id.len() > 128
+                    || !build_id.chars().all(|c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+')
+                    }) =>
+            {
+                Err(Error::InvalidBuildId(build_id.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails with [`Error::InvalidUpdaterEndpoint`] when the [`Config::updater_endpoint`] is not an http or https URL.
+    pub(crate) fn validate_updater_endpoint(&self) -> crate::Result<()> {
+        match &self.updater_endpoint {
+            Some(endpoint)
+                if !Url::parse(endpoint).is_ok_and(|u| matches!(u.scheme(), "http" | "https")) =>
+            {
+                Err(Error::InvalidUpdaterEndpoint(endpoint.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails with [`Error::InvalidSlug`] when the [`Config::slug`] doesn't follow the slug rules.
+    pub(crate) fn validate_slug(&self) -> crate::Result<()> {
+        match &self.slug {
+            Some(slug) if slug.is_empty() || util::slugify(slug) != *slug => {
+                Err(Error::InvalidSlug(slug.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails with [`Error::PathTooLong`] when a binary or a resource installed by the Windows installers
+    /// of `formats` would have a longer path than [`WindowsConfig::max_path_length`]
+    /// in the longest default install directory.
+    pub(crate) fn validate_install_path_lengths(
+        &self,
+        formats: &[PackageFormat],
+    ) -> crate::Result<()> {
+        let mut install_dir_names = Vec::new();
+        if formats.contains(&PackageFormat::Wix) {
+            install_dir_names.push(self.wix_install_dir_name()?);
+        }
+        if formats
+            .iter()
+            .any(|f| matches!(f, PackageFormat::Nsis | PackageFormat::InnoSetup))
+        {
+            install_dir_names.push(self.product_name.clone());
+        }
+        let Some(install_dir_name) = install_dir_names
+            .into_iter()
+            .max_by_key(|name| name.encode_utf16().count())
+        else {
+            return Ok(());
+        };
+
+        let windows = self.windows();
+        let limit = windows.and_then(|w| w.max_path_length).unwrap_or(
+            if windows.is_some_and(|w| w.long_path_aware) {
+                MAX_LONG_PATH
+            } else {
+                MAX_PATH
+            },
+        );
+        let install_dir = format!("{WORST_CASE_INSTALL_ROOT}{install_dir_name}");
+        let binaries = self
+            .binaries
+            .iter()
+            .map(|b| PathBuf::from(b.path.file_name().unwrap_or_default()).with_extension("exe"));
+        let resources = self.resources()?.into_iter().map(|r| r.target);
+        for relative in binaries.chain(resources) {
+            let mut path = install_dir.clone();
+            for component in relative.components() {
+                path.push('\\');
+                path.push_str(&component.as_os_str().to_string_lossy());
+            }
+            // the limit counts the terminating null character
+            let length = path.encode_utf16().count() + 1;
+            if length > limit {
+                return Err(Error::PathTooLong {
+                    path,
+                    length,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the [`Config::keywords`], which must be usable in the desktop file and the MSI summary information.
+    pub(crate) fn validate_keywords(&self) -> crate::Result<()> {
+        for keyword in self.keywords.iter().flatten() {
+            if keyword.trim().is_empty() || keyword.contains(|c: char| c == ';' || c.is_control()) {
+                return Err(Error::InvalidKeyword(keyword.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the pin of an external tool, one of [`PINNABLE_TOOLS`], in [`Config::pinned_tools`].
+    pub(crate) fn tool_pin(&self, name: &str) -> Option<&ToolPin> {
+        self.pinned_tools.as_ref().and_then(|tools| tools.get(name))
+    }
+
+    /// Checks that the [`Config::pinned_tools`] are known tools,
+    /// pinning the version of the executables and the digest of the downloaded files.
+    pub(crate) fn validate_pinned_tools(&self) -> crate::Result<()> {
+        for (name, pin) in self.pinned_tools.iter().flatten() {
+            let invalid = |reason| Err(Error::InvalidToolPin(name.clone(), reason));
+            if !PINNABLE_TOOLS.contains(&name.as_str()) {
+                return invalid("it is not one of `makensis`, `wix`, `linuxdeploy`, `linuxdeploy-plugin-appimage` or `apprun`");
+            }
+            let executable = matches!(name.as_str(), "makensis" | "wix");
+            if executable && pin.sha256.is_some() {
+                return invalid("only the downloaded files have a `sha256` pin, pin the `version` of the tool instead");
+            }
+            if !executable && pin.version.is_some() {
+                return invalid("only `makensis` and `wix` have a `version` pin, pin the `sha256` digest of the file instead");
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the parsed [`Config::default_file_mode`] and [`Config::default_dir_mode`],
+    /// which must not have special bits and must be usable by the owner.
+    pub(crate) fn default_modes(&self) -> crate::Result<DefaultModes> {
+        let parse = |mode: &Option<String>, required: u32, kind: &'static str| {
+            mode.as_deref()
+                .map(|mode| {
+                    let parsed = parse_file_mode(mode)?;
+                    if parsed > 0o777 || parsed & required != required {
+                        return Err(Error::InvalidDefaultMode(kind, mode.to_string()));
+                    }
+                    Ok(parsed)
+                })
+                .transpose()
+        };
+        Ok(DefaultModes {
+            file: parse(&self.default_file_mode, 0o600, "file")?,
+            dir: parse(&self.default_dir_mode, 0o700, "directory")?,
+        })
+    }
+
+    /// Returns the `Manufacturer` of the Windows Installer,
+    /// [`WixConfig::manufacturer`] or [`Config::publisher`](Config::publisher()), which must not be empty.
+    pub(crate) fn wix_manufacturer(&self) -> crate::Result<String> {
+        let manufacturer = self
+            .wix()
+            .and_then(|w| w.manufacturer.clone())
+            .unwrap_or_else(|| self.publisher());
+        if manufacturer.trim().is_empty() {
+            return Err(Error::MissingPublisher("wix"));
+        }
+        Ok(manufacturer)
+    }
+
+    /// Returns the name of the default install directory of the Windows Installer,
+    /// [`WixConfig::install_dir_name`] or [`Config::product_name`], which must be a valid directory name.
+    pub(crate) fn wix_install_dir_name(&self) -> crate::Result<String> {
+        let name = self
+            .wix()
+            .and_then(|w| w.install_dir_name.clone())
+            .unwrap_or_else(|| self.product_name.clone());
+        let invalid = name.trim().is_empty()
+            || name.ends_with(['.', ' '])
+            || name.chars().any(|c| {
+                c.is_control() || matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+            });
+        if invalid {
+            return Err(Error::InvalidWixInstallDirName(name));
+        }
+        Ok(name)
+    }
+
+    /// Returns the publisher of the NSIS installer,
+    /// [`NsisConfig::publisher`] or [`Config::publisher`](Config::publisher()), which must not be empty.
+    pub(crate) fn nsis_publisher(&self) -> crate::Result<String> {
+        let publisher = self
+            .nsis()
+            .and_then(|n| n.publisher.clone())
+            .unwrap_or_else(|| self.publisher());
+        if publisher.trim().is_empty() {
+            return Err(Error::MissingPublisher("nsis"));
+        }
+        Ok(publisher)
+    }
+
+    /// Returns the publisher of the Inno Setup installer,
+    /// [`InnoSetupConfig::publisher`] or [`Config::publisher`](Config::publisher()), which must not be empty.
+    pub(crate) fn inno_setup_publisher(&self) -> crate::Result<String> {
+        let publisher = self
+            .inno_setup()
+            .and_then(|i| i.publisher.clone())
+            .unwrap_or_else(|| self.publisher());
+        if publisher.trim().is_empty() {
+            return Err(Error::MissingPublisher("innosetup"));
+        }
+        Ok(publisher)
+    }
+
+    /// Returns the links of the Add/Remove Programs entry of the MSI and NSIS installers,
+    /// keyed by the name of their uninstall registry value, failing with [`Error::InvalidWindowsUrl`]
+    /// if one of the configured links is not an http or https URL.
+    pub(crate) fn add_remove_programs_links(&self) -> crate::Result<Vec<(&'static str, String)>> {
+        let parse = |url: &str| {
+            url::Url::parse(url)
+                .ok()
+                .filter(|u| matches!(u.scheme(), "http" | "https"))
+        };
+        let windows = self.windows();
+        let about_url = windows
+            .and_then(|w| w.about_url.as_ref())
+            .map(|url| ("aboutUrl", url.as_str()))
+            .or_else(|| {
+                self.homepage
+                    .as_deref()
+                    .filter(|url| parse(url).is_some())
+                    .map(|url| ("homepage", url))
+            });
+
+        let mut links = Vec::new();
+        for (value, link) in [
+            (
+                "HelpLink",
+                windows.and_then(|w| w.help_link.as_deref().map(|url| ("helpLink", url))),
+            ),
+            ("URLInfoAbout", about_url),
+            (
+                "URLUpdateInfo",
+                windows.and_then(|w| {
+                    w.update_info_url
+                        .as_deref()
+                        .map(|url| ("updateInfoUrl", url))
+                }),
+            ),
+        ] {
+            if let Some((field, url)) = link {
+                let url = parse(url).ok_or_else(|| Error::InvalidWindowsUrl(field, url.into()))?;
+                links.push((value, url.to_string()));
+            }
+        }
+        Ok(links)
+    }
+
+    /// Returns the `Homepage`, `Vcs-Browser`, `Vcs-Git` and `Bugs` fields of the Debian Control file,
+    /// failing with [`Error::InvalidDebianUrl`] if one of the configured fields is not a valid URL.
+    pub(crate) fn deb_url_fields(&self) -> crate::Result<Vec<(&'static str, String)>> {
+        let parse = |url: &str, schemes: &[&str]| {
+            Url::parse(url)
+                .ok()
+                .filter(|u| schemes.contains(&u.scheme()))
+        };
+        let web = &["http", "https"][..];
+        let deb = self.deb();
+        let homepage = deb
+            .and_then(|d| d.homepage.as_deref())
+            .map(|url| ("homepage", url))
+            .or_else(|| {
+                self.homepage
+                    .as_deref()
+                    .filter(|url| parse(url, web).is_some())
+                    .map(|url| ("homepage", url))
+            });
+
+        let mut fields = Vec::new();
+        for (name, field, schemes) in [
+            ("Homepage", homepage, web),
+            (
+                "Vcs-Browser",
+                deb.and_then(|d| d.vcs_browser.as_deref().map(|url| ("vcsBrowser", url))),
+                web,
+            ),
+            (
+                "Vcs-Git",
+                deb.and_then(|d| d.vcs_git.as_deref().map(|url| ("vcsGit", url))),
+                &["http", "https", "git", "ssh"][..],
+            ),
+            (
+                "Bugs",
+                deb.and_then(|d| d.bugs.as_deref().map(|url| ("bugs", url))),
+                &["http", "https", "mailto", "debbugs"][..],
+            ),
+        ] {
+            if let Some((field, value)) = field {
+                // the repository URL of `Vcs-Git` can be followed by `-b <branch>`
+                let url = match name {
+                    "Vcs-Git" => value.split_whitespace().next().unwrap_or_default(),
+                    _ => value,
+                };
+                if parse(url, schemes).is_none() || value.contains(['\n', '\r']) {
+                    return Err(Error::InvalidDebianUrl(field, value.into()));
+                }
+                fields.push((name, value.to_string()));
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Returns the [`NsisConfig::compression`] of the NSIS installer, [`NsisCompression::Lzma`] by default,
+    /// and whether it is [`NsisConfig::solid`], which requires a compression algorithm.
+    pub(crate) fn nsis_compression(&self) -> crate::Result<(NsisCompression, bool)> {
+        let nsis = self.nsis();
+        let compression = nsis
+            .and_then(|n| n.compression)
+            .unwrap_or(NsisCompression::Lzma);
+        let solid = match (compression, nsis.and_then(|n| n.solid)) {
+            (NsisCompression::Off, Some(true)) => return Err(Error::InvalidNsisSolidCompression),
+            (NsisCompression::Off, _) => false,
+            (_, solid) => solid.unwrap_or(true),
+        };
+        Ok((compression, solid))
+    }
+
+    /// Returns the [`NsisConfig::custom_pages`] of the NSIS installer,
+    /// checking their scripts exist and their functions have valid names.
+    pub(crate) fn nsis_custom_pages(&self) -> crate::Result<&[NsisCustomPage]> {
+        let pages = self
+            .nsis()
+            .and_then(|n| n.custom_pages.as_deref())
+            .unwrap_or_default();
+        for page in pages {
+            if !page.script.is_file() {
+                return Err(Error::DoesNotExist(page.script.clone()));
+            }
+            for function in std::iter::once(&page.show).chain(&page.leave) {
+                let valid = function
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+                    && function
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+                if !valid {
+                    return Err(Error::InvalidNsisCustomPageFunction(function.clone()));
+                }
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Returns the `CFBundleIdentifier` of the macOS app, [`Config::identifier`] or,
+    /// when not set, `com.<publisher>.<product-name>` derived from [`Config::publisher`]
+    /// and [`Config::product_name`] in lowercase with the other characters replaced with `-`.
+    ///
+    /// The identifier must be made of at least two non-empty components separated by periods,
+    /// with only alphanumeric characters and hyphens.
+    pub(crate) fn bundle_identifier(&self) -> crate::Result<String> {
+        let identifier = match (&self.identifier, &self.publisher) {
+            (Some(identifier), _) => identifier.clone(),
+            (None, Some(publisher)) => format!(
+                "com.{}.{}",
+                util::slugify(publisher),
+                util::slugify(&self.product_name)
+            ),
+            (None, None) => String::new(),
+        };
+        let components = identifier.split('.').collect::<Vec<_>>();
+        let valid = components.len() >= 2
+            && components
+                .iter()
+                .all(|c| !c.is_empty() && c.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+        if !valid {
+            return Err(Error::InvalidBundleIdentifier(identifier));
+        }
+        Ok(identifier)
+    }
+
+    /// Returns the [`PkgConfig::install_location`], which must be absolute, or `/Applications`.
+    pub(crate) fn pkg_install_location(&self) -> crate::Result<PathBuf> {
+        let location = self
+            .pkg()
+            .and_then(|p| p.install_location.clone())
+            .unwrap_or_else(|| PathBuf::from("/Applications"));
+        if !location.has_root() {
+            return Err(Error::InvalidPkgInstallLocation(location));
+        }
+        Ok(location)
+    }
+
+    /// Returns the [`PkgConfig::preinstall_script`] and [`PkgConfig::postinstall_script`]
+    /// with the file names expected by `pkgbuild`, checking that they start with a `#!` shebang.
+    pub(crate) fn pkg_scripts(&self) -> crate::Result<Vec<(&'static str, PathBuf)>> {
+        let Some(pkg) = self.pkg() else {
+            return Ok(Vec::new());
+        };
+        let mut scripts = Vec::new();
+        for (name, script) in [
+            ("preinstall", &pkg.preinstall_script),
+            ("postinstall", &pkg.postinstall_script),
+        ] {
+            let Some(script) = script else {
+                continue;
+            };
+            if !script.is_file() {
+                return Err(Error::DoesNotExist(script.clone()));
+            }
+            let content = fs::read(script).map_err(|e| Error::IoWithPath(script.clone(), e))?;
+            if !content.starts_with(b"#!") {
+                return Err(Error::InvalidPkgScript(script.clone()));
+            }
+            scripts.push((name, script.clone()));
+        }
+        Ok(scripts)
+    }
+
+    /// Returns the [`WindowsConfig::shortcuts`] with the Start Menu folder and the binary filled in,
+    /// checking that the binary is one of [`Config::binaries`].
+    pub(crate) fn windows_shortcuts(&self) -> crate::Result<ShortcutsConfig> {
+        let mut shortcuts = self
+            .windows()
+            .and_then(|w| w.shortcuts.clone())
+            .unwrap_or_default();
+        if shortcuts.start_menu_folder.is_none() {
+            shortcuts
+                .start_menu_folder
+                .replace(self.product_name.clone());
+        }
+        match &shortcuts.binary {
+            Some(binary) => {
+                if !self
+                    .binaries
+                    .iter()
+                    .any(|b| b.path.file_stem().is_some_and(|s| s == binary.as_str()))
+                {
+                    return Err(Error::ShortcutBinaryNotFound(binary.clone()));
+                }
+            }
+            None => {
+                shortcuts.binary.replace(self.main_binary_name()?);
+            }
+        }
+        Ok(shortcuts)
+    }
+
+    /// Returns whether the symlinks of the resources copied into the packages of `format` are followed,
+    /// see [`Config::follow_symlinks`].
+    pub(crate) fn follows_symlinks(&self, format: PackageFormat) -> bool {
+        // the symlinks can't be created on Windows hosts nor listed in the installers
+        if !cfg!(unix)
+            || matches!(
+                format,
+                PackageFormat::Nsis | PackageFormat::Wix | PackageFormat::InnoSetup
+            )
+        {
+            return true;
+        }
+        self.follow_symlinks_per_format
+            .as_ref()
+            .and_then(|f| f.get(&format))
+            .or(self.follow_symlinks.as_ref())
+            .copied()
+            .unwrap_or(format == PackageFormat::Zip)
+    }
+
+    /// Returns the files of `src_dir` with their targets in `target_dir`.
+    ///
+    /// The symlinks are followed when `follow_symlinks` is set, otherwise the relative ones
+    /// resolving inside `src_dir` are preserved.
+    #[inline]
+    pub(crate) fn resources_from_dir(
+        src_dir: &Path,
+        target_dir: &Path,
+        ignore: &IgnorePatterns,
+        follow_symlinks: bool,
+    ) -> crate::Result<Vec<ResolvedResource>> {
+        let mut patterns = IgnorePatterns::from_dir(src_dir)?;
+        patterns.extend(ignore);
+        let root = dunce::canonicalize(src_dir)
+            .map_err(|e| Error::IoWithPath(src_dir.to_path_buf(), e))?;
+
+        let mut out = Vec::new();
+        for entry in walkdir::WalkDir::new(src_dir).follow_links(follow_symlinks) {
+            let entry = entry.map_err(|e| match e.loop_ancestor() {
+                Some(_) => Error::ResourceSymlinkLoop(e.path().unwrap_or(src_dir).to_path_buf()),
+                None => e.into(),
+            })?;
             let path = entry.path();
+            let relative = path.relative_to(src_dir)?.to_path("");
+            if !follow_symlinks && entry.path_is_symlink() {
+                if patterns.is_ignored(&relative) {
+                    tracing::debug!("Ignoring resource {}", path.display());
+                    continue;
+                }
+                if let Some(link) = preserved_symlink(path, &root)? {
+                    out.push(ResolvedResource {
+                        src: path.to_path_buf(),
+                        target: target_dir.join(relative),
+                        mode: None,
+                        symlink: Some(link),
+                    });
+                    continue;
+                }
+                // a symlink escaping the directory is replaced by its target
+                if path.is_dir() {
+                    out.extend(Self::resources_from_dir(
+                        path,
+                        &target_dir.join(relative),
+                        ignore,
+                        true,
+                    )?);
+                    continue;
+                }
+            }
             if path.is_file() {
-                let relative = path.relative_to(src_dir)?.to_path("");
+                if relative == Path::new(ignore::IGNORE_FILE_NAME) || patterns.is_ignored(&relative)
+                {
+                    tracing::debug!("Ignoring resource {}", path.display());
+                    continue;
+                }
                 let src = dunce::canonicalize(path)
                     .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
                 let resource = ResolvedResource {
                     src,
                     target: target_dir.join(relative),
+                    mode: None,
+                    symlink: None,
                 };
                 out.push(resource);
             }
@@ -1972,59 +5524,324 @@ impl Config {
     }
 
     #[inline]
-    pub(crate) fn resources_from_glob(glob: &str) -> crate::Result<Vec<ResolvedResource>> {
+    pub(crate) fn resources_from_glob(
+        glob: &str,
+        ignore: &IgnorePatterns,
+    ) -> crate::Result<Vec<ResolvedResource>> {
         let mut out = Vec::new();
         for src in glob::glob(glob)? {
             let src = src?;
             let src = dunce::canonicalize(&src).map_err(|e| Error::IoWithPath(src, e))?;
             let target = PathBuf::from(src.file_name().unwrap_or_default());
-            out.push(ResolvedResource { src, target })
+            if ignore.is_ignored(&target) {
+                tracing::debug!("Ignoring resource {}", src.display());
+                continue;
+            }
+            out.push(ResolvedResource {
+                src,
+                target,
+                mode: None,
+                symlink: None,
+            })
         }
         Ok(out)
     }
 
+    /// Returns the resources with their symlinks followed,
+    /// for the installers listing their files and the checks of the packaged files.
     pub(crate) fn resources(&self) -> crate::Result<Vec<ResolvedResource>> {
-        if let Some(resources) = &self.resources {
-            let mut out = Vec::new();
-            for r in resources {
-                match r {
-                    Resource::Single(src) => {
-                        let src_dir = PathBuf::from(src);
-                        if src_dir.is_dir() {
-                            let target_dir = Path::new(src_dir.file_name().unwrap_or_default());
-                            out.extend(Self::resources_from_dir(&src_dir, target_dir)?);
-                        } else {
-                            out.extend(Self::resources_from_glob(src)?);
-                        }
+        self.resolve_resources(true)
+    }
+
+    fn resolve_resources(&self, follow_symlinks: bool) -> crate::Result<Vec<ResolvedResource>> {
+        let ignore = IgnorePatterns::parse(self.resources_ignore.iter().flatten())?;
+        let outputs = if self.include_packaging_outputs {
+            None
+        } else {
+            let mut patterns = IgnorePatterns::parse(DEFAULT_RESOURCES_IGNORE)?;
+            patterns.extend(&ignore);
+            Some((self.out_dir(), patterns))
+        };
+        let without_outputs = |resources| Self::without_packaging_outputs(resources, &outputs);
+        // the configured resources are copied last, overwriting the staged files
+        let mut out = without_outputs(self.staged_resources(&ignore, follow_symlinks)?);
+        for r in self.resources.iter().flatten() {
+            let (src, optional) = match r {
+                Resource::Single(src) => (src, false),
+                Resource::Mapped { src, optional, .. } => (src, *optional),
+                Resource::Remote {
+                    url,
+                    sha256,
+                    target,
+                    mode,
+                } => {
+                    out.push(ResolvedResource {
+                        src: self.fetch_remote_resource(url, sha256)?,
+                        target: sanitize_path(target),
+                        mode: mode.as_deref().map(parse_file_mode).transpose()?,
+                        symlink: None,
+                    });
+                    continue;
+                }
+            };
+            if !src.contains(['*', '?', '[']) && !Path::new(src).exists() {
+                if optional {
+                    tracing::warn!("Skipping the optional resource {src}, it does not exist");
+                    continue;
+                }
+                return Err(Error::DoesNotExist(src.into()));
+            }
+
+            match r {
+                Resource::Single(src) => {
+                    let src_dir = PathBuf::from(src);
+                    if src_dir.is_dir() {
+                        let target_dir = Path::new(src_dir.file_name().unwrap_or_default());
+                        out.extend(without_outputs(Self::resources_from_dir(
+                            &src_dir,
+                            target_dir,
+                            &ignore,
+                            follow_symlinks,
+                        )?));
+                    } else if src_dir.is_file() {
+                        out.extend(Self::resources_from_glob(src, &ignore)?);
+                    } else {
+                        out.extend(without_outputs(Self::resources_from_glob(src, &ignore)?));
                     }
-                    Resource::Mapped { src, target } => {
-                        let src_path = PathBuf::from(src);
-                        let target_dir = sanitize_path(target);
-                        if src_path.is_dir() {
-                            out.extend(Self::resources_from_dir(&src_path, &target_dir)?);
-                        } else if src_path.is_file() {
-                            let src = dunce::canonicalize(&src_path)
-                                .map_err(|e| Error::IoWithPath(src_path, e))?;
-                            out.push(ResolvedResource {
-                                src,
-                                target: sanitize_path(target),
-                            });
-                        } else {
-                            let globbed_res = Self::resources_from_glob(src)?;
-                            let retargetd_res = globbed_res.into_iter().map(|mut r| {
-                                r.target = target_dir.join(r.target);
-                                r
-                            });
-                            out.extend(retargetd_res);
+                }
+                Resource::Mapped {
+                    src, target, mode, ..
+                } => {
+                    let mode = mode.as_deref().map(parse_file_mode).transpose()?;
+                    let src_path = PathBuf::from(src);
+                    let target_dir = sanitize_path(target);
+                    let mut mapped = if src_path.is_dir() {
+                        without_outputs(Self::resources_from_dir(
+                            &src_path,
+                            &target_dir,
+                            &ignore,
+                            follow_symlinks,
+                        )?)
+                    } else if src_path.is_file() {
+                        let src = dunce::canonicalize(&src_path)
+                            .map_err(|e| Error::IoWithPath(src_path, e))?;
+                        vec![ResolvedResource {
+                            src,
+                            target: sanitize_path(target),
+                            mode: None,
+                            symlink: None,
+                        }]
+                    } else {
+                        let mut globbed_res =
+                            without_outputs(Self::resources_from_glob(src, &ignore)?);
+                        for r in &mut globbed_res {
+                            r.target = target_dir.join(&r.target);
                         }
+                        globbed_res
+                    };
+                    for r in &mut mapped {
+                        r.mode = mode;
                     }
+                    out.extend(mapped);
                 }
+                Resource::Remote { .. } => unreachable!("remote resources are fetched above"),
             }
+        }
 
-            Ok(out)
+        Ok(out)
+    }
+
+    /// Removes the files at the root of the out dir matching the [`DEFAULT_RESOURCES_IGNORE`] patterns
+    /// from the expanded `resources`, warning about each of them.
+    fn without_packaging_outputs(
+        resources: Vec<ResolvedResource>,
+        outputs: &Option<(PathBuf, IgnorePatterns)>,
+    ) -> Vec<ResolvedResource> {
+        let Some((out_dir, patterns)) = outputs else {
+            return resources;
+        };
+        resources
+            .into_iter()
+            .filter(|r| {
+                // the outputs are written at the root of the out dir
+                let excluded = r.src.parent() == Some(out_dir)
+                    && r.src
+                        .file_name()
+                        .is_some_and(|name| patterns.is_ignored(Path::new(name)));
+                if excluded {
+                    tracing::warn!(
+                        "Excluding the resource {}, an output of a previous packaging run, set `include_packaging_outputs` to include it",
+                        r.src.display()
+                    );
+                }
+                !excluded
+            })
+            .collect()
+    }
+
+    /// Returns the entries of the [`StagingDir::manifest`], as paths relative to the staging directory
+    /// mapped to their absolute installation path.
+    fn staging_manifest(&self) -> crate::Result<Vec<(PathBuf, String)>> {
+        let Some(manifest) = self
+            .from_staging_dir
+            .as_ref()
+            .and_then(|s| s.manifest.as_ref())
+        else {
+            return Ok(Vec::new());
+        };
+        let contents =
+            fs::read_to_string(manifest).map_err(|e| Error::IoWithPath(manifest.clone(), e))?;
+        let entries: HashMap<String, String> = serde_json::from_str(&contents)?;
+
+        let mut out = Vec::new();
+        for (src, target) in entries {
+            let src_path = PathBuf::from(&src);
+            let is_normal = |path: &Path| {
+                path.components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)))
+            };
+            if src.is_empty() || !is_normal(&src_path) {
+                return Err(Error::UnsafeStagingPath(src));
+            }
+            // the installation paths are Unix paths, absolute on every host
+            if !target.starts_with('/') || !is_normal(Path::new(target.trim_start_matches('/'))) {
+                return Err(Error::UnsafeStagingPath(target));
+            }
+            out.push((src_path, target));
+        }
+        out.sort();
+        Ok(out)
+    }
+
+    /// Returns the files of the [`Config::from_staging_dir`] at their path in the tree,
+    /// except the system files of its manifest and the manifest itself.
+    fn staged_resources(
+        &self,
+        ignore: &IgnorePatterns,
+        follow_symlinks: bool,
+    ) -> crate::Result<Vec<ResolvedResource>> {
+        let Some(staging_dir) = &self.from_staging_dir else {
+            return Ok(Vec::new());
+        };
+        let system_files = self.staging_manifest()?;
+        let manifest = staging_dir
+            .manifest
+            .as_ref()
+            .and_then(|m| dunce::canonicalize(m).ok());
+
+        let mut out =
+            Self::resources_from_dir(&staging_dir.path, Path::new(""), ignore, follow_symlinks)?;
+        out.retain(|r| {
+            Some(&r.src) != manifest.as_ref()
+                && !system_files
+                    .iter()
+                    .any(|(src, _)| r.target.starts_with(src))
+        });
+        Ok(out)
+    }
+
+    /// Returns the system files of the [`Config::from_staging_dir`], mapped to their absolute installation path
+    /// like the [`DebianConfig::files`].
+    #[allow(unused)]
+    pub(crate) fn staged_system_files(&self) -> crate::Result<HashMap<String, String>> {
+        let Some(staging_dir) = &self.from_staging_dir else {
+            return Ok(HashMap::new());
+        };
+        Ok(self
+            .staging_manifest()?
+            .into_iter()
+            .map(|(src, target)| (staging_dir.path.join(src).display().to_string(), target))
+            .collect())
+    }
+
+    /// Checks the [`Config::from_staging_dir`] is a directory with files,
+    /// whose symbolic links and manifest entries stay inside of it.
+    pub(crate) fn validate_staging_dir(&self) -> crate::Result<()> {
+        let Some(staging_dir) = &self.from_staging_dir else {
+            return Ok(());
+        };
+        let path = &staging_dir.path;
+        if !path.exists() {
+            return Err(Error::DoesNotExist(path.clone()));
+        }
+        if !path.is_dir() {
+            return Err(Error::IsNotDirectory(path.clone()));
+        }
+        let root = dunce::canonicalize(path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+
+        let mut empty = true;
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry?;
+            if entry.path_is_symlink() {
+                let escapes = dunce::canonicalize(entry.path())
+                    .map(|target| !target.starts_with(&root))
+                    .unwrap_or(true);
+                if escapes {
+                    return Err(Error::UnsafeStagingPath(entry.path().display().to_string()));
+                }
+            }
+            empty &= !entry.path().is_file();
+        }
+        if empty {
+            return Err(Error::EmptyStagingDir(path.clone()));
+        }
+
+        for (src, _) in self.staging_manifest()? {
+            let src = path.join(src);
+            if !src.exists() {
+                return Err(Error::DoesNotExist(src));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the cached file of a [`Resource::Remote`], downloading it first if needed.
+    fn fetch_remote_resource(&self, url: &str, sha256: &str) -> crate::Result<PathBuf> {
+        use sha2::{Digest, Sha256};
+        use std::io::Write;
+
+        let parsed_url = Url::parse(url)?;
+        let file_name = parsed_url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("resource");
+        let sha256 = sha256.to_lowercase();
+        let cache_dir = dirs::cache_dir()
+            .unwrap_or_else(|| self.out_dir())
+            .join(".cargo-packager/resources")
+            .join(&sha256);
+        let path = cache_dir.join(file_name);
+        if path.is_file() {
+            tracing::debug!("Using the cached resource {}", path.display());
+            return Ok(path);
+        }
+
+        let data = if parsed_url.scheme() == "file" {
+            let src = parsed_url
+                .to_file_path()
+                .map_err(|_| Error::DoesNotExist(url.into()))?;
+            fs::read(&src).map_err(|e| Error::IoWithPath(src, e))?
         } else {
-            Ok(vec![])
+            util::download(url)?
+        };
+
+        let actual = hex::encode(Sha256::digest(&data));
+        if actual != sha256 {
+            return Err(Error::ChecksumMismatch {
+                url: url.into(),
+                expected: sha256,
+                actual,
+            });
         }
+
+        // the file only appears in the cache once verified
+        fs::create_dir_all(&cache_dir).map_err(|e| Error::IoWithPath(cache_dir.clone(), e))?;
+        let mut tmp = tempfile::NamedTempFile::new_in(&cache_dir)?;
+        tmp.write_all(&data)?;
+        tmp.persist(&path)
+            .map_err(|e| Error::IoWithPath(path.clone(), e.error))?;
+        Ok(path)
     }
 
     #[allow(unused)]
@@ -2046,20 +5863,164 @@ impl Config {
         Ok(icon)
     }
 
+    /// Returns the files of the [`Config::bundled_runtime`],
+    /// with their targets relative to the directory the runtime is placed in.
+    pub(crate) fn bundled_runtime(&self) -> crate::Result<Vec<ResolvedResource>> {
+        self.resolve_bundled_runtime(true)
+    }
+
+    fn resolve_bundled_runtime(
+        &self,
+        follow_symlinks: bool,
+    ) -> crate::Result<Vec<ResolvedResource>> {
+        let Some(runtime) = &self.bundled_runtime else {
+            return Ok(Vec::new());
+        };
+        if !runtime.exists() {
+            return Err(Error::DoesNotExist(runtime.clone()));
+        }
+        if !runtime.is_dir() {
+            return Err(Error::IsNotDirectory(runtime.clone()));
+        }
+
+        let name = Path::new(
+            runtime
+                .file_name()
+                .ok_or_else(|| Error::FailedToExtractFilename(runtime.clone()))?,
+        );
+        let bin_dir = name.join("bin");
+        let mut files =
+            Self::resources_from_dir(runtime, name, &IgnorePatterns::default(), follow_symlinks)?;
+        for file in &mut files {
+            if file.target.starts_with(&bin_dir) {
+                file.mode = Some(0o755);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Copies the resources of the packages of `format` into `path`,
+    /// following their symlinks as configured by [`Config::follow_symlinks`].
+    #[allow(unused)]
+    pub(crate) fn copy_resources(&self, path: &Path, format: PackageFormat) -> crate::Result<()> {
+        copy_resolved_resources(self.resolve_resources(self.follows_symlinks(format))?, path)
+    }
+
+    /// Copies the [`Config::bundled_runtime`] of the packages of `format` into `path`.
     #[allow(unused)]
-    pub(crate) fn copy_resources(&self, path: &Path) -> crate::Result<()> {
-        for resource in self.resources()? {
-            let dest = path.join(resource.target);
-            fs::create_dir_all(
-                dest.parent()
-                    .ok_or_else(|| crate::Error::ParentDirNotFound(dest.to_path_buf()))?,
-            )?;
-            fs::copy(&resource.src, &dest)
-                .map_err(|e| Error::CopyFile(resource.src.clone(), dest.clone(), e))?;
+    pub(crate) fn copy_bundled_runtime(
+        &self,
+        path: &Path,
+        format: PackageFormat,
+    ) -> crate::Result<()> {
+        copy_resolved_resources(
+            self.resolve_bundled_runtime(self.follows_symlinks(format))?,
+            path,
+        )
+    }
+
+    /// Checks the [`Config::launcher_shim`] targets Linux or macOS, with relative paths inside of the resources,
+    /// valid environment variable names and an existing template.
+    pub(crate) fn validate_launcher_shim(&self) -> crate::Result<()> {
+        let Some(shim) = &self.launcher_shim else {
+            return Ok(());
+        };
+        let invalid = |reason: &str| Err(Error::InvalidLauncherShim(reason.into()));
+
+        if self.target_triple().contains("windows") {
+            return invalid("Windows targets aren't supported");
+        }
+        let inside = |path: &Path| {
+            path.components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+                && path.components().next().is_some()
+        };
+        if !inside(&shim.interpreter) {
+            return invalid("the interpreter must be a relative path inside of the resources");
+        }
+        if shim.entrypoint.as_deref().is_some_and(|p| !inside(p)) {
+            return invalid("the entrypoint must be a relative path inside of the resources");
+        }
+        let valid_name = |name: &str| {
+            !name.starts_with(|c: char| c.is_ascii_digit())
+                && !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if let Some(name) = shim
+            .env
+            .iter()
+            .flatten()
+            .map(|(name, _)| name)
+            .find(|name| !valid_name(name))
+        {
+            return invalid(&format!("invalid environment variable name `{name}`"));
+        }
+        if let Some(template) = shim.template.as_ref().filter(|t| !t.is_file()) {
+            return Err(Error::DoesNotExist(template.clone()));
         }
         Ok(())
     }
 
+    /// Fails with [`Error::DoesNotExist`] when the [`Config::release_notes`] file doesn't exist.
+    pub(crate) fn validate_release_notes(&self) -> crate::Result<()> {
+        match &self.release_notes {
+            Some(ReleaseNotes::File(path)) if !path.is_file() => {
+                Err(Error::DoesNotExist(path.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the [`Config::release_notes`] as a resource targeting [`RELEASE_NOTES_FILE_NAME`],
+    /// writing the inline ones into the intermediates directory of the packages.
+    pub(crate) fn release_notes(&self) -> crate::Result<Option<ResolvedResource>> {
+        let src = match &self.release_notes {
+            Some(ReleaseNotes::File(path)) => {
+                dunce::canonicalize(path).map_err(|e| Error::IoWithPath(path.clone(), e))?
+            }
+            Some(ReleaseNotes::Text(text)) => {
+                let path = self
+                    .out_dir()
+                    .join(".cargo-packager")
+                    .join(RELEASE_NOTES_FILE_NAME);
+                let mut file = util::create_file(&path)?;
+                io::Write::write_all(&mut file, text.as_bytes())?;
+                io::Write::flush(&mut file)?;
+                path
+            }
+            None => return Ok(None),
+        };
+        Ok(Some(ResolvedResource {
+            src,
+            target: RELEASE_NOTES_FILE_NAME.into(),
+            mode: None,
+            symlink: None,
+        }))
+    }
+
+    /// Copies the [`Config::release_notes`] into `path`.
+    pub(crate) fn copy_release_notes(&self, path: &Path) -> crate::Result<()> {
+        copy_resolved_resources(self.release_notes()?.into_iter().collect(), path)
+    }
+
+    /// Returns the paths of the [`Config::external_binaries`],
+    /// with their `-<target-triple><.exe>` suffix.
+    pub(crate) fn external_binaries_paths(&self) -> crate::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        let target_triple = self.target_triple();
+        for src in self.external_binaries.iter().flatten() {
+            let file_name = src
+                .file_name()
+                .ok_or_else(|| crate::Error::FailedToExtractFilename(src.clone()))?
+                .to_string_lossy();
+            #[cfg(windows)]
+            paths.push(src.with_file_name(format!("{file_name}-{target_triple}.exe")));
+            #[cfg(not(windows))]
+            paths.push(src.with_file_name(format!("{file_name}-{target_triple}")));
+        }
+        Ok(paths)
+    }
+
     #[allow(unused)]
     pub(crate) fn copy_external_binaries(&self, path: &Path) -> crate::Result<Vec<PathBuf>> {
         let mut paths = Vec::new();
@@ -2101,3 +6062,689 @@ fn sanitize_path<P: AsRef<Path>>(path: P) -> PathBuf {
 fn default_true() -> bool {
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ignore::IGNORE_FILE_NAME;
+
+    #[test]
+    fn it_parses_file_modes() {
+        assert_eq!(parse_file_mode("0755").unwrap(), 0o755);
+        assert_eq!(parse_file_mode("644").unwrap(), 0o644);
+        assert_eq!(parse_file_mode("0o4755").unwrap(), 0o4755);
+        for invalid in ["", "0o", "0789", "rwxr-xr-x", "17777", "-755"] {
+            assert!(parse_file_mode(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn it_ignores_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(assets.join("images/.cache")).unwrap();
+        for file in [
+            "images/logo.png",
+            "images/.DS_Store",
+            "images/.cache/thumb.png",
+            "config.json",
+            "config.json~",
+            "debug.log",
+        ] {
+            fs::write(assets.join(file), "").unwrap();
+        }
+        fs::write(assets.join(IGNORE_FILE_NAME), "*.log\n.cache/\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        fs::write(dir.path().join("notes.txt~"), "").unwrap();
+
+        let config = Config {
+            resources: Some(vec![
+                Resource::Single(assets.display().to_string()),
+                Resource::Single(dir.path().join("notes.txt*").display().to_string()),
+            ]),
+            resources_ignore: Some(vec![".DS_Store".into(), "*~".into()]),
+            ..Default::default()
+        };
+
+        let out_dir = dir.path().join("out");
+        config.copy_resources(&out_dir, PackageFormat::Deb).unwrap();
+        let mut files = walkdir::WalkDir::new(&out_dir)
+            .into_iter()
+            .map(|e| e.unwrap())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| {
+                e.path()
+                    .strip_prefix(&out_dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            ["assets/config.json", "assets/images/logo.png", "notes.txt"]
+        );
+    }
+
+    #[test]
+    fn it_ignores_previous_packaging_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let dist = dir.path().join("dist");
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(dist.join("sub")).unwrap();
+        fs::create_dir_all(&assets).unwrap();
+        for file in [
+            "data.bin",
+            "data.bin.sig",
+            "data.bin.sha256",
+            "app.deb.intoto.json",
+            "latest.json",
+        ] {
+            fs::write(dist.join(file), "").unwrap();
+        }
+        // the files matching the patterns outside of the root of the out dir are kept
+        fs::write(dist.join("sub/latest.json"), "").unwrap();
+        fs::write(assets.join("data.bin.sig"), "").unwrap();
+
+        let mut config = Config {
+            out_dir: dist.clone(),
+            resources: Some(vec![
+                Resource::Single(dist.display().to_string()),
+                Resource::Single(assets.display().to_string()),
+            ]),
+            ..Default::default()
+        };
+        let files = |config: &Config| {
+            let mut files = config
+                .resources()
+                .unwrap()
+                .into_iter()
+                .map(|r| r.target.to_string_lossy().replace('\\', "/"))
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        };
+        assert_eq!(
+            files(&config),
+            [
+                "assets/data.bin.sig",
+                "dist/data.bin",
+                "dist/sub/latest.json"
+            ]
+        );
+
+        config.resources_ignore = Some(vec!["!latest.json".into()]);
+        assert_eq!(
+            files(&config),
+            [
+                "assets/data.bin.sig",
+                "dist/data.bin",
+                "dist/latest.json",
+                "dist/sub/latest.json"
+            ]
+        );
+
+        config.include_packaging_outputs = true;
+        assert_eq!(
+            files(&config),
+            [
+                "assets/data.bin.sig",
+                "dist/app.deb.intoto.json",
+                "dist/data.bin",
+                "dist/data.bin.sha256",
+                "dist/data.bin.sig",
+                "dist/latest.json",
+                "dist/sub/latest.json"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_preserves_or_follows_resource_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(assets.join("lib")).unwrap();
+        fs::write(assets.join("lib/libfoo.so.1"), "foo").unwrap();
+        fs::write(dir.path().join("outside.txt"), "outside").unwrap();
+        symlink("libfoo.so.1", assets.join("lib/libfoo.so")).unwrap();
+        symlink("lib", assets.join("current")).unwrap();
+        symlink("../outside.txt", assets.join("outside.txt")).unwrap();
+
+        let config = Config {
+            resources: Some(vec![Resource::Single(assets.display().to_string())]),
+            follow_symlinks_per_format: Some(HashMap::from([(PackageFormat::Shar, true)])),
+            ..Default::default()
+        };
+        assert!(!config.follows_symlinks(PackageFormat::Deb));
+        assert!(config.follows_symlinks(PackageFormat::Zip));
+        assert!(config.follows_symlinks(PackageFormat::Shar));
+
+        let preserved = dir.path().join("preserved");
+        config
+            .copy_resources(&preserved, PackageFormat::Deb)
+            .unwrap();
+        let link = |path: &str| fs::read_link(preserved.join(path)).ok();
+        assert_eq!(link("assets/lib/libfoo.so"), Some("libfoo.so.1".into()));
+        assert_eq!(link("assets/current"), Some("lib".into()));
+        // the symlink escaping the resource directory is followed
+        assert_eq!(link("assets/outside.txt"), None);
+        assert_eq!(
+            fs::read_to_string(preserved.join("assets/outside.txt")).unwrap(),
+            "outside"
+        );
+
+        let followed = dir.path().join("followed");
+        config
+            .copy_resources(&followed, PackageFormat::Shar)
+            .unwrap();
+        for path in ["assets/lib/libfoo.so", "assets/current/libfoo.so.1"] {
+            let path = followed.join(path);
+            assert!(!path.is_symlink(), "{}", path.display());
+            assert_eq!(fs::read_to_string(path).unwrap(), "foo");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_rejects_resource_symlink_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(assets.join("nested")).unwrap();
+        std::os::unix::fs::symlink("..", assets.join("nested/parent")).unwrap();
+
+        let config = Config {
+            resources: Some(vec![Resource::Single(assets.display().to_string())]),
+            follow_symlinks: Some(true),
+            ..Default::default()
+        };
+        let out_dir = dir.path().join("out");
+        assert!(matches!(
+            config.copy_resources(&out_dir, PackageFormat::Deb),
+            Err(Error::ResourceSymlinkLoop(_))
+        ));
+
+        // the symlink to a parent inside the directory is preserved instead
+        let config = Config {
+            follow_symlinks: Some(false),
+            ..config
+        };
+        config.copy_resources(&out_dir, PackageFormat::Deb).unwrap();
+        assert_eq!(
+            fs::read_link(out_dir.join("assets/nested/parent")).unwrap(),
+            Path::new("..")
+        );
+    }
+
+    #[test]
+    fn it_skips_missing_optional_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let readme = dir.path().join("README.md");
+        let license = dir.path().join("LICENSE");
+        fs::write(&readme, "").unwrap();
+
+        let mut config: Config = serde_json::from_value(serde_json::json!({
+            "resources": [
+                readme,
+                { "src": license, "target": "LICENSE", "optional": true },
+                dir.path().join("*.txt"),
+            ]
+        }))
+        .unwrap();
+        let resources = config.resources().unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].target, Path::new("README.md"));
+
+        config.resources = Some(vec![Resource::Single(license.display().to_string())]);
+        assert!(matches!(
+            config.resources(),
+            Err(Error::DoesNotExist(path)) if path == license
+        ));
+    }
+
+    #[test]
+    fn it_fetches_remote_resources() {
+        use sha2::{Digest, Sha256};
+
+        let dir = tempfile::tempdir().unwrap();
+        let asset = dir.path().join("model.bin");
+        // unique per run so it isn't in the cache yet
+        let data = format!("model {}", dir.path().display());
+        fs::write(&asset, &data).unwrap();
+        let sha256 = hex::encode(Sha256::digest(&data));
+        let url = Url::from_file_path(&asset).unwrap().to_string();
+
+        let mut config: Config = serde_json::from_value(serde_json::json!({
+            "resources": [
+                { "url": url, "sha256": sha256.to_uppercase(), "target": "assets/model.bin", "mode": "0600" },
+            ]
+        }))
+        .unwrap();
+        let resources = config.resources().unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].target, Path::new("assets/model.bin"));
+        assert_eq!(resources[0].mode, Some(0o600));
+        assert_eq!(fs::read_to_string(&resources[0].src).unwrap(), data);
+        let cache_dir = resources[0].src.parent().unwrap().to_path_buf();
+        assert!(cache_dir.ends_with(&sha256));
+
+        // the cached download is reused
+        fs::remove_file(&asset).unwrap();
+        assert_eq!(config.resources().unwrap()[0].src, resources[0].src);
+        fs::remove_dir_all(&cache_dir).unwrap();
+
+        fs::write(&asset, "tampered").unwrap();
+        config.resources = Some(vec![Resource::Remote {
+            url: url.clone(),
+            sha256: sha256.clone(),
+            target: "assets/model.bin".into(),
+            mode: None,
+        }]);
+        assert!(matches!(
+            config.resources(),
+            Err(Error::ChecksumMismatch { url: u, expected, .. }) if u == url && expected == sha256
+        ));
+        assert!(!cache_dir.exists());
+    }
+
+    #[test]
+    fn it_validates_bundle_identifier() {
+        let mut config = Config::default();
+        for identifier in ["com.example.app", "com.Example.my-app2", "io.app"] {
+            config.identifier = Some(identifier.into());
+            assert_eq!(config.bundle_identifier().unwrap(), identifier);
+        }
+        for identifier in [
+            "",
+            "app",
+            "com.example_inc.app",
+            "com..app",
+            "com.example.app.",
+            "com.exämple.app",
+        ] {
+            config.identifier = Some(identifier.into());
+            assert!(
+                matches!(
+                    config.bundle_identifier(),
+                    Err(Error::InvalidBundleIdentifier(i)) if i == identifier
+                ),
+                "{identifier}"
+            );
+        }
+
+        config.identifier = None;
+        assert!(matches!(
+            config.bundle_identifier(),
+            Err(Error::InvalidBundleIdentifier(_))
+        ));
+        config.publisher = Some("Example Inc.".into());
+        config.product_name = "My_App 2".into();
+        assert_eq!(
+            config.bundle_identifier().unwrap(),
+            "com.example-inc.my-app-2"
+        );
+    }
+
+    #[test]
+    fn it_validates_pkg_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let postinstall = dir.path().join("postinstall.sh");
+        let preinstall = dir.path().join("preinstall.sh");
+        fs::write(&postinstall, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::write(&preinstall, "exit 0\n").unwrap();
+
+        let mut config = Config::default();
+        assert_eq!(
+            config.pkg_install_location().unwrap(),
+            Path::new("/Applications")
+        );
+        assert!(config.pkg_scripts().unwrap().is_empty());
+
+        config.pkg = Some(PkgConfig::new().postinstall_script(&postinstall));
+        assert_eq!(
+            config.pkg_scripts().unwrap(),
+            [("postinstall", postinstall.clone())]
+        );
+
+        config.pkg = Some(PkgConfig::new().install_location("Applications"));
+        assert!(matches!(
+            config.pkg_install_location(),
+            Err(Error::InvalidPkgInstallLocation(_))
+        ));
+
+        config.pkg = Some(PkgConfig::new().preinstall_script(&preinstall));
+        assert!(matches!(
+            config.pkg_scripts(),
+            Err(Error::InvalidPkgScript(path)) if path == preinstall
+        ));
+
+        let missing = dir.path().join("missing.sh");
+        config.pkg = Some(PkgConfig::new().postinstall_script(&missing));
+        assert!(matches!(
+            config.pkg_scripts(),
+            Err(Error::DoesNotExist(path)) if path == missing
+        ));
+    }
+
+    #[test]
+    fn it_builds_flavors() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "productName": "My App",
+                "version": "1.0.0",
+                "identifier": "com.example.app",
+                "outDir": "dist",
+                "flavors": {
+                    "stable": {},
+                    "nightly": {
+                        "productName": "My App Nightly",
+                        "identifier": "com.example.app.nightly",
+                        "icons": ["icons/nightly.png"],
+                        "versionSuffix": "-nightly"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let flavors = config.flavor_configs(None).unwrap();
+        let [nightly, stable] = flavors.as_slice() else {
+            panic!("expected two flavors");
+        };
+        assert_eq!(stable.identifier(), "com.example.app");
+        assert_eq!(nightly.identifier(), "com.example.app.nightly");
+        assert_eq!(nightly.product_name, "My App Nightly");
+        assert_eq!(nightly.version, "1.0.0-nightly");
+        assert_eq!(nightly.icons, Some(vec!["icons/nightly.png".into()]));
+        assert_eq!(stable.out_dir, Path::new("dist/stable"));
+        assert_eq!(nightly.out_dir, Path::new("dist/nightly"));
+        assert_eq!(nightly.binaries_dir, Some(PathBuf::from("dist")));
+        assert!(nightly.flavors.is_none());
+
+        let selected = config.flavor_configs(Some(&["stable".into()])).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].out_dir, Path::new("dist/stable"));
+        assert!(matches!(
+            config.flavor_configs(Some(&["beta".into()])),
+            Err(Error::UnknownFlavor(name)) if name == "beta"
+        ));
+        assert_eq!(
+            Config::default().flavor_configs(None).unwrap()[0].out_dir,
+            PathBuf::new()
+        );
+
+        assert!(serde_json::from_str::<Config>(
+            r#"{ "flavors": { "nightly": { "description": "Nightly builds" } } }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_signs_flavors_with_their_own_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let stable_key = crate::sign::generate_key_unencrypted().unwrap();
+        let beta_key = crate::sign::generate_key(Some("beta".into())).unwrap();
+
+        let config = Config {
+            version: "1.0.0".into(),
+            out_dir: dir.path().into(),
+            signing: Some(SigningConfig::new().private_key(&stable_key.sk)),
+            flavors: Some(HashMap::from([
+                ("stable".into(), Flavor::new()),
+                (
+                    "beta".into(),
+                    Flavor::new().version_suffix("-beta").signing(
+                        SigningConfig::new()
+                            .private_key(&beta_key.sk)
+                            .password("beta")
+                            .trusted_comment("channel:{channel}"),
+                    ),
+                ),
+            ])),
+            ..Default::default()
+        };
+
+        let flavors = config.flavor_configs(None).unwrap();
+        let [beta, stable] = flavors.as_slice() else {
+            panic!("expected two flavors");
+        };
+        for (flavor, key, other_key) in [
+            (stable, &stable_key, &beta_key),
+            (beta, &beta_key, &stable_key),
+        ] {
+            let signing = flavor.signing.as_ref().unwrap();
+            signing.validate().unwrap();
+
+            fs::create_dir_all(&flavor.out_dir).unwrap();
+            let artifact = flavor.out_dir.join("app.tar.gz");
+            fs::write(&artifact, &flavor.version).unwrap();
+            crate::sign::sign_file(signing, &artifact).unwrap();
+            crate::sign::verify_file(&artifact, &key.pk).unwrap();
+            assert!(crate::sign::verify_file(&artifact, &other_key.pk).is_err());
+        }
+        assert_eq!(
+            crate::sign::verify_file(beta.out_dir.join("app.tar.gz"), &beta_key.pk).unwrap(),
+            "channel:beta"
+        );
+
+        let wrong_password = SigningConfig::new()
+            .private_key(&beta_key.sk)
+            .password("stable");
+        assert!(matches!(
+            wrong_password.validate(),
+            Err(Error::WrongSigningKeyPassword)
+        ));
+        // the password of an encrypted key is prompted for when signing
+        assert!(SigningConfig::new()
+            .private_key(&beta_key.sk)
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn it_validates_wix_install_dir_name() {
+        let mut config = Config {
+            product_name: "My App".into(),
+            ..Default::default()
+        };
+        assert_eq!(config.wix_install_dir_name().unwrap(), "My App");
+
+        config.wix = Some(WixConfig::new().install_dir_name("Example Inc. App"));
+        assert_eq!(config.wix_install_dir_name().unwrap(), "Example Inc. App");
+
+        for name in ["", " ", "Example\\App", "App/1", "App:", "App.", "App?"] {
+            config.wix = Some(WixConfig::new().install_dir_name(name));
+            assert!(
+                matches!(
+                    config.wix_install_dir_name(),
+                    Err(Error::InvalidWixInstallDirName(n)) if n == name
+                ),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_validates_install_path_lengths() {
+        let dir = tempfile::tempdir().unwrap();
+        let resource = dir.path().join("asset.bin");
+        fs::write(&resource, "asset").unwrap();
+
+        let mut config = Config {
+            product_name: "My App".into(),
+            binaries: vec![Binary::new("my-app").main(true)],
+            ..Default::default()
+        };
+        let target = Path::new("assets").join("a".repeat(180)).join("asset.bin");
+        config.resources = Some(vec![Resource::Mapped {
+            src: resource.display().to_string(),
+            target: target.clone(),
+            mode: None,
+            optional: false,
+        }]);
+        // not installed by a Windows installer
+        config
+            .validate_install_path_lengths(&[PackageFormat::Deb])
+            .unwrap();
+        // `C:\Users\<20 characters>\AppData\Local\Programs\My App\assets\<180 a>\asset.bin`
+        config
+            .validate_install_path_lengths(&[PackageFormat::Nsis])
+            .unwrap();
+
+        let long_name = "Example Inc. My Application With A Long Name";
+        config.wix = Some(WixConfig::new().install_dir_name(long_name));
+        let err = config
+            .validate_install_path_lengths(&[PackageFormat::Nsis, PackageFormat::Wix])
+            .unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::PathTooLong { path, length, limit: 260 }
+                    if path.ends_with(&format!("\\{long_name}\\assets\\{}\\asset.bin", "a".repeat(180)))
+                        && *length == path.len() + 1
+                        && *length > 260
+            ),
+            "{err}"
+        );
+
+        config.windows = Some(WindowsConfig::new().long_path_aware(true));
+        config
+            .validate_install_path_lengths(&[PackageFormat::Wix])
+            .unwrap();
+        config.windows = Some(
+            WindowsConfig::new()
+                .long_path_aware(true)
+                .max_path_length(100),
+        );
+        assert!(matches!(
+            config.validate_install_path_lengths(&[PackageFormat::InnoSetup]),
+            Err(Error::PathTooLong { limit: 100, .. })
+        ));
+    }
+
+    /// Builds a BMP image header of `width`×`height` pixels.
+    fn bmp(width: i32, height: i32) -> Vec<u8> {
+        let mut data = b"BM".to_vec();
+        data.extend([0; 12]);
+        data.extend(40u32.to_le_bytes());
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        data.extend([0; 28]);
+        data
+    }
+
+    #[test]
+    fn it_validates_wix_bitmaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let banner = dir.path().join("banner.bmp");
+        let dialog = dir.path().join("dialog.bmp");
+        std::fs::write(&banner, bmp(493, 58)).unwrap();
+        // top-down bitmap
+        std::fs::write(&dialog, bmp(493, -312)).unwrap();
+
+        let mut config = Config::default();
+        config.wix_bitmaps().unwrap();
+        config.wix = Some(
+            WixConfig::new()
+                .banner_path(&banner)
+                .dialog_image_path(&dialog),
+        );
+        config.wix_bitmaps().unwrap();
+
+        std::fs::write(&dialog, bmp(500, 312)).unwrap();
+        let err = config.wix_bitmaps().unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidWixBitmap(path, 493, 312, _) if path == &dialog),
+            "{err}"
+        );
+        assert!(err.to_string().contains("found 500×312 pixels"));
+
+        std::fs::write(&banner, b"\x89PNG\r\n").unwrap();
+        assert!(matches!(
+            config.wix_bitmaps(),
+            Err(Error::InvalidWixBitmap(path, 493, 58, _)) if path == banner
+        ));
+    }
+
+    #[test]
+    fn it_validates_nsis_compression() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.nsis_compression().unwrap(),
+            (NsisCompression::Lzma, true)
+        );
+
+        config.nsis = Some(
+            NsisConfig::new()
+                .compression(NsisCompression::Zlib)
+                .solid(false),
+        );
+        assert_eq!(
+            config.nsis_compression().unwrap(),
+            (NsisCompression::Zlib, false)
+        );
+
+        config.nsis = Some(NsisConfig::new().compression(NsisCompression::Off));
+        assert_eq!(
+            config.nsis_compression().unwrap(),
+            (NsisCompression::Off, false)
+        );
+
+        config.nsis = Some(
+            NsisConfig::new()
+                .compression(NsisCompression::Off)
+                .solid(true),
+        );
+        assert!(matches!(
+            config.nsis_compression(),
+            Err(Error::InvalidNsisSolidCompression)
+        ));
+    }
+
+    #[test]
+    fn it_maps_publisher_to_formats() {
+        let config: Config =
+            serde_json::from_str(r#"{ "identifier": "com.example.app", "vendor": "Example Inc" }"#)
+                .unwrap();
+        assert_eq!(config.publisher(), "Example Inc");
+        assert_eq!(config.wix_manufacturer().unwrap(), "Example Inc");
+        assert_eq!(config.nsis_publisher().unwrap(), "Example Inc");
+
+        let config = Config {
+            identifier: Some("com.example.app".into()),
+            ..Default::default()
+        };
+        assert_eq!(config.wix_manufacturer().unwrap(), "example");
+
+        let config = Config {
+            wix: Some(WixConfig::new().manufacturer("Example MSI")),
+            nsis: Some(NsisConfig::new().publisher("Example NSIS")),
+            ..config
+        };
+        assert_eq!(config.wix_manufacturer().unwrap(), "Example MSI");
+        assert_eq!(config.nsis_publisher().unwrap(), "Example NSIS");
+
+        let config = Config::default();
+        assert!(matches!(
+            config.wix_manufacturer(),
+            Err(Error::MissingPublisher("wix"))
+        ));
+        assert!(matches!(
+            config.nsis_publisher(),
+            Err(Error::MissingPublisher("nsis"))
+        ));
+
+        let config = Config {
+            wix: Some(WixConfig::new().manufacturer(" ")),
+            publisher: Some("Example Inc".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.wix_manufacturer(),
+            Err(Error::MissingPublisher("wix"))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Delta updates, binary patches turning the artifact of a previous version into the new one.
+//!
+//! [`make_delta`] writes a bsdiff delta next to the new artifact, named `<artifact>.<old digest>.delta`
+//! after the first 16 hex digits of the SHA-256 digest of the old artifact.
+//! [`delta_outputs`] makes the deltas of the update artifacts, signs them and lists them
+//! in the `deltas` of their package summary in `latest.json`, as an alternative to the full download:
+//!
+//! ```json
+//! {
+//!   "url": "https://example.com/app_1.1.0_x86_64.AppImage",
+//!   "signature": "Content of app_1.1.0_x86_64.AppImage.sig",
+//!   "format": "appimage",
+//!   "deltas": [
+//!     {
+//!       "url": "https://example.com/app_1.1.0_x86_64.AppImage.3a7bd3e2360a3d29.delta",
+//!       "signature": "Content of app_1.1.0_x86_64.AppImage.3a7bd3e2360a3d29.delta.sig",
+//!       "from_sha256": "3a7bd3e2360a3d29eea436fcfb7e44c735d117c42d1c1835420b6b9942dd4f1b"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The updater picks the delta made from the SHA-256 digest of its installed artifact,
+//! and verifies the patched artifact against the signature of the full download.
+
+use std::{
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cargo_packager_utils::delta::{self, DeltaError};
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    checksum, sign,
+    util::{self, PathExt},
+    Error, PackageOutput, SigningConfig,
+};
+
+/// The extension of the deltas written by [`make_delta`].
+pub const DELTA_EXTENSION: &str = "delta";
+
+/// A delta of an update artifact, listed in the `deltas` of its [`PackageOutputSummary`](crate::PackageOutputSummary).
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct DeltaSummary {
+    /// Download URL of the delta.
+    pub url: Url,
+    /// Signature of the delta.
+    pub signature: Option<String>,
+    /// SHA-256 digest of the artifact the delta applies to.
+    pub from_sha256: String,
+}
+
+fn read(path: &Path) -> crate::Result<Vec<u8>> {
+    fs::read(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))
+}
+
+/// Makes the delta turning the `old` artifact into the `new` one and writes it next to `new`,
+/// returning its path.
+#[tracing::instrument(level = "trace")]
+pub fn make_delta<P: AsRef<Path> + Debug, Q: AsRef<Path> + Debug>(
+    old: P,
+    new: Q,
+) -> crate::Result<PathBuf> {
+    let (old, new) = (old.as_ref(), new.as_ref());
+    let old_digest = checksum::sha256_file(old)?;
+    let delta_path =
+        new.with_additional_extension(format!("{}.{DELTA_EXTENSION}", &old_digest[..16]));
+
+    let delta = delta::make_delta(&read(old)?, &read(new)?)?;
+    fs::write(&delta_path, delta).map_err(|e| Error::IoWithPath(delta_path.clone(), e))?;
+    Ok(delta_path)
+}
+
+/// Applies the `delta` made by [`make_delta`] to the `old` artifact and writes the new one into `output`.
+///
+/// Fails with [`Error::DeltaOldArtifactMismatch`] when `old` is not the artifact the delta was made from.
+#[tracing::instrument(level = "trace")]
+pub fn apply_delta<P: AsRef<Path> + Debug, Q: AsRef<Path> + Debug, R: AsRef<Path> + Debug>(
+    old: P,
+    delta: Q,
+    output: R,
+) -> crate::Result<()> {
+    let (old, delta, output) = (old.as_ref(), delta.as_ref(), output.as_ref());
+    let new = delta::apply_delta(&read(old)?, &read(delta)?).map_err(|e| match e {
+        DeltaError::OldFileMismatch { expected, found } => Error::DeltaOldArtifactMismatch {
+            path: old.to_path_buf(),
+            expected,
+            found,
+        },
+        e => Error::InvalidDelta(delta.to_path_buf(), e.to_string()),
+    })?;
+    fs::write(output, new).map_err(|e| Error::IoWithPath(output.to_path_buf(), e))
+}
+
+/// Makes the delta of the update artifact of each package from the `previous` artifact with the same extension,
+/// signs it when `signing_config` is provided, and adds it to the package summary.
+///
+/// The URL of a delta is the URL of its package with the file name replaced.
+/// If `packages` contain a directory in the case of [`PackageFormat::App`](crate::PackageFormat::App)
+/// it will zip the directory and appends it to `packages`, like [`crate::sign_outputs`].
+///
+/// Returns the paths of the deltas and of their signatures.
+#[tracing::instrument(level = "trace")]
+pub fn delta_outputs(
+    packages: &mut Vec<PackageOutput>,
+    previous: &[PathBuf],
+    signing_config: Option<&SigningConfig>,
+) -> crate::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for package in packages {
+        let Some(extension) = sign::primary_extension(package.format) else {
+            continue;
+        };
+        let Some(artifact) = crate::archive_package_dirs(package)?.into_iter().next() else {
+            continue;
+        };
+        let suffix = format!(".{extension}");
+        let Some(old) = previous.iter().find(|p| {
+            p.file_name()
+                .is_some_and(|f| f.to_string_lossy().ends_with(&suffix))
+        }) else {
+            tracing::debug!(
+                "No previous artifact to make the delta of {}",
+                artifact.display()
+            );
+            continue;
+        };
+
+        let delta_path = make_delta(old, &artifact)?;
+        tracing::info!("Made delta: {}", util::display_path(&delta_path));
+        paths.push(delta_path.clone());
+        let signature = match signing_config {
+            Some(signing_config) => {
+                let (signature_path, signature) = sign::sign_file(signing_config, &delta_path)?;
+                paths.push(signature_path);
+                Some(signature)
+            }
+            None => None,
+        };
+
+        if let Some(summary) = &mut package.summary {
+            let mut url = summary.url.clone();
+            if let Ok(mut segments) = url.path_segments_mut() {
+                segments
+                    .pop()
+                    .push(&delta_path.file_name().unwrap_or_default().to_string_lossy());
+            }
+            summary.deltas.push(DeltaSummary {
+                url,
+                signature,
+                from_sha256: checksum::sha256_file(old)?,
+            });
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PackageFormat, PackageOutputSummary};
+
+    #[test]
+    fn it_round_trips_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("app_1.0.0_amd64.AppImage");
+        let new = dir.path().join("app_1.1.0_amd64.AppImage");
+        let old_data = (0..50_000u32)
+            .map(|n| (n * 7 % 251) as u8)
+            .collect::<Vec<_>>();
+        let mut new_data = old_data.clone();
+        new_data[20_000..20_010].copy_from_slice(b"new bytes!");
+        fs::write(&old, &old_data).unwrap();
+        fs::write(&new, &new_data).unwrap();
+
+        let delta = make_delta(&old, &new).unwrap();
+        let digest = checksum::sha256_file(&old).unwrap();
+        assert_eq!(
+            delta.file_name().unwrap().to_string_lossy(),
+            format!("app_1.1.0_amd64.AppImage.{}.delta", &digest[..16])
+        );
+        assert!(fs::metadata(&delta).unwrap().len() < new_data.len() as u64 / 10);
+
+        let patched = dir.path().join("patched");
+        apply_delta(&old, &delta, &patched).unwrap();
+        assert_eq!(fs::read(&patched).unwrap(), new_data);
+
+        assert!(matches!(
+            apply_delta(&new, &delta, &patched),
+            Err(Error::DeltaOldArtifactMismatch { expected, .. }) if expected == digest
+        ));
+    }
+
+    #[test]
+    fn it_lists_signed_deltas_in_the_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("previous/app_1.0.0_amd64.AppImage");
+        let new = dir.path().join("app_1.1.0_amd64.AppImage");
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, "old AppImage").unwrap();
+        fs::write(&new, "new AppImage").unwrap();
+        let keypair = sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+
+        let mut package = PackageOutput::new(PackageFormat::AppImage, vec![new.clone()]);
+        package.summary = Some(PackageOutputSummary {
+            url: "https://example.com/1.1.0/app_1.1.0_amd64.AppImage?token=1"
+                .parse()
+                .unwrap(),
+            signature: None,
+            format: PackageFormat::AppImage,
+            platform: "linux-x86_64".into(),
+            notes: None,
+            blake3: None,
+            deltas: Vec::new(),
+        });
+        let previous = [dir.path().join("previous/app_1.0.0.deb"), old.clone()];
+        let mut packages = vec![package];
+        let paths = delta_outputs(&mut packages, &previous, Some(&signing_config)).unwrap();
+        assert_eq!(paths.len(), 2);
+        sign::verify_file(&paths[0], &keypair.pk).unwrap();
+
+        let deltas = &packages[0].summary.as_ref().unwrap().deltas;
+        let name = paths[0].file_name().unwrap().to_string_lossy();
+        assert_eq!(
+            deltas[0].url.as_str(),
+            format!("https://example.com/1.1.0/{name}?token=1")
+        );
+        assert_eq!(deltas[0].from_sha256, checksum::sha256_file(&old).unwrap());
+        assert!(deltas[0].signature.is_some());
+    }
+}
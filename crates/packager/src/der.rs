@@ -0,0 +1,75 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! A minimal reader of the DER encoding of the signing keys and timestamps.
+
+/// A DER reader over the content of a constructed element.
+///
+/// The lengths are checked against the remaining input, so truncated
+/// or malformed input makes the reads return `None` instead of panicking.
+pub(crate) struct DerReader<'a>(pub &'a [u8]);
+
+impl<'a> DerReader<'a> {
+    /// Returns the tag and the content of the next element.
+    pub fn next(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > std::mem::size_of::<usize>() || rest.len() < count {
+                return None;
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, &b| (len << 8) | b as usize);
+            (len, &rest[count..])
+        };
+        if rest.len() < len {
+            return None;
+        }
+        self.0 = &rest[len..];
+        Some((tag, &rest[..len]))
+    }
+
+    /// Returns the content of the next element, which must have `tag`.
+    pub fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        self.next().filter(|(t, _)| *t == tag).map(|(_, c)| c)
+    }
+
+    /// Returns the content of the next element if it has `tag`.
+    pub fn optional(&mut self, tag: u8) -> Option<&'a [u8]> {
+        let mut peek = DerReader(self.0);
+        let content = peek.expect(tag)?;
+        self.0 = peek.0;
+        Some(content)
+    }
+
+    /// Whether all the elements were read.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rejects_truncated_elements() {
+        // the length is longer than the content
+        assert_eq!(DerReader(&[0x04, 0x03, 0x01]).next(), None);
+        // the length bytes are missing
+        assert_eq!(DerReader(&[0x04, 0x82, 0x01]).next(), None);
+        // an indefinite length
+        assert_eq!(DerReader(&[0x30, 0x80, 0x00, 0x00]).next(), None);
+
+        let mut reader = DerReader(&[0x02, 0x01, 0x05, 0x04, 0x81, 0x01, 0xff]);
+        assert_eq!(reader.optional(0x04), None);
+        assert_eq!(reader.expect(0x02), Some(&[0x05][..]));
+        assert_eq!(reader.next(), Some((0x04, &[0xff][..])));
+        assert!(reader.is_empty());
+    }
+}
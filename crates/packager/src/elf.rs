@@ -0,0 +1,99 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! A minimal reader of the ELF files of the AppImages.
+
+/// The parts of an ELF file read to replace the runtime of an AppImage
+/// and to read its update information.
+///
+/// See <https://refspecs.linuxfoundation.org/elf/gabi4+/ch4.eheader.html>
+pub(crate) struct Elf<'a> {
+    pub data: &'a [u8],
+    is_64: bool,
+    little_endian: bool,
+}
+
+// the runtime is only replaced when packaging AppImages on Linux
+#[cfg_attr(
+    not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )),
+    allow(dead_code)
+)]
+impl<'a> Elf<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, &'static str> {
+        if !data.starts_with(b"\x7fELF") || data.len() < 52 {
+            return Err("not an ELF file");
+        }
+        Ok(Self {
+            data,
+            is_64: data[4] == 2,
+            little_endian: data[5] == 1,
+        })
+    }
+
+    fn uint(&self, offset: usize, size: usize) -> Option<u64> {
+        let bytes = self.data.get(offset..offset + size)?;
+        let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+        Some(if self.little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        })
+    }
+
+    /// Reads a header field, whose offset and size depend on the ELF class.
+    fn field(&self, (offset_32, offset_64): (usize, usize), size_32: usize) -> Option<u64> {
+        if self.is_64 {
+            self.uint(offset_64, if size_32 == 4 { 8 } else { size_32 })
+        } else {
+            self.uint(offset_32, size_32)
+        }
+    }
+
+    pub fn machine(&self) -> Option<u16> {
+        self.uint(18, 2).map(|m| m as u16)
+    }
+
+    fn section_headers(&self) -> Option<(usize, usize, usize)> {
+        let offset = self.field((0x20, 0x28), 4)? as usize;
+        let entry_size = self.field((0x2E, 0x3A), 2)? as usize;
+        let count = self.field((0x30, 0x3C), 2)? as usize;
+        Some((offset, entry_size, count))
+    }
+
+    /// The size of the ELF file, which ends with its section headers.
+    /// This is where the AppImage runtime looks for the squashfs image.
+    pub fn size(&self) -> Option<usize> {
+        let (offset, entry_size, count) = self.section_headers()?;
+        Some(offset + entry_size * count)
+    }
+
+    /// Returns the `(offset, size)` of the section named `name`.
+    pub fn section(&self, name: &str) -> Option<(usize, usize)> {
+        let (offset, entry_size, count) = self.section_headers()?;
+        let header = |index: usize| offset + index * entry_size;
+        // sh_offset and sh_size
+        let range = |index: usize| {
+            Some((
+                self.field((header(index) + 0x10, header(index) + 0x18), 4)? as usize,
+                self.field((header(index) + 0x14, header(index) + 0x20), 4)? as usize,
+            ))
+        };
+
+        let names_index = self.field((0x32, 0x3E), 2)? as usize;
+        let (names_offset, _) = range(names_index)?;
+        (0..count).find_map(|index| {
+            let name_offset = names_offset + self.uint(header(index), 4)? as usize;
+            let section_name = self.data.get(name_offset..)?.split(|b| *b == 0).next()?;
+            (section_name == name.as_bytes())
+                .then(|| range(index))
+                .flatten()
+        })
+    }
+}
@@ -3,8 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use thiserror::Error;
 
 #[non_exhaustive]
@@ -35,6 +36,109 @@ pub enum Error {
     /// I/O symlink file errors.
     #[error("Failed to symlink file from {0} to {1}: {2}")]
     Symlink(PathBuf, PathBuf, std::io::Error),
+    /// A symlink of the resources points to one of its parent directories.
+    #[error(
+        "The resource symlink {0} points to one of its parent directories, following it never ends"
+    )]
+    ResourceSymlinkLoop(PathBuf),
+    /// Not enough space left on the device to write a file.
+    #[error("Not enough space left on the device to write {path}")]
+    OutOfSpace {
+        /// The path of the file being written.
+        path: PathBuf,
+    },
+    /// Some formats failed to package, see [`Config::fail_fast`](crate::Config::fail_fast).
+    #[error(
+        "Failed to package {}",
+        .errors.iter().map(|(format, e)| format!("{}: {e}", format.short_name())).collect::<Vec<_>>().join(", ")
+    )]
+    PackagingFailed {
+        /// The packages of the formats that succeeded.
+        packages: Vec<crate::PackageOutput>,
+        /// The formats that failed, with their error.
+        errors: Vec<(crate::PackageFormat, Error)>,
+    },
+    /// A generated package exceeds [`Config::max_artifact_size`](crate::Config::max_artifact_size).
+    #[error("{path} is {size} bytes, exceeding the maximum artifact size of {limit} bytes")]
+    ArtifactTooLarge {
+        /// The path of the package.
+        path: PathBuf,
+        /// The size of the package in bytes.
+        size: u64,
+        /// The maximum size in bytes.
+        limit: u64,
+    },
+    /// A package built by an external tool is structurally invalid.
+    #[error("The {format:?} package {path} is corrupt: {detail}")]
+    CorruptArtifact {
+        /// The path of the package.
+        path: PathBuf,
+        /// The format of the package.
+        format: crate::PackageFormat,
+        /// What is wrong with the package.
+        detail: String,
+    },
+    /// Failed to generate the software bill of materials.
+    #[error("Failed to generate the SBOM: {0}")]
+    Sbom(String),
+    /// The generated software bill of materials is not well-formed.
+    #[error("The generated SBOM is not well-formed: {0}")]
+    InvalidSbom(String),
+    /// The virus scanner detected something in a package.
+    #[error("The virus scan of {path} failed: {detail}")]
+    ScanFailed {
+        /// The path of the scanned package.
+        path: PathBuf,
+        /// The output of the scanner.
+        detail: String,
+    },
+    /// The virus scanner is not installed.
+    #[error("Could not find the virus scanner `{0}`, make sure it is installed and in the PATH")]
+    ScannerNotFound(String),
+    /// The old artifact of a delta is not the one it was made from.
+    #[error("{path} is not the artifact the delta was made from, expected the SHA-256 digest {expected} but found {found}")]
+    DeltaOldArtifactMismatch {
+        /// The path of the old artifact.
+        path: PathBuf,
+        /// The SHA-256 digest of the artifact the delta was made from.
+        expected: String,
+        /// The SHA-256 digest of the old artifact.
+        found: String,
+    },
+    /// Invalid delta.
+    #[error("Invalid delta {0}: {1}")]
+    InvalidDelta(PathBuf, String),
+    /// The volume size to split a file with is zero.
+    #[error("The volume size to split files with must be positive")]
+    InvalidVolumeSize,
+    /// A downloaded file doesn't match its expected checksum.
+    #[error("The SHA-256 checksum of {url} is `{actual}`, expected `{expected}`")]
+    ChecksumMismatch {
+        /// The URL of the file.
+        url: String,
+        /// The expected checksum.
+        expected: String,
+        /// The checksum of the downloaded file.
+        actual: String,
+    },
+    /// A volume of a split file, or the joined file, doesn't match its checksum.
+    #[error("{0} doesn't match its checksum in the split manifest")]
+    VolumeChecksumMismatch(PathBuf),
+    /// The main binary links shared libraries that won't be present on a clean target system.
+    #[error("{} links shared libraries that won't be present on a clean target system and aren't bundled: {}", .0.display(), .1.join(", "))]
+    UnavailableSharedLibraries(PathBuf, Vec<String>),
+    /// The version of the main binary differs from the config version,
+    /// see [`Config::binary_version_check`](crate::Config::binary_version_check).
+    #[error("The main binary reports the version {binary}, expected the config version {config}")]
+    VersionMismatch {
+        /// The version of the main binary.
+        binary: String,
+        /// The config version.
+        config: String,
+    },
+    /// Failed to read the version of the main binary.
+    #[error("Failed to read the version of {}: {}", .0.display(), .1)]
+    UnknownBinaryVersion(PathBuf, String),
     /// I/O errors.
     #[error(transparent)]
     Io(#[from] std::io::Error),
@@ -56,9 +160,207 @@ pub enum Error {
     /// Windows SignTool not found.
     #[error("SignTool not found")]
     SignToolNotFound,
+    /// Cryptographic provider of the hardware signing key not found.
+    #[error(
+        "Cryptographic provider `{0}` is not registered, make sure its middleware is installed"
+    )]
+    SignCryptoProviderNotFound(String),
+    /// Invalid octal file mode.
+    #[error("Invalid file mode `{0}`, expected an octal mode like `0755`")]
+    InvalidFileMode(String),
+    /// Invalid WiX banner or dialog bitmap.
+    #[error("{0} must be a BMP image of {1}×{2} pixels, {3}")]
+    InvalidWixBitmap(PathBuf, u32, u32, String),
+    /// Invalid build id.
+    #[error(
+        "Invalid build id `{0}`, it must be 1 to 128 ASCII letters, digits, `.`, `-`, `_` or `+`"
+    )]
+    InvalidBuildId(String),
+    /// Invalid keyword in [`Config::keywords`](crate::Config::keywords).
+    #[error("Invalid keyword `{0}`, it must be a non-empty single line without `;`")]
+    InvalidKeyword(String),
+    /// The staging directory has no files.
+    #[error("The staging directory {0} has no files")]
+    EmptyStagingDir(PathBuf),
+    /// A path of the staging directory or of its manifest leads outside of it.
+    #[error("Unsafe path `{0}` in the staging directory, it must stay inside of the staging directory or be an absolute installation path")]
+    UnsafeStagingPath(String),
+    /// Invalid default file or directory mode.
+    #[error("Invalid default {0} mode `{1}`, it must not have the setuid, setgid or sticky bits and must be usable by the owner")]
+    InvalidDefaultMode(&'static str, String),
+    /// Invalid trusted comment template.
+    #[error("Invalid trusted comment template `{0}`, it must be a single line and placeholders must be closed")]
+    InvalidTrustedComment(String),
+    /// Invalid trusted comment of a file signed by [`crate::sign::sign_files`].
+    #[error("Invalid trusted comment `{1}` of {0}, it must be a single line and must not set the `timestamp` or `file` fields")]
+    InvalidFileTrustedComment(PathBuf, String),
+    /// Unknown placeholder in the trusted comment template.
+    #[error("Unknown placeholder `{{{0}}}` in the trusted comment template, expected one of `{{timestamp}}`, `{{file}}`, `{{version}}` or `{{channel}}`")]
+    UnknownTrustedCommentPlaceholder(String),
+    /// Missing value for a placeholder of the trusted comment template.
+    #[error("The trusted comment template uses `{{{0}}}` but no {0} was specified")]
+    MissingTrustedCommentValue(String),
+    /// Path that can't be recorded in a directory manifest.
+    #[error(
+        "Path {0} is not valid UTF-8 or contains a newline and can't be recorded in the manifest"
+    )]
+    InvalidManifestPath(PathBuf),
+    /// Directory content doesn't match its signed manifest.
+    #[error("Directory doesn't match its signed manifest at `{0}`")]
+    DirectoryManifestMismatch(String),
+    /// Files of a release don't match its manifests or signatures, see [`crate::release::verify_release`].
+    #[error("The release at `{0}` doesn't match its manifests or signatures ({1} mismatches)")]
+    ReleaseDrift(PathBuf, usize),
+    /// Invalid release date of the update manifest.
+    #[error(
+        "Invalid update manifest `pub_date` `{0}`, it must be formatted according to RFC 3339"
+    )]
+    InvalidPubDate(String),
+    /// Invalid [`Config::launcher_shim`](crate::Config::launcher_shim).
+    #[error("Invalid `launcherShim`: {0}")]
+    InvalidLauncherShim(String),
+    /// Invalid [`Config::slug`](crate::Config::slug), or no slug could be derived from the app name.
+    #[error("Invalid slug `{0}`, it must only contain lowercase ASCII letters and digits separated by single `-`")]
+    InvalidSlug(String),
+    /// A file installed by the Windows installers would have a longer path
+    /// than [`WindowsConfig::max_path_length`](crate::config::WindowsConfig::max_path_length).
+    #[error("The installed path `{path}` is {length} characters long, longer than the limit of {limit}, shorten the product name or the resource paths, or set `windows.maxPathLength`")]
+    PathTooLong {
+        /// The installed path, in the longest default install directory.
+        path: String,
+        /// The length of the path, in UTF-16 code units and counting the terminating null character.
+        length: usize,
+        /// The [`WindowsConfig::max_path_length`](crate::config::WindowsConfig::max_path_length).
+        limit: usize,
+    },
+    /// Invalid `expires_at` of the update manifest.
+    #[error(
+        "Invalid update manifest `expires_at` `{0}`, it must be formatted according to RFC 3339"
+    )]
+    InvalidManifestExpiry(String),
+    /// The update manifest expired, see [`crate::updater::verify_update_manifest`].
+    #[error("The update manifest expired at {0}")]
+    ManifestExpired(String),
+    /// The packaging run was cancelled through its [`crate::CancellationToken`].
+    #[error("Packaging was cancelled")]
+    Cancelled,
+    /// The package format is not supported by the updater.
+    #[error("The {0} package format is not supported by the updater")]
+    UnsupportedUpdateFormat(&'static str),
+    /// The payload of the update bundles of the package format can't be signed.
+    #[error("The payload of {0} update bundles can't be signed, the bundle is the payload itself")]
+    UnsupportedPayloadSigning(&'static str),
+    /// Invalid update bundle name template.
+    #[error("Invalid update bundle name template `{0}`, placeholders must be closed and it must produce a file name")]
+    InvalidUpdateBundleName(String),
+    /// Unknown placeholder in the update bundle name template.
+    #[error("Unknown placeholder `{{{0}}}` in the update bundle name template, expected one of `{{name}}`, `{{version}}`, `{{target}}`, `{{arch}}` or `{{format}}`")]
+    UnknownUpdateBundleNamePlaceholder(String),
+    /// No `.dmg` or `.app` package to reference in the Homebrew Cask.
+    #[error("Couldn't find a dmg or app package to generate the Homebrew Cask")]
+    HomebrewCaskArtifactNotFound,
+    /// No NSIS installer to reference in the Scoop manifest.
+    #[error("Couldn't find an nsis package to generate the Scoop manifest")]
+    ScoopArtifactNotFound,
+    /// The generated Scoop manifest is invalid.
+    #[error("Invalid Scoop manifest: {0}")]
+    InvalidScoopManifest(String),
+    /// No NSIS or MSI installer to reference in the winget manifests.
+    #[error("Couldn't find an nsis or wix package to generate the winget manifests")]
+    WingetArtifactNotFound,
+    /// The generated winget manifests are invalid.
+    #[error("Invalid winget manifest: {0}")]
+    InvalidWingetManifest(String),
+    /// No `.deb` package to reference in the apt repository.
+    #[error("Couldn't find a deb package to generate the apt repository metadata")]
+    AptPackageNotFound,
+    /// The control file of a `.deb` package couldn't be read.
+    #[error("Invalid deb package {0}: {1}")]
+    InvalidDebPackage(PathBuf, String),
+    /// gpg error.
+    #[error("Error running gpg: {0}")]
+    FailedToRunGpg(std::io::Error),
+    /// Invalid Debian changelog.
+    #[error("Invalid Debian changelog: {0}")]
+    InvalidDebianChangelog(String),
+    /// Invalid [`DebianConfig::control_compression`](crate::config::DebianConfig::control_compression)
+    /// or [`DebianConfig::data_compression`](crate::config::DebianConfig::data_compression).
+    #[error("Invalid Debian compression: {0}")]
+    InvalidDebianCompression(String),
+    /// Failed to run the compression tool of a member of a Debian package.
+    #[error("Failed to compress the Debian package with `{0}`: {1}")]
+    DebianCompressorFailed(&'static str, std::io::Error),
+    /// Invalid package name.
+    #[error("Invalid `{0}` package name `{1}`, it must match `{2}`")]
+    InvalidPackageName(&'static str, String, &'static str),
+    /// Invalid resource ignore pattern.
+    #[error("Invalid resource ignore pattern `{0}`: {1}")]
+    InvalidIgnorePattern(String, String),
+    /// The zip compression method is not supported by the standard unzippers.
+    #[error("The `zstd` compression of `{0}` in the zip config is not supported by the unzippers of Windows Explorer, macOS and Info-ZIP, use `store` or `deflate` instead")]
+    UnsupportedZipCompression(String),
+    /// The macOS bundle identifier doesn't follow the reverse-DNS rules of Apple.
+    #[error("Invalid bundle identifier `{0}`, it must be in reverse-DNS notation with only alphanumeric characters, hyphens and periods, for example `com.example.app`")]
+    InvalidBundleIdentifier(String),
+    /// The install location of the macOS installer package is not absolute.
+    #[error("The pkg install location {0} must be an absolute path")]
+    InvalidPkgInstallLocation(PathBuf),
+    /// An install script of the macOS installer package has no shebang.
+    #[error("The pkg install script {0} must start with a `#!` shebang")]
+    InvalidPkgScript(PathBuf),
+    /// The package format requires a non-empty publisher.
+    #[error("The `{0}` package format requires a publisher, set `publisher` or the `{0}` specific override")]
+    MissingPublisher(&'static str),
+    /// Invalid [`DebianFileOwner`](crate::config::DebianFileOwner).
+    #[error("Invalid Debian file owner of `{0}`: {1}")]
+    InvalidDebianFileOwner(String, &'static str),
+    /// Empty [`NsisConfig::license_text`](crate::config::NsisConfig::license_text).
+    #[error("The `nsis.licenseText` license agreement must not be empty")]
+    EmptyNsisLicenseText,
+    /// Invalid [`Config::updater_endpoint`](crate::Config::updater_endpoint).
+    #[error("Invalid `updaterEndpoint` URL `{0}`, it must be an http or https URL")]
+    InvalidUpdaterEndpoint(String),
+    /// Invalid URL field of the Debian Control file.
+    #[error("Invalid `deb.{0}` URL `{1}`")]
+    InvalidDebianUrl(&'static str, String),
+    /// Invalid link of the Add/Remove Programs entry.
+    #[error("Invalid `windows.{0}` URL `{1}`, it must be an http or https URL")]
+    InvalidWindowsUrl(&'static str, String),
+    /// Invalid default install directory name of the Windows Installer.
+    #[error("Invalid wix install directory name `{0}`, it must be a non-empty directory name without path separators or reserved characters")]
+    InvalidWixInstallDirName(String),
+    /// Solid compression of the NSIS installer without a compression algorithm.
+    #[error("The NSIS `solid` compression can't be enabled when `compression` is `off`")]
+    InvalidNsisSolidCompression,
+    /// Invalid NSIS custom page function name.
+    #[error("The NSIS custom page function `{0}` is not a valid NSIS function name")]
+    InvalidNsisCustomPageFunction(String),
+    /// The binary of the installer shortcuts is not bundled.
+    #[error("The `{0}` shortcut binary is not one of the bundled binaries")]
+    ShortcutBinaryNotFound(String),
+    /// The selected flavor is not defined.
+    #[error("The `{0}` flavor is not defined in the configuration")]
+    UnknownFlavor(String),
     /// Unexpected target triple.
     #[error("Unexpected target triple: {0}")]
     UnexpectedTargetTriple(String),
+    /// Invalid pin in [`Config::pinned_tools`](crate::Config::pinned_tools).
+    #[error("Invalid pin of the `{0}` tool: {1}")]
+    InvalidToolPin(String, &'static str),
+    /// The version or the digest of an external tool differs from its pin
+    /// in [`Config::pinned_tools`](crate::Config::pinned_tools).
+    #[error("The `{tool}` tool doesn't match its pin, expected {expected} but found {found}")]
+    ToolVersionMismatch {
+        /// The name of the tool.
+        tool: String,
+        /// The pinned version or digest.
+        expected: String,
+        /// The version or digest of the tool.
+        found: String,
+    },
+    /// Failed to read the version of an external tool.
+    #[error("Failed to read the version of `{0}` from its output: {1}")]
+    UnknownToolVersion(String, String),
     /// Unsupported architecture.
     #[error("Unsupported architecture for \"{0}\" target triple: {0}")]
     UnsupportedArch(String, String),
@@ -83,9 +385,21 @@ pub enum Error {
     /// Nsis error
     #[error("Error running makensis.exe: {0}")]
     NsisFailed(std::io::Error),
+    /// Inno Setup compiler error.
+    #[error("Error running ISCC: {0}")]
+    InnoSetupFailed(std::io::Error),
+    /// Invalid NSIS uninstaller commands.
+    #[error("NSIS uninstaller commands must not open or close sections or functions, found: {0}")]
+    InvalidNsisUninstallerCommands(String),
     /// Nsis error
     #[error("Error running {0}: {0}")]
     WixFailed(String, std::io::Error),
+    /// pkgbuild error
+    #[error("Error running pkgbuild: {0}")]
+    PkgbuildFailed(std::io::Error),
+    /// productbuild error
+    #[error("Error running productbuild: {0}")]
+    ProductbuildFailed(std::io::Error),
     /// create-dmg script error
     #[error("Error running create-dmg script: {0}")]
     CreateDmgFailed(std::io::Error),
@@ -121,6 +435,13 @@ pub enum Error {
     #[cfg(windows)]
     #[error("Wix language {0} not found. It must be one of {1}")]
     UnsupportedWixLanguage(String, String),
+    /// Unsupported NSIS language.
+    #[error("NSIS language {0} not found. It must be one of {1}")]
+    UnsupportedNsisLanguage(String, String),
+    /// The MSI license agreement is not an RTF file.
+    #[cfg(windows)]
+    #[error("The WiX license agreement {0} must be an RTF file")]
+    InvalidLicenseAgreement(PathBuf),
     /// Image crate errors.
     #[error(transparent)]
     ImageError(#[from] image::ImageError),
@@ -150,15 +471,46 @@ pub enum Error {
         /// Reason why this framework is invalid
         reason: &'static str,
     },
+    /// A package format can't be built on the current operating system.
+    #[error("The `{format}` package format can't be built on {host_os}, pass `--skip-unsupported` or set `skipUnsupportedFormats` to skip it")]
+    FormatUnsupportedOnHost {
+        /// The package format.
+        format: &'static str,
+        /// The current operating system.
+        host_os: &'static str,
+    },
     /// Invalid icons.
     #[error("Could not find a valid icon")]
     InvalidIconList,
+    /// An icon doesn't meet the requirements of a package format.
+    #[error("Invalid icon for {format}: {reason}")]
+    InvalidIcon {
+        /// The package format requiring the icon.
+        format: &'static str,
+        /// Reason why this icon is invalid.
+        reason: String,
+    },
+    /// The binary is not a valid PE image.
+    #[error("{0} is not a valid PE binary: {1}")]
+    InvalidPeBinary(PathBuf, String),
+    /// Failed to write the binary with the embedded version info.
+    #[error("Failed to embed the version info into {0}: {1}")]
+    FailedToEmbedVersionInfo(PathBuf, String),
+    /// A version number doesn't fit in the 16 bits of the Windows version info.
+    #[error("Version number {0} is too large for the Windows version info, the maximum is 65535")]
+    VersionInfoNumberTooLarge(u64),
     /// Failed to notarize.
     #[error("Failed to notarize app")]
     FailedToNotarize,
     /// Rejected on notarize.
     #[error("Failed to notarize app: {0}")]
     NotarizeRejected(String),
+    /// Timed out waiting for the notarization.
+    #[error("Timed out after {1}s waiting for the notarization submission {0}, resume it with `notarize_resume`")]
+    NotarizeTimeout(String, u64),
+    /// No notarization submission to resume.
+    #[error("No notarization submission found at {0}")]
+    NotarizeSubmissionNotFound(PathBuf),
     /// Failed to parse notarytool output.
     #[error("Failed to parse notarytool output as JSON: `{0}`")]
     FailedToParseNotarytoolOutput(String),
@@ -195,9 +547,33 @@ pub enum Error {
     /// Failed to run codesign utility.
     #[error("Failed to run codesign utility: {0}")]
     FailedToRunCodesign(std::io::Error),
+    /// Failed to run `productsign`.
+    #[error("Failed to run productsign: {0}")]
+    FailedToRunProductsign(std::io::Error),
+    /// Entitlements file is not a valid plist.
+    #[error("Entitlements file {0} is not a valid plist: {1}")]
+    InvalidEntitlements(PathBuf, plist::Error),
+    /// Failed to run `otool`.
+    #[error("Failed to run otool: {0}")]
+    FailedToRunOtool(std::io::Error),
+    /// Failed to run `install_name_tool`.
+    #[error("Failed to run install_name_tool: {0}")]
+    FailedToRunInstallNameTool(std::io::Error),
+    /// A binary of the app loads a dylib from an absolute path that doesn't exist on other Macs.
+    #[error("{0} loads {1} from an absolute path, bundle it with `macos.frameworks` or disable `macos.relocateDylibs`")]
+    NonRelocatableDylib(PathBuf, String),
+    /// Failed to run lipo utility.
+    #[error("Failed to run lipo utility: {0}")]
+    FailedToRunLipo(std::io::Error),
+    /// Binary was not built for the expected architecture.
+    #[error("{0} is not a {1} Mach-O binary")]
+    UnexpectedBinaryArch(PathBuf, &'static str),
     /// Failed to run ditto utility.
     #[error("Failed to run ditto utility: {0}")]
     FailedToRunDitto(std::io::Error),
+    /// Failed to run spctl utility.
+    #[error("Failed to run spctl utility: {0}")]
+    FailedToRunSpctl(std::io::Error),
     /// Failed to run xcrun utility.
     #[error("Failed to run xcrun utility: {0}")]
     FailedToRunXcrun(std::io::Error),
@@ -210,9 +586,57 @@ pub enum Error {
     /// Path is not a directory.
     #[error("{0} is not a directory")]
     IsNotDirectory(PathBuf),
+    /// Invalid desktop entry key.
+    #[error("Invalid desktop entry key `{0}`, keys must only contain `A-Za-z0-9-`, optionally followed by a `[locale]`, and must not be generated by the packager")]
+    InvalidDesktopEntryKey(String),
+    /// Invalid custom field of the Debian Control file.
+    #[error("Invalid Debian Control field `{0}`: {1}")]
+    InvalidDebControlField(String, String),
+    /// Failed to timestamp a signature with a time-stamping authority.
+    #[error("Failed to timestamp the signature with `{0}`: {1}")]
+    TimestampRequestFailed(String, String),
+    /// Invalid timestamp of a signature.
+    #[error("Invalid signature timestamp {0}: {1}")]
+    InvalidTimestamp(PathBuf, String),
+    /// Invalid AppStream metadata.
+    #[error("Invalid AppStream metadata: {0}")]
+    InvalidAppStreamMetadata(String),
+    /// Invalid symlink of a Linux package.
+    #[error("Invalid symlink `{0}`: {1}")]
+    InvalidSymlink(String, String),
+    /// Invalid desktop entry value.
+    #[error("Value of desktop entry key `{0}` must not contain newlines")]
+    InvalidDesktopEntryValue(String),
+    /// Invalid AppImage update information.
+    #[error("Invalid AppImage update information `{0}`, see <https://github.com/AppImage/AppImageSpec/blob/master/draft.md#update-information> for the supported formats")]
+    InvalidAppImageUpdateInformation(String),
+    /// Invalid AppImage runtime.
+    #[error("Invalid AppImage runtime {0}: {1}")]
+    InvalidAppImageRuntime(String, String),
+    /// Invalid AppImage.
+    #[error("Invalid AppImage {0}: {1}")]
+    InvalidAppImage(PathBuf, String),
+    /// Invalid macOS app bundle.
+    #[error("Invalid app bundle {0}: {1}")]
+    InvalidAppBundle(PathBuf, String),
+    /// The AppImages of a delta update don't embed the same update information.
+    #[error("The previous and new AppImages must embed the same update information, found `{0}` and `{1}`")]
+    AppImageUpdateInformationMismatch(String, String),
+    /// A dependency of the Debian package is not satisfiable by the available packages.
+    #[error("The dependency `{0}` is not satisfiable: {1}")]
+    UnsatisfiableDependency(String, String),
+    /// Failed to list the available Debian packages.
+    #[error("Failed to list the available packages with `apt-cache dumpavail`: {0}")]
+    ListAvailablePackagesFailed(std::io::Error),
+    /// A binary of an AppImage architecture is not built for it.
+    #[error("The binary {0} is not an ELF executable built for {1}")]
+    AppImageBinaryArchMismatch(PathBuf, String),
     /// Could not find a square icon to use as AppImage icon
     #[error("Could not find a square icon to use as AppImage icon")]
     AppImageSquareIcon,
+    /// Invalid glob in `appimage.exclude`.
+    #[error("Invalid AppImage exclude pattern `{0}`: {1}")]
+    InvalidAppImageExcludePattern(String, String),
     /// Base64 decoding error.
     #[error(transparent)]
     Base64DecodeError(#[from] base64::DecodeError),
@@ -222,6 +646,32 @@ pub enum Error {
     /// minisign errors.
     #[error(transparent)]
     Minisign(#[from] minisign::PError),
+    /// The minisign private key can't be decoded, like when it was truncated while copying it.
+    #[error("Malformed signing private key: {reason}")]
+    MalformedSigningKey {
+        /// Why the key can't be decoded.
+        reason: String,
+    },
+    /// The signing private key is encrypted, no password was set and stdin is not a terminal to prompt for it.
+    #[error("The signing private key is encrypted but no password was set, and stdin is not a terminal to prompt for it. Set the password with the `--password` flag or the `CARGO_PACKAGER_SIGN_PRIVATE_KEY_PASSWORD` environment variable, or `SigningConfig::password` with the library")]
+    NoTtyForPasswordPrompt,
+    /// The password of the signing private key is wrong.
+    #[error("Wrong password for the signing private key")]
+    WrongSigningKeyPassword,
+    /// The public key of a key pair is not the public key of its private key.
+    #[error("The public key {public_key_id} doesn't match the private key, whose public key is {private_key_id}")]
+    KeyPairMismatch {
+        /// The key id of the public key.
+        public_key_id: String,
+        /// The key id of the public key of the private key.
+        private_key_id: String,
+    },
+    /// Unsupported or malformed OpenSSH or PEM signing key.
+    #[error("Invalid OpenSSH or PEM signing key: {0}")]
+    InvalidSigningKey(String),
+    /// The signature doesn't match any of the trusted public keys.
+    #[error("The signature doesn't match any of the trusted public keys")]
+    NoTrustedKeyMatched,
     /// System time errors.
     #[error(transparent)]
     SystemTimeError(#[from] std::time::SystemTimeError),
@@ -257,3 +707,80 @@ pub enum Error {
 
 /// Convenient type alias of Result type for cargo-packager.
 pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The name of the variant of this error, like `IoWithPath`.
+    ///
+    /// It is stable across releases and used as the `kind` of the serialized error.
+    pub fn kind(&self) -> String {
+        variant_name(&format!("{self:?}"))
+    }
+
+    /// The path associated with this error, if any.
+    ///
+    /// For the errors involving two paths, like [`Error::CopyFile`], this is the source path.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::IoWithPath(path, _)
+            | Error::CopyFile(path, ..)
+            | Error::RenameFile(path, ..)
+            | Error::Symlink(path, ..)
+            | Error::ResourceSymlinkLoop(path)
+            | Error::OutOfSpace { path }
+            | Error::ArtifactTooLarge { path, .. }
+            | Error::CorruptArtifact { path, .. }
+            | Error::ScanFailed { path, .. }
+            | Error::DeltaOldArtifactMismatch { path, .. }
+            | Error::InvalidDelta(path, _)
+            | Error::InvalidFileTrustedComment(path, _)
+            | Error::VolumeChecksumMismatch(path)
+            | Error::UnavailableSharedLibraries(path, _)
+            | Error::UnknownBinaryVersion(path, _)
+            | Error::InvalidTimestamp(path, _)
+            | Error::InvalidAppImage(path, _)
+            | Error::InvalidWixBitmap(path, ..)
+            | Error::InvalidManifestPath(path)
+            | Error::ReleaseDrift(path, _)
+            | Error::ParentDirNotFound(path)
+            | Error::InvalidPeBinary(path, _)
+            | Error::InvalidDebPackage(path, _)
+            | Error::InvalidPkgInstallLocation(path)
+            | Error::InvalidPkgScript(path)
+            | Error::FailedToEmbedVersionInfo(path, _)
+            | Error::NotarizeSubmissionNotFound(path)
+            | Error::InvalidEntitlements(path, _)
+            | Error::UnexpectedBinaryArch(path, _)
+            | Error::NonRelocatableDylib(path, _)
+            | Error::AlreadyExists(path)
+            | Error::DoesNotExist(path)
+            | Error::IsNotDirectory(path)
+            | Error::EmptyStagingDir(path)
+            | Error::SigningKeyExists(path)
+            | Error::FailedToExtractFilename(path)
+            | Error::EmbeddedProvisionprofileFileNotFound(path)
+            | Error::FailedToCopyEmbeddedProvisionprofile(path, _) => Some(path),
+            #[cfg(windows)]
+            Error::InvalidLicenseAgreement(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the variant name of the derived `Debug` representation of an enum.
+pub(crate) fn variant_name(debug: &str) -> String {
+    debug
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect()
+}
+
+/// Serializes the error as an object with its [`Error::kind`], its message and its [`Error::path`].
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("Error", 3)?;
+        error.serialize_field("kind", &self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("path", &self.path())?;
+        error.end()
+    }
+}
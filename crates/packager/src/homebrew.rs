@@ -0,0 +1,200 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of Homebrew Cask formulae for the macOS packages.
+//!
+//! The generated `.rb` file is ready to be submitted to a tap,
+//! publishing the artifact it references is left to the caller.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    checksum,
+    updater::{self, UpdateBundleConfig},
+    Config, Error, PackageFormat, PackageOutput,
+};
+
+/// Options of the Homebrew Cask generated by [`generate_cask`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HomebrewCaskConfig {
+    /// A template for the download URL of the artifact,
+    /// where `{{version}}` is replaced with [`Config::version`]
+    /// and `{{artefact}}` with the file name of the artifact.
+    pub url_template: String,
+    /// The token of the cask, used with `brew install --cask <token>`.
+    ///
+    /// Defaults to the [`Config::slug`].
+    pub token: Option<String>,
+}
+
+impl HomebrewCaskConfig {
+    /// Creates a new [`HomebrewCaskConfig`] with the download URL template of the artifact.
+    pub fn new<S: Into<String>>(url_template: S) -> Self {
+        Self {
+            url_template: url_template.into(),
+            token: None,
+        }
+    }
+
+    /// Set the token of the cask.
+    pub fn token<S: Into<String>>(mut self, token: S) -> Self {
+        self.token.replace(token.into());
+        self
+    }
+}
+
+/// Quotes a string as a Ruby double-quoted string literal.
+fn ruby_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            // prevent string interpolation
+            '#' if matches!(chars.peek(), Some('{' | '$' | '@')) => out.push_str("\\#"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Returns the artifact referenced by the cask, the `.dmg` when available,
+/// otherwise the `.app.tar.gz` update bundle of the `.app`, built in [`Config::out_dir`].
+fn cask_artifact(config: &Config, packages: &[PackageOutput]) -> crate::Result<PathBuf> {
+    let find = |format: PackageFormat| packages.iter().find(|p| p.format == format);
+    if let Some(dmg) = find(PackageFormat::Dmg).and_then(|p| p.paths.first()) {
+        Ok(dmg.clone())
+    } else if let Some(app) = find(PackageFormat::App) {
+        updater::build_update_bundle(config, app, &UpdateBundleConfig::new())
+    } else {
+        Err(Error::HomebrewCaskArtifactNotFound)
+    }
+}
+
+/// Generates the Homebrew Cask formula of the `.dmg` or `.app` in `packages`.
+///
+/// The formula uses the SHA-256 digest of the artifact and its download URL
+/// built from [`HomebrewCaskConfig::url_template`],
+/// with the [`Config::description`] and [`Config::homepage`] when defined.
+pub fn generate_cask(
+    config: &Config,
+    packages: &[PackageOutput],
+    cask_config: &HomebrewCaskConfig,
+) -> crate::Result<String> {
+    let artifact = cask_artifact(config, packages)?;
+    let artifact_name = artifact
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| Error::FailedToExtractFilename(artifact.clone()))?;
+    let url = cask_config
+        .url_template
+        .replace("{{version}}", &config.version)
+        .replace("{{artefact}}", artifact_name);
+    let token = match &cask_config.token {
+        Some(token) => token.clone(),
+        None => config.slug()?,
+    };
+
+    let mut cask = String::new();
+    let _ = writeln!(cask, "cask {} do", ruby_string(&token));
+    let _ = writeln!(cask, "  version {}", ruby_string(&config.version));
+    let _ = writeln!(
+        cask,
+        "  sha256 {}",
+        ruby_string(&checksum::sha256_file(&artifact)?)
+    );
+    cask.push('\n');
+    let _ = writeln!(cask, "  url {}", ruby_string(&url));
+    let _ = writeln!(cask, "  name {}", ruby_string(&config.product_name));
+    if let Some(description) = &config.description {
+        let _ = writeln!(cask, "  desc {}", ruby_string(description));
+    }
+    if let Some(homepage) = &config.homepage {
+        let _ = writeln!(cask, "  homepage {}", ruby_string(homepage));
+    }
+    cask.push('\n');
+    let _ = writeln!(
+        cask,
+        "  app {}",
+        ruby_string(&format!("{}.app", config.product_name))
+    );
+    cask.push_str("end\n");
+
+    Ok(cask)
+}
+
+/// Writes the Homebrew Cask formula generated by [`generate_cask`]
+/// into `<out_dir>/<token>.rb` and returns its path.
+pub fn write_cask(
+    config: &Config,
+    packages: &[PackageOutput],
+    cask_config: &HomebrewCaskConfig,
+    out_dir: &Path,
+) -> crate::Result<PathBuf> {
+    let cask = generate_cask(config, packages, cask_config)?;
+    let token = match &cask_config.token {
+        Some(token) => token.clone(),
+        None => config.slug()?,
+    };
+    let cask_path = out_dir.join(format!("{token}.rb"));
+    fs::write(&cask_path, cask).map_err(|e| Error::IoWithPath(cask_path.clone(), e))?;
+    Ok(cask_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_generates_cask() {
+        let dir = tempfile::tempdir().unwrap();
+        let dmg = dir.path().join("My App_1.2.0_aarch64.dmg");
+        fs::write(&dmg, "").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.2.0".into();
+        config.description = Some("Says \"hi\" to #{user}".into());
+        let packages = vec![PackageOutput::new(PackageFormat::Dmg, vec![dmg])];
+        let cask_config =
+            HomebrewCaskConfig::new("https://example.com/releases/v{{version}}/{{artefact}}");
+
+        let cask = generate_cask(&config, &packages, &cask_config).unwrap();
+        assert_eq!(
+            cask,
+            r#"cask "my-app" do
+  version "1.2.0"
+  sha256 "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+  url "https://example.com/releases/v1.2.0/My App_1.2.0_aarch64.dmg"
+  name "My App"
+  desc "Says \"hi\" to \#{user}"
+
+  app "My App.app"
+end
+"#
+        );
+
+        let cask_path =
+            write_cask(&config, &packages, &cask_config.token("myapp"), dir.path()).unwrap();
+        assert_eq!(cask_path, dir.path().join("myapp.rb"));
+        assert!(fs::read_to_string(cask_path)
+            .unwrap()
+            .starts_with("cask \"myapp\" do\n"));
+
+        assert!(matches!(
+            generate_cask(&config, &[], &HomebrewCaskConfig::new("")),
+            Err(Error::HomebrewCaskArtifactNotFound)
+        ));
+    }
+}
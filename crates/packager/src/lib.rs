@@ -13,6 +13,7 @@
 //!
 //! - macOS
 //!   - DMG (.dmg)
+//!   - Installer package (.pkg)
 //!   - Bundle (.app)
 //! - Linux
 //!   - Debian package (.deb)
@@ -46,11 +47,18 @@
 //!
 //! By default, the packager reads its configuration from `Packager.toml` or `packager.json` if it exists, and from `package.metadata.packager` table in `Cargo.toml`.
 //! You can also specify a custom configuration using the `-c/--config` cli argument.
+//! Run `cargo packager init` to generate a commented starter `Packager.toml`, or a `packager.json` with `--format json`, pre-filled from your `Cargo.toml`.
 //!
 //! For a full list of configuration options, see [Config].
 //!
 //! You could also use the [schema](./schema.json) file from GitHub to validate your configuration or have auto completions in your IDE.
 //!
+//! ### Relative paths
+//!
+//! The relative paths of a configuration, like its resources, binaries, icons and output directory, are resolved against the directory of its configuration file,
+//! or against the project root when set with [`projectRoot`](Config::project_root) or the `--root` cli argument, independently of the current directory.
+//! A relative project root is itself resolved against the directory of the configuration file.
+//!
 //! ### Building your application before packaging
 //!
 //! By default, the packager doesn't build your application, so if your app requires a compilation step, the packager has an option to specify a shell command to be executed before packaing your app, `beforePackagingCommand`.
@@ -60,6 +68,15 @@
 //! By default, the packager looks for binaries built using the `debug` profile, if your `beforePackagingCommand` builds your app using `cargo build --release`, you will also need to
 //! run the packager in release mode `cargo packager --release`, otherwise, if you have a custom cargo profile, you will need to specify it using `--profile` cli arg `cargo packager --profile custom-release-profile`.
 //!
+//! ### Notarization
+//!
+//! When notarization credentials are available, the `.app` is submitted to Apple's notary service and
+//! its status is checked every `notarizationPollInterval` seconds until it is accepted or rejected.
+//! The submission id is saved into `<out-dir>/.cargo-packager/<product-name>.app.notarization-id`, so if
+//! the wait is interrupted, for example when `notarizationTimeout` is reached or a CI job times out,
+//! the same submission can be resumed with [`notarize_resume`] instead of being submitted again.
+//! Note that packaging again cleans this directory and creates a new submission.
+//!
 //! ### Library
 //!
 //! This crate is also published to crates.io as a library that you can integrate into your tooling, just make sure to disable the default-feature flags.
@@ -76,31 +93,62 @@
 #![cfg_attr(doc_cfg, feature(doc_cfg))]
 #![deny(missing_docs)]
 
-use std::{collections::HashMap, fs::File, io::Write, path::PathBuf};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
+mod cancellation;
 mod codesign;
+mod der;
+mod elf;
 mod error;
 mod package;
 mod shell;
 mod util;
 
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub mod apt;
+pub mod checksum;
 #[cfg(feature = "cli")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "cli")))]
 pub mod cli;
 pub mod config;
+pub mod delta;
+pub mod homebrew;
+pub mod provenance;
+pub mod release;
+pub mod sbom;
+pub mod scan;
+pub mod scoop;
 pub mod sign;
+pub mod split;
+pub mod timestamp;
+pub mod updater;
+pub mod winget;
+pub mod zsync;
 
 pub use config::{Config, PackageFormat};
 pub use error::{Error, Result};
 use flate2::{write::GzEncoder, Compression};
-use serde::Serialize;
 pub use sign::SigningConfig;
 
-pub use package::{package, PackageOutput};
+pub use cancellation::CancellationToken;
+#[cfg(target_os = "macos")]
+#[cfg_attr(doc_cfg, doc(cfg(target_os = "macos")))]
+pub use codesign::macos::NotarizationStatus;
+pub use package::{
+    estimate_output_size, output_paths, package, package_with_cancellation, PackageOutput,
+    PackageOutputSummary,
+};
 use util::PathExt;
 
-use crate::package::PackageOutputSummary;
-
 #[cfg(feature = "cli")]
 fn parse_log_level(verbose: u8) -> tracing::Level {
     match verbose {
@@ -203,10 +251,50 @@ where
     }
 }
 
+// Archives the directories in `package` paths, like the one produced by [`PackageFormat::App`],
+// in place so they can be signed or hashed, returning the list of files.
+// Directories archived by a previous call are skipped, their archive is already in the paths.
+fn archive_package_dirs(package: &mut PackageOutput) -> crate::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in &package.paths.clone() {
+        if path.is_dir() {
+            let zip = path.with_additional_extension("tar.gz");
+            if package.paths.contains(&zip) {
+                continue;
+            }
+            let write = || -> crate::Result<()> {
+                let dest_file = util::create_file(&zip)?;
+                let gzip_encoder = GzEncoder::new(dest_file, Compression::default());
+                let writer = util::create_tar_from_dir(path, gzip_encoder)?;
+                let mut dest_file = writer.finish()?;
+                dest_file.flush()?;
+                Ok(())
+            };
+            write().map_err(|e| util::write_error(&zip, "failed to archive the package", e))?;
+
+            package.paths.push(zip.clone());
+            files.push(zip);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
 /// Sign the specified packages and return the signatures paths.
 ///
 /// If `packages` contain a directory in the case of [`PackageFormat::App`]
 /// it will zip the directory before signing and appends it to `packages`.
+///
+/// When [`SigningConfig::algorithms`] is set, a signature set is created for each file
+/// with [`sign::sign_file_set`] instead of a single `.sig` signature.
+/// When [`SigningConfig::timestamp_url`] is set, the `.tsr` timestamps of the signatures
+/// are returned along with them.
+///
+/// Only the files allowed by [`SigningConfig::should_sign`] are signed,
+/// which defaults to the primary artifact of each package.
+/// The files of the [`SigningConfig::hash_only_formats`] get a `.sha256` digest file instead,
+/// which is returned along with the signatures.
 #[tracing::instrument(level = "trace")]
 pub fn sign_outputs(
     config: &SigningConfig,
@@ -214,79 +302,125 @@ pub fn sign_outputs(
 ) -> crate::Result<Vec<PathBuf>> {
     let mut signatures = Vec::new();
     for package in packages {
-        for path in &package.paths.clone() {
-            let path = if path.is_dir() {
-                let zip = path.with_additional_extension("tar.gz");
-                let dest_file = util::create_file(&zip)?;
-                let gzip_encoder = GzEncoder::new(dest_file, Compression::default());
-                let writer = util::create_tar_from_dir(path, gzip_encoder)?;
-                let mut dest_file = writer.finish()?;
-                dest_file.flush()?;
+        for (i, path) in archive_package_dirs(package)?.into_iter().enumerate() {
+            if !config.should_sign(package.format, &path) {
+                tracing::debug!("Skipping signing {}", path.display());
+                continue;
+            }
 
-                package.paths.push(zip);
-                package.paths.last().unwrap()
+            if config
+                .hash_only_formats
+                .iter()
+                .flatten()
+                .any(|f| *f == package.format)
+            {
+                tracing::debug!("Hashing {} instead of signing it", path.display());
+                let (_, digest_file) = checksum::write_sha256_file(&path)?;
+                signatures.push(digest_file);
+                continue;
+            }
+
+            let (sig_files, sig) = if config.algorithms.is_some() {
+                let set = sign::sign_file_set(config, &path)?;
+                // the updater verifies minisign signatures
+                let sig = set
+                    .iter()
+                    .find(|s| s.algorithm == sign::SignatureAlgorithm::Minisign)
+                    .map(|s| s.signature.clone());
+                (set.into_iter().map(|s| s.path).collect(), sig)
             } else {
-                path
+                let (sig_file, sig) = sign::sign_file(config, &path)?;
+                (vec![sig_file], Some(sig))
             };
 
-            let (sig_file, sig) = sign::sign_file(config, path)?;
-
-            // Add signature to package summary
-            if let Some(summary) = &mut package.summary {
-                summary.signature = Some(sig);
+            // Add signature to package summary,
+            // the first file is the one referenced by the summary
+            if let (0, Some(summary)) = (i, &mut package.summary) {
+                summary.signature = sig;
             }
 
-            signatures.push(sig_file);
+            package.signed = true;
+            if config.timestamp_url.is_some() {
+                signatures.extend(sig_files.iter().map(|s| timestamp::timestamp_path(s)));
+            }
+            signatures.extend(sig_files);
         }
     }
 
     Ok(signatures)
 }
 
-/// Create a `latest.json` output summarising the built packages
-pub fn summarise_outputs(
-    config: &Config,
-    packages: &mut Vec<PackageOutput>,
-) -> crate::Result<PathBuf> {
-    #[derive(Debug, Clone, Serialize)]
-    struct InnerRemoteRelease {
-        version: String,
-        notes: Option<String>,
-        pub_date: Option<String>,
-        platforms: Option<HashMap<String, PackageOutputSummary>>,
+/// Write the BLAKE3 digest of the specified packages into `<file>.b3` files
+/// and return the digest files paths.
+///
+/// The digests are also added to the package summaries included in `latest.json`.
+/// If `packages` contain a directory in the case of [`PackageFormat::App`]
+/// it will zip the directory before hashing and appends it to `packages`.
+#[tracing::instrument(level = "trace")]
+pub fn hash_outputs_blake3(packages: &mut Vec<PackageOutput>) -> crate::Result<Vec<PathBuf>> {
+    let mut digest_files = Vec::new();
+    for package in packages {
+        for (i, path) in archive_package_dirs(package)?.into_iter().enumerate() {
+            let (digest, digest_file) = checksum::write_blake3_file(&path)?;
+
+            // the first file is the one referenced by the summary
+            if let (0, Some(summary)) = (i, &mut package.summary) {
+                summary.blake3 = Some(digest);
+            }
+
+            digest_files.push(digest_file);
+        }
     }
 
-    //Collect releases
-    let mut platforms = HashMap::with_capacity(packages.len());
+    Ok(digest_files)
+}
 
+/// Generate a [`checksum::SHA256SUMS_FILE_NAME`] manifest of the specified packages in `out_dir`
+/// and sign it, instead of signing each package individually.
+///
+/// If `packages` contain a directory in the case of [`PackageFormat::App`]
+/// it will zip the directory before hashing and appends it to `packages`.
+///
+/// Returns a tuple of the manifest path and its signature,
+/// the signature is also written next to the manifest with a `.sig` extension.
+#[tracing::instrument(level = "trace")]
+pub fn sign_checksums(
+    config: &SigningConfig,
+    packages: &mut Vec<PackageOutput>,
+    out_dir: &Path,
+) -> crate::Result<(PathBuf, String)> {
+    let mut files = Vec::new();
     for package in packages {
-        if let Some(summary) = package.summary.clone() {
-            // if summary.signature.is_some() {
-            platforms.insert(summary.platform.clone(), summary);
-            // } else {
-            //     // The signer failed to update the signature field
-            //     tracing::warn!("A package could not be summarized in latest.json because it could not be signed.")
-            // }
-        }
+        files.extend(archive_package_dirs(package)?);
     }
 
-    // Write latest.json
-    let release = InnerRemoteRelease {
-        version: config.version.clone(),
-        notes: None,
-        pub_date: Some(
-            time::OffsetDateTime::now_utc()
-                .format(&time::format_description::well_known::Rfc3339)
-                .unwrap(),
-        ),
-        platforms: Some(platforms),
-    };
+    let manifest_path = checksum::write_sha256sums(out_dir, &files)?;
+    let (_, signature) = sign::sign_file(config, &manifest_path)?;
 
-    //Write it to the file
-    let summary_path = config.out_dir().join("latest.json");
-    let summary_file = File::create(summary_path.clone())?;
+    Ok((manifest_path, signature))
+}
 
-    serde_json::to_writer_pretty(summary_file, &release)?;
+/// Create a `latest.json` output summarising the built packages
+#[deprecated(note = "use `summarise_outputs_with_config` instead")]
+// the signature is kept as it was
+#[allow(clippy::ptr_arg)]
+pub fn summarise_outputs(
+    config: &Config,
+    packages: &mut Vec<PackageOutput>,
+) -> crate::Result<PathBuf> {
+    summarise_outputs_with_config(config, packages, &Default::default())
+}
+
+/// Create a `latest.json` output summarising the built packages, with the options of `manifest_config`
+///
+/// This is similar to calling `updater::write_update_manifest(&updater::build_update_manifest(config, packages, manifest_config)?, config.out_dir())`
+pub fn summarise_outputs_with_config(
+    config: &Config,
+    packages: &[PackageOutput],
+    manifest_config: &updater::UpdateManifestConfig,
+) -> crate::Result<PathBuf> {
+    let manifest = updater::build_update_manifest(config, packages, manifest_config)?;
+    let summary_path = updater::write_update_manifest(&manifest, &config.out_dir())?;
 
     tracing::info!("Finished summarising at:\n{}", summary_path.display());
 
@@ -305,6 +439,41 @@ pub fn package_and_sign(
     signing_config: &SigningConfig,
 ) -> crate::Result<(Vec<PackageOutput>, Vec<PathBuf>)> {
     let mut packages = package(config)?;
-    let signatures = sign_outputs(signing_config, &mut packages)?;
+    let mut signing_config = signing_config.clone();
+    signing_config
+        .version
+        .get_or_insert_with(|| config.version.clone());
+    let signatures = sign_outputs(&signing_config, &mut packages)?;
     Ok((packages, signatures))
 }
+
+/// Checks whether the `.app`, `.dmg` or `.pkg` at `path` is notarized and whether its ticket is stapled,
+/// independently of the packaging, for example to audit released artifacts.
+#[cfg(target_os = "macos")]
+#[cfg_attr(doc_cfg, doc(cfg(target_os = "macos")))]
+pub fn check_notarization<P: AsRef<Path>>(path: P) -> crate::Result<NotarizationStatus> {
+    codesign::macos::check_notarization(path.as_ref())
+}
+
+/// Resumes waiting for the notarization of the `.app` at `app_bundle_path`
+/// instead of submitting it again, then staples it once accepted.
+///
+/// When `submission_id` is `None`, the id saved into [`Config::out_dir`] during packaging is used.
+/// The credentials are read from [`config::MacOsConfig::notarization_credentials`]
+/// or the notarization environment variables.
+#[cfg(target_os = "macos")]
+#[cfg_attr(doc_cfg, doc(cfg(target_os = "macos")))]
+#[tracing::instrument(level = "trace", skip(config))]
+pub fn notarize_resume<P: AsRef<Path> + std::fmt::Debug>(
+    config: &Config,
+    app_bundle_path: P,
+    submission_id: Option<String>,
+) -> crate::Result<()> {
+    let auth = codesign::macos::config_notarize_auth(config)?;
+    codesign::macos::notarize_resume(
+        app_bundle_path.as_ref().to_path_buf(),
+        submission_id,
+        auth,
+        config,
+    )
+}
@@ -7,22 +7,24 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use super::Context;
+use super::{naming, Context};
 use crate::Error;
-use crate::{config::Config, util};
-
-#[cfg(target_os = "macos")]
 use crate::{
-    codesign::macos::{self as codesign, SignTarget},
-    shell::CommandExt,
+    config::{Config, PackageFormat},
+    util,
 };
 
+use crate::shell::CommandExt;
+
+#[cfg(target_os = "macos")]
+use crate::codesign::macos::{self as codesign, SignTarget};
+
 #[tracing::instrument(level = "trace", skip(ctx))]
 pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     let Context { config, .. } = ctx;
     // we should use the bundle name (App name) as a macOS standard.
     // version or platform shouldn't be included in the App name.
-    let app_product_name = format!("{}.app", config.product_name);
+    let app_product_name = naming::package_file_name(config, PackageFormat::App)?;
     let app_bundle_path = config.out_dir().join(&app_product_name);
 
     if app_bundle_path.exists() {
@@ -36,6 +38,15 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         app_bundle_path.display()
     );
 
+    ctx.write_artifact(&app_bundle_path, |partial| {
+        write_app_bundle(config, partial)
+    })?;
+
+    Ok(vec![app_bundle_path])
+}
+
+/// Writes the app bundle of `config` at `app_bundle_path`, signing and notarizing it if configured.
+fn write_app_bundle(config: &Config, app_bundle_path: &Path) -> crate::Result<()> {
     let contents_directory = app_bundle_path.join("Contents");
     fs::create_dir_all(&contents_directory)
         .map_err(|e| Error::IoWithPath(contents_directory.clone(), e))?;
@@ -54,6 +65,12 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
 
     tracing::debug!("Copying frameworks");
     let _framework_paths = copy_frameworks_to_bundle(&contents_directory, config)?;
+    #[cfg(target_os = "macos")]
+    let bundled_dylibs = _framework_paths
+        .iter()
+        .filter(|p| p.extension() == Some(std::ffi::OsStr::new("dylib")))
+        .cloned()
+        .collect::<Vec<_>>();
 
     #[cfg(target_os = "macos")]
     sign_paths.extend(
@@ -70,7 +87,13 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     );
 
     tracing::debug!("Copying resources");
-    config.copy_resources(&resources_dir)?;
+    config.copy_resources(&resources_dir, PackageFormat::App)?;
+
+    tracing::debug!("Copying bundled runtime");
+    config.copy_bundled_runtime(&bin_dir, PackageFormat::App)?;
+
+    tracing::debug!("Copying release notes");
+    config.copy_release_notes(&resources_dir)?;
 
     tracing::debug!("Copying embedded.provisionprofile");
     copy_embedded_provisionprofile_file(&contents_directory, config)?;
@@ -81,11 +104,39 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     tracing::debug!("Copying external binaries");
     config.copy_external_binaries(&bin_dir)?;
     tracing::debug!("Copying binaries");
+    let universal_binary = config.macos().and_then(|m| m.universal_binary.as_ref());
     for bin in &config.binaries {
-        let bin_path = config.binary_path(bin);
         let dest_path = bin_dir.join(bin.path.file_name().unwrap());
-        fs::copy(&bin_path, &dest_path)
-            .map_err(|e| Error::CopyFile(bin_path.clone(), dest_path.clone(), e))?;
+        if let Some(universal_binary) = universal_binary {
+            create_universal_binary(universal_binary, &bin.path, &dest_path)?;
+        } else {
+            let bin_path = config.binary_path(bin);
+            fs::copy(&bin_path, &dest_path)
+                .map_err(|e| Error::CopyFile(bin_path.clone(), dest_path.clone(), e))?;
+        }
+    }
+
+    if let Some(minimum_system_version) = config
+        .macos()
+        .and_then(|macos| macos.minimum_system_version.as_deref())
+    {
+        for bin in &config.binaries {
+            check_minimum_system_version(
+                minimum_system_version,
+                &bin_dir.join(bin.path.file_name().unwrap()),
+            )?;
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    if config.macos().and_then(|m| m.relocate_dylibs) != Some(false) {
+        tracing::debug!("Relocating dylibs");
+        let binaries = config
+            .binaries
+            .iter()
+            .map(|bin| bin_dir.join(bin.path.file_name().unwrap()))
+            .collect::<Vec<_>>();
+        relocate_dylibs(config, &binaries, &bundled_dylibs)?;
     }
 
     // All dylib files and native executables should be signed manually
@@ -93,7 +144,7 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     // https://developer.apple.com/forums/thread/129980
 
     // Find all files in the app bundle
-    let files = walkdir::WalkDir::new(&app_bundle_path)
+    let files = walkdir::WalkDir::new(app_bundle_path)
         .into_iter()
         .flatten()
         .map(|dir| dir.into_path())
@@ -135,6 +186,14 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
             continue;
         }
 
+        // native executables must be runnable regardless of the mode of their source
+        #[cfg(unix)]
+        if file.starts_with(&bin_dir) {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&file, fs::Permissions::from_mode(0o755))
+                .map_err(|e| Error::IoWithPath(file.clone(), e))?;
+        }
+
         #[cfg(target_os = "macos")]
         sign_paths.push(SignTarget {
             path: file,
@@ -142,6 +201,21 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         });
     }
 
+    #[cfg(unix)]
+    {
+        let resources = Path::new("Contents/Resources");
+        let runtime = Path::new("Contents/MacOS");
+        let explicit = config
+            .resources()?
+            .into_iter()
+            .map(|r| (resources, r))
+            .chain(config.bundled_runtime()?.into_iter().map(|r| (runtime, r)))
+            .filter_map(|(dir, r)| r.mode.map(|mode| (dir.join(r.target), mode)))
+            .collect();
+        let modes = super::file_modes(config, app_bundle_path, explicit)?;
+        super::apply_file_modes(app_bundle_path, &modes)?;
+    }
+
     #[cfg(target_os = "macos")]
     if let Some(identity) = config
         .macos()
@@ -151,28 +225,29 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         // Sign frameworks and sidecar binaries first, per apple, signing must be done inside out
         // https://developer.apple.com/forums/thread/701514
         sign_paths.push(SignTarget {
-            path: app_bundle_path.clone(),
+            path: app_bundle_path.to_path_buf(),
             is_native_binary: true,
         });
 
         // Remove extra attributes, which could cause codesign to fail
         // https://developer.apple.com/library/archive/qa/qa1940/_index.html
-        remove_extra_attr(&app_bundle_path)?;
+        remove_extra_attr(app_bundle_path)?;
 
         // sign application
         let sign_paths = sign_paths.into_sorted_vec();
         codesign::try_sign(sign_paths, identity, config)?;
 
         // notarization is required for distribution
-        match config
-            .macos()
-            .and_then(|m| m.notarization_credentials.clone())
-            .ok_or(crate::Error::MissingNotarizeAuthVars)
-            .or_else(|_| codesign::notarize_auth())
-        {
+        match codesign::config_notarize_auth(config) {
             Ok(auth) => {
+                if config.macos().and_then(|m| m.hardened_runtime) == Some(false) {
+                    tracing::warn!(
+                        "Notarizing {} with the hardened runtime disabled, Apple will reject it",
+                        app_bundle_path.display()
+                    );
+                }
                 tracing::debug!("Notarizing {}", app_bundle_path.display());
-                codesign::notarize(app_bundle_path.clone(), auth, config)?;
+                codesign::notarize(app_bundle_path.to_path_buf(), auth, config)?;
             }
             Err(e) => {
                 tracing::warn!("Skipping app notarization, {}", e.to_string());
@@ -180,7 +255,332 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         }
     }
 
-    Ok(vec![app_bundle_path])
+    Ok(())
+}
+
+const CPU_TYPE_X86_64: u32 = 0x01000007;
+const CPU_TYPE_ARM64: u32 = 0x0100000c;
+
+// Reads the CPU type from the header of a thin 64-bit Mach-O binary.
+fn macho_cpu_type(path: &Path) -> crate::Result<Option<u32>> {
+    let mut header = [0; 8];
+    let mut file = fs::File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    if std::io::Read::read_exact(&mut file, &mut header).is_err() {
+        return Ok(None);
+    }
+
+    let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    Ok((magic == 0xfeedfacf)
+        .then(|| u32::from_le_bytes([header[4], header[5], header[6], header[7]])))
+}
+
+const LC_VERSION_MIN_MACOSX: u32 = 0x24;
+const LC_BUILD_VERSION: u32 = 0x32;
+const PLATFORM_MACOS: u32 = 1;
+
+// Parses a `X[.Y[.Z]]` macOS version into the `xxxx.yy.zz` nibbles of the Mach-O load commands.
+pub(super) fn parse_macos_version(version: &str) -> Option<u32> {
+    let mut parts = version.trim().split('.');
+    let mut packed = 0;
+    for (shift, max) in [(16, 0xffff), (8, 0xff), (0, 0xff)] {
+        let part = match parts.next() {
+            Some(part) => part.parse::<u32>().ok().filter(|p| *p <= max)?,
+            None if shift < 16 => 0,
+            None => return None,
+        };
+        packed |= part << shift;
+    }
+    parts.next().is_none().then_some(packed)
+}
+
+fn format_macos_version(version: u32) -> String {
+    let (major, minor, patch) = (version >> 16, (version >> 8) & 0xff, version & 0xff);
+    if patch == 0 {
+        format!("{major}.{minor}")
+    } else {
+        format!("{major}.{minor}.{patch}")
+    }
+}
+
+// Reads the minimum macOS version of each architecture of a thin or universal 64-bit Mach-O binary,
+// from its `LC_BUILD_VERSION` or `LC_VERSION_MIN_MACOSX` load command.
+fn macho_minimum_versions(path: &Path) -> crate::Result<Vec<u32>> {
+    let data = fs::read(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let u32_be = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+    };
+
+    // the slices of a universal binary are described by big-endian `fat_arch` entries
+    let slices = if u32_be(0) == Some(0xcafebabe) {
+        let count = u32_be(4).unwrap_or_default() as usize;
+        (0..count)
+            .filter_map(|i| u32_be(8 + i * 20 + 8).map(|offset| offset as usize))
+            .collect()
+    } else {
+        vec![0]
+    };
+
+    let mut versions = Vec::new();
+    for slice in slices {
+        let u32_le = |offset: usize| {
+            data.get(slice + offset..slice + offset + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        };
+        if u32_le(0) != Some(0xfeedfacf) {
+            continue;
+        }
+        let commands = u32_le(16).unwrap_or_default();
+        // the load commands follow the 32 bytes of the `mach_header_64`
+        let mut offset = 32;
+        for _ in 0..commands {
+            let (Some(cmd), Some(size)) = (u32_le(offset), u32_le(offset + 4)) else {
+                break;
+            };
+            let version = match cmd {
+                LC_BUILD_VERSION if u32_le(offset + 8) == Some(PLATFORM_MACOS) => {
+                    u32_le(offset + 12)
+                }
+                LC_VERSION_MIN_MACOSX => u32_le(offset + 8),
+                _ => None,
+            };
+            if let Some(version) = version {
+                versions.push(version);
+                break;
+            }
+            if size == 0 {
+                break;
+            }
+            offset += size as usize;
+        }
+    }
+    Ok(versions)
+}
+
+// Warns when the binary at `path` requires a newer macOS than the `LSMinimumSystemVersion` of the app,
+// so it would crash on the oldest versions the app claims to support.
+fn check_minimum_system_version(minimum_system_version: &str, path: &Path) -> crate::Result<()> {
+    let Some(minimum) = parse_macos_version(minimum_system_version) else {
+        tracing::warn!(
+            "The minimum system version `{minimum_system_version}` is not a valid macOS version, skipping the compatibility check of {}",
+            path.display()
+        );
+        return Ok(());
+    };
+    if let Some(required) = macho_minimum_versions(path)?
+        .into_iter()
+        .filter(|v| *v > minimum)
+        .max()
+    {
+        tracing::warn!(
+            "{} requires macOS {} but the minimum system version of the app is {minimum_system_version}, set the `MACOSX_DEPLOYMENT_TARGET` environment variable to {minimum_system_version} when building it",
+            path.display(),
+            format_macos_version(required)
+        );
+    }
+    Ok(())
+}
+
+// Creates a universal binary at `dest_path` from the per-architecture builds of `bin_path`,
+// or copies the binary when only one architecture was provided.
+#[tracing::instrument(level = "trace")]
+fn create_universal_binary(
+    universal_binary: &crate::config::MacOsUniversalBinaryConfig,
+    bin_path: &Path,
+    dest_path: &Path,
+) -> crate::Result<()> {
+    let mut inputs = Vec::new();
+    for (dir, arch, cpu_type) in [
+        (
+            &universal_binary.x86_64_binaries_dir,
+            "x86_64",
+            CPU_TYPE_X86_64,
+        ),
+        (
+            &universal_binary.aarch64_binaries_dir,
+            "aarch64",
+            CPU_TYPE_ARM64,
+        ),
+    ] {
+        let Some(dir) = dir else {
+            continue;
+        };
+
+        let path = match bin_path.file_name() {
+            Some(file_name) if bin_path.is_absolute() => dir.join(file_name),
+            _ => dir.join(bin_path),
+        };
+        if !path.exists() {
+            return Err(Error::DoesNotExist(path));
+        }
+        if macho_cpu_type(&path)? != Some(cpu_type) {
+            return Err(Error::UnexpectedBinaryArch(path, arch));
+        }
+        inputs.push(path);
+    }
+
+    match inputs.as_slice() {
+        [] => Err(Error::DoesNotExist(bin_path.to_path_buf())),
+        [input] => {
+            fs::copy(input, dest_path)
+                .map_err(|e| Error::CopyFile(input.clone(), dest_path.to_path_buf(), e))?;
+            Ok(())
+        }
+        _ => {
+            tracing::debug!("Creating universal binary {}", dest_path.display());
+            std::process::Command::new("lipo")
+                .arg("-create")
+                .arg("-output")
+                .arg(dest_path)
+                .args(&inputs)
+                .output_ok()
+                .map_err(Error::FailedToRunLipo)?;
+            Ok(())
+        }
+    }
+}
+
+/// Parses the `otool -L` output of a Mach-O file into the install names of the dylibs it loads,
+/// listed once for every architecture of a universal binary, the install name of a dylib first.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_otool_dylibs(output: &str) -> Vec<String> {
+    let mut dylibs = Vec::new();
+    for line in output
+        .lines()
+        .filter(|l| l.starts_with(char::is_whitespace))
+    {
+        let line = line.trim();
+        let name = line
+            .rsplit_once(" (compatibility version")
+            .map_or(line, |(name, _)| name);
+        if !dylibs.iter().any(|d| d == name) {
+            dylibs.push(name.to_string());
+        }
+    }
+    dylibs
+}
+
+/// Parses the `otool -l` output of a Mach-O file into the paths of its `LC_RPATH` load commands.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_otool_rpaths(output: &str) -> Vec<String> {
+    let mut rpaths = Vec::new();
+    let mut lines = output.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line != "cmd LC_RPATH" {
+            continue;
+        }
+        if let Some(path) = lines.find_map(|l| l.strip_prefix("path ")) {
+            let path = path.rsplit_once(" (offset").map_or(path, |(path, _)| path);
+            rpaths.push(path.to_string());
+        }
+    }
+    rpaths
+}
+
+/// Whether a dylib loaded from `install_name` is found on every Mac, relative to the
+/// loading file or the rpaths, or one of the system libraries.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn is_relocatable_install_name(install_name: &str) -> bool {
+    !install_name.starts_with('/')
+        || install_name.starts_with("/usr/lib/")
+        || install_name.starts_with("/System/")
+}
+
+#[cfg(target_os = "macos")]
+fn otool(path: &Path, flag: &str) -> crate::Result<String> {
+    let output = std::process::Command::new("otool")
+        .arg(flag)
+        .arg(path)
+        .output_ok()
+        .map_err(Error::FailedToRunOtool)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+fn install_name_tool<const N: usize>(path: &Path, args: [&str; N]) -> crate::Result<()> {
+    std::process::Command::new("install_name_tool")
+        .args(args)
+        .arg(path)
+        .output_ok()
+        .map_err(Error::FailedToRunInstallNameTool)?;
+    Ok(())
+}
+
+/// Makes the bundled `dylibs` loadable on other Macs: rewrites their `LC_ID_DYLIB` to `@rpath/<name>`
+/// and the references of the `binaries` and `dylibs` to them, discovered with `otool -L`,
+/// adding the rpath of the `Frameworks` directory to the files loading them.
+///
+/// Fails with [`Error::NonRelocatableDylib`] when a file still loads a dylib from an absolute path
+/// outside of the system libraries, like the build directory or a Homebrew prefix.
+#[cfg(target_os = "macos")]
+fn relocate_dylibs(config: &Config, binaries: &[PathBuf], dylibs: &[PathBuf]) -> crate::Result<()> {
+    let names = dylibs
+        .iter()
+        .map(|d| {
+            d.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    for (dylib, name) in dylibs.iter().zip(&names) {
+        install_name_tool(dylib, ["-id", &format!("@rpath/{name}")])?;
+    }
+
+    let mut modified = dylibs.to_vec();
+    let files = binaries
+        .iter()
+        .map(|b| (b, "@executable_path/../Frameworks"))
+        .chain(dylibs.iter().map(|d| (d, "@loader_path")));
+    for (file, rpath) in files {
+        let mut loads_bundled_dylib = false;
+        for dependency in parse_otool_dylibs(&otool(file, "-L")?) {
+            let name = Path::new(&dependency).file_name().unwrap_or_default();
+            if !names.iter().any(|n| n.as_str() == name) {
+                continue;
+            }
+            loads_bundled_dylib = true;
+            let relocated = format!("@rpath/{}", name.to_string_lossy());
+            if dependency != relocated {
+                install_name_tool(file, ["-change", &dependency, &relocated])?;
+                modified.push(file.clone());
+            }
+        }
+        if loads_bundled_dylib
+            && !parse_otool_rpaths(&otool(file, "-l")?)
+                .iter()
+                .any(|r| r == rpath)
+        {
+            install_name_tool(file, ["-add_rpath", rpath])?;
+            modified.push(file.clone());
+        }
+
+        if let Some(dependency) = parse_otool_dylibs(&otool(file, "-L")?)
+            .into_iter()
+            .find(|d| !is_relocatable_install_name(d))
+        {
+            return Err(Error::NonRelocatableDylib(file.clone(), dependency));
+        }
+    }
+
+    // install_name_tool invalidates the signatures, without which the arm64 binaries don't run,
+    // so the modified files are signed ad hoc when the app is not signed afterwards
+    if config
+        .macos()
+        .and_then(|m| m.signing_identity.as_ref())
+        .is_none()
+    {
+        modified.sort();
+        modified.dedup();
+        for file in modified {
+            std::process::Command::new("codesign")
+                .args(["--force", "--sign", "-"])
+                .arg(&file)
+                .output_ok()
+                .map_err(Error::FailedToRunCodesign)?;
+        }
+    }
+    Ok(())
 }
 
 // Creates the Info.plist file.
@@ -216,7 +616,8 @@ fn create_info_plist(
                 .into(),
         );
     }
-    plist.insert("CFBundleIdentifier".into(), config.identifier().into());
+    let identifier = config.bundle_identifier()?;
+    plist.insert("CFBundleIdentifier".into(), identifier.clone().into());
     plist.insert("CFBundleInfoDictionaryVersion".into(), "6.0".into());
     plist.insert("CFBundleName".into(), config.product_name.clone().into());
     plist.insert("CFBundlePackageType".into(), "APPL".into());
@@ -225,6 +626,9 @@ fn create_info_plist(
         config.version.clone().into(),
     );
     plist.insert("CFBundleVersion".into(), build_number.into());
+    if let Some(build_id) = &config.build_id {
+        plist.insert("CargoPackagerBuildId".into(), build_id.clone().into());
+    }
     plist.insert("CSResourcesFileMapped".into(), true.into());
     if let Some(category) = &config.category {
         plist.insert(
@@ -300,11 +704,7 @@ fn create_info_plist(
                             protocol
                                 .name
                                 .clone()
-                                .unwrap_or(format!(
-                                    "{} {}",
-                                    config.identifier(),
-                                    protocol.schemes[0]
-                                ))
+                                .unwrap_or(format!("{identifier} {}", protocol.schemes[0]))
                                 .into(),
                         );
                         dict.insert("CFBundleTypeRole".into(), protocol.role.to_string().into());
@@ -548,3 +948,321 @@ fn copy_embedded_apps(contents_directory: &Path, config: &Config) -> crate::Resu
     }
     Ok(paths)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_macho_cpu_type() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let arm64 = dir.path().join("arm64");
+        fs::write(&arm64, [0xcf, 0xfa, 0xed, 0xfe, 0x0c, 0x00, 0x00, 0x01]).unwrap();
+        assert_eq!(macho_cpu_type(&arm64).unwrap(), Some(CPU_TYPE_ARM64));
+
+        let x86_64 = dir.path().join("x86_64");
+        fs::write(&x86_64, [0xcf, 0xfa, 0xed, 0xfe, 0x07, 0x00, 0x00, 0x01]).unwrap();
+        assert_eq!(macho_cpu_type(&x86_64).unwrap(), Some(CPU_TYPE_X86_64));
+
+        let not_macho = dir.path().join("not-macho");
+        fs::write(&not_macho, "#!/bin/sh\n").unwrap();
+        assert_eq!(macho_cpu_type(&not_macho).unwrap(), None);
+    }
+
+    #[test]
+    fn it_parses_otool_output() {
+        let dylibs = "app.app/Contents/MacOS/app (architecture x86_64):
+\t/Users/me/app/target/release/deps/libfoo.dylib (compatibility version 0.0.0, current version 0.0.0)
+\t/usr/lib/libSystem.B.dylib (compatibility version 1.0.0, current version 1319.0.0)
+app.app/Contents/MacOS/app (architecture arm64):
+\t/Users/me/app/target/release/deps/libfoo.dylib (compatibility version 0.0.0, current version 0.0.0)
+\t@rpath/libbar.dylib (compatibility version 1.0.0, current version 1.2.0)
+";
+        let dylibs = parse_otool_dylibs(dylibs);
+        assert_eq!(
+            dylibs,
+            [
+                "/Users/me/app/target/release/deps/libfoo.dylib",
+                "/usr/lib/libSystem.B.dylib",
+                "@rpath/libbar.dylib"
+            ]
+        );
+        assert_eq!(
+            dylibs
+                .iter()
+                .filter(|d| !is_relocatable_install_name(d))
+                .collect::<Vec<_>>(),
+            ["/Users/me/app/target/release/deps/libfoo.dylib"]
+        );
+
+        let load_commands = "Load command 12
+          cmd LC_LOAD_DYLIB
+      cmdsize 56
+         name /usr/lib/libSystem.B.dylib (offset 24)
+Load command 13
+          cmd LC_RPATH
+      cmdsize 48
+         path @executable_path/../Frameworks (offset 12)
+";
+        assert_eq!(
+            parse_otool_rpaths(load_commands),
+            ["@executable_path/../Frameworks"]
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn it_relocates_bundled_dylibs() {
+        use std::process::Command;
+
+        let dir = tempfile::tempdir().unwrap();
+        let build_dir = dir.path().join("build");
+        fs::create_dir_all(&build_dir).unwrap();
+        fs::write(build_dir.join("foo.c"), "int foo(void) { return 42; }").unwrap();
+        fs::write(
+            build_dir.join("main.c"),
+            "int foo(void);\nint main(void) { return foo(); }",
+        )
+        .unwrap();
+        // the install name of the dylib defaults to its absolute build path
+        let lib = build_dir.join("libfoo.dylib");
+        Command::new("cc")
+            .arg("-dynamiclib")
+            .arg("-o")
+            .arg(&lib)
+            .arg(build_dir.join("foo.c"))
+            .output_ok()
+            .unwrap();
+        let main = build_dir.join("main");
+        Command::new("cc")
+            .arg("-o")
+            .arg(&main)
+            .arg(build_dir.join("main.c"))
+            .arg(&lib)
+            .output_ok()
+            .unwrap();
+
+        let contents = dir.path().join("App.app/Contents");
+        let (bin_dir, frameworks_dir) = (contents.join("MacOS"), contents.join("Frameworks"));
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::create_dir_all(&frameworks_dir).unwrap();
+        let (binary, dylib) = (bin_dir.join("main"), frameworks_dir.join("libfoo.dylib"));
+        fs::copy(&main, &binary).unwrap();
+        fs::copy(&lib, &dylib).unwrap();
+        fs::remove_dir_all(&build_dir).unwrap();
+
+        relocate_dylibs(
+            &Config::default(),
+            std::slice::from_ref(&binary),
+            std::slice::from_ref(&dylib),
+        )
+        .unwrap();
+
+        let dylib_names = parse_otool_dylibs(&otool(&dylib, "-L").unwrap());
+        assert_eq!(dylib_names[0], "@rpath/libfoo.dylib");
+        let binary_dylibs = parse_otool_dylibs(&otool(&binary, "-L").unwrap());
+        assert!(binary_dylibs.contains(&"@rpath/libfoo.dylib".to_string()));
+        assert!(binary_dylibs.iter().all(|d| is_relocatable_install_name(d)));
+        assert!(parse_otool_rpaths(&otool(&binary, "-l").unwrap())
+            .contains(&"@executable_path/../Frameworks".to_string()));
+        // the build directory is gone, the dylib is loaded from the bundle
+        assert_eq!(Command::new(&binary).status().unwrap().code(), Some(42));
+    }
+
+    // A thin 64-bit Mach-O binary with a single load command.
+    fn macho_with_command(cmd: u32, args: &[u32]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for word in [
+            0xfeedfacf,
+            CPU_TYPE_ARM64,
+            0,
+            2,
+            1,
+            8 + 4 * args.len() as u32,
+            0,
+            0,
+        ] {
+            data.extend(u32::to_le_bytes(word));
+        }
+        for word in [cmd, 8 + 4 * args.len() as u32].iter().chain(args) {
+            data.extend(word.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn it_writes_the_category_to_info_plist() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.identifier = Some("com.example.app".into());
+        config.binaries = vec![crate::config::Binary::new("app").main(true)];
+        config.category = Some("Developer Tool".parse().unwrap());
+        create_info_plist(dir.path(), None, &config).unwrap();
+
+        let plist = plist::Value::from_file(dir.path().join("Info.plist")).unwrap();
+        assert_eq!(
+            plist.as_dictionary().unwrap()["LSApplicationCategoryType"].as_string(),
+            Some("public.app-category.developer-tools")
+        );
+    }
+
+    #[test]
+    fn it_reads_the_minimum_system_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Contents");
+        fs::create_dir_all(&contents).unwrap();
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.identifier = Some("com.example.app".into());
+        config.binaries = vec![crate::config::Binary::new("app").main(true)];
+        config.macos = Some(crate::config::MacOsConfig::new().minimum_system_version("10.13"));
+        create_info_plist(&contents, None, &config).unwrap();
+        let plist = plist::Value::from_file(contents.join("Info.plist")).unwrap();
+        let minimum = plist.as_dictionary().unwrap()["LSMinimumSystemVersion"]
+            .as_string()
+            .unwrap();
+        assert_eq!(parse_macos_version(minimum), Some(0x000a0d00));
+
+        // LC_BUILD_VERSION of macOS 11.0 with the 14.2 SDK
+        let build_version = dir.path().join("build_version");
+        fs::write(
+            &build_version,
+            macho_with_command(
+                LC_BUILD_VERSION,
+                &[PLATFORM_MACOS, 0x000b0000, 0x000e0200, 0],
+            ),
+        )
+        .unwrap();
+        let versions = macho_minimum_versions(&build_version).unwrap();
+        assert_eq!(versions, [0x000b0000]);
+        assert!(versions[0] > parse_macos_version(minimum).unwrap());
+        assert_eq!(format_macos_version(versions[0]), "11.0");
+
+        let version_min = dir.path().join("version_min");
+        fs::write(
+            &version_min,
+            macho_with_command(LC_VERSION_MIN_MACOSX, &[0x000a0c01, 0x000a0f00]),
+        )
+        .unwrap();
+        assert_eq!(macho_minimum_versions(&version_min).unwrap(), [0x000a0c01]);
+        assert_eq!(format_macos_version(0x000a0c01), "10.12.1");
+
+        // the slices of a universal binary
+        let slice = fs::read(&build_version).unwrap();
+        let mut universal = Vec::new();
+        for word in [
+            0xcafebabe,
+            1,
+            CPU_TYPE_ARM64,
+            0,
+            4096,
+            slice.len() as u32,
+            12,
+        ] {
+            universal.extend(u32::to_be_bytes(word));
+        }
+        universal.resize(4096, 0);
+        universal.extend(slice);
+        let universal_path = dir.path().join("universal");
+        fs::write(&universal_path, universal).unwrap();
+        assert_eq!(
+            macho_minimum_versions(&universal_path).unwrap(),
+            [0x000b0000]
+        );
+
+        for version in ["", "10.13.1.2", "ten", "10.256"] {
+            assert_eq!(parse_macos_version(version), None, "{version}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_sets_file_modes_in_app_bundle() {
+        use crate::config::{Binary, Resource};
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("out");
+        fs::create_dir_all(&out_dir).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+        // a Mach-O binary that lost its executable bit
+        let bin = out_dir.join("app");
+        fs::write(&bin, [0xcf, 0xfa, 0xed, 0xfe, 0x0c, 0x00, 0x00, 0x01]).unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o644)).unwrap();
+        let helper = dir.path().join("helper.sh");
+        fs::write(&helper, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&helper, fs::Permissions::from_mode(0o644)).unwrap();
+        let tool = dir.path().join("tool.sh");
+        fs::write(&tool, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+        let data = dir.path().join("data.json");
+        fs::write(&data, "{}").unwrap();
+        fs::set_permissions(&data, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.identifier = Some("com.example.app".into());
+        config.out_dir = out_dir;
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.resources = Some(vec![
+            Resource::Mapped {
+                src: helper.to_string_lossy().into_owned(),
+                target: "scripts/helper.sh".into(),
+                mode: Some("0755".into()),
+                optional: false,
+            },
+            Resource::Single(tool.to_string_lossy().into_owned()),
+            Resource::Single(data.to_string_lossy().into_owned()),
+        ]);
+
+        let ctx = Context::new(&config).unwrap();
+        let app = package(&ctx).unwrap().remove(0);
+        let contents = app.join("Contents");
+        assert_eq!(mode(&contents.join("MacOS/app")), 0o755);
+        assert_eq!(mode(&contents.join("Resources/scripts/helper.sh")), 0o755);
+        assert_eq!(mode(&contents.join("Resources/tool.sh")), 0o755);
+        assert_eq!(mode(&contents.join("Resources/data.json")), 0o644);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn it_creates_universal_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("main.c");
+        fs::write(&source, "int main() { return 0; }\n").unwrap();
+
+        let mut universal_binary = crate::config::MacOsUniversalBinaryConfig::new();
+        for (arch, name) in [("x86_64", "x86_64"), ("arm64", "aarch64")] {
+            let arch_dir = dir.path().join(name);
+            fs::create_dir_all(&arch_dir).unwrap();
+            std::process::Command::new("cc")
+                .args(["-arch", arch, "-o"])
+                .arg(arch_dir.join("app"))
+                .arg(&source)
+                .output_ok()
+                .unwrap();
+            universal_binary = match name {
+                "x86_64" => universal_binary.x86_64_binaries_dir(arch_dir),
+                _ => universal_binary.aarch64_binaries_dir(arch_dir),
+            };
+        }
+
+        let dest_path = dir.path().join("app");
+        create_universal_binary(&universal_binary, Path::new("app"), &dest_path).unwrap();
+
+        let output = std::process::Command::new("lipo")
+            .arg("-info")
+            .arg(&dest_path)
+            .output()
+            .unwrap();
+        let info = String::from_utf8_lossy(&output.stdout);
+        assert!(info.contains("x86_64"));
+        assert!(info.contains("arm64"));
+    }
+}
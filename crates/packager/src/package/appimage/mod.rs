@@ -6,35 +6,49 @@
 use std::{
     collections::BTreeMap,
     fs,
+    io::Read,
     os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
     process::Command,
+    time::{Duration, UNIX_EPOCH},
 };
 
 use handlebars::{to_json, Handlebars};
+use sha2::{Digest, Sha256};
 
-use super::{deb, Context};
-use crate::{shell::CommandExt, util, Error};
+use super::{deb, naming, tools, Context};
+use crate::{config::PackageFormat, elf::Elf, shell::CommandExt, util, Config, Error};
 
+/// The tools of [`donwload_dependencies`] that can be pinned in [`Config::pinned_tools`](crate::Config::pinned_tools).
+const PINNABLE_DEPENDENCIES: &[&str] = &["apprun", "linuxdeploy", "linuxdeploy-plugin-appimage"];
+
+/// Downloads the tools used to build the AppImage into `cache_path`, unless they exist,
+/// and copies them into `appimage_tools_path` when it differs.
+///
+/// The pinned tools are copied from their pinned path instead,
+/// and the digests of the tools are checked against their pin.
 #[tracing::instrument(level = "trace", skip(ctx))]
 fn donwload_dependencies(
     ctx: &Context,
+    cache_path: &Path,
     appimage_tools_path: &Path,
     arch: &str,
     linuxdeploy_arch: &str,
 ) -> crate::Result<()> {
     let internal_deps = vec![
         (
+            "apprun",
             format!("AppRun-{arch}"),
             format!("https://github.com/tauri-apps/binary-releases/releases/download/apprun-old/AppRun-{arch}")
         ),
         (
+            "linuxdeploy",
             format!("linuxdeploy-{linuxdeploy_arch}.AppImage"),
             format!("https://github.com/tauri-apps/binary-releases/releases/download/linuxdeploy/linuxdeploy-{linuxdeploy_arch}.AppImage")
         ),
-        // This path is incompatible with cross-platform compilation but linuxdeploy doens't support that anyway.
         (
-            "linuxdeploy-plugin-appimage.AppImage".to_string(),
+            "linuxdeploy-plugin-appimage",
+            format!("linuxdeploy-plugin-appimage-{arch}.AppImage"),
             format!("https://github.com/linuxdeploy/linuxdeploy-plugin-appimage/releases/download/continuous/linuxdeploy-plugin-appimage-{arch}.AppImage")
         ),
     ];
@@ -45,39 +59,121 @@ fn donwload_dependencies(
         .and_then(|a| a.linuxdeploy_plugins.clone())
         .unwrap_or_default()
         .into_iter()
-        .map(|mut p| {
-            p.0 = format!("linuxdeploy-plugin-{}.sh", p.0);
-            p
-        })
-        .collect();
-
-    for (path, url) in [internal_deps, user_deps].concat() {
-        let path = appimage_tools_path.join(path);
-        if !path.exists() {
-            let data = util::download(&url)?;
-            tracing::debug!(
-                "Writing {} and setting its permissions to 764",
-                path.display()
-            );
-            fs::write(&path, data).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+        .map(|(name, url)| (None, format!("linuxdeploy-plugin-{name}.sh"), url));
+
+    for (tool, file_name, url) in internal_deps
+        .into_iter()
+        .map(|(tool, file_name, url)| (Some(tool), file_name, url))
+        .chain(user_deps)
+    {
+        let path = appimage_tools_path.join(&file_name);
+        let src = match tool.and_then(|tool| tools::pinned_path(ctx, tool)) {
+            Some(pinned) => pinned,
+            None => {
+                let cached = cache_path.join(&file_name);
+                if !cached.exists() {
+                    let data = util::download(&url)?;
+                    tracing::debug!(
+                        "Writing {} and setting its permissions to 764",
+                        cached.display()
+                    );
+                    fs::write(&cached, data).map_err(|e| Error::IoWithPath(cached.clone(), e))?;
+                    fs::set_permissions(&cached, fs::Permissions::from_mode(0o764))
+                        .map_err(|e| Error::IoWithPath(cached.clone(), e))?;
+                }
+                cached
+            }
+        };
+        if src != path {
+            fs::copy(&src, &path).map_err(|e| Error::CopyFile(src, path.clone(), e))?;
             fs::set_permissions(&path, fs::Permissions::from_mode(0o764))
-                .map_err(|e| Error::IoWithPath(path, e))?;
+                .map_err(|e| Error::IoWithPath(path.clone(), e))?;
+        }
+        match tool {
+            Some("apprun") => tools::check_file(ctx, "apprun", &path)?,
+            Some(tool) => tools::check_digest(ctx, tool, &published_digest(&path)?)?,
+            None => {}
         }
     }
 
     Ok(())
 }
 
+/// Returns the SHA-256 hex digest of the AppImage tool at `path` as published,
+/// restoring its AppImage magic bytes that the build script zeroes in place
+/// to run it without FUSE, so the digest doesn't change after the first build.
+fn published_digest(path: &Path) -> crate::Result<String> {
+    let mut data = fs::read(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    if data.get(1..4) == Some(b"ELF") && data.get(8..11) == Some(&[0, 0, 0]) {
+        data[8..11].copy_from_slice(b"AI\x02");
+    }
+    Ok(hex::encode(Sha256::digest(&data)))
+}
+
+/// Returns the AppImage and linuxdeploy names of the architecture of `config`.
+fn appimage_arch(config: &Config) -> crate::Result<(&str, &str)> {
+    let arch = naming::package_arch(config, PackageFormat::AppImage)?;
+    let linuxdeploy_arch = match arch {
+        "i686" => "i386",
+        "armhf" => "arm",
+        other => other,
+    };
+    Ok((arch, linuxdeploy_arch))
+}
+
+/// Returns the [`naming::appimage_architecture_configs`] of the
+/// [`AppImageConfig::architectures`](crate::config::AppImageConfig::architectures).
+///
+/// The ELF machine type of the binaries is checked against the architecture they are declared for.
+fn architecture_configs(config: &Config) -> crate::Result<Vec<Config>> {
+    let configs = naming::appimage_architecture_configs(config);
+    for config in &configs {
+        let (arch, _) = appimage_arch(config)?;
+        for binary in &config.binaries {
+            let path = config.binary_path(binary);
+            let mut header = Vec::with_capacity(64);
+            fs::File::open(&path)
+                .and_then(|f| f.take(64).read_to_end(&mut header))
+                .map_err(|e| Error::IoWithPath(path.clone(), e))?;
+            let machine = Elf::parse(&header).ok().and_then(|elf| elf.machine());
+            if machine.is_none() || machine != elf_machine(arch) {
+                return Err(Error::AppImageBinaryArchMismatch(path, arch.into()));
+            }
+        }
+    }
+    Ok(configs)
+}
+
 #[tracing::instrument(level = "trace", skip(ctx))]
 pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
-    let Context {
-        config,
-        intermediates_path,
-        tools_path,
-        ..
-    } = ctx;
-
-    let mut config = config.clone();
+    if ctx
+        .config
+        .appimage()
+        .and_then(|a| a.architectures.as_ref())
+        .is_none()
+    {
+        return package_arch(ctx, ctx.config.clone(), &ctx.intermediates_path);
+    }
+
+    let configs = architecture_configs(&ctx.config)?;
+    let mut outputs = Vec::new();
+    for config in configs {
+        let (arch, _) = appimage_arch(&config)?;
+        let intermediates_path = ctx.intermediates_path.join(format!("appimage_{arch}"));
+        fs::create_dir_all(&intermediates_path)
+            .map_err(|e| Error::IoWithPath(intermediates_path.clone(), e))?;
+        outputs.extend(package_arch(ctx, config, &intermediates_path)?);
+    }
+    Ok(outputs)
+}
+
+/// Packages the AppImage of the architecture of `config` using `intermediates_path` for its intermediate files.
+fn package_arch(
+    ctx: &Context,
+    mut config: Config,
+    intermediates_path: &Path,
+) -> crate::Result<Vec<PathBuf>> {
+    let tools_path = &ctx.tools_path;
     let main_binary_name = config.main_binary_name()?;
 
     // if binary file name contains spaces, we must change it to kebab-case
@@ -92,34 +188,56 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     }
 
     // generate the deb binary name
-    let (arch, linuxdeploy_arch) = match config.target_arch()? {
-        "x86" => ("i686", "i386"),
-        "arm" => ("armhf", "arm"),
-        other => (other, other),
-    };
+    let (arch, linuxdeploy_arch) = appimage_arch(&config)?;
 
-    let appimage_tools_path = tools_path.join("AppImage");
-    fs::create_dir_all(&appimage_tools_path)
-        .map_err(|e| Error::IoWithPath(appimage_tools_path.clone(), e))?;
+    // the pinned tools are copied next to the downloaded ones in the intermediates
+    // to leave the downloaded ones in the cache untouched
+    let cache_path = tools_path.join("AppImage");
+    let appimage_tools_path = if PINNABLE_DEPENDENCIES
+        .iter()
+        .any(|tool| tools::pinned_path(ctx, tool).is_some())
+    {
+        intermediates_path.join("appimage_tools")
+    } else {
+        cache_path.clone()
+    };
+    for dir in [&cache_path, &appimage_tools_path] {
+        fs::create_dir_all(dir).map_err(|e| Error::IoWithPath(dir.clone(), e))?;
+    }
 
-    donwload_dependencies(ctx, &appimage_tools_path, arch, linuxdeploy_arch)?;
+    donwload_dependencies(
+        ctx,
+        &cache_path,
+        &appimage_tools_path,
+        arch,
+        linuxdeploy_arch,
+    )?;
 
     let appimage_deb_data_dir = intermediates_path.join("appimage_deb").join("data");
     let intermediates_path = intermediates_path.join("appimage");
 
     // generate deb_folder structure
     tracing::debug!("Generating data");
-    let icons = deb::generate_data(&config, &appimage_deb_data_dir)?;
+    let icons = deb::generate_data(&config, &appimage_deb_data_dir, PackageFormat::AppImage)?;
     tracing::debug!("Copying files specified in `appimage.files`");
     if let Some(files) = config.appimage().and_then(|d| d.files.as_ref()) {
         deb::copy_custom_files(files, &appimage_deb_data_dir)?;
     }
+    let modes = super::file_modes(
+        &config,
+        &appimage_deb_data_dir,
+        deb::resource_modes(
+            &config,
+            &Path::new("usr/lib").join(config.main_binary_name()?),
+        )?,
+    )?;
+    super::apply_file_modes(&appimage_deb_data_dir, &modes)?;
     let icons: Vec<deb::DebIcon> = icons.into_iter().collect();
 
     let main_binary_name = config.main_binary_name()?;
     let upcase_app_name = main_binary_name.to_uppercase();
     let app_dir_path = intermediates_path.join(format!("{}.AppDir", &main_binary_name));
-    let appimage_filename = format!("{}_{}_{}.AppImage", main_binary_name, config.version, arch);
+    let appimage_filename = naming::package_file_name(&config, PackageFormat::AppImage)?;
     let appimage_path = config.out_dir().join(&appimage_filename);
 
     fs::create_dir_all(&app_dir_path).map_err(|e| Error::IoWithPath(app_dir_path.clone(), e))?;
@@ -168,6 +286,20 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         .join(" ");
     sh_map.insert("excluded_libs", to_json(excluded_libraries));
 
+    let update_information = config
+        .appimage()
+        .and_then(|a| a.update_information.as_deref());
+    if let Some(update_information) = update_information {
+        validate_update_information(update_information)?;
+    }
+
+    let exclude = parse_exclude_patterns(
+        config
+            .appimage()
+            .and_then(|a| a.exclude.as_deref())
+            .unwrap_or_default(),
+    )?;
+
     let larger_icon = icons
         .iter()
         .filter(|i| i.width == i.height)
@@ -204,11 +336,712 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         appimage_path.display()
     );
 
-    // execute the shell script to build the appimage.
+    // execute the shell script to build the AppDir.
     Command::new(&sh_file)
-        .current_dir(intermediates_path)
+        .current_dir(&intermediates_path)
         .output_ok()
         .map_err(crate::Error::AppImageScriptFailed)?;
 
-    Ok(vec![appimage_path])
+    if !exclude.is_empty() {
+        tracing::debug!("Removing the files of `appimage.exclude` from the AppDir");
+        exclude_files(&app_dir_path, &exclude)?;
+    }
+
+    let source_date_epoch = util::source_date_epoch();
+
+    let zsync_file_name = format!("{appimage_filename}.zsync");
+    let intermediate_zsync_path = intermediates_path.join(&zsync_file_name);
+
+    ctx.write_artifact(&appimage_path, |partial| {
+        // create the AppImage from the AppDir, mksquashfs always sorts the entries
+        // and uses `SOURCE_DATE_EPOCH` as the timestamp of the filesystem and its inodes.
+        let mut cmd = Command::new(
+            appimage_tools_path.join(format!("linuxdeploy-plugin-appimage-{arch}.AppImage")),
+        );
+        cmd.arg("--appimage-extract-and-run")
+            .arg("--appdir")
+            .arg(&app_dir_path)
+            .env("ARCH", arch)
+            .env("OUTPUT", partial)
+            .current_dir(&intermediates_path);
+        if let Some(update_information) = update_information {
+            // embed the update information and generate the .zsync file
+            cmd.env("UPDATE_INFORMATION", update_information);
+        }
+        apply_source_date_epoch(&app_dir_path, &mut cmd, source_date_epoch)?;
+        if let Some(args) = config.appimage().and_then(|a| a.extra_tool_args.as_ref()) {
+            tracing::debug!("Appending extra linuxdeploy-plugin-appimage arguments: {args:?}");
+            cmd.args(args);
+        }
+        cmd.output_ok().map_err(|e| {
+            if util::is_out_of_space(&e) {
+                Error::OutOfSpace {
+                    path: appimage_path.clone(),
+                }
+            } else {
+                Error::AppImageScriptFailed(e)
+            }
+        })?;
+
+        if let Some(runtime) = config.appimage().and_then(|a| a.runtime.as_deref()) {
+            tracing::debug!("Replacing the AppImage runtime with {runtime}");
+            let runtime_data = read_runtime(runtime, arch)?;
+            replace_runtime(partial, &runtime_data, runtime, arch, update_information)?;
+
+            // the .zsync file generated by appimagetool is invalidated by the new runtime
+            if intermediate_zsync_path.exists() {
+                let regenerated = Command::new("zsyncmake")
+                    .arg("-u")
+                    .arg(&appimage_filename)
+                    .arg("-o")
+                    .arg(&intermediate_zsync_path)
+                    .arg(partial)
+                    .current_dir(&intermediates_path)
+                    .output_ok()
+                    .is_ok();
+                if !regenerated {
+                    fs::remove_file(&intermediate_zsync_path)
+                        .map_err(|e| Error::IoWithPath(intermediate_zsync_path.clone(), e))?;
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut outputs = vec![appimage_path.clone()];
+
+    if update_information.is_some() {
+        // appimagetool writes the .zsync file in its working directory
+        let zsync_path = config.out_dir().join(&zsync_file_name);
+        if intermediate_zsync_path.exists() {
+            fs::rename(&intermediate_zsync_path, &zsync_path).map_err(|e| {
+                Error::RenameFile(intermediate_zsync_path.clone(), zsync_path.clone(), e)
+            })?;
+        }
+
+        if zsync_path.exists() {
+            outputs.push(zsync_path);
+        } else {
+            tracing::warn!(
+                "Could not find the generated {zsync_file_name} file, make sure `zsyncmake` is installed"
+            );
+        }
+    }
+
+    Ok(outputs)
+}
+
+/// Parses the globs of [`AppImageConfig::exclude`](crate::config::AppImageConfig::exclude).
+fn parse_exclude_patterns(patterns: &[String]) -> crate::Result<Vec<(&str, glob::Pattern)>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let glob = pattern.trim_start_matches("./").trim_start_matches('/');
+            glob::Pattern::new(glob)
+                .map(|glob| (pattern.as_str(), glob))
+                .map_err(|e| Error::InvalidAppImageExcludePattern(pattern.clone(), e.msg.into()))
+        })
+        .collect()
+}
+
+/// Removes the files and directories of `app_dir` whose path relative to it matches one of `patterns`,
+/// warning about the patterns that match nothing.
+fn exclude_files(app_dir: &Path, patterns: &[(&str, glob::Pattern)]) -> crate::Result<()> {
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let mut matched = vec![false; patterns.len()];
+
+    let mut entries = walkdir::WalkDir::new(app_dir).min_depth(1).into_iter();
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = path.strip_prefix(app_dir).unwrap_or(path);
+        let relative = relative.to_string_lossy();
+
+        let mut excluded = false;
+        for (matched, (_, pattern)) in matched.iter_mut().zip(patterns) {
+            if pattern.matches_with(&relative, options) {
+                *matched = true;
+                excluded = true;
+            }
+        }
+        if !excluded {
+            continue;
+        }
+
+        tracing::debug!("Excluding {relative} from the AppImage");
+        if entry.file_type().is_dir() {
+            entries.skip_current_dir();
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    }
+
+    for (matched, (pattern, _)) in matched.iter().zip(patterns) {
+        if !matched {
+            tracing::warn!(
+                "The `appimage.exclude` pattern `{pattern}` matches no file of the AppDir"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Makes the AppImage built from `app_dir` by `cmd` reproducible when `SOURCE_DATE_EPOCH` is set,
+/// by setting the modification time of the AppDir files to `epoch` and passing it to the tool.
+fn apply_source_date_epoch(
+    app_dir: &Path,
+    cmd: &mut Command,
+    epoch: Option<u64>,
+) -> crate::Result<()> {
+    if let Some(epoch) = epoch {
+        tracing::debug!("Setting the modification time of the AppDir files to {epoch}");
+        set_mtimes(app_dir, epoch)?;
+        cmd.env("SOURCE_DATE_EPOCH", epoch.to_string());
+    }
+    Ok(())
+}
+
+/// Sets the modification time of `dir` and of all the files and directories inside it to `epoch`.
+///
+/// Symlinks are skipped, mksquashfs uses `SOURCE_DATE_EPOCH` for their timestamps.
+fn set_mtimes(dir: &Path, epoch: u64) -> crate::Result<()> {
+    let mtime = UNIX_EPOCH + Duration::from_secs(epoch);
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_symlink() {
+            continue;
+        }
+        let path = entry.path();
+        fs::File::open(path)
+            .and_then(|f| f.set_modified(mtime))
+            .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    }
+    Ok(())
+}
+
+/// The ELF `e_machine` value of an AppImage architecture.
+fn elf_machine(arch: &str) -> Option<u16> {
+    match arch {
+        "x86_64" => Some(62),
+        "i686" => Some(3),
+        "aarch64" => Some(183),
+        "armhf" => Some(40),
+        _ => None,
+    }
+}
+
+/// The [`AppImageConfig::runtime`](crate::config::AppImageConfig::runtime) selecting the runtime
+/// embedded with the `vendored-appimage-runtime` feature.
+const VENDORED_RUNTIME: &str = "vendored";
+
+// the `runtime-<arch>` files embedded by the build script
+#[cfg(feature = "vendored-appimage-runtime")]
+include!(concat!(env!("OUT_DIR"), "/vendored_appimage_runtime.rs"));
+#[cfg(not(feature = "vendored-appimage-runtime"))]
+const VENDORED_RUNTIMES: &[(&str, &[u8])] = &[];
+
+/// Returns the runtime of `arch` in `runtimes`, checking that it is built for that architecture.
+fn vendored_runtime(runtimes: &[(&str, &[u8])], arch: &str) -> crate::Result<Vec<u8>> {
+    let invalid = |reason: String| Error::InvalidAppImageRuntime(VENDORED_RUNTIME.into(), reason);
+    let (_, runtime) = runtimes
+        .iter()
+        .find(|(runtime_arch, _)| *runtime_arch == arch)
+        .ok_or_else(|| {
+            invalid(if cfg!(feature = "vendored-appimage-runtime") {
+                format!("no runtime was vendored for {arch}")
+            } else {
+                "cargo-packager was built without the `vendored-appimage-runtime` feature".into()
+            })
+        })?;
+    let machine = Elf::parse(runtime)
+        .map_err(|e| invalid(e.into()))?
+        .machine();
+    if machine != elf_machine(arch) {
+        return Err(invalid(format!(
+            "the runtime vendored for {arch} is not built for {arch}"
+        )));
+    }
+    Ok(runtime.to_vec())
+}
+
+/// Reads the AppImage runtime configured with [`AppImageConfig::runtime`](crate::config::AppImageConfig::runtime).
+fn read_runtime(runtime: &str, arch: &str) -> crate::Result<Vec<u8>> {
+    if runtime == VENDORED_RUNTIME {
+        return vendored_runtime(VENDORED_RUNTIMES, arch);
+    }
+    let runtime = runtime.replace("{{arch}}", arch);
+    if runtime.starts_with("https://") || runtime.starts_with("http://") {
+        return util::download(&runtime);
+    }
+
+    let path = Path::new(&runtime);
+    if path.is_file() {
+        fs::read(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))
+    } else if !runtime.contains(['/', '\\']) {
+        util::download(&format!(
+            "https://github.com/AppImage/type2-runtime/releases/download/{runtime}/runtime-{arch}"
+        ))
+    } else {
+        Err(Error::InvalidAppImageRuntime(
+            runtime.clone(),
+            "the file does not exist".into(),
+        ))
+    }
+}
+
+/// Replaces the runtime of the AppImage at `appimage_path` with `runtime`,
+/// keeping its squashfs image and embedding `update_information` into the `.upd_info` section.
+fn replace_runtime(
+    appimage_path: &Path,
+    runtime: &[u8],
+    runtime_name: &str,
+    arch: &str,
+    update_information: Option<&str>,
+) -> crate::Result<()> {
+    let invalid = |reason: &str| Error::InvalidAppImageRuntime(runtime_name.into(), reason.into());
+
+    let runtime = Elf::parse(runtime).map_err(invalid)?;
+    if runtime.machine() != elf_machine(arch) {
+        return Err(invalid(&format!("the runtime is not built for {arch}")));
+    }
+    let runtime_size = runtime
+        .size()
+        .filter(|size| *size <= runtime.data.len())
+        .ok_or_else(|| invalid("the runtime is truncated"))?;
+    // an existing AppImage can be used as the runtime, its squashfs image is discarded
+    let mut data = runtime.data[..runtime_size].to_vec();
+
+    if let Some(update_information) = update_information {
+        let (offset, size) = runtime
+            .section(".upd_info")
+            .filter(|(offset, size)| offset + size <= runtime_size)
+            .ok_or_else(|| invalid("the runtime does not have an `.upd_info` section"))?;
+        if update_information.len() > size {
+            return Err(invalid(
+                "the update information does not fit in the `.upd_info` section",
+            ));
+        }
+        data[offset..offset + size].fill(0);
+        data[offset..offset + update_information.len()]
+            .copy_from_slice(update_information.as_bytes());
+    }
+
+    let appimage =
+        fs::read(appimage_path).map_err(|e| Error::IoWithPath(appimage_path.into(), e))?;
+    let payload = Elf::parse(&appimage)
+        .ok()
+        .and_then(|elf| elf.size())
+        .and_then(|offset| appimage.get(offset..))
+        .filter(|payload| payload.starts_with(b"hsqs"))
+        .ok_or_else(|| {
+            Error::InvalidAppImageRuntime(
+                appimage_path.display().to_string(),
+                "could not find the squashfs image of the AppImage".into(),
+            )
+        })?;
+    data.extend_from_slice(payload);
+
+    fs::write(appimage_path, data).map_err(|e| {
+        util::write_error(
+            appimage_path,
+            "failed to replace the AppImage runtime",
+            e.into(),
+        )
+    })
+}
+
+// Validates the AppImage update information string.
+// See <https://github.com/AppImage/AppImageSpec/blob/master/draft.md#update-information>
+fn validate_update_information(update_information: &str) -> crate::Result<()> {
+    let parts = update_information.split('|').collect::<Vec<_>>();
+    let valid = match parts.as_slice() {
+        ["zsync", url] => url.starts_with("https://") || url.starts_with("http://"),
+        ["gh-releases-zsync", user, repo, tag, file_name] => {
+            [user, repo, tag, file_name].iter().all(|p| !p.is_empty())
+        }
+        ["pling-v1-zsync", product_id, file_name] => {
+            !product_id.is_empty() && !file_name.is_empty()
+        }
+        _ => false,
+    };
+
+    // quotes and line breaks are never part of a valid update information and
+    // would break the tools consuming it
+    if !valid || update_information.contains(['\'', '\n']) {
+        return Err(Error::InvalidAppImageUpdateInformation(
+            update_information.into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppImageArchitecture;
+    use std::time::SystemTime;
+
+    #[test]
+    fn it_hashes_the_published_appimage_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join("linuxdeploy-x86_64.AppImage");
+        let mut data = b"\x7fELF\x02\x01\x01\x00AI\x02\x00\x00\x00\x00\x00 payload".to_vec();
+        fs::write(&tool, &data).unwrap();
+        let digest = published_digest(&tool).unwrap();
+        assert_eq!(digest, crate::checksum::sha256_file(&tool).unwrap());
+
+        // zeroed by the build script to run the tool without FUSE
+        data[8..11].copy_from_slice(&[0, 0, 0]);
+        fs::write(&tool, &data).unwrap();
+        assert_eq!(published_digest(&tool).unwrap(), digest);
+    }
+
+    #[test]
+    fn it_validates_update_information() {
+        for update_information in [
+            "zsync|https://example.com/app_1.0.0_x86_64.AppImage.zsync",
+            "gh-releases-zsync|user|repo|latest|app_*_x86_64.AppImage.zsync",
+            "pling-v1-zsync|1234567|app_*_x86_64.AppImage.zsync",
+        ] {
+            assert!(validate_update_information(update_information).is_ok());
+        }
+
+        for update_information in [
+            "",
+            "zsync|",
+            "zsync|ftp://example.com/app.zsync",
+            "gh-releases-zsync|user|repo|latest",
+            "gh-releases-zsync|user||latest|app.zsync",
+            "bintray-zsync|user|repo|app|app.zsync",
+            "zsync|https://example.com/app'.zsync",
+        ] {
+            assert!(validate_update_information(update_information).is_err());
+        }
+    }
+
+    /// Builds a little-endian ELF64 file with a `.upd_info` section of 32 bytes
+    /// filled with `fill`, ending with its section headers.
+    fn elf(machine: u16, fill: u8) -> Vec<u8> {
+        let names = b"\0.shstrtab\0.upd_info\0";
+        let mut data = vec![0; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        data[18..20].copy_from_slice(&machine.to_le_bytes());
+        data.extend([fill; 32]);
+        data.extend(names);
+        let shoff = data.len();
+        data[0x28..0x30].copy_from_slice(&(shoff as u64).to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+        data[0x3E..0x40].copy_from_slice(&1u16.to_le_bytes());
+
+        for (name, offset, size) in [
+            (0u32, 0u64, 0u64),
+            (1, 96, names.len() as u64),
+            (11, 64, 32),
+        ] {
+            let mut header = vec![0; 64];
+            header[..4].copy_from_slice(&name.to_le_bytes());
+            header[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+            header[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+            data.extend(header);
+        }
+        data
+    }
+
+    #[test]
+    fn it_uses_the_vendored_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let appimage_path = dir.path().join("app.AppImage");
+        let payload = b"hsqs squashfs image";
+        fs::write(&appimage_path, [elf(62, 1), payload.to_vec()].concat()).unwrap();
+
+        // the vendored runtime is read without downloading anything
+        let x86_64 = elf(62, 2);
+        let aarch64 = elf(183, 3);
+        let runtimes: &[(&str, &[u8])] = &[("x86_64", &x86_64), ("aarch64", &aarch64)];
+        let runtime = vendored_runtime(runtimes, "x86_64").unwrap();
+        assert_eq!(runtime, x86_64);
+        replace_runtime(&appimage_path, &runtime, VENDORED_RUNTIME, "x86_64", None).unwrap();
+        let appimage = fs::read(&appimage_path).unwrap();
+        assert_eq!(appimage, [x86_64.clone(), payload.to_vec()].concat());
+
+        assert!(matches!(
+            vendored_runtime(runtimes, "armhf"),
+            Err(Error::InvalidAppImageRuntime(runtime, _)) if runtime == VENDORED_RUNTIME
+        ));
+        // the runtime vendored for an architecture is built for another one
+        let mismatched: &[(&str, &[u8])] = &[("aarch64", &x86_64)];
+        assert!(matches!(
+            vendored_runtime(mismatched, "aarch64"),
+            Err(Error::InvalidAppImageRuntime(_, reason)) if reason.contains("not built for aarch64")
+        ));
+
+        if !cfg!(feature = "vendored-appimage-runtime") {
+            assert!(matches!(
+                read_runtime(VENDORED_RUNTIME, "x86_64"),
+                Err(Error::InvalidAppImageRuntime(_, reason)) if reason.contains("feature")
+            ));
+        }
+    }
+
+    #[test]
+    fn it_replaces_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let appimage_path = dir.path().join("app.AppImage");
+        let payload = b"hsqs squashfs image";
+        fs::write(&appimage_path, [elf(62, 1), payload.to_vec()].concat()).unwrap();
+
+        let runtime = elf(62, 2);
+        replace_runtime(&appimage_path, &runtime, "runtime", "x86_64", None).unwrap();
+        let appimage = fs::read(&appimage_path).unwrap();
+        assert_eq!(appimage, [runtime.clone(), payload.to_vec()].concat());
+
+        // an AppImage can be used as the runtime
+        let update_information = "zsync|https://a.io/app.zsync";
+        let mut runtime = elf(62, 3);
+        runtime.extend(b"hsqs other image");
+        replace_runtime(
+            &appimage_path,
+            &runtime,
+            "runtime",
+            "x86_64",
+            Some(update_information),
+        )
+        .unwrap();
+        let appimage = fs::read(&appimage_path).unwrap();
+        assert!(appimage.ends_with(payload));
+        let (offset, size) = Elf::parse(&appimage).unwrap().section(".upd_info").unwrap();
+        assert_eq!((offset, size), (64, 32));
+        assert_eq!(
+            &appimage[offset..offset + update_information.len()],
+            update_information.as_bytes()
+        );
+        assert!(appimage[offset + update_information.len()..offset + size]
+            .iter()
+            .all(|b| *b == 0));
+
+        for (runtime, update_information) in [
+            (elf(183, 2), None),
+            (b"#!/bin/sh".to_vec(), None),
+            (elf(62, 2)[..100].to_vec(), None),
+            (elf(62, 2), Some(&"x".repeat(33)[..])),
+        ] {
+            assert!(matches!(
+                replace_runtime(
+                    &appimage_path,
+                    &runtime,
+                    "runtime",
+                    "x86_64",
+                    update_information
+                ),
+                Err(Error::InvalidAppImageRuntime(..))
+            ));
+        }
+        assert!(fs::read(&appimage_path).unwrap().ends_with(payload));
+    }
+
+    #[test]
+    fn it_builds_an_appimage_per_architecture() {
+        let dir = tempfile::tempdir().unwrap();
+        for (arch, machine) in [("x86_64", 62), ("aarch64", 183)] {
+            fs::create_dir_all(dir.path().join(arch)).unwrap();
+            fs::write(dir.path().join(arch).join("app"), elf(machine, 0)).unwrap();
+        }
+        fs::write(dir.path().join("runtime-x86_64"), elf(62, 4)).unwrap();
+        fs::write(dir.path().join("static-runtime-aarch64"), elf(183, 5)).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.target_triple = Some("aarch64-unknown-linux-gnu".into());
+        config.binaries = vec![crate::config::Binary::new("app").main(true)];
+        let appimage_config = crate::config::AppImageConfig::new()
+            .runtime(dir.path().join("runtime-{{arch}}").display().to_string());
+        config.appimage = Some(
+            appimage_config.clone().architectures([
+                AppImageArchitecture::new("x86_64-unknown-linux-gnu", dir.path().join("x86_64")),
+                AppImageArchitecture::new("aarch64-unknown-linux-gnu", dir.path().join("aarch64"))
+                    .runtime(
+                        dir.path()
+                            .join("static-runtime-aarch64")
+                            .display()
+                            .to_string(),
+                    ),
+            ]),
+        );
+
+        let configs = architecture_configs(&config).unwrap();
+        let archs = configs
+            .iter()
+            .map(|c| appimage_arch(c).unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(archs, ["aarch64", "x86_64"]);
+
+        let payload = b"hsqs squashfs image";
+        let mut runtimes = Vec::new();
+        for (config, (arch, fill)) in configs.iter().zip([("aarch64", 5), ("x86_64", 4)]) {
+            let machine = elf_machine(arch).unwrap();
+            let appimage_path = dir.path().join(format!("app_1.0.0_{arch}.AppImage"));
+            fs::write(&appimage_path, [elf(machine, 1), payload.to_vec()].concat()).unwrap();
+            let runtime = config.appimage().unwrap().runtime.as_deref().unwrap();
+            let runtime_data = read_runtime(runtime, arch).unwrap();
+            replace_runtime(&appimage_path, &runtime_data, runtime, arch, None).unwrap();
+            assert_eq!(
+                fs::read(&appimage_path).unwrap(),
+                [elf(machine, fill), payload.to_vec()].concat()
+            );
+            runtimes.push(runtime_data);
+        }
+        assert_ne!(runtimes[0], runtimes[1]);
+
+        // the aarch64 binaries declared as x86_64 ones
+        config.appimage = Some(appimage_config.architectures([AppImageArchitecture::new(
+            "x86_64-unknown-linux-gnu",
+            dir.path().join("aarch64"),
+        )]));
+        assert!(matches!(
+            architecture_configs(&config),
+            Err(Error::AppImageBinaryArchMismatch(path, arch))
+                if path == dir.path().join("aarch64/app") && arch == "x86_64"
+        ));
+    }
+
+    /// Creates an AppDir-like fixture in `dir`.
+    fn create_fixture(dir: &Path) {
+        fs::create_dir_all(dir.join("usr/bin")).unwrap();
+        fs::create_dir_all(dir.join("usr/share/applications")).unwrap();
+        fs::write(dir.join("AppRun"), "#!/bin/sh").unwrap();
+        fs::write(dir.join("usr/bin/app"), "app").unwrap();
+        fs::write(
+            dir.join("usr/share/applications/app.desktop"),
+            "[Desktop Entry]",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(
+            "usr/share/applications/app.desktop",
+            dir.join("app.desktop"),
+        )
+        .unwrap();
+    }
+
+    /// Returns the paths and modification times of the entries of `dir`, skipping the symlinks.
+    fn mtimes(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+        walkdir::WalkDir::new(dir)
+            .sort_by_file_name()
+            .into_iter()
+            .map(|e| e.unwrap())
+            .filter(|e| !e.file_type().is_symlink())
+            .map(|e| {
+                (
+                    e.path().strip_prefix(dir).unwrap().to_path_buf(),
+                    e.metadata().unwrap().modified().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_builds_reproducible_payloads() {
+        let epoch = 1_700_000_000;
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut builds = Vec::new();
+        // the AppDirs of two builds made at different times
+        for (name, built_at) in [("a", 1_000), ("b", 2_000)] {
+            let app_dir = dir.path().join(name).join("app.AppDir");
+            create_fixture(&app_dir);
+            set_mtimes(&app_dir, built_at).unwrap();
+
+            let mut cmd = Command::new("linuxdeploy-plugin-appimage");
+            apply_source_date_epoch(&app_dir, &mut cmd, Some(epoch)).unwrap();
+            let envs = cmd.get_envs().collect::<Vec<_>>();
+            let epoch_value = epoch.to_string();
+            assert_eq!(
+                envs,
+                [(
+                    std::ffi::OsStr::new("SOURCE_DATE_EPOCH"),
+                    Some(std::ffi::OsStr::new(&epoch_value))
+                )]
+            );
+
+            let mtimes = mtimes(&app_dir);
+            assert!(mtimes
+                .iter()
+                .all(|(_, mtime)| *mtime == UNIX_EPOCH + Duration::from_secs(epoch)));
+            builds.push(mtimes);
+        }
+        assert_eq!(builds[0], builds[1]);
+
+        // without SOURCE_DATE_EPOCH the files are left untouched
+        let app_dir = dir.path().join("a").join("app.AppDir");
+        set_mtimes(&app_dir, 1_000).unwrap();
+        let mut cmd = Command::new("linuxdeploy-plugin-appimage");
+        apply_source_date_epoch(&app_dir, &mut cmd, None).unwrap();
+        assert_eq!(cmd.get_envs().count(), 0);
+        assert!(mtimes(&app_dir)
+            .iter()
+            .all(|(_, mtime)| *mtime == UNIX_EPOCH + Duration::from_secs(1_000)));
+    }
+
+    #[test]
+    fn it_excludes_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("app.AppDir");
+        for file in [
+            "usr/bin/app",
+            "usr/share/locale/de/LC_MESSAGES/app.mo",
+            "usr/share/doc/app/README",
+            "usr/share/doc-base/app",
+            "usr/lib/libfoo.so.1",
+            "usr/lib/libfoo.a",
+        ] {
+            let path = app_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+
+        let patterns = [
+            "usr/share/locale/**",
+            "/usr/share/doc/**",
+            "usr/lib/*.a",
+            "usr/share/man/**",
+        ]
+        .map(String::from);
+        let exclude = parse_exclude_patterns(&patterns).unwrap();
+        exclude_files(&app_dir, &exclude).unwrap();
+
+        let mut files = walkdir::WalkDir::new(&app_dir)
+            .into_iter()
+            .map(|e| e.unwrap())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| {
+                e.path()
+                    .strip_prefix(&app_dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "usr/bin/app",
+                "usr/lib/libfoo.so.1",
+                "usr/share/doc-base/app"
+            ]
+        );
+
+        assert!(matches!(
+            parse_exclude_patterns(&["usr/share/[".into()]),
+            Err(Error::InvalidAppImageExcludePattern(pattern, _)) if pattern == "usr/share/["
+        ));
+    }
 }
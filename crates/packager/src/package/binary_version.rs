@@ -0,0 +1,162 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Verification that the main binary reports the config version,
+//! catching a stale binary left by a build step that didn't run.
+
+use std::{path::Path, process::Command};
+
+use editpe::Image;
+
+use crate::{config::BinaryVersionCheck, shell::CommandExt, util, Config, Error};
+
+/// Returns the first version in `output`, with an optional `v` prefix.
+fn find_version(output: &str) -> Option<semver::Version> {
+    output
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '"' | '\''))
+        .map(|word| word.trim_end_matches('.'))
+        .find_map(|word| semver::Version::parse(word.strip_prefix('v').unwrap_or(word)).ok())
+}
+
+/// Runs `cmd` and returns the first version in its stdout or stderr.
+fn command_version(binary: &Path, cmd: &mut Command) -> crate::Result<Option<semver::Version>> {
+    let output = cmd
+        .output_ok()
+        .map_err(|e| Error::UnknownBinaryVersion(binary.into(), e.to_string()))?;
+    Ok(find_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| find_version(&String::from_utf8_lossy(&output.stderr))))
+}
+
+/// Reads the `ProductVersion` string of the `VERSIONINFO` resource,
+/// falling back to its fixed product version numbers.
+fn pe_version(binary: &Path) -> crate::Result<Option<semver::Version>> {
+    let invalid = |e: &dyn std::fmt::Display| Error::InvalidPeBinary(binary.into(), e.to_string());
+
+    let image = Image::parse_file(binary).map_err(|e| invalid(&e))?;
+    let Some(info) = image
+        .resource_directory()
+        .map(|resources| resources.get_version_info())
+        .transpose()
+        .map_err(|e| invalid(&e))?
+        .flatten()
+    else {
+        return Ok(None);
+    };
+
+    let product_version = info
+        .strings
+        .iter()
+        .find_map(|table| table.strings.get("ProductVersion"))
+        .and_then(|version| find_version(version));
+    Ok(product_version.or_else(|| {
+        let version = info.info.product_version;
+        find_version(&format!(
+            "{}.{}.{}",
+            version.major >> 16,
+            version.major & 0xffff,
+            version.minor >> 16
+        ))
+    }))
+}
+
+/// Checks that the version of the main binary matches [`Config::version`]
+/// when [`Config::binary_version_check`] is set, ignoring the build metadata.
+pub(crate) fn check(config: &Config) -> crate::Result<()> {
+    let Some(check) = &config.binary_version_check else {
+        return Ok(());
+    };
+
+    let mut binary = config.binary_path(config.main_binary()?);
+    if config.target_triple().contains("windows") {
+        binary.set_extension("exe");
+    }
+    tracing::debug!("Checking the version of {}", binary.display());
+
+    let version = match check {
+        BinaryVersionCheck::VersionFlag => {
+            command_version(&binary, Command::new(&binary).arg("--version"))?
+        }
+        BinaryVersionCheck::PeVersionInfo => pe_version(&binary)?,
+        BinaryVersionCheck::Command(script) => command_version(
+            &binary,
+            util::cross_command(script).env("CARGO_PACKAGER_MAIN_BINARY", &binary),
+        )?,
+    }
+    .ok_or_else(|| Error::UnknownBinaryVersion(binary.clone(), "no version found".into()))?;
+
+    let expected = semver::Version::parse(&config.version)?;
+    if (version.major, version.minor, version.patch, &version.pre)
+        != (
+            expected.major,
+            expected.minor,
+            expected.patch,
+            &expected.pre,
+        )
+    {
+        return Err(Error::VersionMismatch {
+            binary: version.to_string(),
+            config: config.version.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_version_in_the_output() {
+        let version = |output| find_version(output).map(|v| v.to_string());
+        assert_eq!(version("my-app 1.2.3\n"), Some("1.2.3".into()));
+        assert_eq!(
+            version("my-app v2.0.0-beta.1 (3f2c9a1e)"),
+            Some("2.0.0-beta.1".into())
+        );
+        assert_eq!(version("Version: 1.0.0+4."), Some("1.0.0+4".into()));
+        assert_eq!(version("my-app 1.2"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_rejects_a_mismatched_binary() {
+        use crate::config::Binary;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("app");
+        std::fs::write(&binary, "#!/bin/sh\necho \"app 1.2.2\"\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = Config::default();
+        config.version = "1.2.3".into();
+        config.binaries = vec![Binary::new(&binary).main(true)];
+        config.binary_version_check = Some(BinaryVersionCheck::VersionFlag);
+        let err = check(&config).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::VersionMismatch { binary, config } if binary == "1.2.2" && config == "1.2.3"
+            ),
+            "{err}"
+        );
+
+        // the build metadata is ignored
+        config.version = "1.2.2+7".into();
+        check(&config).unwrap();
+
+        config.version = "1.2.3".into();
+        config.binary_version_check = Some(BinaryVersionCheck::Command(
+            "\"$CARGO_PACKAGER_MAIN_BINARY\" | sed s/1.2.2/1.2.3/".into(),
+        ));
+        check(&config).unwrap();
+
+        config.binary_version_check = Some(BinaryVersionCheck::Command("echo unknown".into()));
+        assert!(matches!(
+            check(&config),
+            Err(Error::UnknownBinaryVersion(..))
+        ));
+    }
+}
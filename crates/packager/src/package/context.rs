@@ -2,9 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use std::{fs, path::PathBuf};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::{util, Config};
+use crate::{util, Config, Error};
 
 /// The packaging context info
 #[derive(Debug)]
@@ -16,6 +21,9 @@ pub struct Context {
     /// The global path which we store tools used by cargo-packager and usually is
     /// `<cache-dir>/.cargo-packager`
     pub tools_path: PathBuf,
+    /// The versions and digests of the external tools used by the package being built,
+    /// recorded by the [`tools`](super::tools) checks.
+    pub tools: RefCell<BTreeMap<String, String>>,
 }
 
 impl Context {
@@ -28,12 +36,128 @@ impl Context {
         }
 
         let intermediates_path = config.out_dir().join(".cargo-packager");
+        // the partial artifacts left by an interrupted build are removed with the intermediates
+        if let Ok(entries) = fs::read_dir(partial_artifacts_path(&intermediates_path)) {
+            for entry in entries.flatten() {
+                tracing::warn!(
+                    "Removing the partial artifact {} of an interrupted build",
+                    entry.file_name().to_string_lossy()
+                );
+            }
+        }
         util::create_clean_dir(&intermediates_path)?;
 
         Ok(Self {
             config: config.clone(),
             tools_path,
             intermediates_path,
+            tools: RefCell::default(),
+        })
+    }
+
+    /// Writes the artifact at `dest` with `write`, which receives the path to write it to,
+    /// a file or directory with the same name in the intermediates path,
+    /// and moves it to `dest` only when `write` succeeds,
+    /// so an interrupted or failed build never leaves a half-written artifact at `dest`.
+    pub(crate) fn write_artifact<T, F>(&self, dest: &Path, write: F) -> crate::Result<T>
+    where
+        F: FnOnce(&Path) -> crate::Result<T>,
+    {
+        let file_name = dest
+            .file_name()
+            .ok_or_else(|| Error::FailedToExtractFilename(dest.to_path_buf()))?;
+        let partial_dir = partial_artifacts_path(&self.intermediates_path);
+        fs::create_dir_all(&partial_dir).map_err(|e| Error::IoWithPath(partial_dir.clone(), e))?;
+        let partial = partial_dir.join(file_name);
+        remove_path(&partial)?;
+
+        let output = match write(&partial) {
+            Ok(output) => output,
+            Err(e) => {
+                if let Err(remove_error) = remove_path(&partial) {
+                    tracing::debug!("Failed to remove the partial artifact: {remove_error}");
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::IoWithPath(parent.to_path_buf(), e))?;
+        }
+        // a file is atomically replaced by the rename, but not a directory like the app bundle
+        if dest.is_dir() {
+            remove_path(dest)?;
+        }
+        fs::rename(&partial, dest)
+            .map_err(|e| Error::RenameFile(partial.clone(), dest.to_path_buf(), e))?;
+        Ok(output)
+    }
+}
+
+/// Returns the directory of the artifacts being written by [`Context::write_artifact`].
+fn partial_artifacts_path(intermediates_path: &Path) -> PathBuf {
+    intermediates_path.join("partial")
+}
+
+/// Removes the file, symlink or directory at `path` if it exists.
+fn remove_path(path: &Path) -> crate::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => return Ok(()),
+    };
+    result.map_err(|e| Error::IoWithPath(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn it_writes_artifacts_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.out_dir = dir.path().to_path_buf();
+        let ctx = Context::new(&config).unwrap();
+        let dest = config.out_dir().join("app_1.0.0_amd64.deb");
+
+        // the build fails after writing a part of the artifact
+        let partial_path = RefCell::new(PathBuf::new());
+        let result = ctx.write_artifact(&dest, |partial| -> crate::Result<()> {
+            fs::write(partial, "half of the").unwrap();
+            partial_path.replace(partial.to_path_buf());
+            Err(Error::Io(io::Error::from(io::ErrorKind::StorageFull)))
+        });
+        assert!(matches!(result, Err(Error::Io(_))));
+        assert!(!dest.exists());
+        assert!(!partial_path.borrow().exists());
+
+        ctx.write_artifact(&dest, |partial| {
+            fs::write(partial, "deb").map_err(|e| Error::IoWithPath(partial.to_path_buf(), e))
         })
+        .unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "deb");
+
+        // directories replace the previous artifact
+        let app = config.out_dir().join("App.app");
+        for binary in ["old", "new"] {
+            ctx.write_artifact(&app, |partial| {
+                fs::create_dir_all(partial.join("Contents")).unwrap();
+                fs::write(partial.join("Contents").join(binary), binary).unwrap();
+                Ok(())
+            })
+            .unwrap();
+        }
+        assert!(app.join("Contents/new").exists());
+        assert!(!app.join("Contents/old").exists());
+
+        // the partial artifacts of a killed build are removed by the next one
+        let stale = partial_artifacts_path(&ctx.intermediates_path).join("app.AppImage");
+        fs::write(&stale, "half").unwrap();
+        Context::new(&config).unwrap();
+        assert!(!stale.exists());
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "deb");
     }
 }
@@ -0,0 +1,300 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Checks of the [`DebianConfig::dependency_check`](crate::config::DebianConfig::dependency_check),
+//! that the declared dependencies are satisfiable by the packages of a package list.
+
+use std::{cmp::Ordering, collections::HashMap, fs, process::Command};
+
+use crate::{config::DebianDependencyCheck, shell::CommandExt, util::display_path, Config, Error};
+
+/// The versions of the packages of a package list and of the virtual packages they provide.
+#[derive(Debug, Default)]
+pub(crate) struct PackageList {
+    packages: HashMap<String, Vec<String>>,
+    // the virtual packages with the version they are provided at, if any
+    provides: HashMap<String, Vec<Option<String>>>,
+}
+
+impl PackageList {
+    /// Parses a Debian `Packages` index, the stanzas of the `Package`, `Version`
+    /// and `Provides` fields of each package separated by empty lines.
+    pub fn parse(index: &str) -> Self {
+        let mut list = Self::default();
+        for stanza in index.split("\n\n") {
+            let mut name = None;
+            let mut version = None;
+            let mut provides = None;
+            for line in stanza.lines() {
+                if let Some((field, value)) = line.split_once(':') {
+                    match field {
+                        "Package" => name = Some(value.trim()),
+                        "Version" => version = Some(value.trim()),
+                        "Provides" => provides = Some(value.trim()),
+                        _ => {}
+                    }
+                }
+            }
+            let (Some(name), Some(version)) = (name, version) else {
+                continue;
+            };
+            list.packages
+                .entry(name.to_string())
+                .or_default()
+                .push(version.to_string());
+            for provided in provides.into_iter().flat_map(|p| p.split(',')) {
+                let (provided, constraint) = parse_relation(provided);
+                list.provides
+                    .entry(provided.to_string())
+                    .or_default()
+                    .push(constraint.map(|(_, version)| version.to_string()));
+            }
+        }
+        list
+    }
+
+    /// Returns whether a package named `name` satisfies the optional `(operator, version)` constraint,
+    /// or the reason why it doesn't.
+    fn satisfies(&self, name: &str, constraint: Option<(&str, &str)>) -> Result<(), String> {
+        let versions = self.packages.get(name).map(Vec::as_slice).unwrap_or(&[]);
+        let provided = self.provides.get(name).map(Vec::as_slice).unwrap_or(&[]);
+        if versions.is_empty() && provided.is_empty() {
+            return Err(format!(
+                "no package named or providing `{name}` is available"
+            ));
+        }
+        let Some((operator, version)) = constraint else {
+            return Ok(());
+        };
+
+        // an unversioned provide never satisfies a versioned dependency
+        let candidates = versions.iter().chain(provided.iter().flatten());
+        if candidates.clone().next().is_none() {
+            return Err(format!("`{name}` is only provided without a version"));
+        }
+        for candidate in candidates.clone() {
+            let ordering = compare_versions(candidate, version);
+            let satisfied = match operator {
+                "<<" => ordering == Ordering::Less,
+                "<=" | "<" => ordering != Ordering::Greater,
+                "=" => ordering == Ordering::Equal,
+                ">=" | ">" => ordering != Ordering::Less,
+                ">>" => ordering == Ordering::Greater,
+                _ => return Err(format!("`{operator}` is not a version operator")),
+            };
+            if satisfied {
+                return Ok(());
+            }
+        }
+        Err(format!(
+            "no version of `{name}` is {operator} {version}, the available versions are {}",
+            candidates.cloned().collect::<Vec<_>>().join(", ")
+        ))
+    }
+
+    /// Checks each relation of `relations`, a `,` separated list of `|` separated alternatives,
+    /// returning the [`Error::UnsatisfiableDependency`] of the ones no package satisfies.
+    pub fn check(&self, relations: &str) -> Vec<Error> {
+        let mut errors = Vec::new();
+        for relation in relations
+            .split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+        {
+            let mut reasons = Vec::new();
+            for alternative in relation.split('|') {
+                let (name, constraint) = parse_relation(alternative);
+                match self.satisfies(name, constraint) {
+                    Ok(()) => {
+                        reasons.clear();
+                        break;
+                    }
+                    Err(reason) => reasons.push(reason),
+                }
+            }
+            if !reasons.is_empty() {
+                errors.push(Error::UnsatisfiableDependency(
+                    relation.to_string(),
+                    reasons.join(" and "),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// Parses a `name[:arch] [(operator version)] [[architectures]]` relation
+/// into its name and optional version constraint.
+fn parse_relation(relation: &str) -> (&str, Option<(&str, &str)>) {
+    let relation = relation.trim();
+    let name_end = relation
+        .find(|c: char| c.is_whitespace() || c == '(' || c == '[' || c == '<')
+        .unwrap_or(relation.len());
+    let name = relation[..name_end].split(':').next().unwrap_or_default();
+    let constraint = relation[name_end..]
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .and_then(|(constraint, _)| {
+            let constraint = constraint.trim();
+            let operator_end = constraint
+                .find(|c: char| !matches!(c, '<' | '=' | '>'))
+                .unwrap_or(constraint.len());
+            let (operator, version) = constraint.split_at(operator_end);
+            (!operator.is_empty()).then(|| (operator, version.trim()))
+        });
+    (name, constraint)
+}
+
+/// Compares two Debian versions, `[epoch:]upstream_version[-debian_revision]`, like `dpkg --compare-versions`.
+///
+/// See <https://www.debian.org/doc/debian-policy/ch-controlfields.html#version>
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (u64, &str, &str) {
+        let (epoch, rest) = match version.split_once(':') {
+            Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+            None => (0, version),
+        };
+        let (upstream, revision) = rest.rsplit_once('-').unwrap_or((rest, ""));
+        (epoch, upstream, revision)
+    }
+
+    let (a_epoch, a_upstream, a_revision) = split(a.trim());
+    let (b_epoch, b_upstream, b_revision) = split(b.trim());
+    a_epoch
+        .cmp(&b_epoch)
+        .then_with(|| compare_parts(a_upstream, b_upstream))
+        .then_with(|| compare_parts(a_revision, b_revision))
+}
+
+/// Compares the upstream versions or Debian revisions with the `verrevcmp` algorithm of dpkg,
+/// alternating between comparing the non-digit parts lexically, where `~` sorts before everything
+/// and letters before the other characters, and the digit parts numerically.
+fn compare_parts(a: &str, b: &str) -> Ordering {
+    fn order(c: Option<u8>) -> i32 {
+        match c {
+            None => 0,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => c as i32,
+            Some(b'~') => -1,
+            Some(c) => c as i32 + 256,
+        }
+    }
+
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    while !a.is_empty() || !b.is_empty() {
+        while a.first().is_some_and(|c| !c.is_ascii_digit())
+            || b.first().is_some_and(|c| !c.is_ascii_digit())
+        {
+            let (a_order, b_order) = (order(a.first().copied()), order(b.first().copied()));
+            if a_order != b_order {
+                return a_order.cmp(&b_order);
+            }
+            a = a.get(1..).unwrap_or_default();
+            b = b.get(1..).unwrap_or_default();
+        }
+
+        let a_digits = a.iter().take_while(|c| c.is_ascii_digit()).count();
+        let b_digits = b.iter().take_while(|c| c.is_ascii_digit()).count();
+        let trim = |digits: &[u8]| -> Vec<u8> {
+            digits.iter().copied().skip_while(|c| *c == b'0').collect()
+        };
+        let (a_number, b_number) = (trim(&a[..a_digits]), trim(&b[..b_digits]));
+        let ordering = a_number
+            .len()
+            .cmp(&b_number.len())
+            .then_with(|| a_number.cmp(&b_number));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        a = &a[a_digits..];
+        b = &b[b_digits..];
+    }
+    Ordering::Equal
+}
+
+/// Reads the package list of `check`, defaulting to the packages of the configured apt repositories.
+fn read_package_list(check: &DebianDependencyCheck) -> crate::Result<PackageList> {
+    let index = match &check.package_list {
+        Some(path) => {
+            tracing::debug!("Reading the package list {}", display_path(path));
+            fs::read_to_string(path).map_err(|e| Error::IoWithPath(path.clone(), e))?
+        }
+        None => {
+            tracing::debug!("Listing the available packages with `apt-cache dumpavail`");
+            let output = Command::new("apt-cache")
+                .arg("dumpavail")
+                .output_ok()
+                .map_err(Error::ListAvailablePackagesFailed)?;
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+    };
+    Ok(PackageList::parse(&index))
+}
+
+/// Checks the `Depends` and `Pre-Depends` of the [`DebianConfig`](crate::config::DebianConfig)
+/// when its [`DebianConfig::dependency_check`](crate::config::DebianConfig::dependency_check) is set,
+/// logging a warning for each unsatisfiable dependency, or failing with the first one when it is strict.
+pub(crate) fn check_dependencies(config: &Config) -> crate::Result<()> {
+    let Some(deb) = config.deb() else {
+        return Ok(());
+    };
+    let Some(check) = &deb.dependency_check else {
+        return Ok(());
+    };
+
+    let mut relations = Vec::new();
+    for dependencies in [&deb.depends, &deb.pre_depends].into_iter().flatten() {
+        relations.extend(dependencies.to_list()?);
+    }
+    if relations.is_empty() {
+        return Ok(());
+    }
+
+    let list = read_package_list(check)?;
+    for error in relations.iter().flat_map(|r| list.check(r)) {
+        if check.strict {
+            return Err(error);
+        }
+        tracing::warn!("{error}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_compares_debian_versions() {
+        for (a, b, ordering) in [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0", "1.00", Ordering::Equal),
+            ("1.0", "1.1", Ordering::Less),
+            ("1.10", "1.9", Ordering::Greater),
+            ("1.0~rc1", "1.0", Ordering::Less),
+            ("1.0", "1.0a", Ordering::Less),
+            ("1.0a", "1.0+", Ordering::Less),
+            ("1:0.9", "2.0", Ordering::Greater),
+            ("2.0-1", "2.0-1ubuntu2", Ordering::Less),
+            ("2.31-0ubuntu9.9", "2.31-0ubuntu9.10", Ordering::Less),
+            ("1.2-3-4", "1.2-3-10", Ordering::Less),
+        ] {
+            assert_eq!(compare_versions(a, b), ordering, "{a} {b}");
+            assert_eq!(compare_versions(b, a), ordering.reverse(), "{b} {a}");
+        }
+    }
+
+    #[test]
+    fn it_parses_relations() {
+        assert_eq!(parse_relation("libfoo"), ("libfoo", None));
+        assert_eq!(
+            parse_relation(" libfoo:any (>= 2.0) [amd64]"),
+            ("libfoo", Some((">=", "2.0")))
+        );
+        assert_eq!(
+            parse_relation("libfoo(<<3:1.0-1)"),
+            ("libfoo", Some(("<<", "3:1.0-1")))
+        );
+    }
+}
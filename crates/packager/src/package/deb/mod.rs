@@ -5,11 +5,12 @@
 // SPDX-License-Identifier: MIT
 
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     ffi::OsStr,
     fs::{self, File},
     io::{BufReader, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    process::Command,
 };
 
 use flate2::{write::GzEncoder, Compression};
@@ -21,13 +22,19 @@ use serde::Serialize;
 use tar::HeaderMode;
 use walkdir::WalkDir;
 
-use super::Context;
+use super::{naming, Context};
 use crate::{
-    config::Config,
+    config::{
+        AppStreamConfig, Config, DebianChangelog, DebianChangelogEntry, DebianCompression,
+        DebianDataPackage, DebianFileOwner, PackageFormat,
+    },
+    shell::CommandExt,
     util::{self, PathExt as UtilPathExt},
     Error,
 };
 
+mod dependencies;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub struct DebIcon {
     pub width: u32,
@@ -89,6 +96,46 @@ fn generate_icon_files(config: &Config, data_dir: &Path) -> crate::Result<BTreeS
     Ok(icons_set)
 }
 
+/// Keys that are already generated by the default desktop file template.
+const GENERATED_DESKTOP_ENTRY_KEYS: &[&str] = &[
+    "Categories",
+    "Comment",
+    "Exec",
+    "Icon",
+    "Name",
+    "Terminal",
+    "Type",
+    "MimeType",
+    "StartupWMClass",
+    "StartupNotify",
+];
+
+// Validates a key against the desktop entry spec, which only allows `A-Za-z0-9-`
+// optionally followed by a locale suffix, such as `Keywords[de_DE]`.
+// See <https://specifications.freedesktop.org/desktop-entry-spec/latest/basic-format.html#entries>
+fn validate_desktop_entry_key(key: &str) -> crate::Result<()> {
+    let (name, locale) = match key.split_once('[') {
+        Some((name, locale)) => match locale.strip_suffix(']') {
+            Some(locale) => (name, Some(locale)),
+            None => return Err(Error::InvalidDesktopEntryKey(key.into())),
+        },
+        None => (key, None),
+    };
+
+    let valid_name =
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let valid_locale = locale.is_none_or(|l| {
+        !l.is_empty()
+            && l.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '@' | '-'))
+    });
+    if !valid_name || !valid_locale || GENERATED_DESKTOP_ENTRY_KEYS.contains(&key) {
+        return Err(Error::InvalidDesktopEntryKey(key.into()));
+    }
+
+    Ok(())
+}
+
 /// Generate the application desktop file and store it under the `data_dir`.
 #[tracing::instrument(level = "trace", skip(config))]
 fn generate_desktop_file(config: &Config, data_dir: &Path) -> crate::Result<()> {
@@ -117,12 +164,16 @@ fn generate_desktop_file(config: &Config, data_dir: &Path) -> crate::Result<()>
     #[derive(Serialize)]
     struct DesktopTemplateParams<'a> {
         categories: &'a str,
+        keywords: Option<String>,
         comment: Option<&'a str>,
         exec: &'a str,
         exec_arg: Option<&'a str>,
         icon: &'a str,
         name: &'a str,
         mime_type: Option<String>,
+        startup_wm_class: Option<&'a str>,
+        startup_notify: Option<&'a str>,
+        extra_entries: BTreeMap<&'a str, &'a str>,
     }
 
     // Set the argument code at the end of the `Exec` key.
@@ -158,6 +209,22 @@ fn generate_desktop_file(config: &Config, data_dir: &Path) -> crate::Result<()>
 
     let mime_type = (!mime_type.is_empty()).then(|| mime_type.join(";"));
 
+    let linux = config.linux();
+    let mut extra_entries = BTreeMap::new();
+    if let Some(entries) = linux.and_then(|l| l.extra_desktop_entries.as_ref()) {
+        for (key, value) in entries {
+            validate_desktop_entry_key(key)?;
+            // the `Keywords` entry is only generated from the `Config::keywords`
+            if key == "Keywords" && config.keywords.is_some() {
+                return Err(Error::InvalidDesktopEntryKey(key.clone()));
+            }
+            if value.contains('\n') || value.contains('\r') {
+                return Err(Error::InvalidDesktopEntryValue(key.clone()));
+            }
+            extra_entries.insert(key.as_str(), value.as_str());
+        }
+    }
+
     let bin_name_exec = if bin_name.contains(' ') {
         format!("\"{bin_name}\"")
     } else {
@@ -171,12 +238,219 @@ fn generate_desktop_file(config: &Config, data_dir: &Path) -> crate::Result<()>
                 .category
                 .map(|category| category.gnome_desktop_categories())
                 .unwrap_or(""),
+            keywords: config
+                .keywords
+                .as_ref()
+                .filter(|keywords| !keywords.is_empty())
+                .map(|keywords| format!("{};", keywords.join(";"))),
             comment: config.description.as_deref(),
             exec: &bin_name_exec,
             exec_arg,
             icon: &bin_name,
             name: config.product_name.as_str(),
             mime_type,
+            startup_wm_class: linux.and_then(|l| l.startup_wm_class.as_deref()),
+            startup_notify: linux.and_then(|l| l.startup_notify).map(|n| {
+                if n {
+                    "true"
+                } else {
+                    "false"
+                }
+            }),
+            extra_entries,
+        },
+        file,
+    )?;
+
+    Ok(())
+}
+
+/// Generate the AppStream metainfo file of the app and store it under the `data_dir`.
+///
+/// See <https://www.freedesktop.org/software/appstream/docs/chap-Metadata.html>
+#[tracing::instrument(level = "trace", skip(config, appstream))]
+fn generate_metainfo_file(
+    config: &Config,
+    appstream: &AppStreamConfig,
+    data_dir: &Path,
+) -> crate::Result<()> {
+    use time::{format_description, format_description::well_known::Rfc2822, Date, OffsetDateTime};
+
+    let invalid = Error::InvalidAppStreamMetadata;
+
+    let bin_name = config.main_binary_name()?;
+    let id = appstream.id.as_deref().unwrap_or(&bin_name);
+    let valid_id = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+    if !valid_id {
+        return Err(invalid(format!(
+            "the id `{id}` must only contain ASCII letters, digits, `.`, `_` and `-`"
+        )));
+    }
+    let generate_desktop_entry = config
+        .linux()
+        .is_none_or(|linux| linux.generate_desktop_entry);
+    if generate_desktop_entry && id != bin_name {
+        return Err(invalid(format!(
+            "the id `{id}` must match the id of the desktop entry `{bin_name}.desktop`"
+        )));
+    }
+
+    let summary = appstream.summary.trim();
+    if summary.is_empty() || summary.contains('\n') {
+        return Err(invalid(
+            "the summary must be a non-empty single line".into(),
+        ));
+    }
+
+    let description = appstream
+        .description
+        .split("\n\n")
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+    if description.is_empty() {
+        return Err(invalid("the description must not be empty".into()));
+    }
+
+    let screenshots = appstream.screenshots.as_deref().unwrap_or_default();
+    if let Some(screenshot) = screenshots
+        .iter()
+        .find(|s| !s.url.starts_with("https://") && !s.url.starts_with("http://"))
+    {
+        return Err(invalid(format!(
+            "the screenshot `{}` must be an http or https URL",
+            screenshot.url
+        )));
+    }
+
+    let date_format =
+        format_description::parse("[year]-[month]-[day]").map_err(|e| invalid(e.to_string()))?;
+    let default_date = match util::source_date_epoch() {
+        Some(epoch) => {
+            OffsetDateTime::from_unix_timestamp(epoch as i64).map_err(|e| invalid(e.to_string()))?
+        }
+        None => OffsetDateTime::now_utc(),
+    }
+    .date();
+    let format_date = |date: Date| {
+        date.format(&date_format)
+            .map_err(|e| invalid(e.to_string()))
+    };
+
+    #[derive(Serialize)]
+    struct Release {
+        version: String,
+        date: String,
+        changes: Vec<String>,
+    }
+
+    let releases = match (
+        &appstream.releases,
+        config.deb().and_then(|d| d.changelog.as_ref()),
+    ) {
+        (Some(releases), _) => releases
+            .iter()
+            .map(|release| {
+                let date = match &release.date {
+                    Some(date) => Date::parse(date, &date_format).map_err(|_| {
+                        invalid(format!(
+                            "the release `{}` has an invalid date, expected `YYYY-MM-DD`",
+                            release.version
+                        ))
+                    })?,
+                    None => default_date,
+                };
+                Ok(Release {
+                    version: release.version.clone(),
+                    date: format_date(date)?,
+                    changes: release.changes.clone().unwrap_or_default(),
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?,
+        (None, Some(DebianChangelog::Entries(entries))) => entries
+            .iter()
+            .map(|entry| {
+                let date = match &entry.date {
+                    Some(date) => OffsetDateTime::parse(date, &Rfc2822)
+                        .map_err(|e| Error::InvalidDebianChangelog(e.to_string()))?
+                        .date(),
+                    None => default_date,
+                };
+                Ok(Release {
+                    version: entry.version.clone(),
+                    date: format_date(date)?,
+                    changes: entry.changes.clone(),
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?,
+        (None, _) => vec![Release {
+            version: config.version.clone(),
+            date: format_date(default_date)?,
+            changes: Vec::new(),
+        }],
+    };
+    if releases.iter().any(|r| r.version.is_empty()) {
+        return Err(invalid("the releases must have a version".into()));
+    }
+
+    #[derive(Serialize)]
+    struct MetainfoTemplateParams<'a> {
+        id: &'a str,
+        name: &'a str,
+        summary: &'a str,
+        metadata_license: &'a str,
+        project_license: Option<&'a str>,
+        developer: Option<&'a str>,
+        description: Vec<String>,
+        desktop_id: String,
+        homepage: Option<&'a str>,
+        categories: Vec<&'a str>,
+        keywords: &'a [String],
+        screenshots: &'a [crate::config::AppStreamScreenshot],
+        releases: Vec<Release>,
+    }
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(|value| {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&apos;")
+    });
+    handlebars
+        .register_template_string("main.metainfo.xml", include_str!("./main.metainfo.xml"))
+        .map_err(Box::new)?;
+
+    let metainfo_path = data_dir
+        .join("usr/share/metainfo")
+        .join(format!("{id}.metainfo.xml"));
+    let file = &mut util::create_file(&metainfo_path)?;
+    handlebars.render_to_write(
+        "main.metainfo.xml",
+        &MetainfoTemplateParams {
+            id,
+            name: &config.product_name,
+            summary,
+            metadata_license: appstream.metadata_license.as_deref().unwrap_or("CC0-1.0"),
+            project_license: appstream.project_license.as_deref(),
+            developer: config.publisher.as_deref(),
+            description,
+            desktop_id: format!("{id}.desktop"),
+            homepage: config.homepage.as_deref(),
+            categories: config
+                .category
+                .map(|category| category.gnome_desktop_categories())
+                .unwrap_or_default()
+                .split_terminator(';')
+                .collect(),
+            keywords: config.keywords.as_deref().unwrap_or_default(),
+            screenshots,
+            releases,
         },
         file,
     )?;
@@ -185,7 +459,11 @@ fn generate_desktop_file(config: &Config, data_dir: &Path) -> crate::Result<()>
 }
 
 #[tracing::instrument(level = "trace", skip(config))]
-pub fn generate_data(config: &Config, data_dir: &Path) -> crate::Result<BTreeSet<DebIcon>> {
+pub fn generate_data(
+    config: &Config,
+    data_dir: &Path,
+    format: PackageFormat,
+) -> crate::Result<BTreeSet<DebIcon>> {
     let bin_dir = data_dir.join("usr/bin");
 
     tracing::debug!("Copying binaries");
@@ -200,11 +478,20 @@ pub fn generate_data(config: &Config, data_dir: &Path) -> crate::Result<BTreeSet
 
     tracing::debug!("Copying resources");
     let resource_dir = data_dir.join("usr/lib").join(config.main_binary_name()?);
-    config.copy_resources(&resource_dir)?;
+    config.copy_resources(&resource_dir, format)?;
+
+    tracing::debug!("Copying bundled runtime");
+    config.copy_bundled_runtime(&resource_dir, format)?;
 
     tracing::debug!("Copying external binaries");
     config.copy_external_binaries(&bin_dir)?;
 
+    if config.release_notes.is_some() {
+        tracing::debug!("Copying release notes");
+        let doc_dir = data_dir.join("usr/share/doc").join(package_name(config)?);
+        config.copy_release_notes(&doc_dir)?;
+    }
+
     tracing::debug!("Generating icons");
     let icons = generate_icon_files(config, data_dir)?;
 
@@ -217,33 +504,54 @@ pub fn generate_data(config: &Config, data_dir: &Path) -> crate::Result<BTreeSet
         generate_desktop_file(config, data_dir)?;
     }
 
+    if let Some(appstream) = config.linux().and_then(|l| l.appstream.as_ref()) {
+        tracing::debug!("Generating AppStream metainfo file");
+        generate_metainfo_file(config, appstream, data_dir)?;
+    }
+
     Ok(icons)
 }
 
+/// Returns the file modes configured for the resources and the bundled runtime copied to `resource_dir`,
+/// keyed by their path relative to the data directory.
+///
+/// The resources are copied to `usr/lib/<main-binary-name>` by [`generate_data`].
+pub fn resource_modes(
+    config: &Config,
+    resource_dir: &Path,
+) -> crate::Result<HashMap<PathBuf, u32>> {
+    Ok(config
+        .resources()?
+        .into_iter()
+        .chain(config.bundled_runtime()?)
+        .filter_map(|r| r.mode.map(|mode| (resource_dir.join(r.target), mode)))
+        .collect())
+}
+
+/// Returns the total size in bytes of the files and symlinks in `path`, without following symlinks.
 pub fn get_size<P: AsRef<Path>>(path: P) -> crate::Result<u64> {
     let mut result = 0;
-    let path = path.as_ref();
-
-    if path.is_dir() {
-        for entry in fs::read_dir(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))? {
-            let path = entry?.path();
-            if path.is_file() {
-                let metadata = path.metadata().map_err(|e| Error::IoWithPath(path, e))?;
-                result += metadata.len();
-            } else {
-                result += get_size(path)?;
-            }
+    for entry in WalkDir::new(path.as_ref()) {
+        let entry = entry?;
+        if !entry.file_type().is_dir() {
+            let metadata = entry
+                .path()
+                .symlink_metadata()
+                .map_err(|e| Error::IoWithPath(entry.path().to_path_buf(), e))?;
+            result += metadata.len();
         }
-    } else {
-        let metadata = path
-            .metadata()
-            .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
-        result += metadata.len();
     }
-
     Ok(result)
 }
 
+/// Returns the `Installed-Size` of the package data in KiB, which per Debian policy
+/// is the size in bytes divided by 1024 and rounded up.
+///
+/// See <https://www.debian.org/doc/debian-policy/ch-controlfields.html#installed-size>
+fn installed_size<P: AsRef<Path>>(data_dir: P) -> crate::Result<u64> {
+    Ok(get_size(data_dir)?.div_ceil(1024))
+}
+
 /// Copies user-defined files to the deb package.
 #[tracing::instrument(level = "trace")]
 pub fn copy_custom_files(files: &HashMap<String, String>, data_dir: &Path) -> crate::Result<()> {
@@ -290,6 +598,280 @@ pub fn copy_custom_files(files: &HashMap<String, String>, data_dir: &Path) -> cr
     Ok(())
 }
 
+/// Returns `path` relative to the root of the package, rejecting paths escaping it.
+fn package_relative_path(path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::RootDir => {}
+            Component::Normal(c) => relative.push(c),
+            _ => return None,
+        }
+    }
+    relative.components().next().is_some().then_some(relative)
+}
+
+/// Creates the [`LinuxConfig::symlinks`](crate::config::LinuxConfig::symlinks) in `data_dir`.
+///
+/// The links are relative so they point to their target both in the package and once installed.
+pub fn create_symlinks(config: &Config, data_dir: &Path) -> crate::Result<()> {
+    let Some(symlinks) = config.linux().and_then(|l| l.symlinks.as_ref()) else {
+        return Ok(());
+    };
+
+    for (link, target) in symlinks.iter().collect::<BTreeMap<_, _>>() {
+        let invalid = |reason: &str| Error::InvalidSymlink(link.clone(), reason.into());
+
+        let link_path = if Path::new(link).components().count() == 1 {
+            format!("/usr/bin/{link}")
+        } else {
+            link.clone()
+        };
+        let link_path = package_relative_path(&link_path)
+            .ok_or_else(|| invalid("the link must be a path inside the package"))?;
+        let target_path = package_relative_path(target)
+            .ok_or_else(|| invalid("the target must be a path inside the package"))?;
+
+        if !data_dir.join(&target_path).exists() {
+            return Err(invalid(&format!(
+                "the target `{target}` does not exist in the package"
+            )));
+        }
+        let dest = data_dir.join(&link_path);
+        if dest.symlink_metadata().is_ok() {
+            return Err(invalid("a file already exists at the link path"));
+        }
+
+        let parent = dest
+            .parent()
+            .ok_or_else(|| crate::Error::ParentDirNotFound(dest.clone()))?;
+        fs::create_dir_all(parent).map_err(|e| Error::IoWithPath(parent.to_path_buf(), e))?;
+        let depth = link_path.components().count() - 1;
+        let relative_target = std::iter::repeat_n(Path::new(".."), depth)
+            .collect::<PathBuf>()
+            .join(&target_path);
+        std::os::unix::fs::symlink(&relative_target, &dest)
+            .map_err(|e| Error::IoWithPath(dest.clone(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Whether `name` is a valid package name as required by the Debian policy, matching `[a-z0-9][a-z0-9+.-]+`.
+fn is_valid_package_name(name: &str) -> bool {
+    let valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    let mut chars = name.chars();
+    chars.next().is_some_and(valid_char)
+        && name.len() >= 2
+        && chars.all(|c| valid_char(c) || matches!(c, '+' | '.' | '-'))
+}
+
+/// Returns the [`DebianConfig::package_name`](crate::config::DebianConfig::package_name) override,
+/// validated against the Debian policy.
+fn package_name_override(config: &Config) -> crate::Result<Option<&str>> {
+    match config.deb().and_then(|deb| deb.package_name.as_deref()) {
+        Some(name) if !is_valid_package_name(name) => Err(Error::InvalidPackageName(
+            "deb",
+            name.into(),
+            "[a-z0-9][a-z0-9+.-]+",
+        )),
+        name => Ok(name),
+    }
+}
+
+/// Validates the package names of the relationship fields, like `oldapp (<< 2.0.0)` or `app-a | app-b`,
+/// against the Debian policy.
+///
+/// See <https://www.debian.org/doc/debian-policy/ch-relationships.html#syntax-of-relationship-fields>
+fn validate_relations(relations: &[String]) -> crate::Result<()> {
+    for relation in relations.iter().flat_map(|r| r.split('|')) {
+        let relation = relation.trim();
+        let name = relation
+            .split(|c: char| c.is_whitespace() || c == '(' || c == ':')
+            .next()
+            .unwrap_or_default();
+        if !is_valid_package_name(name) {
+            return Err(Error::InvalidPackageName(
+                "deb",
+                relation.into(),
+                "[a-z0-9][a-z0-9+.-]+",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of the package, used in the `Package` field of the control file.
+fn package_name(config: &Config) -> crate::Result<String> {
+    Ok(package_name_override(config)?
+        .map(ToString::to_string)
+        .unwrap_or_else(|| AsKebabCase(&config.product_name).to_string()))
+}
+
+/// Returns the name of the [`DebianConfig::data_package`](crate::config::DebianConfig::data_package),
+/// `<package>-data`.
+fn data_package_name(config: &Config) -> crate::Result<String> {
+    Ok(format!("{}-data", package_name(config)?))
+}
+
+/// Returns the maintainer of the package, [`DebianConfig::maintainer`](crate::config::DebianConfig::maintainer),
+/// [`Config::authors`] or [`Config::publisher`](Config::publisher()), in that order.
+pub(crate) fn maintainer(config: &Config) -> Option<String> {
+    let maintainer = config
+        .deb()
+        .and_then(|d| d.maintainer.clone())
+        .or_else(|| config.authors.as_ref().map(|a| a.join(", ")))
+        .unwrap_or_else(|| config.publisher());
+    (!maintainer.trim().is_empty()).then_some(maintainer)
+}
+
+/// The urgencies allowed in a changelog entry.
+const CHANGELOG_URGENCIES: &[&str] = &["low", "medium", "high", "emergency", "critical"];
+
+/// Renders the changelog `entries` of the package, validating them against the Debian policy.
+///
+/// See <https://www.debian.org/doc/debian-policy/ch-source.html#debian-changelog-debian-changelog>
+fn render_changelog(
+    config: &Config,
+    package_name: &str,
+    entries: &[DebianChangelogEntry],
+) -> crate::Result<String> {
+    use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+    let invalid = |version: &str, reason: &str| {
+        Error::InvalidDebianChangelog(format!("the entry of version `{version}` {reason}"))
+    };
+
+    match entries.first() {
+        None => {
+            return Err(Error::InvalidDebianChangelog(
+                "the changelog must have at least one entry".into(),
+            ))
+        }
+        Some(entry) if entry.version != config.version => {
+            return Err(invalid(
+                &entry.version,
+                &format!(
+                    "is the newest but doesn't match the package version `{}`",
+                    config.version
+                ),
+            ))
+        }
+        _ => {}
+    }
+
+    let default_date = match util::source_date_epoch() {
+        Some(epoch) => OffsetDateTime::from_unix_timestamp(epoch as i64)
+            .map_err(|e| Error::InvalidDebianChangelog(e.to_string()))?,
+        None => OffsetDateTime::now_utc(),
+    };
+
+    let mut changelog = String::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let version = entry.version.as_str();
+        let valid_version = version.starts_with(|c: char| c.is_ascii_digit())
+            && version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".+~:-".contains(c));
+        if !valid_version {
+            return Err(invalid(version, "has an invalid version"));
+        }
+
+        let distribution = entry.distribution.as_deref().unwrap_or("unstable");
+        let valid_distribution = !distribution.is_empty()
+            && distribution.split(' ').all(|d| {
+                !d.is_empty()
+                    && d.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || ".+-".contains(c))
+            });
+        if !valid_distribution {
+            return Err(invalid(version, "has an invalid distribution"));
+        }
+
+        let urgency = entry.urgency.as_deref().unwrap_or("medium");
+        if !CHANGELOG_URGENCIES.contains(&urgency) {
+            return Err(invalid(
+                version,
+                &format!("has an invalid urgency, expected one of {CHANGELOG_URGENCIES:?}"),
+            ));
+        }
+
+        let author = entry
+            .author
+            .as_deref()
+            .or_else(|| config.authors.as_ref()?.first().map(String::as_str))
+            .ok_or_else(|| invalid(version, "doesn't have an author"))?;
+        let valid_author = author.split_once(" <").is_some_and(|(name, email)| {
+            !name.trim().is_empty()
+                && email.ends_with('>')
+                && email[..email.len() - 1].contains('@')
+        }) && !author.contains('\n');
+        if !valid_author {
+            return Err(invalid(
+                version,
+                "has an invalid author, expected `Name <email>`",
+            ));
+        }
+
+        let date = match &entry.date {
+            Some(date) => OffsetDateTime::parse(date, &Rfc2822)
+                .map_err(|_| invalid(version, "has an invalid date, expected an RFC 2822 date"))?,
+            None => default_date,
+        };
+        let date = date
+            .format(&Rfc2822)
+            .map_err(|e| Error::InvalidDebianChangelog(e.to_string()))?;
+
+        if entry.changes.is_empty() || entry.changes.iter().any(|c| c.trim().is_empty()) {
+            return Err(invalid(version, "must have non-empty changes"));
+        }
+
+        if i > 0 {
+            changelog.push('\n');
+        }
+        changelog.push_str(&format!(
+            "{package_name} ({version}) {distribution}; urgency={urgency}\n\n"
+        ));
+        for change in &entry.changes {
+            let mut lines = change.lines().map(str::trim_end).filter(|l| !l.is_empty());
+            if let Some(first) = lines.next() {
+                changelog.push_str(&format!("  * {}\n", first.trim_start()));
+            }
+            for line in lines {
+                changelog.push_str(&format!("    {}\n", line.trim_start()));
+            }
+        }
+        changelog.push_str(&format!("\n -- {author}  {date}\n"));
+    }
+
+    Ok(changelog)
+}
+
+/// Writes the gzipped changelog of the package into `usr/share/doc/<package>/changelog.Debian.gz`.
+fn generate_changelog_file(
+    config: &Config,
+    changelog: &DebianChangelog,
+    data_dir: &Path,
+) -> crate::Result<()> {
+    let package_name = package_name(config)?;
+    let changelog = match changelog {
+        DebianChangelog::Entries(entries) => render_changelog(config, &package_name, entries)?,
+        DebianChangelog::Path(path) => {
+            fs::read_to_string(path).map_err(|e| Error::IoWithPath(path.clone(), e))?
+        }
+    };
+
+    let dest_path = data_dir
+        .join("usr/share/doc")
+        .join(&package_name)
+        .join("changelog.Debian.gz");
+    // the gzip header doesn't store a file name or a timestamp, as `gzip -9n` does
+    let mut encoder = GzEncoder::new(util::create_file(&dest_path)?, Compression::best());
+    encoder.write_all(changelog.as_bytes())?;
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
 /// Generates the debian control file and stores it under the `control_dir`.
 #[tracing::instrument(level = "trace", skip(config))]
 fn generate_control_file(
@@ -301,46 +883,68 @@ fn generate_control_file(
     // For more information about the format of this file, see
     // https://www.debian.org/doc/debian-policy/ch-controlfields.html
     let dest_path = control_dir.join("control");
-    let mut file = util::create_file(&dest_path)?;
 
-    let pkg_name = config
-        .deb()
-        .and_then(|deb| deb.package_name.clone())
-        .unwrap_or_else(|| AsKebabCase(&config.product_name).to_string());
+    let pkg_name = package_name(config)?;
+    let deb = config.deb();
 
-    writeln!(file, "Package: {pkg_name}")?;
-    writeln!(file, "Version: {}", &config.version)?;
-    writeln!(file, "Architecture: {arch}")?;
-    // Installed-Size must be divided by 1024, see https://www.debian.org/doc/debian-policy/ch-controlfields.html#installed-size
-    writeln!(file, "Installed-Size: {}", get_size(data_dir)? / 1024)?;
-    if let Some(authors) = &config.authors {
-        writeln!(file, "Maintainer: {}", authors.join(", "))?;
+    let mut fields = vec![
+        ("Package".to_string(), pkg_name),
+        ("Version".to_string(), config.version.clone()),
+        ("Architecture".to_string(), arch.to_string()),
+        (
+            "Installed-Size".to_string(),
+            installed_size(data_dir)?.to_string(),
+        ),
+    ];
+    if let Some(maintainer) = maintainer(config) {
+        fields.push(("Maintainer".into(), maintainer));
     }
-    if let Some(section) = config.deb().and_then(|d| d.section.as_ref()) {
-        writeln!(file, "Section: {section}")?;
+    if let Some(section) = deb.and_then(|d| d.section.as_ref()) {
+        fields.push(("Section".into(), section.clone()));
     }
 
-    if let Some(priority) = config.deb().and_then(|d| d.priority.as_ref()) {
-        writeln!(file, "Priority: {priority}")?;
-    } else {
-        writeln!(file, "Priority: optional")?;
-    }
+    let priority = deb.and_then(|d| d.priority.as_deref());
+    fields.push(("Priority".into(), priority.unwrap_or("optional").into()));
 
-    if let Some(homepage) = &config.homepage {
-        writeln!(file, "Homepage: {homepage}")?;
+    for (name, url) in config.deb_url_fields()? {
+        fields.push((name.into(), url));
     }
-    if let Some(depends) = config.deb().and_then(|d| d.depends.as_ref()) {
-        let dependencies = depends.to_list()?;
+    if let Some(pre_depends) = deb.and_then(|d| d.pre_depends.as_ref()) {
+        let dependencies = pre_depends.to_list()?;
         if !dependencies.is_empty() {
-            writeln!(file, "Depends: {}", dependencies.join(", "))?;
+            fields.push(("Pre-Depends".into(), dependencies.join(", ")));
+        }
+    }
+    let mut dependencies = match deb.and_then(|d| d.depends.as_ref()) {
+        Some(depends) => depends.to_list()?,
+        None => Vec::new(),
+    };
+    if let Some(data_package) = deb.and_then(|d| d.data_package.as_ref()) {
+        dependencies.push(format!(
+            "{} (= {})",
+            data_package_name(config)?,
+            data_package.version.as_ref().unwrap_or(&config.version)
+        ));
+    }
+    if !dependencies.is_empty() {
+        fields.push(("Depends".into(), dependencies.join(", ")));
+    }
+    for (field, relations) in [
+        ("Conflicts", deb.and_then(|d| d.conflicts.as_ref())),
+        ("Replaces", deb.and_then(|d| d.replaces.as_ref())),
+        ("Provides", deb.and_then(|d| d.provides.as_ref())),
+    ] {
+        if let Some(relations) = relations.filter(|r| !r.is_empty()) {
+            validate_relations(relations)?;
+            fields.push((field.into(), relations.join(", ")));
         }
     }
 
-    writeln!(
-        file,
-        "Description: {}",
-        config.description.as_deref().unwrap_or("(none)")
-    )?;
+    let mut description = config
+        .description
+        .as_deref()
+        .unwrap_or("(none)")
+        .to_string();
     for line in config
         .long_description
         .as_deref()
@@ -349,25 +953,220 @@ fn generate_control_file(
     {
         let line = line.trim();
         if line.is_empty() {
-            writeln!(file, " .")?;
+            description.push_str("\n .");
         } else {
-            writeln!(file, " {line}")?;
+            description.push_str("\n ");
+            description.push_str(line);
+        }
+    }
+    fields.push(("Description".into(), description));
+    if let Some(build_id) = &config.build_id {
+        fields.push(("X-Build-Id".into(), build_id.clone()));
+    }
+
+    if let Some(custom_fields) = deb.and_then(|d| d.custom_fields.as_ref()) {
+        let allow_standard = deb.is_some_and(|d| d.allow_standard_fields);
+        for (name, value) in custom_fields.iter().collect::<BTreeMap<_, _>>() {
+            validate_control_field(name, value, allow_standard)?;
+            match fields
+                .iter_mut()
+                .find(|(f, _)| f.eq_ignore_ascii_case(name))
+            {
+                Some(field) => field.1.clone_from(value),
+                None => fields.push((name.clone(), value.clone())),
+            }
         }
     }
+
+    let mut file = util::create_file(&dest_path)?;
+    for (name, value) in fields {
+        writeln!(file, "{name}: {value}")?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Generates the control file of the [`DebianConfig::data_package`](crate::config::DebianConfig::data_package)
+/// holding the resources of the app.
+fn generate_data_control_file(
+    config: &Config,
+    data_package: &DebianDataPackage,
+    arch: &str,
+    control_dir: &Path,
+    data_dir: &Path,
+) -> crate::Result<()> {
+    let dest_path = control_dir.join("control");
+    let deb = config.deb();
+
+    let mut fields = vec![
+        ("Package".to_string(), data_package_name(config)?),
+        (
+            "Version".to_string(),
+            data_package
+                .version
+                .clone()
+                .unwrap_or_else(|| config.version.clone()),
+        ),
+        (
+            "Architecture".to_string(),
+            if data_package.arch_independent {
+                "all".to_string()
+            } else {
+                arch.to_string()
+            },
+        ),
+        (
+            "Installed-Size".to_string(),
+            installed_size(data_dir)?.to_string(),
+        ),
+    ];
+    if let Some(maintainer) = maintainer(config) {
+        fields.push(("Maintainer".into(), maintainer));
+    }
+    if let Some(section) = deb.and_then(|d| d.section.as_ref()) {
+        fields.push(("Section".into(), section.clone()));
+    }
+    let priority = deb.and_then(|d| d.priority.as_deref());
+    fields.push(("Priority".into(), priority.unwrap_or("optional").into()));
+    for (name, url) in config.deb_url_fields()? {
+        fields.push((name.into(), url));
+    }
+    fields.push((
+        "Description".into(),
+        format!(
+            "Data files of {}\n This package contains the resources of {}, installed at /usr/lib/{}.",
+            config.product_name,
+            config.product_name,
+            config.main_binary_name()?
+        ),
+    ));
+
+    let mut file = util::create_file(&dest_path)?;
+    for (name, value) in fields {
+        writeln!(file, "{name}: {value}")?;
+    }
     file.flush()?;
     Ok(())
 }
 
+/// The fields of binary packages defined by the Debian policy.
+///
+/// See <https://www.debian.org/doc/debian-policy/ch-controlfields.html#binary-package-control-files-debian-control>
+const STANDARD_CONTROL_FIELDS: &[&str] = &[
+    "Package",
+    "Source",
+    "Version",
+    "Section",
+    "Priority",
+    "Architecture",
+    "Essential",
+    "Depends",
+    "Pre-Depends",
+    "Recommends",
+    "Suggests",
+    "Enhances",
+    "Breaks",
+    "Conflicts",
+    "Provides",
+    "Replaces",
+    "Installed-Size",
+    "Maintainer",
+    "Description",
+    "Homepage",
+    "Vcs-Browser",
+    "Vcs-Git",
+    "Bugs",
+    "Built-Using",
+    "Static-Built-Using",
+    "Multi-Arch",
+    "Protected",
+];
+
+// Validates a custom field of the control file, which must be a user-defined `X-` field
+// or a standard field when `allow_standard` is set, with a single-line value.
+// See <https://www.debian.org/doc/debian-policy/ch-controlfields.html#syntax-of-control-files>
+fn validate_control_field(name: &str, value: &str, allow_standard: bool) -> crate::Result<()> {
+    let invalid = |reason: &str| Err(Error::InvalidDebControlField(name.into(), reason.into()));
+
+    let valid_name = !name.is_empty()
+        && !name.starts_with(['#', '-'])
+        && name.chars().all(|c| c.is_ascii_graphic() && c != ':');
+    if !valid_name {
+        return invalid("field names must only contain printable ASCII characters other than `:` and must not start with `#` or `-`");
+    }
+    let standard = STANDARD_CONTROL_FIELDS
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(name));
+    if standard && !allow_standard {
+        return invalid(
+            "it is a standard field, set `allowStandardFields` to override the generated value",
+        );
+    }
+    if !standard && !name.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("X-")) {
+        return invalid("custom field names must start with `X-`");
+    }
+    if value.trim().is_empty() || value.contains(['\n', '\r']) {
+        return invalid("the value must be a non-empty single line");
+    }
+    Ok(())
+}
+
+/// Refreshes the desktop entry and icon caches, so the launcher of the app shows up
+/// without logging out, doing nothing when the tools aren't installed.
+const REFRESH_DESKTOP_CACHES: &str = r#"    if command -v update-desktop-database >/dev/null 2>&1; then
+        update-desktop-database -q /usr/share/applications || true
+    fi
+    if command -v gtk-update-icon-cache >/dev/null 2>&1; then
+        gtk-update-icon-cache -q -t -f /usr/share/icons/hicolor || true
+    fi
+"#;
+
+/// Generates the `postinst` and `postrm` maintainer scripts under the `control_dir`,
+/// refreshing the desktop caches after the package is configured and after it is removed,
+/// unless [`DebianConfig::refresh_desktop_caches`](crate::config::DebianConfig::refresh_desktop_caches) is `false`.
+///
+/// See <https://www.debian.org/doc/debian-policy/ch-maintainerscripts.html>
+fn generate_maintainer_scripts(config: &Config, control_dir: &Path) -> crate::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if config
+        .deb()
+        .and_then(|d| d.refresh_desktop_caches)
+        .is_some_and(|refresh| !refresh)
+    {
+        return Ok(());
+    }
+
+    let scripts = [
+        ("postinst", r#"[ "$1" = "configure" ]"#),
+        ("postrm", r#"[ "$1" = "remove" ] || [ "$1" = "purge" ]"#),
+    ];
+    for (name, condition) in scripts {
+        let path = control_dir.join(name);
+        let mut file = util::create_file(&path)?;
+        write!(
+            file,
+            "#!/bin/sh\nset -e\n\nif {condition}; then\n{REFRESH_DESKTOP_CACHES}fi\n"
+        )?;
+        file.flush()?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    }
+
+    Ok(())
+}
+
 /// Creates an `md5sums` file in the `control_dir` containing the MD5 checksums
 /// for each file within the `data_dir`.
 #[tracing::instrument(level = "trace")]
 fn generate_md5sums(control_dir: &Path, data_dir: &Path) -> crate::Result<()> {
     let md5sums_path = control_dir.join("md5sums");
     let mut md5sums_file = util::create_file(&md5sums_path)?;
-    for entry in WalkDir::new(data_dir) {
+    for entry in WalkDir::new(data_dir).sort_by_file_name() {
         let entry = entry?;
         let path = entry.path();
-        if path.is_dir() {
+        // dpkg only lists the checksums of regular files
+        if !entry.file_type().is_file() {
             continue;
         }
         let mut file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
@@ -386,24 +1185,125 @@ fn generate_md5sums(control_dir: &Path, data_dir: &Path) -> crate::Result<()> {
     Ok(())
 }
 
-fn create_tar_from_dir<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> crate::Result<W> {
+/// Whether `name` is a valid user or group name, like the ones accepted by `adduser`.
+fn is_valid_owner_name(name: &str) -> bool {
+    let name = name.strip_suffix('$').unwrap_or(name);
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name.len() <= 32
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'))
+}
+
+/// The owners of the files of a package, from the [`DebianConfig::file_owners`](crate::config::DebianConfig::file_owners).
+#[derive(Default)]
+pub struct FileOwners(Vec<(glob::Pattern, DebianFileOwner)>);
+
+impl FileOwners {
+    /// Reads and validates the [`DebianConfig::file_owners`](crate::config::DebianConfig::file_owners).
+    pub fn new(config: &Config) -> crate::Result<Self> {
+        let mut owners = Vec::new();
+        for owner in config
+            .deb()
+            .and_then(|d| d.file_owners.as_ref())
+            .into_iter()
+            .flatten()
+        {
+            let invalid = |reason| Error::InvalidDebianFileOwner(owner.pattern.clone(), reason);
+            for name in [&owner.user, &owner.group].into_iter().flatten() {
+                if !is_valid_owner_name(name) {
+                    return Err(invalid(
+                        "user and group names must start with a lowercase letter or `_`, followed by at most 31 lowercase letters, digits, `_` or `-`",
+                    ));
+                }
+            }
+            if owner.user.is_none()
+                && owner.uid.is_none()
+                && owner.group.is_none()
+                && owner.gid.is_none()
+            {
+                return Err(invalid("a user, uid, group or gid is required"));
+            }
+            owners.push((glob::Pattern::new(&owner.pattern)?, owner.clone()));
+        }
+        Ok(Self(owners))
+    }
+
+    /// Sets the owner of the entry at `path`, relative to the root of the file system,
+    /// to the first matching owner.
+    fn apply(&self, path: &Path, header: &mut tar::Header) -> crate::Result<()> {
+        let installed_path = Path::new("/").join(path);
+        let Some((_, owner)) = self
+            .0
+            .iter()
+            .find(|(pattern, _)| pattern.matches_path(&installed_path))
+        else {
+            return Ok(());
+        };
+        header.set_uid(owner.uid.unwrap_or(0));
+        header.set_gid(owner.gid.unwrap_or(0));
+        header.set_username(owner.user.as_deref().unwrap_or("root"))?;
+        header.set_groupname(owner.group.as_deref().unwrap_or("root"))?;
+        Ok(())
+    }
+}
+
+/// Creates a tar archive from the given directory.
+///
+/// Entries use deterministic metadata, with the file modes in `modes`,
+/// keyed by the path relative to `src_dir`, and the `owners` taking precedence.
+/// Symlinks are archived as symlinks and the extended attributes are kept when `xattrs` is true.
+///
+/// With a `reproducible_mtime`, the entries are sorted by path, owned by `root` unless the `owners`
+/// say otherwise and all modified at that timestamp, so the same files always give the same archive.
+fn create_tar_from_dir<P: AsRef<Path>, W: Write>(
+    src_dir: P,
+    dest_file: W,
+    modes: &HashMap<PathBuf, u32>,
+    owners: &FileOwners,
+    xattrs: bool,
+    reproducible_mtime: Option<u64>,
+) -> crate::Result<W> {
     use std::os::unix::fs::MetadataExt;
 
     let src_dir = src_dir.as_ref();
     let mut tar_builder = tar::Builder::new(dest_file);
-    for entry in walkdir::WalkDir::new(src_dir) {
+    let mut walker = walkdir::WalkDir::new(src_dir);
+    if reproducible_mtime.is_some() {
+        walker = walker.sort_by_file_name();
+    }
+    for entry in walker {
         let entry = entry?;
         let src_path = entry.path();
         if src_path == src_dir {
             continue;
         }
         let dest_path = src_path.strip_prefix(src_dir)?;
-        let stat =
-            fs::metadata(src_path).map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+        let stat = fs::symlink_metadata(src_path)
+            .map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
         let mut header = tar::Header::new_gnu();
         header.set_metadata_in_mode(&stat, HeaderMode::Deterministic);
-        header.set_mtime(stat.mtime() as u64);
-        if entry.file_type().is_dir() {
+        match reproducible_mtime {
+            Some(mtime) => {
+                header.set_mtime(mtime);
+                header.set_username("root")?;
+                header.set_groupname("root")?;
+            }
+            None => header.set_mtime(stat.mtime() as u64),
+        }
+        if let Some(mode) = modes.get(dest_path) {
+            header.set_mode(*mode);
+        }
+        owners.apply(dest_path, &mut header)?;
+        if xattrs {
+            util::append_tar_xattrs(&mut tar_builder, src_path)?;
+        }
+        if entry.file_type().is_symlink() {
+            let target = fs::read_link(src_path)
+                .map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+            tar_builder.append_link(&mut header, dest_path, target)?;
+        } else if entry.file_type().is_dir() {
             tar_builder.append_data(&mut header, dest_path, &mut std::io::empty())?;
         } else {
             let mut src_file =
@@ -417,28 +1317,224 @@ fn create_tar_from_dir<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> cr
 /// Creates a `.tar.gz` file from the given directory (placing the new file
 /// within the given directory's parent directory), then deletes the original
 /// directory and returns the path to the new file.
-pub fn tar_and_gzip_dir<P: AsRef<Path>>(src_dir: P) -> crate::Result<PathBuf> {
-    let src_dir = src_dir.as_ref();
-    let dest_path = src_dir.with_additional_extension("tar.gz");
-    let dest_file = util::create_file(&dest_path)?;
-    let gzip_encoder = GzEncoder::new(dest_file, Compression::default());
-    let gzip_encoder = create_tar_from_dir(src_dir, gzip_encoder)?;
-    let mut dest_file = gzip_encoder.finish()?;
-    dest_file.flush()?;
+pub fn tar_and_gzip_dir<P: AsRef<Path>>(
+    src_dir: P,
+    modes: &HashMap<PathBuf, u32>,
+    owners: &FileOwners,
+    xattrs: bool,
+    reproducible_mtime: Option<u64>,
+) -> crate::Result<PathBuf> {
+    tar_and_compress_dir(
+        src_dir.as_ref(),
+        DebianCompression::Gzip,
+        None,
+        modes,
+        owners,
+        xattrs,
+        reproducible_mtime,
+    )
+}
+
+/// The extension of a member of the `.deb` archive with the given compression.
+fn tar_extension(compression: DebianCompression) -> &'static str {
+    match compression {
+        DebianCompression::None => "tar",
+        DebianCompression::Gzip => "tar.gz",
+        DebianCompression::Xz => "tar.xz",
+        DebianCompression::Zstd => "tar.zst",
+    }
+}
+
+/// Creates a tar file from the given directory, next to it, compressed with `compression` at `level`,
+/// and returns the path to the new file.
+///
+/// The xz and Zstandard compressions run the `xz` and `zstd` tools on the tar file,
+/// with a single thread so the compressed file is reproducible.
+fn tar_and_compress_dir(
+    src_dir: &Path,
+    compression: DebianCompression,
+    level: Option<u32>,
+    modes: &HashMap<PathBuf, u32>,
+    owners: &FileOwners,
+    xattrs: bool,
+    reproducible_mtime: Option<u64>,
+) -> crate::Result<PathBuf> {
+    let dest_path = src_dir.with_additional_extension(tar_extension(compression));
+    let write = || -> crate::Result<()> {
+        match compression {
+            DebianCompression::Gzip => {
+                let level = level.map(Compression::new).unwrap_or_default();
+                let gzip_encoder = create_tar_from_dir(
+                    src_dir,
+                    GzEncoder::new(util::create_file(&dest_path)?, level),
+                    modes,
+                    owners,
+                    xattrs,
+                    reproducible_mtime,
+                )?;
+                gzip_encoder.finish()?.flush()?;
+            }
+            DebianCompression::None => create_tar_from_dir(
+                src_dir,
+                util::create_file(&dest_path)?,
+                modes,
+                owners,
+                xattrs,
+                reproducible_mtime,
+            )?
+            .flush()?,
+            DebianCompression::Xz | DebianCompression::Zstd => {
+                let tar_path = src_dir.with_additional_extension("tar");
+                create_tar_from_dir(
+                    src_dir,
+                    util::create_file(&tar_path)?,
+                    modes,
+                    owners,
+                    xattrs,
+                    reproducible_mtime,
+                )?
+                .flush()?;
+                // both tools write `<file>.xz` or `<file>.zst` next to the kept file
+                let (tool, default_level) = match compression {
+                    DebianCompression::Xz => ("xz", 6),
+                    _ => ("zstd", 3),
+                };
+                Command::new(tool)
+                    .arg(format!("-{}", level.unwrap_or(default_level)))
+                    .args(["-T1", "-q", "-f", "-k"])
+                    .arg(&tar_path)
+                    .output_ok()
+                    .map_err(|e| Error::DebianCompressorFailed(tool, e))?;
+                fs::remove_file(&tar_path).map_err(|e| Error::IoWithPath(tar_path.clone(), e))?;
+            }
+        }
+        Ok(())
+    };
+    write().map_err(|e| {
+        let operation = format!(
+            "failed to create the {} archive",
+            tar_extension(compression)
+        );
+        util::write_error(&dest_path, &operation, e)
+    })?;
     Ok(dest_path)
 }
 
-/// Creates an `ar` archive from the given source files and writes it to the
-/// given destination path.
-fn create_archive(srcs: Vec<PathBuf>, dest: &Path) -> crate::Result<()> {
-    let mut builder = ar::Builder::new(util::create_file(dest)?);
-    for path in &srcs {
-        builder.append_path(path)?;
+/// Parses the leading `major.minor.patch` numbers of a dpkg version, like `1.21.22ubuntu1`.
+fn parse_dpkg_version(version: &str) -> Option<(u32, u32, u32)> {
+    let numbers = version
+        .split_once(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or(version, |(numbers, _)| numbers);
+    let mut parts = numbers.split('.').map(str::parse::<u32>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+/// Checks the [`DebianConfig::control_compression`](crate::config::DebianConfig::control_compression)
+/// and [`DebianConfig::data_compression`](crate::config::DebianConfig::data_compression) levels,
+/// and that the [`DebianConfig::min_dpkg_version`](crate::config::DebianConfig::min_dpkg_version)
+/// can extract the members compressed with them.
+fn validate_compression(config: &Config) -> crate::Result<()> {
+    let Some(deb) = config.deb() else {
+        return Ok(());
+    };
+    let invalid = |reason: String| Err(Error::InvalidDebianCompression(reason));
+
+    let min_dpkg_version = match &deb.min_dpkg_version {
+        Some(version) => match parse_dpkg_version(version) {
+            Some(parsed) => Some((version, parsed)),
+            None => return invalid(format!("`{version}` is not a valid dpkg version")),
+        },
+        None => None,
+    };
+    for (member, compression, level) in [
+        (
+            "control.tar",
+            deb.control_compression.unwrap_or_default(),
+            deb.control_compression_level,
+        ),
+        (
+            "data.tar",
+            deb.data_compression.unwrap_or_default(),
+            deb.data_compression_level,
+        ),
+    ] {
+        if let Some(level) = level {
+            let levels = match compression {
+                DebianCompression::None => {
+                    return invalid(format!(
+                        "`{member}` is not compressed, it can't have a compression level"
+                    ))
+                }
+                DebianCompression::Gzip | DebianCompression::Xz => 0..=9,
+                DebianCompression::Zstd => 1..=19,
+            };
+            if !levels.contains(&level) {
+                return invalid(format!(
+                    "the compression level of `{member}` must be from {} to {}, got {level}",
+                    levels.start(),
+                    levels.end()
+                ));
+            }
+        }
+
+        let required = match (member, compression) {
+            (_, DebianCompression::Gzip) | ("data.tar", DebianCompression::None) => None,
+            ("data.tar", DebianCompression::Xz) => Some((1, 15, 6)),
+            (_, DebianCompression::Zstd) => Some((1, 21, 18)),
+            _ => Some((1, 17, 6)),
+        };
+        if let (Some((version, parsed)), Some(required)) = (min_dpkg_version, required) {
+            if parsed < required {
+                return invalid(format!(
+                    "dpkg {version} can't extract a `{}` member, it requires dpkg {}.{}.{}",
+                    tar_extension(compression).replacen("tar", member, 1),
+                    required.0,
+                    required.1,
+                    required.2
+                ));
+            }
+        }
     }
-    builder.into_inner()?.flush()?;
     Ok(())
 }
 
+/// Creates an `ar` archive from the given source files and writes it to the
+/// given destination path.
+///
+/// With a `reproducible_mtime`, the members are owned by `root` with the `0644` mode
+/// and modified at that timestamp, instead of the metadata of the source files.
+fn create_archive(
+    srcs: Vec<PathBuf>,
+    dest: &Path,
+    reproducible_mtime: Option<u64>,
+) -> crate::Result<()> {
+    let write = || -> crate::Result<()> {
+        let mut builder = ar::Builder::new(util::create_file(dest)?);
+        for path in &srcs {
+            let Some(mtime) = reproducible_mtime else {
+                builder.append_path(path)?;
+                continue;
+            };
+            let file = File::open(path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+            let size = file
+                .metadata()
+                .map_err(|e| Error::IoWithPath(path.clone(), e))?
+                .len();
+            let name = path.file_name().unwrap_or_default().as_encoded_bytes();
+            let mut header = ar::Header::new(name.to_vec(), size);
+            header.set_mtime(mtime);
+            header.set_mode(0o644);
+            builder.append(&header, file)?;
+        }
+        builder.into_inner()?.flush()?;
+        Ok(())
+    };
+    write().map_err(|e| util::write_error(dest, "failed to create the deb archive", e))
+}
+
 #[tracing::instrument(level = "trace", skip(ctx))]
 pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     let Context {
@@ -447,19 +1543,16 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         ..
     } = ctx;
 
-    let arch = match config.target_arch()? {
-        "x86" => "i386",
-        "x86_64" => "amd64",
-        "arm" => "armhf",
-        "aarch64" => "arm64",
-        other => other,
-    };
+    let arch = naming::package_arch(config, PackageFormat::Deb)?;
+
+    dependencies::check_dependencies(config)?;
+    validate_compression(config)?;
 
     let intermediates_path = intermediates_path.join("deb");
     util::create_clean_dir(&intermediates_path)?;
 
-    let deb_base_name = format!("{}_{}_{}", config.main_binary_name()?, config.version, arch);
-    let deb_name = format!("{deb_base_name}.deb");
+    let deb_base_name = naming::package_base_name(config, PackageFormat::Deb)?;
+    let deb_name = naming::package_file_name(config, PackageFormat::Deb)?;
 
     let deb_dir = intermediates_path.join(&deb_base_name);
     let deb_path = config.out_dir().join(&deb_name);
@@ -468,17 +1561,89 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
 
     tracing::debug!("Generating data");
     let data_dir = deb_dir.join("data");
-    let _ = generate_data(config, &data_dir)?;
+    let _ = generate_data(config, &data_dir, PackageFormat::Deb)?;
+
+    let data_package = match (
+        config.deb().and_then(|d| d.data_package.as_ref()),
+        naming::deb_data_file_name(config)?,
+    ) {
+        (Some(data_package), Some(data_deb_name)) => {
+            Some((config.out_dir().join(data_deb_name), data_package))
+        }
+        _ => None,
+    };
+    let data_deb_dir = intermediates_path.join(format!("{deb_base_name}-data"));
+    if let Some((data_deb_path, data_package)) = &data_package {
+        tracing::info!("Packaging the resources into {}", data_deb_path.display());
+        let resource_dir = Path::new("usr/lib").join(config.main_binary_name()?);
+        let data_resource_dir = data_deb_dir.join("data").join(&resource_dir);
+        let resource_dir = data_dir.join(resource_dir);
+        if resource_dir.exists() {
+            let parent = data_resource_dir.parent().unwrap();
+            fs::create_dir_all(parent).map_err(|e| Error::IoWithPath(parent.to_path_buf(), e))?;
+            fs::rename(&resource_dir, &data_resource_dir)
+                .map_err(|e| Error::IoWithPath(resource_dir.clone(), e))?;
+        } else {
+            fs::create_dir_all(&data_resource_dir)
+                .map_err(|e| Error::IoWithPath(data_resource_dir.clone(), e))?;
+        }
+        generate_data_control_file(
+            config,
+            data_package,
+            arch,
+            &data_deb_dir.join("control"),
+            &data_deb_dir.join("data"),
+        )?;
+    }
 
     tracing::debug!("Copying files specified in `deb.files`");
     if let Some(files) = config.deb().and_then(|d| d.files.as_ref()) {
         copy_custom_files(files, &data_dir)?;
     }
 
+    tracing::debug!("Copying the system files of the staging directory");
+    copy_custom_files(&config.staged_system_files()?, &data_dir)?;
+
+    tracing::debug!("Creating symlinks");
+    create_symlinks(config, &data_dir)?;
+
+    if let Some(changelog) = config.deb().and_then(|d| d.changelog.as_ref()) {
+        tracing::debug!("Generating changelog");
+        generate_changelog_file(config, changelog, &data_dir)?;
+    }
+
     let control_dir = deb_dir.join("control");
     tracing::debug!("Generating control file");
     generate_control_file(config, arch, &control_dir, &data_dir)?;
 
+    tracing::debug!("Generating maintainer scripts");
+    generate_maintainer_scripts(config, &control_dir)?;
+
+    let owners = FileOwners::new(config)?;
+    ctx.write_artifact(&deb_path, |partial| {
+        write_deb(config, &deb_dir, &owners, partial)
+    })?;
+
+    let Some((data_deb_path, _)) = data_package else {
+        return Ok(vec![deb_path]);
+    };
+    tracing::debug!("Creating data package: {}", data_deb_path.display());
+    ctx.write_artifact(&data_deb_path, |partial| {
+        write_deb(config, &data_deb_dir, &owners, partial)
+    })?;
+    Ok(vec![deb_path, data_deb_path])
+}
+
+/// Writes the `.deb` archive of the `control` and `data` directories of `deb_dir` into `deb_path`.
+fn write_deb(
+    config: &Config,
+    deb_dir: &Path,
+    owners: &FileOwners,
+    deb_path: &Path,
+) -> crate::Result<()> {
+    let control_dir = deb_dir.join("control");
+    let data_dir = deb_dir.join("data");
+
     tracing::debug!("Generating md5sums");
     generate_md5sums(&control_dir, &data_dir)?;
 
@@ -491,16 +1656,944 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     file.flush()?;
 
     // Apply tar/gzip/ar to create the final package file.
-    tracing::debug!("Zipping control dir using tar and gzip");
-    let control_tar_gz_path = tar_and_gzip_dir(control_dir)?;
+    let reproducible_mtime = reproducible_mtime(config);
+    let deb = config.deb();
+    tracing::debug!("Archiving the control dir");
+    let control_tar_path = tar_and_compress_dir(
+        &control_dir,
+        deb.and_then(|d| d.control_compression).unwrap_or_default(),
+        deb.and_then(|d| d.control_compression_level),
+        &HashMap::new(),
+        &FileOwners::default(),
+        false,
+        reproducible_mtime,
+    )?;
 
-    tracing::debug!("Zipping data dir using tar and gzip");
-    let data_tar_gz_path = tar_and_gzip_dir(data_dir)?;
+    tracing::debug!("Archiving the data dir");
+    let modes = super::file_modes(
+        config,
+        &data_dir,
+        resource_modes(
+            config,
+            &Path::new("usr/lib").join(config.main_binary_name()?),
+        )?,
+    )?;
+    let data_tar_path = tar_and_compress_dir(
+        &data_dir,
+        deb.and_then(|d| d.data_compression).unwrap_or_default(),
+        deb.and_then(|d| d.data_compression_level),
+        &modes,
+        owners,
+        config.preserve_xattrs,
+        reproducible_mtime,
+    )?;
 
     tracing::debug!("Creating final archive: {}", deb_path.display());
     create_archive(
-        vec![debian_binary_path, control_tar_gz_path, data_tar_gz_path],
-        &deb_path,
-    )?;
-    Ok(vec![deb_path])
+        vec![debian_binary_path, control_tar_path, data_tar_path],
+        deb_path,
+        reproducible_mtime,
+    )
+}
+
+/// Returns the timestamp of the entries of the archives when [`DebianConfig::reproducible`]
+/// is set, the `SOURCE_DATE_EPOCH` or `0`.
+fn reproducible_mtime(config: &Config) -> Option<u64> {
+    config
+        .deb()
+        .is_some_and(|d| d.reproducible)
+        .then(|| util::source_date_epoch().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        AppCategory, AppStreamConfig, AppStreamRelease, AppStreamScreenshot, Binary, DebianConfig,
+        LinuxConfig, ReleaseNotes,
+    };
+
+    #[test]
+    fn it_sets_the_owners_of_data_tar_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        fs::create_dir_all(data_dir.join("var/lib/app")).unwrap();
+        fs::create_dir_all(data_dir.join("usr/bin")).unwrap();
+        fs::write(data_dir.join("var/lib/app/state.db"), "state").unwrap();
+        fs::write(data_dir.join("usr/bin/app"), "app").unwrap();
+
+        let mut config = Config::default();
+        config.deb = Some(
+            DebianConfig::new().file_owners([
+                DebianFileOwner::new("/var/lib/app*")
+                    .user("app-daemon")
+                    .uid(999)
+                    .group("app-daemon")
+                    .gid(998),
+                DebianFileOwner::new("/usr/bin/*").group("staff").gid(50),
+            ]),
+        );
+        let owners = FileOwners::new(&config).unwrap();
+        let tar_gz = tar_and_gzip_dir(&data_dir, &HashMap::new(), &owners, false, None).unwrap();
+
+        let mut archive =
+            tar::Archive::new(flate2::read::GzDecoder::new(File::open(tar_gz).unwrap()));
+        let entries: HashMap<PathBuf, (u64, u64, String, String)> = archive
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let e = e.unwrap();
+                let header = e.header();
+                let name = |n: Option<&str>| n.unwrap_or_default().to_string();
+                (
+                    e.path().unwrap().into_owned(),
+                    (
+                        header.uid().unwrap(),
+                        header.gid().unwrap(),
+                        name(header.username().unwrap()),
+                        name(header.groupname().unwrap()),
+                    ),
+                )
+            })
+            .collect();
+        let daemon = (999, 998, "app-daemon".into(), "app-daemon".into());
+        assert_eq!(entries[Path::new("var/lib/app")], daemon);
+        assert_eq!(entries[Path::new("var/lib/app/state.db")], daemon);
+        assert_eq!(
+            entries[Path::new("usr/bin/app")],
+            (0, 50, "root".into(), "staff".into())
+        );
+        assert_eq!(entries[Path::new("usr/bin")].0, 0);
+        assert_eq!(entries[Path::new("var/lib")].0, 0);
+
+        for owner in [
+            DebianFileOwner::new("/var/lib/app*").user("App"),
+            DebianFileOwner::new("/var/lib/app*"),
+        ] {
+            config.deb = Some(DebianConfig::new().file_owners([owner]));
+            assert!(matches!(
+                FileOwners::new(&config),
+                Err(Error::InvalidDebianFileOwner(pattern, _)) if pattern == "/var/lib/app*"
+            ));
+        }
+    }
+
+    #[test]
+    fn it_applies_file_modes_to_data_tar() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let resource_dir = data_dir.join("usr/lib/app");
+        fs::create_dir_all(&resource_dir).unwrap();
+        fs::write(resource_dir.join("run.sh"), "#!/bin/sh").unwrap();
+        fs::write(resource_dir.join("data.txt"), "data").unwrap();
+
+        let modes = HashMap::from([(PathBuf::from("usr/lib/app/run.sh"), 0o755)]);
+        let tar_gz =
+            tar_and_gzip_dir(&data_dir, &modes, &FileOwners::default(), false, None).unwrap();
+
+        let mut archive =
+            tar::Archive::new(flate2::read::GzDecoder::new(File::open(tar_gz).unwrap()));
+        let entries: HashMap<PathBuf, u32> = archive
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let e = e.unwrap();
+                (e.path().unwrap().into_owned(), e.header().mode().unwrap())
+            })
+            .collect();
+        assert_eq!(entries[Path::new("usr/lib/app/run.sh")], 0o755);
+        assert_eq!(entries[Path::new("usr/lib/app/data.txt")], 0o644);
+        assert_eq!(entries[Path::new("usr/lib/app")], 0o755);
+    }
+
+    #[test]
+    fn it_preserves_symlinks_and_xattrs_in_data_tar() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let lib_dir = data_dir.join("usr/lib/app");
+        fs::create_dir_all(&lib_dir).unwrap();
+        fs::write(lib_dir.join("libfoo.so.1.2"), "lib").unwrap();
+        std::os::unix::fs::symlink("libfoo.so.1.2", lib_dir.join("libfoo.so.1")).unwrap();
+        // the file system of the temporary directory may not support extended attributes
+        let xattrs = xattr::set(lib_dir.join("libfoo.so.1.2"), "user.origin", b"test").is_ok();
+
+        let tar_gz = tar_and_gzip_dir(
+            &data_dir,
+            &HashMap::new(),
+            &FileOwners::default(),
+            true,
+            None,
+        )
+        .unwrap();
+        let mut archive =
+            tar::Archive::new(flate2::read::GzDecoder::new(File::open(tar_gz).unwrap()));
+        let mut entries = HashMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let xattr = entry.pax_extensions().unwrap().and_then(|extensions| {
+                extensions
+                    .map(|e| e.unwrap())
+                    .find(|e| e.key() == Ok("SCHILY.xattr.user.origin"))
+                    .map(|e| e.value_bytes().to_vec())
+            });
+            entries.insert(
+                entry.path().unwrap().into_owned(),
+                (
+                    entry.header().entry_type(),
+                    entry.link_name().unwrap().map(|l| l.into_owned()),
+                    entry.size(),
+                    xattr,
+                ),
+            );
+        }
+
+        let (kind, link, size, _) = &entries[Path::new("usr/lib/app/libfoo.so.1")];
+        assert_eq!(*kind, tar::EntryType::Symlink);
+        assert_eq!(link.as_deref(), Some(Path::new("libfoo.so.1.2")));
+        assert_eq!(*size, 0);
+        let (kind, _, size, xattr) = &entries[Path::new("usr/lib/app/libfoo.so.1.2")];
+        assert_eq!(*kind, tar::EntryType::Regular);
+        assert_eq!(*size, 3);
+        if xattrs {
+            assert_eq!(xattr.as_deref(), Some(&b"test"[..]));
+        }
+    }
+
+    #[test]
+    fn it_builds_reproducible_data_tar() {
+        use std::time::{Duration, SystemTime};
+
+        let dir = tempfile::tempdir().unwrap();
+        let owners = FileOwners::default();
+        let build = |name: &str, files: &[&str], modified: SystemTime| {
+            let data_dir = dir.path().join(name).join("data");
+            for file in files {
+                let path = data_dir.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, file).unwrap();
+                File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(modified)
+                    .unwrap();
+            }
+            let tar_gz = tar_and_gzip_dir(
+                &data_dir,
+                &HashMap::new(),
+                &owners,
+                false,
+                Some(1_700_000_000),
+            )
+            .unwrap();
+            let deb = dir.path().join(name).join("app.deb");
+            create_archive(vec![tar_gz.clone()], &deb, Some(1_700_000_000)).unwrap();
+
+            let mut data_tar = Vec::new();
+            std::io::Read::read_to_end(
+                &mut flate2::read::GzDecoder::new(File::open(tar_gz).unwrap()),
+                &mut data_tar,
+            )
+            .unwrap();
+            (data_tar, fs::read(deb).unwrap())
+        };
+
+        // the same files, created in another order at another time
+        let (first_tar, first_deb) = build(
+            "first",
+            &["usr/bin/app", "usr/share/app/b.txt", "usr/share/app/a.txt"],
+            SystemTime::now(),
+        );
+        let (second_tar, second_deb) = build(
+            "second",
+            &["usr/share/app/a.txt", "usr/share/app/b.txt", "usr/bin/app"],
+            SystemTime::UNIX_EPOCH + Duration::from_secs(86_400),
+        );
+        assert_eq!(md5::compute(&first_tar), md5::compute(&second_tar));
+        assert_eq!(first_deb, second_deb);
+
+        let mut archive = tar::Archive::new(first_tar.as_slice());
+        let mut paths = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let entry = entry.unwrap();
+            let header = entry.header();
+            assert_eq!(header.mtime().unwrap(), 1_700_000_000);
+            assert_eq!((header.uid().unwrap(), header.gid().unwrap()), (0, 0));
+            assert_eq!(header.username().unwrap(), Some("root"));
+            paths.push(entry.path().unwrap().display().to_string());
+        }
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+    }
+
+    #[test]
+    fn it_compresses_the_members_independently() {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let deb_dir = dir.path().join("app");
+        fs::create_dir_all(deb_dir.join("control")).unwrap();
+        fs::write(deb_dir.join("control/control"), "Package: app\n").unwrap();
+        fs::create_dir_all(deb_dir.join("data/usr/bin")).unwrap();
+        fs::write(deb_dir.join("data/usr/bin/app"), "app").unwrap();
+
+        let mut config = Config::default();
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.deb = Some(
+            DebianConfig::new()
+                .control_compression(DebianCompression::None)
+                .data_compression(DebianCompression::Gzip)
+                .data_compression_level(9),
+        );
+        validate_compression(&config).unwrap();
+        let deb_path = dir.path().join("app.deb");
+        write_deb(&config, &deb_dir, &FileOwners::default(), &deb_path).unwrap();
+
+        let mut archive = ar::Archive::new(File::open(&deb_path).unwrap());
+        let mut members = Vec::new();
+        while let Some(entry) = archive.next_entry() {
+            let mut entry = entry.unwrap();
+            let name = String::from_utf8(entry.header().identifier().to_vec()).unwrap();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            members.push((name, content));
+        }
+        let names = members
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["debian-binary", "control.tar", "data.tar.gz"]);
+        assert_eq!(members[0].1, b"2.0\n");
+
+        let control = tar::Archive::new(members[1].1.as_slice())
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().display().to_string())
+            .collect::<Vec<_>>();
+        assert!(control.contains(&"control".to_string()));
+        let mut data = tar::Archive::new(flate2::read::GzDecoder::new(members[2].1.as_slice()));
+        assert!(data
+            .entries()
+            .unwrap()
+            .any(|e| e.unwrap().path().unwrap() == Path::new("usr/bin/app")));
+
+        let mut invalid = |deb: DebianConfig| {
+            config.deb = Some(deb);
+            match validate_compression(&config) {
+                Err(Error::InvalidDebianCompression(reason)) => reason,
+                r => panic!("unexpected result {r:?}"),
+            }
+        };
+        assert!(invalid(
+            DebianConfig::new()
+                .control_compression(DebianCompression::Xz)
+                .min_dpkg_version("1.17.5")
+        )
+        .contains("can't extract a `control.tar.xz` member, it requires dpkg 1.17.6"));
+        assert!(invalid(
+            DebianConfig::new()
+                .data_compression(DebianCompression::Zstd)
+                .min_dpkg_version("1.19.7")
+        )
+        .contains("requires dpkg 1.21.18"));
+        assert!(invalid(
+            DebianConfig::new()
+                .control_compression(DebianCompression::None)
+                .control_compression_level(1)
+        )
+        .contains("can't have a compression level"));
+        assert!(invalid(DebianConfig::new().data_compression_level(10)).contains("from 0 to 9"));
+
+        config.deb = Some(
+            DebianConfig::new()
+                .data_compression(DebianCompression::Xz)
+                .min_dpkg_version("1.21.22ubuntu1"),
+        );
+        validate_compression(&config).unwrap();
+    }
+
+    #[test]
+    fn it_installs_the_release_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("my-app"), "").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.0.0".into();
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("my-app").main(true)];
+        config.release_notes = Some(ReleaseNotes::Text("- Add dark mode.\n".into()));
+
+        let data_dir = dir.path().join("data");
+        generate_data(&config, &data_dir, PackageFormat::Deb).unwrap();
+        assert_eq!(
+            fs::read_to_string(data_dir.join("usr/share/doc/my-app/RELEASE_NOTES.md")).unwrap(),
+            "- Add dark mode.\n"
+        );
+
+        config.release_notes = Some(ReleaseNotes::File(dir.path().join("CHANGELOG.md")));
+        assert!(matches!(
+            config.validate_release_notes(),
+            Err(Error::DoesNotExist(path)) if path.ends_with("CHANGELOG.md")
+        ));
+    }
+
+    #[test]
+    fn it_generates_changelog() {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.1.0".into();
+        config.authors = Some(vec!["Jane Doe <jane@example.com>".into()]);
+        let entries = vec![
+            DebianChangelogEntry::new("1.1.0")
+                .date("Tue, 02 Jan 2024 10:00:00 +0100")
+                .changes(["Add dark mode.", "Fix a crash\nwhen opening files."]),
+            DebianChangelogEntry::new("1.0.0")
+                .date("Mon, 01 Jan 2024 12:00:00 +0000")
+                .author("John Doe <john@example.com>")
+                .urgency("low")
+                .changes(["Initial release."]),
+        ];
+        generate_changelog_file(&config, &DebianChangelog::Entries(entries), data_dir).unwrap();
+
+        let mut changelog = String::new();
+        flate2::read::GzDecoder::new(
+            File::open(data_dir.join("usr/share/doc/my-app/changelog.Debian.gz")).unwrap(),
+        )
+        .read_to_string(&mut changelog)
+        .unwrap();
+        assert_eq!(
+            changelog,
+            "my-app (1.1.0) unstable; urgency=medium
+
+  * Add dark mode.
+  * Fix a crash
+    when opening files.
+
+ -- Jane Doe <jane@example.com>  Tue, 02 Jan 2024 10:00:00 +0100
+
+my-app (1.0.0) unstable; urgency=low
+
+  * Initial release.
+
+ -- John Doe <john@example.com>  Mon, 01 Jan 2024 12:00:00 +0000
+"
+        );
+
+        let entry = || {
+            DebianChangelogEntry::new("1.1.0")
+                .date("Tue, 02 Jan 2024 10:00:00 +0100")
+                .changes(["Add dark mode."])
+        };
+        for entries in [
+            vec![],
+            vec![DebianChangelogEntry::new("1.0.0").changes(["Initial release."])],
+            vec![entry().date("2024-01-02")],
+            vec![entry().author("Jane Doe")],
+            vec![entry().urgency("whenever")],
+            vec![entry().distribution("")],
+            vec![entry().changes([""])],
+        ] {
+            assert!(matches!(
+                render_changelog(&config, "my-app", &entries),
+                Err(Error::InvalidDebianChangelog(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn it_writes_maintainer_to_control_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let control_dir = dir.path().join("control");
+        let data_dir = dir.path().join("data");
+        fs::create_dir_all(&control_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.0.0".into();
+        config.publisher = Some("Example Inc".into());
+        assert_eq!(maintainer(&config).unwrap(), "Example Inc");
+        config.authors = Some(vec!["John Doe <john@example.com>".into()]);
+        assert_eq!(maintainer(&config).unwrap(), "John Doe <john@example.com>");
+        config.authors = None;
+        generate_control_file(&config, "amd64", &control_dir, &data_dir).unwrap();
+        let control = fs::read_to_string(control_dir.join("control")).unwrap();
+        assert!(control.contains("\nMaintainer: Example Inc\n"), "{control}");
+
+        config.deb =
+            Some(crate::config::DebianConfig::new().maintainer("Jane Doe <jane@example.com>"));
+        generate_control_file(&config, "amd64", &control_dir, &data_dir).unwrap();
+        let control = fs::read_to_string(control_dir.join("control")).unwrap();
+        assert!(
+            control.contains("\nMaintainer: Jane Doe <jane@example.com>\n"),
+            "{control}"
+        );
+
+        config.deb = None;
+        config.publisher = Some(String::new());
+        assert_eq!(maintainer(&config), None);
+    }
+
+    #[test]
+    fn it_writes_url_fields_to_control_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let control_dir = dir.path().join("control");
+        let data_dir = dir.path().join("data");
+        fs::create_dir_all(&control_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.0.0".into();
+        config.homepage = Some("https://example.com".into());
+        config.deb = Some(
+            crate::config::DebianConfig::new()
+                .vcs_browser("https://github.com/example/app")
+                .vcs_git("https://github.com/example/app.git -b main")
+                .bugs("mailto:bugs@example.com"),
+        );
+        generate_control_file(&config, "amd64", &control_dir, &data_dir).unwrap();
+        let control = fs::read_to_string(control_dir.join("control")).unwrap();
+        assert!(
+            control.contains(
+                "\nHomepage: https://example.com\n\
+                 Vcs-Browser: https://github.com/example/app\n\
+                 Vcs-Git: https://github.com/example/app.git -b main\n\
+                 Bugs: mailto:bugs@example.com\n"
+            ),
+            "{control}"
+        );
+
+        // the general homepage is only used when it is a web URL
+        config.homepage = Some("example.com".into());
+        config.deb = None;
+        assert!(config.deb_url_fields().unwrap().is_empty());
+
+        config.deb = Some(crate::config::DebianConfig::new().vcs_git("github.com/example/app"));
+        assert!(matches!(
+            generate_control_file(&config, "amd64", &control_dir, &data_dir),
+            Err(Error::InvalidDebianUrl("vcsGit", _))
+        ));
+    }
+
+    #[test]
+    fn it_refreshes_desktop_caches_in_maintainer_scripts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let control_dir = dir.path().join("control");
+        fs::create_dir_all(&control_dir).unwrap();
+
+        let mut config = Config::default();
+        generate_maintainer_scripts(&config, &control_dir).unwrap();
+        let postinst = control_dir.join("postinst");
+        let script = fs::read_to_string(&postinst).unwrap();
+        assert!(script.starts_with("#!/bin/sh\nset -e\n"), "{script}");
+        assert!(
+            script.contains("if [ \"$1\" = \"configure\" ]; then\n"),
+            "{script}"
+        );
+        assert!(
+            script.contains("update-desktop-database -q /usr/share/applications"),
+            "{script}"
+        );
+        assert!(
+            script.contains("gtk-update-icon-cache -q -t -f /usr/share/icons/hicolor"),
+            "{script}"
+        );
+        // no-op when the tools are absent
+        assert!(
+            script.contains("if command -v gtk-update-icon-cache >/dev/null 2>&1; then"),
+            "{script}"
+        );
+        let mode = fs::metadata(&postinst).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        let postrm = fs::read_to_string(control_dir.join("postrm")).unwrap();
+        assert!(postrm.contains("[ \"$1\" = \"remove\" ]"), "{postrm}");
+        assert!(postrm.contains("update-desktop-database"), "{postrm}");
+
+        fs::remove_dir_all(&control_dir).unwrap();
+        fs::create_dir_all(&control_dir).unwrap();
+        config.deb = Some(DebianConfig::new().refresh_desktop_caches(false));
+        generate_maintainer_scripts(&config, &control_dir).unwrap();
+        assert!(!control_dir.join("postinst").exists());
+        assert!(!control_dir.join("postrm").exists());
+    }
+
+    #[test]
+    fn it_writes_custom_fields_to_control_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let control_dir = dir.path().join("control");
+        let data_dir = dir.path().join("data");
+        fs::create_dir_all(&control_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.0.0".into();
+        config.deb = Some(
+            DebianConfig::new()
+                .custom_fields([("X-MyOrg-Build-Id", "1234"), ("X-Team", "desktop")]),
+        );
+        generate_control_file(&config, "amd64", &control_dir, &data_dir).unwrap();
+        let control = fs::read_to_string(control_dir.join("control")).unwrap();
+        assert!(
+            control.ends_with(" (none)\nX-MyOrg-Build-Id: 1234\nX-Team: desktop\n"),
+            "{control}"
+        );
+
+        for (name, value) in [
+            ("Recommends", "curl"),
+            ("Build-Id", "1234"),
+            ("X-Bad:Name", "1234"),
+            ("X-Multi-Line", "a\nb"),
+            ("X-Empty", " "),
+        ] {
+            config.deb = Some(DebianConfig::new().custom_fields([(name, value)]));
+            assert!(
+                matches!(
+                    generate_control_file(&config, "amd64", &control_dir, &data_dir),
+                    Err(Error::InvalidDebControlField(field, _)) if field == name
+                ),
+                "{name}"
+            );
+        }
+
+        config.deb = Some(
+            DebianConfig::new()
+                .custom_fields([("Recommends", "curl"), ("priority", "required")])
+                .allow_standard_fields(true),
+        );
+        generate_control_file(&config, "amd64", &control_dir, &data_dir).unwrap();
+        let control = fs::read_to_string(control_dir.join("control")).unwrap();
+        assert!(control.contains("\nPriority: required\n"), "{control}");
+        assert!(!control.contains("optional"), "{control}");
+        assert!(control.ends_with("\nRecommends: curl\n"), "{control}");
+    }
+
+    #[test]
+    fn it_writes_relationships_to_control_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let control_dir = dir.path().join("control");
+        let data_dir = dir.path().join("data");
+        fs::create_dir_all(&control_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "newapp".into();
+        config.version = "2.0.0".into();
+        config.deb = Some(
+            DebianConfig::new()
+                .replaces(["oldapp (<< 2.0.0)"])
+                .conflicts(["oldapp (<< 2.0.0)"])
+                .provides(["oldapp (= 2.0.0)", "app-a | app-b"]),
+        );
+        generate_control_file(&config, "amd64", &control_dir, &data_dir).unwrap();
+        let control = fs::read_to_string(control_dir.join("control")).unwrap();
+        assert!(
+            control.contains(
+                "\nConflicts: oldapp (<< 2.0.0)\nReplaces: oldapp (<< 2.0.0)\nProvides: oldapp (= 2.0.0), app-a | app-b\n"
+            ),
+            "{control}"
+        );
+
+        for relation in ["Old_App", "oldapp | Bad", " (<< 1.0)"] {
+            config.deb = Some(DebianConfig::new().replaces([relation]));
+            assert!(
+                matches!(
+                    generate_control_file(&config, "amd64", &control_dir, &data_dir),
+                    Err(Error::InvalidPackageName("deb", _, _))
+                ),
+                "{relation}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_validates_package_name() {
+        let mut config = Config::default();
+        config.product_name = "My Cool App".into();
+        assert_eq!(package_name(&config).unwrap(), "my-cool-app");
+
+        for name in ["my-cool-app", "libfoo2", "g++", "app.desktop", "0ad"] {
+            config.deb = Some(DebianConfig::new().package_name(name));
+            assert_eq!(package_name(&config).unwrap(), name);
+        }
+        for name in [
+            "", "a", "My-App", "-app", "+app", "my_app", "my app", "app/x",
+        ] {
+            config.deb = Some(DebianConfig::new().package_name(name));
+            assert!(
+                matches!(
+                    package_name(&config),
+                    Err(Error::InvalidPackageName("deb", n, _)) if n == name
+                ),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_computes_installed_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path();
+        fs::create_dir_all(data_dir.join("usr/bin")).unwrap();
+        fs::create_dir_all(data_dir.join("usr/share/empty")).unwrap();
+        fs::write(data_dir.join("usr/bin/app"), vec![0; 3000]).unwrap();
+        fs::write(data_dir.join("usr/share/data"), vec![0; 1025]).unwrap();
+        fs::write(data_dir.join("usr/share/empty-file"), "").unwrap();
+
+        assert_eq!(get_size(data_dir).unwrap(), 3000 + 1025);
+        assert_eq!(installed_size(data_dir).unwrap(), 4);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("bin/app", data_dir.join("usr/app")).unwrap();
+            assert_eq!(
+                get_size(data_dir).unwrap(),
+                3000 + 1025 + "bin/app".len() as u64
+            );
+        }
+    }
+
+    #[test]
+    fn it_creates_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path();
+        fs::create_dir_all(data_dir.join("usr/lib/myapp/bin")).unwrap();
+        fs::write(data_dir.join("usr/lib/myapp/bin/myapp"), "").unwrap();
+
+        let mut config = Config::default();
+        config.linux = Some(LinuxConfig::new().symlinks([
+            ("myapp", "/usr/lib/myapp/bin/myapp"),
+            ("ma", "/usr/lib/myapp/bin/myapp"),
+            ("/usr/local/share/myapp", "/usr/lib/myapp"),
+        ]));
+        create_symlinks(&config, data_dir).unwrap();
+
+        for (link, target) in [
+            ("usr/bin/myapp", "../../usr/lib/myapp/bin/myapp"),
+            ("usr/bin/ma", "../../usr/lib/myapp/bin/myapp"),
+            ("usr/local/share/myapp", "../../../usr/lib/myapp"),
+        ] {
+            let link = data_dir.join(link);
+            assert_eq!(fs::read_link(&link).unwrap(), Path::new(target));
+            assert!(link.exists(), "{} should resolve", link.display());
+        }
+
+        for (link, target) in [
+            ("other", "/usr/lib/myapp/bin/missing"),
+            ("other", "/usr/lib/../../etc/passwd"),
+            ("../other", "/usr/lib/myapp/bin/myapp"),
+            ("myapp", "/usr/lib/myapp/bin/myapp"),
+        ] {
+            config.linux = Some(LinuxConfig::new().symlinks([(link, target)]));
+            assert!(
+                matches!(
+                    create_symlinks(&config, data_dir),
+                    Err(Error::InvalidSymlink(l, _)) if l == link
+                ),
+                "{link} -> {target}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_validates_desktop_entry_keys() {
+        for key in [
+            "X-GNOME-Autostart",
+            "Keywords",
+            "Keywords[de_DE]",
+            "Name[sr@latin]",
+        ] {
+            assert!(
+                validate_desktop_entry_key(key).is_ok(),
+                "{key} should be valid"
+            );
+        }
+        for key in [
+            "",
+            "Key With Spaces",
+            "Key[",
+            "Key[]",
+            "Key_Name",
+            "Exec",
+            "StartupNotify",
+        ] {
+            assert!(
+                validate_desktop_entry_key(key).is_err(),
+                "{key} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn it_writes_category_and_keywords_to_desktop_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.binaries = vec![Binary::new("my-app").main(true)];
+        config.category = Some(AppCategory::Photography);
+        config.keywords = Some(vec!["photo".into(), "editor".into()]);
+        generate_desktop_file(&config, data_dir).unwrap();
+
+        let desktop =
+            fs::read_to_string(data_dir.join("usr/share/applications/my-app.desktop")).unwrap();
+        assert!(desktop.contains("\nCategories=Graphics;Photography;\nKeywords=photo;editor;\n"));
+
+        // the keywords can't also be set as an extra entry
+        config.linux =
+            Some(LinuxConfig::new().extra_desktop_entries([("Keywords", "photo;editor;")]));
+        assert!(matches!(
+            generate_desktop_file(&config, data_dir),
+            Err(Error::InvalidDesktopEntryKey(key)) if key == "Keywords"
+        ));
+        config.keywords = None;
+        generate_desktop_file(&config, data_dir).unwrap();
+
+        for keyword in ["", "photo;editor", "multi\nline"] {
+            config.keywords = Some(vec![keyword.into()]);
+            assert!(matches!(
+                config.validate_keywords(),
+                Err(Error::InvalidKeyword(k)) if k == keyword
+            ));
+        }
+    }
+
+    #[test]
+    fn it_generates_metainfo_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.1.0".into();
+        config.homepage = Some("https://example.com/?a=1&b=2".into());
+        config.binaries = vec![Binary::new("my-app").main(true)];
+        config.category = Some(AppCategory::Music);
+        config.keywords = Some(vec!["audio".into(), "mixer & effects".into()]);
+        config.deb = Some(
+            DebianConfig::new().changelog([
+                DebianChangelogEntry::new("1.1.0")
+                    .date("Tue, 02 Jan 2024 10:00:00 +0100")
+                    .changes(["Add <dark> mode & more."]),
+                DebianChangelogEntry::new("1.0.0").date("Mon, 01 Jan 2024 12:00:00 +0000"),
+            ]),
+        );
+        let appstream = AppStreamConfig::new(
+            "Edits \"things\"",
+            "First paragraph\nof the description.\n\nSecond paragraph.",
+        )
+        .project_license("MIT OR Apache-2.0")
+        .screenshots([
+            AppStreamScreenshot::new("https://example.com/main.png").caption("Main window"),
+            AppStreamScreenshot::new("https://example.com/settings.png"),
+        ]);
+        generate_metainfo_file(&config, &appstream, data_dir).unwrap();
+
+        let metainfo =
+            fs::read_to_string(data_dir.join("usr/share/metainfo/my-app.metainfo.xml")).unwrap();
+        let doc = roxmltree::Document::parse(&metainfo).unwrap();
+        let component = doc.root_element();
+        assert_eq!(component.tag_name().name(), "component");
+        assert_eq!(component.attribute("type"), Some("desktop-application"));
+        let text = |name: &str| {
+            component
+                .children()
+                .find(|n| n.has_tag_name(name))
+                .and_then(|n| n.text())
+        };
+        assert_eq!(text("id"), Some("my-app"));
+        assert_eq!(text("name"), Some("My App"));
+        assert_eq!(text("summary"), Some("Edits \"things\""));
+        assert_eq!(text("launchable"), Some("my-app.desktop"));
+        assert_eq!(text("url"), Some("https://example.com/?a=1&b=2"));
+        assert_eq!(text("metadata_license"), Some("CC0-1.0"));
+
+        let paragraphs = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("p"))
+            .map(|n| n.text().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paragraphs,
+            ["First paragraph of the description.", "Second paragraph."]
+        );
+
+        let texts = |name: &str| {
+            doc.descendants()
+                .filter(|n| n.has_tag_name(name))
+                .map(|n| n.text().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts("category"), ["AudioVideo", "Audio", "Music"]);
+        assert_eq!(texts("keyword"), ["audio", "mixer & effects"]);
+
+        let screenshots = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("screenshot"))
+            .map(|n| n.attribute("type"))
+            .collect::<Vec<_>>();
+        assert_eq!(screenshots, [Some("default"), None]);
+
+        let releases = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("release"))
+            .map(|n| {
+                (
+                    n.attribute("version").unwrap(),
+                    n.attribute("date").unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(releases, [("1.1.0", "2024-01-02"), ("1.0.0", "2024-01-01")]);
+        assert_eq!(
+            doc.descendants()
+                .find(|n| n.has_tag_name("li"))
+                .and_then(|n| n.text()),
+            Some("Add <dark> mode & more.")
+        );
+
+        let appstream = appstream.releases([AppStreamRelease::new("1.1.0").date("2024-02-30")]);
+        assert!(matches!(
+            generate_metainfo_file(&config, &appstream, data_dir),
+            Err(Error::InvalidAppStreamMetadata(_))
+        ));
+
+        for appstream in [
+            AppStreamConfig::new("Summary", "Description").id("com.example.other"),
+            AppStreamConfig::new("", "Description"),
+            AppStreamConfig::new("Summary", "\n\n"),
+            AppStreamConfig::new("Summary", "Description")
+                .screenshots([AppStreamScreenshot::new("file:///main.png")]),
+        ] {
+            assert!(
+                matches!(
+                    generate_metainfo_file(&config, &appstream, data_dir),
+                    Err(Error::InvalidAppStreamMetadata(_))
+                ),
+                "{appstream:?} should be invalid"
+            );
+        }
+
+        // without a desktop entry, the id doesn't have to match it
+        config.linux = Some(LinuxConfig::new().generate_desktop_entry(false));
+        let appstream = AppStreamConfig::new("Summary", "Description").id("com.example.other");
+        generate_metainfo_file(&config, &appstream, data_dir).unwrap();
+        assert!(data_dir
+            .join("usr/share/metainfo/com.example.other.metainfo.xml")
+            .is_file());
+    }
 }
@@ -5,9 +5,10 @@
 
 use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf, process::Command};
 
-use super::Context;
+use super::{app::parse_macos_version, naming, Context};
 use crate::{
     codesign::macos as codesign,
+    config::{DmgFilesystem, PackageFormat},
     shell::CommandExt,
     util::{self, download},
     Error,
@@ -16,6 +17,26 @@ use crate::{
 const CREATE_DMG_URL: &str =
     "https://raw.githubusercontent.com/create-dmg/create-dmg/28867ba3563ddef62f55dcf130677103b4296c42/create-dmg";
 
+// Warns when the disk image `filesystem` can't be mounted by the `minimum_system_version` of the app.
+fn check_filesystem(filesystem: DmgFilesystem, minimum_system_version: Option<&str>) {
+    let Some((required, minimum)) = filesystem
+        .minimum_system_version()
+        .zip(minimum_system_version)
+    else {
+        return;
+    };
+    let incompatible = matches!(
+        (parse_macos_version(minimum), parse_macos_version(required)),
+        (Some(minimum), Some(required)) if minimum < required
+    );
+    if incompatible {
+        tracing::warn!(
+            "The {} filesystem of the DMG requires macOS {required} but the minimum system version of the app is {minimum}, use the HFS+ filesystem to support older macOS versions",
+            filesystem.as_str()
+        );
+    }
+}
+
 #[tracing::instrument(level = "trace", skip(ctx))]
 pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     let Context {
@@ -29,17 +50,8 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     let intermediates_path = intermediates_path.join("dmg");
     util::create_clean_dir(&intermediates_path)?;
 
-    let package_base_name = format!(
-        "{}_{}_{}",
-        config.product_name,
-        config.version,
-        match config.target_arch()? {
-            "x86_64" => "x64",
-            other => other,
-        }
-    );
-    let app_bundle_file_name = format!("{}.app", config.product_name);
-    let dmg_name = format!("{}.dmg", &package_base_name);
+    let app_bundle_file_name = naming::package_file_name(config, PackageFormat::App)?;
+    let dmg_name = naming::package_file_name(config, PackageFormat::Dmg)?;
     let dmg_path = out_dir.join(&dmg_name);
 
     tracing::info!("Packaging {} ({})", dmg_name, dmg_path.display());
@@ -181,30 +193,56 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         }
     }
 
-    tracing::debug!("Running create-dmg");
+    let filesystem = dmg.and_then(|d| d.filesystem).unwrap_or_default();
+    check_filesystem(
+        filesystem,
+        config
+            .macos()
+            .and_then(|m| m.minimum_system_version.as_deref()),
+    );
+    bundle_dmg_cmd.args(["--filesystem", filesystem.as_str()]);
 
-    // execute the bundle script
-    bundle_dmg_cmd
-        .current_dir(&out_dir)
-        .args(vec![dmg_name.as_str(), app_bundle_file_name.as_str()])
-        .output_ok()
-        .map_err(crate::Error::CreateDmgFailed)?;
-
-    // Sign DMG if needed
-    if let Some(identity) = &config
-        .macos()
-        .and_then(|macos| macos.signing_identity.as_ref())
-    {
-        tracing::debug!("Codesigning {}", dmg_path.display());
-        codesign::try_sign(
-            vec![codesign::SignTarget {
-                path: dmg_path.clone(),
-                is_native_binary: false,
-            }],
-            identity,
-            config,
-        )?;
+    if let Some(args) = dmg.and_then(|d| d.extra_tool_args.as_ref()) {
+        tracing::debug!("Appending extra create-dmg arguments: {args:?}");
+        bundle_dmg_cmd.args(args);
     }
 
+    tracing::debug!("Running create-dmg");
+
+    ctx.write_artifact(&dmg_path, |partial| {
+        // execute the bundle script
+        bundle_dmg_cmd
+            .current_dir(&out_dir)
+            .arg(partial)
+            .arg(&app_bundle_file_name)
+            .output_ok()
+            .map_err(|e| {
+                if util::is_out_of_space(&e) {
+                    Error::OutOfSpace {
+                        path: dmg_path.clone(),
+                    }
+                } else {
+                    Error::CreateDmgFailed(e)
+                }
+            })?;
+
+        // Sign DMG if needed
+        if let Some(identity) = &config
+            .macos()
+            .and_then(|macos| macos.signing_identity.as_ref())
+        {
+            tracing::debug!("Codesigning {}", dmg_name);
+            codesign::try_sign(
+                vec![codesign::SignTarget {
+                    path: partial.to_path_buf(),
+                    is_native_binary: false,
+                }],
+                identity,
+                config,
+            )?;
+        }
+        Ok(())
+    })?;
+
     Ok(vec![dmg_path])
 }
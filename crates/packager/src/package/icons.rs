@@ -0,0 +1,264 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Validation of the icons against the requirements of each package format,
+//! so invalid icons are reported before running any of the packaging tools.
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+use crate::{Config, Error, PackageFormat};
+
+/// The sizes a `.ico` file must contain, MSI installers use the 256x256 entry.
+const ICO_REQUIRED_SIZES: &[u32] = &[256];
+
+fn invalid_icon(format: PackageFormat, path: &Path, reason: impl std::fmt::Display) -> Error {
+    Error::InvalidIcon {
+        format: format.short_name(),
+        reason: format!("{}: {reason}", path.display()),
+    }
+}
+
+/// Returns the `(width, height)` of the entries of a `.ico` file.
+///
+/// See <https://en.wikipedia.org/wiki/ICO_(file_format)#Icon_resource_structure>
+fn ico_entries(data: &[u8]) -> Result<Vec<(u32, u32)>, &'static str> {
+    let u16_at = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    match (u16_at(0), u16_at(2)) {
+        (Some(0), Some(1)) => {}
+        _ => return Err("not a valid ICO file"),
+    }
+    let count = u16_at(4).ok_or("not a valid ICO file")? as usize;
+    if count == 0 {
+        return Err("the ICO file doesn't contain any image");
+    }
+
+    (0..count)
+        .map(|i| {
+            let entry = data
+                .get(6 + i * 16..6 + (i + 1) * 16)
+                .ok_or("the ICO file is truncated")?;
+            // a size of 0 means 256 pixels
+            let size = |b: u8| if b == 0 { 256 } else { b as u32 };
+            Ok((size(entry[0]), size(entry[1])))
+        })
+        .collect()
+}
+
+fn validate_ico(format: PackageFormat, path: &Path) -> crate::Result<()> {
+    let data = fs::read(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let entries = ico_entries(&data).map_err(|reason| invalid_icon(format, path, reason))?;
+    for size in ICO_REQUIRED_SIZES {
+        if !entries.contains(&(*size, *size)) {
+            return Err(invalid_icon(
+                format,
+                path,
+                format!("the ICO file must contain a {size}x{size} image"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_icns(format: PackageFormat, path: &Path) -> crate::Result<()> {
+    let file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let family = icns::IconFamily::read(BufReader::new(file))
+        .map_err(|e| invalid_icon(format, path, format!("not a valid ICNS file, {e}")))?;
+    if family.is_empty() {
+        return Err(invalid_icon(
+            format,
+            path,
+            "the ICNS file doesn't contain any image",
+        ));
+    }
+    Ok(())
+}
+
+fn image_dimensions(format: PackageFormat, path: &Path) -> crate::Result<(u32, u32)> {
+    image::image_dimensions(path).map_err(|e| invalid_icon(format, path, e))
+}
+
+/// Parses the `<width>x<height>` or `<width>x<height>@2[x]` name of an icon theme directory
+/// and returns the expected size of its images in pixels.
+fn parse_size_dir(name: &str) -> Option<(u32, u32)> {
+    let (name, scale) = match name.strip_suffix("@2x").or_else(|| name.strip_suffix("@2")) {
+        Some(name) => (name, 2),
+        None => (name, 1),
+    };
+    let (width, height) = name.split_once('x')?;
+    Some((
+        width.parse::<u32>().ok()? * scale,
+        height.parse::<u32>().ok()? * scale,
+    ))
+}
+
+fn validate_linux_png(format: PackageFormat, path: &Path) -> crate::Result<()> {
+    let (width, height) = image_dimensions(format, path)?;
+    let dir_size = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .and_then(parse_size_dir);
+    match dir_size {
+        Some(size) if size != (width, height) => Err(invalid_icon(
+            format,
+            path,
+            format!(
+                "the image is {width}x{height} but its directory is for {}x{} icons",
+                size.0, size.1
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validates the icons used by `formats`.
+pub(crate) fn validate(config: &Config, formats: &[PackageFormat]) -> crate::Result<()> {
+    let icons = config.icons()?.unwrap_or_default();
+
+    for &format in formats {
+        match format {
+            PackageFormat::App | PackageFormat::Dmg | PackageFormat::Pkg => {
+                for icon in &icons {
+                    if icon.extension().is_some_and(|e| e == "icns") {
+                        validate_icns(format, icon)?;
+                    } else {
+                        image_dimensions(format, icon)?;
+                    }
+                }
+            }
+            PackageFormat::Wix => {
+                if let Some(icon) = config.find_ico()? {
+                    if icon.extension().is_some_and(|e| e == "ico") {
+                        validate_ico(format, &icon)?;
+                    } else {
+                        image_dimensions(format, &icon)?;
+                    }
+                }
+            }
+            PackageFormat::Nsis => {
+                if let Some(icon) = config.nsis().and_then(|n| n.installer_icon.as_ref()) {
+                    validate_ico(format, icon)?;
+                }
+            }
+            PackageFormat::Deb | PackageFormat::AppImage | PackageFormat::Pacman => {
+                for icon in icons
+                    .iter()
+                    .filter(|i| i.extension().is_some_and(|e| e == "png"))
+                {
+                    validate_linux_png(format, icon)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_png(path: &Path, size: u32) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image::RgbaImage::new(size, size).save(path).unwrap();
+    }
+
+    fn config(icons: &[&Path]) -> Config {
+        let mut config = Config::default();
+        config.icons = Some(icons.iter().map(|i| i.display().to_string()).collect());
+        config
+    }
+
+    #[test]
+    fn it_validates_ico() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let valid = dir.path().join("valid.ico");
+        image::RgbaImage::new(256, 256).save(&valid).unwrap();
+        validate(&config(&[&valid]), &[PackageFormat::Wix]).unwrap();
+
+        let small = dir.path().join("small.ico");
+        image::RgbaImage::new(32, 32).save(&small).unwrap();
+        let err = validate(&config(&[&small]), &[PackageFormat::Wix]).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidIcon { format: "wix", reason } if reason.contains("256x256")),
+            "{err}"
+        );
+
+        // the header announces two images but contains a single entry
+        let truncated = dir.path().join("truncated.ico");
+        let mut data = fs::read(&valid).unwrap();
+        data[4] = 2;
+        data.truncate(6 + 16 + 8);
+        fs::write(&truncated, data).unwrap();
+        assert!(matches!(
+            validate(&config(&[&truncated]), &[PackageFormat::Wix]),
+            Err(Error::InvalidIcon { .. })
+        ));
+
+        let header_only = dir.path().join("header.ico");
+        fs::write(&header_only, &fs::read(&valid).unwrap()[..4]).unwrap();
+        assert!(matches!(
+            validate(&config(&[&header_only]), &[PackageFormat::Wix]),
+            Err(Error::InvalidIcon { .. })
+        ));
+    }
+
+    #[test]
+    fn it_validates_icns() {
+        let dir = tempfile::tempdir().unwrap();
+        let icns = dir.path().join("icon.icns");
+        fs::write(&icns, "not an icns file").unwrap();
+        assert!(matches!(
+            validate(&config(&[&icns]), &[PackageFormat::App]),
+            Err(Error::InvalidIcon { format: "app", .. })
+        ));
+
+        let mut family = icns::IconFamily::new();
+        let image = icns::Image::new(icns::PixelFormat::RGBA, 32, 32);
+        family.add_icon(&image).unwrap();
+        family.write(File::create(&icns).unwrap()).unwrap();
+        validate(&config(&[&icns]), &[PackageFormat::App]).unwrap();
+    }
+
+    #[test]
+    fn it_validates_linux_png_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = dir.path().join("32x32/icon.png");
+        let retina = dir.path().join("32x32@2x/icon.png");
+        let unsized_dir = dir.path().join("icons/icon.png");
+        write_png(&valid, 32);
+        write_png(&retina, 64);
+        write_png(&unsized_dir, 48);
+        validate(
+            &config(&[&valid, &retina, &unsized_dir]),
+            &[PackageFormat::Deb],
+        )
+        .unwrap();
+
+        let mismatch = dir.path().join("128x128/icon.png");
+        write_png(&mismatch, 64);
+        let err = validate(&config(&[&mismatch]), &[PackageFormat::Deb]).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidIcon { format: "deb", reason } if reason.contains("64x64")),
+            "{err}"
+        );
+
+        let corrupted = dir.path().join("16x16/icon.png");
+        fs::create_dir_all(corrupted.parent().unwrap()).unwrap();
+        fs::write(&corrupted, "not a png").unwrap();
+        assert!(matches!(
+            validate(&config(&[&corrupted]), &[PackageFormat::AppImage]),
+            Err(Error::InvalidIcon { .. })
+        ));
+    }
+}
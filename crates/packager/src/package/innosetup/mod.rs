@@ -0,0 +1,364 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! The Inno Setup installer, a `.iss` script compiled by `ISCC` when it is installed,
+//! see [`InnoSetupConfig`](crate::config::InnoSetupConfig).
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use handlebars::{to_json, Handlebars};
+
+use super::{naming, nsis, version_info, Context};
+use crate::{
+    codesign::windows as codesign,
+    config::{Config, LogLevel, PackageFormat},
+    shell::CommandExt,
+    util, Error,
+};
+
+/// The name of the sign tool passed to `ISCC` and used by the `SignTool` directive of the script.
+const SIGN_TOOL_NAME: &str = "cargopackager";
+
+/// Finds the `ISCC` compiler in `PATH`, then in the default installation directories of Inno Setup 6.
+fn find_iscc() -> Option<PathBuf> {
+    let file_name = if cfg!(windows) { "ISCC.exe" } else { "iscc" };
+    let paths = std::env::var_os("PATH").unwrap_or_default();
+    let installation_dirs = ["ProgramFiles(x86)", "ProgramFiles"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .map(|dir| PathBuf::from(dir).join("Inno Setup 6"));
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(file_name))
+        .chain(installation_dirs.map(|dir| dir.join("ISCC.exe")))
+        .find(|path| path.is_file())
+}
+
+/// Escapes a value of the script, doubling the quotes of the quoted parameters
+/// and the braces that would start an Inno Setup constant.
+fn escape(value: &str) -> String {
+    let mut output = String::new();
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\"\""),
+            '{' => output.push_str("{{"),
+            c if c.is_control() => output.push(' '),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Renders the `{cm:LaunchProgram,<name>}` message of the finish page,
+/// encoding the characters of the name that are special in a message argument.
+fn launch_program(
+    h: &handlebars::Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let name = h.param(0).unwrap().render();
+    let name = name
+        .replace('%', "%25")
+        .replace(',', "%2c")
+        .replace('{', "%7b")
+        .replace('}', "%7d");
+    out.write(&format!("{{cm:LaunchProgram,{}}}", escape(&name)))?;
+    Ok(())
+}
+
+fn render_installer_iss(
+    data: &BTreeMap<&str, serde_json::Value>,
+    custom_template_path: Option<&Path>,
+) -> crate::Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("or", Box::new(nsis::handlebars_or));
+    handlebars.register_helper(
+        "association-description",
+        Box::new(nsis::association_description),
+    );
+    handlebars.register_helper("launch-program", Box::new(launch_program));
+    handlebars.register_escape_fn(escape);
+    if let Some(path) = custom_template_path {
+        let template =
+            fs::read_to_string(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+        handlebars
+            .register_template_string("installer.iss", template)
+            .map_err(Box::new)?;
+    } else {
+        handlebars
+            .register_template_string("installer.iss", include_str!("./installer.iss"))
+            .map_err(Box::new)?;
+    }
+
+    Ok(handlebars.render("installer.iss", data)?)
+}
+
+/// Returns the name the installer is written to, without its extension.
+fn output_base_file_name(config: &Config) -> crate::Result<String> {
+    Ok(format!(
+        "{}-innosetup",
+        naming::package_base_name(config, PackageFormat::InnoSetup)?
+    ))
+}
+
+/// Returns the data of the `installer.iss` template.
+fn installer_data(
+    config: &Config,
+    main_binary_path: &Path,
+    arch: &str,
+) -> crate::Result<BTreeMap<&'static str, serde_json::Value>> {
+    let mut data = BTreeMap::new();
+
+    data.insert("identifier", to_json(config.identifier()));
+    data.insert("product_name", to_json(&config.product_name));
+    data.insert("version", to_json(&config.version));
+    data.insert(
+        "version_with_build",
+        to_json(nsis::add_build_number_if_needed(&config.version)?),
+    );
+    data.insert("publisher", to_json(config.inno_setup_publisher()?));
+    data.insert("copyright", to_json(&config.copyright));
+    data.insert("short_description", to_json(&config.description));
+
+    // the links of the Add/Remove Programs entry, keyed by their directive
+    let urls = config
+        .add_remove_programs_links()?
+        .into_iter()
+        .filter_map(|(value, url)| {
+            let directive = match value {
+                "HelpLink" => "AppSupportURL",
+                "URLInfoAbout" => "AppPublisherURL",
+                "URLUpdateInfo" => "AppUpdatesURL",
+                _ => return None,
+            };
+            Some((directive, url))
+        })
+        .collect::<BTreeMap<_, _>>();
+    data.insert("urls", to_json(urls));
+
+    if arch != "x86" {
+        data.insert("architectures", to_json(arch));
+    }
+    if let Some(license) = &config.license_file {
+        let license =
+            dunce::canonicalize(license).map_err(|e| Error::IoWithPath(license.clone(), e))?;
+        data.insert("license", to_json(license));
+    }
+    data.insert(
+        "output_base_file_name",
+        to_json(output_base_file_name(config)?),
+    );
+
+    data.insert("main_binary_name", to_json(config.main_binary_name()?));
+    data.insert("main_binary_path", to_json(main_binary_path));
+
+    let shortcuts = config.windows_shortcuts()?;
+    data.insert("start_menu_shortcut", to_json(shortcuts.start_menu));
+    data.insert("desktop_shortcut", to_json(shortcuts.desktop));
+    data.insert("start_menu_folder", to_json(&shortcuts.start_menu_folder));
+    data.insert("shortcut_binary_name", to_json(&shortcuts.binary));
+    data.insert("shortcut_arguments", to_json(&shortcuts.arguments));
+
+    if let Some(file_associations) = &config.file_associations {
+        data.insert("file_associations", to_json(file_associations));
+    }
+
+    data.insert("binaries", to_json(nsis::generate_binaries_data(config)?));
+
+    // the targets are `\` separated relative paths, split into their directory and file name
+    let (_, resources) = nsis::generate_resource_data(config)?;
+    let resources = resources
+        .iter()
+        .map(|(source, target)| {
+            let target = target.display().to_string().replace('/', "\\");
+            let (dest_dir, dest_name) = target.rsplit_once('\\').unwrap_or(("", &target));
+            serde_json::json!({
+                "source": source,
+                "dest_dir": dest_dir,
+                "dest_name": dest_name,
+            })
+        })
+        .collect::<Vec<_>>();
+    data.insert("resources", to_json(resources));
+
+    Ok(data)
+}
+
+/// Returns the command signing the `$f` file, as expected by the `/S` option of `ISCC`.
+fn sign_tool_command(config: &Config) -> crate::Result<String> {
+    let command = codesign::sign_command("$f", &config.sign_params())?;
+    let quote = |arg: &str| format!("$q{}$q", arg.replace('$', "$$"));
+    let mut parts = vec![quote(&command.get_program().to_string_lossy())];
+    for arg in command.get_args() {
+        let arg = arg.to_string_lossy();
+        // the file name replacing `$f` is already quoted
+        parts.push(if arg == "$f" {
+            arg.into_owned()
+        } else {
+            quote(&arg)
+        });
+    }
+    Ok(parts.join(" "))
+}
+
+#[tracing::instrument(level = "trace", skip(ctx))]
+pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
+    let Context {
+        config,
+        intermediates_path,
+        ..
+    } = ctx;
+
+    let arch = naming::package_arch(config, PackageFormat::InnoSetup)?;
+
+    let main_binary_path = version_info::main_binary_path(config, intermediates_path)?;
+    if config.can_sign() {
+        tracing::debug!("Codesigning {}", main_binary_path.display());
+        codesign::try_sign(&main_binary_path, config)?;
+    }
+
+    let intermediates_path = intermediates_path.join("innosetup").join(arch);
+    util::create_clean_dir(&intermediates_path)?;
+
+    let iscc = find_iscc();
+    let mut data = installer_data(config, &main_binary_path, arch)?;
+    // ISCC signs the installer and the uninstaller with the sign tool of the `SignTool` directive
+    let sign = iscc.is_some() && config.can_sign();
+    if sign {
+        data.insert("sign_tool", to_json(SIGN_TOOL_NAME));
+    }
+
+    let custom_template_path = config.inno_setup().and_then(|i| i.template.as_deref());
+    let installer_iss = render_installer_iss(&data, custom_template_path)?;
+    let installer_iss_path = intermediates_path.join("installer.iss");
+    // the BOM makes ISCC read the script as UTF-8
+    fs::write(&installer_iss_path, format!("\u{feff}{installer_iss}"))
+        .map_err(|e| Error::IoWithPath(installer_iss_path.clone(), e))?;
+
+    let Some(iscc) = iscc else {
+        let script_path = config
+            .out_dir()
+            .join(naming::innosetup_script_file_name(config)?);
+        tracing::warn!(
+            "ISCC was not found in PATH or in the Inno Setup 6 installation directory, only writing the script {}",
+            util::display_path(&script_path)
+        );
+        ctx.write_artifact(&script_path, |partial| {
+            fs::copy(&installer_iss_path, partial)
+                .map(|_| ())
+                .map_err(|e| Error::CopyFile(installer_iss_path.clone(), partial.to_path_buf(), e))
+        })?;
+        return Ok(vec![script_path]);
+    };
+
+    let installer_path = config
+        .out_dir()
+        .join(naming::package_file_name(config, PackageFormat::InnoSetup)?);
+    tracing::info!(
+        "Running ISCC to produce {}",
+        util::display_path(&installer_path)
+    );
+    ctx.write_artifact(&installer_path, |partial| {
+        let output_dir = partial
+            .parent()
+            .ok_or_else(|| Error::ParentDirNotFound(partial.to_path_buf()))?;
+        let mut iscc_cmd = Command::new(&iscc);
+        iscc_cmd
+            .arg(format!("/O{}", output_dir.display()))
+            .arg(format!("/F{}", output_base_file_name(config)?));
+        if sign {
+            iscc_cmd.arg(format!("/S{SIGN_TOOL_NAME}={}", sign_tool_command(config)?));
+        }
+        if matches!(config.log_level, Some(LogLevel::Error | LogLevel::Warn)) {
+            iscc_cmd.arg("/Qp");
+        }
+        if let Some(args) = config.inno_setup().and_then(|i| i.extra_tool_args.as_ref()) {
+            tracing::debug!("Appending extra ISCC arguments: {args:?}");
+            iscc_cmd.args(args);
+        }
+        iscc_cmd
+            .arg(&installer_iss_path)
+            .current_dir(&intermediates_path)
+            .output_ok()
+            .map_err(Error::InnoSetupFailed)?;
+        Ok(())
+    })?;
+
+    Ok(vec![installer_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Binary, FileAssociation, Resource, ShortcutsConfig, WindowsConfig};
+
+    #[test]
+    fn it_generates_the_setup_and_files_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("assets/icons")).unwrap();
+        fs::write(dir.path().join("assets/icons/app.png"), "png").unwrap();
+        fs::write(dir.path().join("readme.txt"), "readme").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My {App}".into();
+        config.version = "1.2.3".into();
+        config.target_triple = Some("x86_64-pc-windows-msvc".into());
+        config.identifier = Some("com.example.app".into());
+        config.publisher = Some("Example \"Inc\"".into());
+        config.out_dir = dir.path().to_path_buf();
+        config.binaries = vec![Binary::new("app").main(true), Binary::new("helper")];
+        config.resources = Some(vec![
+            Resource::Single(dir.path().join("assets").display().to_string()),
+            Resource::Single(dir.path().join("readme.txt").display().to_string()),
+        ]);
+        config.file_associations = Some(vec![FileAssociation::new(["myext"])]);
+        config.windows = Some(
+            WindowsConfig::new()
+                .shortcuts(ShortcutsConfig::new().desktop(true).arguments("--open")),
+        );
+        let main_binary_path = dir.path().join("app.exe");
+
+        let data = installer_data(&config, &main_binary_path, "x64").unwrap();
+        let iss = render_installer_iss(&data, None).unwrap();
+
+        let setup = &iss[iss.find("[Setup]\n").unwrap()..iss.find("[Tasks]").unwrap()];
+        assert!(setup.contains("AppId=com.example.app\n"));
+        assert!(setup.contains("AppName=\"My {{App}\"\n"));
+        assert!(setup.contains("AppVersion=\"1.2.3\"\n"));
+        assert!(setup.contains("AppPublisher=\"Example \"\"Inc\"\"\"\n"));
+        assert!(setup.contains("VersionInfoVersion=1.2.3.0\n"));
+        assert!(setup.contains("DefaultDirName={autopf}\\My {{App}\n"));
+        assert!(setup.contains("ArchitecturesAllowed=x64\n"));
+        assert!(setup.contains("ChangesAssociations=yes\n"));
+        assert!(setup.contains("OutputBaseFilename=my-app_1.2.3_x64-innosetup\n"));
+        assert!(!setup.contains("SignTool"));
+
+        let files = &iss[iss.find("[Files]\n").unwrap()..iss.find("[Icons]").unwrap()];
+        assert!(files.contains(&format!(
+            "Source: \"{}\"; DestDir: \"{{app}}\"; DestName: \"app.exe\"; Flags: ignoreversion\n",
+            main_binary_path.display()
+        )));
+        assert!(files.contains("DestDir: \"{app}\"; DestName: \"helper.exe\""));
+        assert!(files.contains("DestDir: \"{app}\\assets\\icons\"; DestName: \"app.png\""));
+        assert!(files.contains("DestDir: \"{app}\"; DestName: \"readme.txt\""));
+
+        let icons = &iss[iss.find("[Icons]\n").unwrap()..iss.find("[Registry]").unwrap()];
+        assert!(icons.contains(
+            "Name: \"{group}\\My {{App}\"; Filename: \"{app}\\app.exe\"; Parameters: \"--open\"\n"
+        ));
+        assert!(icons.contains("Name: \"{autodesktop}\\My {{App}\""));
+        assert!(!icons.contains("Tasks: desktopicon"));
+
+        assert!(iss.contains(
+            "Root: HKA; Subkey: \"Software\\Classes\\myext\\shell\\open\\command\"; ValueType: string; ValueName: \"\"; ValueData: \"\"\"{app}\\app.exe\"\" \"\"%1\"\"\"\n"
+        ));
+        assert!(iss.contains("Description: \"{cm:LaunchProgram,My %7bApp%7d}\""));
+    }
+}
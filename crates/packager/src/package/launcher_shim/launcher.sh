@@ -0,0 +1,25 @@
+#!/bin/sh
+# The launcher of the app, generated by cargo-packager.
+# It runs the app with the interpreter bundled in its resources.
+
+here=$(cd "$(dirname "$0")" && pwd -P) || exit 1
+
+# the resources are in `Contents/Resources` of the macOS app bundle,
+# in `../lib/<main-binary-name>` of the Linux packages and next to the launcher in the archives
+RESOURCES_DIR=
+for dir in "$here/../Resources" "$here/../lib/"{{main_binary_name}} "$here"; do
+  if [ -e "$dir/"{{interpreter}} ]; then
+    RESOURCES_DIR=$(cd "$dir" && pwd -P) || exit 1
+    break
+  fi
+done
+if [ -z "$RESOURCES_DIR" ]; then
+  echo "$0: the interpreter "{{interpreter}}" was not found in the resources of the app" >&2
+  exit 127
+fi
+export RESOURCES_DIR
+{{#each env}}
+export {{name}}="{{value}}"
+{{/each}}
+
+exec "$RESOURCES_DIR/"{{interpreter}}{{#each args}} {{this}}{{/each}}{{#if entrypoint}} "$RESOURCES_DIR/"{{entrypoint}}{{/if}} "$@"
@@ -0,0 +1,204 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! The launcher shim installed in place of the main binary,
+//! running the app with an interpreter bundled in the resources.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use handlebars::Handlebars;
+use serde::Serialize;
+
+use super::Context;
+use crate::{util, Error};
+
+/// The directory of the intermediates path the shim is generated in.
+const LAUNCHER_SHIM_DIR: &str = "launcher-shim";
+
+/// Quotes `value` as a single shell word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Escapes `value` to be used inside double quotes in a shell script, keeping the variable expansions.
+fn shell_double_quote_escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('`', "\\`")
+}
+
+fn path_word(path: &Path) -> String {
+    shell_quote(&path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Generates the shim of the [`Config::launcher_shim`](crate::Config::launcher_shim)
+/// and replaces the path of the main binary with it, so every package installs the shim as the main binary.
+pub(crate) fn install(ctx: &mut Context) -> crate::Result<()> {
+    let Some(shim) = &ctx.config.launcher_shim else {
+        return Ok(());
+    };
+    tracing::debug!("Generating the launcher shim");
+
+    let main_binary_name = ctx.config.main_binary_name()?;
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    let template = match &shim.template {
+        Some(template) => {
+            fs::read_to_string(template).map_err(|e| Error::IoWithPath(template.clone(), e))?
+        }
+        None => include_str!("launcher.sh").to_string(),
+    };
+    handlebars
+        .register_template_string("launcher.sh", template)
+        .map_err(Box::new)?;
+
+    #[derive(Serialize)]
+    struct EnvVar {
+        name: String,
+        value: String,
+    }
+
+    #[derive(Serialize)]
+    struct LauncherTemplateParams {
+        main_binary_name: String,
+        interpreter: String,
+        entrypoint: Option<String>,
+        args: Vec<String>,
+        env: Vec<EnvVar>,
+    }
+
+    let mut env = shim
+        .env
+        .iter()
+        .flatten()
+        .map(|(name, value)| EnvVar {
+            name: name.clone(),
+            value: shell_double_quote_escape(value),
+        })
+        .collect::<Vec<_>>();
+    env.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let params = LauncherTemplateParams {
+        main_binary_name: shell_quote(&main_binary_name),
+        interpreter: path_word(&shim.interpreter),
+        entrypoint: shim.entrypoint.as_deref().map(path_word),
+        args: shim.args.iter().flatten().map(|a| shell_quote(a)).collect(),
+        env,
+    };
+    let script = handlebars.render("launcher.sh", &params)?;
+
+    let main_binary = ctx.config.main_binary_mut()?;
+    let file_name = main_binary
+        .path
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(main_binary.path.clone()))?;
+    let path: PathBuf = ctx
+        .intermediates_path
+        .join(LAUNCHER_SHIM_DIR)
+        .join(file_name);
+
+    let mut file = util::create_file(&path)?;
+    file.write_all(script.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    }
+
+    main_binary.path = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::LauncherShim, Config};
+
+    #[cfg(unix)]
+    #[test]
+    fn it_installs_the_shim_as_the_main_binary() {
+        use crate::config::Binary;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.out_dir = dir.path().join("out");
+        config.binaries = vec![Binary::new("my-app").main(true), Binary::new("helper")];
+        config.launcher_shim = Some(
+            LauncherShim::new("venv/bin/python3")
+                .entrypoint("app/main.py")
+                .args(["-I"])
+                .env([
+                    ("GREETING", "it's \"hi\""),
+                    ("APP_DATA", "$RESOURCES_DIR/data"),
+                ]),
+        );
+        config.validate_launcher_shim().unwrap();
+
+        let mut ctx = Context::new(&config).unwrap();
+        install(&mut ctx).unwrap();
+        let shim = ctx.config.binary_path(ctx.config.main_binary().unwrap());
+        assert_eq!(
+            shim,
+            ctx.intermediates_path
+                .join(LAUNCHER_SHIM_DIR)
+                .join("my-app")
+        );
+        assert_eq!(ctx.config.main_binary_name().unwrap(), "my-app");
+        let mode = fs::metadata(&shim).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        let script = fs::read_to_string(&shim).unwrap();
+        assert!(script.starts_with("#!/bin/sh\n"), "{script}");
+        assert!(
+            script.contains(
+                "export APP_DATA=\"$RESOURCES_DIR/data\"\n\
+                 export GREETING=\"it's \\\"hi\\\"\"\n"
+            ),
+            "{script}"
+        );
+        assert!(
+            script.contains(
+                "exec \"$RESOURCES_DIR/\"'venv/bin/python3' '-I' \"$RESOURCES_DIR/\"'app/main.py' \"$@\"\n"
+            ),
+            "{script}"
+        );
+    }
+
+    #[test]
+    fn it_validates_the_launcher_shim() {
+        let mut config = Config::default();
+        config.target_triple = Some("x86_64-unknown-linux-gnu".into());
+        config.launcher_shim = Some(LauncherShim::new("../python3"));
+        assert!(matches!(
+            config.validate_launcher_shim(),
+            Err(Error::InvalidLauncherShim(_))
+        ));
+
+        config.launcher_shim =
+            Some(LauncherShim::new("venv/bin/python3").env([("1_INVALID", "value")]));
+        assert!(matches!(
+            config.validate_launcher_shim(),
+            Err(Error::InvalidLauncherShim(_))
+        ));
+
+        config.launcher_shim = Some(LauncherShim::new("venv/bin/python3").entrypoint("main.py"));
+        config.validate_launcher_shim().unwrap();
+
+        config.target_triple = Some("x86_64-pc-windows-msvc".into());
+        assert!(matches!(
+            config.validate_launcher_shim(),
+            Err(Error::InvalidLauncherShim(_))
+        ));
+    }
+}
@@ -2,12 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use serde::Serialize;
 use url::Url;
 
-use crate::{config, shell::CommandExt, util, Config, PackageFormat};
+use crate::{
+    cancellation::{self, CancellationGuard, CancellationToken},
+    checksum, config,
+    shell::CommandExt,
+    timestamp,
+    util::{self, PathExt},
+    Config, Error, PackageFormat,
+};
 
 use self::context::Context;
 
@@ -30,6 +42,7 @@ mod appimage;
 mod deb;
 #[cfg(target_os = "macos")]
 mod dmg;
+mod innosetup;
 mod nsis;
 #[cfg(any(
     target_os = "linux",
@@ -39,10 +52,29 @@ mod nsis;
     target_os = "openbsd"
 ))]
 mod pacman;
+#[cfg(target_os = "macos")]
+mod pkg;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod shar;
 #[cfg(windows)]
 mod wix;
+mod zip;
 
+mod binary_version;
 mod context;
+mod icons;
+mod launcher_shim;
+mod naming;
+mod shared_libraries;
+mod tools;
+mod validate;
+mod version_info;
 
 /// Generated Package metadata.
 #[derive(Debug, Clone)]
@@ -54,6 +86,17 @@ pub struct PackageOutput {
     pub paths: Vec<PathBuf>,
     /// Package summary for `latest.json`
     pub summary: Option<PackageOutputSummary>,
+    /// Time spent building this package.
+    pub duration: Duration,
+    /// Whether [`sign_outputs`](crate::sign_outputs) generated a signature for this package.
+    pub signed: bool,
+    /// The [`Config::build_id`] stamped into the package, shared by all the packages of a packaging run.
+    pub build_id: Option<String>,
+    /// The versions of the external tools and the `sha256:<digest>` of the downloaded runtime files
+    /// used to build this package, keyed by their name, see [`Config::pinned_tools`].
+    pub tools: BTreeMap<String, String>,
+    /// The software bill of materials written next to the packages, see [`Config::sbom`].
+    pub sbom: Option<PathBuf>,
 }
 
 impl PackageOutput {
@@ -66,6 +109,11 @@ impl PackageOutput {
             format,
             paths,
             summary: None,
+            duration: Duration::ZERO,
+            signed: false,
+            build_id: None,
+            tools: BTreeMap::new(),
+            sbom: None,
         }
     }
 }
@@ -82,11 +130,128 @@ pub struct PackageOutputSummary {
     /// Target triple for this package
     #[serde(skip)]
     pub platform: String,
+    /// Release notes of this platform, overriding the release notes of the update manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// BLAKE3 digest of the package, set by [`hash_outputs_blake3`](crate::hash_outputs_blake3)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blake3: Option<String>,
+    /// Deltas from the artifacts of previous versions, set by [`delta_outputs`](crate::delta::delta_outputs)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deltas: Vec<crate::delta::DeltaSummary>,
 }
 
 /// Package an app using the specified config.
+///
+/// The relative paths of the config are resolved against [`Config::project_root`] if set,
+/// or against the current directory.
 #[tracing::instrument(level = "trace", skip(config))]
 pub fn package(config: &Config) -> crate::Result<Vec<PackageOutput>> {
+    package_inner(&config.with_project_root()?)
+}
+
+/// Package an app using the specified config, stopping early with [`Error::Cancelled`]
+/// when `token` is cancelled and removing the intermediate files of the run.
+///
+/// See [`CancellationToken`] for when the cancellation is checked.
+#[tracing::instrument(level = "trace", skip(config))]
+pub fn package_with_cancellation(
+    config: &Config,
+    token: &CancellationToken,
+) -> crate::Result<Vec<PackageOutput>> {
+    let config = &config.with_project_root()?;
+    let result = {
+        let _guard = CancellationGuard::new(token);
+        package_inner(config)
+    };
+
+    if token.is_cancelled() {
+        tracing::info!("Packaging cancelled");
+        let intermediates_path = config.out_dir().join(".cargo-packager");
+        if intermediates_path.exists() {
+            std::fs::remove_dir_all(&intermediates_path)
+                .map_err(|e| Error::IoWithPath(intermediates_path, e))?;
+        }
+        return Err(Error::Cancelled);
+    }
+
+    result
+}
+
+/// Whether `format` can be built on the current operating system.
+fn is_supported_on_host(format: PackageFormat) -> bool {
+    let linux_like = cfg!(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ));
+    match format {
+        PackageFormat::App
+        | PackageFormat::Nsis
+        | PackageFormat::InnoSetup
+        | PackageFormat::Zip => true,
+        PackageFormat::Dmg | PackageFormat::Pkg => cfg!(target_os = "macos"),
+        PackageFormat::Wix => cfg!(target_os = "windows"),
+        PackageFormat::Deb
+        | PackageFormat::AppImage
+        | PackageFormat::Pacman
+        | PackageFormat::Shar => linux_like,
+        _ => false,
+    }
+}
+
+/// Returns the `formats` that can be built on the current operating system,
+/// warning about the other ones when `skip_unsupported` is `true` and failing otherwise.
+fn supported_formats(
+    formats: Vec<PackageFormat>,
+    skip_unsupported: bool,
+) -> crate::Result<Vec<PackageFormat>> {
+    let host_os = std::env::consts::OS;
+    let mut supported = Vec::with_capacity(formats.len());
+    for format in formats {
+        if is_supported_on_host(format) {
+            supported.push(format);
+        } else if skip_unsupported {
+            tracing::warn!(
+                "Skipping `{}`, this package format can't be built on {host_os}",
+                format.short_name()
+            );
+        } else {
+            return Err(Error::FormatUnsupportedOnHost {
+                format: format.short_name(),
+                host_os,
+            });
+        }
+    }
+    Ok(supported)
+}
+
+/// Sorts `formats` in the order they are built, by priority then by their position in `format_order`,
+/// keeping the app bundle before the DMG and PKG packages.
+fn order_formats(formats: &mut [PackageFormat], format_order: Option<&[PackageFormat]>) {
+    formats.sort_by_key(|f| f.priority());
+    let Some(order) = format_order else {
+        return;
+    };
+    formats.sort_by_key(|f| order.iter().position(|o| o == f).unwrap_or(order.len()));
+
+    // the DMG and PKG packages need the app bundle
+    if let Some(app) = formats.iter().position(|f| *f == PackageFormat::App) {
+        if let Some(first) = formats[..app]
+            .iter()
+            .position(|f| matches!(f, PackageFormat::Dmg | PackageFormat::Pkg))
+        {
+            formats[first..=app].rotate_right(1);
+        }
+    }
+}
+
+/// Returns the [`Config::formats`] to build, sorted by [`order_formats`],
+/// with [`PackageFormat::Default`] and [`PackageFormat::All`] expanded
+/// and the formats unsupported on the current operating system handled by [`supported_formats`].
+pub(crate) fn resolve_formats(config: &Config) -> crate::Result<Vec<PackageFormat>> {
     let mut formats = config
         .formats
         .clone()
@@ -104,7 +269,143 @@ pub fn package(config: &Config) -> crate::Result<Vec<PackageOutput>> {
         formats = PackageFormat::platform_all().to_vec();
     }
 
-    formats.sort_by_key(|f| f.priority());
+    order_formats(&mut formats, config.format_order.as_deref());
+
+    supported_formats(formats, config.skip_unsupported_formats)
+}
+
+/// The rough ratio between the size of a package and the size of its content.
+fn compression_ratio(format: PackageFormat) -> f64 {
+    match format {
+        // an uncompressed directory
+        PackageFormat::App => 1.0,
+        // LZMA
+        PackageFormat::Nsis => 0.4,
+        // gzip, base64-encoded in the script
+        PackageFormat::Shar => 0.5 * 4.0 / 3.0,
+        // zlib, deflate, MSZIP, gzip or squashfs
+        _ => 0.5,
+    }
+}
+
+/// Estimates the size in bytes of the package of each format built from `config`,
+/// without building them.
+///
+/// The estimate is the total size of the [`Config::binaries`], [`Config::external_binaries`]
+/// and [`Config::resources`] with a rough compression ratio applied for the compressed formats,
+/// so it only gives the order of magnitude of the package sizes,
+/// for example to provision the disk of CI runners.
+///
+/// The binaries must already be built.
+pub fn estimate_output_size(config: &Config) -> crate::Result<HashMap<PackageFormat, u64>> {
+    let windows = config.target_triple().contains("windows");
+    let mut paths = Vec::new();
+    for binary in &config.binaries {
+        let path = config.binary_path(binary);
+        paths.push(if windows {
+            path.with_extension("exe")
+        } else {
+            path
+        });
+    }
+    paths.extend(config.external_binaries_paths()?);
+    paths.extend(
+        config
+            .resources()?
+            .into_iter()
+            .chain(config.bundled_runtime()?)
+            .map(|r| r.src),
+    );
+
+    let mut content_size = 0;
+    for path in paths {
+        content_size += fs::metadata(&path)
+            .map_err(|e| Error::IoWithPath(path, e))?
+            .len();
+    }
+
+    Ok(resolve_formats(config)?
+        .into_iter()
+        .map(|format| {
+            let size = content_size as f64 * compression_ratio(format);
+            (format, size as u64)
+        })
+        .collect())
+}
+
+/// Returns the paths of the files written by packaging `config` for each format, without building anything.
+///
+/// The paths are resolved with the same naming as the package builders,
+/// in [`Config::out_dir`] or the subdirectories of [`Config::output_layout`].
+/// When [`Config::signing`] is set, the [`PackageFormat::App`] archive and the signatures,
+/// `.sha256` digests and timestamps written by [`sign_outputs`](crate::sign_outputs) are included.
+pub fn output_paths(config: &Config) -> crate::Result<HashMap<PackageFormat, Vec<PathBuf>>> {
+    let config = config.with_project_root()?.resolved()?;
+    let layout = config.output_layout.unwrap_or_default();
+
+    let mut outputs = HashMap::new();
+    for &format in config.formats.iter().flatten() {
+        let out_dir = match layout.subdirectory(format) {
+            Some(subdirectory) => config.out_dir.join(subdirectory),
+            None => config.out_dir.clone(),
+        };
+        let mut paths = naming::package_paths(&config, format, &out_dir)?;
+
+        if let Some(signing) = &config.signing {
+            if format == PackageFormat::App {
+                let archives = paths
+                    .iter()
+                    .map(|p| p.with_additional_extension("tar.gz"))
+                    .collect::<Vec<_>>();
+                paths.extend(archives);
+            }
+
+            let hash_only = signing
+                .hash_only_formats
+                .iter()
+                .flatten()
+                .any(|f| *f == format);
+            let mut signatures = Vec::new();
+            for path in paths.iter().filter(|p| signing.should_sign(format, p)) {
+                if hash_only {
+                    signatures.push(path.with_additional_extension(checksum::SHA256_EXTENSION));
+                    continue;
+                }
+
+                let sig_files = match &signing.algorithms {
+                    Some(algorithms) => {
+                        let mut sig_files = Vec::new();
+                        for algorithm in algorithms {
+                            let sig_file = algorithm.signature_path(path);
+                            if !sig_files.contains(&sig_file) {
+                                sig_files.push(sig_file);
+                            }
+                        }
+                        sig_files
+                    }
+                    None => vec![path.with_additional_extension("sig")],
+                };
+                if signing.timestamp_url.is_some() {
+                    signatures.extend(sig_files.iter().map(|s| timestamp::timestamp_path(s)));
+                }
+                signatures.extend(sig_files);
+            }
+            paths.extend(signatures);
+        }
+
+        outputs.insert(format, paths);
+    }
+
+    Ok(outputs)
+}
+
+fn package_inner(config: &Config) -> crate::Result<Vec<PackageOutput>> {
+    cancellation::check()?;
+
+    let formats = resolve_formats(config)?;
+    if formats.is_empty() {
+        return Ok(Vec::new());
+    }
 
     let formats_comma_separated = formats
         .iter()
@@ -114,113 +415,477 @@ pub fn package(config: &Config) -> crate::Result<Vec<PackageOutput>> {
 
     run_before_packaging_command_hook(config, &formats_comma_separated)?;
 
-    let ctx = Context::new(config)?;
+    tracing::debug!("Validating icons");
+    icons::validate(config, &formats)?;
+
+    if formats.iter().any(|f| {
+        matches!(
+            f,
+            PackageFormat::App | PackageFormat::Dmg | PackageFormat::Pkg
+        )
+    }) {
+        config.bundle_identifier()?;
+    }
+    if formats.contains(&PackageFormat::Pkg) {
+        config.pkg_install_location()?;
+        config.pkg_scripts()?;
+    }
+    if formats.contains(&PackageFormat::Wix) {
+        config.wix_manufacturer()?;
+        config.wix_install_dir_name()?;
+        config.wix_bitmaps()?;
+    }
+    if formats.contains(&PackageFormat::Nsis) {
+        config.nsis_publisher()?;
+        config.nsis_compression()?;
+        config.nsis_custom_pages()?;
+    }
+    if formats.contains(&PackageFormat::Wix) || formats.contains(&PackageFormat::Nsis) {
+        config.windows_shortcuts()?;
+    }
+    if formats.contains(&PackageFormat::Deb) {
+        config.deb_url_fields()?;
+    }
+    config.validate_install_path_lengths(&formats)?;
+
+    config.validate_build_id()?;
+    config.validate_keywords()?;
+    config.validate_pinned_tools()?;
+    config.validate_staging_dir()?;
+    config.validate_release_notes()?;
+    config.validate_launcher_shim()?;
+    config.validate_updater_endpoint()?;
+    config.validate_slug()?;
+    config.default_modes()?;
+    shared_libraries::check(config, &formats)?;
+    binary_version::check(config)?;
+
+    if config.clean_outputs {
+        clean_outputs(config, &formats)?;
+    }
+
+    let mut ctx = Context::new(config)?;
+    let build_id = ctx
+        .config
+        .build_id
+        .get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+    tracing::debug!("Packaging build {build_id}");
+
+    let sbom = match ctx.config.sbom.clone() {
+        Some(sbom_config) => {
+            tracing::debug!("Generating the software bill of materials");
+            let path = crate::sbom::write_sbom(&ctx.config, &sbom_config, &ctx.config.out_dir())?;
+            if sbom_config.embed {
+                ctx.config
+                    .resources
+                    .get_or_insert_with(Vec::new)
+                    .push(config::Resource::Mapped {
+                        src: glob::Pattern::escape(&path.to_string_lossy()),
+                        target: format!("sbom.{}", sbom_config.format.extension()).into(),
+                        mode: None,
+                        optional: false,
+                    });
+            }
+            Some(path)
+        }
+        None => None,
+    };
+    embed_updater_endpoint(&mut ctx)?;
+    launcher_shim::install(&mut ctx)?;
     tracing::trace!(ctx = ?ctx);
 
     let mut packages = Vec::new();
+    let mut errors = Vec::new();
     for &format in &formats {
-        run_before_each_packaging_command_hook(
-            config,
-            &formats_comma_separated,
-            format.short_name(),
-        )?;
-
-        #[allow(unused_mut)]
-        let mut produce_summary: bool = true;
-
-        let paths = match format {
-            PackageFormat::App => app::package(&ctx),
-            #[cfg(target_os = "macos")]
-            PackageFormat::Dmg => {
-                produce_summary = false;
-
-                // PackageFormat::App is required for the DMG bundle
-                if !packages
-                    .iter()
-                    .any(|b: &PackageOutput| b.format == PackageFormat::App)
-                {
-                    let paths = app::package(&ctx)?;
-                    packages.push(PackageOutput {
-                        format: PackageFormat::App,
-                        summary: None,
-                        paths,
-                    });
-                }
-                dmg::package(&ctx)
+        cancellation::check()?;
+
+        match package_format(&ctx, format, &formats_comma_separated, &mut packages) {
+            Ok(outputs) => packages.extend(outputs),
+            Err(Error::Cancelled) => return Err(Error::Cancelled),
+            Err(e) if config.fail_fast => {
+                #[cfg(target_os = "macos")]
+                clean_app_bundle(&formats, &mut packages)?;
+                return Err(e);
             }
-            #[cfg(target_os = "windows")]
-            PackageFormat::Wix => wix::package(&ctx),
-            PackageFormat::Nsis => nsis::package(&ctx),
-            #[cfg(any(
-                target_os = "linux",
-                target_os = "dragonfly",
-                target_os = "freebsd",
-                target_os = "netbsd",
-                target_os = "openbsd"
-            ))]
-            PackageFormat::Deb => deb::package(&ctx),
-            #[cfg(any(
-                target_os = "linux",
-                target_os = "dragonfly",
-                target_os = "freebsd",
-                target_os = "netbsd",
-                target_os = "openbsd"
-            ))]
-            PackageFormat::AppImage => appimage::package(&ctx),
-            #[cfg(any(
-                target_os = "linux",
-                target_os = "dragonfly",
-                target_os = "freebsd",
-                target_os = "netbsd",
-                target_os = "openbsd"
-            ))]
-            PackageFormat::Pacman => pacman::package(&ctx),
-
-            _ => {
-                tracing::warn!("ignoring {}", format.short_name());
-                continue;
+            Err(e) => {
+                tracing::error!("Failed to package {}: {e}", format.short_name());
+                errors.push((format, e));
             }
-        }?;
+        }
+    }
 
-        let summary = produce_summary
-            .then(|| build_package_summary(&paths, format, config))
-            .transpose()?
-            .flatten();
+    #[cfg(target_os = "macos")]
+    clean_app_bundle(&formats, &mut packages)?;
 
-        packages.push(PackageOutput {
-            format,
-            summary,
-            paths,
-        });
+    for package in &mut packages {
+        package.sbom.clone_from(&sbom);
     }
 
-    #[cfg(target_os = "macos")]
+    apply_output_layout(config, &mut packages)?;
+
+    if errors.is_empty() {
+        return Ok(packages);
+    }
+    if packages.is_empty() && errors.len() == 1 {
+        return Err(errors.remove(0).1);
+    }
+    Err(Error::PackagingFailed { packages, errors })
+}
+
+/// Removes the app bundle built for the DMG and PKG packages from `packages` and from the disk
+/// when [`PackageFormat::App`] isn't one of the `formats`.
+#[cfg(target_os = "macos")]
+fn clean_app_bundle(
+    formats: &[PackageFormat],
+    packages: &mut Vec<PackageOutput>,
+) -> crate::Result<()> {
+    if formats.contains(&PackageFormat::App) {
+        return Ok(());
+    }
+    if let Some(app_bundle_paths) = packages
+        .iter()
+        .position(|b| b.format == PackageFormat::App)
+        .map(|i| packages.remove(i))
+        .map(|b| b.paths)
     {
-        // Clean up .app if only building dmg
-        if !formats.contains(&PackageFormat::App) {
-            if let Some(app_bundle_paths) = packages
+        for p in &app_bundle_paths {
+            tracing::debug!("Cleaning {}", p.display());
+            match p.is_dir() {
+                true => fs::remove_dir_all(p).map_err(|e| Error::IoWithPath(p.clone(), e))?,
+                false => fs::remove_file(p).map_err(|e| Error::IoWithPath(p.clone(), e))?,
+            };
+        }
+    }
+    Ok(())
+}
+
+/// Writes the [`Config::updater_endpoint`] into [`config::UPDATER_ENDPOINT_FILE_NAME`]
+/// in the intermediates directory and adds it to the resources of the packages.
+fn embed_updater_endpoint(ctx: &mut Context) -> crate::Result<()> {
+    let Some(endpoint) = &ctx.config.updater_endpoint else {
+        return Ok(());
+    };
+    tracing::debug!("Writing the updater endpoint");
+    let path = ctx
+        .intermediates_path
+        .join(config::UPDATER_ENDPOINT_FILE_NAME);
+    let contents = serde_json::to_vec_pretty(&serde_json::json!({
+        "endpoint": endpoint,
+        "version": ctx.config.version,
+    }))?;
+    fs::write(&path, contents).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    ctx.config
+        .resources
+        .get_or_insert_with(Vec::new)
+        .push(config::Resource::Mapped {
+            src: glob::Pattern::escape(&path.to_string_lossy()),
+            target: config::UPDATER_ENDPOINT_FILE_NAME.into(),
+            mode: None,
+            optional: false,
+        });
+    Ok(())
+}
+
+/// Packages `format`, returning no package when it is ignored on this host.
+///
+/// `packages` are the packages built before. The app bundle built for the DMG and PKG packages
+/// is added to them before the DMG or PKG is built, so it is reported and cleaned up when that fails.
+fn package_format(
+    ctx: &Context,
+    format: PackageFormat,
+    formats_comma_separated: &str,
+    #[cfg_attr(not(target_os = "macos"), allow(unused_variables, clippy::ptr_arg))]
+    packages: &mut Vec<PackageOutput>,
+) -> crate::Result<Vec<PackageOutput>> {
+    let config = &ctx.config;
+
+    run_before_each_packaging_command_hook(config, formats_comma_separated, format.short_name())?;
+    ctx.tools.take();
+
+    #[allow(unused_mut)]
+    let mut produce_summary: bool = true;
+
+    #[allow(unused_mut)]
+    let mut started = Instant::now();
+
+    let paths = match format {
+        PackageFormat::App => app::package(ctx),
+        #[cfg(target_os = "macos")]
+        PackageFormat::Dmg | PackageFormat::Pkg => {
+            produce_summary = false;
+
+            // PackageFormat::App is required for the DMG and PKG packages
+            if !packages
                 .iter()
-                .position(|b| b.format == PackageFormat::App)
-                .map(|i| packages.remove(i))
-                .map(|b| b.paths)
+                .any(|b: &PackageOutput| b.format == PackageFormat::App)
             {
-                for p in &app_bundle_paths {
-                    use crate::Error;
-                    use std::fs;
-
-                    tracing::debug!("Cleaning {}", p.display());
-                    match p.is_dir() {
-                        true => {
-                            fs::remove_dir_all(p).map_err(|e| Error::IoWithPath(p.clone(), e))?
-                        }
-                        false => fs::remove_file(p).map_err(|e| Error::IoWithPath(p.clone(), e))?,
-                    };
+                let paths = app::package(ctx)?;
+                packages.push(PackageOutput {
+                    format: PackageFormat::App,
+                    summary: None,
+                    paths,
+                    duration: started.elapsed(),
+                    signed: false,
+                    build_id: config.build_id.clone(),
+                    tools: ctx.tools.take(),
+                    sbom: None,
+                });
+                // don't account the app bundle to this package
+                started = Instant::now();
+            }
+            if format == PackageFormat::Dmg {
+                dmg::package(ctx)
+            } else {
+                pkg::package(ctx)
+            }
+        }
+        #[cfg(target_os = "windows")]
+        PackageFormat::Wix => wix::package(ctx),
+        PackageFormat::Nsis => nsis::package(ctx),
+        PackageFormat::InnoSetup => {
+            // the updater doesn't support Inno Setup installers
+            produce_summary = false;
+            innosetup::package(ctx)
+        }
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        PackageFormat::Deb => deb::package(ctx),
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        PackageFormat::AppImage => appimage::package(ctx),
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        PackageFormat::Pacman => pacman::package(ctx),
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        PackageFormat::Shar => {
+            // the updater doesn't support installer scripts
+            produce_summary = false;
+            shar::package(ctx)
+        }
+        PackageFormat::Zip => {
+            // the updater doesn't support portable archives
+            produce_summary = false;
+            zip::package(ctx)
+        }
+
+        _ => {
+            tracing::warn!("ignoring {}", format.short_name());
+            return Ok(Vec::new());
+        }
+    }?;
+    validate::artifacts(config, format, &paths)?;
+    check_artifact_sizes(config, format, &paths)?;
+    let duration = started.elapsed();
+    tracing::debug!(
+        "Built {} in {:.2}s",
+        format.short_name(),
+        duration.as_secs_f64()
+    );
+
+    let summary = produce_summary
+        .then(|| build_package_summary(&paths, format, config))
+        .transpose()?
+        .flatten();
+
+    Ok(vec![PackageOutput {
+        format,
+        summary,
+        paths,
+        duration,
+        signed: false,
+        build_id: config.build_id.clone(),
+        tools: ctx.tools.take(),
+        sbom: None,
+    }])
+}
+
+/// Moves the packages placed in [`Config::out_dir`] into the subdirectories of [`Config::output_layout`],
+/// so signatures and checksums are later written next to them.
+fn apply_output_layout(config: &Config, packages: &mut [PackageOutput]) -> crate::Result<()> {
+    let layout = config.output_layout.unwrap_or_default();
+    let out_dir = config.out_dir();
+
+    for package in packages {
+        let Some(subdirectory) = layout.subdirectory(package.format) else {
+            continue;
+        };
+        let dest_dir = out_dir.join(subdirectory);
+
+        for path in &mut package.paths {
+            let in_out_dir = path
+                .parent()
+                .is_some_and(|p| dunce::canonicalize(p).unwrap_or_else(|_| p.into()) == out_dir);
+            if !in_out_dir {
+                continue;
+            }
+
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| Error::FailedToExtractFilename(path.clone()))?;
+            let dest = dest_dir.join(file_name);
+            fs::create_dir_all(&dest_dir).map_err(|e| Error::IoWithPath(dest_dir.clone(), e))?;
+            if dest.is_dir() {
+                fs::remove_dir_all(&dest).map_err(|e| Error::IoWithPath(dest.clone(), e))?;
+            } else if dest.exists() {
+                fs::remove_file(&dest).map_err(|e| Error::IoWithPath(dest.clone(), e))?;
+            }
+
+            tracing::debug!("Moving {} to {}", path.display(), dest.display());
+            fs::rename(&path, &dest)
+                .map_err(|e| Error::RenameFile(path.clone(), dest.clone(), e))?;
+            *path = dest;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the prefix and the suffix of the file names of the packages of `format`,
+/// ignoring the version and architecture in between.
+fn output_file_pattern(
+    config: &Config,
+    format: PackageFormat,
+) -> crate::Result<Option<(String, &'static str)>> {
+    let product_name = &config.product_name;
+    let slug = naming::package_name(config, format)?;
+    Ok(Some(match format {
+        PackageFormat::App => (format!("{product_name}.app"), ""),
+        PackageFormat::Dmg => (format!("{slug}_"), ".dmg"),
+        PackageFormat::Pkg => (format!("{slug}_"), ".pkg"),
+        PackageFormat::Wix => (format!("{slug}_"), ".msi"),
+        PackageFormat::Nsis => (format!("{slug}_"), "-setup.exe"),
+        PackageFormat::InnoSetup => (format!("{slug}_"), "-innosetup"),
+        PackageFormat::Deb => (format!("{slug}_"), ".deb"),
+        PackageFormat::AppImage => (format!("{slug}_"), ".AppImage"),
+        PackageFormat::Pacman => (format!("{slug}_"), ".tar.gz"),
+        PackageFormat::Shar => (format!("{slug}_"), ".sh"),
+        PackageFormat::Zip => (format!("{slug}_"), ".zip"),
+        _ => return Ok(None),
+    }))
+}
+
+/// Whether `file_name` is a package matching the pattern of [`output_file_pattern`],
+/// or a file written next to it like `<package>.sig`.
+fn is_output_file(file_name: &str, prefix: &str, suffix: &str) -> bool {
+    let Some(rest) = file_name.strip_prefix(prefix) else {
+        return false;
+    };
+    let is_package_end = |end: &str| end.is_empty() || end.starts_with('.');
+    if suffix.is_empty() {
+        return is_package_end(rest);
+    }
+    rest.match_indices(suffix)
+        .any(|(i, _)| is_package_end(&rest[i + suffix.len()..]))
+}
+
+/// Removes the outputs of previous runs for `formats`, see [`Config::clean_outputs`].
+fn clean_outputs(config: &Config, formats: &[PackageFormat]) -> crate::Result<()> {
+    let out_dir = config.out_dir();
+    let layout = config.output_layout.unwrap_or_default();
+
+    for &format in formats {
+        let Some((prefix, suffix)) = output_file_pattern(config, format)? else {
+            continue;
+        };
+
+        let mut dirs = vec![out_dir.clone()];
+        if let Some(subdirectory) = layout.subdirectory(format) {
+            dirs.push(out_dir.join(subdirectory));
+        }
+
+        for dir in dirs {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name();
+                if !is_output_file(&file_name.to_string_lossy(), &prefix, suffix) {
+                    continue;
+                }
+
+                let path = entry.path();
+                tracing::debug!("Removing previous output {}", path.display());
+                if path.is_dir() {
+                    fs::remove_dir_all(&path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+                } else {
+                    fs::remove_file(&path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
                 }
             }
         }
     }
 
-    Ok(packages)
+    Ok(())
+}
+
+/// Returns the modes of the files and directories of `dir`, keyed by their path relative to it,
+/// with the `explicit` modes of the resources taking precedence over the [`Config::default_file_mode`]
+/// and [`Config::default_dir_mode`]. Symlinks are skipped.
+#[cfg(unix)]
+pub(crate) fn file_modes(
+    config: &Config,
+    dir: &std::path::Path,
+    mut explicit: HashMap<PathBuf, u32>,
+) -> crate::Result<HashMap<PathBuf, u32>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let defaults = config.default_modes()?;
+    if defaults == config::DefaultModes::default() {
+        return Ok(explicit);
+    }
+
+    for entry in walkdir::WalkDir::new(dir).min_depth(1) {
+        let entry = entry?;
+        if entry.file_type().is_symlink() {
+            continue;
+        }
+        let path = entry.path().strip_prefix(dir)?.to_path_buf();
+        if explicit.contains_key(&path) {
+            continue;
+        }
+        let executable = entry.metadata()?.permissions().mode() & 0o111 != 0;
+        if let Some(mode) = defaults.mode(entry.file_type().is_dir(), executable) {
+            explicit.insert(path, mode);
+        }
+    }
+
+    Ok(explicit)
+}
+
+/// Sets the permissions of the entries of `dir` to their `modes`, see [`file_modes`].
+#[cfg(unix)]
+pub(crate) fn apply_file_modes(
+    dir: &std::path::Path,
+    modes: &HashMap<PathBuf, u32>,
+) -> crate::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    for (path, mode) in modes {
+        let path = dir.join(path);
+        fs::set_permissions(&path, fs::Permissions::from_mode(*mode))
+            .map_err(|e| Error::IoWithPath(path, e))?;
+    }
+    Ok(())
 }
 
 fn run_before_each_packaging_command_hook(
@@ -307,8 +972,27 @@ fn run_before_packaging_command_hook(
     Ok(())
 }
 
+/// Returns the `<os>-<arch>` platform string of the updater for the target of `config`.
+pub(crate) fn updater_platform(config: &Config) -> Option<String> {
+    let target_triple = config.target_triple();
+    // See the updater crate for which particular target strings are required.
+    let target_arch = if target_triple.starts_with("x86_64") {
+        "x86_64"
+    } else if target_triple.starts_with('i') {
+        "i686"
+    } else if target_triple.starts_with("arm") {
+        "armv7"
+    } else if target_triple.starts_with("aarch64") {
+        "aarch64"
+    } else {
+        return None;
+    };
+    let target_os = config.target_os()?;
+    Some(format!("{target_os}-{target_arch}"))
+}
+
 fn build_package_summary(
-    paths: &Vec<PathBuf>,
+    paths: &[PathBuf],
     format: PackageFormat,
     config: &Config,
 ) -> crate::Result<Option<PackageOutputSummary>> {
@@ -319,47 +1003,40 @@ fn build_package_summary(
             .filter_map(|path| path.file_name().and_then(|f| f.to_str().map(Into::into)))
             .collect::<Vec<String>>();
 
-        if paths.len() == 1 {
-            let artefact = paths.first().unwrap();
+        let artefact = match (format, paths.as_slice()) {
+            (_, [artefact]) => Some(artefact),
+            // the other AppImage outputs, like the `.zsync` file, are not updater artefacts
+            (PackageFormat::AppImage, [artefact, ..]) => Some(artefact),
+            _ => None,
+        };
 
+        if let Some(artefact) = artefact {
             let url: Url = url
                 .to_string()
                 // url::Url automatically url-encodes the path components
                 .replace("%7B%7Bversion%7D%7D", &config.version)
-                .replace("%7B%7Bartefact%7D%7D", &artefact)
+                .replace("%7B%7Bartefact%7D%7D", artefact)
                 // but not query parameters
                 .replace("{{version}}", &config.version)
-                .replace("{{artefact}}", &artefact)
+                .replace("{{artefact}}", artefact)
                 .parse()?;
 
-            let target_triple = config.target_triple();
-            // See the updater crate for which particular target strings are required.
-            let target_arch = if target_triple.starts_with("x86_64") {
-                Some("x86_64")
-            } else if target_triple.starts_with('i') {
-                Some("i686")
-            } else if target_triple.starts_with("arm") {
-                Some("armv7")
-            } else if target_triple.starts_with("aarch64") {
-                Some("aarch64")
-            } else {
-                None
-            };
-            let target_os = config.target_os();
-            match (target_arch, target_os) {
-                (Some(target_arch), Some(target_os)) => {
-                    let platform = format!("{target_os}-{target_arch}");
-
+            match updater_platform(config) {
+                Some(platform) => {
                     Some(PackageOutputSummary {
                         url,
                         format,
                         platform,
                         // Signature will be set later
                         signature: None,
+                        notes: None,
+                        blake3: None,
+                        deltas: Vec::new(),
                     })
                 }
                 _ => {
-                    tracing::warn!(target_triple =?config.target_triple(), ?target_arch, ?target_os, "A package could not be summarized in latest.json because the platform string could not be determined from {target_triple}.");
+                    let target_triple = config.target_triple();
+                    tracing::warn!("A package could not be summarized in latest.json because the platform string could not be determined from {target_triple}.");
                     None
                 }
             }
@@ -373,3 +1050,665 @@ fn build_package_summary(
         None
     })
 }
+
+/// Checks the size of the package files against the configured maximum artifact size.
+fn check_artifact_sizes(
+    config: &Config,
+    format: PackageFormat,
+    paths: &[PathBuf],
+) -> crate::Result<()> {
+    let limit = config
+        .max_artifact_size_per_format
+        .as_ref()
+        .and_then(|sizes| sizes.get(&format))
+        .or(config.max_artifact_size.as_ref());
+    let Some(&limit) = limit else {
+        return Ok(());
+    };
+
+    for path in paths {
+        let size = util::disk_size(path);
+        if size > limit {
+            return Err(Error::ArtifactTooLarge {
+                path: path.clone(),
+                size,
+                limit,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Binary, OutputLayout, Resource};
+
+    #[test]
+    fn it_bundles_the_updater_endpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("app"), "app").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.2.0".into();
+        config.target_triple = Some("x86_64-unknown-linux-gnu".into());
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.updater_endpoint = Some("https://releases.example.com/stable/latest.json".into());
+        config.validate_updater_endpoint().unwrap();
+
+        let mut ctx = Context::new(&config).unwrap();
+        embed_updater_endpoint(&mut ctx).unwrap();
+        let zip_path = zip::package(&ctx).unwrap().remove(0);
+
+        let mut archive = ::zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        let endpoint: serde_json::Value =
+            serde_json::from_reader(archive.by_name(config::UPDATER_ENDPOINT_FILE_NAME).unwrap())
+                .unwrap();
+        assert_eq!(
+            endpoint,
+            serde_json::json!({
+                "endpoint": "https://releases.example.com/stable/latest.json",
+                "version": "1.2.0",
+            })
+        );
+
+        for endpoint in [
+            "releases.example.com/latest.json",
+            "ftp://example.com/latest.json",
+        ] {
+            config.updater_endpoint = Some(endpoint.into());
+            assert!(matches!(
+                config.validate_updater_endpoint(),
+                Err(Error::InvalidUpdaterEndpoint(e)) if e == endpoint
+            ));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_uses_the_same_slug_across_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("My_App"), "app").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App (Beta)".into();
+        config.version = "1.2.0".into();
+        config.target_triple = Some("x86_64-unknown-linux-gnu".into());
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("My_App").main(true)];
+        assert_eq!(config.slug().unwrap(), "my-app-beta");
+
+        let file_names = |config: &Config| {
+            let ctx = Context::new(config).unwrap();
+            [
+                zip::package(&ctx).unwrap().remove(0),
+                shar::package(&ctx).unwrap().remove(0),
+                deb::package(&ctx).unwrap().remove(0),
+            ]
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        };
+        assert_eq!(
+            file_names(&config),
+            [
+                "my-app-beta_1.2.0_x86_64.zip",
+                "my-app-beta_1.2.0_x86_64.sh",
+                "my-app-beta_1.2.0_amd64.deb",
+            ]
+        );
+        for format in [
+            PackageFormat::Dmg,
+            PackageFormat::Nsis,
+            PackageFormat::Wix,
+            PackageFormat::AppImage,
+            PackageFormat::Pacman,
+        ] {
+            let (prefix, _) = output_file_pattern(&config, format).unwrap().unwrap();
+            assert_eq!(prefix, "my-app-beta_", "{format:?}");
+        }
+
+        config.slug = Some("myapp".into());
+        config.validate_slug().unwrap();
+        assert_eq!(
+            file_names(&config),
+            [
+                "myapp_1.2.0_x86_64.zip",
+                "myapp_1.2.0_x86_64.sh",
+                "myapp_1.2.0_amd64.deb",
+            ]
+        );
+
+        // the package name of the deb and pacman packages takes precedence over the slug
+        config.deb = Some(config::DebianConfig::new().package_name("my-app-deb"));
+        config.pacman = Some(config::PacmanConfig::new().package_name("my-app-pacman"));
+        assert_eq!(
+            file_names(&config),
+            [
+                "myapp_1.2.0_x86_64.zip",
+                "myapp_1.2.0_x86_64.sh",
+                "my-app-deb_1.2.0_amd64.deb",
+            ]
+        );
+        let (prefix, _) = output_file_pattern(&config, PackageFormat::Pacman)
+            .unwrap()
+            .unwrap();
+        assert_eq!(prefix, "my-app-pacman_");
+        assert_eq!(
+            naming::package_file_name(&config, PackageFormat::Pacman).unwrap(),
+            "my-app-pacman_1.2.0_x86_64.tar.gz"
+        );
+        config.deb = None;
+        config.pacman = None;
+
+        // the slug falls back to the main binary name
+        config.slug = None;
+        config.product_name = "アプリ".into();
+        assert_eq!(config.slug().unwrap(), "my-app");
+
+        for slug in ["", "My App", "my--app", "-myapp", "my_app"] {
+            config.slug = Some(slug.into());
+            assert!(
+                matches!(config.validate_slug(), Err(Error::InvalidSlug(s)) if s == slug),
+                "{slug}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_checks_artifact_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("app.deb");
+        fs::write(&deb, [0; 100]).unwrap();
+        let app = dir.path().join("App.app");
+        fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        fs::write(app.join("Contents/MacOS/app"), [0; 60]).unwrap();
+        fs::write(app.join("Contents/Info.plist"), [0; 60]).unwrap();
+
+        let mut config = Config::default();
+        check_artifact_sizes(&config, PackageFormat::Deb, std::slice::from_ref(&deb)).unwrap();
+
+        config.max_artifact_size = Some(100);
+        check_artifact_sizes(&config, PackageFormat::Deb, std::slice::from_ref(&deb)).unwrap();
+        let err = check_artifact_sizes(&config, PackageFormat::App, std::slice::from_ref(&app))
+            .unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::ArtifactTooLarge { path, size: 120, limit: 100 } if path == &app
+            ),
+            "{err}"
+        );
+
+        config.max_artifact_size_per_format = Some(HashMap::from([
+            (PackageFormat::App, 200),
+            (PackageFormat::Deb, 10),
+        ]));
+        check_artifact_sizes(&config, PackageFormat::App, &[app]).unwrap();
+        assert!(matches!(
+            check_artifact_sizes(&config, PackageFormat::Deb, &[deb]),
+            Err(Error::ArtifactTooLarge {
+                size: 100,
+                limit: 10,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_checks_formats_supported_on_host() {
+        let unsupported = if cfg!(target_os = "macos") {
+            PackageFormat::Wix
+        } else {
+            PackageFormat::Dmg
+        };
+
+        let err = supported_formats(vec![PackageFormat::Nsis, unsupported], false).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::FormatUnsupportedOnHost { format, host_os }
+                    if format == unsupported.short_name() && host_os == std::env::consts::OS
+            ),
+            "{err}"
+        );
+        assert_eq!(
+            supported_formats(vec![PackageFormat::Nsis, unsupported], true).unwrap(),
+            [PackageFormat::Nsis]
+        );
+        for &format in PackageFormat::platform_all() {
+            assert!(is_supported_on_host(format), "{format}");
+        }
+
+        let config = Config::builder()
+            .formats([unsupported])
+            .skip_unsupported_formats(true);
+        assert!(package(config.config()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_estimates_output_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("app"), vec![0; 4000]).unwrap();
+        let resources_dir = dir.path().join("assets");
+        fs::create_dir_all(&resources_dir).unwrap();
+        fs::write(resources_dir.join("a.txt"), vec![0; 1000]).unwrap();
+        fs::write(resources_dir.join("b.txt"), vec![0; 1000]).unwrap();
+
+        let mut config = Config::default();
+        config.out_dir = dir.path().to_path_buf();
+        config.target_triple = Some("x86_64-unknown-linux-gnu".into());
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.resources = Some(vec![Resource::Single(
+            resources_dir.to_string_lossy().into_owned(),
+        )]);
+        config.formats = Some(vec![PackageFormat::App, PackageFormat::Nsis]);
+
+        let sizes = estimate_output_size(&config).unwrap();
+        assert_eq!(
+            sizes,
+            HashMap::from([(PackageFormat::App, 6000), (PackageFormat::Nsis, 2400)])
+        );
+
+        config.binaries.push(Binary::new("missing"));
+        assert!(matches!(
+            estimate_output_size(&config),
+            Err(Error::IoWithPath(path, _)) if path.ends_with("missing")
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_stamps_the_build_id_into_every_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.identifier = Some("com.example.app".into());
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.formats = Some(vec![PackageFormat::Deb, PackageFormat::Shar]);
+
+        // a random id shared by the packages of the run
+        let packages = package(&config).unwrap();
+        let build_id = packages[0].build_id.clone().unwrap();
+        uuid::Uuid::parse_str(&build_id).unwrap();
+        assert!(packages
+            .iter()
+            .all(|p| p.build_id.as_ref() == Some(&build_id)));
+        let manifest = crate::updater::build_update_manifest(
+            &config,
+            &packages,
+            &crate::updater::UpdateManifestConfig::new(),
+        )
+        .unwrap();
+        assert_eq!(manifest.build_id.as_ref(), Some(&build_id));
+
+        config.build_id = Some("3f2c9a1e".into());
+        package(&config).unwrap();
+        let control = walkdir::WalkDir::new(config.out_dir().join(".cargo-packager/deb"))
+            .into_iter()
+            .map(|e| e.unwrap().into_path())
+            .find(|p| p.ends_with("control/control"))
+            .unwrap();
+        let control = fs::read_to_string(control).unwrap();
+        assert!(control.contains("\nX-Build-Id: 3f2c9a1e\n"), "{control}");
+
+        let ctx = Context::new(&config).unwrap();
+        let app = app::package(&ctx).unwrap().remove(0);
+        let plist = plist::Value::from_file(app.join("Contents/Info.plist")).unwrap();
+        assert_eq!(
+            plist
+                .as_dictionary()
+                .and_then(|d| d.get("CargoPackagerBuildId"))
+                .and_then(|v| v.as_string()),
+            Some("3f2c9a1e")
+        );
+
+        config.build_id = Some("build 1".into());
+        assert!(matches!(
+            package(&config),
+            Err(Error::InvalidBuildId(id)) if id == "build 1"
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_builds_a_deb_from_a_staging_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh").unwrap();
+        let staging = dir.path().join("staging");
+        fs::create_dir_all(staging.join("share/assets")).unwrap();
+        fs::create_dir_all(staging.join("etc")).unwrap();
+        fs::write(staging.join("share/assets/logo.svg"), "<svg/>").unwrap();
+        fs::write(staging.join("etc/app.conf"), "key=value").unwrap();
+        let manifest = staging.join("manifest.json");
+        fs::write(&manifest, r#"{ "etc/app.conf": "/etc/app/app.conf" }"#).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.formats = Some(vec![PackageFormat::Deb]);
+        config.from_staging_dir =
+            Some(crate::config::StagingDir::new(&staging).manifest(&manifest));
+        package(&config).unwrap();
+
+        let data_dir = walkdir::WalkDir::new(config.out_dir().join(".cargo-packager/deb"))
+            .into_iter()
+            .map(|e| e.unwrap().into_path())
+            .find(|p| p.ends_with("data"))
+            .unwrap();
+        let resources = data_dir.join("usr/lib/app");
+        assert!(resources.join("share/assets/logo.svg").is_file());
+        assert_eq!(
+            fs::read_to_string(data_dir.join("etc/app/app.conf")).unwrap(),
+            "key=value"
+        );
+        assert!(!resources.join("etc").exists());
+        assert!(!resources.join("manifest.json").exists());
+
+        fs::write(&manifest, r#"{ "../app.conf": "/etc/app.conf" }"#).unwrap();
+        assert!(matches!(
+            package(&config),
+            Err(Error::UnsafeStagingPath(path)) if path == "../app.conf"
+        ));
+        fs::write(&manifest, r#"{ "etc/app.conf": "etc/app.conf" }"#).unwrap();
+        assert!(matches!(package(&config), Err(Error::UnsafeStagingPath(_))));
+
+        let empty = dir.path().join("empty");
+        fs::create_dir_all(empty.join("usr")).unwrap();
+        config.from_staging_dir = Some(crate::config::StagingDir::new(&empty));
+        assert!(matches!(
+            package(&config),
+            Err(Error::EmptyStagingDir(path)) if path == empty
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_keeps_packaging_after_a_format_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.formats = Some(vec![PackageFormat::Deb, PackageFormat::Shar]);
+        config.before_each_package_command = Some(config::HookCommand::Script(
+            "test \"$CARGO_PACKAGER_FORMAT\" != deb".into(),
+        ));
+
+        let Err(Error::PackagingFailed { packages, errors }) = package(&config) else {
+            panic!("expected the deb package to fail");
+        };
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].format, PackageFormat::Shar);
+        assert!(packages[0].paths[0].exists());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, PackageFormat::Deb);
+        assert!(matches!(errors[0].1, Error::HookCommandFailure(..)));
+
+        fs::remove_dir_all(config.out_dir()).unwrap();
+        config.fail_fast = true;
+        assert!(matches!(
+            package(&config),
+            Err(Error::HookCommandFailure(..))
+        ));
+        assert!(!packages[0].paths[0].exists());
+
+        // a single failed format is returned as is
+        config.fail_fast = false;
+        config.formats = Some(vec![PackageFormat::Deb]);
+        assert!(matches!(
+            package(&config),
+            Err(Error::HookCommandFailure(..))
+        ));
+    }
+
+    #[test]
+    fn it_orders_formats() {
+        use PackageFormat::*;
+
+        let mut formats = vec![Dmg, App, Pkg, Zip];
+        order_formats(&mut formats, None);
+        assert_eq!(formats, [App, Zip, Dmg, Pkg]);
+
+        let mut formats = vec![App, Deb, Dmg, AppImage, Zip];
+        order_formats(&mut formats, Some(&[Dmg, AppImage, App]));
+        assert_eq!(formats, [App, Dmg, AppImage, Deb, Zip]);
+
+        let mut formats = vec![Deb, Zip, Shar];
+        order_formats(&mut formats, Some(&[Shar, All]));
+        assert_eq!(formats, [Shar, Deb, Zip]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_builds_formats_in_the_configured_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh").unwrap();
+        let log = dir.path().join("formats.log");
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir);
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.formats = Some(vec![
+            PackageFormat::Deb,
+            PackageFormat::Shar,
+            PackageFormat::Zip,
+        ]);
+        config.format_order = Some(vec![PackageFormat::Zip, PackageFormat::Shar]);
+        config.before_each_package_command = Some(config::HookCommand::Script(format!(
+            "echo \"$CARGO_PACKAGER_FORMAT\" >> '{}'",
+            log.display()
+        )));
+
+        let packages = package(&config).unwrap();
+        let formats = packages.iter().map(|p| p.format).collect::<Vec<_>>();
+        assert_eq!(
+            formats,
+            [PackageFormat::Zip, PackageFormat::Shar, PackageFormat::Deb]
+        );
+        assert_eq!(fs::read_to_string(&log).unwrap(), "zip\nshar\ndeb\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_applies_default_file_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        for (file, mode) in [("data.txt", 0o664), ("tool.sh", 0o775), ("key.pem", 0o664)] {
+            let path = src.join(file);
+            fs::write(&path, file).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let mut config = Config::default();
+        config.resources = Some(vec![
+            Resource::Mapped {
+                src: src.join("data.txt").to_string_lossy().into_owned(),
+                target: "assets/data.txt".into(),
+                mode: None,
+                optional: false,
+            },
+            Resource::Mapped {
+                src: src.join("tool.sh").to_string_lossy().into_owned(),
+                target: "assets/tool.sh".into(),
+                mode: None,
+                optional: false,
+            },
+            Resource::Mapped {
+                src: src.join("key.pem").to_string_lossy().into_owned(),
+                target: "assets/key.pem".into(),
+                mode: Some("0600".into()),
+                optional: false,
+            },
+        ]);
+        config.default_file_mode = Some("0644".into());
+        config.default_dir_mode = Some("0750".into());
+
+        let data_dir = dir.path().join("data");
+        config
+            .copy_resources(&data_dir, PackageFormat::Deb)
+            .unwrap();
+        let explicit = config
+            .resources()
+            .unwrap()
+            .into_iter()
+            .filter_map(|r| r.mode.map(|mode| (r.target, mode)))
+            .collect();
+        let modes = file_modes(&config, &data_dir, explicit).unwrap();
+        apply_file_modes(&data_dir, &modes).unwrap();
+
+        let mode = |path: &str| {
+            fs::metadata(data_dir.join(path))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o7777
+        };
+        assert_eq!(mode("assets"), 0o750);
+        assert_eq!(mode("assets/data.txt"), 0o644);
+        assert_eq!(mode("assets/tool.sh"), 0o755);
+        assert_eq!(mode("assets/key.pem"), 0o600);
+
+        for (file, dir) in [
+            ("4644", "0755"),
+            ("0400", "0755"),
+            ("0644", "0644"),
+            ("0x644", "0755"),
+        ] {
+            config.default_file_mode = Some(file.into());
+            config.default_dir_mode = Some(dir.into());
+            assert!(config.default_modes().is_err(), "{file} {dir}");
+        }
+    }
+
+    #[test]
+    fn it_cleans_previous_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dunce::canonicalize(dir.path()).unwrap();
+        let removed = [
+            "app_0.1.0_amd64.deb",
+            "app_0.1.0_amd64.deb.sig",
+            "app_0.2.0_arm64.deb",
+            "linux/app_0.1.0_amd64.deb",
+            "App.app.tar.gz",
+            "macos/App.app/Contents/Info.plist",
+        ];
+        let kept = [
+            "app",
+            "notes.txt",
+            "other_0.1.0_amd64.deb",
+            "app_0.1.0_x86_64.AppImage",
+            "app_0.1.0_amd64.debian",
+            "App.apple",
+        ];
+        for file in removed.iter().chain(&kept) {
+            let path = out_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::create_dir_all(out_dir.join("App.app/Contents")).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.out_dir = out_dir.clone();
+        config.output_layout = Some(OutputLayout::Os);
+        config.binaries = vec![Binary::new("app").main(true)];
+
+        clean_outputs(&config, &[PackageFormat::Deb, PackageFormat::App]).unwrap();
+        for file in removed {
+            assert!(!out_dir.join(file).exists(), "{file} should be removed");
+        }
+        assert!(!out_dir.join("App.app").exists());
+        for file in kept {
+            assert!(out_dir.join(file).exists(), "{file} should be kept");
+        }
+    }
+
+    #[test]
+    fn it_applies_output_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dunce::canonicalize(dir.path()).unwrap();
+        let deb = out_dir.join("app_1.0.0_amd64.deb");
+        let appimage = out_dir.join("app_1.0.0_x86_64.AppImage");
+        let app = out_dir.join("App.app");
+        let nested = out_dir.join("other").join("app.zsync");
+        for file in [&deb, &appimage] {
+            fs::write(file, "").unwrap();
+        }
+        fs::create_dir_all(app.join("Contents")).unwrap();
+        fs::create_dir_all(nested.parent().unwrap()).unwrap();
+        fs::write(&nested, "").unwrap();
+
+        let mut config = Config::default();
+        config.out_dir = out_dir.clone();
+
+        let mut packages = vec![
+            PackageOutput::new(PackageFormat::Deb, vec![deb]),
+            PackageOutput::new(PackageFormat::AppImage, vec![appimage, nested.clone()]),
+            PackageOutput::new(PackageFormat::App, vec![app]),
+        ];
+
+        // the default layout leaves the packages in place
+        apply_output_layout(&config, &mut packages).unwrap();
+        assert_eq!(packages[0].paths[0], out_dir.join("app_1.0.0_amd64.deb"));
+
+        config.output_layout = Some(OutputLayout::Os);
+        apply_output_layout(&config, &mut packages).unwrap();
+        assert_eq!(
+            packages.iter().map(|p| p.paths.clone()).collect::<Vec<_>>(),
+            vec![
+                vec![out_dir.join("linux/app_1.0.0_amd64.deb")],
+                vec![out_dir.join("linux/app_1.0.0_x86_64.AppImage"), nested],
+                vec![out_dir.join("macos/App.app")],
+            ]
+        );
+        assert!(out_dir.join("linux/app_1.0.0_amd64.deb").is_file());
+        assert!(out_dir.join("macos/App.app/Contents").is_dir());
+        assert!(!out_dir.join("App.app").exists());
+
+        let mut packages = vec![PackageOutput::new(
+            PackageFormat::Deb,
+            vec![out_dir.join("app_1.0.0_amd64.deb")],
+        )];
+        fs::write(&packages[0].paths[0], "new").unwrap();
+        config.output_layout = Some(OutputLayout::Format);
+        apply_output_layout(&config, &mut packages).unwrap();
+        assert_eq!(
+            fs::read_to_string(out_dir.join("deb/app_1.0.0_amd64.deb")).unwrap(),
+            "new"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! The file names of the packages, shared by the package builders and [`output_paths`](super::output_paths)
+//! so the predicted paths are the ones the builders write.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::{Config, PackageFormat, WixLanguage},
+    util::PathExt,
+    Error,
+};
+
+/// Returns the architecture in the file names of the packages of `format`,
+/// which is also the architecture declared in their metadata.
+pub(crate) fn package_arch(config: &Config, format: PackageFormat) -> crate::Result<&str> {
+    let arch = config.target_arch()?;
+    Ok(match format {
+        PackageFormat::Dmg | PackageFormat::Pkg => match arch {
+            "x86_64" => "x64",
+            other => other,
+        },
+        PackageFormat::Wix | PackageFormat::Nsis | PackageFormat::InnoSetup => match arch {
+            "x86_64" => "x64",
+            "x86" => "x86",
+            "aarch64" => "arm64",
+            other => {
+                return Err(Error::UnsupportedArch(
+                    format.short_name().into(),
+                    other.into(),
+                ))
+            }
+        },
+        PackageFormat::Deb => match arch {
+            "x86" => "i386",
+            "x86_64" => "amd64",
+            "arm" => "armhf",
+            "aarch64" => "arm64",
+            other => other,
+        },
+        PackageFormat::AppImage => match arch {
+            "x86_64" => "x86_64",
+            "x86" => "i686",
+            "arm" => "armhf",
+            "aarch64" => "aarch64",
+            other => return Err(Error::UnsupportedArch("appimage".into(), other.into())),
+        },
+        PackageFormat::Pacman => match arch {
+            "x86" => "i386",
+            "arm" => "armhf",
+            other => other,
+        },
+        _ => arch,
+    })
+}
+
+/// Returns the name at the start of the file names of the packages of `format`:
+/// the `package_name` of the [`DebianConfig`](crate::config::DebianConfig)
+/// or [`PacmanConfig`](crate::config::PacmanConfig) when set, used verbatim, or the [`Config::slug`].
+pub(crate) fn package_name(config: &Config, format: PackageFormat) -> crate::Result<String> {
+    let package_name = match format {
+        PackageFormat::Deb => config.deb().and_then(|d| d.package_name.as_ref()),
+        PackageFormat::Pacman => config.pacman().and_then(|p| p.package_name.as_ref()),
+        _ => None,
+    };
+    match package_name {
+        Some(name) => Ok(name.clone()),
+        None => config.slug(),
+    }
+}
+
+/// Returns the name of the package of `format` without its suffix, `<name>_<version>_<arch>`
+/// with the [`package_name`], or the product name of the [`PackageFormat::App`] bundle.
+pub(crate) fn package_base_name(config: &Config, format: PackageFormat) -> crate::Result<String> {
+    if format == PackageFormat::App {
+        return Ok(config.product_name.clone());
+    }
+    Ok(format!(
+        "{}_{}_{}",
+        package_name(config, format)?,
+        config.version,
+        package_arch(config, format)?
+    ))
+}
+
+/// Returns the suffix appended to the [`package_base_name`] of the packages of `format`.
+fn package_suffix(format: PackageFormat) -> &'static str {
+    match format {
+        PackageFormat::App => ".app",
+        PackageFormat::Dmg => ".dmg",
+        PackageFormat::Pkg => ".pkg",
+        PackageFormat::Wix => ".msi",
+        PackageFormat::Nsis => "-setup.exe",
+        PackageFormat::InnoSetup => "-innosetup.exe",
+        PackageFormat::Deb => ".deb",
+        PackageFormat::AppImage => ".AppImage",
+        PackageFormat::Pacman => ".tar.gz",
+        PackageFormat::Shar => ".sh",
+        PackageFormat::Zip => ".zip",
+        _ => "",
+    }
+}
+
+/// Returns the file name of the package of `format`, like `app_1.0.0_amd64.deb`.
+///
+/// The [`PackageFormat::Wix`] installers are named by [`wix_file_name`] instead.
+pub(crate) fn package_file_name(config: &Config, format: PackageFormat) -> crate::Result<String> {
+    Ok(format!(
+        "{}{}",
+        package_base_name(config, format)?,
+        package_suffix(format)
+    ))
+}
+
+/// Returns the file name of the Inno Setup script written instead of the installer when ISCC isn't found.
+pub(crate) fn innosetup_script_file_name(config: &Config) -> crate::Result<String> {
+    Ok(format!(
+        "{}-innosetup.iss",
+        package_base_name(config, PackageFormat::InnoSetup)?
+    ))
+}
+
+/// Returns the file name of the `.msi` installer of `language`.
+pub(crate) fn wix_file_name(config: &Config, language: &str) -> crate::Result<String> {
+    Ok(format!(
+        "{}_{language}.msi",
+        package_base_name(config, PackageFormat::Wix)?
+    ))
+}
+
+/// Returns the file name of the [`DebianConfig::data_package`](crate::config::DebianConfig::data_package),
+/// if it is set.
+pub(crate) fn deb_data_file_name(config: &Config) -> crate::Result<Option<String>> {
+    let Some(data_package) = config.deb().and_then(|d| d.data_package.as_ref()) else {
+        return Ok(None);
+    };
+    let version = data_package.version.as_ref().unwrap_or(&config.version);
+    let arch = if data_package.arch_independent {
+        "all"
+    } else {
+        package_arch(config, PackageFormat::Deb)?
+    };
+    Ok(Some(format!(
+        "{}-data_{version}_{arch}.deb",
+        package_name(config, PackageFormat::Deb)?
+    )))
+}
+
+/// Returns the configs of the [`AppImageConfig::architectures`](crate::config::AppImageConfig::architectures)
+/// using their target triple, binaries and runtime, with the one of [`Config::target_triple`] first,
+/// or `config` itself when no architecture is set.
+pub(crate) fn appimage_architecture_configs(config: &Config) -> Vec<Config> {
+    let Some(architectures) = config.appimage().and_then(|a| a.architectures.as_deref()) else {
+        return vec![config.clone()];
+    };
+    let target_triple = config.target_triple();
+    let mut configs = architectures
+        .iter()
+        .map(|architecture| {
+            let mut config = config.clone();
+            config.target_triple = Some(architecture.target_triple.clone());
+            config.binaries_dir = Some(architecture.binaries_dir.clone());
+            if let (Some(runtime), Some(appimage)) =
+                (&architecture.runtime, config.appimage.as_mut())
+            {
+                appimage.runtime = Some(runtime.clone());
+            }
+            config
+        })
+        .collect::<Vec<_>>();
+    // the summary of the package refers to its first AppImage
+    if let Some(i) = configs
+        .iter()
+        .position(|c| c.target_triple.as_ref() == Some(&target_triple))
+    {
+        configs[..=i].rotate_right(1);
+    }
+    configs
+}
+
+/// Returns the paths of the files written in `out_dir` by the builder of `format`,
+/// the package first.
+///
+/// The Inno Setup installer is predicted even when only its script can be written,
+/// and the `.zsync` file of the AppImages whenever their update information is set.
+pub(crate) fn package_paths(
+    config: &Config,
+    format: PackageFormat,
+    out_dir: &Path,
+) -> crate::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    match format {
+        PackageFormat::Wix => {
+            let languages = config
+                .wix()
+                .and_then(|w| w.languages.clone())
+                .unwrap_or_else(|| vec![WixLanguage::default()]);
+            for language in languages {
+                let language = match language {
+                    WixLanguage::Identifier(identifier) => identifier,
+                    WixLanguage::Custom { identifier, .. } => identifier,
+                };
+                paths.push(out_dir.join(wix_file_name(config, &language)?));
+            }
+        }
+        PackageFormat::Deb => {
+            paths.push(out_dir.join(package_file_name(config, format)?));
+            if let Some(data_file_name) = deb_data_file_name(config)? {
+                paths.push(out_dir.join(data_file_name));
+            }
+        }
+        PackageFormat::AppImage => {
+            let zsync = config
+                .appimage()
+                .is_some_and(|a| a.update_information.is_some());
+            for config in appimage_architecture_configs(config) {
+                let path = out_dir.join(package_file_name(&config, format)?);
+                paths.push(path.clone());
+                if zsync {
+                    paths.push(path.with_additional_extension("zsync"));
+                }
+            }
+        }
+        _ => paths.push(out_dir.join(package_file_name(config, format)?)),
+    }
+    Ok(paths)
+}
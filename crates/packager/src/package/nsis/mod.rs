@@ -13,14 +13,14 @@ use std::{
 
 use handlebars::{to_json, Handlebars};
 
-use super::Context;
+use super::{naming, tools, version_info, Context};
 use crate::{
     codesign::windows::{self as codesign},
     util::verify_file_hash,
     Error,
 };
 use crate::{
-    config::{Config, LogLevel, NSISInstallerMode, NsisCompression},
+    config::{Config, LogLevel, NSISInstallerMode, NsisCompression, PackageFormat},
     shell::CommandExt,
     util::{self, download, download_and_verify, extract_zip, HashAlgorithm},
 };
@@ -82,10 +82,17 @@ fn normalize_resource_path<P: AsRef<Path>>(path: P) -> PathBuf {
 }
 
 #[tracing::instrument(level = "trace", skip(config))]
-fn generate_resource_data(config: &Config) -> crate::Result<(DirectoriesSet, ResourcesMap)> {
+pub(super) fn generate_resource_data(
+    config: &Config,
+) -> crate::Result<(DirectoriesSet, ResourcesMap)> {
     let mut directories = BTreeSet::new();
     let mut resources_map = BTreeMap::new();
-    for r in config.resources()? {
+    for r in config
+        .resources()?
+        .into_iter()
+        .chain(config.bundled_runtime()?)
+        .chain(config.release_notes()?)
+    {
         // only add if resource has a parent e.g. `files/a.txt`
         // and is not empty. this is to ensure that we don't
         // generate `CreateDirectory "$INSTDIR\"` which is useless
@@ -104,7 +111,7 @@ fn generate_resource_data(config: &Config) -> crate::Result<(DirectoriesSet, Res
 /// BTreeMap<OriginalPath, TargetFileName>
 type BinariesMap = BTreeMap<PathBuf, String>;
 #[tracing::instrument(level = "trace", skip(config))]
-fn generate_binaries_data(config: &Config) -> crate::Result<BinariesMap> {
+pub(super) fn generate_binaries_data(config: &Config) -> crate::Result<BinariesMap> {
     let mut binaries = BinariesMap::new();
 
     if let Some(external_binaries) = &config.external_binaries {
@@ -139,6 +146,137 @@ fn generate_binaries_data(config: &Config) -> crate::Result<BinariesMap> {
     Ok(binaries)
 }
 
+/// The languages supported by the NSIS Modern UI,
+/// see <https://github.com/kichik/nsis/tree/9465c08046f00ccb6eda985abbdbf52c275c6c4d/Contrib/Language%20files>.
+const NSIS_LANGUAGES: &[&str] = &[
+    "Afrikaans",
+    "Albanian",
+    "Arabic",
+    "Armenian",
+    "Asturian",
+    "Basque",
+    "Belarusian",
+    "Bosnian",
+    "Breton",
+    "Bulgarian",
+    "Catalan",
+    "Corsican",
+    "Croatian",
+    "Czech",
+    "Danish",
+    "Dutch",
+    "English",
+    "Esperanto",
+    "Estonian",
+    "Farsi",
+    "Finnish",
+    "French",
+    "Galician",
+    "Georgian",
+    "German",
+    "Greek",
+    "Hebrew",
+    "Hindi",
+    "Hungarian",
+    "Icelandic",
+    "Indonesian",
+    "Irish",
+    "Italian",
+    "Japanese",
+    "Korean",
+    "Kurdish",
+    "Latvian",
+    "Lithuanian",
+    "Luxembourgish",
+    "Macedonian",
+    "Malay",
+    "Mongolian",
+    "Norwegian",
+    "NorwegianNynorsk",
+    "Pashto",
+    "Persian",
+    "Polish",
+    "Portuguese",
+    "PortugueseBR",
+    "Romanian",
+    "Russian",
+    "ScotsGaelic",
+    "Serbian",
+    "SerbianLatin",
+    "SimpChinese",
+    "Slovak",
+    "Slovenian",
+    "Spanish",
+    "SpanishInternational",
+    "Swedish",
+    "Tatar",
+    "Thai",
+    "TradChinese",
+    "Turkish",
+    "Ukrainian",
+    "Uzbek",
+    "Vietnamese",
+    "Welsh",
+];
+
+/// Returns the canonicalized path of the license page,
+/// [`NsisConfig::license_agreement`](crate::config::NsisConfig::license_agreement) or [`Config::license_file`],
+/// or the path of the `license.txt` file written into `intermediates_path`
+/// with the [`NsisConfig::license_text`](crate::config::NsisConfig::license_text).
+fn license_path(config: &Config, intermediates_path: &Path) -> crate::Result<Option<PathBuf>> {
+    let nsis = config.nsis();
+    if let Some(text) = nsis.and_then(|n| n.license_text.as_ref()) {
+        if text.trim().is_empty() {
+            return Err(Error::EmptyNsisLicenseText);
+        }
+        let path = intermediates_path.join("license.txt");
+        write_ut16_le_with_bom(&path, text)?;
+        return Ok(Some(path));
+    }
+
+    nsis.and_then(|n| n.license_agreement.as_ref())
+        .or(config.license_file.as_ref())
+        .map(|license| {
+            dunce::canonicalize(license).map_err(|e| Error::IoWithPath(license.clone(), e))
+        })
+        .transpose()
+}
+
+/// Returns the installer languages, the first one being the default,
+/// with the names of [`NSIS_LANGUAGES`] casing.
+/// Inserts the shortcuts data of the installer into `data`.
+fn insert_shortcuts_data(
+    config: &Config,
+    data: &mut BTreeMap<&str, serde_json::Value>,
+) -> crate::Result<()> {
+    let shortcuts = config.windows_shortcuts()?;
+    data.insert("start_menu_shortcut", to_json(shortcuts.start_menu));
+    data.insert("desktop_shortcut", to_json(shortcuts.desktop));
+    data.insert("start_menu_folder", to_json(&shortcuts.start_menu_folder));
+    data.insert("shortcut_binary_name", to_json(&shortcuts.binary));
+    data.insert("shortcut_arguments", to_json(&shortcuts.arguments));
+    Ok(())
+}
+
+fn installer_languages(config: &Config) -> crate::Result<Vec<String>> {
+    let Some(languages) = config.nsis().and_then(|n| n.languages.as_ref()) else {
+        return Ok(vec!["English".into()]);
+    };
+
+    languages
+        .iter()
+        .map(|lang| {
+            NSIS_LANGUAGES
+                .iter()
+                .find(|l| l.eq_ignore_ascii_case(lang))
+                .map(|l| l.to_string())
+                .ok_or_else(|| {
+                    Error::UnsupportedNsisLanguage(lang.clone(), NSIS_LANGUAGES.join(", "))
+                })
+        })
+        .collect()
+}
+
 #[tracing::instrument(level = "trace")]
 fn get_lang_data(
     lang: &str,
@@ -190,7 +328,7 @@ fn write_ut16_le_with_bom<P: AsRef<Path> + Debug>(path: P, content: &str) -> cra
     Ok(())
 }
 
-fn handlebars_or(
+pub(super) fn handlebars_or(
     h: &handlebars::Helper<'_>,
     _: &Handlebars<'_>,
     _: &handlebars::Context,
@@ -208,7 +346,7 @@ fn handlebars_or(
     Ok(())
 }
 
-fn association_description(
+pub(super) fn association_description(
     h: &handlebars::Helper<'_>,
     _: &Handlebars<'_>,
     _: &handlebars::Context,
@@ -250,7 +388,7 @@ fn unescape_dollar_sign(
     Ok(())
 }
 
-fn add_build_number_if_needed(version_str: &str) -> crate::Result<String> {
+pub(super) fn add_build_number_if_needed(version_str: &str) -> crate::Result<String> {
     let version = semver::Version::parse(version_str)?;
     if !version.build.is_empty() {
         let build = version.build.parse::<u64>();
@@ -334,6 +472,95 @@ fn get_and_extract_nsis(
     Ok(())
 }
 
+// Ensures the user provided uninstaller commands do not close the uninstall section
+// or open new sections and functions.
+fn validate_uninstaller_commands(commands: &str) -> crate::Result<()> {
+    const DISALLOWED_KEYWORDS: &[&str] = &[
+        "section",
+        "sectionend",
+        "sectiongroup",
+        "sectiongroupend",
+        "function",
+        "functionend",
+    ];
+
+    for line in commands.lines() {
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        if DISALLOWED_KEYWORDS.contains(&keyword.to_lowercase().as_str()) {
+            return Err(Error::InvalidNsisUninstallerCommands(
+                line.trim().to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the name of a compression algorithm as used by `SetCompressor`.
+fn compression_name(compression: NsisCompression) -> &'static str {
+    match compression {
+        NsisCompression::Zlib => "zlib",
+        NsisCompression::Bzip2 => "bzip2",
+        NsisCompression::Lzma => "lzma",
+        NsisCompression::Off => "off",
+    }
+}
+
+fn render_installer_nsi(
+    data: &BTreeMap<&str, serde_json::Value>,
+    custom_template_path: Option<&Path>,
+) -> crate::Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("or", Box::new(handlebars_or));
+    handlebars.register_helper("association-description", Box::new(association_description));
+    handlebars.register_helper("unescape_newlines", Box::new(unescape_newlines));
+    handlebars.register_helper("unescape_dollar_sign", Box::new(unescape_dollar_sign));
+    handlebars.register_escape_fn(|s| {
+        let mut output = String::new();
+        for c in s.chars() {
+            match c {
+                '\"' => output.push_str("$\\\""),
+                '$' => output.push_str("$$"),
+                '`' => output.push_str("$\\`"),
+                '\n' => output.push_str("$\\n"),
+                '\t' => output.push_str("$\\t"),
+                '\r' => output.push_str("$\\r"),
+                _ => output.push(c),
+            }
+        }
+        output
+    });
+    if let Some(path) = custom_template_path {
+        handlebars
+            .register_template_string("installer.nsi", fs::read_to_string(path)?)
+            .map_err(Box::new)?;
+    } else {
+        handlebars
+            .register_template_string("installer.nsi", include_str!("./installer.nsi"))
+            .map_err(Box::new)?;
+    }
+
+    Ok(handlebars.render("installer.nsi", data)?)
+}
+
+/// Returns the [`crate::config::NsisConfig::custom_pages`] with their canonicalized scripts,
+/// to be included after the install directory page.
+fn custom_pages(config: &Config) -> crate::Result<Vec<serde_json::Value>> {
+    config
+        .nsis_custom_pages()?
+        .iter()
+        .map(|page| {
+            let script = dunce::canonicalize(&page.script)
+                .map_err(|e| Error::IoWithPath(page.script.clone(), e))?;
+            Ok(serde_json::json!({
+                "script": script,
+                "show": page.show,
+                "leave": page.leave,
+            }))
+        })
+        .collect()
+}
+
 #[tracing::instrument(level = "trace", skip(ctx))]
 fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Vec<PathBuf>> {
     let Context {
@@ -342,16 +569,10 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
         ..
     } = ctx;
 
-    let arch = match config.target_arch()? {
-        "x86_64" => "x64",
-        "x86" => "x86",
-        "aarch64" => "arm64",
-        target => return Err(Error::UnsupportedArch("nsis".into(), target.into())),
-    };
+    let arch = naming::package_arch(config, PackageFormat::Nsis)?;
 
-    let main_binary = config.main_binary()?;
     let main_binary_name = config.main_binary_name()?;
-    let main_binary_path = config.binary_path(main_binary).with_extension("exe");
+    let main_binary_path = version_info::main_binary_path(config, intermediates_path)?;
 
     if config.can_sign() {
         tracing::debug!("Codesigning {}", main_binary_path.display());
@@ -373,15 +594,23 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
     }
 
     let identifier = config.identifier();
-    let manufacturer = config.publisher();
+    let manufacturer = config.nsis_publisher()?;
 
     data.insert("arch", to_json(arch));
+    let (compression, solid) = config.nsis_compression()?;
+    data.insert("compression", to_json(compression_name(compression)));
+    data.insert("solid", to_json(solid));
     data.insert("identifier", to_json(identifier));
     data.insert("manufacturer", to_json(&manufacturer));
+    data.insert(
+        "add_remove_programs_links",
+        to_json(config.add_remove_programs_links()?),
+    );
     data.insert("product_name", to_json(&config.product_name));
     data.insert("short_description", to_json(&config.description));
     data.insert("copyright", to_json(&config.copyright));
     data.insert("version", to_json(&config.version));
+    data.insert("build_id", to_json(&config.build_id));
     data.insert(
         "version_with_build",
         to_json(add_build_number_if_needed(&config.version)?),
@@ -396,24 +625,18 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
         data.insert("uninstaller_sign_cmd", to_json(sign_cmd));
     }
 
-    if let Some(license) = &config.license_file {
-        let canonicalized =
-            dunce::canonicalize(license).map_err(|e| Error::IoWithPath(license.clone(), e))?;
-        data.insert("license", to_json(canonicalized));
+    if let Some(license) = license_path(config, &intermediates_path)? {
+        data.insert("license", to_json(license));
     }
 
     let mut install_mode = NSISInstallerMode::CurrentUser;
-    let mut languages = vec!["English".into()];
+    let languages = installer_languages(config)?;
     let mut custom_template_path = None;
     let mut custom_language_files = None;
     if let Some(nsis) = config.nsis() {
         custom_template_path.clone_from(&nsis.template);
         custom_language_files.clone_from(&nsis.custom_language_files);
         install_mode = nsis.install_mode;
-        if let Some(langs) = &nsis.languages {
-            languages.clear();
-            languages.extend_from_slice(langs);
-        }
         data.insert(
             "display_language_selector",
             to_json(nsis.display_language_selector && languages.len() > 1),
@@ -436,17 +659,6 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
         if let Some(preinstall_section) = &nsis.preinstall_section {
             data.insert("preinstall_section", to_json(preinstall_section));
         }
-        if let Some(compression) = &nsis.compression {
-            data.insert(
-                "compression",
-                to_json(match &compression {
-                    NsisCompression::Zlib => "zlib",
-                    NsisCompression::Bzip2 => "bzip2",
-                    NsisCompression::Lzma => "lzma",
-                    NsisCompression::Off => "off",
-                }),
-            );
-        }
         if let Some(appdata_paths) = &nsis.appdata_paths {
             let appdata_paths = appdata_paths
                 .iter()
@@ -458,6 +670,11 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
                 .collect::<Vec<_>>();
             data.insert("appdata_paths", to_json(appdata_paths));
         }
+        data.insert("custom_pages", to_json(custom_pages(config)?));
+        if let Some(uninstaller_commands) = &nsis.uninstaller_commands {
+            validate_uninstaller_commands(uninstaller_commands)?;
+            data.insert("uninstaller_commands", to_json(uninstaller_commands));
+        }
     }
 
     data.insert("install_mode", to_json(install_mode));
@@ -483,6 +700,7 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
 
     data.insert("main_binary_name", to_json(&main_binary_name));
     data.insert("main_binary_path", to_json(&main_binary_path));
+    insert_shortcuts_data(config, &mut data)?;
 
     if let Some(file_associations) = &config.file_associations {
         data.insert("file_associations", to_json(file_associations));
@@ -510,36 +728,6 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
         generate_estimated_size(main_binary_path, resources.keys().chain(binaries.keys()))?;
     data.insert("estimated_size", to_json(estimated_size));
 
-    let mut handlebars = Handlebars::new();
-    handlebars.register_helper("or", Box::new(handlebars_or));
-    handlebars.register_helper("association-description", Box::new(association_description));
-    handlebars.register_helper("unescape_newlines", Box::new(unescape_newlines));
-    handlebars.register_helper("unescape_dollar_sign", Box::new(unescape_dollar_sign));
-    handlebars.register_escape_fn(|s| {
-        let mut output = String::new();
-        for c in s.chars() {
-            match c {
-                '\"' => output.push_str("$\\\""),
-                '$' => output.push_str("$$"),
-                '`' => output.push_str("$\\`"),
-                '\n' => output.push_str("$\\n"),
-                '\t' => output.push_str("$\\t"),
-                '\r' => output.push_str("$\\r"),
-                _ => output.push(c),
-            }
-        }
-        output
-    });
-    if let Some(path) = custom_template_path {
-        handlebars
-            .register_template_string("installer.nsi", fs::read_to_string(path)?)
-            .map_err(Box::new)?;
-    } else {
-        handlebars
-            .register_template_string("installer.nsi", include_str!("./installer.nsi"))
-            .map_err(Box::new)?;
-    }
-
     write_ut16_le_with_bom(
         intermediates_path.join("FileAssociation.nsh"),
         include_str!("./FileAssociation.nsh"),
@@ -548,7 +736,7 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
     let installer_nsi_path = intermediates_path.join("installer.nsi");
     write_ut16_le_with_bom(
         &installer_nsi_path,
-        handlebars.render("installer.nsi", &data)?.as_str(),
+        render_installer_nsi(&data, custom_template_path.as_deref())?.as_str(),
     )?;
 
     for (lang, data) in languages_data.iter() {
@@ -559,10 +747,9 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
 
     let nsis_output_path = intermediates_path.join(out_file);
 
-    let installer_path = config.out_dir().join(format!(
-        "{}_{}_{}-setup.exe",
-        main_binary_name, config.version, arch
-    ));
+    let installer_path = config
+        .out_dir()
+        .join(naming::package_file_name(config, PackageFormat::Nsis)?);
 
     let installer_path_parent = installer_path
         .parent()
@@ -575,9 +762,20 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
         util::display_path(&installer_path)
     );
     #[cfg(target_os = "windows")]
-    let mut nsis_cmd = Command::new(nsis_path.join("makensis.exe"));
+    let default_makensis = nsis_path.join("makensis.exe");
     #[cfg(not(target_os = "windows"))]
-    let mut nsis_cmd = Command::new("makensis");
+    let default_makensis = PathBuf::from("makensis");
+    let makensis = tools::pinned_path(ctx, "makensis").unwrap_or(default_makensis);
+    let version = Command::new(&makensis)
+        .arg("-VERSION")
+        .output_ok()
+        .map_err(Error::NsisFailed)?;
+    tools::check_version(
+        ctx,
+        "makensis",
+        &tools::parse_version("makensis", &String::from_utf8_lossy(&version.stdout))?,
+    )?;
+    let mut nsis_cmd = Command::new(makensis);
 
     if let Some(level) = config.log_level {
         nsis_cmd.arg(match level {
@@ -588,23 +786,30 @@ fn build_nsis_app_installer(ctx: &Context, nsis_path: &Path) -> crate::Result<Ve
         });
     }
 
+    if let Some(args) = config.nsis().and_then(|n| n.extra_tool_args.as_ref()) {
+        tracing::debug!("Appending extra makensis arguments: {args:?}");
+        nsis_cmd.args(args);
+    }
+
     nsis_cmd
         .arg(installer_nsi_path)
         .current_dir(intermediates_path)
         .output_ok()
         .map_err(Error::NsisFailed)?;
 
-    fs::rename(&nsis_output_path, &installer_path)
-        .map_err(|e| Error::RenameFile(nsis_output_path, installer_path.clone(), e))?;
-
+    // the installer is only moved to the output directory once signed,
+    // so an interrupted build never leaves an unsigned installer behind
     if config.can_sign() {
-        tracing::debug!("Codesigning {}", installer_path.display());
-        codesign::try_sign(&installer_path, config)?;
+        tracing::debug!("Codesigning {}", nsis_output_path.display());
+        codesign::try_sign(&nsis_output_path, config)?;
     } else {
         #[cfg(not(target_os = "windows"))]
         tracing::warn!("Codesigning is by default is only supported on Windows hosts, but you can specify a custom signing command in `config.windows.sign_command`, for now, skipping signing the installer...");
     }
 
+    fs::rename(&nsis_output_path, &installer_path)
+        .map_err(|e| Error::RenameFile(nsis_output_path, installer_path.clone(), e))?;
+
     Ok(vec![installer_path])
 }
 
@@ -642,3 +847,315 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
 
     build_nsis_app_installer(ctx, &nsis_toolset_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Binary, NsisConfig, NsisCustomPage, ShortcutsConfig, WindowsConfig};
+
+    #[test]
+    fn it_injects_uninstaller_commands() {
+        let commands =
+            "nsExec::Exec 'sc stop \"${PRODUCTNAME}Service\"'\nRMDir /r \"$INSTDIR\\data\"";
+        validate_uninstaller_commands(commands).unwrap();
+
+        let mut data = BTreeMap::new();
+        data.insert("uninstaller_commands", to_json(commands));
+        let nsi = render_installer_nsi(&data, None).unwrap();
+
+        let uninstall_section = &nsi[nsi.find("Section Uninstall").unwrap()..];
+        let uninstall_section = &uninstall_section[..uninstall_section.find("SectionEnd").unwrap()];
+        assert!(uninstall_section.contains("nsExec::Exec 'sc stop \"${PRODUCTNAME}Service\"'"));
+        assert!(uninstall_section.contains("RMDir /r \"$INSTDIR\\data\""));
+    }
+
+    #[test]
+    fn it_includes_custom_pages_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let license_key = dir.path().join("license-key.nsh");
+        let telemetry = dir.path().join("telemetry.nsh");
+        fs::write(&license_key, "Function LicenseKeyPage\nFunctionEnd").unwrap();
+        fs::write(&telemetry, "Function TelemetryPage\nFunctionEnd").unwrap();
+
+        let mut config = Config::default();
+        config.nsis = Some(NsisConfig::new().custom_pages([
+            NsisCustomPage::new(&license_key, "LicenseKeyPage").leave("LicenseKeyPageLeave"),
+            NsisCustomPage::new(&telemetry, "TelemetryPage"),
+        ]));
+        let mut data = BTreeMap::new();
+        data.insert("custom_pages", to_json(custom_pages(&config).unwrap()));
+        let nsi = render_installer_nsi(&data, None).unwrap();
+
+        let directory = nsi.find("!insertmacro MUI_PAGE_DIRECTORY").unwrap();
+        let license_key_page = nsi
+            .find(&format!(
+                "!include \"{}\"\nPage custom LicenseKeyPage LicenseKeyPageLeave\n",
+                dunce::canonicalize(&license_key).unwrap().display()
+            ))
+            .unwrap();
+        let telemetry_page = nsi
+            .find(&format!(
+                "!include \"{}\"\nPage custom TelemetryPage\n",
+                dunce::canonicalize(&telemetry).unwrap().display()
+            ))
+            .unwrap();
+        let instfiles = nsi.find("!insertmacro MUI_PAGE_INSTFILES").unwrap();
+        assert!(directory < license_key_page);
+        assert!(license_key_page < telemetry_page);
+        assert!(telemetry_page < instfiles);
+
+        config.nsis = Some(NsisConfig::new().custom_pages([NsisCustomPage::new(
+            dir.path().join("missing.nsh"),
+            "MissingPage",
+        )]));
+        assert!(matches!(
+            custom_pages(&config),
+            Err(Error::DoesNotExist(path)) if path.ends_with("missing.nsh")
+        ));
+
+        config.nsis = Some(
+            NsisConfig::new().custom_pages([NsisCustomPage::new(&telemetry, "Telemetry Page")]),
+        );
+        assert!(matches!(
+            custom_pages(&config),
+            Err(Error::InvalidNsisCustomPageFunction(function)) if function == "Telemetry Page"
+        ));
+    }
+
+    #[test]
+    fn it_writes_the_add_remove_programs_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("app.exe");
+        let resource = dir.path().join("data.bin");
+        fs::write(&main, vec![0; 3000]).unwrap();
+        fs::write(&resource, vec![0; 2000]).unwrap();
+
+        let mut config = Config::default();
+        config.homepage = Some("https://example.com".into());
+        config.windows = Some(
+            WindowsConfig::new()
+                .help_link("https://example.com/support")
+                .update_info_url("https://example.com/releases?channel=stable"),
+        );
+        let mut data = BTreeMap::new();
+        data.insert(
+            "add_remove_programs_links",
+            to_json(config.add_remove_programs_links().unwrap()),
+        );
+        data.insert(
+            "estimated_size",
+            to_json(generate_estimated_size(&main, [&resource]).unwrap()),
+        );
+        let nsi = render_installer_nsi(&data, None).unwrap();
+
+        for line in [
+            r#"!define ESTIMATEDSIZE "0x000005""#,
+            r#"WriteRegStr SHCTX "${UNINSTKEY}" "DisplayIcon" "$\"$INSTDIR\${MAINBINARYNAME}.exe$\"""#,
+            r#"WriteRegStr SHCTX "${UNINSTKEY}" "HelpLink" "https://example.com/support""#,
+            r#"WriteRegStr SHCTX "${UNINSTKEY}" "URLInfoAbout" "https://example.com/""#,
+            r#"WriteRegStr SHCTX "${UNINSTKEY}" "URLUpdateInfo" "https://example.com/releases?channel=stable""#,
+            r#"WriteRegDWORD SHCTX "${UNINSTKEY}" "EstimatedSize" "${ESTIMATEDSIZE}""#,
+        ] {
+            assert!(nsi.lines().any(|l| l.trim() == line), "{line}");
+        }
+
+        config.windows = Some(WindowsConfig::new().about_url("ftp://example.com"));
+        assert!(matches!(
+            config.add_remove_programs_links(),
+            Err(Error::InvalidWindowsUrl("aboutUrl", url)) if url == "ftp://example.com"
+        ));
+        config.windows = Some(WindowsConfig::new().help_link("example.com/support"));
+        assert!(config.add_remove_programs_links().is_err());
+    }
+
+    #[test]
+    fn it_sets_the_compressor() {
+        let mut config = Config::default();
+        for (nsis, expected) in [
+            (None, "SetCompressor /SOLID \"lzma\""),
+            (
+                Some(NsisConfig::new().compression(NsisCompression::Bzip2)),
+                "SetCompressor /SOLID \"bzip2\"",
+            ),
+            (
+                Some(
+                    NsisConfig::new()
+                        .compression(NsisCompression::Zlib)
+                        .solid(false),
+                ),
+                "SetCompressor \"zlib\"",
+            ),
+            (
+                Some(NsisConfig::new().compression(NsisCompression::Off)),
+                "SetCompress off",
+            ),
+        ] {
+            config.nsis = nsis;
+            let (compression, solid) = config.nsis_compression().unwrap();
+            let mut data = BTreeMap::new();
+            data.insert("compression", to_json(compression_name(compression)));
+            data.insert("solid", to_json(solid));
+            let nsi = render_installer_nsi(&data, None).unwrap();
+            let compressor = nsi
+                .lines()
+                .filter(|l| l.starts_with("SetCompress"))
+                .collect::<Vec<_>>();
+            assert_eq!(compressor, [expected]);
+        }
+    }
+
+    #[test]
+    fn it_includes_installer_languages() {
+        let mut config = Config::default();
+        assert_eq!(installer_languages(&config).unwrap(), ["English"]);
+
+        config.nsis = Some(NsisConfig::new().languages(["french", "German"]));
+        let languages = installer_languages(&config).unwrap();
+        assert_eq!(languages, ["French", "German"]);
+
+        let mut data = BTreeMap::new();
+        data.insert("languages", to_json(languages));
+        let nsi = render_installer_nsi(&data, None).unwrap();
+        let mui_languages = nsi
+            .lines()
+            .filter(|l| l.starts_with("!insertmacro MUI_LANGUAGE "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mui_languages,
+            [
+                "!insertmacro MUI_LANGUAGE \"French\"",
+                "!insertmacro MUI_LANGUAGE \"German\""
+            ]
+        );
+
+        config.nsis = Some(NsisConfig::new().languages(["French", "Klingon"]));
+        assert!(matches!(
+            installer_languages(&config),
+            Err(Error::UnsupportedNsisLanguage(lang, _)) if lang == "Klingon"
+        ));
+    }
+
+    #[test]
+    fn it_shows_license_agreement() {
+        let dir = tempfile::tempdir().unwrap();
+        let license_file = dir.path().join("LICENSE");
+        let license_agreement = dir.path().join("EULA.rtf");
+        fs::write(&license_file, "MIT").unwrap();
+        fs::write(&license_agreement, r"{\rtf1 EULA}").unwrap();
+
+        let mut config = Config::default();
+        assert!(license_path(&config, dir.path()).unwrap().is_none());
+        let nsi = render_installer_nsi(&BTreeMap::new(), None).unwrap();
+        assert!(nsi.contains("!define LICENSE \"\""));
+
+        config.license_file = Some(license_file.clone());
+        config.nsis = Some(NsisConfig::new().license_agreement(&license_agreement));
+        let license = license_path(&config, dir.path()).unwrap().unwrap();
+        assert_eq!(license, dunce::canonicalize(&license_agreement).unwrap());
+
+        let mut data = BTreeMap::new();
+        data.insert("license", to_json(&license));
+        let nsi = render_installer_nsi(&data, None).unwrap();
+        assert!(nsi.contains(&format!("!define LICENSE \"{}\"", license.display())));
+        assert!(nsi.contains("!insertmacro MUI_PAGE_LICENSE \"${LICENSE}\""));
+
+        config.nsis = Some(NsisConfig::new().license_agreement(dir.path().join("missing.rtf")));
+        assert!(matches!(
+            license_path(&config, dir.path()),
+            Err(Error::IoWithPath(path, _)) if path.ends_with("missing.rtf")
+        ));
+    }
+
+    #[test]
+    fn it_embeds_the_license_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.license_file = Some(dir.path().join("LICENSE"));
+        config.nsis = Some(NsisConfig::new().license_text("MIT License\n\nCopyright (c) Me"));
+
+        let license = license_path(&config, dir.path()).unwrap().unwrap();
+        assert_eq!(license, dir.path().join("license.txt"));
+        let content = fs::read(&license).unwrap();
+        assert_eq!(content[..2], [0xFF, 0xFE]);
+        let utf16 = content[2..]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            String::from_utf16(&utf16).unwrap(),
+            "MIT License\n\nCopyright (c) Me"
+        );
+
+        let mut data = BTreeMap::new();
+        data.insert("license", to_json(&license));
+        let nsi = render_installer_nsi(&data, None).unwrap();
+        assert!(nsi.contains(&format!("!define LICENSE \"{}\"", license.display())));
+        assert!(nsi.contains("!insertmacro MUI_PAGE_LICENSE \"${LICENSE}\""));
+
+        config.nsis = Some(NsisConfig::new().license_text(" \n"));
+        assert!(matches!(
+            license_path(&config, dir.path()),
+            Err(Error::EmptyNsisLicenseText)
+        ));
+    }
+
+    #[test]
+    fn it_creates_configured_shortcuts() {
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.binaries = vec![Binary::new("app").main(true), Binary::new("app-cli")];
+
+        let mut data = BTreeMap::new();
+        insert_shortcuts_data(&config, &mut data).unwrap();
+        let nsi = render_installer_nsi(&data, None).unwrap();
+        assert!(nsi.contains("!define SHORTCUTBINARYNAME \"app\""));
+        assert!(nsi.contains("!define STARTMENUFOLDER \"My App\""));
+        assert!(nsi.contains("!insertmacro MUI_PAGE_STARTMENU Application $AppStartMenuFolder"));
+        assert!(nsi.contains("!define MUI_FINISHPAGE_SHOWREADME_NOTCHECKED"));
+        let install_section = &nsi[nsi.find("Section Install").unwrap()..];
+        let install_section = &install_section[..install_section.find("SectionEnd").unwrap()];
+        assert!(install_section.contains("Call CreateStartMenuShortcut"));
+        assert!(!install_section.contains("Call CreateDesktopShortcut"));
+
+        config.windows = Some(
+            WindowsConfig::new().shortcuts(
+                ShortcutsConfig::new()
+                    .start_menu(false)
+                    .desktop(true)
+                    .start_menu_folder("Tools")
+                    .arguments("--profile \"default\"")
+                    .binary("app-cli"),
+            ),
+        );
+        let mut data = BTreeMap::new();
+        insert_shortcuts_data(&config, &mut data).unwrap();
+        let nsi = render_installer_nsi(&data, None).unwrap();
+        assert!(nsi.contains("!define SHORTCUTBINARYNAME \"app-cli\""));
+        assert!(nsi.contains("!define SHORTCUTARGUMENTS \"--profile $\\\"default$\\\"\""));
+        assert!(nsi.contains("!define STARTMENUFOLDER \"Tools\""));
+        assert!(!nsi.contains("MUI_PAGE_STARTMENU"));
+        assert!(!nsi.contains("MUI_FINISHPAGE_SHOWREADME_NOTCHECKED"));
+        assert!(!nsi.contains("Function CreateStartMenuShortcut"));
+        let install_section = &nsi[nsi.find("Section Install").unwrap()..];
+        let install_section = &install_section[..install_section.find("SectionEnd").unwrap()];
+        assert!(install_section.contains("Call CreateDesktopShortcut"));
+        assert!(!install_section.contains("Call CreateStartMenuShortcut"));
+        assert!(nsi.contains(
+            "CreateShortcut \"$DESKTOP\\${PRODUCTNAME}.lnk\" \"$INSTDIR\\${SHORTCUTBINARYNAME}.exe\" \"${SHORTCUTARGUMENTS}\""
+        ));
+
+        config.windows =
+            Some(WindowsConfig::new().shortcuts(ShortcutsConfig::new().binary("missing")));
+        assert!(matches!(
+            insert_shortcuts_data(&config, &mut BTreeMap::new()),
+            Err(Error::ShortcutBinaryNotFound(binary)) if binary == "missing"
+        ));
+    }
+
+    #[test]
+    fn it_rejects_uninstaller_commands_with_sections() {
+        for commands in ["SectionEnd", "  functionend", "Section Other\nSectionEnd"] {
+            assert!(validate_uninstaller_commands(commands).is_err());
+        }
+    }
+}
@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use super::deb;
-use crate::{config::Config, package::Context, util, Error};
+use super::{deb, naming};
+use crate::{
+    config::{Config, PackageFormat},
+    package::Context,
+    util, Error,
+};
 use heck::AsKebabCase;
 use sha2::{Digest, Sha512};
 use std::{
@@ -20,17 +24,13 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         ..
     } = ctx;
 
-    let arch = match config.target_arch()? {
-        "x86" => "i386",
-        "arm" => "armhf",
-        other => other,
-    };
+    let arch = naming::package_arch(config, PackageFormat::Pacman)?;
 
     let intermediates_path = intermediates_path.join("pacman");
     util::create_clean_dir(&intermediates_path)?;
 
-    let package_base_name = format!("{}_{}_{}", config.main_binary_name()?, config.version, arch);
-    let package_name = format!("{package_base_name}.tar.gz");
+    let package_base_name = naming::package_base_name(config, PackageFormat::Pacman)?;
+    let package_name = naming::package_file_name(config, PackageFormat::Pacman)?;
 
     let pkg_dir = intermediates_path.join(&package_base_name);
     let pkg_path = config.out_dir().join(&package_name);
@@ -39,18 +39,40 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     tracing::info!("Packaging {} ({})", package_name, pkg_path.display());
 
     tracing::debug!("Generating data");
-    let _ = deb::generate_data(config, &pkg_dir)?;
+    let _ = deb::generate_data(config, &pkg_dir, PackageFormat::Pacman)?;
 
     tracing::debug!("Copying files specified in `pacman.files`");
     if let Some(files) = config.pacman().and_then(|d| d.files.as_ref()) {
         deb::copy_custom_files(files, &pkg_dir)?;
     }
 
+    tracing::debug!("Copying the system files of the staging directory");
+    deb::copy_custom_files(&config.staged_system_files()?, &pkg_dir)?;
+
+    tracing::debug!("Creating symlinks");
+    deb::create_symlinks(config, &pkg_dir)?;
+
     // Apply tar/gzip to create the final package file.
     tracing::debug!("Creating package archive using tar and gzip");
-    let data_tar_gz_path = deb::tar_and_gzip_dir(pkg_dir)?;
-    fs::copy(&data_tar_gz_path, &pkg_path)
-        .map_err(|e| Error::CopyFile(data_tar_gz_path, pkg_path.clone(), e))?;
+    let modes = super::file_modes(
+        config,
+        &pkg_dir,
+        deb::resource_modes(
+            config,
+            &Path::new("usr/lib").join(config.main_binary_name()?),
+        )?,
+    )?;
+    let data_tar_gz_path = deb::tar_and_gzip_dir(
+        pkg_dir,
+        &modes,
+        &deb::FileOwners::default(),
+        config.preserve_xattrs,
+        None,
+    )?;
+    ctx.write_artifact(&pkg_path, |partial| {
+        fs::rename(&data_tar_gz_path, partial)
+            .map_err(|e| Error::RenameFile(data_tar_gz_path.clone(), partial.to_path_buf(), e))
+    })?;
 
     tracing::info!("Generating PKGBUILD: {}", pkgbuild_path.display());
     generate_pkgbuild_file(config, arch, pkgbuild_path.as_path(), pkg_path.as_path())?;
@@ -58,6 +80,29 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
     Ok(vec![pkg_path])
 }
 
+/// Whether `name` is a valid `pkgname`, only containing lowercase alphanumerics and `@._+-`,
+/// without starting with a hyphen or a period.
+fn is_valid_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '@' | '.' | '_' | '+' | '-')
+        })
+}
+
+/// Returns the [`PacmanConfig::package_name`](crate::config::PacmanConfig::package_name) override,
+/// validated against the PKGBUILD rules.
+fn package_name_override(config: &Config) -> crate::Result<Option<&str>> {
+    match config.pacman().and_then(|p| p.package_name.as_deref()) {
+        Some(name) if !is_valid_package_name(name) => Err(Error::InvalidPackageName(
+            "pacman",
+            name.into(),
+            "[a-z0-9@_+][a-z0-9@._+-]*",
+        )),
+        name => Ok(name),
+    }
+}
+
 /// Generates the pacman PKGBUILD file.
 /// For more information about the format of this file, see
 /// <https://wiki.archlinux.org/title/PKGBUILD>
@@ -70,10 +115,13 @@ fn generate_pkgbuild_file(
     let pkgbuild_path = dest_dir.with_file_name("PKGBUILD");
     let mut file = util::create_file(&pkgbuild_path)?;
 
-    if let Some(authors) = &config.authors {
-        writeln!(file, "# Maintainer: {}", authors.join(", "))?;
+    if let Some(maintainer) = deb::maintainer(config) {
+        writeln!(file, "# Maintainer: {maintainer}")?;
+    }
+    match package_name_override(config)? {
+        Some(name) => writeln!(file, "pkgname={name}")?,
+        None => writeln!(file, "pkgname={}", AsKebabCase(&config.product_name))?,
     }
-    writeln!(file, "pkgname={}", AsKebabCase(&config.product_name))?;
     writeln!(file, "pkgver={}", config.version)?;
     writeln!(file, "pkgrel=1")?;
     writeln!(file, "epoch=")?;
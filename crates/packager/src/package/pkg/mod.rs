@@ -0,0 +1,165 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf, process::Command};
+
+use super::{naming, Context};
+use crate::{codesign::macos as codesign, config::PackageFormat, shell::CommandExt, util, Error};
+
+#[tracing::instrument(level = "trace", skip(ctx))]
+pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
+    let Context {
+        config,
+        intermediates_path,
+        ..
+    } = ctx;
+
+    let out_dir = config.out_dir();
+    let intermediates_path = intermediates_path.join("pkg");
+    util::create_clean_dir(&intermediates_path)?;
+
+    let app_bundle_path = out_dir.join(naming::package_file_name(config, PackageFormat::App)?);
+    let pkg_name = naming::package_file_name(config, PackageFormat::Pkg)?;
+    let pkg_path = out_dir.join(&pkg_name);
+
+    tracing::info!("Packaging {} ({})", pkg_name, pkg_path.display());
+
+    if pkg_path.exists() {
+        fs::remove_file(&pkg_path).map_err(|e| Error::IoWithPath(pkg_path.clone(), e))?;
+    }
+
+    let install_location = config.pkg_install_location()?;
+    let identifier = config.bundle_identifier()?;
+
+    let mut pkgbuild_cmd = Command::new("pkgbuild");
+    pkgbuild_cmd
+        .arg("--component")
+        .arg(&app_bundle_path)
+        .arg("--install-location")
+        .arg(&install_location)
+        .args(["--identifier", &identifier, "--version", &config.version]);
+
+    let scripts = config.pkg_scripts()?;
+    if !scripts.is_empty() {
+        tracing::debug!("Copying install scripts");
+        let scripts_dir = intermediates_path.join("scripts");
+        fs::create_dir_all(&scripts_dir).map_err(|e| Error::IoWithPath(scripts_dir.clone(), e))?;
+        for (name, script) in scripts {
+            let dest = scripts_dir.join(name);
+            fs::copy(&script, &dest).map_err(|e| Error::CopyFile(script, dest.clone(), e))?;
+            fs::set_permissions(&dest, fs::Permissions::from_mode(0o755))
+                .map_err(|e| Error::IoWithPath(dest, e))?;
+        }
+        pkgbuild_cmd.arg("--scripts").arg(scripts_dir);
+    }
+
+    if let Some(args) = config.pkg().and_then(|p| p.extra_tool_args.as_ref()) {
+        tracing::debug!("Appending extra pkgbuild arguments: {args:?}");
+        pkgbuild_cmd.args(args);
+    }
+
+    let component_path = intermediates_path.join(format!("{}-component.pkg", config.product_name));
+    tracing::debug!("Running pkgbuild");
+    pkgbuild_cmd
+        .arg(&component_path)
+        .output_ok()
+        .map_err(Error::PkgbuildFailed)?;
+
+    ctx.write_artifact(&pkg_path, |partial| {
+        let signing_identity = config.pkg().and_then(|p| p.signing_identity.as_ref());
+        let product_path = match signing_identity {
+            Some(_) => intermediates_path.join(&pkg_name),
+            None => partial.to_path_buf(),
+        };
+        tracing::debug!("Running productbuild");
+        Command::new("productbuild")
+            .arg("--package")
+            .arg(&component_path)
+            .arg(&product_path)
+            .output_ok()
+            .map_err(|e| {
+                if util::is_out_of_space(&e) {
+                    Error::OutOfSpace {
+                        path: pkg_path.clone(),
+                    }
+                } else {
+                    Error::ProductbuildFailed(e)
+                }
+            })?;
+
+        if let Some(identity) = signing_identity {
+            codesign::sign_installer(&product_path, partial, identity, config)?;
+
+            // notarization is required for distribution
+            match codesign::config_notarize_auth(config) {
+                Ok(auth) => {
+                    tracing::debug!("Notarizing {}", pkg_name);
+                    codesign::notarize(partial.to_path_buf(), auth, config)?;
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping pkg notarization, {}", e.to_string());
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(vec![pkg_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::PkgConfig, Config};
+
+    #[test]
+    fn it_builds_installable_pkg() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("out");
+        let macos_dir = out_dir.join("App.app/Contents/MacOS");
+        fs::create_dir_all(&macos_dir).unwrap();
+        fs::write(macos_dir.join("app"), "#!/bin/sh\necho app").unwrap();
+        fs::set_permissions(macos_dir.join("app"), fs::Permissions::from_mode(0o755)).unwrap();
+        let mut info_plist = plist::Dictionary::new();
+        info_plist.insert("CFBundleIdentifier".into(), "com.example.app".into());
+        info_plist.insert("CFBundleExecutable".into(), "app".into());
+        info_plist.insert("CFBundleVersion".into(), "1.0.0".into());
+        info_plist.insert("CFBundlePackageType".into(), "APPL".into());
+        plist::Value::Dictionary(info_plist)
+            .to_file_xml(out_dir.join("App.app/Contents/Info.plist"))
+            .unwrap();
+        let postinstall = dir.path().join("postinstall.sh");
+        fs::write(&postinstall, "#!/bin/sh\nexit 0\n").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.identifier = Some("com.example.app".into());
+        config.out_dir = out_dir;
+        config.pkg = Some(
+            PkgConfig::new()
+                .install_location("/Applications/Example")
+                .postinstall_script(postinstall),
+        );
+
+        let ctx = Context::new(&config).unwrap();
+        let pkg = package(&ctx).unwrap().remove(0);
+        assert!(pkg.is_file());
+
+        let output = Command::new("installer")
+            .arg("-pkginfo")
+            .arg("-pkg")
+            .arg(&pkg)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let output = Command::new("installer")
+            .arg("-volinfo")
+            .arg("-pkg")
+            .arg(&pkg)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+    }
+}
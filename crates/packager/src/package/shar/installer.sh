@@ -0,0 +1,77 @@
+#!/bin/sh
+# Self-extracting installer for {{product_name}} {{version}}, generated by cargo-packager.
+#
+# Usage: sh {{script_name}} [--prefix <dir>] [--uninstall]
+#
+# Installs the binaries to <prefix>/bin and the resources to <prefix>/lib/{{app_name}}.
+# The prefix defaults to the PREFIX environment variable or {{default_prefix}}.
+# Run it again with --uninstall and the same prefix to remove the installed files.
+set -eu
+
+PREFIX="${PREFIX:-{{default_prefix}}}"
+UNINSTALL=0
+
+while [ "$#" -gt 0 ]; do
+  case "$1" in
+    --prefix)
+      [ "$#" -ge 2 ] || { echo "error: --prefix requires a value" >&2; exit 1; }
+      PREFIX="$2"
+      shift 2
+      ;;
+    --prefix=*)
+      PREFIX="${1#--prefix=}"
+      shift
+      ;;
+    --uninstall)
+      UNINSTALL=1
+      shift
+      ;;
+    -h|--help)
+      echo "Usage: sh {{script_name}} [--prefix <dir>] [--uninstall]"
+      exit 0
+      ;;
+    *)
+      echo "error: unknown argument: $1" >&2
+      exit 1
+      ;;
+  esac
+done
+
+if [ "$UNINSTALL" -eq 1 ]; then
+  echo "Uninstalling {{product_name}} from $PREFIX"
+{{#each files}}
+  rm -f "$PREFIX"/'{{this}}'
+{{/each}}
+{{#each dirs}}
+  rmdir "$PREFIX"/'{{this}}' 2>/dev/null || true
+{{/each}}
+  exit 0
+fi
+
+sha256() {
+  if command -v sha256sum >/dev/null 2>&1; then
+    sha256sum "$1" | cut -d ' ' -f 1
+  elif command -v shasum >/dev/null 2>&1; then
+    shasum -a 256 "$1" | cut -d ' ' -f 1
+  else
+    echo "error: sha256sum or shasum is required to verify the installer" >&2
+    exit 1
+  fi
+}
+
+WORK_DIR="$(mktemp -d)"
+trap 'rm -rf "$WORK_DIR"' EXIT INT TERM
+
+base64 -d > "$WORK_DIR/archive.tar.gz" <<'__CARGO_PACKAGER_ARCHIVE__'
+{{archive}}
+__CARGO_PACKAGER_ARCHIVE__
+
+if [ "$(sha256 "$WORK_DIR/archive.tar.gz")" != "{{sha256}}" ]; then
+  echo "error: the embedded archive is corrupted, checksum mismatch" >&2
+  exit 1
+fi
+
+echo "Installing {{product_name}} {{version}} to $PREFIX"
+mkdir -p "$PREFIX"
+gzip -dc "$WORK_DIR/archive.tar.gz" | (cd "$PREFIX" && tar -xf -)
+echo "Installed {{product_name}}, run this installer with --uninstall to remove it"
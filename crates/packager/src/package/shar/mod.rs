@@ -0,0 +1,391 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::GzEncoder, Compression};
+use handlebars::{to_json, Handlebars};
+use tar::HeaderMode;
+
+use super::{deb, naming, Context};
+use crate::{
+    checksum,
+    config::{Config, PackageFormat},
+    util, Error,
+};
+
+/// The default installation prefix of the installer script.
+const DEFAULT_PREFIX: &str = "/usr/local";
+
+/// Copies the binaries, resources and bundled runtime into `data_dir`, laid out relative to the installation prefix.
+fn generate_data(config: &Config, data_dir: &Path) -> crate::Result<()> {
+    let bin_dir = data_dir.join("bin");
+
+    tracing::debug!("Copying binaries");
+    fs::create_dir_all(&bin_dir).map_err(|e| Error::IoWithPath(bin_dir.clone(), e))?;
+    for bin in config.binaries.iter() {
+        let bin_path = config.binary_path(bin);
+        let bin_out_path = bin_dir.join(bin.path.file_name().unwrap());
+        fs::copy(&bin_path, &bin_out_path)
+            .map_err(|e| Error::CopyFile(bin_path.clone(), bin_out_path.clone(), e))?;
+    }
+
+    tracing::debug!("Copying resources");
+    let resource_dir = data_dir.join("lib").join(config.main_binary_name()?);
+    config.copy_resources(&resource_dir, PackageFormat::Shar)?;
+
+    tracing::debug!("Copying bundled runtime");
+    config.copy_bundled_runtime(&resource_dir, PackageFormat::Shar)?;
+
+    tracing::debug!("Copying external binaries");
+    config.copy_external_binaries(&bin_dir)?;
+
+    Ok(())
+}
+
+/// Creates a `.tar.gz` of the files and symlinks of `data_dir` with deterministic metadata,
+/// and returns the relative paths of the archived files and directories.
+/// The extended attributes of the files are kept when `xattrs` is true.
+///
+/// Directories are not archived so extracting it doesn't change the
+/// permissions of existing directories like `<prefix>/bin`.
+fn create_archive(
+    data_dir: &Path,
+    dest: &Path,
+    modes: &HashMap<PathBuf, u32>,
+    xattrs: bool,
+) -> crate::Result<(Vec<String>, Vec<String>)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+
+    let gzip_encoder = GzEncoder::new(util::create_file(dest)?, Compression::default());
+    let mut tar_builder = tar::Builder::new(gzip_encoder);
+    for entry in walkdir::WalkDir::new(data_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+    {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dest_path = src_path.strip_prefix(data_dir)?;
+        let name = dest_path.to_string_lossy().into_owned();
+
+        if entry.file_type().is_dir() {
+            dirs.push(name);
+            continue;
+        }
+
+        let stat = fs::symlink_metadata(src_path)
+            .map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata_in_mode(&stat, HeaderMode::Deterministic);
+        header.set_mtime(stat.mtime() as u64);
+        if let Some(mode) = modes.get(dest_path) {
+            header.set_mode(*mode);
+        }
+
+        if xattrs {
+            util::append_tar_xattrs(&mut tar_builder, src_path)?;
+        }
+        if entry.file_type().is_symlink() {
+            let target = fs::read_link(src_path)
+                .map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+            tar_builder.append_link(&mut header, dest_path, target)?;
+        } else {
+            let mut src_file =
+                File::open(src_path).map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+            tar_builder.append_data(&mut header, dest_path, &mut src_file)?;
+        }
+        files.push(name);
+    }
+
+    let mut dest_file = tar_builder.into_inner()?.finish()?;
+    dest_file.flush()?;
+
+    Ok((files, dirs))
+}
+
+/// Escapes a path to be used inside single quotes in a shell script.
+fn shell_single_quote_escape(path: &str) -> String {
+    path.replace('\'', r"'\''")
+}
+
+/// Base64-encodes `data`, wrapping the lines at 76 characters.
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for (i, c) in encoded.chars().enumerate() {
+        if i > 0 && i % 76 == 0 {
+            out.push('\n');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[tracing::instrument(level = "trace", skip(ctx))]
+pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
+    let Context {
+        config,
+        intermediates_path,
+        ..
+    } = ctx;
+
+    let main_binary_name = config.main_binary_name()?;
+    let script_name = naming::package_file_name(config, PackageFormat::Shar)?;
+    let script_path = config.out_dir().join(&script_name);
+
+    tracing::info!("Packaging {} ({})", script_name, script_path.display());
+
+    let intermediates_path = intermediates_path.join("shar");
+    util::create_clean_dir(&intermediates_path)?;
+
+    tracing::debug!("Generating data");
+    let data_dir = intermediates_path.join("data");
+    generate_data(config, &data_dir)?;
+
+    tracing::debug!("Creating the embedded archive");
+    let archive_path = intermediates_path.join("archive.tar.gz");
+    let modes = super::file_modes(
+        config,
+        &data_dir,
+        deb::resource_modes(config, &Path::new("lib").join(&main_binary_name))?,
+    )?;
+    let (files, mut dirs) =
+        create_archive(&data_dir, &archive_path, &modes, config.preserve_xattrs).map_err(|e| {
+            util::write_error(&archive_path, "failed to create the embedded archive", e)
+        })?;
+    // `bin` and `lib` are shared with other apps installed in the same prefix,
+    // remove the deepest directories first so their parents are empty when removed
+    dirs.retain(|d| d != "bin" && d != "lib");
+    dirs.sort_by(|a, b| b.cmp(a));
+
+    let archive =
+        fs::read(&archive_path).map_err(|e| Error::IoWithPath(archive_path.clone(), e))?;
+
+    let mut data = BTreeMap::new();
+    data.insert("product_name", to_json(&config.product_name));
+    data.insert("version", to_json(&config.version));
+    data.insert("app_name", to_json(&main_binary_name));
+    data.insert("script_name", to_json(&script_name));
+    data.insert(
+        "default_prefix",
+        to_json(
+            config
+                .shar()
+                .and_then(|s| s.prefix.as_deref())
+                .unwrap_or(DEFAULT_PREFIX),
+        ),
+    );
+    data.insert(
+        "files",
+        to_json(
+            files
+                .iter()
+                .map(|f| shell_single_quote_escape(f))
+                .collect::<Vec<_>>(),
+        ),
+    );
+    data.insert(
+        "dirs",
+        to_json(
+            dirs.iter()
+                .map(|d| shell_single_quote_escape(d))
+                .collect::<Vec<_>>(),
+        ),
+    );
+    data.insert("sha256", to_json(checksum::sha256_file(&archive_path)?));
+    data.insert("archive", to_json(base64_lines(&archive)));
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars
+        .register_template_string("installer.sh", include_str!("installer.sh"))
+        .map_err(Box::new)?;
+    let script = handlebars.render("installer.sh", &data)?;
+
+    tracing::debug!("Writing {}", script_path.display());
+    ctx.write_artifact(&script_path, |partial| {
+        let write = || -> crate::Result<()> {
+            let mut script_file = util::create_file(partial)?;
+            script_file.write_all(script.as_bytes())?;
+            script_file.flush()?;
+            Ok(())
+        };
+        write().map_err(|e| {
+            util::write_error(&script_path, "failed to write the installer script", e)
+        })?;
+        fs::set_permissions(partial, fs::Permissions::from_mode(0o755))
+            .map_err(|e| Error::IoWithPath(partial.to_path_buf(), e))
+    })?;
+
+    Ok(vec![script_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Binary, Resource};
+    use std::process::Command;
+
+    #[test]
+    fn it_installs_and_uninstalls() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(bin_dir.join("assets")).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh\necho app").unwrap();
+        fs::set_permissions(bin_dir.join("app"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(bin_dir.join("assets/it's.txt"), "data").unwrap();
+        fs::write(bin_dir.join("assets/run.sh"), "#!/bin/sh").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir.clone());
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.resources = Some(vec![
+            Resource::Single(bin_dir.join("assets/it's.txt").display().to_string()),
+            Resource::Mapped {
+                src: bin_dir.join("assets/run.sh").display().to_string(),
+                target: "scripts/run.sh".into(),
+                mode: Some("0755".into()),
+                optional: false,
+            },
+        ]);
+
+        let ctx = Context::new(&config).unwrap();
+        let script = package(&ctx).unwrap().remove(0);
+
+        let prefix = dir.path().join("prefix");
+        fs::create_dir_all(prefix.join("bin")).unwrap();
+        fs::write(prefix.join("bin/other"), "").unwrap();
+
+        let output = Command::new("sh")
+            .arg(&script)
+            .arg("--prefix")
+            .arg(&prefix)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+
+        let mode = |p: &str| fs::metadata(prefix.join(p)).unwrap().permissions().mode() & 0o777;
+        assert_eq!(
+            fs::read_to_string(prefix.join("bin/app")).unwrap(),
+            "#!/bin/sh\necho app"
+        );
+        assert_eq!(mode("bin/app"), 0o755);
+        assert_eq!(
+            fs::read_to_string(prefix.join("lib/app/it's.txt")).unwrap(),
+            "data"
+        );
+        assert_eq!(mode("lib/app/it's.txt"), 0o644);
+        assert_eq!(mode("lib/app/scripts/run.sh"), 0o755);
+
+        // a corrupted archive is rejected
+        let corrupted = dir.path().join("corrupted.sh");
+        let content = fs::read_to_string(&script).unwrap();
+        let archive_line = content
+            .lines()
+            .skip_while(|l| !l.starts_with("base64 -d"))
+            .nth(1)
+            .unwrap();
+        let mut corrupted_line = archive_line.to_string();
+        let first = if corrupted_line.starts_with('A') {
+            "B"
+        } else {
+            "A"
+        };
+        corrupted_line.replace_range(0..1, first);
+        fs::write(
+            &corrupted,
+            content.replacen(archive_line, &corrupted_line, 1),
+        )
+        .unwrap();
+        let output = Command::new("sh")
+            .arg(&corrupted)
+            .arg("--prefix")
+            .arg(dir.path().join("corrupted-prefix"))
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+
+        let output = Command::new("sh")
+            .arg(&script)
+            .arg("--uninstall")
+            .env("PREFIX", &prefix)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        assert!(!prefix.join("bin/app").exists());
+        assert!(!prefix.join("lib/app").exists());
+        assert!(prefix.join("bin/other").exists());
+    }
+
+    #[test]
+    fn it_installs_bundled_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        let runtime_dir = dir.path().join("runtime");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::create_dir_all(runtime_dir.join("bin")).unwrap();
+        fs::create_dir_all(runtime_dir.join("lib")).unwrap();
+        fs::write(bin_dir.join("app"), "#!/bin/sh").unwrap();
+        let files = [
+            ("bin/java", 0o755),
+            ("bin/keytool", 0o644),
+            ("lib/jspawnhelper", 0o755),
+            ("lib/libjvm.so", 0o644),
+        ];
+        for (file, mode) in files {
+            fs::write(runtime_dir.join(file), file).unwrap();
+            fs::set_permissions(runtime_dir.join(file), fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir.clone());
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.bundled_runtime = Some(runtime_dir);
+
+        let ctx = Context::new(&config).unwrap();
+        let script = package(&ctx).unwrap().remove(0);
+
+        let prefix = dir.path().join("prefix");
+        let output = Command::new("sh")
+            .arg(&script)
+            .arg("--prefix")
+            .arg(&prefix)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+
+        let mode = |p: &str| fs::metadata(prefix.join(p)).unwrap().permissions().mode() & 0o777;
+        // the files of the `bin` directory are executable, the other files keep their mode
+        assert_eq!(mode("lib/app/runtime/bin/java"), 0o755);
+        assert_eq!(mode("lib/app/runtime/bin/keytool"), 0o755);
+        assert_eq!(mode("lib/app/runtime/lib/jspawnhelper"), 0o755);
+        assert_eq!(mode("lib/app/runtime/lib/libjvm.so"), 0o644);
+        assert_eq!(
+            fs::read_to_string(prefix.join("lib/app/runtime/bin/java")).unwrap(),
+            "bin/java"
+        );
+
+        config.bundled_runtime = Some(dir.path().join("missing"));
+        assert!(matches!(
+            package(&Context::new(&config).unwrap()),
+            Err(Error::DoesNotExist(_))
+        ));
+    }
+}
@@ -0,0 +1,363 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Detection of the shared libraries linked by the main binary
+//! that won't be present on a clean target system.
+
+use std::{collections::BTreeSet, path::Path, process::Command};
+
+use crate::{
+    config::{Config, SharedLibrariesCheck},
+    shell::CommandExt,
+    Error, PackageFormat,
+};
+
+/// The libraries expected on every glibc based Linux system,
+/// and the graphics and desktop stack the Linux apps link to.
+const LINUX_BASELINE: &[&str] = &[
+    "linux-vdso",
+    "linux-gate",
+    "ld-linux",
+    "ld-linux-x86-64",
+    "ld-linux-aarch64",
+    "ld-linux-armhf",
+    "libc",
+    "libm",
+    "libdl",
+    "libpthread",
+    "librt",
+    "libutil",
+    "libresolv",
+    "libgcc_s",
+    "libstdc++",
+    "libz",
+    "libX11",
+    "libX11-xcb",
+    "libxcb",
+    "libXext",
+    "libXrender",
+    "libXrandr",
+    "libXi",
+    "libXcursor",
+    "libxkbcommon",
+    "libwayland-client",
+    "libwayland-cursor",
+    "libwayland-egl",
+    "libGL",
+    "libEGL",
+    "libGLX",
+    "libOpenGL",
+    "libvulkan",
+    "libasound",
+    "libpulse",
+    "libfontconfig",
+    "libfreetype",
+    "libdbus-1",
+    "libglib-2.0",
+    "libgobject-2.0",
+    "libgio-2.0",
+    "libgmodule-2.0",
+    "libgtk-3",
+    "libgdk-3",
+    "libgdk_pixbuf-2.0",
+    "libcairo",
+    "libcairo-gobject",
+    "libpango-1.0",
+    "libpangocairo-1.0",
+    "libatk-1.0",
+    "libharfbuzz",
+    "libsoup-2.4",
+    "libsoup-3.0",
+    "libwebkit2gtk-4.0",
+    "libwebkit2gtk-4.1",
+    "libjavascriptcoregtk-4.0",
+    "libjavascriptcoregtk-4.1",
+];
+
+/// A shared library linked by a binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Library {
+    /// The name the binary links the library with,
+    /// the soname on Linux or the install name on macOS.
+    name: String,
+    /// Whether the dynamic linker found the library on this machine.
+    found: bool,
+}
+
+/// Parses the output of `ldd`.
+fn parse_ldd(output: &str) -> Vec<Library> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (name, resolved) = match line.split_once("=>") {
+                Some((name, resolved)) => (name.trim(), resolved.trim()),
+                None => (line.split_whitespace().next()?, ""),
+            };
+            // `ldd` lists the interpreter by its path
+            let name = Path::new(name).file_name()?.to_string_lossy().into_owned();
+            Some(Library {
+                name,
+                found: resolved != "not found",
+            })
+        })
+        .collect()
+}
+
+/// Parses the output of `otool -L`, skipping the first line naming the binary.
+fn parse_otool(output: &str) -> Vec<Library> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let name = line.trim().split(" (").next()?.trim();
+            (!name.is_empty()).then(|| Library {
+                name: name.to_string(),
+                found: true,
+            })
+        })
+        .collect()
+}
+
+/// Whether a Linux library soname like `libgtk-3.so.0` is `name` or one of its versions.
+fn matches_library(soname: &str, name: &str) -> bool {
+    soname == name
+        || soname
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with(".so"))
+}
+
+/// Returns the libraries that won't be present on a clean target system and aren't bundled.
+fn unavailable_libraries(
+    libraries: Vec<Library>,
+    macos: bool,
+    allowed: &[String],
+    bundled: &BTreeSet<String>,
+) -> Vec<String> {
+    libraries
+        .into_iter()
+        .filter(|library| {
+            let file_name = library.name.rsplit('/').next().unwrap_or(&library.name);
+            if bundled.contains(file_name) {
+                return false;
+            }
+            if allowed.iter().any(|a| matches_library(file_name, a)) {
+                return false;
+            }
+            if !library.found {
+                return true;
+            }
+            if macos {
+                // the libraries relative to the app bundle, like `@rpath/libfoo.dylib`,
+                // must be bundled and were checked above
+                !(library.name.starts_with("/usr/lib/") || library.name.starts_with("/System/"))
+            } else {
+                !LINUX_BASELINE.iter().any(|b| matches_library(file_name, b))
+            }
+        })
+        .map(|library| library.name)
+        .collect()
+}
+
+/// Returns the file names of the resources and frameworks bundled with the app.
+fn bundled_files(config: &Config) -> crate::Result<BTreeSet<String>> {
+    let mut bundled = BTreeSet::new();
+    for resource in config.resources()? {
+        if let Some(name) = resource.target.file_name() {
+            bundled.insert(name.to_string_lossy().into_owned());
+        }
+    }
+    for framework in config
+        .macos()
+        .and_then(|m| m.frameworks.as_ref())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(name) = Path::new(framework).file_name() {
+            let name = name.to_string_lossy();
+            bundled.insert(name.trim_end_matches(".framework").to_string());
+        }
+    }
+    Ok(bundled)
+}
+
+/// Checks the shared libraries linked by the main binary with `ldd` or `otool -L`
+/// when [`Config::shared_libraries_check`] is set, for the formats that don't bundle them.
+///
+/// The libraries that won't be present on a clean target system and aren't bundled
+/// are reported with a warning, or fail the packaging with [`Error::UnavailableSharedLibraries`]
+/// when the check is [`SharedLibrariesCheck::Error`].
+pub(crate) fn check(config: &Config, formats: &[PackageFormat]) -> crate::Result<()> {
+    let Some(check) = config.shared_libraries_check else {
+        return Ok(());
+    };
+
+    let macos = cfg!(target_os = "macos");
+    let checked = formats.iter().any(|f| match f {
+        PackageFormat::App | PackageFormat::Dmg | PackageFormat::Pkg => macos,
+        // linuxdeploy bundles the libraries of the AppImage
+        PackageFormat::Deb | PackageFormat::Pacman | PackageFormat::Shar => !macos,
+        _ => false,
+    });
+    if !checked || cfg!(windows) {
+        return Ok(());
+    }
+
+    let binary = config.binary_path(config.main_binary()?);
+    let tool = if macos { "otool" } else { "ldd" };
+    let mut cmd = Command::new(tool);
+    if macos {
+        cmd.arg("-L");
+    }
+    let output = match cmd.arg(&binary).output_ok() {
+        Ok(output) => output,
+        Err(e) => {
+            tracing::warn!(
+                "Skipping the shared libraries check, failed to run `{tool}` on {}: {e}",
+                binary.display()
+            );
+            return Ok(());
+        }
+    };
+    let output = String::from_utf8_lossy(&output.stdout);
+    let libraries = if macos {
+        parse_otool(&output)
+    } else {
+        parse_ldd(&output)
+    };
+
+    let allowed = config
+        .allowed_shared_libraries
+        .as_deref()
+        .unwrap_or_default();
+    let unavailable = unavailable_libraries(libraries, macos, allowed, &bundled_files(config)?);
+    if unavailable.is_empty() {
+        return Ok(());
+    }
+
+    match check {
+        SharedLibrariesCheck::Warn => {
+            tracing::warn!(
+                "{} links shared libraries that won't be present on a clean target system and aren't bundled: {}",
+                binary.display(),
+                unavailable.join(", ")
+            );
+            Ok(())
+        }
+        SharedLibrariesCheck::Error => Err(Error::UnavailableSharedLibraries(binary, unavailable)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_unavailable_linux_libraries() {
+        let libraries = parse_ldd(
+            "	linux-vdso.so.1 (0x00007ffd)
+	libgtk-3.so.0 => /lib/x86_64-linux-gnu/libgtk-3.so.0 (0x00007f)
+	libunusual.so.2 => /opt/unusual/lib/libunusual.so.2 (0x00007f)
+	libmissing.so.1 => not found
+	libbundled.so => /opt/lib/libbundled.so (0x00007f)
+	libc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f)
+	/lib64/ld-linux-x86-64.so.2 (0x00007f)",
+        );
+        assert_eq!(libraries.len(), 7);
+        assert!(!libraries[3].found);
+
+        let bundled = BTreeSet::from(["libbundled.so".to_string()]);
+        assert_eq!(
+            unavailable_libraries(libraries.clone(), false, &[], &bundled),
+            ["libunusual.so.2", "libmissing.so.1"]
+        );
+        assert_eq!(
+            unavailable_libraries(libraries, false, &["libunusual".into()], &bundled),
+            ["libmissing.so.1"]
+        );
+    }
+
+    #[test]
+    fn it_detects_unavailable_macos_libraries() {
+        let libraries = parse_otool(
+            "target/release/app:
+	/System/Library/Frameworks/AppKit.framework/Versions/C/AppKit (compatibility version 45.0.0, current version 2299.0.0)
+	/usr/lib/libSystem.B.dylib (compatibility version 1.0.0, current version 1336.0.0)
+	/opt/homebrew/opt/openssl@3/lib/libssl.3.dylib (compatibility version 3.0.0, current version 3.0.0)
+	@rpath/Sparkle.framework/Versions/B/Sparkle (compatibility version 1.6.0, current version 2.0.0)
+	@rpath/libhelper.dylib (compatibility version 1.0.0, current version 1.0.0)",
+        );
+        assert_eq!(libraries.len(), 5);
+
+        let bundled = BTreeSet::from(["Sparkle".to_string()]);
+        assert_eq!(
+            unavailable_libraries(libraries, true, &[], &bundled),
+            [
+                "/opt/homebrew/opt/openssl@3/lib/libssl.3.dylib",
+                "@rpath/libhelper.dylib"
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_checks_binary_linking_unusual_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib_dir = dir.path().join("lib");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        std::fs::write(
+            dir.path().join("unusual.c"),
+            "int unusual() { return 0; }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("main.c"),
+            "int unusual();\nint main() { return unusual(); }\n",
+        )
+        .unwrap();
+        let compiled = Command::new("cc")
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(lib_dir.join("libunusual.so"))
+            .arg(dir.path().join("unusual.c"))
+            .output_ok()
+            .and_then(|_| {
+                Command::new("cc")
+                    .arg("-o")
+                    .arg(dir.path().join("app"))
+                    .arg(dir.path().join("main.c"))
+                    .arg("-L")
+                    .arg(&lib_dir)
+                    .arg("-lunusual")
+                    .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+                    .output_ok()
+            });
+        if compiled.is_err() {
+            eprintln!("skipping, a C compiler is required to build the fixture");
+            return;
+        }
+
+        let mut config = Config::default();
+        config.out_dir = dir.path().into();
+        config.binaries = vec![crate::config::Binary::new("app").main(true)];
+        config.shared_libraries_check = Some(SharedLibrariesCheck::Error);
+        let err = check(&config, &[PackageFormat::Deb]).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::UnavailableSharedLibraries(_, libraries) if libraries == &["libunusual.so"]
+            ),
+            "{err}"
+        );
+
+        // the AppImage bundles its libraries
+        check(&config, &[PackageFormat::AppImage]).unwrap();
+
+        config.shared_libraries_check = Some(SharedLibrariesCheck::Warn);
+        check(&config, &[PackageFormat::Deb]).unwrap();
+
+        config.shared_libraries_check = Some(SharedLibrariesCheck::Error);
+        config.allowed_shared_libraries = Some(vec!["libunusual".into()]);
+        check(&config, &[PackageFormat::Deb]).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Checks of the external tools and downloaded runtime files against their [`Config::pinned_tools`](crate::Config::pinned_tools).
+//!
+//! The resolved versions and digests are recorded in the [`Context`]
+//! and end up in the [`PackageOutput::tools`](crate::PackageOutput::tools) of the package being built.
+
+use std::path::{Path, PathBuf};
+
+use super::Context;
+use crate::{checksum, Error};
+
+/// Returns the pinned path of `tool`, used instead of the one found in `PATH` or downloaded.
+pub(crate) fn pinned_path(ctx: &Context, tool: &str) -> Option<PathBuf> {
+    ctx.config.tool_pin(tool).and_then(|pin| pin.path.clone())
+}
+
+/// Parses the version in the output of a version command,
+/// the first word made of digits and dots after an optional `v`, like `v3.09` or `3.11.2.4516`.
+pub(crate) fn parse_version(tool: &str, output: &str) -> crate::Result<String> {
+    output
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| {
+            word.starts_with(|c: char| c.is_ascii_digit())
+                && word.contains('.')
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+        })
+        .map(ToString::to_string)
+        .ok_or_else(|| Error::UnknownToolVersion(tool.to_string(), output.trim().to_string()))
+}
+
+/// Compares the `version` reported by `tool` with its pinned version, ignoring a leading `v`, and records it.
+pub(crate) fn check_version(ctx: &Context, tool: &str, version: &str) -> crate::Result<()> {
+    let version = version.trim_start_matches('v');
+    if let Some(expected) = ctx.config.tool_pin(tool).and_then(|p| p.version.as_deref()) {
+        if expected.trim_start_matches('v') != version {
+            return Err(Error::ToolVersionMismatch {
+                tool: tool.to_string(),
+                expected: expected.to_string(),
+                found: version.to_string(),
+            });
+        }
+    }
+    tracing::debug!("Using {tool} {version}");
+    ctx.tools
+        .borrow_mut()
+        .insert(tool.to_string(), version.to_string());
+    Ok(())
+}
+
+/// Compares the SHA-256 digest of the `path` file of `tool` with its pinned digest and records it.
+#[cfg_attr(
+    not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn check_file(ctx: &Context, tool: &str, path: &Path) -> crate::Result<()> {
+    check_digest(ctx, tool, &checksum::sha256_file(path)?)
+}
+
+/// Compares the SHA-256 hex `digest` of the file of `tool` with its pinned digest and records it.
+#[cfg_attr(
+    not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn check_digest(ctx: &Context, tool: &str, digest: &str) -> crate::Result<()> {
+    if let Some(expected) = ctx.config.tool_pin(tool).and_then(|p| p.sha256.as_deref()) {
+        if !expected.eq_ignore_ascii_case(digest) {
+            return Err(Error::ToolVersionMismatch {
+                tool: tool.to_string(),
+                expected: format!("sha256:{expected}"),
+                found: format!("sha256:{digest}"),
+            });
+        }
+    }
+    ctx.tools
+        .borrow_mut()
+        .insert(tool.to_string(), format!("sha256:{digest}"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ToolPin, Config};
+
+    fn context(pins: Vec<(&str, ToolPin)>) -> (tempfile::TempDir, Context) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.out_dir = dir.path().to_path_buf();
+        config.pinned_tools = Some(
+            pins.into_iter()
+                .map(|(name, pin)| (name.to_string(), pin))
+                .collect(),
+        );
+        let ctx = Context::new(&config).unwrap();
+        (dir, ctx)
+    }
+
+    #[test]
+    fn it_parses_tool_versions() {
+        assert_eq!(parse_version("makensis", "v3.09\n").unwrap(), "3.09");
+        assert_eq!(
+            parse_version(
+                "wix",
+                "Windows Installer XML Toolset Compiler version 3.11.2.4516\nCopyright (c) .NET Foundation and contributors. All rights reserved.\n"
+            )
+            .unwrap(),
+            "3.11.2.4516"
+        );
+        assert!(matches!(
+            parse_version("makensis", "command not found"),
+            Err(Error::UnknownToolVersion(..))
+        ));
+    }
+
+    #[test]
+    fn it_detects_mismatched_tool_versions() {
+        let (dir, ctx) = context(vec![
+            ("makensis", ToolPin::new().version("v3.09")),
+            ("apprun", ToolPin::new().sha256("00".repeat(32))),
+        ]);
+
+        check_version(&ctx, "makensis", "3.09").unwrap();
+        assert!(matches!(
+            check_version(&ctx, "makensis", "v3.10"),
+            Err(Error::ToolVersionMismatch { tool, expected, found })
+                if tool == "makensis" && expected == "v3.09" && found == "3.10"
+        ));
+        // unpinned tools are only recorded
+        check_version(&ctx, "wix", "3.11.2.4516").unwrap();
+
+        let apprun = dir.path().join("AppRun-x86_64");
+        std::fs::write(&apprun, "AppRun").unwrap();
+        assert!(matches!(
+            check_file(&ctx, "apprun", &apprun),
+            Err(Error::ToolVersionMismatch { tool, .. }) if tool == "apprun"
+        ));
+        let linuxdeploy = dir.path().join("linuxdeploy-x86_64.AppImage");
+        std::fs::write(&linuxdeploy, "linuxdeploy").unwrap();
+        check_file(&ctx, "linuxdeploy", &linuxdeploy).unwrap();
+
+        let tools = ctx.tools.take();
+        assert_eq!(tools["makensis"], "3.09");
+        assert_eq!(tools["wix"], "3.11.2.4516");
+        assert_eq!(
+            tools["linuxdeploy"],
+            format!("sha256:{}", checksum::sha256_file(&linuxdeploy).unwrap())
+        );
+        assert!(!tools.contains_key("apprun"));
+    }
+
+    #[test]
+    fn it_validates_pinned_tools() {
+        let (_dir, ctx) = context(vec![("mksquashfs", ToolPin::new().version("4.6"))]);
+        assert!(matches!(
+            ctx.config.validate_pinned_tools(),
+            Err(Error::InvalidToolPin(tool, _)) if tool == "mksquashfs"
+        ));
+        for (name, pin) in [
+            ("wix", ToolPin::new().sha256("00".repeat(32))),
+            ("linuxdeploy", ToolPin::new().version("1-alpha")),
+        ] {
+            let (_dir, ctx) = context(vec![(name, pin)]);
+            assert!(ctx.config.validate_pinned_tools().is_err(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Structural validation of the packages produced by external tools,
+//! which may exit successfully after a partial failure and leave a corrupt package behind.
+
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+use crate::{elf::Elf, Config, Error, PackageFormat};
+
+/// The magic number of the squashfs superblock, `hsqs`.
+const SQUASHFS_MAGIC: &[u8] = b"hsqs";
+/// The size of the squashfs superblock.
+const SQUASHFS_SUPERBLOCK_SIZE: usize = 96;
+/// The size of the UDIF trailer at the end of the disk images.
+const KOLY_SIZE: u64 = 512;
+
+type Validation = Result<(), String>;
+
+/// Reads exactly `buf.len()` bytes at `offset`, describing `what` was truncated otherwise.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, buf: &mut [u8], what: &str) -> Validation {
+    reader
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| reader.read_exact(buf))
+        .map_err(|e| format!("failed to read the {what} at offset {offset}: {e}"))
+}
+
+fn file_len<R: Seek>(reader: &mut R) -> Result<u64, String> {
+    reader
+        .seek(SeekFrom::End(0))
+        .map_err(|e| format!("failed to read the file size: {e}"))
+}
+
+/// Checks the `ar` archive of a Debian package and its `debian-binary`, `control.tar` and `data.tar` members.
+///
+/// See <https://manpages.debian.org/unstable/dpkg-dev/deb.5.en.html>
+fn validate_deb<R: Read + Seek>(reader: &mut R) -> Validation {
+    let len = file_len(reader)?;
+    let mut magic = [0; 8];
+    read_at(reader, 0, &mut magic, "ar magic")?;
+    if &magic != b"!<arch>\n" {
+        return Err("not an ar archive".into());
+    }
+
+    let mut members = Vec::new();
+    let mut offset = 8;
+    while offset < len {
+        let mut header = [0; 60];
+        read_at(reader, offset, &mut header, "ar member header")?;
+        if &header[58..] != b"`\n" {
+            return Err(format!("invalid ar member header at offset {offset}"));
+        }
+        let name = String::from_utf8_lossy(&header[..16])
+            .trim_end()
+            .trim_end_matches('/')
+            .to_string();
+        let size = std::str::from_utf8(&header[48..58])
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .ok_or_else(|| format!("invalid size of the `{name}` member"))?;
+        let start = offset + 60;
+        if start + size > len {
+            return Err(format!(
+                "the `{name}` member is truncated, {size} bytes expected but {} found",
+                len - start
+            ));
+        }
+        if members.is_empty() {
+            let mut version = vec![0; size.min(16) as usize];
+            read_at(reader, start, &mut version, "debian-binary member")?;
+            if name != "debian-binary" || !version.starts_with(b"2.") {
+                return Err(
+                    "the first member must be `debian-binary` with the format version 2".into(),
+                );
+            }
+        }
+        members.push(name);
+        // the members are aligned to 2 bytes
+        offset = start + size + (start + size) % 2;
+    }
+
+    for member in ["control.tar", "data.tar"] {
+        if !members.iter().any(|m| m.starts_with(member)) {
+            return Err(format!("the `{member}` member is missing"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the squashfs image appended to the runtime of an AppImage.
+///
+/// See <https://dr-emann.github.io/squashfs/squashfs.html#_the_superblock>
+fn validate_appimage<R: Read + Seek>(reader: &mut R) -> Validation {
+    let len = file_len(reader)?;
+    // the ELF header holds the location of the section headers, which end the runtime
+    let mut header = [0; 64];
+    read_at(reader, 0, &mut header, "ELF header")?;
+    let runtime_size = Elf::parse(&header)?
+        .size()
+        .ok_or("invalid ELF header of the runtime")? as u64;
+
+    let mut superblock = [0; SQUASHFS_SUPERBLOCK_SIZE];
+    read_at(reader, runtime_size, &mut superblock, "squashfs superblock")?;
+    if &superblock[..4] != SQUASHFS_MAGIC {
+        return Err(format!(
+            "no squashfs image after the runtime, at offset {runtime_size}"
+        ));
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([superblock[offset], superblock[offset + 1]]);
+    let (major, minor) = (u16_at(28), u16_at(30));
+    if major != 4 {
+        return Err(format!("unsupported squashfs version {major}.{minor}"));
+    }
+    let block_size = u32::from_le_bytes(superblock[12..16].try_into().unwrap());
+    if u32::checked_shl(1, u16_at(22).into()) != Some(block_size) {
+        return Err(format!("invalid squashfs block size {block_size}"));
+    }
+    let bytes_used = u64::from_le_bytes(superblock[40..48].try_into().unwrap());
+    if runtime_size + bytes_used > len {
+        return Err(format!(
+            "the squashfs image is truncated, {bytes_used} bytes expected but {} found",
+            len - runtime_size
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the UDIF trailer of a disk image and the data fork and property list it references.
+///
+/// See <http://newosxbook.com/DMG.html>
+fn validate_dmg<R: Read + Seek>(reader: &mut R) -> Validation {
+    let len = file_len(reader)?;
+    if len < KOLY_SIZE {
+        return Err("the disk image is smaller than its trailer".into());
+    }
+    let mut koly = [0; KOLY_SIZE as usize];
+    read_at(reader, len - KOLY_SIZE, &mut koly, "UDIF trailer")?;
+    if &koly[..4] != b"koly" {
+        return Err("no UDIF trailer at the end of the disk image".into());
+    }
+    let u64_at = |offset: usize| u64::from_be_bytes(koly[offset..offset + 8].try_into().unwrap());
+    for (what, offset, length) in [
+        ("data fork", u64_at(0x18), u64_at(0x20)),
+        ("property list", u64_at(0xD8), u64_at(0xE0)),
+    ] {
+        if offset
+            .checked_add(length)
+            .is_none_or(|end| end > len - KOLY_SIZE)
+        {
+            return Err(format!(
+                "the {what} at offset {offset} of {length} bytes is past the end of the disk image"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validates the structure of the packages of the formats built by external tools
+/// unless [`Config::validate_artifacts`] is disabled, failing with [`Error::CorruptArtifact`].
+pub(crate) fn artifacts(
+    config: &Config,
+    format: PackageFormat,
+    paths: &[PathBuf],
+) -> crate::Result<()> {
+    if !config.validate_artifacts.unwrap_or(true) {
+        return Ok(());
+    }
+    let validate: fn(&mut BufReader<File>) -> Validation = match format {
+        PackageFormat::Deb => validate_deb,
+        PackageFormat::AppImage => validate_appimage,
+        PackageFormat::Dmg => validate_dmg,
+        _ => return Ok(()),
+    };
+
+    for path in paths.iter().filter(|p| p.is_file()) {
+        tracing::debug!("Validating {}", path.display());
+        let corrupt = |detail| Error::CorruptArtifact {
+            path: path.clone(),
+            format,
+            detail,
+        };
+        let file = File::open(path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+        validate(&mut BufReader::new(file)).map_err(corrupt)?;
+
+        // the disk image must also be mountable, which `hdiutil verify` checks with its checksums
+        #[cfg(target_os = "macos")]
+        if format == PackageFormat::Dmg {
+            use crate::shell::CommandExt;
+            std::process::Command::new("hdiutil")
+                .arg("verify")
+                .arg(path)
+                .output_ok()
+                .map_err(|e| corrupt(format!("`hdiutil verify` failed: {e}")))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn ar_member(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut member = format!(
+            "{name:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            0,
+            0,
+            0,
+            100644,
+            data.len()
+        )
+        .into_bytes();
+        member.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            member.push(b'\n');
+        }
+        member
+    }
+
+    #[test]
+    fn it_detects_truncated_debs() {
+        let mut deb = b"!<arch>\n".to_vec();
+        deb.extend(ar_member("debian-binary", b"2.0\n"));
+        deb.extend(ar_member("control.tar.gz", &[1; 121]));
+        deb.extend(ar_member("data.tar.gz", &[2; 1000]));
+        validate_deb(&mut Cursor::new(&deb)).unwrap();
+
+        let truncated = &deb[..deb.len() - 100];
+        let err = validate_deb(&mut Cursor::new(truncated)).unwrap_err();
+        assert!(err.contains("`data.tar.gz` member is truncated"), "{err}");
+
+        let missing_data = &deb[..deb.len() - 1060];
+        let err = validate_deb(&mut Cursor::new(missing_data)).unwrap_err();
+        assert!(err.contains("`data.tar` member is missing"), "{err}");
+
+        assert!(validate_deb(&mut Cursor::new(b"not an archive")).is_err());
+    }
+
+    #[test]
+    fn it_detects_truncated_appimages() {
+        // an ELF64 header whose section headers end at offset 64
+        let mut appimage = vec![0; 64];
+        appimage[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        appimage[0x28] = 64;
+        appimage[0x3A] = 64;
+        let mut superblock = [0; SQUASHFS_SUPERBLOCK_SIZE];
+        superblock[..4].copy_from_slice(SQUASHFS_MAGIC);
+        superblock[12..16].copy_from_slice(&(128 * 1024u32).to_le_bytes());
+        superblock[22] = 17;
+        superblock[28] = 4;
+        superblock[40..48].copy_from_slice(&4096u64.to_le_bytes());
+        appimage.extend(superblock);
+        appimage.resize(64 + 4096, 0);
+        validate_appimage(&mut Cursor::new(&appimage)).unwrap();
+
+        let err = validate_appimage(&mut Cursor::new(&appimage[..2000])).unwrap_err();
+        assert!(err.contains("squashfs image is truncated"), "{err}");
+
+        let err = validate_appimage(&mut Cursor::new(&appimage[..100])).unwrap_err();
+        assert!(err.contains("squashfs superblock"), "{err}");
+    }
+
+    #[test]
+    fn it_detects_truncated_dmgs() {
+        let mut dmg = vec![0; 10_000];
+        let mut koly = [0; KOLY_SIZE as usize];
+        koly[..4].copy_from_slice(b"koly");
+        koly[0x20..0x28].copy_from_slice(&9000u64.to_be_bytes());
+        koly[0xD8..0xE0].copy_from_slice(&9000u64.to_be_bytes());
+        koly[0xE0..0xE8].copy_from_slice(&1000u64.to_be_bytes());
+        dmg.extend(koly);
+        validate_dmg(&mut Cursor::new(&dmg)).unwrap();
+
+        // the trailer is lost when the disk image is truncated
+        let err = validate_dmg(&mut Cursor::new(&dmg[..dmg.len() - 1000])).unwrap_err();
+        assert!(err.contains("no UDIF trailer"), "{err}");
+
+        let mut truncated = dmg[..5000].to_vec();
+        truncated.extend(koly);
+        let err = validate_dmg(&mut Cursor::new(&truncated)).unwrap_err();
+        assert!(err.contains("data fork at offset 0"), "{err}");
+    }
+
+    #[test]
+    fn it_fails_with_corrupt_artifact() {
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("app.deb");
+        std::fs::write(&deb, b"!<arch>\n").unwrap();
+
+        let mut config = Config::default();
+        assert!(matches!(
+            artifacts(&config, PackageFormat::Deb, std::slice::from_ref(&deb)),
+            Err(Error::CorruptArtifact { format: PackageFormat::Deb, detail, .. })
+                if detail.contains("`control.tar` member is missing")
+        ));
+        // the formats built without external tools aren't validated
+        artifacts(&config, PackageFormat::Shar, std::slice::from_ref(&deb)).unwrap();
+
+        config.validate_artifacts = Some(false);
+        artifacts(&config, PackageFormat::Deb, &[deb]).unwrap();
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Embedding of the `VERSIONINFO` resource into the Windows main binary,
+//! shown in the Details tab of the `.exe` Properties dialog,
+//! and of the `longPathAware` setting into its application manifest.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use editpe::{
+    types::{VersionU16, VersionU32},
+    Image, VersionInfo, VersionStringTable,
+};
+
+use crate::{Config, Error};
+
+/// The `VERSIONINFO` language and code page of the strings, US English and Unicode.
+const LANGUAGE: u16 = 0x0409;
+const CODE_PAGE: u16 = 0x04B0;
+
+/// Returns the `(major, minor, patch, build)` numbers of a semver version.
+fn version_numbers(version: &str) -> crate::Result<[u16; 4]> {
+    let version = semver::Version::parse(version)?;
+    let build = if version.build.is_empty() {
+        0
+    } else {
+        version
+            .build
+            .parse()
+            .map_err(|_| Error::NonNumericBuildMetadata(None))?
+    };
+    let number = |n: u64| u16::try_from(n).map_err(|_| Error::VersionInfoNumberTooLarge(n));
+    Ok([
+        number(version.major)?,
+        number(version.minor)?,
+        number(version.patch)?,
+        number(build)?,
+    ])
+}
+
+/// Builds the `VERSIONINFO` resource of the main binary from the config.
+fn version_info(config: &Config) -> crate::Result<VersionInfo> {
+    let [major, minor, patch, build] = version_numbers(&config.version)?;
+    let version = VersionU32 {
+        major: (u32::from(major) << 16) | u32::from(minor),
+        minor: (u32::from(patch) << 16) | u32::from(build),
+    };
+    let main_binary_name = format!("{}.exe", config.main_binary_name()?);
+
+    let mut strings = vec![
+        ("CompanyName", config.publisher()),
+        (
+            "FileDescription",
+            config
+                .description
+                .clone()
+                .unwrap_or_else(|| config.product_name.clone()),
+        ),
+        ("FileVersion", config.version.clone()),
+        ("InternalName", main_binary_name.clone()),
+        ("OriginalFilename", main_binary_name),
+        ("ProductName", config.product_name.clone()),
+        ("ProductVersion", config.version.clone()),
+    ];
+    if let Some(copyright) = &config.copyright {
+        strings.push(("LegalCopyright", copyright.clone()));
+    }
+    if let Some(build_id) = &config.build_id {
+        strings.push(("BuildId", build_id.clone()));
+    }
+
+    let mut info = VersionInfo::default();
+    info.info.file_version = version;
+    info.info.product_version = version;
+    info.strings.push(VersionStringTable {
+        key: format!("{LANGUAGE:04X}{CODE_PAGE:04X}"),
+        strings: strings
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    });
+    info.vars.push(VersionU16 {
+        major: LANGUAGE,
+        minor: CODE_PAGE,
+    });
+    Ok(info)
+}
+
+/// The `longPathAware` setting of the application manifest.
+const LONG_PATH_AWARE: &str = r#"<longPathAware xmlns="http://schemas.microsoft.com/SMI/2016/WindowsSettings">true</longPathAware>"#;
+
+/// Returns the position of the first opening tag, or the last closing tag, of the `name` element
+/// in `xml`, with any namespace prefix.
+fn find_tag(xml: &str, name: &str, closing: bool) -> Option<usize> {
+    let mut tags = xml.match_indices('<').map(|(i, _)| i).filter(|&i| {
+        let tag = &xml[i + 1..];
+        let tag = if closing {
+            match tag.strip_prefix('/') {
+                Some(tag) => tag,
+                None => return false,
+            }
+        } else {
+            tag
+        };
+        let tag_name = &tag[..tag
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(tag.len())];
+        tag_name == name
+            || tag_name
+                .strip_suffix(name)
+                .is_some_and(|p| p.ends_with(':'))
+    });
+    if closing {
+        tags.next_back()
+    } else {
+        tags.next()
+    }
+}
+
+/// Returns the application `manifest` of a binary with the `longPathAware` setting,
+/// or a minimal manifest declaring it when the binary has none.
+fn long_path_aware_manifest(manifest: Option<&str>) -> Result<String, &'static str> {
+    let Some(manifest) = manifest else {
+        return Ok(format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <application xmlns="urn:schemas-microsoft-com:asm.v3">
+    <windowsSettings>
+      {LONG_PATH_AWARE}
+    </windowsSettings>
+  </application>
+</assembly>
+"#
+        ));
+    };
+    if manifest.contains("longPathAware") {
+        return Ok(manifest.to_string());
+    }
+
+    // added to the existing `windowsSettings`, or to a new `application` element
+    let (position, setting) = match find_tag(manifest, "windowsSettings", false)
+        .and_then(|start| manifest[start..].find('>').map(|end| start + end))
+        .filter(|&end| !manifest[..end].ends_with('/'))
+    {
+        Some(end) => (end + 1, LONG_PATH_AWARE.to_string()),
+        None => (
+            find_tag(manifest, "assembly", true)
+                .ok_or("the application manifest has no `assembly` element")?,
+            format!(
+                r#"<application xmlns="urn:schemas-microsoft-com:asm.v3"><windowsSettings>{LONG_PATH_AWARE}</windowsSettings></application>"#
+            ),
+        ),
+    };
+    let mut manifest = manifest.to_string();
+    manifest.insert_str(position, &setting);
+    Ok(manifest)
+}
+
+/// Writes a copy of the PE binary at `binary` with the `VERSIONINFO` resource of the config
+/// when [`WindowsConfig::embed_version_info`](crate::config::WindowsConfig::embed_version_info) is enabled, and the `longPathAware` setting
+/// when [`WindowsConfig::long_path_aware`](crate::config::WindowsConfig::long_path_aware) is enabled, into `<intermediates_path>/version-info`,
+/// leaving the original binary untouched, and returns its path.
+pub(crate) fn embed_resources(
+    config: &Config,
+    binary: &Path,
+    intermediates_path: &Path,
+) -> crate::Result<PathBuf> {
+    let invalid = |e: &dyn std::fmt::Display| Error::InvalidPeBinary(binary.into(), e.to_string());
+    let windows = config.windows();
+
+    let data = fs::read(binary).map_err(|e| Error::IoWithPath(binary.into(), e))?;
+    let mut image = Image::parse(data).map_err(|e| invalid(&e))?;
+    let mut resources = image.resource_directory().cloned().unwrap_or_default();
+    if windows.is_some_and(|w| w.embed_version_info) {
+        resources
+            .set_version_info(&version_info(config)?)
+            .map_err(|e| invalid(&e))?;
+    }
+    if windows.is_some_and(|w| w.long_path_aware) {
+        let manifest = resources.get_manifest().map_err(|e| invalid(&e))?;
+        let manifest = long_path_aware_manifest(manifest.as_deref()).map_err(|e| invalid(&e))?;
+        resources.set_manifest(&manifest).map_err(|e| invalid(&e))?;
+    }
+    image
+        .set_resource_directory(resources)
+        .map_err(|e| invalid(&e))?;
+
+    let out_dir = intermediates_path.join("version-info");
+    fs::create_dir_all(&out_dir).map_err(|e| Error::IoWithPath(out_dir.clone(), e))?;
+    let file_name = binary
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(binary.into()))?;
+    let out_path = out_dir.join(file_name);
+    image
+        .write_file(&out_path)
+        .map_err(|e| Error::FailedToEmbedVersionInfo(out_path.clone(), e.to_string()))?;
+
+    Ok(out_path)
+}
+
+/// Returns the path of the main binary to package, which is a copy with the resources of [`embed_resources`]
+/// when [`WindowsConfig::embed_version_info`](crate::config::WindowsConfig::embed_version_info) or [`WindowsConfig::long_path_aware`](crate::config::WindowsConfig::long_path_aware) is enabled.
+pub(crate) fn main_binary_path(
+    config: &Config,
+    intermediates_path: &Path,
+) -> crate::Result<PathBuf> {
+    let main_binary_path = config
+        .binary_path(config.main_binary()?)
+        .with_extension("exe");
+    if config
+        .windows()
+        .is_some_and(|w| w.embed_version_info || w.long_path_aware)
+    {
+        tracing::debug!("Embedding resources into {}", main_binary_path.display());
+        embed_resources(config, &main_binary_path, intermediates_path)
+    } else {
+        Ok(main_binary_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Binary, WindowsConfig};
+
+    /// A minimal x64 PE image with an empty `.text` section.
+    fn minimal_pe() -> Vec<u8> {
+        let mut pe = vec![0u8; 0x400];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            pe[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        put(0, b"MZ");
+        put(0x3c, &0x40u32.to_le_bytes());
+        put(0x40, b"PE\0\0");
+        // COFF header: machine, number of sections, optional header size, characteristics
+        put(0x44, &0x8664u16.to_le_bytes());
+        put(0x46, &1u16.to_le_bytes());
+        put(0x54, &240u16.to_le_bytes());
+        put(0x56, &0x22u16.to_le_bytes());
+        // PE32+ optional header
+        put(0x58, &0x20bu16.to_le_bytes());
+        put(0x58 + 32, &0x1000u32.to_le_bytes()); // section alignment
+        put(0x58 + 36, &0x200u32.to_le_bytes()); // file alignment
+        put(0x58 + 48, &6u16.to_le_bytes()); // subsystem major version
+        put(0x58 + 56, &0x2000u32.to_le_bytes()); // image size
+        put(0x58 + 60, &0x200u32.to_le_bytes()); // headers size
+        put(0x58 + 68, &3u16.to_le_bytes()); // console subsystem
+        put(0x58 + 108, &16u32.to_le_bytes()); // number of data directories
+                                               // .text section header
+        let section = 0x58 + 240;
+        put(section, b".text");
+        put(section + 8, &0x200u32.to_le_bytes());
+        put(section + 12, &0x1000u32.to_le_bytes());
+        put(section + 16, &0x200u32.to_le_bytes());
+        put(section + 20, &0x200u32.to_le_bytes());
+        put(section + 36, &0x60000020u32.to_le_bytes());
+        pe
+    }
+
+    #[test]
+    fn it_embeds_version_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("app.exe");
+        fs::write(&binary, minimal_pe()).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.2.3+4".into();
+        config.publisher = Some("CrabNebula".into());
+        config.copyright = Some("Copyright © 2023".into());
+        config.build_id = Some("3f2c9a1e".into());
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.windows = Some(WindowsConfig::new().embed_version_info(true));
+
+        let intermediates_path = dir.path().join("intermediates");
+        let out_path = embed_resources(&config, &binary, &intermediates_path).unwrap();
+        assert_eq!(out_path, intermediates_path.join("version-info/app.exe"));
+        // the original binary is left untouched
+        assert_eq!(fs::read(&binary).unwrap(), minimal_pe());
+
+        let image = Image::parse_file(&out_path).unwrap();
+        let info = image
+            .resource_directory()
+            .unwrap()
+            .get_version_info()
+            .unwrap()
+            .unwrap();
+        let file_version = info.info.file_version;
+        assert_eq!(
+            (file_version.major, file_version.minor),
+            ((1 << 16) | 2, (3 << 16) | 4)
+        );
+        let strings = &info.strings[0].strings;
+        for (key, value) in [
+            ("FileVersion", "1.2.3+4"),
+            ("ProductVersion", "1.2.3+4"),
+            ("CompanyName", "CrabNebula"),
+            ("ProductName", "My App"),
+            ("LegalCopyright", "Copyright © 2023"),
+            ("BuildId", "3f2c9a1e"),
+            ("OriginalFilename", "app.exe"),
+        ] {
+            assert_eq!(strings.get(key).map(|s| s.as_str()), Some(value), "{key}");
+        }
+
+        fs::write(&binary, "not a PE").unwrap();
+        assert!(matches!(
+            embed_resources(&config, &binary, &intermediates_path),
+            Err(Error::InvalidPeBinary(..))
+        ));
+    }
+
+    #[test]
+    fn it_declares_long_path_awareness() {
+        let minimal = long_path_aware_manifest(None).unwrap();
+        assert!(minimal.contains(&format!("<windowsSettings>\n      {LONG_PATH_AWARE}")));
+
+        // the default manifest of the MSVC linker
+        let manifest = r#"<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0"><trustInfo xmlns="urn:schemas-microsoft-com:asm.v3"></trustInfo></assembly>"#;
+        assert_eq!(
+            long_path_aware_manifest(Some(manifest)).unwrap(),
+            manifest.replace(
+                "</assembly>",
+                &format!(r#"<application xmlns="urn:schemas-microsoft-com:asm.v3"><windowsSettings>{LONG_PATH_AWARE}</windowsSettings></application></assembly>"#)
+            )
+        );
+
+        let manifest = r#"<asmv1:assembly><asmv3:application><asmv3:windowsSettings><dpiAware>true</dpiAware></asmv3:windowsSettings></asmv3:application></asmv1:assembly>"#;
+        assert_eq!(
+            long_path_aware_manifest(Some(manifest)).unwrap(),
+            manifest.replace(
+                "<asmv3:windowsSettings>",
+                &format!("<asmv3:windowsSettings>{LONG_PATH_AWARE}")
+            )
+        );
+
+        let aware = long_path_aware_manifest(None).unwrap();
+        assert_eq!(long_path_aware_manifest(Some(&aware)).unwrap(), aware);
+        assert!(long_path_aware_manifest(Some("<xml/>")).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("app.exe");
+        fs::write(&binary, minimal_pe()).unwrap();
+        let mut config = Config::default();
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.windows = Some(WindowsConfig::new().long_path_aware(true));
+        let out_path = embed_resources(&config, &binary, dir.path()).unwrap();
+        let image = Image::parse_file(&out_path).unwrap();
+        let resources = image.resource_directory().unwrap();
+        assert_eq!(resources.get_manifest().unwrap(), Some(minimal));
+        // the version info is only embedded when enabled
+        assert!(resources.get_version_info().unwrap().is_none());
+    }
+}
@@ -16,10 +16,10 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::Context;
+use super::{naming, tools, version_info, Context};
 use crate::{
     codesign::windows as codesign,
-    config::{Config, LogLevel, WixLanguage},
+    config::{Config, LogLevel, PackageFormat, WixConfig, WixLanguage},
     shell::CommandExt,
     util::{self, download_and_verify, extract_zip, HashAlgorithm},
     Error,
@@ -268,7 +268,12 @@ type ResourceMap = BTreeMap<String, ResourceDirectory>;
 #[tracing::instrument(level = "trace", skip(config))]
 fn generate_resource_data(config: &Config) -> crate::Result<ResourceMap> {
     let mut resources_map = ResourceMap::new();
-    for resource in config.resources()? {
+    for resource in config
+        .resources()?
+        .into_iter()
+        .chain(config.bundled_runtime()?)
+        .chain(config.release_notes()?)
+    {
         let resource_entry = ResourceFile {
             id: format!("I{}", Uuid::new_v4().as_simple()),
             guid: Uuid::new_v4().to_string(),
@@ -419,6 +424,11 @@ fn run_light(
 
     args.extend(arguments);
 
+    if let Some(extra_args) = config.wix().and_then(|w| w.extra_tool_args.as_ref()) {
+        tracing::debug!("Appending extra light.exe arguments: {extra_args:?}");
+        args.extend(extra_args.iter().cloned());
+    }
+
     let mut cmd = Command::new(light_exe);
     for ext in extensions {
         cmd.arg("-ext");
@@ -453,6 +463,25 @@ fn get_and_extract_wix(path: &Path) -> crate::Result<()> {
     extract_zip(&data, path)
 }
 
+/// Inserts the paths of the [`WixConfig::banner_path`] and [`WixConfig::dialog_image_path`] bitmaps
+/// into the template `data`, the standard WiX images are used when they are not set.
+fn insert_bitmaps(
+    wix: &WixConfig,
+    data: &mut BTreeMap<&str, serde_json::Value>,
+) -> crate::Result<()> {
+    for (key, path) in [
+        ("banner_path", &wix.banner_path),
+        ("dialog_image_path", &wix.dialog_image_path),
+    ] {
+        if let Some(path) = path {
+            let canonicalized =
+                dunce::canonicalize(path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+            data.insert(key, to_json(canonicalized));
+        }
+    }
+    Ok(())
+}
+
 #[tracing::instrument(level = "trace", skip(ctx))]
 fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<PathBuf>> {
     let Context {
@@ -461,16 +490,10 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
         ..
     } = ctx;
 
-    let arch = match config.target_arch()? {
-        "x86_64" => "x64",
-        "x86" => "x86",
-        "aarch64" => "arm64",
-        target => return Err(Error::UnsupportedArch("wix".into(), target.into())),
-    };
+    let arch = naming::package_arch(config, PackageFormat::Wix)?;
 
-    let main_binary = config.main_binary()?;
     let main_binary_name = config.main_binary_name()?;
-    let main_binary_path = config.binary_path(main_binary).with_extension("exe");
+    let main_binary_path = version_info::main_binary_path(config, intermediates_path)?;
 
     tracing::debug!("Codesigning {}", main_binary_path.display());
     codesign::try_sign(&main_binary_path, config)?;
@@ -482,10 +505,24 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
 
     data.insert("product_name", to_json(&config.product_name));
     data.insert("version", to_json(convert_version(&config.version)?));
+    data.insert(
+        "keywords",
+        to_json(
+            std::iter::once("Installer")
+                .chain(config.keywords.iter().flatten().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(";"),
+        ),
+    );
     let identifier = config.identifier();
-    let manufacturer = config.publisher();
+    let manufacturer = config.wix_manufacturer()?;
     data.insert("identifier", to_json(identifier));
     data.insert("manufacturer", to_json(manufacturer));
+    data.insert("install_dir_name", to_json(config.wix_install_dir_name()?));
+    data.insert(
+        "add_remove_programs_links",
+        to_json(add_remove_programs_properties(config)?),
+    );
     let upgrade_code = Uuid::new_v5(
         &Uuid::NAMESPACE_DNS,
         format!("{main_binary_name}.app.x64").as_bytes(),
@@ -507,6 +544,30 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
     let binaries = generate_binaries_data(config)?;
     data.insert("binaries", to_json(binaries));
 
+    let shortcuts = config.windows_shortcuts()?;
+    let shortcut_binary = shortcuts.binary.unwrap_or_default();
+    // the main binary file id is `Path`, other binaries are `Bin_<id>` as in `generate_binaries_data`
+    let shortcut_target = if shortcut_binary == config.main_binary_name()? {
+        "Path".to_string()
+    } else {
+        format!(
+            "Bin_{}",
+            Regex::new(r"[^\w\d\.]")?.replace_all(&shortcut_binary.replace('-', "_"), "")
+        )
+    };
+    data.insert("start_menu_shortcut", to_json(shortcuts.start_menu));
+    data.insert("desktop_shortcut", to_json(shortcuts.desktop));
+    data.insert("start_menu_folder", to_json(shortcuts.start_menu_folder));
+    data.insert("shortcut_target", to_json(shortcut_target));
+    data.insert(
+        "shortcut_arguments",
+        to_json(shortcuts.arguments.map(|a| {
+            a.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('"', "&quot;")
+        })),
+    );
+
     let resources = generate_resource_data(config)?;
     let mut resources_wix_string = String::from("");
     let mut files_ids = Vec::new();
@@ -528,8 +589,21 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
         data.insert("icon_path", to_json(icon_path));
     }
 
-    if let Some(license) = &config.license_file {
-        if license.ends_with(".rtf") {
+    if let Some(license) = config.wix().and_then(|w| w.license_agreement.as_ref()) {
+        if !license
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("rtf"))
+        {
+            return Err(Error::InvalidLicenseAgreement(license.clone()));
+        }
+        let license =
+            dunce::canonicalize(license).map_err(|e| Error::IoWithPath(license.clone(), e))?;
+        data.insert("license", to_json(license));
+    } else if let Some(license) = &config.license_file {
+        if license
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("rtf"))
+        {
             data.insert("license", to_json(license));
         } else {
             let license_contents =
@@ -576,17 +650,7 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
             }
         }
 
-        if let Some(banner_path) = &wix.banner_path {
-            let canonicalized = dunce::canonicalize(banner_path)
-                .map_err(|e| Error::IoWithPath(banner_path.clone(), e))?;
-            data.insert("banner_path", to_json(canonicalized));
-        }
-
-        if let Some(dialog_image_path) = &wix.dialog_image_path {
-            let canonicalized = dunce::canonicalize(dialog_image_path)
-                .map_err(|e| Error::IoWithPath(dialog_image_path.clone(), e))?;
-            data.insert("dialog_image_path", to_json(canonicalized));
-        }
+        insert_bitmaps(wix, &mut data)?;
 
         if let Some(merge_modules) = &wix.merge_modules {
             let merge_modules = merge_modules
@@ -742,10 +806,9 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
             "*.wixobj".into(),
         ];
         let msi_output_path = intermediates_path.join("output.msi");
-        let msi_path = config.out_dir().join(format!(
-            "{}_{}_{}_{}.msi",
-            main_binary_name, config.version, arch, language
-        ));
+        let msi_path = config
+            .out_dir()
+            .join(naming::wix_file_name(config, &language)?);
         let msi_path_parent = msi_path
             .parent()
             .ok_or_else(|| Error::ParentDirNotFound(msi_path.clone()))?;
@@ -765,10 +828,12 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
             &(fragment_extensions.clone().into_iter().collect()),
             &msi_output_path,
         )?;
+        // the installer is only moved to the output directory once signed,
+        // so an interrupted build never leaves an unsigned installer behind
+        tracing::debug!("Codesigning {}", msi_output_path.display());
+        codesign::try_sign(&msi_output_path, config)?;
         fs::rename(&msi_output_path, &msi_path)
             .map_err(|e| Error::RenameFile(msi_output_path, msi_path.clone(), e))?;
-        tracing::debug!("Codesigning {}", msi_path.display());
-        codesign::try_sign(&msi_path, config)?;
         output_paths.push(msi_path);
     }
 
@@ -777,6 +842,11 @@ fn build_wix_app_installer(ctx: &Context, wix_path: &Path) -> crate::Result<Vec<
 
 #[tracing::instrument(level = "trace", skip(ctx))]
 pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
+    if let Some(wix_path) = tools::pinned_path(ctx, "wix") {
+        check_wix_version(ctx, &wix_path)?;
+        return build_wix_app_installer(ctx, &wix_path);
+    }
+
     let wix_path = ctx.tools_path.join("WixTools");
     if !wix_path.exists() {
         get_and_extract_wix(&wix_path)?;
@@ -789,5 +859,139 @@ pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
         get_and_extract_wix(&wix_path)?;
     }
 
+    check_wix_version(ctx, &wix_path)?;
     build_wix_app_installer(ctx, &wix_path)
 }
+
+/// Checks the version of the WiX toolset in `wix_path` reported by `candle.exe` against its pin.
+fn check_wix_version(ctx: &Context, wix_path: &Path) -> crate::Result<()> {
+    let output = Command::new(wix_path.join("candle.exe"))
+        .arg("-?")
+        .output()
+        .map_err(|e| Error::WixFailed("candle.exe".into(), e))?;
+    tools::check_version(
+        ctx,
+        "wix",
+        &tools::parse_version("wix", &String::from_utf8_lossy(&output.stdout))?,
+    )
+}
+
+/// Maps the [`Config::add_remove_programs_links`] to the Windows Installer properties
+/// of their uninstall registry values, the `EstimatedSize` being computed by the Windows Installer itself.
+fn add_remove_programs_properties(config: &Config) -> crate::Result<Vec<(&'static str, String)>> {
+    Ok(config
+        .add_remove_programs_links()?
+        .into_iter()
+        .map(|(value, url)| {
+            let property = match value {
+                "HelpLink" => "ARPHELPLINK",
+                "URLInfoAbout" => "ARPURLINFOABOUT",
+                _ => "ARPURLUPDATEINFO",
+            };
+            (property, url.replace('&', "&amp;"))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_references_the_bitmaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let banner = dir.path().join("banner.bmp");
+        let dialog = dir.path().join("dialog.bmp");
+        fs::write(&banner, b"BM banner").unwrap();
+        fs::write(&dialog, b"BM dialog").unwrap();
+
+        let render = |wix: &WixConfig| {
+            let mut data = BTreeMap::new();
+            insert_bitmaps(wix, &mut data).unwrap();
+            let mut handlebars = Handlebars::new();
+            handlebars.register_escape_fn(handlebars::no_escape);
+            handlebars
+                .render_template(include_str!("main.wxs"), &data)
+                .unwrap()
+        };
+
+        let wxs = render(
+            &WixConfig::new()
+                .banner_path(&banner)
+                .dialog_image_path(&dialog),
+        );
+        let banner = dunce::canonicalize(&banner).unwrap();
+        let dialog = dunce::canonicalize(&dialog).unwrap();
+        assert!(wxs.contains(&format!(
+            r#"<WixVariable Id="WixUIBannerBmp" Value="{}" />"#,
+            banner.display()
+        )));
+        assert!(wxs.contains(&format!(
+            r#"<WixVariable Id="WixUIDialogBmp" Value="{}" />"#,
+            dialog.display()
+        )));
+
+        // the standard WiX images are used by default
+        let wxs = render(&WixConfig::new());
+        assert!(!wxs.contains("WixUIBannerBmp"));
+        assert!(!wxs.contains("WixUIDialogBmp"));
+    }
+
+    #[test]
+    fn it_references_the_install_dir_ui() {
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.wix = Some(WixConfig::new().install_dir_name("Example App"));
+
+        let mut data = BTreeMap::new();
+        data.insert(
+            "install_dir_name",
+            to_json(config.wix_install_dir_name().unwrap()),
+        );
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        let wxs = handlebars
+            .render_template(include_str!("main.wxs"), &data)
+            .unwrap();
+
+        assert!(wxs.contains(r#"<UIRef Id="WixUI_InstallDir" />"#));
+        assert!(wxs.contains(r#"<Property Id="WIXUI_INSTALLDIR" Value="INSTALLDIR" />"#));
+        assert!(wxs.contains(r#"<Directory Id="INSTALLDIR" Name="Example App"/>"#));
+        assert!(wxs.contains(r#"ConfigurableDirectory="INSTALLDIR""#));
+        // the MSI is installed per machine, in its `Program Files`
+        assert!(wxs.contains(r#"InstallScope="perMachine""#));
+        assert!(wxs.contains(r#"<Directory Id="$(var.PlatformProgramFilesFolder)" Name="PFiles">"#));
+        // without a license, the welcome dialog leads to the install directory dialog
+        assert!(wxs.contains(r#"Value="InstallDirDlg""#));
+    }
+
+    #[test]
+    fn it_sets_the_add_remove_programs_properties() {
+        let mut config = Config::default();
+        config.homepage = Some("not a url".into());
+        config.windows = Some(
+            crate::config::WindowsConfig::new()
+                .help_link("https://example.com/support")
+                .update_info_url("https://example.com/releases?channel=stable&os=windows"),
+        );
+        let mut data = BTreeMap::new();
+        data.insert(
+            "add_remove_programs_links",
+            to_json(add_remove_programs_properties(&config).unwrap()),
+        );
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        let wxs = handlebars
+            .render_template(include_str!("main.wxs"), &data)
+            .unwrap();
+
+        assert!(
+            wxs.contains(r#"<Property Id="ARPHELPLINK" Value="https://example.com/support" />"#)
+        );
+        assert!(wxs.contains(
+            r#"<Property Id="ARPURLUPDATEINFO" Value="https://example.com/releases?channel=stable&amp;os=windows" />"#
+        ));
+        // the homepage is only used if it is a valid URL
+        assert!(!wxs.contains("ARPURLINFOABOUT"));
+    }
+}
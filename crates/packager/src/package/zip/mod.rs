@@ -0,0 +1,236 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use super::{naming, Context};
+use crate::{
+    config::{Config, PackageFormat, ZipCompression},
+    util, Error,
+};
+
+/// Copies the binaries, resources and bundled runtime into `data_dir`, with the resources next to the binaries.
+fn generate_data(config: &Config, data_dir: &Path) -> crate::Result<()> {
+    let windows = config.target_triple().contains("windows");
+
+    tracing::debug!("Copying binaries");
+    fs::create_dir_all(data_dir).map_err(|e| Error::IoWithPath(data_dir.to_path_buf(), e))?;
+    for bin in config.binaries.iter() {
+        let bin_path = config.binary_path(bin);
+        let bin_path = if windows {
+            bin_path.with_extension("exe")
+        } else {
+            bin_path
+        };
+        let file_name = bin_path
+            .file_name()
+            .ok_or_else(|| Error::FailedToExtractFilename(bin_path.clone()))?;
+        let bin_out_path = data_dir.join(file_name);
+        fs::copy(&bin_path, &bin_out_path)
+            .map_err(|e| Error::CopyFile(bin_path.clone(), bin_out_path.clone(), e))?;
+    }
+
+    tracing::debug!("Copying resources");
+    config.copy_resources(data_dir, PackageFormat::Zip)?;
+
+    tracing::debug!("Copying bundled runtime");
+    config.copy_bundled_runtime(data_dir, PackageFormat::Zip)?;
+
+    tracing::debug!("Copying release notes");
+    config.copy_release_notes(data_dir)?;
+
+    tracing::debug!("Copying external binaries");
+    config.copy_external_binaries(data_dir)?;
+
+    Ok(())
+}
+
+/// The compression methods of the entries, the default one and the overrides matched by glob patterns.
+struct Compression {
+    default: ZipCompression,
+    overrides: Vec<(glob::Pattern, ZipCompression)>,
+}
+
+impl Compression {
+    /// Reads the compression methods of the [`Config::zip`], rejecting [`ZipCompression::Zstd`]
+    /// which the standard unzippers can't extract.
+    fn new(config: &Config) -> crate::Result<Self> {
+        let zip = config.zip();
+        let default = zip.and_then(|z| z.compression).unwrap_or_default();
+        if default == ZipCompression::Zstd {
+            return Err(Error::UnsupportedZipCompression("compression".into()));
+        }
+
+        let mut overrides = Vec::new();
+        for o in zip
+            .and_then(|z| z.compression_overrides.as_ref())
+            .into_iter()
+            .flatten()
+        {
+            if o.compression == ZipCompression::Zstd {
+                return Err(Error::UnsupportedZipCompression(o.pattern.clone()));
+            }
+            overrides.push((glob::Pattern::new(&o.pattern)?, o.compression));
+        }
+
+        Ok(Self { default, overrides })
+    }
+
+    /// Returns the compression method of the entry at `name`, the first matching override or the default one.
+    fn method(&self, name: &str) -> CompressionMethod {
+        let compression = self
+            .overrides
+            .iter()
+            .find(|(pattern, _)| pattern.matches(name))
+            .map_or(self.default, |(_, compression)| *compression);
+        match compression {
+            ZipCompression::Store => CompressionMethod::Stored,
+            _ => CompressionMethod::Deflated,
+        }
+    }
+}
+
+/// Creates a zip archive of the files, directories and symlinks of `data_dir` sorted by path,
+/// with the modification time of every entry set to the zip epoch so the archive is reproducible.
+fn create_archive(data_dir: &Path, dest: &Path, compression: &Compression) -> crate::Result<()> {
+    let mut zip = ZipWriter::new(util::create_file(dest)?);
+    for entry in walkdir::WalkDir::new(data_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+    {
+        let entry = entry?;
+        let src_path = entry.path();
+        // zip entries are always separated by `/`
+        let name = src_path
+            .strip_prefix(data_dir)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let stat = fs::symlink_metadata(src_path)
+            .map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+        #[cfg(unix)]
+        let mode = std::os::unix::fs::PermissionsExt::mode(&stat.permissions()) & 0o777;
+        #[cfg(not(unix))]
+        let mode = if stat.is_dir() { 0o755 } else { 0o644 };
+        let options = FileOptions::default()
+            .compression_method(compression.method(&name))
+            .unix_permissions(mode);
+
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options)?;
+        } else if entry.file_type().is_symlink() {
+            let target = fs::read_link(src_path)
+                .map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+            zip.add_symlink(name, target.to_string_lossy(), options)?;
+        } else {
+            zip.start_file(name, options)?;
+            let mut src_file =
+                File::open(src_path).map_err(|e| Error::IoWithPath(src_path.to_path_buf(), e))?;
+            io::copy(&mut src_file, &mut zip)?;
+        }
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+#[tracing::instrument(level = "trace", skip(ctx))]
+pub(crate) fn package(ctx: &Context) -> crate::Result<Vec<PathBuf>> {
+    let Context {
+        config,
+        intermediates_path,
+        ..
+    } = ctx;
+
+    let compression = Compression::new(config)?;
+    let zip_name = naming::package_file_name(config, PackageFormat::Zip)?;
+    let zip_path = config.out_dir().join(&zip_name);
+
+    tracing::info!("Packaging {} ({})", zip_name, zip_path.display());
+
+    let intermediates_path = intermediates_path.join("zip");
+    util::create_clean_dir(&intermediates_path)?;
+
+    tracing::debug!("Generating data");
+    let data_dir = intermediates_path.join("data");
+    generate_data(config, &data_dir)?;
+
+    tracing::debug!("Creating the archive");
+    ctx.write_artifact(&zip_path, |partial| {
+        create_archive(&data_dir, partial, &compression)
+            .map_err(|e| util::write_error(&zip_path, "failed to create the zip archive", e))
+    })?;
+
+    Ok(vec![zip_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Binary, Resource, ZipCompressionOverride, ZipConfig};
+
+    #[test]
+    fn it_overrides_the_compression_of_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("target");
+        fs::create_dir_all(bin_dir.join("assets")).unwrap();
+        fs::write(bin_dir.join("app"), "app").unwrap();
+        fs::write(bin_dir.join("assets/image.png"), [0; 4096]).unwrap();
+        fs::write(bin_dir.join("assets/notes.txt"), "notes ".repeat(1000)).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        config.target_triple = Some("x86_64-unknown-linux-gnu".into());
+        config.out_dir = dir.path().join("out");
+        config.binaries_dir = Some(bin_dir.clone());
+        config.binaries = vec![Binary::new("app").main(true)];
+        config.resources = Some(vec![Resource::Single(
+            bin_dir.join("assets").display().to_string(),
+        )]);
+        config.zip = Some(
+            ZipConfig::new().compression_overrides([ZipCompressionOverride::new(
+                "*.png",
+                ZipCompression::Store,
+            )]),
+        );
+
+        let ctx = Context::new(&config).unwrap();
+        let zip_path = package(&ctx).unwrap().remove(0);
+        assert_eq!(zip_path, config.out_dir().join("app_1.0.0_x86_64.zip"));
+
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        let method = |archive: &mut zip::ZipArchive<File>, name: &str| {
+            archive.by_name(name).unwrap().compression()
+        };
+        assert_eq!(method(&mut archive, "app"), CompressionMethod::Deflated);
+        assert_eq!(
+            method(&mut archive, "assets/image.png"),
+            CompressionMethod::Stored
+        );
+        assert_eq!(
+            method(&mut archive, "assets/notes.txt"),
+            CompressionMethod::Deflated
+        );
+
+        config.zip = Some(
+            ZipConfig::new().compression_overrides([ZipCompressionOverride::new(
+                "*.txt",
+                ZipCompression::Zstd,
+            )]),
+        );
+        let ctx = Context::new(&config).unwrap();
+        assert!(matches!(
+            package(&ctx),
+            Err(Error::UnsupportedZipCompression(pattern)) if pattern == "*.txt"
+        ));
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of in-toto provenance attestations of the packages, using the SLSA provenance predicate.
+//!
+//! [`write_provenance`] writes a `<package>.intoto.json` statement next to each package,
+//! listing the files of the package with their SHA-256 digest as its subjects,
+//! and signs it into `<package>.intoto.json.sig` when a [`SigningConfig`] is provided.
+//!
+//! See <https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md>
+//! and <https://slsa.dev/spec/v1.0/provenance>.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{checksum, util::PathExt, Config, Error, PackageOutput, SigningConfig};
+
+/// The extension of the provenance statements generated by [`write_provenance`].
+pub const PROVENANCE_EXTENSION: &str = "intoto.json";
+
+/// The type of the in-toto statements.
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// The type of the SLSA provenance predicate.
+pub const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+/// The build type of the provenance, describing how the parameters are interpreted.
+pub const BUILD_TYPE: &str = "https://github.com/crabnebula-dev/cargo-packager/provenance/v1";
+
+/// Options of the provenance statements generated by [`generate_provenance`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ProvenanceConfig {
+    /// The identity of the builder, a URI of the system that ran the packager, like a CI runner.
+    ///
+    /// Defaults to `https://github.com/crabnebula-dev/cargo-packager@v<version>`.
+    pub builder_id: Option<String>,
+    /// The parameters of the invocation, like the CLI flags, recorded in the `externalParameters`
+    /// along with the format, product name, version and target triple of the package.
+    pub parameters: BTreeMap<String, Value>,
+}
+
+impl ProvenanceConfig {
+    /// Creates a new [`ProvenanceConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the identity of the builder.
+    pub fn builder_id<S: Into<String>>(mut self, builder_id: S) -> Self {
+        self.builder_id.replace(builder_id.into());
+        self
+    }
+
+    /// Set a parameter of the invocation.
+    pub fn parameter<S: Into<String>, V: Into<Value>>(mut self, name: S, value: V) -> Self {
+        self.parameters.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// A subject of a [`Statement`], an artifact identified by its digests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Subject {
+    /// The file name of the artifact.
+    pub name: String,
+    /// The digests of the artifact, keyed by algorithm, like `sha256`.
+    pub digest: BTreeMap<String, String>,
+}
+
+/// The SLSA build definition of a [`Provenance`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct BuildDefinition {
+    /// The [`BUILD_TYPE`].
+    pub build_type: String,
+    /// The parameters of the invocation.
+    pub external_parameters: BTreeMap<String, Value>,
+}
+
+/// The builder of a [`RunDetails`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Builder {
+    /// The identity of the builder, see [`ProvenanceConfig::builder_id`].
+    pub id: String,
+    /// The versions of the builder components, `cargo-packager` and the external tools
+    /// that built the package, see [`PackageOutput::tools`].
+    pub version: BTreeMap<String, String>,
+}
+
+/// The metadata of a [`RunDetails`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct BuildMetadata {
+    /// The [`Config::build_id`] of the packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invocation_id: Option<String>,
+    /// When the statement was generated, formatted according to RFC 3339.
+    pub finished_on: String,
+}
+
+/// The SLSA run details of a [`Provenance`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RunDetails {
+    /// The builder that ran the packager.
+    pub builder: Builder,
+    /// The metadata of the run.
+    pub metadata: BuildMetadata,
+}
+
+/// The SLSA provenance predicate of a [`Statement`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Provenance {
+    /// How the package was built.
+    pub build_definition: BuildDefinition,
+    /// Who built the package and when.
+    pub run_details: RunDetails,
+}
+
+/// An in-toto statement attesting the provenance of the files of a package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Statement {
+    /// The [`STATEMENT_TYPE`].
+    #[serde(rename = "_type")]
+    pub type_: String,
+    /// The files of the package.
+    pub subject: Vec<Subject>,
+    /// The [`PREDICATE_TYPE`].
+    pub predicate_type: String,
+    /// The provenance of the package.
+    pub predicate: Provenance,
+}
+
+/// Generates the provenance statement of a package, whose subjects are the files of [`PackageOutput::paths`].
+///
+/// The directories of a package, like the `.app` bundle, aren't subjects as they have no digest.
+pub fn generate_provenance(
+    config: &Config,
+    package: &PackageOutput,
+    provenance_config: &ProvenanceConfig,
+) -> crate::Result<Statement> {
+    let mut subject = Vec::new();
+    for path in package.paths.iter().filter(|p| p.is_file()) {
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::FailedToExtractFilename(path.clone()))?
+            .to_string_lossy()
+            .into_owned();
+        let digest = BTreeMap::from([("sha256".to_string(), checksum::sha256_file(path)?)]);
+        subject.push(Subject { name, digest });
+    }
+
+    let mut external_parameters = BTreeMap::from([
+        (
+            "format".to_string(),
+            Value::from(package.format.short_name()),
+        ),
+        (
+            "productName".to_string(),
+            config.product_name.clone().into(),
+        ),
+        ("version".to_string(), config.version.clone().into()),
+        ("targetTriple".to_string(), config.target_triple().into()),
+    ]);
+    external_parameters.extend(provenance_config.parameters.clone());
+
+    let version = env!("CARGO_PKG_VERSION");
+    Ok(Statement {
+        type_: STATEMENT_TYPE.into(),
+        subject,
+        predicate_type: PREDICATE_TYPE.into(),
+        predicate: Provenance {
+            build_definition: BuildDefinition {
+                build_type: BUILD_TYPE.into(),
+                external_parameters,
+            },
+            run_details: RunDetails {
+                builder: Builder {
+                    id: provenance_config.builder_id.clone().unwrap_or_else(|| {
+                        format!("https://github.com/crabnebula-dev/cargo-packager@v{version}")
+                    }),
+                    version: BTreeMap::from([("cargo-packager".to_string(), version.to_string())])
+                        .into_iter()
+                        .chain(package.tools.clone())
+                        .collect(),
+                },
+                metadata: BuildMetadata {
+                    invocation_id: package.build_id.clone().or_else(|| config.build_id.clone()),
+                    finished_on: OffsetDateTime::now_utc()
+                        .format(&Rfc3339)
+                        .map_err(time::error::Error::from)?,
+                },
+            },
+        },
+    })
+}
+
+/// Writes the provenance statement of each package with files next to its first file,
+/// as `<file>.intoto.json`, and signs it when `signing_config` is provided.
+///
+/// Returns the paths of the statements and of their signatures.
+pub fn write_provenance(
+    config: &Config,
+    packages: &[PackageOutput],
+    provenance_config: &ProvenanceConfig,
+    signing_config: Option<&SigningConfig>,
+) -> crate::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for package in packages {
+        let Some(artifact) = package.paths.iter().find(|p| p.is_file()) else {
+            tracing::debug!(
+                "Skipping the provenance of the {:?} package, it has no files",
+                package.format
+            );
+            continue;
+        };
+        let statement = generate_provenance(config, package, provenance_config)?;
+        let path = artifact.with_additional_extension(PROVENANCE_EXTENSION);
+        write_statement(&statement, &path)?;
+        tracing::info!("Generated provenance: {}", path.display());
+        paths.push(path.clone());
+
+        if let Some(signing_config) = signing_config {
+            let (signature_path, _) = crate::sign::sign_file(signing_config, &path)?;
+            paths.push(signature_path);
+        }
+    }
+    Ok(paths)
+}
+
+fn write_statement(statement: &Statement, path: &Path) -> crate::Result<()> {
+    let file = File::create(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    serde_json::to_writer_pretty(file, statement)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackageFormat;
+
+    #[test]
+    fn it_attests_the_digest_of_the_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("app_1.0.0_amd64.deb");
+        std::fs::write(&deb, "deb").unwrap();
+        let app = dir.path().join("App.app");
+        std::fs::create_dir_all(&app).unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.0.0".into();
+        let mut package = PackageOutput::new(PackageFormat::Deb, vec![deb.clone()]);
+        package.build_id = Some("3f2c9a1e".into());
+        package.tools.insert("makensis".into(), "3.09".into());
+        let packages = [package, PackageOutput::new(PackageFormat::App, vec![app])];
+        let provenance_config = ProvenanceConfig::new()
+            .builder_id("https://ci.example.com/runner")
+            .parameter("release", true);
+
+        let paths = write_provenance(&config, &packages, &provenance_config, None).unwrap();
+        assert_eq!(
+            paths,
+            vec![dir.path().join("app_1.0.0_amd64.deb.intoto.json")]
+        );
+
+        let statement: Statement = serde_json::from_reader(File::open(&paths[0]).unwrap()).unwrap();
+        assert_eq!(statement.type_, STATEMENT_TYPE);
+        assert_eq!(statement.predicate_type, PREDICATE_TYPE);
+        assert_eq!(statement.subject.len(), 1);
+        assert_eq!(statement.subject[0].name, "app_1.0.0_amd64.deb");
+        assert_eq!(
+            statement.subject[0].digest["sha256"],
+            checksum::sha256_file(&deb).unwrap()
+        );
+
+        let provenance = statement.predicate;
+        let parameters = provenance.build_definition.external_parameters;
+        assert_eq!(parameters["format"], "deb");
+        assert_eq!(parameters["version"], "1.0.0");
+        assert_eq!(parameters["release"], true);
+        assert_eq!(
+            provenance.run_details.builder.id,
+            "https://ci.example.com/runner"
+        );
+        assert_eq!(provenance.run_details.builder.version["makensis"], "3.09");
+        assert_eq!(
+            provenance.run_details.metadata.invocation_id.as_deref(),
+            Some("3f2c9a1e")
+        );
+    }
+
+    #[test]
+    fn it_signs_the_statements() {
+        let dir = tempfile::tempdir().unwrap();
+        let deb = dir.path().join("app.deb");
+        std::fs::write(&deb, "deb").unwrap();
+        let keypair = crate::sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+
+        let paths = write_provenance(
+            &Config::default(),
+            &[PackageOutput::new(PackageFormat::Deb, vec![deb])],
+            &ProvenanceConfig::new(),
+            Some(&signing_config),
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 2);
+        crate::sign::verify_file(&paths[0], &keypair.pk).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of a single release bundle of all the outputs of a packaging run.
+//!
+//! [`bundle_release`] collects the files of the packages with their `.sig` signatures,
+//! the [`updater::UPDATE_MANIFEST_FILE_NAME`] update manifest and the
+//! [`checksum::SHA256SUMS_FILE_NAME`] manifest along with their signatures,
+//! into a `release-<version>.zip` archive in the output directory,
+//! and signs it into `release-<version>.zip.sig` when a [`SigningConfig`] is provided.
+//!
+//! [`verify_release`] re-checks the files of a published release, for example downloaded back
+//! from the release host after the upload, against these manifests and signatures without rebuilding anything.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    checksum, sign, updater,
+    util::{self, PathExt},
+    Config, Error, PackageOutput, SigningConfig,
+};
+
+/// Returns the file name of the release bundle of `version`.
+pub fn release_bundle_name(version: &str) -> String {
+    format!("release-{version}.zip")
+}
+
+/// Returns the name of `path` in the release bundle,
+/// its path relative to `out_dir` separated by `/` or just its file name if it is not inside `out_dir`.
+fn entry_name(out_dir: &Path, path: &Path) -> String {
+    match path.strip_prefix(out_dir) {
+        Ok(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    }
+}
+
+/// Writes a zip archive of `files` keyed by their entry name,
+/// with the modification time of every entry set to the zip epoch so the archive is reproducible.
+fn create_archive(files: &BTreeMap<String, PathBuf>, dest: &Path) -> crate::Result<()> {
+    let mut zip = ZipWriter::new(util::create_file(dest)?);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+    for (name, path) in files {
+        zip.start_file(name, options)?;
+        let mut file = File::open(path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+        io::copy(&mut file, &mut zip)?;
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+/// Bundle the specified packages, their signatures, the update manifest, the checksums manifest
+/// and the public key of [`Config::out_dir`] into a `release-<version>.zip` archive in the output directory
+/// and sign it when `signing_config` is provided.
+///
+/// `extra_files`, like the provenance statements or the timestamps of the signatures, are bundled as well.
+/// If `packages` contain a directory in the case of [`PackageFormat::App`](crate::PackageFormat::App)
+/// it will zip the directory before bundling and appends it to `packages`.
+///
+/// Returns the paths of the release bundle and its signature.
+#[tracing::instrument(level = "trace", skip(config, packages))]
+pub fn bundle_release(
+    config: &Config,
+    packages: &mut [PackageOutput],
+    extra_files: &[PathBuf],
+    signing_config: Option<&SigningConfig>,
+) -> crate::Result<Vec<PathBuf>> {
+    let out_dir = config.out_dir();
+
+    let mut files = Vec::new();
+    for package in packages {
+        for path in crate::archive_package_dirs(package)? {
+            files.push(path.with_additional_extension("sig"));
+            files.push(path);
+        }
+    }
+    for manifest in [
+        updater::UPDATE_MANIFEST_FILE_NAME,
+        checksum::SHA256SUMS_FILE_NAME,
+    ] {
+        let manifest = out_dir.join(manifest);
+        files.push(manifest.with_additional_extension("sig"));
+        files.push(manifest);
+    }
+    files.push(out_dir.join(updater::PUBLIC_KEY_FILE_NAME));
+    files.extend(extra_files.iter().cloned());
+
+    // the signatures and manifests are optional,
+    // and the same file can be both a package path and an extra file
+    let files = files
+        .into_iter()
+        .filter(|path| path.is_file())
+        .map(|path| (entry_name(&out_dir, &path), path))
+        .collect::<BTreeMap<_, _>>();
+
+    let bundle_path = out_dir.join(release_bundle_name(&config.version));
+    tracing::info!(
+        "Bundling {} files into {}",
+        files.len(),
+        util::display_path(&bundle_path)
+    );
+    create_archive(&files, &bundle_path)
+        .map_err(|e| util::write_error(&bundle_path, "failed to create the release bundle", e))?;
+
+    let mut paths = vec![bundle_path];
+    if let Some(signing_config) = signing_config {
+        let (signature_path, _) = sign::sign_file(signing_config, &paths[0])?;
+        paths.push(signature_path);
+    }
+
+    Ok(paths)
+}
+
+/// A mismatch between a file of a release and its manifests or signatures, found by [`verify_release`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReleaseDrift {
+    /// A file listed by a manifest, or the file of a signature, is missing.
+    Missing {
+        /// The name of the missing file.
+        file: String,
+        /// The name of the manifest or signature referencing it.
+        source: String,
+    },
+    /// The digest of a file doesn't match the one of a manifest.
+    DigestMismatch {
+        /// The name of the file.
+        file: String,
+        /// The name of the manifest listing the digest.
+        source: String,
+        /// The digest listed by the manifest.
+        expected: String,
+        /// The digest of the file.
+        found: String,
+    },
+    /// The signature of a file is invalid or wasn't made with the public key.
+    InvalidSignature {
+        /// The name of the file.
+        file: String,
+        /// Why the verification failed.
+        reason: String,
+    },
+}
+
+impl fmt::Display for ReleaseDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { file, source } => write!(f, "`{file}` listed by `{source}` is missing"),
+            Self::DigestMismatch {
+                file,
+                source,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{file}` doesn't match `{source}`, expected {expected} but found {found}"
+            ),
+            Self::InvalidSignature { file, reason } => {
+                write!(f, "the signature of `{file}` is invalid: {reason}")
+            }
+        }
+    }
+}
+
+/// Verifies the signature of `file` in `dir` against `signature`,
+/// recording an [`ReleaseDrift::InvalidSignature`] when it doesn't match.
+fn check_signature(
+    dir: &Path,
+    file: &str,
+    signature: &str,
+    public_key: &str,
+    drift: &mut Vec<ReleaseDrift>,
+) -> crate::Result<()> {
+    match sign::verify_file_with_signature(dir.join(file), signature, public_key) {
+        Ok(_) => Ok(()),
+        Err(e @ (Error::Minisign(_) | Error::Base64DecodeError(_) | Error::Utf8Error(_))) => {
+            drift.push(ReleaseDrift::InvalidSignature {
+                file: file.to_string(),
+                reason: e.to_string(),
+            });
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Parses a `<digest>  <path>` manifest, like the ones written by [`checksum::write_sha256sums`]
+/// and [`checksum::write_blake3_file`], into `(path, digest)` pairs.
+fn parse_digests(path: &Path) -> crate::Result<Vec<(String, String)>> {
+    let manifest =
+        fs::read_to_string(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    manifest
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split_once("  ")
+                .map(|(digest, name)| {
+                    (name.trim_start_matches('*').to_string(), digest.to_string())
+                })
+                .ok_or_else(|| Error::DirectoryManifestMismatch(line.to_string()))
+        })
+        .collect()
+}
+
+/// Verifies the files of a release in `dir`, as published by a packaging run,
+/// against its manifests and signatures using the specified base64-encoded public key.
+///
+/// It checks:
+/// - the signatures of the [`checksum::SHA256SUMS_FILE_NAME`] and [`updater::UPDATE_MANIFEST_FILE_NAME`] manifests.
+/// - the SHA-256 digest of every file listed by [`checksum::SHA256SUMS_FILE_NAME`], which is required.
+/// - the signature of every file of the update manifest and every `<file>.sig` signature in `dir`.
+/// - the BLAKE3 digest of the update manifest and of every `<file>.b3` digest file in `dir`.
+///
+/// Returns every mismatch found, which is empty when the release is intact,
+/// and only fails when the release can't be read or the public key is invalid.
+#[tracing::instrument(level = "trace")]
+pub fn verify_release<P: AsRef<Path> + fmt::Debug>(
+    dir: P,
+    public_key: &str,
+) -> crate::Result<Vec<ReleaseDrift>> {
+    let dir = dir.as_ref();
+    sign::decode_public_key(public_key)?;
+
+    let mut drift = Vec::new();
+    let missing = |file: &str, source: &str, drift: &mut Vec<ReleaseDrift>| {
+        let exists = dir.join(file).is_file();
+        if !exists {
+            drift.push(ReleaseDrift::Missing {
+                file: file.to_string(),
+                source: source.to_string(),
+            });
+        }
+        !exists
+    };
+
+    // the detached signatures, including the ones of the manifests
+    let mut signatures = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let name = entry_name(dir, entry.path());
+        if entry.file_type().is_file() {
+            if let Some(file) = name.strip_suffix(".sig") {
+                signatures.push((file.to_string(), name));
+            }
+        }
+    }
+    for manifest in [
+        checksum::SHA256SUMS_FILE_NAME,
+        updater::UPDATE_MANIFEST_FILE_NAME,
+    ] {
+        let signature = format!("{manifest}.sig");
+        if dir.join(manifest).is_file() && !signatures.iter().any(|(_, s)| *s == signature) {
+            drift.push(ReleaseDrift::Missing {
+                file: signature,
+                source: manifest.to_string(),
+            });
+        }
+    }
+    for (file, signature_name) in &signatures {
+        if missing(file, signature_name, &mut drift) {
+            continue;
+        }
+        let signature_path = dir.join(signature_name);
+        let signature = fs::read_to_string(&signature_path)
+            .map_err(|e| Error::IoWithPath(signature_path, e))?;
+        check_signature(dir, file, &signature, public_key, &mut drift)?;
+    }
+
+    for (file, expected) in parse_digests(&dir.join(checksum::SHA256SUMS_FILE_NAME))? {
+        if missing(&file, checksum::SHA256SUMS_FILE_NAME, &mut drift) {
+            continue;
+        }
+        let found = checksum::sha256_file(dir.join(&file))?;
+        if !found.eq_ignore_ascii_case(&expected) {
+            drift.push(ReleaseDrift::DigestMismatch {
+                file,
+                source: checksum::SHA256SUMS_FILE_NAME.to_string(),
+                expected,
+                found,
+            });
+        }
+    }
+
+    let mut blake3_digests = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let name = entry_name(dir, entry.path());
+        if entry.file_type().is_file()
+            && name.ends_with(&format!(".{}", checksum::BLAKE3_EXTENSION))
+        {
+            for (file, digest) in parse_digests(entry.path())? {
+                // the digest files list the file name of the file next to them
+                let file = match name.rsplit_once('/') {
+                    Some((parent, _)) => format!("{parent}/{file}"),
+                    None => file,
+                };
+                blake3_digests.push((file, name.clone(), digest));
+            }
+        }
+    }
+
+    let manifest_path = dir.join(updater::UPDATE_MANIFEST_FILE_NAME);
+    if manifest_path.is_file() {
+        let manifest: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(&manifest_path)
+                .map_err(|e| Error::IoWithPath(manifest_path.clone(), e))?,
+        )?;
+        let platforms = manifest
+            .get("platforms")
+            .and_then(serde_json::Value::as_object)
+            .into_iter()
+            .flatten();
+        for platform in platforms.map(|(_, platform)| platform) {
+            let Some(url) = platform.get("url").and_then(serde_json::Value::as_str) else {
+                continue;
+            };
+            let file = url.rsplit('/').next().unwrap_or(url).to_string();
+            if missing(&file, updater::UPDATE_MANIFEST_FILE_NAME, &mut drift) {
+                continue;
+            }
+            if let Some(signature) = platform
+                .get("signature")
+                .and_then(serde_json::Value::as_str)
+            {
+                check_signature(dir, &file, signature, public_key, &mut drift)?;
+            }
+            if let Some(digest) = platform.get("blake3").and_then(serde_json::Value::as_str) {
+                blake3_digests.push((
+                    file,
+                    updater::UPDATE_MANIFEST_FILE_NAME.to_string(),
+                    digest.to_string(),
+                ));
+            }
+        }
+    }
+
+    for (file, source, expected) in blake3_digests {
+        if missing(&file, &source, &mut drift) {
+            continue;
+        }
+        let found = checksum::blake3_file(dir.join(&file))?;
+        if !found.eq_ignore_ascii_case(&expected) {
+            drift.push(ReleaseDrift::DigestMismatch {
+                file,
+                source,
+                expected,
+                found,
+            });
+        }
+    }
+
+    Ok(drift)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::PackageFormat;
+
+    #[test]
+    fn it_bundles_the_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.product_name = "App".into();
+        config.version = "1.2.3".into();
+        config.out_dir = dir.path().join("out");
+        let out_dir = config.out_dir();
+        fs::create_dir_all(out_dir.join("App.app/Contents/MacOS")).unwrap();
+        fs::write(out_dir.join("App.app/Contents/MacOS/App"), "app").unwrap();
+        fs::write(out_dir.join("app_1.2.3_amd64.deb"), "deb").unwrap();
+        fs::write(out_dir.join("app_1.2.3_amd64.deb.intoto.json"), "{}").unwrap();
+
+        let keypair = sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+        let mut packages = vec![
+            PackageOutput::new(PackageFormat::App, vec![out_dir.join("App.app")]),
+            PackageOutput::new(
+                PackageFormat::Deb,
+                vec![out_dir.join("app_1.2.3_amd64.deb")],
+            ),
+        ];
+        crate::sign_checksums(&signing_config, &mut packages, &out_dir).unwrap();
+        let manifest = out_dir.join(updater::UPDATE_MANIFEST_FILE_NAME);
+        fs::write(&manifest, "{}").unwrap();
+        updater::sign_update_manifest(&signing_config, &manifest).unwrap();
+        sign::sign_file(&signing_config, out_dir.join("app_1.2.3_amd64.deb")).unwrap();
+
+        let paths = bundle_release(
+            &config,
+            &mut packages,
+            &[out_dir.join("app_1.2.3_amd64.deb.intoto.json")],
+            Some(&signing_config),
+        )
+        .unwrap();
+        assert_eq!(
+            paths,
+            [
+                out_dir.join("release-1.2.3.zip"),
+                out_dir.join("release-1.2.3.zip.sig")
+            ]
+        );
+        sign::verify_file(&paths[0], &keypair.pk).unwrap();
+
+        let mut zip = zip::ZipArchive::new(File::open(&paths[0]).unwrap()).unwrap();
+        let mut names = zip.file_names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "App.app.tar.gz",
+                "SHA256SUMS",
+                "SHA256SUMS.sig",
+                "app_1.2.3_amd64.deb",
+                "app_1.2.3_amd64.deb.intoto.json",
+                "app_1.2.3_amd64.deb.sig",
+                "latest.json",
+                "latest.json.sig",
+            ]
+        );
+        let mut deb = String::new();
+        io::Read::read_to_string(&mut zip.by_name("app_1.2.3_amd64.deb").unwrap(), &mut deb)
+            .unwrap();
+        assert_eq!(deb, "deb");
+    }
+
+    #[test]
+    fn it_verifies_the_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let release = dir.path();
+        fs::write(release.join("app_1.2.3_amd64.deb"), "deb").unwrap();
+        fs::write(release.join("app_1.2.3_x64-setup.exe"), "nsis").unwrap();
+
+        let keypair = sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+        let mut packages = vec![
+            PackageOutput::new(
+                PackageFormat::Deb,
+                vec![release.join("app_1.2.3_amd64.deb")],
+            ),
+            PackageOutput::new(
+                PackageFormat::Nsis,
+                vec![release.join("app_1.2.3_x64-setup.exe")],
+            ),
+        ];
+        crate::sign_checksums(&signing_config, &mut packages, release).unwrap();
+        sign::sign_file(&signing_config, release.join("app_1.2.3_amd64.deb")).unwrap();
+        let (_, exe_signature) =
+            sign::sign_file(&signing_config, release.join("app_1.2.3_x64-setup.exe")).unwrap();
+        let (exe_blake3, _) =
+            checksum::write_blake3_file(release.join("app_1.2.3_x64-setup.exe")).unwrap();
+        let manifest = release.join(updater::UPDATE_MANIFEST_FILE_NAME);
+        fs::write(
+            &manifest,
+            serde_json::json!({
+                "version": "1.2.3",
+                "platforms": {
+                    "windows-x86_64": {
+                        "url": "https://example.com/1.2.3/app_1.2.3_x64-setup.exe",
+                        "signature": exe_signature,
+                        "format": "nsis",
+                        "blake3": exe_blake3,
+                    }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        updater::sign_update_manifest(&signing_config, &manifest).unwrap();
+
+        assert_eq!(verify_release(release, &keypair.pk).unwrap(), []);
+
+        // a file corrupted after the upload
+        fs::write(release.join("app_1.2.3_x64-setup.exe"), "corrupted").unwrap();
+        fs::remove_file(release.join("app_1.2.3_amd64.deb")).unwrap();
+        let drift = verify_release(release, &keypair.pk).unwrap();
+        let corrupted_blake3 =
+            checksum::blake3_file(release.join("app_1.2.3_x64-setup.exe")).unwrap();
+        // the detached signature and the update manifest both sign the corrupted file
+        assert_eq!(drift.len(), 7, "{drift:#?}");
+        assert!(drift.contains(&ReleaseDrift::Missing {
+            file: "app_1.2.3_amd64.deb".into(),
+            source: "app_1.2.3_amd64.deb.sig".into(),
+        }));
+        assert!(drift.contains(&ReleaseDrift::Missing {
+            file: "app_1.2.3_amd64.deb".into(),
+            source: checksum::SHA256SUMS_FILE_NAME.into(),
+        }));
+        assert!(drift.iter().any(|d| matches!(
+            d,
+            ReleaseDrift::DigestMismatch { file, source, .. }
+                if file == "app_1.2.3_x64-setup.exe" && source == checksum::SHA256SUMS_FILE_NAME
+        )));
+        for source in [
+            "app_1.2.3_x64-setup.exe.b3",
+            updater::UPDATE_MANIFEST_FILE_NAME,
+        ] {
+            assert!(drift.contains(&ReleaseDrift::DigestMismatch {
+                file: "app_1.2.3_x64-setup.exe".into(),
+                source: source.into(),
+                expected: exe_blake3.clone(),
+                found: corrupted_blake3.clone(),
+            }));
+        }
+        assert_eq!(
+            drift
+                .iter()
+                .filter(|d| matches!(
+                    d,
+                    ReleaseDrift::InvalidSignature { file, .. } if file == "app_1.2.3_x64-setup.exe"
+                ))
+                .count(),
+            2
+        );
+    }
+}
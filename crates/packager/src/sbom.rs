@@ -0,0 +1,561 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of a software bill of materials (SBOM) of the app, from the `cargo metadata` of its crate.
+//!
+//! [`write_sbom`] writes a [CycloneDX](https://cyclonedx.org/specification/overview/) or
+//! [SPDX](https://spdx.github.io/spdx-spec/v2.3/) JSON document listing the crate of the app
+//! and all its normal and build dependencies for the target, with their version, license and
+//! [package URL](https://github.com/package-url/purl-spec).
+//!
+//! When [`Config::sbom`] is set, the SBOM is written next to the packages and recorded
+//! in [`PackageOutput::sbom`](crate::PackageOutput::sbom), and embedded into each package
+//! with [`SbomConfig::embed`].
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cargo_metadata::{DependencyKind, Metadata, MetadataCommand, Package, PackageId};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{util, Config, Error};
+
+/// The format of a software bill of materials.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum SbomFormat {
+    /// A CycloneDX 1.5 JSON document, written into a `.cdx.json` file.
+    #[default]
+    #[serde(alias = "cyclone-dx", alias = "cyclone_dx")]
+    CycloneDx,
+    /// An SPDX 2.3 JSON document, written into a `.spdx.json` file.
+    Spdx,
+}
+
+impl SbomFormat {
+    /// The extension of the files of this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::CycloneDx => "cdx.json",
+            Self::Spdx => "spdx.json",
+        }
+    }
+}
+
+/// The software bill of materials configuration, see [`Config::sbom`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct SbomConfig {
+    /// The format of the SBOM, defaults to [`SbomFormat::CycloneDx`].
+    #[serde(default)]
+    pub format: SbomFormat,
+    /// Whether to also embed the SBOM into each package, as the `sbom.cdx.json` or `sbom.spdx.json` file
+    /// at the root of its resources: in `usr/lib/<main-binary-name>` on Linux,
+    /// in `Contents/Resources` of the macOS app and in the installation directory on Windows.
+    #[serde(default)]
+    pub embed: bool,
+    /// The path of the `Cargo.toml` of the crate of the app, defaults to the one in the current directory.
+    #[serde(alias = "manifest-path", alias = "manifest_path")]
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl SbomConfig {
+    /// Creates a new [`SbomConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the format of the SBOM.
+    pub fn format(mut self, format: SbomFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set whether to embed the SBOM into each package.
+    pub fn embed(mut self, embed: bool) -> Self {
+        self.embed = embed;
+        self
+    }
+
+    /// Set the path of the `Cargo.toml` of the crate of the app.
+    pub fn manifest_path<P: Into<PathBuf>>(mut self, manifest_path: P) -> Self {
+        self.manifest_path.replace(manifest_path.into());
+        self
+    }
+}
+
+/// A crate listed in the SBOM.
+struct Component<'a> {
+    package: &'a Package,
+    /// The unique reference of the component in the document.
+    reference: String,
+    /// The indices of the direct dependencies of the component.
+    dependencies: BTreeSet<usize>,
+}
+
+impl Component<'_> {
+    fn purl(&self) -> String {
+        format!("pkg:cargo/{}@{}", self.package.name, self.package.version)
+    }
+
+    fn download_location(&self) -> String {
+        match &self.package.source {
+            Some(source) if source.is_crates_io() => format!(
+                "https://crates.io/api/v1/crates/{}/{}/download",
+                self.package.name, self.package.version
+            ),
+            _ => "NOASSERTION".into(),
+        }
+    }
+}
+
+/// Returns the crate of the app and its dependencies, the crate of the app first.
+///
+/// The crate of the app is the workspace package named like [`Config::name`],
+/// or the root package of the metadata.
+fn components<'a>(config: &Config, metadata: &'a Metadata) -> crate::Result<Vec<Component<'a>>> {
+    let root = config
+        .name
+        .as_deref()
+        .and_then(|name| {
+            metadata
+                .workspace_packages()
+                .into_iter()
+                .find(|p| p.name == name)
+        })
+        .or_else(|| metadata.root_package())
+        .ok_or_else(|| {
+            Error::Sbom("could not find the crate of the app in the cargo metadata".into())
+        })?;
+    let resolve = metadata
+        .resolve
+        .as_ref()
+        .ok_or_else(|| Error::Sbom("the cargo metadata has no dependency graph".into()))?;
+
+    let packages = metadata
+        .packages
+        .iter()
+        .map(|p| (&p.id, p))
+        .collect::<HashMap<_, _>>();
+    let nodes = resolve
+        .nodes
+        .iter()
+        .map(|n| (&n.id, n))
+        .collect::<HashMap<_, _>>();
+
+    let mut components = Vec::<Component>::new();
+    let mut indices = HashMap::<&PackageId, usize>::new();
+    let mut references = HashSet::new();
+    let mut queue = VecDeque::from([&root.id]);
+    let mut edges = Vec::new();
+    while let Some(id) = queue.pop_front() {
+        if indices.contains_key(id) {
+            continue;
+        }
+        let package = packages
+            .get(id)
+            .ok_or_else(|| Error::Sbom(format!("the package {id} is not in the cargo metadata")))?;
+
+        // the same crate can be resolved from several sources
+        let base = format!("{}@{}", package.name, package.version);
+        let mut reference = base.clone();
+        let mut n = 1;
+        while !references.insert(reference.clone()) {
+            n += 1;
+            reference = format!("{base}-{n}");
+        }
+
+        indices.insert(id, components.len());
+        components.push(Component {
+            package,
+            reference,
+            dependencies: BTreeSet::new(),
+        });
+
+        if let Some(node) = nodes.get(id) {
+            for dep in &node.deps {
+                // the dev dependencies are not shipped
+                if dep
+                    .dep_kinds
+                    .iter()
+                    .all(|k| k.kind == DependencyKind::Development)
+                {
+                    continue;
+                }
+                edges.push((id, &dep.pkg));
+                queue.push_back(&dep.pkg);
+            }
+        }
+    }
+    for (from, to) in edges {
+        let to = indices[to];
+        components[indices[from]].dependencies.insert(to);
+    }
+
+    Ok(components)
+}
+
+fn timestamp() -> crate::Result<String> {
+    let now = match util::source_date_epoch() {
+        Some(epoch) => OffsetDateTime::from_unix_timestamp(epoch as i64)
+            .map_err(|e| Error::Sbom(format!("invalid SOURCE_DATE_EPOCH: {e}")))?,
+        None => OffsetDateTime::now_utc(),
+    };
+    now.format(&Rfc3339)
+        .map_err(|e| Error::Sbom(format!("failed to format the timestamp: {e}")))
+}
+
+fn cyclonedx(components: &[Component], timestamp: &str) -> Value {
+    let component = |c: &Component, kind: &str| {
+        let mut component = json!({
+            "type": kind,
+            "bom-ref": c.reference,
+            "name": c.package.name,
+            "version": c.package.version.to_string(),
+            "purl": c.purl(),
+        });
+        if let Some(description) = &c.package.description {
+            component["description"] = description.trim().into();
+        }
+        if let Some(license) = &c.package.license {
+            component["licenses"] = json!([{ "expression": license }]);
+        }
+        component
+    };
+
+    let (root, dependencies) = components.split_first().expect("the app crate is listed");
+    let serial_number = uuid::Uuid::new_v5(
+        &uuid::Uuid::NAMESPACE_URL,
+        format!("{}#{timestamp}", root.purl()).as_bytes(),
+    );
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{serial_number}"),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "cargo-packager",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": component(root, "application"),
+        },
+        "components": dependencies
+            .iter()
+            .map(|c| component(c, "library"))
+            .collect::<Vec<_>>(),
+        "dependencies": components
+            .iter()
+            .map(|c| json!({
+                "ref": c.reference,
+                "dependsOn": c
+                    .dependencies
+                    .iter()
+                    .map(|&i| &components[i].reference)
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn spdx(components: &[Component], timestamp: &str) -> Value {
+    let spdx_id = |c: &Component| {
+        let id = c
+            .reference
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect::<String>();
+        format!("SPDXRef-Package-{id}")
+    };
+
+    let root = &components[0];
+    let namespace = uuid::Uuid::new_v5(
+        &uuid::Uuid::NAMESPACE_URL,
+        format!("{}#{timestamp}", root.purl()).as_bytes(),
+    );
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": spdx_id(root),
+    })];
+    for c in components {
+        for &dependency in &c.dependencies {
+            relationships.push(json!({
+                "spdxElementId": spdx_id(c),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(&components[dependency]),
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{}-{}", root.package.name, root.package.version),
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/{}-{}-{namespace}",
+            root.package.name, root.package.version
+        ),
+        "creationInfo": {
+            "created": timestamp,
+            "creators": [format!("Tool: cargo-packager-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": components
+            .iter()
+            .map(|c| json!({
+                "name": c.package.name,
+                "SPDXID": spdx_id(c),
+                "versionInfo": c.package.version.to_string(),
+                "downloadLocation": c.download_location(),
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": c.package.license.as_deref().unwrap_or("NOASSERTION"),
+                "copyrightText": "NOASSERTION",
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": c.purl(),
+                }],
+            }))
+            .collect::<Vec<_>>(),
+        "relationships": relationships,
+    })
+}
+
+/// Generates the SBOM of the crate of the app and its dependencies for [`Config::target_triple`],
+/// from the `cargo metadata` of [`SbomConfig::manifest_path`], and checks it with [`validate_sbom`].
+#[tracing::instrument(level = "trace", skip(config))]
+pub fn generate_sbom(config: &Config, sbom_config: &SbomConfig) -> crate::Result<Value> {
+    let mut cmd = MetadataCommand::new();
+    if let Some(manifest_path) = &sbom_config.manifest_path {
+        cmd.manifest_path(manifest_path);
+    }
+    cmd.other_options(vec![
+        "--filter-platform".to_string(),
+        config.target_triple(),
+    ]);
+    let metadata = cmd.exec().map_err(|e| Error::Sbom(e.to_string()))?;
+
+    let components = components(config, &metadata)?;
+    let timestamp = timestamp()?;
+    let sbom = match sbom_config.format {
+        SbomFormat::CycloneDx => cyclonedx(&components, &timestamp),
+        SbomFormat::Spdx => spdx(&components, &timestamp),
+    };
+    validate_sbom(&sbom, sbom_config.format)?;
+    Ok(sbom)
+}
+
+/// Generates the SBOM with [`generate_sbom`] and writes it into `dir`,
+/// as `<slug>_<version>.cdx.json` or `<slug>_<version>.spdx.json`, see [`Config::slug`].
+///
+/// Returns the path of the SBOM.
+pub fn write_sbom(config: &Config, sbom_config: &SbomConfig, dir: &Path) -> crate::Result<PathBuf> {
+    let sbom = generate_sbom(config, sbom_config)?;
+    let path = dir.join(format!(
+        "{}_{}.{}",
+        config.slug()?,
+        config.version,
+        sbom_config.format.extension()
+    ));
+    fs::create_dir_all(dir).map_err(|e| Error::IoWithPath(dir.to_path_buf(), e))?;
+    let data = serde_json::to_vec_pretty(&sbom)?;
+    fs::write(&path, data).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    Ok(path)
+}
+
+/// Checks that `sbom` is a well-formed SBOM of `format`, failing with [`Error::InvalidSbom`] otherwise.
+///
+/// The required fields must be set, the references of the components must be unique,
+/// and the dependency graph must only refer to the listed components.
+pub fn validate_sbom(sbom: &Value, format: SbomFormat) -> crate::Result<()> {
+    let invalid = |reason: String| Error::InvalidSbom(reason);
+    let string = |value: &Value, field: &str| {
+        value[field]
+            .as_str()
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+            .ok_or_else(|| invalid(format!("missing the `{field}` string")))
+    };
+    let array = |value: &'_ Value, field: &str| -> crate::Result<Vec<Value>> {
+        value[field]
+            .as_array()
+            .cloned()
+            .ok_or_else(|| invalid(format!("missing the `{field}` array")))
+    };
+
+    let (kind, version, items, id_field, edges) = match format {
+        SbomFormat::CycloneDx => {
+            let mut components = array(sbom, "components")?;
+            components.push(sbom["metadata"]["component"].clone());
+            let mut edges = Vec::new();
+            for dependency in array(sbom, "dependencies")? {
+                let from = string(&dependency, "ref")?;
+                for to in array(&dependency, "dependsOn")? {
+                    edges.push((from.clone(), to.as_str().unwrap_or_default().to_string()));
+                }
+            }
+            (
+                string(sbom, "bomFormat")?,
+                string(sbom, "specVersion")?,
+                components,
+                "bom-ref",
+                edges,
+            )
+        }
+        SbomFormat::Spdx => {
+            let mut edges = Vec::new();
+            for relationship in array(sbom, "relationships")? {
+                let from = string(&relationship, "spdxElementId")?;
+                let to = string(&relationship, "relatedSpdxElement")?;
+                if from != "SPDXRef-DOCUMENT" {
+                    edges.push((from, to));
+                }
+            }
+            string(sbom, "documentNamespace")?;
+            (
+                "SPDX".to_string(),
+                string(sbom, "spdxVersion")?,
+                array(sbom, "packages")?,
+                "SPDXID",
+                edges,
+            )
+        }
+    };
+    match format {
+        SbomFormat::CycloneDx if kind != "CycloneDX" => {
+            return Err(invalid(format!("unexpected `bomFormat` {kind}")))
+        }
+        SbomFormat::Spdx if !version.starts_with("SPDX-") => {
+            return Err(invalid(format!("unexpected `spdxVersion` {version}")))
+        }
+        _ => {}
+    }
+
+    let mut ids = HashSet::new();
+    for item in &items {
+        let id = string(item, id_field)?;
+        let name = string(item, "name")?;
+        if item["version"].is_null() && item["versionInfo"].is_null() {
+            return Err(invalid(format!("the component {name} has no version")));
+        }
+        if !ids.insert(id.clone()) {
+            return Err(invalid(format!("the reference {id} is not unique")));
+        }
+    }
+    for (from, to) in edges {
+        if let Some(id) = [&from, &to].into_iter().find(|id| !ids.contains(*id)) {
+            return Err(invalid(format!(
+                "the dependency graph refers to the unknown component {id}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(format: SbomFormat) -> Value {
+        let mut config = Config::default();
+        config.name = Some("cargo-packager".into());
+        let sbom_config = SbomConfig::new()
+            .format(format)
+            .manifest_path(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+        generate_sbom(&config, &sbom_config).unwrap()
+    }
+
+    #[test]
+    fn it_lists_the_direct_dependencies() {
+        let sbom = generate(SbomFormat::CycloneDx);
+        assert_eq!(sbom["metadata"]["component"]["name"], "cargo-packager");
+
+        let root = sbom["metadata"]["component"]["bom-ref"].as_str().unwrap();
+        let dependencies = sbom["dependencies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["ref"] == root)
+            .unwrap()["dependsOn"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r.as_str().unwrap().split('@').next().unwrap())
+            .collect::<Vec<_>>();
+        for dependency in ["serde", "minisign", "glob", "cargo_metadata"] {
+            assert!(dependencies.contains(&dependency), "{dependency}");
+        }
+        // dev dependencies are not shipped
+        assert!(!dependencies.contains(&"roxmltree"));
+
+        let serde = sbom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == "serde")
+            .unwrap();
+        assert!(serde["purl"]
+            .as_str()
+            .unwrap()
+            .starts_with("pkg:cargo/serde@"));
+
+        let spdx = generate(SbomFormat::Spdx);
+        let root = spdx["relationships"][0]["relatedSpdxElement"]
+            .as_str()
+            .unwrap();
+        assert!(spdx["relationships"].as_array().unwrap().iter().any(|r| {
+            r["spdxElementId"] == root
+                && r["relatedSpdxElement"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with("SPDXRef-Package-serde-")
+        }));
+    }
+
+    #[test]
+    fn it_validates_sboms() {
+        let valid = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "metadata": { "component": { "bom-ref": "app@1.0.0", "name": "app", "version": "1.0.0" } },
+            "components": [{ "bom-ref": "dep@1.0.0", "name": "dep", "version": "1.0.0" }],
+            "dependencies": [{ "ref": "app@1.0.0", "dependsOn": ["dep@1.0.0"] }],
+        });
+        validate_sbom(&valid, SbomFormat::CycloneDx).unwrap();
+
+        let mut unknown = valid.clone();
+        unknown["dependencies"][0]["dependsOn"] = json!(["other@1.0.0"]);
+        assert!(matches!(
+            validate_sbom(&unknown, SbomFormat::CycloneDx),
+            Err(Error::InvalidSbom(reason)) if reason.contains("other@1.0.0")
+        ));
+
+        let mut duplicate = valid.clone();
+        duplicate["components"][0]["bom-ref"] = "app@1.0.0".into();
+        assert!(validate_sbom(&duplicate, SbomFormat::CycloneDx).is_err());
+
+        assert!(validate_sbom(&valid, SbomFormat::Spdx).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Virus scanning of the packages before they are signed.
+//!
+//! [`scan_outputs`] runs [`clamscan`](https://docs.clamav.net/manual/Usage/Scanning.html#clamscan)
+//! or a custom scanner command on each file of the packages, failing with [`Error::ScanFailed`]
+//! on a detection, so a false positive is caught before the packages are signed and published.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{Error, PackageOutput};
+
+/// The scanner command used when [`ScanConfig::command`] is not set.
+pub const DEFAULT_SCAN_COMMAND: &str = "clamscan --no-summary --recursive %1";
+
+/// Options of the virus scan of [`scan_outputs`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ScanConfig {
+    /// The scanner command, split on spaces, where `%1` is replaced with the path of the scanned file.
+    /// The path is appended to the command when it has no `%1`.
+    ///
+    /// The scanner must exit with a non-zero code when it detects something, like `clamscan` does.
+    /// Directories, like the `.app` bundles, are passed as is. Defaults to [`DEFAULT_SCAN_COMMAND`].
+    pub command: Option<String>,
+    /// Fail with [`Error::ScannerNotFound`] when the scanner is not installed,
+    /// instead of skipping the scan with a warning.
+    pub strict: bool,
+}
+
+impl ScanConfig {
+    /// Creates a new [`ScanConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the scanner command.
+    pub fn command<S: Into<String>>(mut self, command: S) -> Self {
+        self.command.replace(command.into());
+        self
+    }
+
+    /// Set whether a missing scanner fails the scan.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn scan_command(&self, path: &Path) -> (String, Command) {
+        let command = self.command.as_deref().unwrap_or(DEFAULT_SCAN_COMMAND);
+        let mut args = command.split_whitespace();
+        let bin = args.next().unwrap_or_default().to_string();
+
+        let mut cmd = Command::new(&bin);
+        let mut has_placeholder = false;
+        for arg in args {
+            if arg == "%1" {
+                has_placeholder = true;
+                cmd.arg(path);
+            } else {
+                cmd.arg(arg);
+            }
+        }
+        if !has_placeholder {
+            cmd.arg(path);
+        }
+        (bin, cmd)
+    }
+}
+
+/// Scans the file or directory at `path`, failing with [`Error::ScanFailed`] when the scanner detects something.
+///
+/// Returns `false` when the scan is skipped because the scanner is not installed.
+#[tracing::instrument(level = "trace")]
+pub fn scan_file(config: &ScanConfig, path: &Path) -> crate::Result<bool> {
+    let (bin, mut cmd) = config.scan_command(path);
+    tracing::debug!("Scanning {} with `{bin}`", path.display());
+
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if config.strict {
+                return Err(Error::ScannerNotFound(bin));
+            }
+            tracing::warn!("Skipping the virus scan of the packages, `{bin}` is not installed");
+            return Ok(false);
+        }
+        Err(e) => return Err(Error::IoWithPath(PathBuf::from(bin), e)),
+    };
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = [stdout.trim(), stderr.trim()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(Error::ScanFailed {
+            path: path.to_path_buf(),
+            detail: if detail.is_empty() {
+                format!(
+                    "`{bin}` exited with code {}",
+                    output.status.code().unwrap_or_default()
+                )
+            } else {
+                detail
+            },
+        });
+    }
+    Ok(true)
+}
+
+/// Scans each file of the packages with [`scan_file`], before they are signed by [`crate::sign_outputs`].
+///
+/// When the scanner is not installed, the remaining files are skipped too.
+#[tracing::instrument(level = "trace")]
+pub fn scan_outputs(config: &ScanConfig, packages: &[PackageOutput]) -> crate::Result<()> {
+    for path in packages.iter().flat_map(|p| &p.paths) {
+        if !scan_file(config, path)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::*;
+    use crate::PackageFormat;
+
+    #[test]
+    fn it_fails_on_detections() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanner = dir.path().join("scanner");
+        fs::write(
+            &scanner,
+            "#!/bin/sh\nif grep -q EICAR \"$2\"; then echo \"$2: Eicar-Signature FOUND\"; exit 1; fi\n",
+        )
+        .unwrap();
+        fs::set_permissions(&scanner, fs::Permissions::from_mode(0o755)).unwrap();
+        let config = ScanConfig::new().command(format!("{} --quiet %1", scanner.display()));
+
+        let clean = dir.path().join("app.deb");
+        fs::write(&clean, "app").unwrap();
+        let infected = dir.path().join("app.AppImage");
+        fs::write(&infected, "EICAR").unwrap();
+
+        let clean = PackageOutput::new(PackageFormat::Deb, vec![clean]);
+        scan_outputs(&config, std::slice::from_ref(&clean)).unwrap();
+
+        let infected = PackageOutput::new(PackageFormat::AppImage, vec![infected.clone()]);
+        match scan_outputs(&config, &[clean, infected.clone()]) {
+            Err(Error::ScanFailed { path, detail }) => {
+                assert_eq!(path, infected.paths[0]);
+                assert!(detail.ends_with("Eicar-Signature FOUND"), "{detail}");
+            }
+            other => panic!("expected a detection, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn it_skips_missing_scanners_unless_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.deb");
+        fs::write(&file, "app").unwrap();
+        let packages = [PackageOutput::new(PackageFormat::Deb, vec![file])];
+
+        let config = ScanConfig::new().command("cargo-packager-missing-scanner");
+        scan_outputs(&config, &packages).unwrap();
+        assert!(matches!(
+            scan_outputs(&config.strict(true), &packages),
+            Err(Error::ScannerNotFound(bin)) if bin == "cargo-packager-missing-scanner"
+        ));
+    }
+}
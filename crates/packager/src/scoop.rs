@@ -0,0 +1,354 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of Scoop manifests for the Windows packages.
+//!
+//! The generated `.json` file is ready to be committed to a Scoop bucket,
+//! publishing the installer it references is left to the caller.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{checksum, Config, Error, PackageFormat, PackageOutput};
+
+/// The top-level keys of a Scoop manifest.
+///
+/// See <https://github.com/ScoopInstaller/Scoop/wiki/App-Manifests>
+const SCOOP_MANIFEST_KEYS: &[&str] = &[
+    "version",
+    "description",
+    "homepage",
+    "license",
+    "notes",
+    "depends",
+    "suggest",
+    "architecture",
+    "url",
+    "hash",
+    "extract_dir",
+    "extract_to",
+    "innosetup",
+    "installer",
+    "uninstaller",
+    "pre_install",
+    "post_install",
+    "pre_uninstall",
+    "post_uninstall",
+    "bin",
+    "shortcuts",
+    "persist",
+    "env_add_path",
+    "env_set",
+    "checkver",
+    "autoupdate",
+];
+
+/// The keys of an `architecture` entry of a Scoop manifest.
+const SCOOP_ARCHITECTURE_KEYS: &[&str] = &[
+    "url",
+    "hash",
+    "extract_dir",
+    "installer",
+    "uninstaller",
+    "bin",
+    "shortcuts",
+];
+
+/// Options of the Scoop manifest generated by [`generate_manifest`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ScoopManifestConfig {
+    /// A template for the download URL of the installer,
+    /// where `{{version}}` is replaced with [`Config::version`]
+    /// and `{{artefact}}` with the file name of the installer.
+    pub url_template: String,
+    /// The SPDX identifier of the license of the app.
+    pub license: Option<String>,
+}
+
+impl ScoopManifestConfig {
+    /// Creates a new [`ScoopManifestConfig`] with the download URL template of the installer.
+    pub fn new<S: Into<String>>(url_template: S) -> Self {
+        Self {
+            url_template: url_template.into(),
+            license: None,
+        }
+    }
+
+    /// Set the SPDX identifier of the license of the app.
+    pub fn license<S: Into<String>>(mut self, license: S) -> Self {
+        self.license.replace(license.into());
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Installer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'static str>,
+    args: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArchitectureEntry {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AutoUpdate {
+    architecture: BTreeMap<&'static str, ArchitectureEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct ScoopManifest<'a> {
+    version: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<&'a str>,
+    architecture: BTreeMap<&'static str, ArchitectureEntry>,
+    installer: Installer,
+    uninstaller: Installer,
+    bin: Vec<String>,
+    autoupdate: AutoUpdate,
+}
+
+/// Returns the Scoop architecture of the target.
+fn scoop_arch(config: &Config) -> crate::Result<&'static str> {
+    match config.target_arch()? {
+        "x86_64" => Ok("64bit"),
+        "x86" => Ok("32bit"),
+        "aarch64" => Ok("arm64"),
+        target => Err(Error::UnsupportedArch("scoop".into(), target.into())),
+    }
+}
+
+/// Checks that `manifest` only uses the keys known by Scoop and defines the required ones.
+fn validate_manifest(manifest: &Value) -> crate::Result<()> {
+    let invalid = |reason: String| Err(Error::InvalidScoopManifest(reason));
+
+    let Some(manifest) = manifest.as_object() else {
+        return invalid("the manifest must be an object".into());
+    };
+    if let Some(key) = manifest
+        .keys()
+        .find(|k| !SCOOP_MANIFEST_KEYS.contains(&k.as_str()))
+    {
+        return invalid(format!("unknown key `{key}`"));
+    }
+    if !manifest.get("version").is_some_and(|v| v.is_string()) {
+        return invalid("`version` must be a string".into());
+    }
+
+    let architectures = manifest.get("architecture").and_then(|a| a.as_object());
+    let has_url = manifest.contains_key("url")
+        || architectures
+            .is_some_and(|a| !a.is_empty() && a.values().all(|entry| entry.get("url").is_some()));
+    if !has_url {
+        return invalid("the manifest must define the `url` of each architecture".into());
+    }
+    for (arch, entry) in architectures.into_iter().flatten() {
+        if !["64bit", "32bit", "arm64"].contains(&arch.as_str()) {
+            return invalid(format!("unknown architecture `{arch}`"));
+        }
+        if let Some(key) = entry
+            .as_object()
+            .into_iter()
+            .flat_map(|e| e.keys())
+            .find(|k| !SCOOP_ARCHITECTURE_KEYS.contains(&k.as_str()))
+        {
+            return invalid(format!("unknown key `{key}` in the `{arch}` architecture"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Generates the Scoop manifest of the NSIS installer in `packages`.
+///
+/// The manifest runs the installer silently into the Scoop app directory,
+/// exposes the main binary and uses the SHA-256 digest of the installer.
+/// Its `autoupdate` entry uses the download URL with the version replaced by `$version`,
+/// so `checkver` can be added to keep the manifest up to date.
+pub fn generate_manifest(
+    config: &Config,
+    packages: &[PackageOutput],
+    manifest_config: &ScoopManifestConfig,
+) -> crate::Result<Value> {
+    let installer = packages
+        .iter()
+        .find(|p| p.format == PackageFormat::Nsis)
+        .and_then(|p| p.paths.first())
+        .ok_or(Error::ScoopArtifactNotFound)?;
+    let installer_name = installer
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| Error::FailedToExtractFilename(installer.clone()))?;
+    let arch = scoop_arch(config)?;
+
+    let url = manifest_config
+        .url_template
+        .replace("{{version}}", &config.version)
+        .replace("{{artefact}}", installer_name);
+    let autoupdate_url = manifest_config
+        .url_template
+        .replace("{{version}}", "$version")
+        .replace(
+            "{{artefact}}",
+            &installer_name.replace(&config.version, "$version"),
+        );
+
+    let manifest = ScoopManifest {
+        version: &config.version,
+        description: config.description.as_deref(),
+        homepage: config.homepage.as_deref(),
+        license: manifest_config.license.as_deref(),
+        architecture: BTreeMap::from([(
+            arch,
+            ArchitectureEntry {
+                url,
+                hash: Some(checksum::sha256_file(installer)?),
+            },
+        )]),
+        installer: Installer {
+            file: None,
+            // `/D` must be the last argument of NSIS installers
+            args: vec!["/S", "/D=$dir"],
+        },
+        uninstaller: Installer {
+            file: Some("uninstall.exe"),
+            args: vec!["/S"],
+        },
+        bin: vec![format!("{}.exe", config.main_binary_name()?)],
+        autoupdate: AutoUpdate {
+            architecture: BTreeMap::from([(
+                arch,
+                ArchitectureEntry {
+                    url: autoupdate_url,
+                    hash: None,
+                },
+            )]),
+        },
+    };
+
+    let manifest = serde_json::to_value(manifest)?;
+    validate_manifest(&manifest)?;
+    Ok(manifest)
+}
+
+/// Writes the Scoop manifest generated by [`generate_manifest`]
+/// into `<out_dir>/<name>.json` and returns its path.
+///
+/// The name is the lowercase [`Config::product_name`] with the characters
+/// other than ASCII letters and digits replaced with `-`.
+pub fn write_manifest(
+    config: &Config,
+    packages: &[PackageOutput],
+    manifest_config: &ScoopManifestConfig,
+    out_dir: &Path,
+) -> crate::Result<PathBuf> {
+    let manifest = generate_manifest(config, packages, manifest_config)?;
+    let manifest_path = out_dir.join(format!("{}.json", config.slug()?));
+    let manifest_file =
+        File::create(&manifest_path).map_err(|e| Error::IoWithPath(manifest_path.clone(), e))?;
+    serde_json::to_writer_pretty(manifest_file, &manifest)?;
+    Ok(manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Binary;
+
+    #[test]
+    fn it_generates_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = dir.path().join("app_1.2.0_x64-setup.exe");
+        std::fs::write(&installer, "").unwrap();
+
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.2.0".into();
+        config.target_triple = Some("x86_64-pc-windows-msvc".into());
+        config.homepage = Some("https://example.com".into());
+        config.binaries = vec![Binary::new("app").main(true)];
+        let packages = vec![PackageOutput::new(PackageFormat::Nsis, vec![installer])];
+        let manifest_config =
+            ScoopManifestConfig::new("https://example.com/releases/v{{version}}/{{artefact}}")
+                .license("MIT");
+
+        let manifest = generate_manifest(&config, &packages, &manifest_config).unwrap();
+        assert_eq!(
+            manifest,
+            serde_json::json!({
+                "version": "1.2.0",
+                "homepage": "https://example.com",
+                "license": "MIT",
+                "architecture": {
+                    "64bit": {
+                        "url": "https://example.com/releases/v1.2.0/app_1.2.0_x64-setup.exe",
+                        "hash": "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                    }
+                },
+                "installer": { "args": ["/S", "/D=$dir"] },
+                "uninstaller": { "file": "uninstall.exe", "args": ["/S"] },
+                "bin": ["app.exe"],
+                "autoupdate": {
+                    "architecture": {
+                        "64bit": {
+                            "url": "https://example.com/releases/v$version/app_$version_x64-setup.exe"
+                        }
+                    }
+                }
+            })
+        );
+
+        let manifest_path =
+            write_manifest(&config, &packages, &manifest_config, dir.path()).unwrap();
+        assert_eq!(manifest_path, dir.path().join("my-app.json"));
+        let written: Value = serde_json::from_reader(File::open(manifest_path).unwrap()).unwrap();
+        assert_eq!(written, manifest);
+
+        assert!(matches!(
+            generate_manifest(&config, &[], &manifest_config),
+            Err(Error::ScoopArtifactNotFound)
+        ));
+    }
+
+    #[test]
+    fn it_validates_manifest_keys() {
+        let valid = serde_json::json!({
+            "version": "1.0.0",
+            "url": "https://example.com/app.exe",
+            "bin": "app.exe"
+        });
+        validate_manifest(&valid).unwrap();
+
+        for invalid in [
+            serde_json::json!({ "url": "https://example.com/app.exe" }),
+            serde_json::json!({ "version": "1.0.0" }),
+            serde_json::json!({ "version": "1.0.0", "url": "", "binaries": [] }),
+            serde_json::json!({ "version": "1.0.0", "architecture": { "x64": { "url": "" } } }),
+            serde_json::json!({ "version": "1.0.0", "architecture": { "64bit": { "url": "", "sha256": "" } } }),
+        ] {
+            assert!(
+                matches!(
+                    validate_manifest(&invalid),
+                    Err(Error::InvalidScoopManifest(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+}
@@ -5,11 +5,107 @@
 
 use std::{
     borrow::Cow,
-    io::{BufRead, BufReader},
+    ffi::OsStr,
+    io::{BufRead, BufReader, Read},
+    path::Path,
     process::{Command, Output, Stdio},
-    sync::{Arc, Mutex},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
+use crate::cancellation;
+
+/// Flags whose following argument is a secret and must not be logged.
+const SECRET_FLAGS: &[&str] = &["-p", "-P", "--password", "/p"];
+
+/// Flags whose following argument is a secret only for a subcommand of a tool, as `(program, subcommand, flag)`.
+///
+/// `-k` is the keychain password of `security set-key-partition-list`,
+/// but the keychain name of the other `security` subcommands, which is logged.
+const SUBCOMMAND_SECRET_FLAGS: &[(&str, &str, &str)] =
+    &[("security", "set-key-partition-list", "-k")];
+
+/// Substrings of environment variable names whose value is a secret and must not be logged.
+const SECRET_ENV_VARS: &[&str] = &["PASSWORD", "SECRET", "TOKEN", "PRIVATE_KEY"];
+
+const REDACTED: &str = "<redacted>";
+
+/// How often a running command checks whether the packaging run was cancelled.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long the output of a command that exited is still read,
+/// when a process it started inherited its pipes and keeps them open.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The message of the `ENOSPC` error, printed by the external tools when the device is full.
+const OUT_OF_SPACE_MESSAGE: &str = "No space left on device";
+
+fn cancelled_error(argv: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Interrupted,
+        format!("packaging was cancelled, `{argv}` was not completed"),
+    )
+}
+
+/// Formats the command program and arguments, redacting secrets.
+fn display_argv(cmd: &Command) -> String {
+    let mut argv = vec![cmd.get_program().to_string_lossy().into_owned()];
+    let program = Path::new(cmd.get_program()).file_name();
+    let subcommand = cmd.get_args().next();
+    let is_secret_flag = |flag: &str| {
+        SECRET_FLAGS.contains(&flag)
+            || SUBCOMMAND_SECRET_FLAGS.iter().any(|(p, s, f)| {
+                program == Some(OsStr::new(p)) && subcommand == Some(OsStr::new(s)) && flag == *f
+            })
+    };
+    let mut redact_next = false;
+    for arg in cmd.get_args() {
+        let arg = arg.to_string_lossy();
+        if redact_next {
+            argv.push(REDACTED.to_string());
+            redact_next = false;
+            continue;
+        }
+        match arg.split_once('=') {
+            Some((flag, _)) if is_secret_flag(flag) => {
+                argv.push(format!("{flag}={REDACTED}"));
+            }
+            _ => {
+                redact_next = is_secret_flag(&arg);
+                argv.push(arg.into_owned());
+            }
+        }
+    }
+    argv.iter()
+        .map(|a| {
+            if a.is_empty() || a.contains(char::is_whitespace) {
+                format!("{a:?}")
+            } else {
+                a.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats the environment variables explicitly set on the command, redacting secrets.
+fn display_envs(cmd: &Command) -> String {
+    cmd.get_envs()
+        .map(|(key, value)| {
+            let key = key.to_string_lossy();
+            let upper = key.to_uppercase();
+            let value = if SECRET_ENV_VARS.iter().any(|s| upper.contains(s)) {
+                Cow::Borrowed(REDACTED)
+            } else {
+                value.map(OsStr::to_string_lossy).unwrap_or_default()
+            };
+            format!("{key}={value}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 pub trait CommandExt {
     fn output_ok(&mut self) -> std::io::Result<Output>;
     fn output_ok_info(&mut self) -> std::io::Result<Output>;
@@ -26,54 +122,70 @@ impl CommandExt for Command {
     }
 
     fn output_ok_inner(&mut self, level: tracing::Level) -> std::io::Result<Output> {
-        tracing::debug!("Running Command `{self:?}`");
+        let argv = display_argv(self);
+        let cwd = self
+            .get_current_dir()
+            .map(|d| d.to_path_buf())
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        tracing::debug!(
+            cwd = %cwd.display(),
+            env = %display_envs(self),
+            "Running Command `{argv}`"
+        );
+
+        if cancellation::is_cancelled() {
+            return Err(cancelled_error(&argv));
+        }
 
         self.stdout(Stdio::piped());
         self.stderr(Stdio::piped());
 
         let mut child = self.spawn()?;
 
-        let mut stdout = child.stdout.take().map(BufReader::new).unwrap();
+        let (done_tx, done_rx) = mpsc::channel();
         let stdout_lines = Arc::new(Mutex::new(Vec::new()));
-        let stdout_lines_ = stdout_lines.clone();
-        std::thread::spawn(move || {
-            let mut buf = Vec::new();
-            let mut lines = stdout_lines_.lock().unwrap();
-            loop {
-                buf.clear();
-                if let Ok(0) = stdout.read_until(b'\n', &mut buf) {
-                    break;
-                }
-                log(
-                    level,
-                    "stdout",
-                    String::from_utf8_lossy(&buf[..buf.len() - 1]),
-                );
-                lines.extend(&buf);
-            }
-        });
-
-        let mut stderr = child.stderr.take().map(BufReader::new).unwrap();
+        let stdout = child.stdout.take().unwrap();
+        read_lines(
+            stdout,
+            level,
+            "stdout",
+            stdout_lines.clone(),
+            done_tx.clone(),
+        );
         let stderr_lines = Arc::new(Mutex::new(Vec::new()));
-        let stderr_lines_ = stderr_lines.clone();
-        std::thread::spawn(move || {
-            let mut buf = Vec::new();
-            let mut lines = stderr_lines_.lock().unwrap();
-            loop {
-                buf.clear();
-                if let Ok(0) = stderr.read_until(b'\n', &mut buf) {
-                    break;
+        let stderr = child.stderr.take().unwrap();
+        read_lines(stderr, level, "stderr", stderr_lines.clone(), done_tx);
+
+        let status = match cancellation::current() {
+            // poll the child so it can be killed when the run is cancelled
+            Some(token) => loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if token.is_cancelled() {
+                    tracing::debug!("Killing `{argv}` because packaging was cancelled");
+                    // the child may exit between `try_wait` and `kill`
+                    let _ = child.kill();
+                    child.wait()?;
+                    return Err(cancelled_error(&argv));
                 }
-                log(
-                    level,
-                    "stderr",
-                    String::from_utf8_lossy(&buf[..buf.len() - 1]),
+                std::thread::sleep(CANCELLATION_POLL_INTERVAL);
+            },
+            None => child.wait()?,
+        };
+        // the output is complete once both readers reach its end, which a process started by the command
+        // delays until it exits when it inherited the pipes, like a daemon, so they are only awaited for a while
+        let deadline = Instant::now() + OUTPUT_DRAIN_TIMEOUT;
+        for _ in 0..2 {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if done_rx.recv_timeout(timeout).is_err() {
+                tracing::debug!(
+                    "The output of `{argv}` is still open after it exited, a process it started may have inherited it"
                 );
-                lines.extend(&buf);
+                break;
             }
-        });
-
-        let status = child.wait()?;
+        }
         let output = Output {
             status,
             stdout: std::mem::take(&mut *stdout_lines.lock().unwrap()),
@@ -83,15 +195,53 @@ impl CommandExt for Command {
         if output.status.success() {
             Ok(output)
         } else {
-            Err(std::io::Error::other(format!(
-                "failed to run command: {self:?}\nstdout: {}\nstderr: {}",
-                String::from_utf8_lossy(&output.stdout),
-                String::from_utf8_lossy(&output.stderr)
-            )))
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            tracing::debug!(
+                cwd = %cwd.display(),
+                "Command `{argv}` failed with {}\nstdout: {stdout}\nstderr: {stderr}",
+                output.status
+            );
+            // the tools report `ENOSPC` failures with its error message
+            let kind =
+                if stderr.contains(OUT_OF_SPACE_MESSAGE) || stdout.contains(OUT_OF_SPACE_MESSAGE) {
+                    std::io::ErrorKind::StorageFull
+                } else {
+                    std::io::ErrorKind::Other
+                };
+            Err(std::io::Error::new(
+                kind,
+                format!("failed to run command: {argv}\nstdout: {stdout}\nstderr: {stderr}"),
+            ))
         }
     }
 }
 
+/// Reads the lines of `reader` into `lines` in a thread until its end, logging them,
+/// then notifies `done`.
+fn read_lines<R: Read + Send + 'static>(
+    reader: R,
+    level: tracing::Level,
+    shell: &'static str,
+    lines: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Sender<()>,
+) {
+    let mut reader = BufReader::new(reader);
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            if let Ok(0) | Err(_) = reader.read_until(b'\n', &mut buf) {
+                break;
+            }
+            let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+            log(level, shell, String::from_utf8_lossy(line));
+            lines.lock().unwrap().extend(&buf);
+        }
+        let _ = done.send(());
+    });
+}
+
 #[inline]
 fn log(level: tracing::Level, shell: &str, msg: Cow<'_, str>) {
     match level {
@@ -99,3 +249,75 @@ fn log(level: tracing::Level, shell: &str, msg: Cow<'_, str>) {
         _ => tracing::debug!(shell = shell, "{msg}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_redacts_secrets() {
+        let mut cmd = Command::new("security");
+        cmd.args([
+            "import",
+            "cert.p12",
+            "-P",
+            "hunter2",
+            "--password=hunter2",
+            "-T",
+        ])
+        .arg("/usr/bin/code sign")
+        .env("CERTIFICATE_PASSWORD", "hunter2")
+        .env("LANG", "C");
+        assert_eq!(
+            display_argv(&cmd),
+            "security import cert.p12 -P <redacted> --password=<redacted> -T \"/usr/bin/code sign\""
+        );
+        assert_eq!(display_envs(&cmd), "CERTIFICATE_PASSWORD=<redacted> LANG=C");
+
+        // `-k` is only a password for some subcommands
+        let mut cmd = Command::new("security");
+        cmd.args(["import", "cert.p12", "-k", "app.keychain"]);
+        assert_eq!(
+            display_argv(&cmd),
+            "security import cert.p12 -k app.keychain"
+        );
+        let mut cmd = Command::new("/usr/bin/security");
+        cmd.args([
+            "set-key-partition-list",
+            "-s",
+            "-k",
+            "hunter2",
+            "app.keychain",
+        ]);
+        assert_eq!(
+            display_argv(&cmd),
+            "/usr/bin/security set-key-partition-list -s -k <redacted> app.keychain"
+        );
+        let mut cmd = Command::new("ditto");
+        cmd.args(["-c", "-k", "App.app", "App.zip"]);
+        assert_eq!(display_argv(&cmd), "ditto -c -k App.app App.zip");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_reads_the_output_of_commands_without_waiting_for_their_daemons() {
+        let start = Instant::now();
+        let output = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2; sleep 10 &"])
+            .output_ok()
+            .unwrap();
+        assert!(
+            start.elapsed() < OUTPUT_DRAIN_TIMEOUT * 3,
+            "{:?}",
+            start.elapsed()
+        );
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+
+        let output = Command::new("sh")
+            .args(["-c", "seq 1 10000"])
+            .output_ok()
+            .unwrap();
+        assert_eq!(output.stdout.iter().filter(|b| **b == b'\n').count(), 10000);
+    }
+}
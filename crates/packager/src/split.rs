@@ -0,0 +1,196 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Splitting of packaged artifacts into fixed-size volumes.
+//!
+//! [`split_file`] splits a file into `<file>.part1`, `<file>.part2`, ... volumes
+//! and writes a `<file>.parts.json` manifest describing how to reassemble them,
+//! which [`join_volumes`] uses to restore the original file.
+//! The volumes can also be joined by concatenating them in order,
+//! for example with `cat app.AppImage.part* > app.AppImage`
+//! when there are less than ten volumes.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{checksum, util::PathExt, Error};
+
+/// The extension of the manifests written by [`split_file`].
+pub const MANIFEST_EXTENSION: &str = "parts.json";
+
+/// A volume of a split file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Volume {
+    /// The file name of the volume.
+    pub name: String,
+    /// The size of the volume in bytes.
+    pub size: u64,
+    /// The SHA-256 digest of the volume, encoded as a lowercase hex string.
+    pub sha256: String,
+}
+
+/// The manifest describing how to reassemble a split file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct SplitManifest {
+    /// The file name of the original file.
+    pub file: String,
+    /// The size of the original file in bytes.
+    pub size: u64,
+    /// The SHA-256 digest of the original file, encoded as a lowercase hex string.
+    pub sha256: String,
+    /// The volumes to concatenate, in order, to restore the original file.
+    pub volumes: Vec<Volume>,
+}
+
+/// Splits the file at `path` into volumes of at most `volume_size` bytes next to it,
+/// and returns the path of the `<file>.parts.json` manifest and the paths of the volumes.
+///
+/// The original file is kept.
+pub fn split_file<P: AsRef<Path>>(
+    path: P,
+    volume_size: u64,
+) -> crate::Result<(PathBuf, Vec<PathBuf>)> {
+    let path = path.as_ref();
+    if volume_size == 0 {
+        return Err(Error::InvalidVolumeSize);
+    }
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(path.to_path_buf()))?
+        .to_string_lossy()
+        .into_owned();
+
+    let mut file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?
+        .len();
+
+    let count = size.div_ceil(volume_size).max(1);
+    let mut volumes = Vec::new();
+    let mut paths = Vec::new();
+    for i in 1..=count {
+        let volume_path = path.with_additional_extension(format!("part{i}"));
+        let mut writer = crate::util::create_file(&volume_path)?;
+        let volume_size = io::copy(&mut (&mut file).take(volume_size), &mut writer)
+            .map_err(|e| Error::IoWithPath(volume_path.clone(), e))?;
+        writer
+            .flush()
+            .map_err(|e| Error::IoWithPath(volume_path.clone(), e))?;
+        drop(writer);
+
+        volumes.push(Volume {
+            name: format!("{file_name}.part{i}"),
+            size: volume_size,
+            sha256: checksum::sha256_file(&volume_path)?,
+        });
+        paths.push(volume_path);
+    }
+
+    let manifest = SplitManifest {
+        file: file_name,
+        size,
+        sha256: checksum::sha256_file(path)?,
+        volumes,
+    };
+    let manifest_path = path.with_additional_extension(MANIFEST_EXTENSION);
+    let mut manifest_file = crate::util::create_file(&manifest_path)?;
+    serde_json::to_writer_pretty(&mut manifest_file, &manifest)?;
+    manifest_file.flush()?;
+
+    Ok((manifest_path, paths))
+}
+
+/// Joins the volumes described by the manifest at `manifest_path`, located next to it,
+/// into the file at `dest` and returns the manifest.
+///
+/// Fails with [`Error::VolumeChecksumMismatch`] if a volume or the joined file
+/// doesn't match its checksum in the manifest.
+pub fn join_volumes<P: AsRef<Path>, D: AsRef<Path>>(
+    manifest_path: P,
+    dest: D,
+) -> crate::Result<SplitManifest> {
+    let manifest_path = manifest_path.as_ref();
+    let dest = dest.as_ref();
+    let manifest_file =
+        File::open(manifest_path).map_err(|e| Error::IoWithPath(manifest_path.to_path_buf(), e))?;
+    let manifest: SplitManifest = serde_json::from_reader(BufReader::new(manifest_file))?;
+    let dir = manifest_path
+        .parent()
+        .ok_or_else(|| Error::ParentDirNotFound(manifest_path.to_path_buf()))?;
+
+    let mut writer = crate::util::create_file(dest)?;
+    for volume in &manifest.volumes {
+        let volume_path = dir.join(&volume.name);
+        if checksum::sha256_file(&volume_path)? != volume.sha256 {
+            return Err(Error::VolumeChecksumMismatch(volume_path));
+        }
+        let mut volume_file =
+            File::open(&volume_path).map_err(|e| Error::IoWithPath(volume_path.clone(), e))?;
+        io::copy(&mut volume_file, &mut writer)
+            .map_err(|e| Error::IoWithPath(dest.to_path_buf(), e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| Error::IoWithPath(dest.to_path_buf(), e))?;
+    drop(writer);
+
+    if checksum::sha256_file(dest)? != manifest.sha256 {
+        return Err(Error::VolumeChecksumMismatch(dest.to_path_buf()));
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_and_joins_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.AppImage");
+        let data = (0..2500u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        std::fs::write(&file, &data).unwrap();
+
+        let (manifest_path, volumes) = split_file(&file, 1000).unwrap();
+        assert_eq!(manifest_path, dir.path().join("app.AppImage.parts.json"));
+        assert_eq!(
+            volumes,
+            ["part1", "part2", "part3"].map(|p| dir.path().join(format!("app.AppImage.{p}")))
+        );
+        let sizes = volumes
+            .iter()
+            .map(|v| std::fs::metadata(v).unwrap().len())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [1000, 1000, 500]);
+
+        let joined = dir.path().join("joined.AppImage");
+        let manifest = join_volumes(&manifest_path, &joined).unwrap();
+        assert_eq!(manifest.file, "app.AppImage");
+        assert_eq!(manifest.size, 2500);
+        assert_eq!(manifest.sha256, checksum::sha256_file(&file).unwrap());
+        assert_eq!(std::fs::read(&joined).unwrap(), data);
+
+        std::fs::write(&volumes[1], "corrupted").unwrap();
+        assert!(matches!(
+            join_volumes(&manifest_path, &joined),
+            Err(Error::VolumeChecksumMismatch(path)) if path == volumes[1]
+        ));
+
+        assert!(matches!(
+            split_file(&file, 0),
+            Err(Error::InvalidVolumeSize)
+        ));
+    }
+}
@@ -0,0 +1,481 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! RFC 3161 timestamps of the signatures.
+//!
+//! When [`SigningConfig::timestamp_url`](crate::sign::SigningConfig::timestamp_url) is set,
+//! each signature file is timestamped by the time-stamping authority (TSA) at that URL
+//! after signing, which proves when the signature was made independently of the clock of the signer.
+//!
+//! The Time-Stamp Protocol of [RFC 3161](https://www.rfc-editor.org/rfc/rfc3161) is used over HTTP:
+//! a DER-encoded `TimeStampReq` with the SHA-256 digest of the signature file, a random nonce
+//! and a request for the TSA certificate is posted with the `application/timestamp-query`
+//! content type, and the DER-encoded `TimeStampResp` is written next to the signature,
+//! in `<signature>.tsr`, for example `app.AppImage.sig.tsr`.
+//!
+//! The response can also be inspected and verified with OpenSSL:
+//! `openssl ts -reply -in app.AppImage.sig.tsr -text` and
+//! `openssl ts -verify -data app.AppImage.sig -in app.AppImage.sig.tsr -CAfile tsa-ca.pem`.
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{checksum::sha256_file_bytes, der::DerReader, shell::CommandExt, util::PathExt, Error};
+
+/// The DER encoding of the SHA-256 algorithm identifier, `2.16.840.1.101.3.4.2.1`.
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// The DER encoding of the CMS signed data content type, `1.2.840.113549.1.7.2`.
+const SIGNED_DATA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// The DER encoding of the TSTInfo content type, `1.2.840.113549.1.9.16.1.4`.
+const TST_INFO_OID: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const BOOLEAN: u8 = 0x01;
+const GENERALIZED_TIME: u8 = 0x18;
+const CONTEXT_0: u8 = 0xa0;
+
+/// The timestamp of a signature, read from its `.tsr` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Timestamp {
+    /// The time the TSA timestamped the signature, in seconds since the Unix epoch.
+    pub time: u64,
+    /// The time the TSA timestamped the signature, as the `GeneralizedTime` of the token,
+    /// for example `20240102100000Z`.
+    pub gen_time: String,
+    /// The serial number of the token, encoded as a lowercase hex string.
+    pub serial_number: String,
+}
+
+/// Returns the path of the timestamp of `signature_path`, `<signature>.tsr`.
+pub fn timestamp_path(signature_path: &Path) -> PathBuf {
+    signature_path.with_additional_extension("tsr")
+}
+
+/// Encodes a DER element.
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let bytes = &bytes[bytes
+            .iter()
+            .position(|&b| b != 0)
+            .unwrap_or(bytes.len() - 1)..];
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Encodes a DER positive integer from its big-endian bytes.
+fn der_unsigned(bytes: &[u8]) -> Vec<u8> {
+    let bytes = &bytes[bytes
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(bytes.len() - 1)..];
+    let mut content = Vec::with_capacity(bytes.len() + 1);
+    if bytes[0] & 0x80 != 0 {
+        content.push(0);
+    }
+    content.extend_from_slice(bytes);
+    der(INTEGER, &content)
+}
+
+/// Encodes the `MessageImprint` of a SHA-256 digest.
+fn message_imprint(digest: &[u8]) -> Vec<u8> {
+    let algorithm = der(
+        SEQUENCE,
+        &[der(OBJECT_IDENTIFIER, SHA256_OID), der(NULL, &[])].concat(),
+    );
+    der(SEQUENCE, &[algorithm, der(OCTET_STRING, digest)].concat())
+}
+
+/// Encodes the `TimeStampReq` of a SHA-256 digest.
+fn timestamp_request(digest: &[u8], nonce: &[u8]) -> Vec<u8> {
+    der(
+        SEQUENCE,
+        &[
+            der(INTEGER, &[1]),
+            message_imprint(digest),
+            der_unsigned(nonce),
+            // certReq, so the TSA certificate is included to verify the token
+            der(BOOLEAN, &[0xff]),
+        ]
+        .concat(),
+    )
+}
+
+/// The fields of a `TimeStampResp` that are checked.
+struct TimestampResponse<'a> {
+    digest: &'a [u8],
+    nonce: Option<&'a [u8]>,
+    gen_time: &'a str,
+    serial_number: &'a [u8],
+}
+
+/// Parses a DER-encoded `TimeStampResp`.
+fn parse_response(response: &[u8]) -> Result<TimestampResponse<'_>, String> {
+    let malformed = || "the response is malformed".to_string();
+
+    let mut resp = DerReader(DerReader(response).expect(SEQUENCE).ok_or_else(malformed)?);
+    let mut status = DerReader(resp.expect(SEQUENCE).ok_or_else(malformed)?);
+    match status.expect(INTEGER).ok_or_else(malformed)? {
+        // granted and grantedWithMods
+        [0] | [1] => {}
+        code => {
+            let reason = status
+                .optional(SEQUENCE)
+                .and_then(|s| DerReader(s).next())
+                .map(|(_, s)| String::from_utf8_lossy(s).into_owned());
+            return Err(format!(
+                "the request was rejected with status {}{}",
+                code.last().copied().unwrap_or_default(),
+                reason.map(|r| format!(": {r}")).unwrap_or_default()
+            ));
+        }
+    }
+
+    // TimeStampToken ::= ContentInfo
+    let mut token = DerReader(
+        resp.expect(SEQUENCE)
+            .ok_or_else(|| "the response doesn't contain a timestamp token".to_string())?,
+    );
+    if token.expect(OBJECT_IDENTIFIER) != Some(SIGNED_DATA_OID) {
+        return Err("the timestamp token isn't a CMS signed data".into());
+    }
+    let signed_data = DerReader(token.expect(CONTEXT_0).ok_or_else(malformed)?)
+        .expect(SEQUENCE)
+        .ok_or_else(malformed)?;
+    let mut signed_data = DerReader(signed_data);
+    signed_data.expect(INTEGER).ok_or_else(malformed)?;
+    signed_data.expect(SET).ok_or_else(malformed)?;
+    let mut content_info = DerReader(signed_data.expect(SEQUENCE).ok_or_else(malformed)?);
+    if content_info.expect(OBJECT_IDENTIFIER) != Some(TST_INFO_OID) {
+        return Err("the timestamp token doesn't contain a TSTInfo".into());
+    }
+    let tst_info = DerReader(content_info.expect(CONTEXT_0).ok_or_else(malformed)?)
+        .expect(OCTET_STRING)
+        .ok_or_else(malformed)?;
+
+    let mut tst_info = DerReader(DerReader(tst_info).expect(SEQUENCE).ok_or_else(malformed)?);
+    tst_info.expect(INTEGER).ok_or_else(malformed)?;
+    tst_info.expect(OBJECT_IDENTIFIER).ok_or_else(malformed)?;
+    let mut imprint = DerReader(tst_info.expect(SEQUENCE).ok_or_else(malformed)?);
+    let mut algorithm = DerReader(imprint.expect(SEQUENCE).ok_or_else(malformed)?);
+    if algorithm.expect(OBJECT_IDENTIFIER) != Some(SHA256_OID) {
+        return Err("the timestamp token isn't a SHA-256 imprint".into());
+    }
+    let digest = imprint.expect(OCTET_STRING).ok_or_else(malformed)?;
+    let serial_number = tst_info.expect(INTEGER).ok_or_else(malformed)?;
+    let gen_time = tst_info.expect(GENERALIZED_TIME).ok_or_else(malformed)?;
+    let gen_time = std::str::from_utf8(gen_time).map_err(|_| malformed())?;
+    // accuracy and ordering
+    tst_info.optional(SEQUENCE);
+    tst_info.optional(BOOLEAN);
+    let nonce = tst_info.optional(INTEGER);
+
+    Ok(TimestampResponse {
+        digest,
+        nonce,
+        gen_time,
+        serial_number,
+    })
+}
+
+/// Converts a `GeneralizedTime` like `20240102100000Z` or `20240102100000.123Z`
+/// to seconds since the Unix epoch.
+fn parse_gen_time(gen_time: &str) -> Option<u64> {
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    let digits = gen_time.strip_suffix('Z')?;
+    let digits = digits.split_once('.').map_or(digits, |(d, _)| d);
+    if digits.len() != 14 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| digits[range].parse::<u16>().ok();
+    let date = Date::from_calendar_date(
+        field(0..4)? as i32,
+        Month::try_from(field(4..6)? as u8).ok()?,
+        field(6..8)? as u8,
+    )
+    .ok()?;
+    let time = Time::from_hms(
+        field(8..10)? as u8,
+        field(10..12)? as u8,
+        field(12..14)? as u8,
+    )
+    .ok()?;
+    u64::try_from(
+        PrimitiveDateTime::new(date, time)
+            .assume_utc()
+            .unix_timestamp(),
+    )
+    .ok()
+}
+
+fn timestamp_from_response(response: &TimestampResponse<'_>) -> Result<Timestamp, String> {
+    Ok(Timestamp {
+        time: parse_gen_time(response.gen_time)
+            .ok_or_else(|| format!("invalid time `{}`", response.gen_time))?,
+        gen_time: response.gen_time.to_string(),
+        serial_number: hex::encode(response.serial_number),
+    })
+}
+
+/// Requests an RFC 3161 timestamp of the signature at `signature_path` from the TSA at `url`,
+/// and writes the response in `<signature>.tsr`.
+///
+/// Returns the path of the timestamp file and the timestamp.
+#[tracing::instrument(level = "trace")]
+pub fn timestamp_signature<P: AsRef<Path> + std::fmt::Debug>(
+    url: &str,
+    signature_path: P,
+) -> crate::Result<(PathBuf, Timestamp)> {
+    let signature_path = signature_path.as_ref();
+    let digest = sha256_file_bytes(signature_path)?;
+
+    let mut nonce_seed = Sha256::new();
+    nonce_seed.update(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_nanos()
+            .to_be_bytes(),
+    );
+    nonce_seed.update(std::process::id().to_be_bytes());
+    nonce_seed.update(digest);
+    let nonce = nonce_seed.finalize()[..8].to_vec();
+
+    tracing::debug!(
+        "Requesting a timestamp of {} from {url}",
+        signature_path.display()
+    );
+    let response = crate::util::http_agent()
+        .post(url)
+        .set("Content-Type", "application/timestamp-query")
+        .send_bytes(&timestamp_request(&digest, &nonce))
+        .map_err(|e| Error::TimestampRequestFailed(url.to_string(), e.to_string()))?;
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)?;
+
+    let failed = |reason: String| Error::TimestampRequestFailed(url.to_string(), reason);
+    let parsed = parse_response(&body).map_err(failed)?;
+    if parsed.digest != digest.as_slice() {
+        return Err(failed(
+            "the timestamp token doesn't match the signature".into(),
+        ));
+    }
+    let expected_nonce = der_unsigned(&nonce);
+    if parsed.nonce != Some(&expected_nonce[2..]) {
+        return Err(failed(
+            "the nonce of the timestamp token doesn't match the request".into(),
+        ));
+    }
+    let timestamp = timestamp_from_response(&parsed).map_err(failed)?;
+
+    let path = timestamp_path(signature_path);
+    std::fs::write(&path, &body).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    Ok((path, timestamp))
+}
+
+/// Verifies the `<signature>.tsr` timestamp of the signature at `signature_path`
+/// written by [`timestamp_signature`], and returns the timestamp.
+///
+/// The token must be granted by the TSA and must contain the SHA-256 digest of the signature file.
+/// When `ca_file` is set, the signature of the token and the certificate chain of the TSA
+/// are also verified against the PEM certificates of `ca_file` with `openssl ts -verify`;
+/// otherwise the token isn't authenticated.
+#[tracing::instrument(level = "trace")]
+pub fn verify_timestamp<P: AsRef<Path> + std::fmt::Debug>(
+    signature_path: P,
+    ca_file: Option<&Path>,
+) -> crate::Result<Timestamp> {
+    let signature_path = signature_path.as_ref();
+    let path = timestamp_path(signature_path);
+    let body = std::fs::read(&path).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+
+    let invalid = |reason: String| Error::InvalidTimestamp(path.clone(), reason);
+    let parsed = parse_response(&body).map_err(invalid)?;
+    if parsed.digest != sha256_file_bytes(signature_path)?.as_slice() {
+        return Err(invalid(
+            "the timestamp token doesn't match the signature".into(),
+        ));
+    }
+    let timestamp = timestamp_from_response(&parsed).map_err(invalid)?;
+
+    if let Some(ca_file) = ca_file {
+        Command::new("openssl")
+            .args(["ts", "-verify", "-data"])
+            .arg(signature_path)
+            .arg("-in")
+            .arg(&path)
+            .arg("-CAfile")
+            .arg(ca_file)
+            .output_ok()
+            .map_err(|e| invalid(e.to_string()))?;
+    }
+
+    Ok(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Builds a `TimeStampResp` granting an unsigned token.
+    fn response(digest: &[u8], nonce: &[u8], gen_time: &str) -> Vec<u8> {
+        let tst_info = der(
+            SEQUENCE,
+            &[
+                der(INTEGER, &[1]),
+                der(OBJECT_IDENTIFIER, &[0x2a, 0x03, 0x04]),
+                message_imprint(digest),
+                der(INTEGER, &[0x01, 0x23]),
+                der(GENERALIZED_TIME, gen_time.as_bytes()),
+                der_unsigned(nonce),
+            ]
+            .concat(),
+        );
+        let signed_data = der(
+            SEQUENCE,
+            &[
+                der(INTEGER, &[3]),
+                der(SET, &[]),
+                der(
+                    SEQUENCE,
+                    &[
+                        der(OBJECT_IDENTIFIER, TST_INFO_OID),
+                        der(CONTEXT_0, &der(OCTET_STRING, &tst_info)),
+                    ]
+                    .concat(),
+                ),
+                der(SET, &[]),
+            ]
+            .concat(),
+        );
+        let token = der(
+            SEQUENCE,
+            &[
+                der(OBJECT_IDENTIFIER, SIGNED_DATA_OID),
+                der(CONTEXT_0, &signed_data),
+            ]
+            .concat(),
+        );
+        der(
+            SEQUENCE,
+            &[der(SEQUENCE, &der(INTEGER, &[0])), token].concat(),
+        )
+    }
+
+    #[test]
+    fn it_timestamps_signatures() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tsa", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            let mut content_type = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_lowercase();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(len) = line.strip_prefix("content-length: ") {
+                    content_length = len.parse().unwrap();
+                }
+                if let Some(ty) = line.strip_prefix("content-type: ") {
+                    content_type = ty.to_string();
+                }
+            }
+            let mut request = vec![0; content_length];
+            reader.read_exact(&mut request).unwrap();
+
+            // TimeStampReq ::= SEQUENCE { version, messageImprint, nonce, certReq }
+            let mut req = DerReader(DerReader(&request).expect(SEQUENCE).unwrap());
+            assert_eq!(req.expect(INTEGER), Some(&[1][..]));
+            let mut imprint = DerReader(req.expect(SEQUENCE).unwrap());
+            imprint.expect(SEQUENCE).unwrap();
+            let digest = imprint.expect(OCTET_STRING).unwrap().to_vec();
+            let nonce = req.expect(INTEGER).unwrap().to_vec();
+            assert_eq!(req.expect(BOOLEAN), Some(&[0xff][..]));
+
+            let body = response(&digest, &nonce, "20240102100000.5Z");
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/timestamp-reply\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+            content_type
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let signature_path = dir.path().join("app.AppImage.sig");
+        std::fs::write(&signature_path, "signature").unwrap();
+
+        let (path, timestamp) = timestamp_signature(&url, &signature_path).unwrap();
+        assert_eq!(
+            server.join().unwrap(),
+            "application/timestamp-query".to_string()
+        );
+        assert_eq!(path, dir.path().join("app.AppImage.sig.tsr"));
+        assert_eq!(timestamp.time, 1704189600);
+        assert_eq!(timestamp.gen_time, "20240102100000.5Z");
+        assert_eq!(timestamp.serial_number, "0123");
+
+        assert_eq!(verify_timestamp(&signature_path, None).unwrap(), timestamp);
+
+        std::fs::write(&signature_path, "modified").unwrap();
+        assert!(matches!(
+            verify_timestamp(&signature_path, None),
+            Err(Error::InvalidTimestamp(p, _)) if p == path
+        ));
+    }
+
+    #[test]
+    fn it_rejects_failed_timestamp_responses() {
+        let rejected = der(
+            SEQUENCE,
+            &der(
+                SEQUENCE,
+                &[
+                    der(INTEGER, &[2]),
+                    der(SEQUENCE, &der(0x0c, b"unsupported algorithm")),
+                ]
+                .concat(),
+            ),
+        );
+        assert_eq!(
+            parse_response(&rejected).err().unwrap(),
+            "the request was rejected with status 2: unsupported algorithm"
+        );
+        assert!(parse_response(&[0x30, 0x05, 0x30]).is_err());
+    }
+}
@@ -0,0 +1,957 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of the update manifest consumed by `cargo-packager-updater`.
+//!
+//! ## Manifest signature
+//!
+//! [`sign_update_manifest`] signs the written `latest.json` into `latest.json.sig`,
+//! so clients can check that the manifest wasn't tampered with before trusting any of its fields.
+//! Clients should download both files and verify the signature of the exact bytes of the manifest
+//! with the public key of the app, for example with [`sign::verify_file`](crate::sign::verify_file)
+//! or `cargo packager verify latest.json --public-key <key>`, before parsing it
+//! and verifying the signatures of the update artifacts it lists.
+//!
+//! ## Manifest expiry
+//!
+//! With [`UpdateManifestConfig::expires_at`], the manifest carries an `expires_at` date, covered by its signature,
+//! after which clients must reject it, so a stale manifest served by a compromised mirror or cache can't be replayed
+//! indefinitely to keep the clients on an old, vulnerable version. [`verify_update_manifest`] verifies the signature
+//! of the manifest and fails with [`Error::ManifestExpired`] once it expired. The manifests without an expiry never expire,
+//! so the release pipeline must publish a new manifest before the current one expires.
+//!
+//! `cargo-packager-updater` also rejects the expired manifests it fetches, but it doesn't verify the signature
+//! of the manifest, so its check alone doesn't protect against a tampered `expires_at`.
+//!
+//! ## Public key
+//!
+//! With [`UpdateManifestConfig::public_key`], the base64-encoded public key that verifies the
+//! signatures is embedded in the `public_key` field of the manifest, and [`write_public_key`] writes it
+//! into the [`PUBLIC_KEY_FILE_NAME`] sidecar file, so the release files are self-contained for verification,
+//! for example with `cargo packager verify-release`.
+//!
+//! A key published along with the artifacts can be replaced along with them, so it can't be trusted
+//! on its own: clients must get the key they trust out-of-band, usually embedded in the app at its first install,
+//! and only use the published key to check it matches or to detect a key rotation.
+//!
+//! ## Payload signature
+//!
+//! With [`UpdateBundleConfig::payload_signing`], [`build_update_bundle`] signs the app bundle
+//! like [`sign::sign_directory`] before archiving it, adding the `MANIFEST` of its files
+//! and the `MANIFEST.sig` signature at the root of the app bundle in the `.app.tar.gz`.
+//! Signing the update bundle as usual then gives two layers of verification:
+//! 1. Before extracting the update bundle, the client verifies its signature from the update manifest,
+//!    like the updater does, proving the downloaded archive is the published one.
+//! 2. After extracting it, the client verifies the app bundle with [`sign::verify_directory`],
+//!    which fails if any file of the app was added, removed or modified since it was packaged,
+//!    even by a tampered archive signed with a leaked update key when the payload is signed with another key.
+//!
+//! The two files are outside of the code signature seal of the app on macOS,
+//! so the client should remove them after the verification for `codesign --verify --strict` to pass.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::GzEncoder, Compression};
+use serde::Serialize;
+use tar::HeaderMode;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+pub use crate::config::ReleaseNotes;
+
+use crate::{
+    package::{updater_platform, PackageOutputSummary},
+    sign, util, Config, Error, PackageOutput, SigningConfig,
+};
+
+/// The file name of the update manifest written by [`write_update_manifest`].
+pub const UPDATE_MANIFEST_FILE_NAME: &str = "latest.json";
+
+/// The file name of the public key written by [`write_public_key`].
+pub const PUBLIC_KEY_FILE_NAME: &str = "pubkey.pub";
+
+/// Options of the generated update manifest.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct UpdateManifestConfig {
+    /// The release notes of all the platforms, defaulting to the [`Config::release_notes`].
+    pub notes: Option<ReleaseNotes>,
+    /// Release notes overriding [`UpdateManifestConfig::notes`] for a platform,
+    /// where the key is `<platform>-<arch>`, e.g. `windows-x86_64`.
+    pub platform_notes: HashMap<String, ReleaseNotes>,
+    /// The release date formatted according to [RFC 3339](https://datatracker.ietf.org/doc/html/rfc3339#section-5.8).
+    ///
+    /// Defaults to the most recent timestamp in the trusted comments of the package signatures,
+    /// or the current time if the packages are not signed.
+    pub pub_date: Option<String>,
+    /// The base64-encoded public key embedded in the manifest, see [the module docs](self#public-key).
+    pub public_key: Option<String>,
+    /// The date after which the manifest must be rejected, formatted according to RFC 3339,
+    /// by [`verify_update_manifest`] and the updater, see [the module docs](self#manifest-expiry).
+    /// The manifest never expires by default.
+    pub expires_at: Option<String>,
+}
+
+impl UpdateManifestConfig {
+    /// Creates a new [`UpdateManifestConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the release notes of all the platforms.
+    pub fn notes(mut self, notes: ReleaseNotes) -> Self {
+        self.notes.replace(notes);
+        self
+    }
+
+    /// Set the release notes of a platform, where `platform` is `<platform>-<arch>`.
+    pub fn platform_notes<S: Into<String>>(mut self, platform: S, notes: ReleaseNotes) -> Self {
+        self.platform_notes.insert(platform.into(), notes);
+        self
+    }
+
+    /// Set the release date, formatted according to RFC 3339.
+    pub fn pub_date<S: Into<String>>(mut self, pub_date: S) -> Self {
+        self.pub_date.replace(pub_date.into());
+        self
+    }
+
+    /// Set the base64-encoded public key embedded in the manifest,
+    /// for example the one returned by [`sign::signing_public_key`].
+    pub fn public_key<S: Into<String>>(mut self, public_key: S) -> Self {
+        self.public_key.replace(public_key.into());
+        self
+    }
+
+    /// Set the expiry date of the manifest, formatted according to RFC 3339.
+    pub fn expires_at<S: Into<String>>(mut self, expires_at: S) -> Self {
+        self.expires_at.replace(expires_at.into());
+        self
+    }
+}
+
+/// The update manifest, in the static format expected by `cargo-packager-updater`.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateManifest {
+    /// The version of the update.
+    pub version: String,
+    /// The release notes.
+    pub notes: Option<String>,
+    /// The release date formatted according to RFC 3339.
+    pub pub_date: String,
+    /// The update of each platform, where the key is `<platform>-<arch>`.
+    pub platforms: BTreeMap<String, PackageOutputSummary>,
+    /// The [`Config::build_id`] of the packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+    /// The base64-encoded public key that verifies the signatures, see [the module docs](self#public-key).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// The date after which the manifest must be rejected, formatted according to RFC 3339,
+    /// see [the module docs](self#manifest-expiry).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+/// Returns the `timestamp` field of the trusted comment of an encoded signature.
+fn signature_timestamp(signature: &str) -> Option<i64> {
+    let decoded = String::from_utf8(STANDARD.decode(signature).ok()?).ok()?;
+    let trusted_comment = minisign::SignatureBox::from_string(&decoded)
+        .ok()?
+        .trusted_comment()
+        .ok()?;
+    trusted_comment
+        .split('\t')
+        .find_map(|field| field.strip_prefix("timestamp:"))
+        .and_then(|timestamp| timestamp.parse().ok())
+}
+
+/// Builds the update manifest of the specified packages.
+///
+/// Only the packages with a summary, which requires [`Config::endpoint`], are included.
+pub fn build_update_manifest(
+    config: &Config,
+    packages: &[PackageOutput],
+    manifest_config: &UpdateManifestConfig,
+) -> crate::Result<UpdateManifest> {
+    let mut platforms = BTreeMap::new();
+    for summary in packages.iter().filter_map(|p| p.summary.clone()) {
+        platforms.insert(summary.platform.clone(), summary);
+    }
+
+    for (platform, notes) in &manifest_config.platform_notes {
+        match platforms.get_mut(platform) {
+            Some(summary) => summary.notes = Some(notes.read()?),
+            None => tracing::warn!(
+                "Ignoring the release notes of `{platform}`, no package was built for this platform"
+            ),
+        }
+    }
+
+    let pub_date = match &manifest_config.pub_date {
+        Some(pub_date) => {
+            OffsetDateTime::parse(pub_date, &Rfc3339)
+                .map_err(|_| Error::InvalidPubDate(pub_date.clone()))?;
+            pub_date.clone()
+        }
+        None => {
+            let date = platforms
+                .values()
+                .filter_map(|s| s.signature.as_deref().and_then(signature_timestamp))
+                .max()
+                .and_then(|timestamp| OffsetDateTime::from_unix_timestamp(timestamp).ok())
+                .unwrap_or_else(OffsetDateTime::now_utc);
+            date.format(&Rfc3339).map_err(time::error::Error::from)?
+        }
+    };
+
+    if let Some(expires_at) = &manifest_config.expires_at {
+        OffsetDateTime::parse(expires_at, &Rfc3339)
+            .map_err(|_| Error::InvalidManifestExpiry(expires_at.clone()))?;
+    }
+
+    Ok(UpdateManifest {
+        version: config.version.clone(),
+        notes: manifest_config
+            .notes
+            .as_ref()
+            .or(config.release_notes.as_ref())
+            .map(ReleaseNotes::read)
+            .transpose()?,
+        pub_date,
+        platforms,
+        build_id: packages
+            .iter()
+            .find_map(|p| p.build_id.clone())
+            .or_else(|| config.build_id.clone()),
+        public_key: manifest_config.public_key.clone(),
+        expires_at: manifest_config.expires_at.clone(),
+    })
+}
+
+/// Writes the update manifest as [`UPDATE_MANIFEST_FILE_NAME`] into `out_dir` and returns its path.
+pub fn write_update_manifest(manifest: &UpdateManifest, out_dir: &Path) -> crate::Result<PathBuf> {
+    let manifest_path = out_dir.join(UPDATE_MANIFEST_FILE_NAME);
+    let manifest_file =
+        File::create(&manifest_path).map_err(|e| Error::IoWithPath(manifest_path.clone(), e))?;
+    serde_json::to_writer_pretty(manifest_file, manifest)?;
+    Ok(manifest_path)
+}
+
+/// Signs the update manifest written by [`write_update_manifest`] into `<manifest>.sig`,
+/// see the [module documentation](self#manifest-signature) for its verification.
+///
+/// Returns a tuple of the signature path and its content.
+pub fn sign_update_manifest(
+    signing_config: &SigningConfig,
+    manifest_path: &Path,
+) -> crate::Result<(PathBuf, String)> {
+    sign::sign_file(signing_config, manifest_path)
+}
+
+/// Fails with [`Error::ManifestExpired`] if the `expires_at` date of the manifest is before `now`.
+fn check_manifest_expiry(manifest: &serde_json::Value, now: OffsetDateTime) -> crate::Result<()> {
+    let Some(expires_at) = manifest.get("expires_at").filter(|e| !e.is_null()) else {
+        return Ok(());
+    };
+    let expires_at = expires_at
+        .as_str()
+        .ok_or_else(|| Error::InvalidManifestExpiry(expires_at.to_string()))?;
+    let expiry = OffsetDateTime::parse(expires_at, &Rfc3339)
+        .map_err(|_| Error::InvalidManifestExpiry(expires_at.to_string()))?;
+    if expiry <= now {
+        return Err(Error::ManifestExpired(expires_at.to_string()));
+    }
+    Ok(())
+}
+
+/// Verifies the update manifest at `manifest_path` against its `<manifest>.sig` signature
+/// using the specified base64-encoded public key, then checks that it hasn't expired,
+/// see the [module documentation](self#manifest-expiry).
+///
+/// Returns the verified manifest.
+pub fn verify_update_manifest<P: AsRef<Path>>(
+    manifest_path: P,
+    public_key: &str,
+) -> crate::Result<serde_json::Value> {
+    let manifest_path = manifest_path.as_ref();
+    sign::verify_file(manifest_path, public_key)?;
+    let manifest: serde_json::Value = serde_json::from_slice(
+        &fs::read(manifest_path).map_err(|e| Error::IoWithPath(manifest_path.to_path_buf(), e))?,
+    )?;
+    check_manifest_expiry(&manifest, OffsetDateTime::now_utc())?;
+    Ok(manifest)
+}
+
+/// Writes the public key of the private key of `signing_config` as [`PUBLIC_KEY_FILE_NAME`] into `out_dir`,
+/// see [the module docs](self#public-key) for how it can be trusted.
+///
+/// Returns a tuple of the file path and the base64-encoded public key.
+pub fn write_public_key(
+    signing_config: &SigningConfig,
+    out_dir: &Path,
+) -> crate::Result<(PathBuf, String)> {
+    let public_key = sign::signing_public_key(signing_config)?;
+    let path = out_dir.join(PUBLIC_KEY_FILE_NAME);
+    fs::write(&path, &public_key).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+    Ok((path, public_key))
+}
+
+/// The default [`UpdateBundleConfig::name_template`].
+pub const DEFAULT_UPDATE_BUNDLE_NAME: &str = "{name}_{version}_{target}";
+
+/// Options of the update bundles built by [`build_update_bundle`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct UpdateBundleConfig {
+    /// A template for the file name of the update bundles, without the extension.
+    ///
+    /// The following placeholders are substituted:
+    /// - `{name}`: the [`Config::product_name`].
+    /// - `{slug}`: the [`Config::slug`], like in the file names of the packages.
+    /// - `{version}`: the [`Config::version`].
+    /// - `{target}`: the `<platform>-<arch>` key of the bundle in the update manifest, e.g. `windows-x86_64`.
+    /// - `{arch}`: the `<arch>` part of `{target}`, one of `x86_64`, `i686`, `aarch64` or `armv7`.
+    /// - `{format}`: the package format, e.g. `nsis`.
+    ///
+    /// Defaults to [`DEFAULT_UPDATE_BUNDLE_NAME`].
+    pub name_template: Option<String>,
+    /// Sign the app bundle of [`PackageFormat::App`](crate::PackageFormat::App) packages
+    /// before archiving it, embedding the signature in the update bundle, see [the module docs](self).
+    pub payload_signing: Option<SigningConfig>,
+}
+
+impl UpdateBundleConfig {
+    /// Creates a new [`UpdateBundleConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the template for the file name of the update bundles.
+    pub fn name_template<S: Into<String>>(mut self, name_template: S) -> Self {
+        self.name_template.replace(name_template.into());
+        self
+    }
+
+    /// Set the signing configuration of the payload of the update bundles.
+    pub fn payload_signing(mut self, signing_config: SigningConfig) -> Self {
+        self.payload_signing.replace(signing_config);
+        self
+    }
+}
+
+/// Values substituted in an update bundle name template.
+struct UpdateBundleNameValues<'a> {
+    name: &'a str,
+    slug: &'a str,
+    version: &'a str,
+    target: &'a str,
+    format: &'a str,
+}
+
+/// Substitutes the placeholders of an update bundle name template.
+fn render_update_bundle_name(
+    template: &str,
+    values: &UpdateBundleNameValues<'_>,
+) -> crate::Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| Error::InvalidUpdateBundleName(template.to_string()))?;
+        match &rest[start + 1..end] {
+            "name" => out.push_str(values.name),
+            "slug" => out.push_str(values.slug),
+            "version" => out.push_str(values.version),
+            "target" => out.push_str(values.target),
+            "arch" => out.push_str(values.target.rsplit('-').next().unwrap_or_default()),
+            "format" => out.push_str(values.format),
+            name => return Err(Error::UnknownUpdateBundleNamePlaceholder(name.to_string())),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    if out.is_empty() || out.contains(['/', '\\', '}']) || out.contains(char::is_control) {
+        return Err(Error::InvalidUpdateBundleName(template.to_string()));
+    }
+
+    Ok(out)
+}
+
+/// Writes a `.tar.gz` of `dir` into `dest` with sorted entries and fixed metadata,
+/// keeping the extended attributes of the files when `xattrs` is true.
+///
+/// The `extra_files` are appended at the root of `dir` in the archive, after its entries.
+fn create_deterministic_tar_gz(
+    dir: &Path,
+    dest: &Path,
+    xattrs: bool,
+    extra_files: &[(&str, &[u8])],
+) -> crate::Result<()> {
+    let name = dir
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(dir.to_path_buf()))?;
+    let gzip_encoder = GzEncoder::new(util::create_file(dest)?, Compression::default());
+    let mut builder = tar::Builder::new(gzip_encoder);
+    builder.mode(HeaderMode::Deterministic);
+    builder.follow_symlinks(false);
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let path = Path::new(name).join(entry.path().strip_prefix(dir)?);
+        if xattrs {
+            util::append_tar_xattrs(&mut builder, entry.path())?;
+        }
+        builder.append_path_with_name(entry.path(), path)?;
+    }
+    for (file_name, content) in extra_files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        builder.append_data(&mut header, Path::new(name).join(file_name), *content)?;
+    }
+    let mut dest_file = builder.into_inner()?.finish()?;
+    dest_file.flush()?;
+    Ok(())
+}
+
+/// Builds the update bundle of a package in [`Config::out_dir`] and returns its path.
+///
+/// The bundle is named after [`UpdateBundleConfig::name_template`] followed by
+/// the extension of the format, and its content is what the updater expects for each format:
+/// - [`PackageFormat::Nsis`](crate::PackageFormat::Nsis): the `.exe` installer, run by the updater.
+/// - [`PackageFormat::Wix`](crate::PackageFormat::Wix): the `.msi` installer, run by the updater with `msiexec`.
+/// - [`PackageFormat::AppImage`](crate::PackageFormat::AppImage): the `.AppImage`, replacing the running one.
+/// - [`PackageFormat::App`](crate::PackageFormat::App): an `.app.tar.gz` archive of the app bundle, extracted in place of the running one.
+///
+/// The same inputs always produce the same file name,
+/// and the `.app.tar.gz` archives don't depend on the file modification times.
+///
+/// When [`UpdateBundleConfig::payload_signing`] is set, the app bundle is signed before it is archived,
+/// see [payload signature](self#payload-signature). It fails with [`Error::UnsupportedPayloadSigning`]
+/// for the other formats, whose bundle is the payload itself and is covered by the signature of the bundle.
+pub fn build_update_bundle(
+    config: &Config,
+    package: &PackageOutput,
+    bundle_config: &UpdateBundleConfig,
+) -> crate::Result<PathBuf> {
+    let format = package.format;
+    let extension = match format {
+        crate::PackageFormat::Nsis => "exe",
+        crate::PackageFormat::Wix => "msi",
+        crate::PackageFormat::AppImage => "AppImage",
+        crate::PackageFormat::App => "app.tar.gz",
+        _ => return Err(Error::UnsupportedUpdateFormat(format.short_name())),
+    };
+    let src = package
+        .paths
+        .first()
+        .ok_or(Error::UnsupportedUpdateFormat(format.short_name()))?;
+    let target = updater_platform(config)
+        .ok_or_else(|| Error::UnexpectedTargetTriple(config.target_triple()))?;
+
+    let name = render_update_bundle_name(
+        bundle_config
+            .name_template
+            .as_deref()
+            .unwrap_or(DEFAULT_UPDATE_BUNDLE_NAME),
+        &UpdateBundleNameValues {
+            name: &config.product_name,
+            slug: &config.slug()?,
+            version: &config.version,
+            target: &target,
+            format: format.short_name(),
+        },
+    )?;
+    let bundle_path = config.out_dir().join(format!("{name}.{extension}"));
+
+    let payload_signature = match &bundle_config.payload_signing {
+        Some(_) if format != crate::PackageFormat::App => {
+            return Err(Error::UnsupportedPayloadSigning(format.short_name()))
+        }
+        Some(signing_config) => {
+            tracing::debug!("Signing the payload of the update bundle");
+            Some(sign::sign_directory_manifest(signing_config, src)?)
+        }
+        None => None,
+    };
+
+    tracing::debug!("Building update bundle {}", bundle_path.display());
+    if format == crate::PackageFormat::App {
+        let manifest_signature_name = format!("{}.sig", sign::DIRECTORY_MANIFEST_FILE_NAME);
+        let extra_files = payload_signature
+            .as_ref()
+            .map(|(manifest, signature)| {
+                vec![
+                    (sign::DIRECTORY_MANIFEST_FILE_NAME, manifest.as_bytes()),
+                    (manifest_signature_name.as_str(), signature.as_bytes()),
+                ]
+            })
+            .unwrap_or_default();
+        create_deterministic_tar_gz(src, &bundle_path, config.preserve_xattrs, &extra_files)
+            .map_err(|e| {
+                util::write_error(&bundle_path, "failed to create the update bundle", e)
+            })?;
+    } else if src != &bundle_path {
+        fs::copy(src, &bundle_path)
+            .map_err(|e| Error::CopyFile(src.clone(), bundle_path.clone(), e))
+            .map_err(|e| util::write_error(&bundle_path, "failed to copy the update bundle", e))?;
+    }
+
+    Ok(bundle_path)
+}
+
+/// Checks that `app` is a macOS app bundle that the updater can extract in place of the running app,
+/// a `<name>.app` directory with a `Contents/Info.plist` whose `CFBundleExecutable` is in `Contents/MacOS`.
+pub fn validate_app_bundle(app: &Path) -> crate::Result<()> {
+    let invalid = |reason: String| Err(Error::InvalidAppBundle(app.to_path_buf(), reason));
+
+    if !app.is_dir() {
+        return invalid("it is not a directory".into());
+    }
+    if app.extension().is_none_or(|e| e != "app") {
+        // archiving the parent directory of the bundle nests it one level too deep
+        let nested = fs::read_dir(app)
+            .map_err(|e| Error::IoWithPath(app.to_path_buf(), e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|path| path.is_dir() && path.extension().is_some_and(|e| e == "app"));
+        return invalid(match nested {
+            Some(nested) => format!(
+                "it is not an app bundle, use the {} app bundle inside it instead",
+                util::display_path(nested)
+            ),
+            None => "its name doesn't end with `.app`".into(),
+        });
+    }
+
+    let info_plist = app.join("Contents/Info.plist");
+    if !info_plist.is_file() {
+        return invalid("`Contents/Info.plist` is missing".into());
+    }
+    let info = match plist::Value::from_file(&info_plist) {
+        Ok(info) => info,
+        Err(e) => return invalid(format!("failed to read `Contents/Info.plist`: {e}")),
+    };
+    let Some(executable) = info
+        .as_dictionary()
+        .and_then(|d| d.get("CFBundleExecutable"))
+        .and_then(|e| e.as_string())
+    else {
+        return invalid("`Contents/Info.plist` doesn't have a `CFBundleExecutable`".into());
+    };
+    if !app.join("Contents/MacOS").join(executable).is_file() {
+        return invalid(format!(
+            "the `Contents/MacOS/{executable}` executable is missing"
+        ));
+    }
+    Ok(())
+}
+
+/// Builds the `.app.tar.gz` update archive of the macOS app bundle at `app` into `out_dir` and signs it,
+/// for app bundles that weren't packaged by [`crate::package`], like the ones built or modified by another tool.
+///
+/// The bundle is checked with [`validate_app_bundle`] then archived like [`build_update_bundle`] does,
+/// with the `<name>.app` directory at the root of the archive as the updater expects,
+/// sorted entries and fixed metadata, keeping the extended attributes of the files when `xattrs` is true.
+///
+/// Returns a tuple of the archive path and its signature,
+/// the signature is also written next to the archive with a `.sig` extension.
+pub fn build_app_update_archive(
+    app: &Path,
+    out_dir: &Path,
+    xattrs: bool,
+    signing_config: &SigningConfig,
+) -> crate::Result<(PathBuf, String)> {
+    validate_app_bundle(app)?;
+
+    let file_name = app
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(app.to_path_buf()))?;
+    let archive_path = out_dir.join(format!("{}.tar.gz", file_name.to_string_lossy()));
+    tracing::debug!("Building update archive {}", archive_path.display());
+    create_deterministic_tar_gz(app, &archive_path, xattrs, &[])
+        .map_err(|e| util::write_error(&archive_path, "failed to create the update archive", e))?;
+
+    let (_, signature) = sign::sign_file(signing_config, &archive_path)?;
+    Ok((archive_path, signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackageFormat;
+
+    fn package(format: PackageFormat, platform: &str, signature: Option<String>) -> PackageOutput {
+        let mut package = PackageOutput::new(format, Vec::new());
+        package.summary = Some(PackageOutputSummary {
+            url: format!("https://example.com/{platform}").parse().unwrap(),
+            signature,
+            format,
+            platform: platform.into(),
+            notes: None,
+            blake3: None,
+            deltas: Vec::new(),
+        });
+        package
+    }
+
+    #[test]
+    fn it_builds_update_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let keypair = sign::generate_key(Some(String::new())).unwrap();
+        let signing_config = SigningConfig::new()
+            .private_key(keypair.sk)
+            .password("")
+            .trusted_comment("timestamp:1700000000\tfile:{file}");
+        let file = dir.path().join("app.exe");
+        std::fs::write(&file, "app").unwrap();
+        let (_, signature) = sign::sign_file(&signing_config, &file).unwrap();
+
+        let notes_path = dir.path().join("notes.md");
+        std::fs::write(&notes_path, "Linux notes").unwrap();
+
+        let mut config = Config::default();
+        config.version = "1.0.0".into();
+        let packages = vec![
+            package(
+                PackageFormat::Nsis,
+                "windows-x86_64",
+                Some(signature.clone()),
+            ),
+            package(
+                PackageFormat::AppImage,
+                "linux-x86_64",
+                Some(signature.clone()),
+            ),
+        ];
+
+        let manifest_config = UpdateManifestConfig::new()
+            .notes(ReleaseNotes::Text("Bug fixes".into()))
+            .platform_notes("linux-x86_64", ReleaseNotes::File(notes_path));
+        let manifest = build_update_manifest(&config, &packages, &manifest_config).unwrap();
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap(),
+            serde_json::json!({
+                "version": "1.0.0",
+                "notes": "Bug fixes",
+                "pub_date": "2023-11-14T22:13:20Z",
+                "platforms": {
+                    "linux-x86_64": {
+                        "url": "https://example.com/linux-x86_64",
+                        "signature": signature,
+                        "format": "appimage",
+                        "notes": "Linux notes",
+                    },
+                    "windows-x86_64": {
+                        "url": "https://example.com/windows-x86_64",
+                        "signature": signature,
+                        "format": "nsis",
+                    },
+                },
+            })
+        );
+
+        let manifest_config = manifest_config.pub_date("2024-01-02T03:04:05+01:00");
+        let manifest = build_update_manifest(&config, &packages, &manifest_config).unwrap();
+        assert_eq!(manifest.pub_date, "2024-01-02T03:04:05+01:00");
+
+        let manifest_config = UpdateManifestConfig::new().pub_date("2024-01-02");
+        assert!(matches!(
+            build_update_manifest(&config, &packages, &manifest_config),
+            Err(Error::InvalidPubDate(_))
+        ));
+    }
+
+    #[test]
+    fn it_emits_the_public_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let keypair = sign::generate_key(Some("password".into())).unwrap();
+        let signing_config = SigningConfig::new()
+            .private_key(keypair.sk)
+            .password("password");
+        let file = dir.path().join("app.AppImage");
+        std::fs::write(&file, "app").unwrap();
+        let (_, signature) = sign::sign_file(&signing_config, &file).unwrap();
+
+        let (path, public_key) = write_public_key(&signing_config, dir.path()).unwrap();
+        assert_eq!(path, dir.path().join("pubkey.pub"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), public_key);
+        assert_eq!(public_key, keypair.pk);
+        sign::verify_file_with_signature(&file, &signature, &public_key).unwrap();
+
+        let mut config = Config::default();
+        config.version = "1.0.0".into();
+        let packages = [package(
+            PackageFormat::AppImage,
+            "linux-x86_64",
+            Some(signature),
+        )];
+        let manifest = build_update_manifest(
+            &config,
+            &packages,
+            &UpdateManifestConfig::new().public_key(&public_key),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap()["public_key"],
+            public_key.as_str()
+        );
+    }
+
+    #[test]
+    fn it_signs_the_update_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let keypair = sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+
+        let mut config = Config::default();
+        config.version = "1.0.0".into();
+        let packages = [package(PackageFormat::Nsis, "windows-x86_64", None)];
+        let manifest =
+            build_update_manifest(&config, &packages, &UpdateManifestConfig::new()).unwrap();
+        let manifest_path = write_update_manifest(&manifest, dir.path()).unwrap();
+
+        let (signature_path, _) = sign_update_manifest(&signing_config, &manifest_path).unwrap();
+        assert_eq!(signature_path, dir.path().join("latest.json.sig"));
+        sign::verify_file(&manifest_path, &keypair.pk).unwrap();
+
+        std::fs::write(&manifest_path, r#"{"version":"9.9.9"}"#).unwrap();
+        assert!(sign::verify_file(&manifest_path, &keypair.pk).is_err());
+    }
+
+    #[test]
+    fn it_rejects_expired_update_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let keypair = sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+
+        let mut config = Config::default();
+        config.version = "1.0.0".into();
+        let packages = [package(PackageFormat::Nsis, "windows-x86_64", None)];
+        let signed_manifest = |manifest_config: &UpdateManifestConfig| {
+            let manifest = build_update_manifest(&config, &packages, manifest_config).unwrap();
+            let manifest_path = write_update_manifest(&manifest, dir.path()).unwrap();
+            sign_update_manifest(&signing_config, &manifest_path).unwrap();
+            manifest_path
+        };
+
+        // no expiry by default
+        let manifest_path = signed_manifest(&UpdateManifestConfig::new());
+        let manifest = verify_update_manifest(&manifest_path, &keypair.pk).unwrap();
+        assert!(manifest.get("expires_at").is_none());
+
+        let manifest_path =
+            signed_manifest(&UpdateManifestConfig::new().expires_at("2999-01-01T00:00:00Z"));
+        let manifest = verify_update_manifest(&manifest_path, &keypair.pk).unwrap();
+        assert_eq!(manifest["expires_at"], "2999-01-01T00:00:00Z");
+
+        let manifest_path =
+            signed_manifest(&UpdateManifestConfig::new().expires_at("2020-01-01T00:00:00Z"));
+        assert!(matches!(
+            verify_update_manifest(&manifest_path, &keypair.pk),
+            Err(Error::ManifestExpired(e)) if e == "2020-01-01T00:00:00Z"
+        ));
+
+        // the expiry is covered by the signature
+        let tampered = std::fs::read_to_string(&manifest_path)
+            .unwrap()
+            .replace("2020-01-01", "2999-01-01");
+        std::fs::write(&manifest_path, tampered).unwrap();
+        assert!(verify_update_manifest(&manifest_path, &keypair.pk).is_err());
+
+        assert!(matches!(
+            build_update_manifest(
+                &config,
+                &packages,
+                &UpdateManifestConfig::new().expires_at("tomorrow")
+            ),
+            Err(Error::InvalidManifestExpiry(_))
+        ));
+    }
+
+    #[test]
+    fn it_names_update_bundles() {
+        let values = UpdateBundleNameValues {
+            name: "MyApp",
+            slug: "myapp",
+            version: "1.2.3",
+            target: "windows-x86_64",
+            format: "nsis",
+        };
+        assert_eq!(
+            render_update_bundle_name(DEFAULT_UPDATE_BUNDLE_NAME, &values).unwrap(),
+            "MyApp_1.2.3_windows-x86_64"
+        );
+        assert_eq!(
+            render_update_bundle_name("{name}_{version}_{arch}-setup.{format}", &values).unwrap(),
+            "MyApp_1.2.3_x86_64-setup.nsis"
+        );
+        assert_eq!(
+            render_update_bundle_name("{slug}_{version}_{target}", &values).unwrap(),
+            "myapp_1.2.3_windows-x86_64"
+        );
+
+        assert!(matches!(
+            render_update_bundle_name("{name}_{os}", &values),
+            Err(Error::UnknownUpdateBundleNamePlaceholder(name)) if name == "os"
+        ));
+        for template in ["", "{name", "releases/{name}", "{name}}"] {
+            assert!(
+                matches!(
+                    render_update_bundle_name(template, &values),
+                    Err(Error::InvalidUpdateBundleName(_))
+                ),
+                "{template}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_builds_deterministic_update_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.product_name = "MyApp".into();
+        config.version = "1.2.3".into();
+        config.out_dir = dir.path().join("out");
+        config.target_triple = Some("aarch64-apple-darwin".into());
+        fs::create_dir_all(&config.out_dir).unwrap();
+
+        let bundle_config = UpdateBundleConfig::new().name_template("{name}_{version}_{arch}");
+        let mut hashes = Vec::new();
+        for name in ["a", "b"] {
+            let app = dir.path().join(name).join("MyApp.app");
+            fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+            fs::write(app.join("Contents/MacOS/MyApp"), "app").unwrap();
+            fs::write(app.join("Contents/Info.plist"), "plist").unwrap();
+
+            let package = PackageOutput::new(PackageFormat::App, vec![app]);
+            let bundle = build_update_bundle(&config, &package, &bundle_config).unwrap();
+            assert_eq!(
+                bundle,
+                config.out_dir.join("MyApp_1.2.3_aarch64.app.tar.gz")
+            );
+            hashes.push(crate::checksum::sha256_file(&bundle).unwrap());
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+        }
+        assert_eq!(hashes[0], hashes[1]);
+
+        let package = PackageOutput::new(PackageFormat::Dmg, vec![dir.path().join("a.dmg")]);
+        assert!(matches!(
+            build_update_bundle(&config, &package, &bundle_config),
+            Err(Error::UnsupportedUpdateFormat("dmg"))
+        ));
+    }
+
+    #[test]
+    fn it_signs_the_payload_of_update_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.product_name = "MyApp".into();
+        config.version = "1.2.3".into();
+        config.out_dir = dir.path().join("out");
+        config.target_triple = Some("aarch64-apple-darwin".into());
+        fs::create_dir_all(&config.out_dir).unwrap();
+
+        let app = dir.path().join("MyApp.app");
+        fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        fs::write(app.join("Contents/MacOS/MyApp"), "app").unwrap();
+        let payload_keypair = sign::generate_key_unencrypted().unwrap();
+        let update_keypair = sign::generate_key_unencrypted().unwrap();
+        let bundle_config = UpdateBundleConfig::new()
+            .payload_signing(SigningConfig::new().private_key(payload_keypair.sk));
+
+        let package = PackageOutput::new(PackageFormat::App, vec![app.clone()]);
+        let bundle = build_update_bundle(&config, &package, &bundle_config).unwrap();
+        assert!(!app.join(sign::DIRECTORY_MANIFEST_FILE_NAME).exists());
+        let update_signing_config = SigningConfig::new().private_key(update_keypair.sk);
+        sign::sign_file(&update_signing_config, &bundle).unwrap();
+
+        // the outer signature of the archive
+        sign::verify_file(&bundle, &update_keypair.pk).unwrap();
+        // the inner signature of the extracted app bundle
+        let extract_dir = dir.path().join("extracted");
+        tar::Archive::new(flate2::read::GzDecoder::new(File::open(&bundle).unwrap()))
+            .unpack(&extract_dir)
+            .unwrap();
+        let extracted_app = extract_dir.join("MyApp.app");
+        sign::verify_directory(&extracted_app, &payload_keypair.pk).unwrap();
+        assert!(sign::verify_directory(&extracted_app, &update_keypair.pk).is_err());
+        fs::write(extracted_app.join("Contents/MacOS/MyApp"), "tampered").unwrap();
+        assert!(sign::verify_directory(&extracted_app, &payload_keypair.pk).is_err());
+
+        let package = PackageOutput::new(PackageFormat::Nsis, vec![dir.path().join("a.exe")]);
+        assert!(matches!(
+            build_update_bundle(&config, &package, &bundle_config),
+            Err(Error::UnsupportedPayloadSigning("nsis"))
+        ));
+    }
+
+    /// Creates a `MyApp.app` bundle in `dir`.
+    fn app_bundle(dir: &Path) -> PathBuf {
+        let app = dir.join("MyApp.app");
+        fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        fs::write(app.join("Contents/MacOS/MyApp"), "app").unwrap();
+        let mut info = plist::Dictionary::new();
+        info.insert("CFBundleExecutable".into(), "MyApp".into());
+        plist::Value::Dictionary(info)
+            .to_file_xml(app.join("Contents/Info.plist"))
+            .unwrap();
+        app
+    }
+
+    #[test]
+    fn it_builds_app_update_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let build_dir = dir.path().join("build/macos");
+        let app = app_bundle(&build_dir);
+        let keypair = sign::generate_key_unencrypted().unwrap();
+        let signing_config = SigningConfig::new().private_key(keypair.sk);
+
+        let (archive, signature) =
+            build_app_update_archive(&app, dir.path(), false, &signing_config).unwrap();
+        assert_eq!(archive, dir.path().join("MyApp.app.tar.gz"));
+        sign::verify_file_with_signature(&archive, &signature, &keypair.pk).unwrap();
+        sign::verify_file(&archive, &keypair.pk).unwrap();
+
+        let mut tar =
+            tar::Archive::new(flate2::read::GzDecoder::new(File::open(&archive).unwrap()));
+        let paths = tar
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "MyApp.app/",
+                "MyApp.app/Contents",
+                "MyApp.app/Contents/Info.plist",
+                "MyApp.app/Contents/MacOS",
+                "MyApp.app/Contents/MacOS/MyApp",
+            ]
+        );
+
+        // the parent directory of the bundle
+        assert!(matches!(
+            build_app_update_archive(&build_dir, dir.path(), false, &signing_config),
+            Err(Error::InvalidAppBundle(_, reason)) if reason.contains("MyApp.app")
+        ));
+        fs::remove_file(app.join("Contents/MacOS/MyApp")).unwrap();
+        assert!(matches!(
+            validate_app_bundle(&app),
+            Err(Error::InvalidAppBundle(_, reason)) if reason.contains("Contents/MacOS/MyApp")
+        ));
+        fs::remove_file(app.join("Contents/Info.plist")).unwrap();
+        assert!(validate_app_bundle(&app).is_err());
+    }
+}
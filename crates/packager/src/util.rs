@@ -8,7 +8,7 @@ use sha2::Digest;
 use std::{
     ffi::OsStr,
     fs::{self, File},
-    io::{Cursor, Read, Write},
+    io::{self, Cursor, Read, Write},
     path::{Path, PathBuf},
     process::Command,
 };
@@ -59,6 +59,30 @@ pub(crate) fn create_file(path: &Path) -> crate::Result<std::io::BufWriter<File>
     Ok(std::io::BufWriter::new(file))
 }
 
+/// Whether an I/O error was caused by the device running out of space.
+pub(crate) fn is_out_of_space(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::StorageFull
+}
+
+/// Adds the destination `path` and the failed `operation` to the I/O errors of a large write,
+/// returning [`Error::OutOfSpace`] when the device ran out of space.
+pub(crate) fn write_error(path: &Path, operation: &str, error: Error) -> Error {
+    match error {
+        Error::Io(e) | Error::IoWithPath(_, e) | Error::CopyFile(_, _, e)
+            if is_out_of_space(&e) =>
+        {
+            Error::OutOfSpace {
+                path: path.to_path_buf(),
+            }
+        }
+        Error::Io(e) => Error::IoWithPath(
+            path.to_path_buf(),
+            io::Error::new(e.kind(), format!("{operation}: {e}")),
+        ),
+        e => e,
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct RustCfg {
     target_arch: Option<String>,
@@ -139,27 +163,67 @@ pub fn target_triple() -> crate::Result<String> {
     Ok(format!("{arch}-{os}"))
 }
 
-pub(crate) fn download(url: &str) -> crate::Result<Vec<u8>> {
-    tracing::debug!("Downloading {}", url);
-
+/// Returns an HTTP agent using the configured TLS implementation and the proxy of the environment.
+pub(crate) fn http_agent() -> ureq::Agent {
     // This is required because ureq does not bind native-tls as the default TLS implementation when rustls is not available.
     // See <https://github.com/crabnebula-dev/cargo-packager/issues/127>
     #[cfg(feature = "native-tls")]
-    let agent = ureq::AgentBuilder::new()
-        .tls_connector(std::sync::Arc::new(
-            native_tls::TlsConnector::new().unwrap(),
-        ))
-        .try_proxy_from_env(true)
-        .build();
+    let builder = ureq::AgentBuilder::new().tls_connector(std::sync::Arc::new(
+        native_tls::TlsConnector::new().unwrap(),
+    ));
     #[cfg(not(feature = "native-tls"))]
-    let agent = ureq::AgentBuilder::new().try_proxy_from_env(true).build();
+    let builder = ureq::AgentBuilder::new();
+    builder.try_proxy_from_env(true).build()
+}
 
-    let response = agent.get(url).call().map_err(Box::new)?;
+pub(crate) fn download(url: &str) -> crate::Result<Vec<u8>> {
+    tracing::debug!("Downloading {}", url);
+
+    let response = http_agent().get(url).call().map_err(Box::new)?;
     let mut bytes = Vec::new();
     response.into_reader().read_to_end(&mut bytes)?;
     Ok(bytes)
 }
 
+/// Returns the `SOURCE_DATE_EPOCH` environment variable, the timestamp used for reproducible builds.
+///
+/// See <https://reproducible-builds.org/specs/source-date-epoch/>
+pub(crate) fn source_date_epoch() -> Option<u64> {
+    let value = std::env::var("SOURCE_DATE_EPOCH").ok()?;
+    match value.trim().parse() {
+        Ok(epoch) => Some(epoch),
+        Err(_) => {
+            tracing::warn!("Ignoring invalid SOURCE_DATE_EPOCH value `{value}`");
+            None
+        }
+    }
+}
+
+/// Returns `name` in lowercase with the characters other than ASCII letters and digits replaced with `-`,
+/// for example `my-app` for `My App`.
+pub(crate) fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Returns the size of a file, or the size of all files in a directory.
+pub(crate) fn disk_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
 #[derive(Clone, Copy)]
 pub(crate) enum HashAlgorithm {
     #[cfg(target_os = "windows")]
@@ -433,6 +497,38 @@ pub fn create_tar_from_dir<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -
     builder.into_inner().map_err(Into::into)
 }
 
+/// Appends the extended attributes of `path` to `builder` as the `SCHILY.xattr.<name>`
+/// PAX headers of the next entry, as written by GNU tar and bsdtar with `--xattrs`.
+///
+/// Extended attributes are only read on Unix hosts, this does nothing on other hosts.
+pub(crate) fn append_tar_xattrs<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &Path,
+) -> crate::Result<()> {
+    #[cfg(unix)]
+    {
+        let names = match xattr::list(path) {
+            Ok(names) => names,
+            // the file system has no extended attributes
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return Ok(()),
+            Err(e) => return Err(Error::IoWithPath(path.to_path_buf(), e)),
+        };
+        let mut headers = Vec::new();
+        for name in names {
+            let value =
+                xattr::get(path, &name).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+            if let Some(value) = value {
+                headers.push((format!("SCHILY.xattr.{}", name.to_string_lossy()), value));
+            }
+        }
+        headers.sort();
+        builder.append_pax_extensions(headers.iter().map(|(k, v)| (k.as_str(), v.as_slice())))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (builder, path);
+    Ok(())
+}
+
 pub trait PathExt {
     fn with_additional_extension(&self, extension: impl AsRef<OsStr>) -> PathBuf;
 }
@@ -469,4 +565,57 @@ mod tests {
             PathBuf::from("./executable.sig")
         )
     }
+
+    /// A writer failing like a full disk after accepting `capacity` bytes.
+    #[derive(Debug)]
+    struct FullDisk {
+        capacity: usize,
+    }
+
+    impl Write for FullDisk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.capacity == 0 {
+                return Err(io::Error::from(io::ErrorKind::StorageFull));
+            }
+            let len = buf.len().min(self.capacity);
+            self.capacity -= len;
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_reports_out_of_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let src_dir = dir.path().join("app");
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::write(src_dir.join("data"), vec![0; 64 * 1024]).unwrap();
+        let dest = dir.path().join("app.tar");
+
+        let err = create_tar_from_dir(&src_dir, FullDisk { capacity: 4096 })
+            .map_err(|e| write_error(&dest, "failed to create the archive", e))
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::OutOfSpace { path } if path == &dest),
+            "{err}"
+        );
+
+        // other write errors keep their kind and get the path and operation
+        let err = write_error(
+            &dest,
+            "failed to create the archive",
+            Error::Io(io::Error::from(io::ErrorKind::PermissionDenied)),
+        );
+        match err {
+            Error::IoWithPath(path, e) => {
+                assert_eq!(path, dest);
+                assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+                assert!(e.to_string().starts_with("failed to create the archive: "));
+            }
+            e => panic!("unexpected error {e}"),
+        }
+    }
 }
@@ -0,0 +1,458 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of winget manifests for the Windows installers.
+//!
+//! The generated multi-file manifest, made of the version, installer and default locale manifests,
+//! is ready to be submitted to <https://github.com/microsoft/winget-pkgs>,
+//! publishing the installers it references is left to the caller.
+//!
+//! See <https://learn.microsoft.com/en-us/windows/package-manager/package/manifest>
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    checksum,
+    config::{NSISInstallerMode, PackageFormat},
+    util, Config, Error, PackageOutput,
+};
+
+/// The version of the winget manifest schema of the generated manifests.
+pub const MANIFEST_VERSION: &str = "1.6.0";
+
+/// Options of the winget manifests generated by [`generate_manifests`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WingetManifestConfig {
+    /// A template for the download URL of the installers,
+    /// where `{{version}}` is replaced with [`Config::version`]
+    /// and `{{artefact}}` with the file name of the installer.
+    pub url_template: String,
+    /// The license of the app, like its SPDX identifier, which winget requires.
+    pub license: String,
+    /// The identifier of the package, used with `winget install --id <identifier>`,
+    /// made of 2 to 8 segments separated by `.`, like `Publisher.App`.
+    ///
+    /// Defaults to the [`Config::publisher`] and [`Config::product_name`]
+    /// without whitespaces, separated by `.`.
+    pub identifier: Option<String>,
+    /// The locale of the default locale manifest, defaults to `en-US`.
+    pub locale: Option<String>,
+}
+
+impl WingetManifestConfig {
+    /// Creates a new [`WingetManifestConfig`] with the download URL template of the installers
+    /// and the license of the app.
+    pub fn new<S: Into<String>, L: Into<String>>(url_template: S, license: L) -> Self {
+        Self {
+            url_template: url_template.into(),
+            license: license.into(),
+            identifier: None,
+            locale: None,
+        }
+    }
+
+    /// Set the identifier of the package.
+    pub fn identifier<S: Into<String>>(mut self, identifier: S) -> Self {
+        self.identifier.replace(identifier.into());
+        self
+    }
+
+    /// Set the locale of the default locale manifest.
+    pub fn locale<S: Into<String>>(mut self, locale: S) -> Self {
+        self.locale.replace(locale.into());
+        self
+    }
+}
+
+/// The manifests generated by [`generate_manifests`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WingetManifests {
+    /// The identifier of the package, which is also the name of the manifest files.
+    pub identifier: String,
+    /// The `<identifier>.yaml` version manifest.
+    pub version: String,
+    /// The `<identifier>.installer.yaml` installer manifest.
+    pub installer: String,
+    /// The `<identifier>.locale.<locale>.yaml` default locale manifest.
+    pub locale: String,
+    /// The locale of the default locale manifest.
+    pub locale_name: String,
+}
+
+/// Quotes a string as a YAML double-quoted scalar.
+fn yaml_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Returns the winget architecture of the target.
+fn winget_arch(config: &Config) -> crate::Result<&'static str> {
+    match config.target_arch()? {
+        "x86_64" => Ok("x64"),
+        "x86" => Ok("x86"),
+        "aarch64" => Ok("arm64"),
+        target => Err(Error::UnsupportedArch("winget".into(), target.into())),
+    }
+}
+
+/// Returns the default package identifier, `<publisher>.<product name>` without whitespaces.
+fn default_identifier(config: &Config) -> String {
+    let segment = |s: &str| s.split_whitespace().collect::<String>().replace('.', "");
+    format!(
+        "{}.{}",
+        segment(&config.publisher()),
+        segment(&config.product_name)
+    )
+}
+
+/// Checks that the package identifier has 2 to 8 segments of at most 32 characters,
+/// without whitespaces or the characters forbidden in file names.
+fn validate_identifier(identifier: &str) -> crate::Result<()> {
+    let segments = identifier.split('.').collect::<Vec<_>>();
+    let valid_segment = |s: &&str| {
+        !s.is_empty()
+            && s.chars().count() <= 32
+            && !s
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control() || "\\/:*?\"<>|".contains(c))
+    };
+    if !(2..=8).contains(&segments.len()) || !segments.iter().all(valid_segment) {
+        return Err(Error::InvalidWingetManifest(format!(
+            "invalid `PackageIdentifier` `{identifier}`, it must be made of 2 to 8 segments separated by `.` of at most 32 characters, without whitespaces or `\\/:*?\"<>|`"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the length of a required field of the manifests.
+fn validate_field(name: &str, value: &str, min: usize, max: usize) -> crate::Result<()> {
+    let len = value.trim().chars().count();
+    if len < min || len > max {
+        return Err(Error::InvalidWingetManifest(format!(
+            "`{name}` must have between {min} and {max} characters, found `{value}`"
+        )));
+    }
+    Ok(())
+}
+
+/// Generates the winget manifests of the NSIS and MSI installers in `packages`.
+///
+/// Each installer is listed with its download URL built from [`WingetManifestConfig::url_template`],
+/// its SHA-256 digest and its silent install switches.
+/// The product code of the NSIS installer is its uninstall registry key, the [`Config::product_name`],
+/// while the product code of the MSI installer, generated for each build, is read by winget from the installer.
+///
+/// Fails with [`Error::InvalidWingetManifest`] when a required field is invalid,
+/// like a missing [`Config::description`] which is the required `ShortDescription`.
+pub fn generate_manifests(
+    config: &Config,
+    packages: &[PackageOutput],
+    manifest_config: &WingetManifestConfig,
+) -> crate::Result<WingetManifests> {
+    let installers = packages
+        .iter()
+        .filter(|p| matches!(p.format, PackageFormat::Nsis | PackageFormat::Wix))
+        .filter_map(|p| Some((p.format, p.paths.first()?)))
+        .collect::<Vec<_>>();
+    if installers.is_empty() {
+        return Err(Error::WingetArtifactNotFound);
+    }
+
+    let identifier = manifest_config
+        .identifier
+        .clone()
+        .unwrap_or_else(|| default_identifier(config));
+    validate_identifier(&identifier)?;
+    validate_field("PackageVersion", &config.version, 1, 128)?;
+    let publisher = config.publisher();
+    validate_field("Publisher", &publisher, 2, 256)?;
+    validate_field("PackageName", &config.product_name, 2, 256)?;
+    validate_field("License", &manifest_config.license, 3, 512)?;
+    let description = config.description.as_deref().unwrap_or_default();
+    validate_field("ShortDescription", description, 3, 256)?;
+    let locale = manifest_config.locale.as_deref().unwrap_or("en-US");
+    let arch = winget_arch(config)?;
+
+    let header = |manifest_type: &str| {
+        format!(
+            "# yaml-language-server: $schema=https://aka.ms/winget-manifest.{manifest_type}.{MANIFEST_VERSION}.schema.json\n\nPackageIdentifier: {}\nPackageVersion: {}\n",
+            yaml_string(&identifier),
+            yaml_string(&config.version)
+        )
+    };
+    let footer = |manifest_type: &str| {
+        format!("ManifestType: {manifest_type}\nManifestVersion: {MANIFEST_VERSION}\n")
+    };
+
+    let mut version = header("version");
+    let _ = writeln!(version, "DefaultLocale: {}", yaml_string(locale));
+    version.push_str(&footer("version"));
+
+    let mut installer = header("installer");
+    installer.push_str("Installers:\n");
+    for (format, path) in installers {
+        let name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .ok_or_else(|| Error::FailedToExtractFilename(path.clone()))?;
+        let url = manifest_config
+            .url_template
+            .replace("{{version}}", &config.version)
+            .replace("{{artefact}}", name);
+        let _ = writeln!(installer, "- Architecture: {arch}");
+        if format == PackageFormat::Nsis {
+            installer.push_str("  InstallerType: nullsoft\n");
+            let scope = match config.nsis().map(|n| n.install_mode).unwrap_or_default() {
+                NSISInstallerMode::CurrentUser => Some("user"),
+                NSISInstallerMode::PerMachine => Some("machine"),
+                NSISInstallerMode::Both => None,
+            };
+            if let Some(scope) = scope {
+                let _ = writeln!(installer, "  Scope: {scope}");
+            }
+        } else {
+            installer.push_str("  InstallerType: wix\n  Scope: machine\n");
+        }
+        let _ = writeln!(installer, "  InstallerUrl: {}", yaml_string(&url));
+        let _ = writeln!(
+            installer,
+            "  InstallerSha256: {}",
+            checksum::sha256_file(path)?.to_uppercase()
+        );
+        let (silent, silent_with_progress) = if format == PackageFormat::Nsis {
+            ("/S", "/S")
+        } else {
+            ("/quiet /norestart", "/passive /norestart")
+        };
+        installer.push_str("  InstallerSwitches:\n");
+        let _ = writeln!(installer, "    Silent: {}", yaml_string(silent));
+        let _ = writeln!(
+            installer,
+            "    SilentWithProgress: {}",
+            yaml_string(silent_with_progress)
+        );
+        if format == PackageFormat::Nsis {
+            let _ = writeln!(
+                installer,
+                "  ProductCode: {}",
+                yaml_string(&config.product_name)
+            );
+        }
+    }
+    installer.push_str(&footer("installer"));
+
+    let mut locale_manifest = header("defaultLocale");
+    let _ = writeln!(locale_manifest, "PackageLocale: {}", yaml_string(locale));
+    let _ = writeln!(locale_manifest, "Publisher: {}", yaml_string(&publisher));
+    let _ = writeln!(
+        locale_manifest,
+        "PackageName: {}",
+        yaml_string(&config.product_name)
+    );
+    if let Some(homepage) = &config.homepage {
+        let _ = writeln!(locale_manifest, "PackageUrl: {}", yaml_string(homepage));
+    }
+    let _ = writeln!(
+        locale_manifest,
+        "License: {}",
+        yaml_string(&manifest_config.license)
+    );
+    if let Some(copyright) = &config.copyright {
+        let _ = writeln!(locale_manifest, "Copyright: {}", yaml_string(copyright));
+    }
+    let _ = writeln!(
+        locale_manifest,
+        "ShortDescription: {}",
+        yaml_string(description)
+    );
+    if let Some(long_description) = &config.long_description {
+        let _ = writeln!(
+            locale_manifest,
+            "Description: {}",
+            yaml_string(long_description)
+        );
+    }
+    locale_manifest.push_str(&footer("defaultLocale"));
+
+    Ok(WingetManifests {
+        identifier,
+        version,
+        installer,
+        locale: locale_manifest,
+        locale_name: locale.to_string(),
+    })
+}
+
+/// Writes the winget manifests generated by [`generate_manifests`] into `<out_dir>/winget`
+/// and returns their paths.
+///
+/// The manifests are named after the package identifier,
+/// like the manifests of <https://github.com/microsoft/winget-pkgs>.
+pub fn write_manifests(
+    config: &Config,
+    packages: &[PackageOutput],
+    manifest_config: &WingetManifestConfig,
+    out_dir: &Path,
+) -> crate::Result<Vec<PathBuf>> {
+    let manifests = generate_manifests(config, packages, manifest_config)?;
+    let dir = out_dir.join("winget");
+    fs::create_dir_all(&dir).map_err(|e| Error::IoWithPath(dir.clone(), e))?;
+
+    let identifier = &manifests.identifier;
+    let mut paths = Vec::new();
+    for (name, content) in [
+        (format!("{identifier}.yaml"), &manifests.version),
+        (format!("{identifier}.installer.yaml"), &manifests.installer),
+        (
+            format!("{identifier}.locale.{}.yaml", manifests.locale_name),
+            &manifests.locale,
+        ),
+    ] {
+        let path = dir.join(name);
+        fs::write(&path, content).map_err(|e| Error::IoWithPath(path.clone(), e))?;
+        tracing::info!("Generated winget manifest: {}", util::display_path(&path));
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.product_name = "My App".into();
+        config.version = "1.2.0".into();
+        config.publisher = Some("My Company".into());
+        config.description = Some("An \"example\" app".into());
+        config.homepage = Some("https://example.com".into());
+        config.target_triple = Some("x86_64-pc-windows-msvc".into());
+        config
+    }
+
+    #[test]
+    fn it_generates_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let nsis = dir.path().join("app_1.2.0_x64-setup.exe");
+        let msi = dir.path().join("app_1.2.0_x64_en-US.msi");
+        std::fs::write(&nsis, "").unwrap();
+        std::fs::write(&msi, "msi").unwrap();
+        let packages = vec![
+            PackageOutput::new(PackageFormat::Nsis, vec![nsis]),
+            PackageOutput::new(PackageFormat::Wix, vec![msi.clone()]),
+        ];
+        let manifest_config = WingetManifestConfig::new(
+            "https://example.com/releases/v{{version}}/{{artefact}}",
+            "MIT",
+        );
+
+        let manifests = generate_manifests(&config(), &packages, &manifest_config).unwrap();
+        assert_eq!(manifests.identifier, "MyCompany.MyApp");
+        assert!(manifests
+            .version
+            .contains("DefaultLocale: \"en-US\"\nManifestType: version\n"));
+        let msi_sha = checksum::sha256_file(&msi).unwrap().to_uppercase();
+        assert_eq!(
+            manifests.installer,
+            format!(
+                r#"# yaml-language-server: $schema=https://aka.ms/winget-manifest.installer.1.6.0.schema.json
+
+PackageIdentifier: "MyCompany.MyApp"
+PackageVersion: "1.2.0"
+Installers:
+- Architecture: x64
+  InstallerType: nullsoft
+  Scope: user
+  InstallerUrl: "https://example.com/releases/v1.2.0/app_1.2.0_x64-setup.exe"
+  InstallerSha256: E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855
+  InstallerSwitches:
+    Silent: "/S"
+    SilentWithProgress: "/S"
+  ProductCode: "My App"
+- Architecture: x64
+  InstallerType: wix
+  Scope: machine
+  InstallerUrl: "https://example.com/releases/v1.2.0/app_1.2.0_x64_en-US.msi"
+  InstallerSha256: {msi_sha}
+  InstallerSwitches:
+    Silent: "/quiet /norestart"
+    SilentWithProgress: "/passive /norestart"
+ManifestType: installer
+ManifestVersion: 1.6.0
+"#
+            )
+        );
+        assert!(manifests
+            .locale
+            .contains("Publisher: \"My Company\"\nPackageName: \"My App\"\nPackageUrl: \"https://example.com\"\nLicense: \"MIT\"\nShortDescription: \"An \\\"example\\\" app\"\n"));
+
+        let paths = write_manifests(&config(), &packages, &manifest_config, dir.path()).unwrap();
+        let winget_dir = dir.path().join("winget");
+        assert_eq!(
+            paths,
+            [
+                winget_dir.join("MyCompany.MyApp.yaml"),
+                winget_dir.join("MyCompany.MyApp.installer.yaml"),
+                winget_dir.join("MyCompany.MyApp.locale.en-US.yaml"),
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&paths[1]).unwrap(),
+            manifests.installer
+        );
+
+        assert!(matches!(
+            generate_manifests(&config(), &[], &manifest_config),
+            Err(Error::WingetArtifactNotFound)
+        ));
+    }
+
+    #[test]
+    fn it_validates_required_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let nsis = dir.path().join("app-setup.exe");
+        std::fs::write(&nsis, "").unwrap();
+        let packages = vec![PackageOutput::new(PackageFormat::Nsis, vec![nsis])];
+
+        let mut config = config();
+        config.description = None;
+        let manifest_config = WingetManifestConfig::new("https://example.com/{{artefact}}", "MIT");
+        assert!(matches!(
+            generate_manifests(&config, &packages, &manifest_config),
+            Err(Error::InvalidWingetManifest(reason)) if reason.contains("ShortDescription")
+        ));
+
+        for identifier in ["App", "My Company.App", "A.B.C.D.E.F.G.H.I", "Company..App"] {
+            assert!(
+                matches!(
+                    validate_identifier(identifier),
+                    Err(Error::InvalidWingetManifest(_))
+                ),
+                "{identifier}"
+            );
+        }
+        validate_identifier("MyCompany.MyApp.Beta").unwrap();
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Generation of the `.zsync` files used by AppImageUpdate to download
+//! only the changed parts of an AppImage.
+//!
+//! [`make_zsync`] writes the `<file>.AppImage.zsync` of an already built AppImage,
+//! after validating that it embeds the same update information as the previous release,
+//! so an AppImage can be published as a delta update without repackaging it.
+//!
+//! The files follow the format of `zsyncmake` 0.6.2,
+//! see <http://zsync.moria.org.uk/paper/> for how the clients use them.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use sha1::{Digest, Sha1};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+use crate::{elf::Elf, util::PathExt, Error};
+
+/// The `.zsync` file written by [`make_zsync`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Zsync {
+    /// The path of the `.zsync` file.
+    pub path: PathBuf,
+    /// The update information embedded in both AppImages.
+    pub update_information: String,
+    /// The number of blocks of the new AppImage.
+    pub blocks: u64,
+    /// The number of blocks of the new AppImage also found at a block boundary of the previous one.
+    ///
+    /// This is a lower bound of the blocks the clients can reuse,
+    /// zsync also finds the blocks at other offsets.
+    pub reused_blocks: u64,
+}
+
+/// Reads the update information embedded in the `.upd_info` section of the AppImage at `path`,
+/// `None` when the section is empty.
+pub fn update_information<P: AsRef<Path>>(path: P) -> crate::Result<Option<String>> {
+    let path = path.as_ref();
+    let invalid = |reason: &str| Error::InvalidAppImage(path.to_path_buf(), reason.into());
+
+    let data = fs::read(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let elf = Elf::parse(&data).map_err(invalid)?;
+    let (offset, size) = elf
+        .section(".upd_info")
+        .ok_or_else(|| invalid("it does not have an `.upd_info` section"))?;
+    let section = data
+        .get(offset..offset + size)
+        .ok_or_else(|| invalid("the `.upd_info` section is truncated"))?;
+    let end = section.iter().position(|b| *b == 0).unwrap_or(size);
+    let update_information = std::str::from_utf8(&section[..end])
+        .map_err(|_| invalid("the update information is not valid UTF-8"))?;
+
+    Ok((!update_information.is_empty()).then(|| update_information.to_string()))
+}
+
+/// Writes the `.zsync` file of the AppImage at `appimage` next to it, to update from `previous`.
+///
+/// Both AppImages must embed the same update information, otherwise the clients of `previous`
+/// can't find the new release and [`Error::AppImageUpdateInformationMismatch`] is returned.
+///
+/// `url` is the URL of the AppImage written to the `.zsync` file,
+/// relative to the `.zsync` file or absolute, defaulting to the file name of `appimage`.
+pub fn make_zsync<P: AsRef<Path>, A: AsRef<Path>>(
+    previous: P,
+    appimage: A,
+    url: Option<&str>,
+) -> crate::Result<Zsync> {
+    let previous = previous.as_ref();
+    let appimage = appimage.as_ref();
+
+    let previous_update_information = update_information(previous)?;
+    let new_update_information = update_information(appimage)?;
+    let update_information = match (previous_update_information, new_update_information) {
+        (Some(previous), Some(new)) if previous == new => new,
+        (previous, new) => {
+            return Err(Error::AppImageUpdateInformationMismatch(
+                previous.unwrap_or_else(|| "<none>".into()),
+                new.unwrap_or_else(|| "<none>".into()),
+            ))
+        }
+    };
+
+    let len = fs::metadata(appimage)
+        .map_err(|e| Error::IoWithPath(appimage.to_path_buf(), e))?
+        .len();
+    let header = ZsyncHeader::new(len);
+    let (path, blocks) = write_zsync(appimage, &header, url)?;
+
+    let previous_checksums = read_blocks(previous, &header)?.1;
+    let previous_checksums = previous_checksums
+        .chunks(header.block_len())
+        .collect::<HashSet<_>>();
+    let reused_blocks = blocks
+        .chunks(header.block_len())
+        .filter(|block| previous_checksums.contains(block))
+        .count() as u64;
+
+    Ok(Zsync {
+        path,
+        update_information,
+        blocks: (blocks.len() / header.block_len()) as u64,
+        reused_blocks,
+    })
+}
+
+/// The lengths of the checksums of a `.zsync` file, computed like `zsyncmake`.
+struct ZsyncHeader {
+    block_size: u64,
+    seq_matches: usize,
+    rsum_len: usize,
+    checksum_len: usize,
+}
+
+impl ZsyncHeader {
+    fn new(len: u64) -> Self {
+        let block_size: u64 = if len < 100_000_000 { 2048 } else { 4096 };
+        let seq_matches = if len > block_size { 2 } else { 1 };
+        let (len_f, block_size_f) = (len.max(1) as f64, block_size as f64);
+        let blocks_f = (1 + len / block_size) as f64;
+
+        let rsum_len =
+            (((len_f.ln() + block_size_f.ln()) / 2f64.ln() - 8.6) / seq_matches as f64 / 8.0)
+                .ceil()
+                .clamp(2.0, 4.0) as usize;
+        let checksum_len =
+            ((20.0 + (len_f.ln() + blocks_f.ln()) / 2f64.ln()) / seq_matches as f64 / 8.0).ceil()
+                as usize;
+        let min_checksum_len = ((7.9 + (20.0 + blocks_f.ln() / 2f64.ln())) / 8.0) as usize;
+
+        Self {
+            block_size,
+            seq_matches,
+            rsum_len,
+            checksum_len: checksum_len.max(min_checksum_len).min(16),
+        }
+    }
+
+    /// The length of the checksums of a block.
+    fn block_len(&self) -> usize {
+        self.rsum_len + self.checksum_len
+    }
+}
+
+/// Reads the file at `path` and returns its SHA-1 digest and the rolling and MD4 checksums of its blocks.
+fn read_blocks(path: &Path, header: &ZsyncHeader) -> crate::Result<(String, Vec<u8>)> {
+    let file = File::open(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut sha1 = Sha1::new();
+    let mut blocks = Vec::new();
+    let mut block = vec![0; header.block_size as usize];
+    loop {
+        let mut read = 0;
+        while read < block.len() {
+            match reader.read(&mut block[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::IoWithPath(path.to_path_buf(), e)),
+            }
+        }
+        if read == 0 {
+            break;
+        }
+        sha1.update(&block[..read]);
+        // the last block is padded with zeros
+        block[read..].fill(0);
+
+        blocks.extend_from_slice(&rsum(&block)[4 - header.rsum_len..]);
+        blocks.extend_from_slice(&md4(&block)[..header.checksum_len]);
+
+        if read < block.len() {
+            break;
+        }
+    }
+    Ok((hex::encode(sha1.finalize()), blocks))
+}
+
+/// Writes the `.zsync` file of the file at `path` next to it, returning its path and the block checksums.
+fn write_zsync(
+    path: &Path,
+    header: &ZsyncHeader,
+    url: Option<&str>,
+) -> crate::Result<(PathBuf, Vec<u8>)> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::FailedToExtractFilename(path.to_path_buf()))?
+        .to_string_lossy()
+        .into_owned();
+    let metadata = fs::metadata(path).map_err(|e| Error::IoWithPath(path.to_path_buf(), e))?;
+    let mtime = metadata
+        .modified()
+        .map(OffsetDateTime::from)
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .format(&Rfc2822)
+        .map_err(|e| Error::InvalidAppImage(path.to_path_buf(), e.to_string()))?;
+
+    let (sha1, blocks) = read_blocks(path, header)?;
+
+    let zsync_path = path.with_additional_extension("zsync");
+    let write = || -> crate::Result<()> {
+        let mut file = crate::util::create_file(&zsync_path)?;
+        write!(
+            file,
+            "zsync: 0.6.2\nFilename: {file_name}\nMTime: {mtime}\nBlocksize: {}\nLength: {}\nHash-Lengths: {},{},{}\nURL: {}\nSHA-1: {sha1}\n\n",
+            header.block_size,
+            metadata.len(),
+            header.seq_matches,
+            header.rsum_len,
+            header.checksum_len,
+            url.unwrap_or(&file_name),
+        )?;
+        file.write_all(&blocks)?;
+        file.flush()?;
+        Ok(())
+    };
+    write()
+        .map_err(|e| crate::util::write_error(&zsync_path, "failed to write the zsync file", e))?;
+
+    Ok((zsync_path, blocks))
+}
+
+/// The rolling checksum of a block, as written by `zsyncmake`.
+fn rsum(block: &[u8]) -> [u8; 4] {
+    let (mut a, mut b) = (0u16, 0u16);
+    for (i, byte) in block.iter().enumerate() {
+        let weight = (block.len() - i) as u16;
+        a = a.wrapping_add(*byte as u16);
+        b = b.wrapping_add(weight.wrapping_mul(*byte as u16));
+    }
+    let [a0, a1] = a.to_be_bytes();
+    let [b0, b1] = b.to_be_bytes();
+    [a0, a1, b0, b1]
+}
+
+/// The MD4 digest of `data`, see <https://www.rfc-editor.org/rfc/rfc1320>.
+fn md4(data: &[u8]) -> [u8; 16] {
+    const ROUND_2: [usize; 16] = [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15];
+    const ROUND_3: [usize; 16] = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend(((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let x = chunk
+            .chunks(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect::<Vec<_>>();
+        let [mut a, mut b, mut c, mut d] = state;
+        for round in 0..3 {
+            for i in 0..16 {
+                let (f, k, constant, shifts) = match round {
+                    0 => ((b & c) | (!b & d), i, 0, [3, 7, 11, 19]),
+                    1 => (
+                        (b & c) | (b & d) | (c & d),
+                        ROUND_2[i],
+                        0x5A827999,
+                        [3, 5, 9, 13],
+                    ),
+                    _ => (b ^ c ^ d, ROUND_3[i], 0x6ED9EBA1, [3, 9, 11, 15]),
+                };
+                let t = a
+                    .wrapping_add(f)
+                    .wrapping_add(x[k])
+                    .wrapping_add(constant)
+                    .rotate_left(shifts[i % 4]);
+                (a, b, c, d) = (d, t, b, c);
+            }
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (out, s) in digest.chunks_mut(4).zip(state) {
+        out.copy_from_slice(&s.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_computes_md4() {
+        for (data, digest) in [
+            ("", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+            ("abc", "a448017aaf21d8525fc10ae87aa6729d"),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "e33b4ddc9c38f2199c3e7b164fcc0536",
+            ),
+        ] {
+            assert_eq!(hex::encode(md4(data.as_bytes())), digest);
+        }
+    }
+
+    /// Builds a little-endian ELF64 file with a `.upd_info` section of 64 bytes
+    /// holding `update_information`, followed by `payload`.
+    fn appimage(update_information: &str, payload: &[u8]) -> Vec<u8> {
+        let names = b"\0.shstrtab\0.upd_info\0";
+        let mut data = vec![0; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        let mut section = update_information.as_bytes().to_vec();
+        section.resize(64, 0);
+        data.extend(section);
+        data.extend(names);
+        let shoff = data.len();
+        data[0x28..0x30].copy_from_slice(&(shoff as u64).to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+        data[0x3E..0x40].copy_from_slice(&1u16.to_le_bytes());
+        for (name, offset, size) in [
+            (0u32, 0u64, 0u64),
+            (1, 128, names.len() as u64),
+            (11, 64, 64),
+        ] {
+            let mut header = vec![0; 64];
+            header[..4].copy_from_slice(&name.to_le_bytes());
+            header[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+            header[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+            data.extend(header);
+        }
+        data.extend(payload);
+        data
+    }
+
+    #[test]
+    fn it_makes_zsync_referencing_the_new_appimage() {
+        let dir = tempfile::tempdir().unwrap();
+        let update_information = "zsync|https://example.com/app_x86_64.AppImage.zsync";
+        let payload = (0..20_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let previous = dir.path().join("app_1.0.0_x86_64.AppImage");
+        fs::write(&previous, appimage(update_information, &payload)).unwrap();
+        let mut changed = payload.clone();
+        changed[15_000..].fill(7);
+        let new = dir.path().join("app_1.1.0_x86_64.AppImage");
+        let new_data = appimage(update_information, &changed);
+        fs::write(&new, &new_data).unwrap();
+
+        let zsync = make_zsync(&previous, &new, None).unwrap();
+        assert_eq!(
+            zsync.path,
+            dir.path().join("app_1.1.0_x86_64.AppImage.zsync")
+        );
+        assert_eq!(zsync.update_information, update_information);
+        assert_eq!(zsync.blocks, new_data.len().div_ceil(2048) as u64);
+        assert!(zsync.reused_blocks >= 7 && zsync.reused_blocks < zsync.blocks);
+
+        let contents = fs::read(&zsync.path).unwrap();
+        let header_end = contents.windows(2).position(|w| w == b"\n\n").unwrap();
+        let header = std::str::from_utf8(&contents[..header_end]).unwrap();
+        let field = |name: &str| {
+            header
+                .lines()
+                .find_map(|l| l.strip_prefix(&format!("{name}: ")))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(field("zsync"), "0.6.2");
+        assert_eq!(field("Filename"), "app_1.1.0_x86_64.AppImage");
+        assert_eq!(field("URL"), "app_1.1.0_x86_64.AppImage");
+        assert_eq!(field("Length"), new_data.len().to_string());
+        assert_eq!(field("Blocksize"), "2048");
+        assert_eq!(field("SHA-1"), hex::encode(Sha1::digest(&new_data)));
+
+        let lengths = field("Hash-Lengths")
+            .split(',')
+            .map(|l| l.parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lengths[0], 2);
+        let blocks = &contents[header_end + 2..];
+        assert_eq!(
+            blocks.len(),
+            zsync.blocks as usize * (lengths[1] + lengths[2])
+        );
+        let first_block = &new_data[..2048];
+        assert_eq!(&blocks[..lengths[1]], &rsum(first_block)[4 - lengths[1]..]);
+        assert_eq!(
+            &blocks[lengths[1]..lengths[1] + lengths[2]],
+            &md4(first_block)[..lengths[2]]
+        );
+
+        let url = "https://example.com/releases/app_1.1.0_x86_64.AppImage";
+        make_zsync(&previous, &new, Some(url)).unwrap();
+        let contents = String::from_utf8_lossy(&fs::read(&zsync.path).unwrap()).into_owned();
+        assert!(contents.contains(&format!("\nURL: {url}\n")));
+
+        fs::write(
+            &previous,
+            appimage("zsync|https://example.com/other.AppImage.zsync", &payload),
+        )
+        .unwrap();
+        assert!(matches!(
+            make_zsync(&previous, &new, None),
+            Err(Error::AppImageUpdateInformationMismatch(..))
+        ));
+        fs::write(&previous, appimage("", &payload)).unwrap();
+        assert!(matches!(
+            make_zsync(&previous, &new, None),
+            Err(Error::AppImageUpdateInformationMismatch(..))
+        ));
+        fs::write(&previous, &payload).unwrap();
+        assert!(matches!(
+            make_zsync(&previous, &new, None),
+            Err(Error::InvalidAppImage(..))
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+#![cfg(target_os = "linux")]
+
+use std::{
+    fs::{self, File},
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use cargo_packager::{
+    config::{Binary, ConfigBuilder},
+    PackageFormat,
+};
+
+/// Sets the modification time of `path` to `secs` seconds since the Unix epoch.
+fn set_mtime(path: &Path, secs: u64) {
+    File::open(path)
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap();
+}
+
+#[test]
+#[ignore = "downloads linuxdeploy and its AppImage plugin"]
+fn it_builds_reproducible_appimages_with_source_date_epoch() {
+    // the only test of this binary, so the variable isn't seen by the other tests
+    std::env::set_var("SOURCE_DATE_EPOCH", "1700000000");
+    let icon = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/slint/32x32.png");
+
+    let mut digests = Vec::new();
+    // two builds of the same sources made at different times
+    for built_at in [1_000, 2_000] {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("dist")).unwrap();
+        let binary = root.path().join("dist/app");
+        fs::copy("/bin/true", &binary).unwrap();
+        fs::copy(&icon, root.path().join("icon.png")).unwrap();
+        set_mtime(&binary, built_at);
+        set_mtime(&root.path().join("icon.png"), built_at);
+
+        let config = ConfigBuilder::new()
+            .product_name("App")
+            .version("1.0.0")
+            .target_triple("x86_64-unknown-linux-gnu")
+            .project_root(root.path())
+            .out_dir("dist")
+            .binaries([Binary::new("app").main(true)])
+            .icons(["icon.png"])
+            .formats([PackageFormat::AppImage])
+            .config()
+            .clone();
+        let packages = cargo_packager::package(&config).unwrap();
+        digests.push(cargo_packager::checksum::sha256_file(&packages[0].paths[0]).unwrap());
+    }
+
+    assert_eq!(digests[0], digests[1]);
+}
@@ -0,0 +1,91 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+#![cfg(target_os = "linux")]
+
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+use cargo_packager::{
+    config::{Binary, ConfigBuilder, DebianConfig, DebianDataPackage, Resource},
+    PackageFormat,
+};
+
+/// Returns the control file and the paths of the data archive of the `.deb` at `path`.
+fn read_deb(path: &Path) -> (String, Vec<String>) {
+    let mut archive = ar::Archive::new(File::open(path).unwrap());
+    let (mut control, mut data) = (String::new(), Vec::new());
+    while let Some(entry) = archive.next_entry() {
+        let entry = entry.unwrap();
+        let name = String::from_utf8(entry.header().identifier().to_vec()).unwrap();
+        if !name.ends_with(".tar.gz") {
+            continue;
+        }
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(entry));
+        for file in tar.entries().unwrap() {
+            let mut file = file.unwrap();
+            let file_path = file.path().unwrap().to_string_lossy().into_owned();
+            if name == "control.tar.gz" && file_path.ends_with("control") {
+                file.read_to_string(&mut control).unwrap();
+            } else if name == "data.tar.gz" {
+                data.push(file_path);
+            }
+        }
+    }
+    (control, data)
+}
+
+#[test]
+fn it_splits_the_resources_into_a_data_package() {
+    let root = tempfile::tempdir().unwrap();
+    fs::create_dir_all(root.path().join("dist")).unwrap();
+    fs::create_dir_all(root.path().join("assets")).unwrap();
+    fs::write(root.path().join("dist/app"), "app").unwrap();
+    fs::write(root.path().join("assets/data.txt"), "data").unwrap();
+
+    let config = ConfigBuilder::new()
+        .product_name("App")
+        .version("1.1.0")
+        .target_triple("x86_64-unknown-linux-gnu")
+        .project_root(root.path())
+        .out_dir("dist")
+        .binaries([Binary::new("app").main(true)])
+        .resources([Resource::Single("assets".into())])
+        .deb(
+            DebianConfig::new().depends(["libc6"]).data_package(
+                DebianDataPackage::new()
+                    .version("1.0.0")
+                    .arch_independent(true),
+            ),
+        )
+        .formats([PackageFormat::Deb])
+        .config()
+        .clone();
+    let packages = cargo_packager::package(&config).unwrap();
+
+    let out_dir = dunce::canonicalize(root.path()).unwrap().join("dist");
+    assert_eq!(
+        packages[0].paths,
+        [
+            out_dir.join("app_1.1.0_amd64.deb"),
+            out_dir.join("app-data_1.0.0_all.deb")
+        ]
+    );
+
+    let (control, data) = read_deb(&packages[0].paths[0]);
+    assert!(control.contains("Package: app\n"));
+    assert!(control.contains("Depends: libc6, app-data (= 1.0.0)\n"));
+    assert!(data.iter().any(|p| p.ends_with("usr/bin/app")));
+    assert!(!data.iter().any(|p| p.contains("usr/lib/app")));
+
+    let (control, data) = read_deb(&packages[0].paths[1]);
+    assert!(control.contains("Package: app-data\nVersion: 1.0.0\nArchitecture: all\n"));
+    assert!(data
+        .iter()
+        .any(|p| p.ends_with("usr/lib/app/assets/data.txt")));
+    assert!(!data.iter().any(|p| p.ends_with("usr/bin/app")));
+}
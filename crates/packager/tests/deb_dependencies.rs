@@ -0,0 +1,77 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+#![cfg(target_os = "linux")]
+
+use std::{fs, path::Path};
+
+use cargo_packager::{
+    config::{Binary, ConfigBuilder, DebianConfig, DebianDependencyCheck},
+    Config, Error, PackageFormat,
+};
+
+/// Returns the config of a strictly checked package depending on `depends`.
+fn checked_config(root: &Path, depends: &[&str]) -> Config {
+    let package_list = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Packages");
+    ConfigBuilder::new()
+        .product_name("App")
+        .version("1.0.0")
+        .target_triple("x86_64-unknown-linux-gnu")
+        .project_root(root)
+        .out_dir("dist")
+        .binaries([Binary::new("app").main(true)])
+        .deb(
+            DebianConfig::new()
+                .depends(depends.iter().copied())
+                .dependency_check(
+                    DebianDependencyCheck::new()
+                        .package_list(package_list)
+                        .strict(true),
+                ),
+        )
+        .formats([PackageFormat::Deb])
+        .config()
+        .clone()
+}
+
+#[test]
+fn it_checks_the_dependencies_against_a_package_list() {
+    let root = tempfile::tempdir().unwrap();
+    fs::create_dir_all(root.path().join("dist")).unwrap();
+    fs::write(root.path().join("dist/app"), "app").unwrap();
+
+    let config = checked_config(
+        root.path(),
+        &[
+            "libc6 (>= 2.31)",
+            "libgtk-3-0:amd64 (>= 3.24.38-2~)",
+            "libjavascriptcoregtk-4.1-0 (= 2.44.2-1~deb12u1)",
+            "xdg-open | xdg-utils (>> 2.0)",
+        ],
+    );
+    let packages = cargo_packager::package(&config).unwrap();
+    assert!(packages[0].paths[0].ends_with("app_1.0.0_amd64.deb"));
+
+    for (dependency, reason) in [
+        ("libgtk3-0", "no package named or providing `libgtk3-0`"),
+        (
+            "libc6 (>= 2.38)",
+            "the available versions are 2.36-9+deb12u4",
+        ),
+        ("xdg-open (>= 1.0)", "only provided without a version"),
+        (
+            "libfoo | xdg-utils (<< 1.0)",
+            "`libfoo` is available and no version",
+        ),
+    ] {
+        let config = checked_config(root.path(), &["libc6", dependency]);
+        match cargo_packager::package(&config) {
+            Err(Error::UnsatisfiableDependency(relation, message)) => {
+                assert_eq!(relation, dependency);
+                assert!(message.contains(reason), "{message}");
+            }
+            other => panic!("{dependency}: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+#![cfg(target_os = "macos")]
+
+use std::{fs, os::unix::fs::PermissionsExt, process::Command};
+
+use cargo_packager::{
+    config::{Binary, ConfigBuilder, DmgConfig, DmgFilesystem},
+    PackageFormat,
+};
+
+#[test]
+fn it_creates_the_dmg_with_the_requested_filesystem() {
+    for (filesystem, partition) in [
+        (DmgFilesystem::HfsPlus, "Apple_HFS"),
+        (DmgFilesystem::Apfs, "Apple_APFS"),
+    ] {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("dist")).unwrap();
+        let binary = root.path().join("dist/app");
+        fs::write(&binary, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = ConfigBuilder::new()
+            .product_name("App")
+            .version("1.0.0")
+            .identifier("com.example.app")
+            .target_triple("aarch64-apple-darwin")
+            .project_root(root.path())
+            .out_dir("dist")
+            .binaries([Binary::new("app").main(true)])
+            .dmg(DmgConfig::new().filesystem(filesystem))
+            .formats([PackageFormat::App, PackageFormat::Dmg])
+            .config()
+            .clone();
+        let packages = cargo_packager::package(&config).unwrap();
+
+        let dmg = packages
+            .iter()
+            .find(|p| p.format == PackageFormat::Dmg)
+            .unwrap();
+        let output = Command::new("hdiutil")
+            .arg("imageinfo")
+            .arg(&dmg.paths[0])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let info = String::from_utf8_lossy(&output.stdout);
+        assert!(info.contains(partition), "{filesystem:?}: {info}");
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+#![cfg(target_os = "linux")]
+
+use std::{fs, path::PathBuf};
+
+use cargo_packager::{
+    config::{Binary, ConfigBuilder, DebianConfig, DebianDataPackage, OutputLayout, Resource},
+    PackageFormat, SigningConfig,
+};
+
+#[test]
+fn it_predicts_the_paths_written_by_packaging() {
+    let root = tempfile::tempdir().unwrap();
+    fs::create_dir_all(root.path().join("target")).unwrap();
+    fs::create_dir_all(root.path().join("assets")).unwrap();
+    fs::write(root.path().join("target/app"), "#!/bin/sh").unwrap();
+    fs::write(root.path().join("assets/data.txt"), "data").unwrap();
+
+    let keypair = cargo_packager::sign::generate_key_unencrypted().unwrap();
+    let signing_config = SigningConfig::new()
+        .private_key(keypair.sk)
+        .hash_only_formats([PackageFormat::Zip]);
+    let config = ConfigBuilder::new()
+        .product_name("App")
+        .version("1.1.0")
+        .identifier("com.example.app")
+        .target_triple("x86_64-unknown-linux-gnu")
+        .project_root(root.path())
+        .out_dir("target")
+        .binaries([Binary::new("app").main(true)])
+        .resources([Resource::Single("assets".into())])
+        .deb(DebianConfig::new().data_package(DebianDataPackage::new().arch_independent(true)))
+        .output_layout(OutputLayout::Format)
+        .signing(signing_config.clone())
+        .formats([PackageFormat::Deb, PackageFormat::Shar, PackageFormat::Zip])
+        .config()
+        .clone();
+
+    let outputs = cargo_packager::output_paths(&config).unwrap();
+    let out_dir = dunce::canonicalize(root.path()).unwrap().join("target");
+    // nothing is written
+    assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 1);
+
+    assert_eq!(
+        outputs[&PackageFormat::Deb],
+        [
+            out_dir.join("deb/app_1.1.0_amd64.deb"),
+            out_dir.join("deb/app-data_1.1.0_all.deb"),
+            out_dir.join("deb/app_1.1.0_amd64.deb.sig"),
+            out_dir.join("deb/app-data_1.1.0_all.deb.sig"),
+        ]
+    );
+    assert_eq!(
+        outputs[&PackageFormat::Zip],
+        [
+            out_dir.join("zip/app_1.1.0_x86_64.zip"),
+            out_dir.join("zip/app_1.1.0_x86_64.zip.sha256"),
+        ]
+    );
+
+    let (packages, signatures) =
+        cargo_packager::package_and_sign(&config, &signing_config).unwrap();
+    let mut written = packages
+        .iter()
+        .flat_map(|p| p.paths.clone())
+        .chain(signatures)
+        .collect::<Vec<_>>();
+    written.sort();
+    let mut predicted = outputs.into_values().flatten().collect::<Vec<PathBuf>>();
+    predicted.sort();
+    assert_eq!(predicted, written);
+    assert!(predicted.iter().all(|p| p.is_file()));
+}
@@ -0,0 +1,102 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::fs::{self, File};
+
+use cargo_packager::{
+    config::{Binary, ConfigBuilder, HookCommand, Resource},
+    PackageFormat,
+};
+
+#[test]
+fn it_resolves_relative_paths_against_the_project_root() {
+    let root = tempfile::tempdir().unwrap();
+    fs::create_dir_all(root.path().join("dist")).unwrap();
+    fs::create_dir_all(root.path().join("assets")).unwrap();
+    fs::write(root.path().join("dist/app"), "app").unwrap();
+    fs::write(root.path().join("assets/data.txt"), "data").unwrap();
+
+    // packaging from another directory, like the working directory of a CI job
+    let cwd = tempfile::tempdir().unwrap();
+    std::env::set_current_dir(cwd.path()).unwrap();
+
+    let config = ConfigBuilder::new()
+        .product_name("App")
+        .version("1.0.0")
+        .target_triple("x86_64-unknown-linux-gnu")
+        .project_root(root.path())
+        .out_dir("dist")
+        .binaries([Binary::new("app").main(true)])
+        .resources([Resource::Single("assets".into())])
+        .formats([PackageFormat::Zip])
+        .before_packaging_command(HookCommand::Script(if cfg!(windows) {
+            "type nul > hook-ran".into()
+        } else {
+            "touch hook-ran".into()
+        }))
+        .config()
+        .clone();
+    let packages = cargo_packager::package(&config).unwrap();
+
+    let zip_path = dunce::canonicalize(root.path())
+        .unwrap()
+        .join("dist/app_1.0.0_x86_64.zip");
+    assert_eq!(packages[0].paths, std::slice::from_ref(&zip_path));
+    let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+    assert!(archive.by_name("app").is_ok());
+    assert!(archive.by_name("assets/data.txt").is_ok());
+
+    // the hook runs in the project root
+    assert!(root.path().join("hook-ran").exists());
+
+    // the current directory is left untouched
+    assert_eq!(
+        dunce::canonicalize(std::env::current_dir().unwrap()).unwrap(),
+        dunce::canonicalize(cwd.path()).unwrap()
+    );
+    assert!(!cwd.path().join("dist").exists());
+}
+
+#[test]
+fn it_packages_projects_with_different_roots_concurrently() {
+    let roots = ["one", "two"].map(|name| {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("dist")).unwrap();
+        fs::create_dir_all(root.path().join("assets")).unwrap();
+        fs::write(root.path().join("dist/app"), "app").unwrap();
+        fs::write(root.path().join("assets/name.txt"), name).unwrap();
+        root
+    });
+
+    std::thread::scope(|scope| {
+        for root in &roots {
+            scope.spawn(|| {
+                let config = ConfigBuilder::new()
+                    .product_name("App")
+                    .version("1.0.0")
+                    .target_triple("x86_64-unknown-linux-gnu")
+                    .project_root(root.path())
+                    .out_dir("dist")
+                    .binaries([Binary::new("app").main(true)])
+                    .resources([Resource::Single("assets".into())])
+                    .formats([PackageFormat::Zip])
+                    .config()
+                    .clone();
+                cargo_packager::package(&config).unwrap();
+            });
+        }
+    });
+
+    for (root, name) in roots.iter().zip(["one", "two"]) {
+        let zip_path = root.path().join("dist/app_1.0.0_x86_64.zip");
+        let mut archive = zip::ZipArchive::new(File::open(zip_path).unwrap()).unwrap();
+        let mut contents = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name("assets/name.txt").unwrap(),
+            &mut contents,
+        )
+        .unwrap();
+        assert_eq!(contents, name);
+    }
+}
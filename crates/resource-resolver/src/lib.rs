@@ -55,11 +55,15 @@ pub fn current_format() -> crate::Result<PackageFormat> {
     match std::option_env!("CARGO_PACKAGER_FORMAT") {
         Some("app") => Ok(PackageFormat::App),
         Some("dmg") => Ok(PackageFormat::Dmg),
+        Some("pkg") => Ok(PackageFormat::Pkg),
         Some("wix") => Ok(PackageFormat::Wix),
         Some("nsis") => Ok(PackageFormat::Nsis),
         Some("deb") => Ok(PackageFormat::Deb),
         Some("appimage") => Ok(PackageFormat::AppImage),
         Some("pacman") => Ok(PackageFormat::Pacman),
+        Some("shar") => Ok(PackageFormat::Shar),
+        Some("zip") => Ok(PackageFormat::Zip),
+        Some("innosetup") => Ok(PackageFormat::InnoSetup),
         _ => Err(Error::UnkownPackageFormat),
     }
 }
@@ -75,14 +79,17 @@ pub fn current_format() -> crate::Result<PackageFormat> {
 /// ```
 pub fn resources_dir(package_format: PackageFormat) -> Result<PathBuf> {
     match package_format {
-        PackageFormat::App | PackageFormat::Dmg => {
+        PackageFormat::App | PackageFormat::Dmg | PackageFormat::Pkg => {
             let exe = current_exe()?;
             let exe_dir = exe
                 .parent()
                 .ok_or_else(|| Error::ParentNotFound(exe.clone()))?;
             Ok(exe_dir.join("../Resources"))
         }
-        PackageFormat::Wix | PackageFormat::Nsis => {
+        PackageFormat::Wix
+        | PackageFormat::Nsis
+        | PackageFormat::Zip
+        | PackageFormat::InnoSetup => {
             let exe = current_exe()?;
             let exe_dir = exe
                 .parent()
@@ -96,6 +103,16 @@ pub fn resources_dir(package_format: PackageFormat) -> Result<PathBuf> {
             let path = format!("/usr/lib/{exe_name}/");
             Ok(PathBuf::from(path))
         }
+        PackageFormat::Shar => {
+            // the self-extracting script installs to a configurable prefix,
+            // with the binaries in `<prefix>/bin` and resources in `<prefix>/lib/<exe_name>`
+            let exe = current_exe()?;
+            let exe_name = exe.file_name().unwrap().to_string_lossy();
+            let exe_dir = exe
+                .parent()
+                .ok_or_else(|| Error::ParentNotFound(exe.clone()))?;
+            Ok(exe_dir.join("../lib").join(&*exe_name))
+        }
 
         PackageFormat::AppImage => {
             let appdir = std::env::var_os("APPDIR").ok_or(Error::AppDirNotFound)?;
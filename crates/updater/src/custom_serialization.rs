@@ -10,7 +10,9 @@ use serde::{de::Error, Deserialize, Deserializer};
 use time::OffsetDateTime;
 use url::Url;
 
-use crate::{ReleaseManifestPlatform, RemoteRelease, RemoteReleaseData, UpdateFormat};
+use crate::{
+    ReleaseDelta, ReleaseManifestPlatform, RemoteRelease, RemoteReleaseData, UpdateFormat,
+};
 
 fn parse_version<'de, D>(deserializer: D) -> std::result::Result<Version, D::Error>
 where
@@ -68,11 +70,14 @@ impl<'de> Deserialize<'de> for RemoteRelease {
             version: Version,
             notes: Option<String>,
             pub_date: Option<String>,
+            expires_at: Option<String>,
             platforms: Option<HashMap<String, ReleaseManifestPlatform>>,
             // dynamic platform response
             url: Option<Url>,
             signature: Option<String>,
             format: Option<UpdateFormat>,
+            #[serde(default)]
+            deltas: Vec<ReleaseDelta>,
         }
 
         let release = InnerRemoteRelease::deserialize(deserializer)?;
@@ -88,10 +93,21 @@ impl<'de> Deserialize<'de> for RemoteRelease {
             None
         };
 
+        let expires_at = release
+            .expires_at
+            .map(|date| {
+                OffsetDateTime::parse(&date, &time::format_description::well_known::Rfc3339)
+                    .map_err(|e| {
+                        serde::de::Error::custom(format!("invalid value for `expires_at`: {e}"))
+                    })
+            })
+            .transpose()?;
+
         Ok(RemoteRelease {
             version: release.version,
             notes: release.notes,
             pub_date,
+            expires_at,
             data: if let Some(platforms) = release.platforms {
                 RemoteReleaseData::Static { platforms }
             } else {
@@ -107,6 +123,8 @@ impl<'de> Deserialize<'de> for RemoteRelease {
                     format: Some(release.format.ok_or_else(|| {
                         Error::custom("the `format` field was not set on the updater response")
                     })?),
+                    notes: None,
+                    deltas: release.deltas,
                 })
             },
         })
@@ -125,4 +143,54 @@ mod tests {
         dbg!(&out);
         out.expect("failed to deserialize");
     }
+
+    #[test]
+    fn it_prefers_platform_notes() {
+        let release = serde_json::from_value::<RemoteRelease>(serde_json::json!({
+            "version": "1.0.0",
+            "notes": "Bug fixes",
+            "pub_date": "2023-11-14T22:13:20Z",
+            "platforms": {
+                "linux-x86_64": {
+                    "url": "https://example.com/linux-x86_64",
+                    "signature": "",
+                    "format": "appimage",
+                    "notes": "Linux notes"
+                },
+                "windows-x86_64": {
+                    "url": "https://example.com/windows-x86_64",
+                    "signature": "",
+                    "format": "nsis"
+                }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            release.notes("linux-x86_64").map(String::as_str),
+            Some("Linux notes")
+        );
+        assert_eq!(
+            release.notes("windows-x86_64").map(String::as_str),
+            Some("Bug fixes")
+        );
+    }
+
+    #[test]
+    fn it_parses_the_expiry() {
+        let release = |expires_at: Option<&str>| {
+            serde_json::from_value::<RemoteRelease>(serde_json::json!({
+                "version": "1.0.0",
+                "expires_at": expires_at,
+                "url": "https://example.com/app",
+                "signature": "",
+                "format": "appimage"
+            }))
+        };
+
+        assert!(!release(None).unwrap().is_expired());
+        assert!(!release(Some("2999-01-01T00:00:00Z")).unwrap().is_expired());
+        assert!(release(Some("2020-01-01T00:00:00Z")).unwrap().is_expired());
+        assert!(release(Some("tomorrow")).is_err());
+    }
 }
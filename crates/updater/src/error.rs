@@ -66,6 +66,12 @@ pub enum Error {
     /// Error returned when persisting a temporary file fails.
     #[error(transparent)]
     PersistError(#[from] tempfile::PersistError),
+    /// The update response is past its `expires_at` date.
+    #[error("The update response expired at {0}.")]
+    ManifestExpired(time::OffsetDateTime),
+    /// Error returned when applying a delta update fails.
+    #[error(transparent)]
+    Delta(#[from] cargo_packager_utils::delta::DeltaError),
 }
 
 /// Convenience alias for `cargo-packager-updater` crate Result type.
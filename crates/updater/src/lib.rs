@@ -64,6 +64,8 @@
 //! - `url` or `platforms.[target].url`: must be a valid url to the update bundle
 //! - `signature` or `platforms.[target].signature`: must be the content of the generated `.sig` file. The signature may change each time you run build your app so make sure to always update it.
 //! - `format` or `platforms.[target].format`: must be one of `app`, `appimage`, `nsis` or `wix`.
+//!   The `url` must point to the `.app.tar.gz` archive of the app bundle for `app`, the `.AppImage` for `appimage`,
+//!   the `.exe` installer for `nsis` and the `.msi` installer for `wix`.
 //!
 //! <div style="border-left: 2px solid rgba(47,129,247);padding-left:0.75em;">
 //!   <p style="display:flex;align-items:center;gap:3px;color:rgb(47,129,247)">
@@ -76,7 +78,17 @@
 //!
 //! It can also contain these optional fields:
 //! - `notes`: Here you can add notes about the update, like release notes.
+//!   When using the `platforms` object, each platform can also have its own `notes` taking precedence over it.
 //! - `pub_date`: must be formatted according to [RFC 3339](https://datatracker.ietf.org/doc/html/rfc3339#section-5.8) if present.
+//! - `expires_at`: the date after which the response is rejected with [`Error::ManifestExpired`],
+//!   formatted according to RFC 3339, so a stale manifest can't be replayed indefinitely.
+//! - `deltas` or `platforms.[target].deltas`: the delta updates from the artifacts of previous versions,
+//!   each with its `url`, `signature` and the `from_sha256` digest of the artifact it applies to,
+//!   downloaded by [`Update::download_delta`] instead of the full update.
+//!
+//! When the packager is configured with a signing key, it also signs the manifest into `latest.json.sig`.
+//! Clients that fetch the manifest themselves should verify this signature against the exact bytes
+//! of the manifest first, before trusting any of its fields, and only then verify the signature of the update artifact.
 //!
 //! Here is an example of the two expected JSON formats:
 //!
@@ -100,12 +112,12 @@
 //!        },
 //!        "linux-x86_64": {
 //!          "signature": "Content of app.AppImage.sig",
-//!          "url": "https://github.com/username/reponame/releases/download/v1.0.0/app-amd64.AppImage.tar.gz",
+//!          "url": "https://github.com/username/reponame/releases/download/v1.0.0/app-amd64.AppImage",
 //!          "format": "appimage"
 //!        },
 //!        "windows-x86_64": {
 //!          "signature": "Content of app-setup.exe.sig or app.msi.sig, depending on the chosen format",
-//!          "url": "https://github.com/username/reponame/releases/download/v1.0.0/app-x64-setup.nsis.zip",
+//!          "url": "https://github.com/username/reponame/releases/download/v1.0.0/app-x64-setup.exe",
 //!          "format": "nsis or wix depending on the chosen format"
 //!        }
 //!      }
@@ -269,6 +281,23 @@ pub struct ReleaseManifestPlatform {
     /// Update format
     #[serde(deserialize_with = "custom_serialization::parse_update_format")]
     pub format: Option<UpdateFormat>,
+    /// Release notes for the platform, overriding [`RemoteRelease::notes`]
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Delta updates from the artifacts of previous versions
+    #[serde(default)]
+    pub deltas: Vec<ReleaseDelta>,
+}
+
+/// A delta update, a binary patch turning the artifact of a previous version into the update.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReleaseDelta {
+    /// Download URL of the delta
+    pub url: Url,
+    /// Signature of the delta
+    pub signature: String,
+    /// SHA-256 digest of the artifact the delta applies to
+    pub from_sha256: String,
 }
 
 /// Information about a release data.
@@ -295,11 +324,20 @@ pub struct RemoteRelease {
     pub notes: Option<String>,
     /// Release date.
     pub pub_date: Option<OffsetDateTime>,
+    /// Date after which the release must be rejected, see [`RemoteRelease::is_expired`].
+    pub expires_at: Option<OffsetDateTime>,
     /// Release data.
     pub data: RemoteReleaseData,
 }
 
 impl RemoteRelease {
+    /// Whether the release is past its [`RemoteRelease::expires_at`] date,
+    /// in which case [`Updater::check`] rejects it with [`Error::ManifestExpired`].
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc())
+    }
+
     /// The release's download URL for the given target.
     pub fn download_url(&self, target: &str) -> Result<&Url> {
         match self.data {
@@ -324,6 +362,28 @@ impl RemoteRelease {
         }
     }
 
+    /// The release's notes for the given target,
+    /// the platform notes of a static release take precedence over [`RemoteRelease::notes`].
+    pub fn notes(&self, target: &str) -> Option<&String> {
+        match self.data {
+            RemoteReleaseData::Dynamic(_) => self.notes.as_ref(),
+            RemoteReleaseData::Static { ref platforms } => platforms
+                .get(target)
+                .and_then(|platform| platform.notes.as_ref())
+                .or(self.notes.as_ref()),
+        }
+    }
+
+    /// The release's delta updates for the given target.
+    pub fn deltas(&self, target: &str) -> &[ReleaseDelta] {
+        match self.data {
+            RemoteReleaseData::Dynamic(ref platform) => &platform.deltas,
+            RemoteReleaseData::Static { ref platforms } => platforms
+                .get(target)
+                .map_or(&[], |platform| &platform.deltas),
+        }
+    }
+
     /// The release's update format for the given target.
     pub fn format(&self, target: &str) -> Result<UpdateFormat> {
         match self.data {
@@ -571,6 +631,10 @@ impl Updater {
                         match serde_json::from_value::<RemoteRelease>(update_response)
                             .map_err(Into::into)
                         {
+                            Ok(release) if release.is_expired() => {
+                                log::error!("the update response expired");
+                                last_error = release.expires_at.map(Error::ManifestExpired);
+                            }
                             Ok(release) => {
                                 log::debug!("parsed release response {release:?}");
                                 last_error = None;
@@ -620,8 +684,9 @@ impl Updater {
                 version: release.version.to_string(),
                 date: release.pub_date,
                 download_url: release.download_url(&self.json_target)?.to_owned(),
-                body: release.notes.clone(),
+                body: release.notes(&self.json_target).cloned(),
                 signature: release.signature(&self.json_target)?.to_owned(),
+                deltas: release.deltas(&self.json_target).to_vec(),
                 timeout: self.timeout,
                 headers: self.headers.clone(),
                 format: release.format(&self.json_target)?,
@@ -655,6 +720,8 @@ pub struct Update {
     pub download_url: Url,
     /// Signature announced
     pub signature: String,
+    /// Delta updates announced
+    pub deltas: Vec<ReleaseDelta>,
     /// Request timeout
     pub timeout: Option<Duration>,
     /// Request headers
@@ -692,6 +759,59 @@ impl Update {
         &self,
         on_chunk: Option<C>,
         on_download_finish: Option<D>,
+    ) -> Result<Vec<u8>> {
+        let buffer = self.fetch(&self.download_url, on_chunk, on_download_finish)?;
+
+        let mut update_buffer = Cursor::new(&buffer);
+
+        verify_signature(&mut update_buffer, &self.signature, &self.config.pubkey)?;
+
+        Ok(buffer)
+    }
+
+    /// Downloads the delta update made from the artifact `old`, the installed artifact of the current version,
+    /// patches `old` with it then verifies the result like [`Update::download`] and returns it as bytes.
+    ///
+    /// Returns `None` when no delta was made from `old`, the full update must be downloaded instead.
+    ///
+    /// Use [`Update::install`] to install it
+    pub fn download_delta(&self, old: &[u8]) -> Result<Option<Vec<u8>>> {
+        let old_sha256 = cargo_packager_utils::delta::sha256_hex(old);
+        let Some(delta) = self
+            .deltas
+            .iter()
+            .find(|d| d.from_sha256.eq_ignore_ascii_case(&old_sha256))
+        else {
+            return Ok(None);
+        };
+
+        let delta_bytes = self.fetch(
+            &delta.url,
+            None::<Box<dyn Fn(usize, Option<u64>)>>,
+            None::<Box<dyn FnOnce()>>,
+        )?;
+        verify_signature(
+            &mut Cursor::new(&delta_bytes),
+            &delta.signature,
+            &self.config.pubkey,
+        )?;
+
+        // the old artifact is checked against the digest the delta was made from before patching it
+        let buffer = cargo_packager_utils::delta::apply_delta(old, &delta_bytes)?;
+        verify_signature(
+            &mut Cursor::new(&buffer),
+            &self.signature,
+            &self.config.pubkey,
+        )?;
+
+        Ok(Some(buffer))
+    }
+
+    fn fetch<C: Fn(usize, Option<u64>), D: FnOnce()>(
+        &self,
+        url: &Url,
+        on_chunk: Option<C>,
+        on_download_finish: Option<D>,
     ) -> Result<Vec<u8>> {
         // set our headers
         let mut headers = self.headers.clone();
@@ -708,9 +828,7 @@ impl Update {
             );
         }
 
-        let mut request = Client::new()
-            .get(self.download_url.clone())
-            .headers(headers);
+        let mut request = Client::new().get(url.clone()).headers(headers);
         if let Some(timeout) = self.timeout {
             request = request.timeout(timeout);
         }
@@ -757,10 +875,6 @@ impl Update {
             on_download_finish();
         }
 
-        let mut update_buffer = Cursor::new(&buffer);
-
-        verify_signature(&mut update_buffer, &self.signature, &self.config.pubkey)?;
-
         Ok(buffer)
     }
 
@@ -0,0 +1,264 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Binary deltas between two versions of a file, using the bsdiff algorithm.
+//!
+//! A delta starts with the `CPDELTA1` magic, the SHA-256 digest of the old file and the size of the new file
+//! as a little-endian `u64`, followed by the deflate-compressed bsdiff records. Each record is made of
+//! the lengths of its diff and extra bytes and the offset to seek in the old file, as little-endian
+//! sign-magnitude `i64`s, followed by the diff bytes, added to the bytes of the old file,
+//! and the extra bytes, copied as is.
+//!
+//! The records are made by the [`bsdiff`] crate, see <https://www.daemonology.net/bsdiff/> for the algorithm.
+
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use sha2::{Digest, Sha256};
+
+/// The magic number at the start of every delta.
+pub const DELTA_MAGIC: &[u8; 8] = b"CPDELTA1";
+
+const HEADER_SIZE: usize = DELTA_MAGIC.len() + 32 + 8;
+
+/// How many times the size of the old file is allocated up front for the new file.
+const MAX_PREALLOCATION_RATIO: usize = 4;
+
+/// An error applying a delta.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DeltaError {
+    /// The old file is not the one the delta was made from.
+    OldFileMismatch {
+        /// The SHA-256 digest of the file the delta was made from.
+        expected: String,
+        /// The SHA-256 digest of the old file.
+        found: String,
+    },
+    /// The delta is corrupt.
+    Corrupt(&'static str),
+    /// Failed to read the delta.
+    Io(io::Error),
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeltaError::OldFileMismatch { expected, found } => write!(
+                f,
+                "the delta was made from a file with the SHA-256 digest {expected}, found {found}"
+            ),
+            DeltaError::Corrupt(reason) => write!(f, "corrupt delta, {reason}"),
+            DeltaError::Io(e) => write!(f, "failed to read the delta: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+impl From<io::Error> for DeltaError {
+    fn from(e: io::Error) -> Self {
+        DeltaError::Io(e)
+    }
+}
+
+/// Computes the SHA-256 digest of `data`, encoded as a lowercase hex string.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Returns the SHA-256 digest of the file a delta was made from, encoded as a lowercase hex string.
+pub fn delta_old_sha256(delta: &[u8]) -> Result<String, DeltaError> {
+    if delta.len() < HEADER_SIZE || !delta.starts_with(DELTA_MAGIC) {
+        return Err(DeltaError::Corrupt("invalid header"));
+    }
+    Ok(delta[DELTA_MAGIC.len()..DELTA_MAGIC.len() + 32]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Makes a delta turning `old` into `new`, to be applied with [`apply_delta`].
+///
+/// Sorting the suffixes of `old` uses about 16 times its size in memory.
+pub fn make_delta(old: &[u8], new: &[u8]) -> io::Result<Vec<u8>> {
+    let mut delta = Vec::with_capacity(HEADER_SIZE);
+    delta.extend_from_slice(DELTA_MAGIC);
+    delta.extend_from_slice(&Sha256::digest(old));
+    delta.extend_from_slice(&(new.len() as u64).to_le_bytes());
+    let mut encoder = DeflateEncoder::new(delta, Compression::best());
+    bsdiff::diff(old, new, &mut encoder)?;
+    encoder.finish()
+}
+
+/// Applies a delta made by [`make_delta`] to `old`, returning the new file.
+///
+/// Fails with [`DeltaError::OldFileMismatch`] when `old` is not the file the delta was made from.
+pub fn apply_delta(old: &[u8], delta: &[u8]) -> Result<Vec<u8>, DeltaError> {
+    let expected = delta_old_sha256(delta)?;
+    let found = sha256_hex(old);
+    if expected != found {
+        return Err(DeltaError::OldFileMismatch { expected, found });
+    }
+    let new_size = u64::from_le_bytes(delta[HEADER_SIZE - 8..HEADER_SIZE].try_into().unwrap());
+    let new_size = usize::try_from(new_size).map_err(|_| DeltaError::Corrupt("invalid size"))?;
+
+    let mut records = DeflateDecoder::new(&delta[HEADER_SIZE..]);
+    let read_i64 = |records: &mut DeflateDecoder<&[u8]>| -> Result<i64, DeltaError> {
+        let mut bytes = [0; 8];
+        records.read_exact(&mut bytes)?;
+        let value = i64::from_le_bytes(bytes);
+        // sign-magnitude
+        Ok(if value < 0 {
+            -(value & i64::MAX)
+        } else {
+            value
+        })
+    };
+    let invalid_length = || DeltaError::Corrupt("invalid record length");
+    let invalid_seek = || DeltaError::Corrupt("invalid record seek");
+
+    // the size in the header is not trusted for the allocation, the bytes are appended as they are read
+    let mut new =
+        Vec::with_capacity(new_size.min(old.len().saturating_mul(MAX_PREALLOCATION_RATIO)));
+    let mut old_pos = 0usize;
+    while new.len() < new_size {
+        let diff_len = usize::try_from(read_i64(&mut records)?).map_err(|_| invalid_length())?;
+        let extra_len = usize::try_from(read_i64(&mut records)?).map_err(|_| invalid_length())?;
+        let seek = isize::try_from(read_i64(&mut records)?).map_err(|_| invalid_seek())?;
+        let len = diff_len.checked_add(extra_len).ok_or_else(invalid_length)?;
+        if len > new_size - new.len() {
+            return Err(invalid_length());
+        }
+
+        let start = new.len();
+        if records.by_ref().take(len as u64).read_to_end(&mut new)? != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let old_end = old_pos.checked_add(diff_len).ok_or_else(invalid_seek)?;
+        let old_bytes = old.get(old_pos..old_end).ok_or_else(invalid_seek)?;
+        for (byte, old_byte) in new[start..start + diff_len].iter_mut().zip(old_bytes) {
+            *byte = byte.wrapping_add(*old_byte);
+        }
+        old_pos = old_end.checked_add_signed(seek).ok_or_else(invalid_seek)?;
+    }
+    Ok(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// A deterministic pseudo-random generator for the test data.
+    fn data(seed: u32, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_round_trips_deltas() {
+        let old = data(1, 100_000);
+        let mut new = old.clone();
+        // modified, inserted and removed bytes
+        new[1000..1100].copy_from_slice(&data(2, 100));
+        new.splice(50_000..50_000, data(3, 5000));
+        new.drain(80_000..81_000);
+        new.extend_from_slice(b"trailing bytes");
+
+        let delta = make_delta(&old, &new).unwrap();
+        assert!(delta.len() < new.len() / 5, "{} bytes", delta.len());
+        assert_eq!(apply_delta(&old, &delta).unwrap(), new);
+        assert_eq!(delta_old_sha256(&delta).unwrap(), sha256_hex(&old));
+
+        for (old, new) in [(&b""[..], &b"new"[..]), (b"old", b""), (b"same", b"same")] {
+            assert_eq!(
+                apply_delta(old, &make_delta(old, new).unwrap()).unwrap(),
+                new
+            );
+        }
+    }
+
+    #[test]
+    fn it_checks_the_old_file() {
+        let delta = make_delta(b"old file", b"new file").unwrap();
+        assert!(matches!(
+            apply_delta(b"other file", &delta),
+            Err(DeltaError::OldFileMismatch { .. })
+        ));
+        assert!(matches!(
+            apply_delta(b"old file", &delta[..delta.len() - 4]),
+            Err(DeltaError::Io(_))
+        ));
+        assert!(matches!(
+            apply_delta(b"old file", b"not a delta"),
+            Err(DeltaError::Corrupt(_))
+        ));
+    }
+
+    /// Makes a delta from `old` to a new file of `new_size` bytes with the given records.
+    fn raw_delta(old: &[u8], new_size: u64, records: &[(i64, i64, i64, &[u8])]) -> Vec<u8> {
+        let mut delta = DELTA_MAGIC.to_vec();
+        delta.extend_from_slice(&Sha256::digest(old));
+        delta.extend_from_slice(&new_size.to_le_bytes());
+        let mut encoder = DeflateEncoder::new(delta, Compression::best());
+        for (diff_len, extra_len, seek, bytes) in records {
+            for value in [diff_len, extra_len, seek] {
+                let value = if *value < 0 {
+                    value.unsigned_abs() | 1 << 63
+                } else {
+                    *value as u64
+                };
+                encoder.write_all(&value.to_le_bytes()).unwrap();
+            }
+            encoder.write_all(bytes).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn it_rejects_malformed_records() {
+        let old = b"old file";
+        assert_eq!(
+            apply_delta(old, &raw_delta(old, 4, &[(3, 1, 0, b"\0\0\0!")])).unwrap(),
+            b"old!"
+        );
+
+        // the size of the header is not allocated up front
+        assert!(matches!(
+            apply_delta(old, &raw_delta(old, u64::MAX, &[(0, 3, 0, b"new")])),
+            Err(DeltaError::Io(_))
+        ));
+        for records in [
+            // longer than the new file
+            &[(0, 5, 0, &b"extra"[..])][..],
+            &[(i64::MAX, i64::MAX, 0, b"")],
+            &[(-1, 0, 0, b"")],
+            // outside the old file
+            &[(0, 1, -1, b"!"), (1, 0, 0, b"\0")],
+            &[(0, 1, i64::MAX, b"!"), (1, 0, i64::MAX, b"\0")],
+            &[(0, 1, 8, b"!"), (1, 0, 0, b"\0")],
+        ] {
+            assert!(
+                matches!(
+                    apply_delta(old, &raw_delta(old, 2, records)),
+                    Err(DeltaError::Corrupt(_))
+                ),
+                "{records:?}"
+            );
+        }
+    }
+}
@@ -9,11 +9,13 @@
 use std::fmt::Display;
 
 pub mod current_exe;
+#[cfg(feature = "delta")]
+pub mod delta;
 
 // NOTE: When making changes to this enum,
 // make sure to also update in updater and resource-resolver bindings if needed
 /// Types of supported packages by [`cargo-packager`](https://docs.rs/cargo-packager).
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -35,6 +37,8 @@ pub enum PackageFormat {
     App,
     /// The macOS DMG package (.dmg).
     Dmg,
+    /// The macOS installer package (.pkg).
+    Pkg,
     /// The Microsoft Software Installer (.msi) through WiX Toolset.
     Wix,
     /// The NSIS installer (.exe).
@@ -45,6 +49,12 @@ pub enum PackageFormat {
     AppImage,
     /// The Linux Pacman package (.tar.gz and PKGBUILD)
     Pacman,
+    /// The Linux self-extracting installer shell script (.sh).
+    Shar,
+    /// The portable zip archive (.zip).
+    Zip,
+    /// The Inno Setup installer (.exe), or its script (.iss) when the Inno Setup compiler is not installed.
+    InnoSetup,
 }
 
 impl Display for PackageFormat {
@@ -55,16 +65,21 @@ impl Display for PackageFormat {
 
 impl PackageFormat {
     /// Maps a short name to a [PackageFormat].
-    /// Possible values are "deb", "pacman", "appimage", "dmg", "app", "wix", "nsis".
+    /// Possible values are "deb", "pacman", "appimage", "shar", "dmg", "pkg", "app", "wix", "nsis", "zip", "innosetup".
     pub fn from_short_name(name: &str) -> Option<PackageFormat> {
         // Other types we may eventually want to support: apk.
         match name {
             "app" => Some(PackageFormat::App),
             "dmg" => Some(PackageFormat::Dmg),
+            "pkg" => Some(PackageFormat::Pkg),
             "wix" => Some(PackageFormat::Wix),
             "nsis" => Some(PackageFormat::Nsis),
             "deb" => Some(PackageFormat::Deb),
             "appimage" => Some(PackageFormat::AppImage),
+            "pacman" => Some(PackageFormat::Pacman),
+            "shar" => Some(PackageFormat::Shar),
+            "zip" => Some(PackageFormat::Zip),
+            "innosetup" => Some(PackageFormat::InnoSetup),
             _ => None,
         }
     }
@@ -78,29 +93,39 @@ impl PackageFormat {
             PackageFormat::Default => "default",
             PackageFormat::App => "app",
             PackageFormat::Dmg => "dmg",
+            PackageFormat::Pkg => "pkg",
             PackageFormat::Wix => "wix",
             PackageFormat::Nsis => "nsis",
             PackageFormat::Deb => "deb",
             PackageFormat::AppImage => "appimage",
             PackageFormat::Pacman => "pacman",
+            PackageFormat::Shar => "shar",
+            PackageFormat::Zip => "zip",
+            PackageFormat::InnoSetup => "innosetup",
         }
     }
 
     /// Gets the list of the possible package types on the current OS.
     ///
-    /// - **macOS**: App, Dmg
-    /// - **Windows**: Nsis, Wix
-    /// - **Linux**: Deb, AppImage, Pacman
+    /// - **macOS**: App, Dmg, Pkg
+    /// - **Windows**: Nsis, Wix, Zip, InnoSetup
+    /// - **Linux**: Deb, AppImage, Pacman, Shar
     pub fn platform_all() -> &'static [PackageFormat] {
         &[
             #[cfg(target_os = "macos")]
             PackageFormat::App,
             #[cfg(target_os = "macos")]
             PackageFormat::Dmg,
+            #[cfg(target_os = "macos")]
+            PackageFormat::Pkg,
             #[cfg(target_os = "windows")]
             PackageFormat::Wix,
             #[cfg(target_os = "windows")]
             PackageFormat::Nsis,
+            #[cfg(target_os = "windows")]
+            PackageFormat::Zip,
+            #[cfg(target_os = "windows")]
+            PackageFormat::InnoSetup,
             #[cfg(any(
                 target_os = "linux",
                 target_os = "dragonfly",
@@ -125,6 +150,14 @@ impl PackageFormat {
                 target_os = "openbsd"
             ))]
             PackageFormat::Pacman,
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            ))]
+            PackageFormat::Shar,
         ]
     }
 
@@ -170,7 +203,7 @@ impl PackageFormat {
 
     /// Gets a number representing priority which used to sort package types
     /// in an order that guarantees that if a certain package type
-    /// depends on another (like Dmg depending on MacOsBundle), the dependency
+    /// depends on another (like Dmg and Pkg depending on MacOsBundle), the dependency
     /// will be built first
     ///
     /// The lower the number, the higher the priority
@@ -186,7 +219,11 @@ impl PackageFormat {
             PackageFormat::Deb => 0,
             PackageFormat::AppImage => 0,
             PackageFormat::Pacman => 0,
+            PackageFormat::Shar => 0,
+            PackageFormat::Zip => 0,
+            PackageFormat::InnoSetup => 0,
             PackageFormat::Dmg => 1,
+            PackageFormat::Pkg => 1,
         }
     }
 }